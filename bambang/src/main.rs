@@ -1,13 +1,14 @@
 use bindereh::{
     manager::Manager,
     operator::{
+        aggregate::{AggregateFunction, AggregateProcessor},
         insert::InsertOperation,
         join::{HashJoinOperation, JoinCondition, JoinType},
         scan::ScanOperation,
     },
     page::Page,
 };
-use shared_types::{Column, DataType, Predicate, Row, ScanOptions, Schema, Value};
+use shared_types::{Column, DataType, Decimal, Expr, Predicate, Row, ScanOptions, Schema, Value};
 use std::process::Command;
 use std::sync::Arc;
 use std::time::Instant;
@@ -21,6 +22,7 @@ const DATE_ROWS: u64 = 2_556; // Keep full date dimension - essential for time-b
 const CUSTOMER_ROWS: u64 = 3_000; // 3k customers (10% of 30k) - maintains customer distribution
 const SUPPLIER_ROWS: u64 = 200; // 200 suppliers (10% of 2k) - sufficient for supplier analysis
 const PART_ROWS: u64 = 20_000; // 20k parts (10% of 200k) - adequate part variety
+const MONEY: DataType = DataType::Decimal { precision: 15, scale: 2 }; // Monetary columns use exact fixed-point
 
 async fn setup_lineorder_table(
     manager: Arc<Manager>,
@@ -82,13 +84,13 @@ async fn setup_lineorder_table(
         },
         Column {
             name: "lo_extendedprice".to_string(),
-            data_type: DataType::Float,
+            data_type: MONEY,
             nullable: false,
             primary_key: false,
         },
         Column {
             name: "lo_ordtotalprice".to_string(),
-            data_type: DataType::Float,
+            data_type: MONEY,
             nullable: false,
             primary_key: false,
         },
@@ -100,13 +102,13 @@ async fn setup_lineorder_table(
         },
         Column {
             name: "lo_revenue".to_string(),
-            data_type: DataType::Float,
+            data_type: MONEY,
             nullable: false,
             primary_key: false,
         },
         Column {
             name: "lo_supplycost".to_string(),
-            data_type: DataType::Float,
+            data_type: MONEY,
             nullable: false,
             primary_key: false,
         },
@@ -169,9 +171,12 @@ async fn setup_lineorder_table(
             let extended_price = 901.0 + (i as f64 % 104949.0);
             let discount = 0 + (i % 11);
             let tax = 0 + (i % 9);
-            let revenue = extended_price * (100.0 - discount as f64) / 100.0;
             let supply_cost = 1.0 + (i as f64 % extended_price * 0.6);
 
+            // Prices are whole currency units, so cents are exact
+            let price_cents = extended_price as i128 * 100;
+            let revenue_cents = price_cents * (100 - discount as i128) / 100;
+
             batch_rows.push(Row {
                 id: i + 1,
                 data: vec![
@@ -184,11 +189,11 @@ async fn setup_lineorder_table(
                     Value::Integer(1 + (i % 5) as i64), // lo_orderpriority
                     Value::Integer(0),                  // lo_shippriority
                     Value::Integer(quantity as i64),    // lo_quantity
-                    Value::Float(extended_price),       // lo_extendedprice
-                    Value::Float(extended_price * 4.0), // lo_ordtotalprice
+                    Value::Decimal(Decimal::new(price_cents, 2)), // lo_extendedprice
+                    Value::Decimal(Decimal::new(price_cents * 4, 2)), // lo_ordtotalprice
                     Value::Integer(discount as i64),    // lo_discount
-                    Value::Float(revenue),              // lo_revenue
-                    Value::Float(supply_cost),          // lo_supplycost
+                    Value::Decimal(Decimal::new(revenue_cents, 2)), // lo_revenue
+                    Value::Decimal(Decimal::from_f64(supply_cost, 2).unwrap_or_default()), // lo_supplycost
                     Value::Integer(tax as i64),         // lo_tax
                     Value::Integer(date_key + 30),      // lo_commitdate
                     Value::Integer(1 + (i % 7) as i64), // lo_shipmode
@@ -512,6 +517,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         ..Default::default()
     };
 
+    let join_condition = vec![JoinCondition {
+        left_column: "lo_orderdate".to_string(),
+        right_column: "d_datekey".to_string(),
+    }];
+
+    let join_op =
+        HashJoinOperation::new(lineorder_manager.clone(), JoinType::Inner, join_condition);
+
+    let scan_start = Instant::now();
+    let dates_result = scan_op_dates
+        .execute(dates_root, dates_scan_options)
+        .await?;
+
+    // Only lineorder rows whose date survived the d_year filter can join
+    let runtime_filters = join_op.build_runtime_filters(&dates_result.rows, &dates_schema)?;
+
     let lineorder_scan_options = ScanOptions {
         schema: Some(lineorder_schema.clone()),
         predicate: Some(Predicate::And(
@@ -531,13 +552,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }),
         )),
         parallel: true,
+        runtime_filters,
         ..Default::default()
     };
 
-    let scan_start = Instant::now();
-    let dates_result = scan_op_dates
-        .execute(dates_root, dates_scan_options)
-        .await?;
     let lineorder_result = scan_op_lineorder
         .execute(lineorder_root, lineorder_scan_options)
         .await?;
@@ -555,14 +573,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
     println!("Scan Time: {:.2}ms", scan_time.as_secs_f64() * 1000.0);
 
-    let join_condition = vec![JoinCondition {
-        left_column: "lo_orderdate".to_string(),
-        right_column: "d_datekey".to_string(),
-    }];
-
-    let join_op =
-        HashJoinOperation::new(lineorder_manager.clone(), JoinType::Inner, join_condition);
-
     let join_start = Instant::now();
     let join_result = join_op
         .execute(
@@ -583,19 +593,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Calculate revenue: sum(lo_extendedprice * lo_discount/100)
     let agg_start = Instant::now();
-    let revenue_sum: f64 = join_result
-        .rows
-        .iter()
-        .filter_map(|row| {
-            if let (Value::Float(extended_price), Value::Integer(discount)) =
-                (&row.data[9], &row.data[11])
-            {
-                Some(extended_price * (*discount as f64) / 100.0)
-            } else {
-                None
-            }
-        })
-        .sum();
+    let revenue = Expr::divide(
+        Expr::multiply(Expr::column("lo_extendedprice"), Expr::column("lo_discount")),
+        Expr::literal(Value::Integer(100)),
+    );
+    let aggregate_row = AggregateProcessor::process_aggregates(
+        &join_result.rows,
+        &[AggregateFunction::Sum { expr: revenue }],
+        &join_result.result_schema,
+    )?;
+    let revenue_sum = match &aggregate_row.data[0] {
+        Value::Decimal(sum) => sum.round(2),
+        other => other.as_decimal().unwrap_or_default(),
+    };
     let agg_time = agg_start.elapsed();
 
     let total_time = total_start.elapsed();
@@ -617,7 +627,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         "Throughput: {:.2} rows/sec",
         join_result.output_rows as f64 / total_time.as_secs_f64()
     );
-    println!("SSB Q1.1 Revenue: {:.2}", revenue_sum);
+    println!("SSB Q1.1 Revenue: {}", revenue_sum);
 
     Ok(())
 }
@@ -4,6 +4,7 @@ use crate::{
     common::StorageError,
     manager::Manager,
     operator::{
+        aggregate::{AggregateResult, HashAggregateOperation},
         delete::{DeleteOperation, DeleteOptions, DeleteResult},
         insert::InsertOperation,
         print::TreePrinter,
@@ -49,6 +50,15 @@ impl Executor {
         self.scan_op.execute(root_id, options).await
     }
 
+    /// Scan with the aggregation folded into the scan workers
+    pub async fn aggregate(
+        &self,
+        options: ScanOptions,
+        aggregate: &HashAggregateOperation,
+    ) -> Result<AggregateResult, StorageError> {
+        self.scan_op.execute_aggregate(options, aggregate).await
+    }
+
     pub async fn insert(&self, row: Row) -> Result<u64, StorageError> {
         let root_id = *self.root_page_id.lock().unwrap();
         let result = self.insert_op.execute(row, root_id).await?;
@@ -5,4 +5,5 @@ pub mod pool;
 pub mod manager;
 pub mod debug;
 pub mod operator;
-pub mod leaf_registry;
\ No newline at end of file
+pub mod leaf_registry;
+pub mod spill;
\ No newline at end of file
@@ -1,6 +1,9 @@
 use std::{
     path::Path,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 use tokio::{
     fs::{File, OpenOptions},
@@ -39,7 +42,14 @@ impl Manager {
     }
 
     pub async fn read_page(&self, page_id: u64) -> Result<Arc<Page>, StorageError> {
+        self.read_page_counting_hits(page_id, &AtomicUsize::new(0)).await
+    }
+
+    /// `read_page` that adds one to `hits` when the buffer pool serves the page, so
+    /// that each reader counts its own hits
+    pub async fn read_page_counting_hits(&self, page_id: u64, hits: &AtomicUsize) -> Result<Arc<Page>, StorageError> {
         if let Some(cached_node) = self.buffer_pool.get_page(page_id) {
+            hits.fetch_add(1, Ordering::Relaxed);
             return Ok(cached_node);
         }
         let mut file = self.file.lock().await;
@@ -254,13 +264,18 @@ impl Manager {
     }
 
     pub async fn read_leaf_chain(&self, start_page_id: u64, max_pages: usize) -> Result<(Vec<Arc<Page>>, Option<u64>), StorageError> {
+        self.read_leaf_chain_counting_hits(start_page_id, max_pages, &AtomicUsize::new(0)).await
+    }
+
+    /// `read_leaf_chain` that adds the pages the buffer pool served to `hits`
+    pub async fn read_leaf_chain_counting_hits(&self, start_page_id: u64, max_pages: usize, hits: &AtomicUsize) -> Result<(Vec<Arc<Page>>, Option<u64>), StorageError> {
         let mut pages = Vec::with_capacity(max_pages);
         let mut current_page_id = Some(start_page_id);
         while let Some(page_id) = current_page_id {
             if pages.len() >= max_pages {
                 break;
             }
-            let page = self.read_page(page_id).await?;
+            let page = self.read_page_counting_hits(page_id, hits).await?;
             if !page.is_leaf {
                 return Err(StorageError::InvalidOperation(format!("Expected leaf page, got internal page: {}", page_id)));
             }
@@ -1,231 +1,783 @@
 use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
 
-use shared_types::{Row, Schema, StorageError, Value};
+use shared_types::decimal::{DIVISION_EXTRA_SCALE, MAX_DECIMAL_PRECISION};
+use shared_types::{Column, DataType, Decimal, Expr, Row, Schema, StorageError, Value};
 
+use crate::operator::expression::{PhysicalExpr, expr_data_type};
+use crate::spill::{
+    DEFAULT_MEMORY_BUDGET, MAX_SPILL_DEPTH, SPILL_FANOUT, SpillFile, create_partitions,
+    spill_partition,
+};
+
+/// Rough per-entry overhead of a hash table slot and its accumulator vector
+const GROUP_OVERHEAD: usize = 64;
+
+/// `Count` is COUNT(*), which counts every row; `CountNonNull` is COUNT(expr), which
+/// counts the rows where `expr` is not NULL
 #[derive(Debug, Clone)]
 pub enum AggregateFunction {
     Count,
-    Sum { column: String },
-    Avg { column: String },
-    Min { column: String },
-    Max { column: String },
-    CountDistinct { column: String },
+    CountNonNull { expr: Expr },
+    Sum { expr: Expr },
+    Avg { expr: Expr },
+    Min { expr: Expr },
+    Max { expr: Expr },
+    CountDistinct { expr: Expr },
 }
 
-pub struct AggregateProcessor;
+impl AggregateFunction {
+    pub fn argument(&self) -> Option<&Expr> {
+        match self {
+            AggregateFunction::Count => None,
+            AggregateFunction::CountNonNull { expr }
+            | AggregateFunction::Sum { expr }
+            | AggregateFunction::Avg { expr }
+            | AggregateFunction::Min { expr }
+            | AggregateFunction::Max { expr }
+            | AggregateFunction::CountDistinct { expr } => Some(expr),
+        }
+    }
 
-impl AggregateProcessor {
-    pub fn process_aggregates(
-        rows: &[Row],
-        aggregates: &[AggregateFunction],
-        schema: &Schema,
-    ) -> Result<Row, StorageError> {
-        let mut result_data = Vec::new();
-
-        for aggregate in aggregates {
-            let value = match aggregate {
-                AggregateFunction::Count => Value::Integer(rows.len() as i64),
-                AggregateFunction::Sum { column } => Self::sum_column(rows, column, schema)?,
-                AggregateFunction::Avg { column } => Self::avg_column(rows, column, schema)?,
-                AggregateFunction::Min { column } => Self::min_column(rows, column, schema)?,
-                AggregateFunction::Max { column } => Self::max_column(rows, column, schema)?,
-                AggregateFunction::CountDistinct { column } => {
-                    Self::count_distinct_column(rows, column, schema)?
-                }
+    /// Type of the aggregate's result over rows of `schema`
+    pub fn result_type(&self, schema: &Schema) -> Result<DataType, StorageError> {
+        match self {
+            AggregateFunction::Count
+            | AggregateFunction::CountNonNull { .. }
+            | AggregateFunction::CountDistinct { .. } => Ok(DataType::Integer),
+            AggregateFunction::Sum { expr } => Ok(match expr_data_type(expr, schema)? {
+                DataType::Decimal { scale, .. } => DataType::Decimal {
+                    precision: MAX_DECIMAL_PRECISION,
+                    scale,
+                },
+                DataType::Float => DataType::Float,
+                _ => DataType::BigInt,
+            }),
+            AggregateFunction::Avg { expr } => Ok(match expr_data_type(expr, schema)? {
+                DataType::Decimal { scale, .. } => DataType::Decimal {
+                    precision: MAX_DECIMAL_PRECISION,
+                    scale: (scale + DIVISION_EXTRA_SCALE).min(MAX_DECIMAL_PRECISION),
+                },
+                _ => DataType::Float,
+            }),
+            AggregateFunction::Min { expr } | AggregateFunction::Max { expr } => {
+                expr_data_type(expr, schema)
+            }
+        }
+    }
+}
+
+/// Running SUM shared by the SUM and AVG accumulators.
+/// Integers are summed exactly in i128 and decimals exactly at their scale; once a
+/// decimal is seen the integers join the decimal total. A sum that no longer fits is
+/// an error rather than a silently rounded float. Floats are summed in f64.
+#[derive(Debug, Clone, Default)]
+pub struct SumState {
+    integer: i128,
+    decimal: Option<Decimal>,
+    float: f64,
+    is_float: bool,
+    count: i64,
+}
+
+impl SumState {
+    pub fn add(&mut self, value: &Value) -> Result<(), StorageError> {
+        if value.numeric_rank().is_none() {
+            return Ok(()); // NULL and non-numeric values don't contribute
+        }
+        self.count += 1;
+        match value {
+            Value::Decimal(d) => self.add_decimal(*d),
+            Value::Float(f) => {
+                self.add_float(*f);
+                Ok(())
+            }
+            other => self.add_integer(other.as_i128().unwrap_or(0)),
+        }
+    }
+
+    pub fn merge(&mut self, other: &SumState) -> Result<(), StorageError> {
+        if other.count == 0 {
+            return Ok(());
+        }
+        self.count += other.count;
+        match other.decimal {
+            Some(d) => self.add_decimal(d)?,
+            None => self.add_integer(other.integer)?,
+        }
+        if other.is_float {
+            self.add_float(other.float);
+        }
+        Ok(())
+    }
+
+    fn add_integer(&mut self, value: i128) -> Result<(), StorageError> {
+        if self.decimal.is_some() {
+            return self.add_decimal(Decimal::from_integer(value));
+        }
+        self.integer = self
+            .integer
+            .checked_add(value)
+            .ok_or_else(|| StorageError::InvalidOperation("Integer overflow in SUM".to_string()))?;
+        Ok(())
+    }
+
+    fn add_decimal(&mut self, value: Decimal) -> Result<(), StorageError> {
+        let current = match self.decimal {
+            Some(current) => current,
+            None => Decimal::from_integer(std::mem::take(&mut self.integer)),
+        };
+        let total = current
+            .checked_add(&value)
+            .filter(|total| total.precision() <= MAX_DECIMAL_PRECISION)
+            .ok_or_else(|| StorageError::InvalidOperation("Decimal overflow in SUM".to_string()))?;
+        self.decimal = Some(total);
+        Ok(())
+    }
+
+    fn add_float(&mut self, value: f64) {
+        self.float += value;
+        self.is_float = true;
+    }
+
+    /// SUM of the values seen so far; NULL if there were none
+    pub fn total(&self) -> Value {
+        if self.count == 0 {
+            Value::Null
+        } else if self.is_float {
+            let exact = match self.decimal {
+                Some(decimal) => decimal.to_f64(),
+                None => self.integer as f64,
             };
-            result_data.push(value);
+            Value::Float(self.float + exact)
+        } else if let Some(decimal) = self.decimal {
+            Value::Decimal(decimal)
+        } else {
+            // Integer sums are always BIGINT, matching `AggregateFunction::result_type`
+            Value::BigInt(self.integer)
         }
+    }
 
-        Ok(Row {
-            id: 0, // Aggregate results don't have meaningful IDs
-            data: result_data,
-        })
+    /// AVG of the values seen so far; NULL if there were none
+    pub fn average(&self) -> Value {
+        match self.total() {
+            Value::Null => Value::Null,
+            // Decimal averages stay exact
+            Value::Decimal(sum) => sum
+                .checked_div(&Decimal::from_integer(self.count as i128))
+                .map_or(
+                    Value::Float(sum.to_f64() / self.count as f64),
+                    Value::Decimal,
+                ),
+            other => Value::Float(other.as_f64().unwrap_or(0.0) / self.count as f64),
+        }
     }
+}
 
-    fn sum_column(rows: &[Row], column: &str, schema: &Schema) -> Result<Value, StorageError> {
-        if let Some(col_idx) = schema.get_column_index(column) {
-            let mut sum = 0i64;
-            let mut float_sum = 0.0f64;
-            let mut is_float = false;
-
-            for row in rows {
-                if let Some(value) = row.data.get(col_idx) {
-                    match value {
-                        Value::Integer(i) => sum += i,
-                        Value::Float(f) => {
-                            if !is_float {
-                                float_sum = sum as f64;
-                                is_float = true;
-                            }
-                            float_sum += f;
-                        }
-                        Value::SmallInt(i) => sum += *i as i64,
-                        Value::BigInt(i) => sum += *i as i64,
-                        Value::TinyInt(i) => sum += *i as i64,
-                        _ => {} // Skip non-numeric values
-                    }
+/// Mergeable state of one aggregate function over one group
+#[derive(Debug, Clone)]
+pub enum Accumulator {
+    Count(i64),
+    CountNonNull(i64),
+    Sum(SumState),
+    Avg(SumState),
+    Min(Option<Value>),
+    Max(Option<Value>),
+    CountDistinct(HashSet<Value>),
+}
+
+impl Accumulator {
+    pub fn new(function: &AggregateFunction) -> Self {
+        match function {
+            AggregateFunction::Count => Accumulator::Count(0),
+            AggregateFunction::CountNonNull { .. } => Accumulator::CountNonNull(0),
+            AggregateFunction::Sum { .. } => Accumulator::Sum(SumState::default()),
+            AggregateFunction::Avg { .. } => Accumulator::Avg(SumState::default()),
+            AggregateFunction::Min { .. } => Accumulator::Min(None),
+            AggregateFunction::Max { .. } => Accumulator::Max(None),
+            AggregateFunction::CountDistinct { .. } => Accumulator::CountDistinct(HashSet::new()),
+        }
+    }
+
+    /// Fold one input value into the state, returning roughly how many bytes the state grew by.
+    /// COUNT(*) counts every call regardless of the value; COUNT(expr) skips NULLs.
+    pub fn update(&mut self, value: &Value) -> Result<usize, StorageError> {
+        Ok(match self {
+            Accumulator::Count(count) => {
+                *count += 1;
+                0
+            }
+            Accumulator::CountNonNull(count) => {
+                if !value.is_null() {
+                    *count += 1;
                 }
+                0
             }
-
-            if is_float {
-                Ok(Value::Float(float_sum))
-            } else {
-                Ok(Value::Integer(sum))
+            Accumulator::Sum(state) | Accumulator::Avg(state) => {
+                state.add(value)?;
+                0
             }
-        } else {
-            Err(StorageError::InvalidOperation(format!(
-                "Column '{}' not found",
-                column
-            )))
-        }
-    }
-
-    fn avg_column(rows: &[Row], column: &str, schema: &Schema) -> Result<Value, StorageError> {
-        if let Some(col_idx) = schema.get_column_index(column) {
-            let mut sum = 0.0f64;
-            let mut count = 0;
-
-            for row in rows {
-                if let Some(value) = row.data.get(col_idx) {
-                    match value {
-                        Value::Integer(i) => {
-                            sum += *i as f64;
-                            count += 1;
-                        }
-                        Value::Float(f) => {
-                            sum += f;
-                            count += 1;
-                        }
-                        Value::SmallInt(i) => {
-                            sum += *i as f64;
-                            count += 1;
-                        }
-                        Value::BigInt(i) => {
-                            sum += *i as f64;
-                            count += 1;
-                        }
-                        Value::TinyInt(i) => {
-                            sum += *i as f64;
-                            count += 1;
-                        }
-                        _ => {} // Skip non-numeric values
-                    }
+            Accumulator::Min(current) => Self::replace_extreme(current, value, Ordering::Less),
+            Accumulator::Max(current) => Self::replace_extreme(current, value, Ordering::Greater),
+            Accumulator::CountDistinct(seen) => {
+                if value.is_null() || seen.contains(value) {
+                    0
+                } else {
+                    let size = value.serialized_size() + GROUP_OVERHEAD / 2;
+                    seen.insert(value.clone());
+                    size
                 }
             }
+        })
+    }
 
-            if count > 0 {
-                Ok(Value::Float(sum / count as f64))
-            } else {
-                Ok(Value::Null)
+    fn replace_extreme(current: &mut Option<Value>, value: &Value, wanted: Ordering) -> usize {
+        if value.is_null() {
+            return 0;
+        }
+        let old_size = current.as_ref().map_or(0, Value::serialized_size);
+        match current {
+            Some(existing) if value.cmp(existing) != wanted => 0,
+            _ => {
+                *current = Some(value.clone());
+                value.serialized_size().saturating_sub(old_size)
             }
-        } else {
-            Err(StorageError::InvalidOperation(format!(
-                "Column '{}' not found",
-                column
-            )))
-        }
-    }
-
-    fn min_column(rows: &[Row], column: &str, schema: &Schema) -> Result<Value, StorageError> {
-        if let Some(col_idx) = schema.get_column_index(column) {
-            let mut min_value: Option<Value> = None;
-
-            for row in rows {
-                if let Some(value) = row.data.get(col_idx) {
-                    if !matches!(value, Value::Null) {
-                        match &min_value {
-                            None => min_value = Some(value.clone()),
-                            Some(current_min) => {
-                                if Self::compare_values_for_aggregate(value, current_min)
-                                    == Ordering::Less
-                                {
-                                    min_value = Some(value.clone());
-                                }
-                            }
-                        }
-                    }
-                }
+        }
+    }
+
+    /// Combine a partial state computed over a disjoint set of rows
+    pub fn merge(&mut self, other: Accumulator) -> Result<(), StorageError> {
+        match (self, other) {
+            (Accumulator::Count(a), Accumulator::Count(b))
+            | (Accumulator::CountNonNull(a), Accumulator::CountNonNull(b)) => *a += b,
+            (Accumulator::Sum(a), Accumulator::Sum(b))
+            | (Accumulator::Avg(a), Accumulator::Avg(b)) => a.merge(&b)?,
+            (Accumulator::Min(a), Accumulator::Min(Some(b))) => {
+                Self::replace_extreme(a, &b, Ordering::Less);
             }
+            (Accumulator::Max(a), Accumulator::Max(Some(b))) => {
+                Self::replace_extreme(a, &b, Ordering::Greater);
+            }
+            (Accumulator::Min(_), Accumulator::Min(None))
+            | (Accumulator::Max(_), Accumulator::Max(None)) => {}
+            (Accumulator::CountDistinct(a), Accumulator::CountDistinct(b)) => a.extend(b),
+            (a, b) => {
+                return Err(StorageError::InvalidOperation(format!(
+                    "Cannot merge aggregate state {:?} into {:?}",
+                    b, a
+                )));
+            }
+        }
+        Ok(())
+    }
 
-            Ok(min_value.unwrap_or(Value::Null))
-        } else {
-            Err(StorageError::InvalidOperation(format!(
-                "Column '{}' not found",
-                column
-            )))
-        }
-    }
-
-    fn max_column(rows: &[Row], column: &str, schema: &Schema) -> Result<Value, StorageError> {
-        if let Some(col_idx) = schema.get_column_index(column) {
-            let mut max_value: Option<Value> = None;
-
-            for row in rows {
-                if let Some(value) = row.data.get(col_idx) {
-                    if !matches!(value, Value::Null) {
-                        match &max_value {
-                            None => max_value = Some(value.clone()),
-                            Some(current_max) => {
-                                if Self::compare_values_for_aggregate(value, current_max)
-                                    == Ordering::Greater
-                                {
-                                    max_value = Some(value.clone());
-                                }
-                            }
-                        }
+    /// Final aggregate value
+    pub fn finish(&self) -> Value {
+        match self {
+            Accumulator::Count(count) | Accumulator::CountNonNull(count) => Value::Integer(*count),
+            Accumulator::Sum(state) => state.total(),
+            Accumulator::Avg(state) => state.average(),
+            Accumulator::Min(value) | Accumulator::Max(value) => {
+                value.clone().unwrap_or(Value::Null)
+            }
+            Accumulator::CountDistinct(seen) => Value::Integer(seen.len() as i64),
+        }
+    }
+
+    pub fn estimated_size(&self) -> usize {
+        match self {
+            Accumulator::Min(Some(value)) | Accumulator::Max(Some(value)) => {
+                value.serialized_size()
+            }
+            Accumulator::CountDistinct(seen) => seen
+                .iter()
+                .map(|v| v.serialized_size() + GROUP_OVERHEAD / 2)
+                .sum(),
+            _ => 0,
+        }
+    }
+}
+
+/// Group-by keys and aggregate arguments compiled against an input schema
+#[derive(Debug, Clone)]
+pub struct CompiledAggregate {
+    group_exprs: Vec<PhysicalExpr>,
+    arguments: Vec<Option<PhysicalExpr>>,
+}
+
+impl CompiledAggregate {
+    pub fn compile(
+        group_by: &[Expr],
+        aggregates: &[AggregateFunction],
+        schema: &Schema,
+    ) -> Result<Self, StorageError> {
+        let group_exprs = group_by
+            .iter()
+            .map(|expr| PhysicalExpr::compile(expr, schema))
+            .collect::<Result<_, _>>()?;
+        let arguments = aggregates
+            .iter()
+            .map(|function| {
+                function
+                    .argument()
+                    .map(|expr| PhysicalExpr::compile(expr, schema))
+                    .transpose()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            group_exprs,
+            arguments,
+        })
+    }
+
+    /// Evaluate the group key and one argument per aggregate (NULL for COUNT(*))
+    pub fn evaluate(&self, row: &Row) -> Result<(Vec<Value>, Vec<Value>), StorageError> {
+        let key = self
+            .group_exprs
+            .iter()
+            .map(|expr| expr.evaluate(row))
+            .collect::<Result<_, _>>()?;
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|argument| match argument {
+                Some(expr) => expr.evaluate(row),
+                None => Ok(Value::Null),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((key, arguments))
+    }
+}
+
+/// Hash table from group key to one accumulator per aggregate
+#[derive(Debug, Clone)]
+pub struct GroupTable {
+    functions: Vec<AggregateFunction>,
+    groups: HashMap<Vec<Value>, Vec<Accumulator>>,
+    memory: usize,
+}
+
+impl GroupTable {
+    pub fn new(functions: Vec<AggregateFunction>) -> Self {
+        Self {
+            functions,
+            groups: HashMap::new(),
+            memory: 0,
+        }
+    }
+
+    pub fn contains(&self, key: &[Value]) -> bool {
+        self.groups.contains_key(key)
+    }
+
+    pub fn update(&mut self, key: Vec<Value>, arguments: &[Value]) -> Result<(), StorageError> {
+        let functions = &self.functions;
+        let memory = &mut self.memory;
+        let accumulators = self.groups.entry(key).or_insert_with_key(|key| {
+            *memory += GROUP_OVERHEAD + key.iter().map(Value::serialized_size).sum::<usize>();
+            functions.iter().map(Accumulator::new).collect()
+        });
+        for (accumulator, value) in accumulators.iter_mut().zip(arguments) {
+            self.memory += accumulator.update(value)?;
+        }
+        Ok(())
+    }
+
+    /// Fold in a table built over a disjoint set of rows
+    pub fn merge(&mut self, other: GroupTable) -> Result<(), StorageError> {
+        for (key, accumulators) in other.groups {
+            match self.groups.get_mut(&key) {
+                Some(existing) => {
+                    for (existing, partial) in existing.iter_mut().zip(accumulators) {
+                        existing.merge(partial)?;
                     }
                 }
+                None => self.insert(key, accumulators),
             }
+        }
+        Ok(())
+    }
 
-            Ok(max_value.unwrap_or(Value::Null))
-        } else {
-            Err(StorageError::InvalidOperation(format!(
-                "Column '{}' not found",
-                column
-            )))
+    fn insert(&mut self, key: Vec<Value>, accumulators: Vec<Accumulator>) {
+        self.memory += GROUP_OVERHEAD
+            + key.iter().map(Value::serialized_size).sum::<usize>()
+            + accumulators
+                .iter()
+                .map(Accumulator::estimated_size)
+                .sum::<usize>();
+        self.groups.insert(key, accumulators);
+    }
+
+    /// Split the groups by the spill partition their key hashes to at `depth`
+    pub fn into_partitions(self, depth: usize) -> Vec<GroupTable> {
+        let mut partitions: Vec<GroupTable> = (0..SPILL_FANOUT)
+            .map(|_| GroupTable::new(self.functions.clone()))
+            .collect();
+        for (key, accumulators) in self.groups {
+            partitions[spill_partition(&key, depth)].insert(key, accumulators);
         }
+        partitions
     }
 
-    fn count_distinct_column(
-        rows: &[Row],
-        column: &str,
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Approximate bytes held by keys and accumulator state
+    pub fn memory_usage(&self) -> usize {
+        self.memory
+    }
+
+    /// One row of values per group: the key followed by each aggregate's result
+    pub fn into_rows(self) -> Vec<Vec<Value>> {
+        self.groups
+            .into_iter()
+            .map(|(mut key, accumulators)| {
+                key.extend(accumulators.iter().map(Accumulator::finish));
+                key
+            })
+            .collect()
+    }
+}
+
+/// Aggregation state owned by one scan worker: rows are folded in as they are
+/// scanned, and the partial tables of all workers are merged at the end.
+/// Past the worker's memory budget, rows of groups not already in the table are
+/// hash-partitioned to disk, as `HashAggregateOperation` does.
+#[derive(Debug)]
+pub struct PartialAggregate {
+    compiled: Arc<CompiledAggregate>,
+    table: GroupTable,
+    memory_budget: usize,
+    spill_dir: PathBuf,
+    partitions: Vec<SpillFile>,
+    spilled_rows: usize,
+}
+
+impl PartialAggregate {
+    pub fn new(compiled: Arc<CompiledAggregate>, functions: Vec<AggregateFunction>) -> Self {
+        Self {
+            compiled,
+            table: GroupTable::new(functions),
+            memory_budget: DEFAULT_MEMORY_BUDGET,
+            spill_dir: std::env::temp_dir(),
+            partitions: Vec::new(),
+            spilled_rows: 0,
+        }
+    }
+
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    pub fn with_spill_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spill_dir = dir.into();
+        self
+    }
+
+    pub fn update(&mut self, row: &Row) -> Result<(), StorageError> {
+        let (key, arguments) = self.compiled.evaluate(row)?;
+        if self.table.memory_usage() < self.memory_budget || self.table.contains(&key) {
+            return self.table.update(key, &arguments);
+        }
+
+        if self.partitions.is_empty() {
+            self.partitions = create_partitions(&self.spill_dir)?;
+        }
+        let partition = spill_partition(&key, 0);
+        let mut data = key;
+        data.extend(arguments);
+        self.partitions[partition].write_row(&Row::new(0, data))?;
+        self.spilled_rows += 1;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct AggregateResult {
+    pub rows: Vec<Row>,
+    pub result_schema: Schema,
+    pub input_rows: usize,
+    pub groups: usize,
+    pub spilled_rows: usize,
+}
+
+/// GROUP BY aggregation with HAVING. When the hash table grows past the memory
+/// budget, rows for groups not already in memory are hash-partitioned to disk
+/// and each partition is aggregated on its own afterwards.
+#[derive(Clone)]
+pub struct HashAggregateOperation {
+    group_by: Vec<(Expr, String)>,
+    aggregates: Vec<(AggregateFunction, String)>,
+    having: Option<Expr>,
+    memory_budget: usize,
+    spill_dir: PathBuf,
+}
+
+impl HashAggregateOperation {
+    pub fn new(
+        group_by: Vec<(Expr, String)>,
+        aggregates: Vec<(AggregateFunction, String)>,
+    ) -> Self {
+        Self {
+            group_by,
+            aggregates,
+            having: None,
+            memory_budget: DEFAULT_MEMORY_BUDGET,
+            spill_dir: std::env::temp_dir(),
+        }
+    }
+
+    /// Filter over the output columns (group aliases and aggregate aliases)
+    pub fn with_having(mut self, having: Expr) -> Self {
+        self.having = Some(having);
+        self
+    }
+
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    pub fn with_spill_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spill_dir = dir.into();
+        self
+    }
+
+    /// Worker state for a scan that splits this aggregate's memory budget `workers` ways
+    pub fn partial(&self, compiled: Arc<CompiledAggregate>, workers: usize) -> PartialAggregate {
+        PartialAggregate::new(compiled, self.functions())
+            .with_memory_budget(self.memory_budget / workers.max(1))
+            .with_spill_dir(&self.spill_dir)
+    }
+
+    pub fn group_exprs(&self) -> Vec<Expr> {
+        self.group_by.iter().map(|(expr, _)| expr.clone()).collect()
+    }
+
+    pub fn functions(&self) -> Vec<AggregateFunction> {
+        self.aggregates
+            .iter()
+            .map(|(function, _)| function.clone())
+            .collect()
+    }
+
+    pub fn output_schema(&self, input_schema: &Schema) -> Result<Schema, StorageError> {
+        let mut columns = Vec::with_capacity(self.group_by.len() + self.aggregates.len());
+        for (expr, alias) in &self.group_by {
+            columns.push(Column::nullable(
+                alias.clone(),
+                expr_data_type(expr, input_schema)?,
+            ));
+        }
+        for (function, alias) in &self.aggregates {
+            columns.push(Column::nullable(
+                alias.clone(),
+                function.result_type(input_schema)?,
+            ));
+        }
+        Ok(Schema::new(columns))
+    }
+
+    pub async fn execute(
+        &self,
+        rows: Vec<Row>,
         schema: &Schema,
-    ) -> Result<Value, StorageError> {
-        if let Some(col_idx) = schema.get_column_index(column) {
-            let mut distinct_values = std::collections::HashSet::new();
+    ) -> Result<AggregateResult, StorageError> {
+        // Hashing, merging and rereading spilled partitions run off the async runtime's workers
+        let operation = self.clone();
+        let schema = schema.clone();
+        tokio::task::spawn_blocking(move || operation.aggregate(rows, &schema))
+            .await
+            .map_err(|e| {
+                StorageError::InvalidOperation(format!("Hash aggregate task failed: {}", e))
+            })?
+    }
 
-            for row in rows {
-                if let Some(value) = row.data.get(col_idx) {
-                    distinct_values.insert(format!("{:?}", value));
-                }
+    fn aggregate(&self, rows: Vec<Row>, schema: &Schema) -> Result<AggregateResult, StorageError> {
+        let compiled = CompiledAggregate::compile(&self.group_exprs(), &self.functions(), schema)?;
+        let input_rows = rows.len();
+        let mut input = rows.iter().map(|row| compiled.evaluate(row));
+
+        let mut groups = Vec::new();
+        let mut spilled_rows = 0;
+        let table = GroupTable::new(self.functions());
+        self.aggregate_partition(&mut input, 0, table, &mut groups, &mut spilled_rows)?;
+
+        let mut result = self.finish(groups, schema)?;
+        result.input_rows = input_rows;
+        result.spilled_rows = spilled_rows;
+        Ok(result)
+    }
+
+    /// Merge the states of the scan workers and aggregate whatever they spilled.
+    /// This is CPU- and disk-bound; async callers run it from a blocking task.
+    pub fn merge_partials(
+        &self,
+        partials: Vec<PartialAggregate>,
+        input_schema: &Schema,
+    ) -> Result<AggregateResult, StorageError> {
+        let mut merged = GroupTable::new(self.functions());
+        let mut spilled: Vec<Vec<SpillFile>> = (0..SPILL_FANOUT).map(|_| Vec::new()).collect();
+        let mut spilled_rows = 0;
+        for partial in partials {
+            spilled_rows += partial.spilled_rows;
+            for (partition, file) in partial.partitions.into_iter().enumerate() {
+                spilled[partition].push(file);
             }
+            merged.merge(partial.table)?;
+        }
 
-            Ok(Value::Integer(distinct_values.len() as i64))
+        let mut groups = Vec::new();
+        if spilled_rows == 0 {
+            groups = merged.into_rows();
         } else {
-            Err(StorageError::InvalidOperation(format!(
-                "Column '{}' not found",
-                column
-            )))
-        }
-    }
-
-    fn compare_values_for_aggregate(a: &Value, b: &Value) -> Ordering {
-        match (a, b) {
-            (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
-            (Value::String(a), Value::String(b)) => a.cmp(b),
-            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
-            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
-            (Value::SmallInt(a), Value::SmallInt(b)) => a.cmp(b),
-            (Value::BigInt(a), Value::BigInt(b)) => a.cmp(b),
-            (Value::TinyInt(a), Value::TinyInt(b)) => a.cmp(b),
-            (Value::Date(a), Value::Date(b)) => a.cmp(b),
-            (Value::Time(a), Value::Time(b)) => a.cmp(b),
-            (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
-            (Value::DateTime(a), Value::DateTime(b)) => a.cmp(b),
-            (Value::Null, Value::Null) => Ordering::Equal,
-            (Value::Null, _) => Ordering::Less,
-            (_, Value::Null) => Ordering::Greater,
-            _ => format!("{:?}", a).cmp(&format!("{:?}", b)),
+            // A key one worker spilled may be in another worker's table, so each
+            // partition resumes from the merged groups that hash to it
+            for (table, files) in merged.into_partitions(0).into_iter().zip(spilled) {
+                let readers = files
+                    .into_iter()
+                    .filter(|file| !file.is_empty())
+                    .map(SpillFile::into_reader)
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut input = readers
+                    .into_iter()
+                    .flatten()
+                    .map(|row| row.map(|row| self.split_spilled(row)));
+                self.aggregate_partition(&mut input, 1, table, &mut groups, &mut spilled_rows)?;
+            }
         }
+
+        let mut result = self.finish(groups, input_schema)?;
+        result.spilled_rows = spilled_rows;
+        Ok(result)
+    }
+
+    /// Key and arguments of a row written to a spill partition
+    fn split_spilled(&self, row: Row) -> (Vec<Value>, Vec<Value>) {
+        let mut key = row.data;
+        let arguments = key.split_off(self.group_by.len());
+        (key, arguments)
+    }
+
+    /// Turn finished group rows into the operator's output, applying HAVING.
+    /// A global aggregate (no GROUP BY) always produces exactly one row.
+    pub fn finish(
+        &self,
+        mut groups: Vec<Vec<Value>>,
+        input_schema: &Schema,
+    ) -> Result<AggregateResult, StorageError> {
+        let result_schema = self.output_schema(input_schema)?;
+        if groups.is_empty() && self.group_by.is_empty() {
+            groups.push(
+                self.aggregates
+                    .iter()
+                    .map(|(function, _)| Accumulator::new(function).finish())
+                    .collect(),
+            );
+        }
+        let group_count = groups.len();
+
+        let having = self
+            .having
+            .as_ref()
+            .map(|expr| PhysicalExpr::compile(expr, &result_schema))
+            .transpose()?;
+        let mut rows = Vec::with_capacity(groups.len());
+        for data in groups {
+            let row = Row::new(rows.len() as u64, data);
+            if let Some(having) = &having
+                && !having.evaluate_predicate(&row)?
+            {
+                continue;
+            }
+            rows.push(row);
+        }
+
+        Ok(AggregateResult {
+            rows,
+            result_schema,
+            input_rows: 0,
+            groups: group_count,
+            spilled_rows: 0,
+        })
+    }
+
+    fn aggregate_partition(
+        &self,
+        input: &mut dyn Iterator<Item = Result<(Vec<Value>, Vec<Value>), StorageError>>,
+        depth: usize,
+        mut table: GroupTable,
+        output: &mut Vec<Vec<Value>>,
+        spilled_rows: &mut usize,
+    ) -> Result<(), StorageError> {
+        let mut partitions: Vec<SpillFile> = Vec::new();
+
+        for item in input {
+            let (key, arguments) = item?;
+            if depth >= MAX_SPILL_DEPTH
+                || table.memory_usage() < self.memory_budget
+                || table.contains(&key)
+            {
+                table.update(key, &arguments)?;
+                continue;
+            }
+
+            if partitions.is_empty() {
+                partitions = create_partitions(&self.spill_dir)?;
+            }
+            let partition = spill_partition(&key, depth);
+            let mut data = key;
+            data.extend(arguments);
+            partitions[partition].write_row(&Row::new(0, data))?;
+            *spilled_rows += 1;
+        }
+        output.extend(table.into_rows());
+
+        // Keys never straddle memory and disk, so each partition aggregates independently
+        for partition in partitions {
+            if partition.is_empty() {
+                continue;
+            }
+            let mut spilled = partition
+                .into_reader()?
+                .map(|row| row.map(|row| self.split_spilled(row)));
+            let table = GroupTable::new(self.functions());
+            self.aggregate_partition(&mut spilled, depth + 1, table, output, spilled_rows)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct AggregateProcessor;
+
+impl AggregateProcessor {
+    /// Aggregate all rows into a single row, one value per aggregate
+    pub fn process_aggregates(
+        rows: &[Row],
+        aggregates: &[AggregateFunction],
+        schema: &Schema,
+    ) -> Result<Row, StorageError> {
+        let compiled = CompiledAggregate::compile(&[], aggregates, schema)?;
+        let mut accumulators: Vec<Accumulator> = aggregates.iter().map(Accumulator::new).collect();
+
+        for row in rows {
+            let (_, arguments) = compiled.evaluate(row)?;
+            for (accumulator, value) in accumulators.iter_mut().zip(&arguments) {
+                accumulator.update(value)?;
+            }
+        }
+
+        Ok(Row {
+            id: 0, // Aggregate results don't have meaningful IDs
+            data: accumulators.iter().map(Accumulator::finish).collect(),
+        })
     }
 }
@@ -1,399 +1,265 @@
-use std::{cmp::Ordering, collections::HashMap};
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap};
 
 use regex::Regex;
 use shared_types::{OrderBy, Predicate, Row, Schema, SortDirection, Value};
-pub fn evaluate_predicate_fast(predicate: &Predicate, row: &Row, column_indices: &HashMap<String, usize>) -> bool {
+
+use crate::operator::expression::like_to_regex;
+
+/// Evaluate a predicate under SQL three-valued logic. `None` is UNKNOWN:
+/// any comparison involving NULL is unknown, AND/OR/NOT follow Kleene
+/// logic, and callers filtering rows keep only `Some(true)`.
+pub fn evaluate_predicate_tristate(
+    predicate: &Predicate,
+    row: &Row,
+    column_indices: &HashMap<String, usize>,
+) -> Option<bool> {
+    let value_of = |column: &str| {
+        column_indices
+            .get(column)
+            .and_then(|&idx| row.data.get(idx))
+    };
+    let compare_with = |column: &str, value: &Value, accept: fn(Ordering) -> bool| {
+        compare_nullable(value_of(column)?, value).map(accept)
+    };
+
     match predicate {
-        Predicate::ColumnEquals { column, value } => {
-            if let Some(&idx) = column_indices.get(column) {
-                if let Some(row_value) = row.data.get(idx) {
-                    return row_value == value;
-                }
-            }
-            false
+        Predicate::ColumnEquals { column, value } => compare_with(column, value, Ordering::is_eq),
+        Predicate::ColumnNotEquals { column, value } => {
+            compare_with(column, value, Ordering::is_ne)
         }
-        Predicate::ColumnLessThan { column, value } => {
-            if let Some(&idx) = column_indices.get(column) {
-                if let Some(row_value) = row.data.get(idx) {
-                    return match (row_value, value) {
-                        (Value::Integer(a), Value::Integer(b)) => a < b,
-                        (Value::Float(a), Value::Float(b)) => a < b,
-                        _ => false,
-                    };
-                }
-            }
-            false
+        Predicate::ColumnLessThan { column, value } => compare_with(column, value, Ordering::is_lt),
+        Predicate::ColumnLessThanOrEqual { column, value } => {
+            compare_with(column, value, Ordering::is_le)
+        }
+        Predicate::ColumnGreaterThan { column, value } => {
+            compare_with(column, value, Ordering::is_gt)
         }
         Predicate::ColumnGreaterThanOrEqual { column, value } => {
-            if let Some(&idx) = column_indices.get(column) {
-                if let Some(row_value) = row.data.get(idx) {
-                    return match (row_value, value) {
-                        (Value::Integer(a), Value::Integer(b)) => a >= b,
-                        (Value::Float(a), Value::Float(b)) => a >= b,
-                        _ => false,
-                    };
-                }
-            }
-            false
+            compare_with(column, value, Ordering::is_ge)
         }
-        Predicate::ColumnLessThanOrEqual { column, value } => {
-            if let Some(&idx) = column_indices.get(column) {
-                if let Some(row_value) = row.data.get(idx) {
-                    return match (row_value, value) {
-                        (Value::Integer(a), Value::Integer(b)) => a <= b,
-                        (Value::Float(a), Value::Float(b)) => a <= b,
-                        _ => false,
-                    };
-                }
-            }
-            false
+        Predicate::ColumnIn { column, values } => in_list_tristate(value_of(column)?, values),
+        Predicate::ColumnNotIn { column, values } => {
+            in_list_tristate(value_of(column)?, values).map(|found| !found)
+        }
+        Predicate::ColumnIsNull { column } => Some(value_of(column)?.is_null()),
+        Predicate::ColumnIsNotNull { column } => Some(!value_of(column)?.is_null()),
+        Predicate::ColumnIsDistinctFrom { column, value } => {
+            Some(is_distinct_from(value_of(column)?, value))
+        }
+        Predicate::ColumnIsNotDistinctFrom { column, value } => {
+            Some(!is_distinct_from(value_of(column)?, value))
+        }
+        Predicate::ColumnLike { column, pattern } => match value_of(column)? {
+            Value::Null => None,
+            Value::String(s) | Value::Text(s) => Some(like_matches(pattern, s)),
+            _ => Some(false),
+        },
+        Predicate::ColumnBetween { column, start, end } => {
+            let row_value = value_of(column)?;
+            kleene_and(
+                compare_nullable(row_value, start).map(Ordering::is_ge),
+                compare_nullable(row_value, end).map(Ordering::is_le),
+            )
         }
         Predicate::And(left, right) => {
-            evaluate_predicate_fast(left, row, column_indices) && evaluate_predicate_fast(right, row, column_indices)
+            let left = evaluate_predicate_tristate(left, row, column_indices);
+            if left == Some(false) {
+                return left;
+            }
+            kleene_and(
+                left,
+                evaluate_predicate_tristate(right, row, column_indices),
+            )
         }
         Predicate::Or(left, right) => {
-            evaluate_predicate_fast(left, row, column_indices) || evaluate_predicate_fast(right, row, column_indices)
+            let left = evaluate_predicate_tristate(left, row, column_indices);
+            if left == Some(true) {
+                return left;
+            }
+            kleene_or(
+                left,
+                evaluate_predicate_tristate(right, row, column_indices),
+            )
+        }
+        Predicate::Not(inner) => {
+            kleene_not(evaluate_predicate_tristate(inner, row, column_indices))
         }
-        _ => false,
     }
 }
 
+/// Distinct LIKE patterns a thread keeps compiled before starting over
+const LIKE_CACHE_CAPACITY: usize = 256;
 
-pub fn compare_values_static(a: &Value, b: &Value) -> std::cmp::Ordering {
-    match (a, b) {
-        (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
-        (Value::String(a), Value::String(b)) => a.cmp(b),
-        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal),
-        (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
-        (Value::SmallInt(a), Value::SmallInt(b)) => a.cmp(b),
-        (Value::BigInt(a), Value::BigInt(b)) => a.cmp(b),
-        (Value::TinyInt(a), Value::TinyInt(b)) => a.cmp(b),
-        (Value::Date(a), Value::Date(b)) => a.cmp(b),
-        (Value::Time(a), Value::Time(b)) => a.cmp(b),
-        (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
-        (Value::DateTime(a), Value::DateTime(b)) => a.cmp(b),
-        (Value::Null, Value::Null) => std::cmp::Ordering::Equal,
-        (Value::Null, _) => std::cmp::Ordering::Less,
-        (_, Value::Null) => std::cmp::Ordering::Greater,
-        _ => format!("{:?}", a).cmp(&format!("{:?}", b)),
-    }
+thread_local! {
+    /// Compiled LIKE patterns, so a predicate builds its regex once instead of per row;
+    /// `None` records a pattern that does not compile
+    static LIKE_REGEXES: RefCell<HashMap<String, Option<Regex>>> = RefCell::new(HashMap::new());
 }
 
-pub fn compare_values(a: &Value, b: &Value) -> Ordering {
-    match (a, b) {
-        (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
-        (Value::String(a), Value::String(b)) => a.cmp(b),
-        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
-        (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
-        (Value::SmallInt(a), Value::SmallInt(b)) => a.cmp(b),
-        (Value::BigInt(a), Value::BigInt(b)) => a.cmp(b),
-        (Value::TinyInt(a), Value::TinyInt(b)) => a.cmp(b),
-        (Value::Date(a), Value::Date(b)) => a.cmp(b),
-        (Value::Time(a), Value::Time(b)) => a.cmp(b),
-        (Value::Timestamp(a), Value::Timestamp(b)) => a.cmp(b),
-        (Value::DateTime(a), Value::DateTime(b)) => a.cmp(b),
-        (Value::Null, Value::Null) => Ordering::Equal,
-        (Value::Null, _) => Ordering::Less,
-        (_, Value::Null) => Ordering::Greater,
-        _ => format!("{:?}", a).cmp(&format!("{:?}", b)),
-    }
+/// `value LIKE pattern`, with the same pattern syntax as LIKE expressions
+fn like_matches(pattern: &str, value: &str) -> bool {
+    LIKE_REGEXES.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if !cache.contains_key(pattern) {
+            if cache.len() >= LIKE_CACHE_CAPACITY {
+                cache.clear();
+            }
+            cache.insert(pattern.to_string(), like_to_regex(pattern, false).ok());
+        }
+        cache[pattern]
+            .as_ref()
+            .is_some_and(|regex| regex.is_match(value))
+    })
 }
 
-pub fn match_value_optimized(
-    column: &str,
+pub fn evaluate_predicate_fast(
+    predicate: &Predicate,
     row: &Row,
-    value: &Value,
-    ord: Ordering,
-    cached_indices: &Option<HashMap<String, usize>>,
+    column_indices: &HashMap<String, usize>,
 ) -> bool {
-    if let Some(col_idx) = cached_indices
-        .as_ref()
-        .and_then(|indices| indices.get(column))
-    {
-        if let Some(row_value) = row.data.get(*col_idx) {
-            return compare_values(row_value, value) == ord;
-        }
-    }
-    false
+    evaluate_predicate_tristate(predicate, row, column_indices) == Some(true)
 }
 
-pub fn match_any_optimized(
-    column: &str,
+pub fn evaluate_predicate_optimized(
+    predicate: &Predicate,
     row: &Row,
-    value: &Value,
-    matches: &[Ordering],
+    _schema: &Schema,
     cached_indices: &Option<HashMap<String, usize>>,
 ) -> bool {
-    if let Some(col_idx) = cached_indices
+    cached_indices
         .as_ref()
-        .and_then(|indices| indices.get(column))
-    {
-        if let Some(row_value) = row.data.get(*col_idx) {
-            return matches.contains(&compare_values(row_value, value));
-        }
-    }
-    false
+        .is_some_and(|indices| evaluate_predicate_fast(predicate, row, indices))
 }
 
-pub fn in_list_optimized(
-    column: &str,
+pub fn evaluate_predicate_optimized_static(
+    predicate: &Predicate,
     row: &Row,
-    values: &[Value],
+    schema: &Schema,
     cached_indices: &Option<HashMap<String, usize>>,
 ) -> bool {
-    if let Some(col_idx) = cached_indices
-        .as_ref()
-        .and_then(|indices| indices.get(column))
-    {
-        if let Some(row_value) = row.data.get(*col_idx) {
-            return values
-                .iter()
-                .any(|v| compare_values(row_value, v) == Ordering::Equal);
-        }
-    }
-    false
+    evaluate_predicate_optimized(predicate, row, schema, cached_indices)
 }
 
-pub fn match_null_optimized(
-    column: &str,
-    row: &Row,
-    is_null: bool,
-    cached_indices: &Option<HashMap<String, usize>>,
-) -> bool {
-    if let Some(col_idx) = cached_indices
-        .as_ref()
-        .and_then(|indices| indices.get(column))
-    {
-        if let Some(val) = row.data.get(*col_idx) {
-            return matches!(val, Value::Null) == is_null;
-        }
+/// Comparison for predicates: UNKNOWN (`None`) when either side is NULL
+pub fn compare_nullable(a: &Value, b: &Value) -> Option<Ordering> {
+    if a.is_null() || b.is_null() {
+        None
+    } else {
+        Some(compare_values(a, b))
     }
-    false
 }
 
-pub fn match_like_optimized(
-    column: &str,
-    row: &Row,
-    pattern: &str,
-    cached_indices: &Option<HashMap<String, usize>>,
-) -> bool {
-    if let Some(col_idx) = cached_indices
-        .as_ref()
-        .and_then(|indices| indices.get(column))
-    {
-        if let Some(Value::String(s)) = row.data.get(*col_idx) {
-            let regex_pattern = pattern.replace('%', ".*").replace('_', ".");
-            if let Ok(regex) = Regex::new(&format!("^{}$", regex_pattern)) {
-                return regex.is_match(s);
-            }
+/// `value IN (list)`: TRUE on a match, otherwise UNKNOWN if the value or any
+/// list element is NULL, so `NOT IN` never matches when the list holds a NULL
+pub fn in_list_tristate(value: &Value, list: &[Value]) -> Option<bool> {
+    if value.is_null() {
+        return None;
+    }
+    let mut saw_null = false;
+    for item in list {
+        if item.is_null() {
+            saw_null = true;
+        } else if value == item {
+            return Some(true);
         }
     }
-    false
+    if saw_null { None } else { Some(false) }
 }
 
-pub fn match_value_optimized_static(
-    column: &str,
-    row: &Row,
-    value: &Value,
-    ord: std::cmp::Ordering,
-    cached_indices: &Option<HashMap<String, usize>>,
-) -> bool {
-    if let Some(col_idx) = cached_indices
-        .as_ref()
-        .and_then(|indices| indices.get(column))
-    {
-        if let Some(row_value) = row.data.get(*col_idx) {
-            return compare_values_static(row_value, value) == ord;
-        }
+/// NULL-safe inequality: two NULLs are not distinct, NULL and a value are
+pub fn is_distinct_from(a: &Value, b: &Value) -> bool {
+    match (a.is_null(), b.is_null()) {
+        (true, true) => false,
+        (true, false) | (false, true) => true,
+        (false, false) => a != b,
     }
-    false
 }
 
-pub fn match_any_optimized_static(
-    column: &str,
-    row: &Row,
-    value: &Value,
-    matches: &[std::cmp::Ordering],
-    cached_indices: &Option<HashMap<String, usize>>,
-) -> bool {
-    if let Some(col_idx) = cached_indices
-        .as_ref()
-        .and_then(|indices| indices.get(column))
-    {
-        if let Some(row_value) = row.data.get(*col_idx) {
-            return matches.contains(&compare_values_static(row_value, value));
-        }
+pub fn kleene_and(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
     }
-    false
 }
 
-pub fn in_list_optimized_static(
-    column: &str,
-    row: &Row,
-    values: &[Value],
-    cached_indices: &Option<HashMap<String, usize>>,
-) -> bool {
-    if let Some(col_idx) = cached_indices
-        .as_ref()
-        .and_then(|indices| indices.get(column))
-    {
-        if let Some(row_value) = row.data.get(*col_idx) {
-            return values
-                .iter()
-                .any(|v| compare_values_static(row_value, v) == std::cmp::Ordering::Equal);
-        }
+pub fn kleene_or(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
     }
-    false
 }
 
-pub fn match_null_optimized_static(
-    column: &str,
-    row: &Row,
-    is_null: bool,
-    cached_indices: &Option<HashMap<String, usize>>,
-) -> bool {
-    if let Some(col_idx) = cached_indices
-        .as_ref()
-        .and_then(|indices| indices.get(column))
-    {
-        if let Some(val) = row.data.get(*col_idx) {
-            return matches!(val, Value::Null) == is_null;
-        }
-    }
-    false
+pub fn kleene_not(value: Option<bool>) -> Option<bool> {
+    value.map(|b| !b)
 }
 
-pub fn match_like_optimized_static(
-    column: &str,
-    row: &Row,
-    pattern: &str,
-    cached_indices: &Option<HashMap<String, usize>>,
-) -> bool {
-    if let Some(col_idx) = cached_indices
-        .as_ref()
-        .and_then(|indices| indices.get(column))
-    {
-        if let Some(Value::String(s)) = row.data.get(*col_idx) {
-            let regex_pattern = pattern.replace('%', ".*").replace('_', ".");
-            if let Ok(regex) = Regex::new(&format!("^{}$", regex_pattern)) {
-                return regex.is_match(s);
-            }
-        }
-    }
-    false
+/// Total ordering over values: numeric widths are promoted, String/Text and
+/// Timestamp/DateTime are treated as the same type, NULL sorts first and
+/// unrelated types are ordered by type family.
+pub fn compare_values_static(a: &Value, b: &Value) -> std::cmp::Ordering {
+    a.cmp(b)
+}
+
+pub fn compare_values(a: &Value, b: &Value) -> Ordering {
+    a.cmp(b)
 }
 
 pub fn sort_rows(rows: &mut Vec<Row>, order_by: &[OrderBy], schema: &Schema) {
-    rows.sort_by(|a, b| {
-        for order in order_by {
-            if let Some(col_idx) = schema.get_column_index(&order.column) {
-                if let (Some(val_a), Some(val_b)) = (a.data.get(col_idx), b.data.get(col_idx)) {
-                    let cmp = compare_values(val_a, val_b);
-                    let result = match order.direction {
-                        SortDirection::Ascending => cmp,
-                        SortDirection::Descending => cmp.reverse(),
-                    };
-                    if result != Ordering::Equal {
-                        return result;
-                    }
-                }
+    let comparator = RowComparator::new(order_by, schema);
+    rows.sort_by(|a, b| comparator.compare(a, b));
+}
+
+/// Compare two values for one ORDER BY key: NULLs go where `nulls_first` puts them,
+/// and only the ordering of non-NULL values follows the sort direction
+pub fn compare_sort_values(a: &Value, b: &Value, order: &OrderBy) -> Ordering {
+    match (a.is_null(), b.is_null()) {
+        (true, true) => Ordering::Equal,
+        (true, false) if order.nulls_first => Ordering::Less,
+        (true, false) => Ordering::Greater,
+        (false, true) if order.nulls_first => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => {
+            let cmp = compare_values(a, b);
+            match order.direction {
+                SortDirection::Ascending => cmp,
+                SortDirection::Descending => cmp.reverse(),
             }
         }
-        Ordering::Equal
-    });
+    }
 }
 
-pub fn evaluate_predicate_optimized_static(
-    predicate: &Predicate,
-    row: &Row,
-    schema: &Schema,
-    cached_indices: &Option<HashMap<String, usize>>,
-) -> bool {
-    match predicate {
-        Predicate::ColumnEquals { column, value } => match_value_optimized_static(
-            column,
-            row,
-            value,
-            std::cmp::Ordering::Equal,
-            cached_indices,
-        ),
-        Predicate::ColumnNotEquals { column, value } => !match_value_optimized_static(
-            column,
-            row,
-            value,
-            std::cmp::Ordering::Equal,
-            cached_indices,
-        ),
-        Predicate::ColumnGreaterThan { column, value } => match_value_optimized_static(
-            column,
-            row,
-            value,
-            std::cmp::Ordering::Greater,
-            cached_indices,
-        ),
-        Predicate::ColumnLessThan { column, value } => match_value_optimized_static(
-            column,
-            row,
-            value,
-            std::cmp::Ordering::Less,
-            cached_indices,
-        ),
-        Predicate::ColumnGreaterThanOrEqual { column, value } => match_any_optimized_static(
-            column,
-            row,
-            value,
-            &[std::cmp::Ordering::Greater, std::cmp::Ordering::Equal],
-            cached_indices,
-        ),
-        Predicate::ColumnLessThanOrEqual { column, value } => match_any_optimized_static(
-            column,
-            row,
-            value,
-            &[std::cmp::Ordering::Less, std::cmp::Ordering::Equal],
-            cached_indices,
-        ),
-        Predicate::ColumnIn { column, values } => {
-            in_list_optimized_static(column, row, values, cached_indices)
-        }
-        Predicate::ColumnNotIn { column, values } => {
-            !in_list_optimized_static(column, row, values, cached_indices)
-        }
-        Predicate::ColumnIsNull { column } => {
-            match_null_optimized_static(column, row, true, cached_indices)
-        }
-        Predicate::ColumnIsNotNull { column } => {
-            match_null_optimized_static(column, row, false, cached_indices)
-        }
-        Predicate::ColumnLike { column, pattern } => {
-            match_like_optimized_static(column, row, pattern, cached_indices)
-        }
-        Predicate::ColumnBetween { column, start, end } => {
-            if let Some(col_idx) = cached_indices
-                .as_ref()
-                .and_then(|indices| indices.get(column))
-            {
-                if let Some(val) = row.data.get(*col_idx) {
-                    let start_cmp = compare_values_static(val, start);
-                    let end_cmp = compare_values_static(val, end);
-                    return (start_cmp == std::cmp::Ordering::Greater
-                        || start_cmp == std::cmp::Ordering::Equal)
-                        && (end_cmp == std::cmp::Ordering::Less
-                            || end_cmp == std::cmp::Ordering::Equal);
+/// Multi-key row ordering with the key columns resolved once; keys naming a
+/// column missing from the schema are ignored
+#[derive(Debug, Clone)]
+pub struct RowComparator {
+    keys: Vec<(usize, OrderBy)>,
+}
+
+impl RowComparator {
+    pub fn new(order_by: &[OrderBy], schema: &Schema) -> Self {
+        let keys = order_by
+            .iter()
+            .filter_map(|order| {
+                schema
+                    .get_column_index(&order.column)
+                    .map(|idx| (idx, order.clone()))
+            })
+            .collect();
+        Self { keys }
+    }
+
+    pub fn compare(&self, a: &Row, b: &Row) -> Ordering {
+        for (col_idx, order) in &self.keys {
+            if let (Some(val_a), Some(val_b)) = (a.data.get(*col_idx), b.data.get(*col_idx)) {
+                let result = compare_sort_values(val_a, val_b, order);
+                if result != Ordering::Equal {
+                    return result;
                 }
             }
-            false
-        }
-        Predicate::And(left, right) => {
-            evaluate_predicate_optimized_static(left, row, schema, cached_indices)
-                && evaluate_predicate_optimized_static(right, row, schema, cached_indices)
-        }
-        Predicate::Or(left, right) => {
-            evaluate_predicate_optimized_static(left, row, schema, cached_indices)
-                || evaluate_predicate_optimized_static(right, row, schema, cached_indices)
-        }
-        Predicate::Not(inner) => {
-            !evaluate_predicate_optimized_static(inner, row, schema, cached_indices)
         }
+        Ordering::Equal
     }
 }
 
@@ -423,7 +289,9 @@ pub fn collect_predicate_columns(
         | Predicate::ColumnIsNull { column }
         | Predicate::ColumnIsNotNull { column }
         | Predicate::ColumnLike { column, .. }
-        | Predicate::ColumnBetween { column, .. } => {
+        | Predicate::ColumnBetween { column, .. }
+        | Predicate::ColumnIsDistinctFrom { column, .. }
+        | Predicate::ColumnIsNotDistinctFrom { column, .. } => {
             if let Some(idx) = schema.get_column_index(column) {
                 indices.insert(column.clone(), idx);
             }
@@ -437,77 +305,3 @@ pub fn collect_predicate_columns(
         }
     }
 }
-
-pub fn evaluate_predicate_optimized(
-    predicate: &Predicate,
-    row: &Row,
-    schema: &Schema,
-    cached_indices: &Option<HashMap<String, usize>>,
-) -> bool {
-    match predicate {
-        Predicate::ColumnEquals { column, value } => {
-            match_value_optimized(column, row, value, Ordering::Equal, cached_indices)
-        }
-        Predicate::ColumnNotEquals { column, value } => {
-            !match_value_optimized(column, row, value, Ordering::Equal, cached_indices)
-        }
-        Predicate::ColumnGreaterThan { column, value } => {
-            match_value_optimized(column, row, value, Ordering::Greater, cached_indices)
-        }
-        Predicate::ColumnLessThan { column, value } => {
-            match_value_optimized(column, row, value, Ordering::Less, cached_indices)
-        }
-        Predicate::ColumnGreaterThanOrEqual { column, value } => match_any_optimized(
-            column,
-            row,
-            value,
-            &[Ordering::Greater, Ordering::Equal],
-            cached_indices,
-        ),
-        Predicate::ColumnLessThanOrEqual { column, value } => match_any_optimized(
-            column,
-            row,
-            value,
-            &[Ordering::Less, Ordering::Equal],
-            cached_indices,
-        ),
-        Predicate::ColumnIn { column, values } => {
-            in_list_optimized(column, row, values, cached_indices)
-        }
-        Predicate::ColumnNotIn { column, values } => {
-            !in_list_optimized(column, row, values, cached_indices)
-        }
-        Predicate::ColumnIsNull { column } => {
-            match_null_optimized(column, row, true, cached_indices)
-        }
-        Predicate::ColumnIsNotNull { column } => {
-            match_null_optimized(column, row, false, cached_indices)
-        }
-        Predicate::ColumnLike { column, pattern } => {
-            match_like_optimized(column, row, pattern, cached_indices)
-        }
-        Predicate::ColumnBetween { column, start, end } => {
-            if let Some(col_idx) = cached_indices
-                .as_ref()
-                .and_then(|indices| indices.get(column))
-            {
-                if let Some(val) = row.data.get(*col_idx) {
-                    let start_cmp = compare_values(val, start);
-                    let end_cmp = compare_values(val, end);
-                    return (start_cmp == Ordering::Greater || start_cmp == Ordering::Equal)
-                        && (end_cmp == Ordering::Less || end_cmp == Ordering::Equal);
-                }
-            }
-            false
-        }
-        Predicate::And(left, right) => {
-            evaluate_predicate_optimized(left, row, schema, cached_indices)
-                && evaluate_predicate_optimized(right, row, schema, cached_indices)
-        }
-        Predicate::Or(left, right) => {
-            evaluate_predicate_optimized(left, row, schema, cached_indices)
-                || evaluate_predicate_optimized(right, row, schema, cached_indices)
-        }
-        Predicate::Not(inner) => !evaluate_predicate_optimized(inner, row, schema, cached_indices),
-    }
-}
@@ -0,0 +1,769 @@
+use regex::Regex;
+use crate::operator::compare::is_distinct_from;
+use crate::operator::function;
+use shared_types::decimal::{DIVISION_EXTRA_SCALE, MAX_DECIMAL_PRECISION};
+use shared_types::{BinaryOp, Decimal, Column, DataType, Expr, Row, Schema, StorageError, UnaryOp, Value};
+
+pub use shared_types::ScalarFunction;
+
+/// Expression compiled against a schema: column references are resolved to
+/// positional indices, functions are resolved and constant LIKE patterns are
+/// turned into regexes once, so evaluation never touches column names.
+#[derive(Debug, Clone)]
+pub enum PhysicalExpr {
+    Column(usize),
+    Literal(Value),
+    Binary {
+        left: Box<PhysicalExpr>,
+        op: BinaryOp,
+        right: Box<PhysicalExpr>,
+    },
+    Unary {
+        op: UnaryOp,
+        expr: Box<PhysicalExpr>,
+    },
+    Function {
+        func: ScalarFunction,
+        args: Vec<PhysicalExpr>,
+    },
+    Case {
+        operand: Option<Box<PhysicalExpr>>,
+        when_then: Vec<(PhysicalExpr, PhysicalExpr)>,
+        else_expr: Option<Box<PhysicalExpr>>,
+    },
+    Cast {
+        expr: Box<PhysicalExpr>,
+        data_type: DataType,
+    },
+    IsNull(Box<PhysicalExpr>),
+    IsNotNull(Box<PhysicalExpr>),
+    InList {
+        expr: Box<PhysicalExpr>,
+        list: Vec<PhysicalExpr>,
+        negated: bool,
+    },
+    Between {
+        expr: Box<PhysicalExpr>,
+        low: Box<PhysicalExpr>,
+        high: Box<PhysicalExpr>,
+        negated: bool,
+    },
+    Like {
+        expr: Box<PhysicalExpr>,
+        pattern: LikePattern,
+        negated: bool,
+        case_insensitive: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum LikePattern {
+    Compiled(Regex),
+    Dynamic(Box<PhysicalExpr>),
+}
+
+impl PhysicalExpr {
+    /// Resolve an expression against the schema of the rows it will be evaluated on
+    pub fn compile(expr: &Expr, schema: &Schema) -> Result<Self, StorageError> {
+        let compile_box = |e: &Expr| -> Result<Box<PhysicalExpr>, StorageError> {
+            Ok(Box::new(Self::compile(e, schema)?))
+        };
+
+        match expr {
+            Expr::Column(name) => schema
+                .get_column_index(name)
+                .map(PhysicalExpr::Column)
+                .ok_or_else(|| {
+                    StorageError::InvalidOperation(format!("Column '{}' not found in schema", name))
+                }),
+            Expr::Literal(value) => Ok(PhysicalExpr::Literal(value.clone())),
+            Expr::Binary { left, op, right } => Ok(PhysicalExpr::Binary {
+                left: compile_box(left)?,
+                op: *op,
+                right: compile_box(right)?,
+            }),
+            Expr::Unary { op, expr } => Ok(PhysicalExpr::Unary {
+                op: *op,
+                expr: compile_box(expr)?,
+            }),
+            Expr::Function { name, args } => {
+                let func = ScalarFunction::from_name(name).ok_or_else(|| {
+                    StorageError::InvalidOperation(format!("Unknown function '{}'", name))
+                })?;
+                func.check_argument_count(args.len())
+                    .map_err(|e| StorageError::InvalidOperation(e.to_string()))?;
+                let args = args
+                    .iter()
+                    .map(|arg| Self::compile(arg, schema))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(PhysicalExpr::Function { func, args })
+            }
+            Expr::Case {
+                operand,
+                when_then,
+                else_expr,
+            } => Ok(PhysicalExpr::Case {
+                operand: operand.as_deref().map(compile_box).transpose()?,
+                when_then: when_then
+                    .iter()
+                    .map(|(when, then)| {
+                        Ok((Self::compile(when, schema)?, Self::compile(then, schema)?))
+                    })
+                    .collect::<Result<Vec<_>, StorageError>>()?,
+                else_expr: else_expr.as_deref().map(compile_box).transpose()?,
+            }),
+            Expr::Cast { expr, data_type } => Ok(PhysicalExpr::Cast {
+                expr: compile_box(expr)?,
+                data_type: data_type.clone(),
+            }),
+            Expr::IsNull(expr) => Ok(PhysicalExpr::IsNull(compile_box(expr)?)),
+            Expr::IsNotNull(expr) => Ok(PhysicalExpr::IsNotNull(compile_box(expr)?)),
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => Ok(PhysicalExpr::InList {
+                expr: compile_box(expr)?,
+                list: list
+                    .iter()
+                    .map(|item| Self::compile(item, schema))
+                    .collect::<Result<Vec<_>, _>>()?,
+                negated: *negated,
+            }),
+            Expr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => Ok(PhysicalExpr::Between {
+                expr: compile_box(expr)?,
+                low: compile_box(low)?,
+                high: compile_box(high)?,
+                negated: *negated,
+            }),
+            Expr::Like {
+                expr,
+                pattern,
+                negated,
+                case_insensitive,
+            } => {
+                let pattern = match pattern.as_ref() {
+                    Expr::Literal(Value::String(p)) | Expr::Literal(Value::Text(p)) => {
+                        LikePattern::Compiled(like_to_regex(p, *case_insensitive)?)
+                    }
+                    other => LikePattern::Dynamic(compile_box(other)?),
+                };
+                Ok(PhysicalExpr::Like {
+                    expr: compile_box(expr)?,
+                    pattern,
+                    negated: *negated,
+                    case_insensitive: *case_insensitive,
+                })
+            }
+        }
+    }
+
+    pub fn evaluate(&self, row: &Row) -> Result<Value, StorageError> {
+        match self {
+            PhysicalExpr::Column(idx) => row.data.get(*idx).cloned().ok_or_else(|| {
+                StorageError::InvalidOperation(format!("Column index {} out of bounds", idx))
+            }),
+            PhysicalExpr::Literal(value) => Ok(value.clone()),
+            PhysicalExpr::Binary { left, op, right } => match op {
+                BinaryOp::And => {
+                    let l = left.evaluate(row)?;
+                    if l == Value::Boolean(false) {
+                        return Ok(l);
+                    }
+                    let r = right.evaluate(row)?;
+                    logical_and(&l, &r)
+                }
+                BinaryOp::Or => {
+                    let l = left.evaluate(row)?;
+                    if l == Value::Boolean(true) {
+                        return Ok(l);
+                    }
+                    let r = right.evaluate(row)?;
+                    logical_or(&l, &r)
+                }
+                _ => binary_op(&left.evaluate(row)?, *op, &right.evaluate(row)?),
+            },
+            PhysicalExpr::Unary { op, expr } => unary_op(*op, &expr.evaluate(row)?),
+            PhysicalExpr::Function { func, args } => {
+                let values = args
+                    .iter()
+                    .map(|arg| arg.evaluate(row))
+                    .collect::<Result<Vec<_>, _>>()?;
+                function::invoke(*func, &values)
+            }
+            PhysicalExpr::Case {
+                operand,
+                when_then,
+                else_expr,
+            } => {
+                let operand = operand.as_ref().map(|o| o.evaluate(row)).transpose()?;
+                for (when, then) in when_then {
+                    let condition = when.evaluate(row)?;
+                    let matched = match &operand {
+                        Some(operand) => binary_op(operand, BinaryOp::Eq, &condition)?,
+                        None => condition,
+                    };
+                    if matched == Value::Boolean(true) {
+                        return then.evaluate(row);
+                    }
+                }
+                match else_expr {
+                    Some(else_expr) => else_expr.evaluate(row),
+                    None => Ok(Value::Null),
+                }
+            }
+            PhysicalExpr::Cast { expr, data_type } => cast_value(expr.evaluate(row)?, data_type),
+            PhysicalExpr::IsNull(expr) => Ok(Value::Boolean(expr.evaluate(row)?.is_null())),
+            PhysicalExpr::IsNotNull(expr) => Ok(Value::Boolean(!expr.evaluate(row)?.is_null())),
+            PhysicalExpr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let value = expr.evaluate(row)?;
+                if value.is_null() {
+                    return Ok(Value::Null);
+                }
+                let mut saw_null = false;
+                for item in list {
+                    let item = item.evaluate(row)?;
+                    if item.is_null() {
+                        saw_null = true;
+                    } else if value == item {
+                        return Ok(Value::Boolean(!negated));
+                    }
+                }
+                if saw_null {
+                    Ok(Value::Null)
+                } else {
+                    Ok(Value::Boolean(*negated))
+                }
+            }
+            PhysicalExpr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let value = expr.evaluate(row)?;
+                let lower = binary_op(&value, BinaryOp::GtEq, &low.evaluate(row)?)?;
+                let upper = binary_op(&value, BinaryOp::LtEq, &high.evaluate(row)?)?;
+                let result = logical_and(&lower, &upper)?;
+                if *negated {
+                    unary_op(UnaryOp::Not, &result)
+                } else {
+                    Ok(result)
+                }
+            }
+            PhysicalExpr::Like {
+                expr,
+                pattern,
+                negated,
+                case_insensitive,
+            } => {
+                let value = expr.evaluate(row)?;
+                let text = match &value {
+                    Value::Null => return Ok(Value::Null),
+                    Value::String(s) | Value::Text(s) => s,
+                    other => return Err(type_error("LIKE", other)),
+                };
+                let is_match = match pattern {
+                    LikePattern::Compiled(regex) => regex.is_match(text),
+                    LikePattern::Dynamic(pattern) => match pattern.evaluate(row)? {
+                        Value::Null => return Ok(Value::Null),
+                        Value::String(p) | Value::Text(p) => {
+                            like_to_regex(&p, *case_insensitive)?.is_match(text)
+                        }
+                        other => return Err(type_error("LIKE", &other)),
+                    },
+                };
+                Ok(Value::Boolean(is_match != *negated))
+            }
+        }
+    }
+
+    /// Evaluate as a filter condition: only TRUE keeps the row, FALSE and NULL drop it
+    pub fn evaluate_predicate(&self, row: &Row) -> Result<bool, StorageError> {
+        Ok(self.evaluate_tristate(row)? == Some(true))
+    }
+
+    /// Evaluate as a condition under three-valued logic, with `None` for UNKNOWN
+    pub fn evaluate_tristate(&self, row: &Row) -> Result<Option<bool>, StorageError> {
+        match self.evaluate(row)? {
+            Value::Boolean(b) => Ok(Some(b)),
+            Value::Null => Ok(None),
+            other => Err(StorageError::InvalidOperation(format!(
+                "Filter expression must be boolean, got {}",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+/// Derive the result type of an expression, used to build output schemas
+pub fn expr_data_type(expr: &Expr, schema: &Schema) -> Result<DataType, StorageError> {
+    match expr {
+        Expr::Column(name) => schema
+            .get_column(name)
+            .map(|c| c.data_type.clone())
+            .ok_or_else(|| {
+                StorageError::InvalidOperation(format!("Column '{}' not found in schema", name))
+            }),
+        Expr::Literal(value) => Ok(value_data_type(value)),
+        Expr::Binary { left, op, right } => match op {
+            BinaryOp::Eq
+            | BinaryOp::NotEq
+            | BinaryOp::Lt
+            | BinaryOp::LtEq
+            | BinaryOp::Gt
+            | BinaryOp::GtEq
+            | BinaryOp::And
+            | BinaryOp::Or
+            | BinaryOp::IsDistinctFrom
+            | BinaryOp::IsNotDistinctFrom => Ok(DataType::Boolean),
+            BinaryOp::StringConcat => Ok(DataType::String),
+            _ => {
+                let l = expr_data_type(left, schema)?;
+                let r = expr_data_type(right, schema)?;
+                Ok(arithmetic_result_type(&l, *op, &r))
+            }
+        },
+        Expr::Unary { op, expr } => match op {
+            UnaryOp::Not => Ok(DataType::Boolean),
+            _ => expr_data_type(expr, schema),
+        },
+        Expr::Function { name, args } => {
+            let func = ScalarFunction::from_name(name).ok_or_else(|| {
+                StorageError::InvalidOperation(format!("Unknown function '{}'", name))
+            })?;
+            // NULL literals have no type of their own and match any parameter
+            let arg_types = args
+                .iter()
+                .map(|arg| match arg {
+                    Expr::Literal(Value::Null) => Ok(None),
+                    arg => expr_data_type(arg, schema).map(Some),
+                })
+                .collect::<Result<Vec<_>, StorageError>>()?;
+            func.return_type(&arg_types)
+                .map(|data_type| data_type.unwrap_or(DataType::String))
+                .map_err(|e| StorageError::InvalidOperation(e.to_string()))
+        }
+        Expr::Case {
+            when_then,
+            else_expr,
+            ..
+        } => match (when_then.first(), else_expr) {
+            (Some((_, then)), _) => expr_data_type(then, schema),
+            (None, Some(else_expr)) => expr_data_type(else_expr, schema),
+            (None, None) => Ok(DataType::String),
+        },
+        Expr::Cast { data_type, .. } => Ok(data_type.clone()),
+        Expr::IsNull(_)
+        | Expr::IsNotNull(_)
+        | Expr::InList { .. }
+        | Expr::Between { .. }
+        | Expr::Like { .. } => Ok(DataType::Boolean),
+    }
+}
+
+pub fn value_data_type(value: &Value) -> DataType {
+    value.data_type().unwrap_or(DataType::String)
+}
+
+/// Keep only the rows for which `predicate` evaluates to TRUE
+pub fn filter_rows(
+    rows: Vec<Row>,
+    predicate: &Expr,
+    schema: &Schema,
+) -> Result<Vec<Row>, StorageError> {
+    let compiled = PhysicalExpr::compile(predicate, schema)?;
+    let mut result = Vec::with_capacity(rows.len());
+    for row in rows {
+        if compiled.evaluate_predicate(&row)? {
+            result.push(row);
+        }
+    }
+    Ok(result)
+}
+
+/// Evaluate `(expression, output name)` pairs for every row, returning the rows and their schema
+pub fn project_rows(
+    rows: &[Row],
+    projections: &[(Expr, String)],
+    schema: &Schema,
+) -> Result<(Vec<Row>, Schema), StorageError> {
+    let mut compiled = Vec::with_capacity(projections.len());
+    let mut columns = Vec::with_capacity(projections.len());
+    for (expr, name) in projections {
+        compiled.push(PhysicalExpr::compile(expr, schema)?);
+        columns.push(Column::nullable(
+            name.clone(),
+            expr_data_type(expr, schema)?,
+        ));
+    }
+
+    let mut result = Vec::with_capacity(rows.len());
+    for row in rows {
+        let data = compiled
+            .iter()
+            .map(|expr| expr.evaluate(row))
+            .collect::<Result<Vec<_>, _>>()?;
+        result.push(Row::new(row.id, data));
+    }
+    Ok((result, Schema::new(columns)))
+}
+
+fn logical_and(l: &Value, r: &Value) -> Result<Value, StorageError> {
+    match (as_bool(l)?, as_bool(r)?) {
+        (Some(false), _) | (_, Some(false)) => Ok(Value::Boolean(false)),
+        (Some(true), Some(true)) => Ok(Value::Boolean(true)),
+        _ => Ok(Value::Null),
+    }
+}
+
+fn logical_or(l: &Value, r: &Value) -> Result<Value, StorageError> {
+    match (as_bool(l)?, as_bool(r)?) {
+        (Some(true), _) | (_, Some(true)) => Ok(Value::Boolean(true)),
+        (Some(false), Some(false)) => Ok(Value::Boolean(false)),
+        _ => Ok(Value::Null),
+    }
+}
+
+fn as_bool(value: &Value) -> Result<Option<bool>, StorageError> {
+    match value {
+        Value::Boolean(b) => Ok(Some(*b)),
+        Value::Null => Ok(None),
+        other => Err(type_error("boolean logic", other)),
+    }
+}
+
+fn unary_op(op: UnaryOp, value: &Value) -> Result<Value, StorageError> {
+    if value.is_null() {
+        return Ok(Value::Null);
+    }
+    match op {
+        UnaryOp::Plus => Ok(value.clone()),
+        UnaryOp::Minus => {
+            // The minimum of each integer type has no positive counterpart
+            let negated = match value {
+                Value::Integer(v) => v.checked_neg().map(Value::Integer),
+                Value::SmallInt(v) => v.checked_neg().map(Value::SmallInt),
+                Value::TinyInt(v) => v.checked_neg().map(Value::TinyInt),
+                Value::BigInt(v) => v.checked_neg().map(Value::BigInt),
+                Value::Float(v) => Some(Value::Float(-v)),
+                Value::Decimal(d) => {
+                    return d.checked_neg().map(Value::Decimal).ok_or_else(|| {
+                        StorageError::InvalidOperation(format!(
+                            "Decimal overflow in {:?}",
+                            BinaryOp::Minus
+                        ))
+                    });
+                }
+                other => return Err(type_error("unary minus", other)),
+            };
+            negated.ok_or_else(|| {
+                StorageError::InvalidOperation(format!("Integer overflow in {:?}", BinaryOp::Minus))
+            })
+        }
+        UnaryOp::Not => match value {
+            Value::Boolean(b) => Ok(Value::Boolean(!b)),
+            other => Err(type_error("NOT", other)),
+        },
+    }
+}
+
+pub(crate) fn binary_op(l: &Value, op: BinaryOp, r: &Value) -> Result<Value, StorageError> {
+    match op {
+        BinaryOp::And => logical_and(l, r),
+        BinaryOp::Or => logical_or(l, r),
+        BinaryOp::IsDistinctFrom => Ok(Value::Boolean(is_distinct_from(l, r))),
+        BinaryOp::IsNotDistinctFrom => Ok(Value::Boolean(!is_distinct_from(l, r))),
+        _ if l.is_null() || r.is_null() => Ok(Value::Null),
+        BinaryOp::Eq => Ok(Value::Boolean(l == r)),
+        BinaryOp::NotEq => Ok(Value::Boolean(l != r)),
+        BinaryOp::Lt => Ok(Value::Boolean(l < r)),
+        BinaryOp::LtEq => Ok(Value::Boolean(l <= r)),
+        BinaryOp::Gt => Ok(Value::Boolean(l > r)),
+        BinaryOp::GtEq => Ok(Value::Boolean(l >= r)),
+        BinaryOp::StringConcat => Ok(Value::String(format!(
+            "{}{}",
+            value_to_string(l),
+            value_to_string(r)
+        ))),
+        BinaryOp::Plus
+        | BinaryOp::Minus
+        | BinaryOp::Multiply
+        | BinaryOp::Divide
+        | BinaryOp::Modulo => arithmetic(l, op, r),
+    }
+}
+
+fn arithmetic(l: &Value, op: BinaryOp, r: &Value) -> Result<Value, StorageError> {
+    if let (Some(a), Some(b)) = (l.as_i128(), r.as_i128()) {
+        let result = match op {
+            BinaryOp::Plus => a.checked_add(b),
+            BinaryOp::Minus => a.checked_sub(b),
+            BinaryOp::Multiply => a.checked_mul(b),
+            BinaryOp::Divide | BinaryOp::Modulo if b == 0 => {
+                return Err(StorageError::InvalidOperation(
+                    "Division by zero".to_string(),
+                ));
+            }
+            BinaryOp::Divide => a.checked_div(b),
+            _ => a.checked_rem(b),
+        };
+        // Integer results take the wider of the two operand types
+        let rank = l.numeric_rank().max(r.numeric_rank()).unwrap_or(2);
+        return result
+            .and_then(|v| integer_with_rank(v, rank))
+            .ok_or_else(|| StorageError::InvalidOperation(format!("Integer overflow in {:?}", op)));
+    }
+
+    // Integers and decimals stay exact; only floats fall back to approximate math
+    if let (Some(a), Some(b)) = (l.as_decimal(), r.as_decimal()) {
+        if b.is_zero() && matches!(op, BinaryOp::Divide | BinaryOp::Modulo) {
+            return Err(StorageError::InvalidOperation(
+                "Division by zero".to_string(),
+            ));
+        }
+        let result = match op {
+            BinaryOp::Plus => a.checked_add(&b),
+            BinaryOp::Minus => a.checked_sub(&b),
+            BinaryOp::Multiply => a.checked_mul(&b),
+            BinaryOp::Divide => a.checked_div(&b),
+            _ => a.checked_rem(&b),
+        };
+        return result
+            .map(Value::Decimal)
+            .ok_or_else(|| StorageError::InvalidOperation(format!("Decimal overflow in {:?}", op)));
+    }
+
+    match (l.as_f64(), r.as_f64()) {
+        (Some(a), Some(b)) => match op {
+            BinaryOp::Plus => Ok(Value::Float(a + b)),
+            BinaryOp::Minus => Ok(Value::Float(a - b)),
+            BinaryOp::Multiply => Ok(Value::Float(a * b)),
+            BinaryOp::Divide | BinaryOp::Modulo if b == 0.0 => Err(StorageError::InvalidOperation(
+                "Division by zero".to_string(),
+            )),
+            BinaryOp::Divide => Ok(Value::Float(a / b)),
+            _ => Ok(Value::Float(a % b)),
+        },
+        _ => Err(StorageError::InvalidOperation(format!(
+            "Cannot apply {:?} to {} and {}",
+            op,
+            l.type_name(),
+            r.type_name()
+        ))),
+    }
+}
+
+pub(crate) fn cast_value(value: Value, data_type: &DataType) -> Result<Value, StorageError> {
+    if value.is_null() {
+        return Ok(Value::Null);
+    }
+    let cast_error = |value: &Value| {
+        StorageError::InvalidOperation(format!(
+            "Cannot cast {} to {:?}",
+            value.type_name(),
+            data_type
+        ))
+    };
+
+    match data_type {
+        DataType::Integer | DataType::SmallInt | DataType::TinyInt | DataType::BigInt => {
+            // BIGINT is 128 bits wide, so every source goes through i128
+            let int = match &value {
+                Value::Float(f) => float_to_i128(*f),
+                Value::Decimal(d) => d.round(0).to_integer(),
+                Value::Boolean(b) => Some(*b as i128),
+                Value::String(s) | Value::Text(s) => s.trim().parse::<i128>().ok(),
+                other => other.as_i128(),
+            }
+            .ok_or_else(|| cast_error(&value))?;
+            match data_type {
+                DataType::SmallInt => i16::try_from(int)
+                    .map(Value::SmallInt)
+                    .map_err(|_| cast_error(&value)),
+                DataType::TinyInt => i8::try_from(int)
+                    .map(Value::TinyInt)
+                    .map_err(|_| cast_error(&value)),
+                DataType::BigInt => Ok(Value::BigInt(int)),
+                _ => i64::try_from(int)
+                    .map(Value::Integer)
+                    .map_err(|_| cast_error(&value)),
+            }
+        }
+        DataType::Float => match &value {
+            Value::String(s) | Value::Text(s) => s
+                .trim()
+                .parse::<f64>()
+                .map(Value::Float)
+                .map_err(|_| cast_error(&value)),
+            other => other.as_f64()
+                .map(Value::Float)
+                .ok_or_else(|| cast_error(&value)),
+        },
+        DataType::Boolean => match &value {
+            Value::Boolean(_) => Ok(value),
+            Value::String(s) | Value::Text(s) => match s.trim().to_lowercase().as_str() {
+                "true" | "t" | "1" => Ok(Value::Boolean(true)),
+                "false" | "f" | "0" => Ok(Value::Boolean(false)),
+                _ => Err(cast_error(&value)),
+            },
+            other => as_i64(other)
+                .map(|i| Value::Boolean(i != 0))
+                .ok_or_else(|| cast_error(&value)),
+        },
+        DataType::Decimal { precision, scale } => {
+            let decimal = match &value {
+                Value::Float(f) => Decimal::from_f64(*f, *scale),
+                Value::String(s) | Value::Text(s) => s.parse::<Decimal>().ok(),
+                other => other.as_decimal(),
+            }
+            .ok_or_else(|| cast_error(&value))?;
+            decimal
+                .fit(*precision, *scale)
+                .map(Value::Decimal)
+                .map_err(|_| cast_error(&value))
+        }
+        DataType::String => Ok(Value::String(value_to_string(&value))),
+        DataType::Text => Ok(Value::Text(value_to_string(&value))),
+        other if value_data_type(&value) == *other => Ok(value),
+        _ => Err(cast_error(&value)),
+    }
+}
+
+pub(crate) fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) | Value::Text(s) | Value::Json(s) => s.clone(),
+        Value::Decimal(d) => d.to_string(),
+        Value::Integer(v) => v.to_string(),
+        Value::SmallInt(v) => v.to_string(),
+        Value::TinyInt(v) => v.to_string(),
+        Value::BigInt(v) => v.to_string(),
+        Value::Float(v) => v.to_string(),
+        Value::Boolean(v) => v.to_string(),
+        Value::Char(c) => c.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Result type of an arithmetic expression, widening along the numeric tower.
+/// Decimal results follow the usual SQL precision/scale rules.
+fn arithmetic_result_type(l: &DataType, op: BinaryOp, r: &DataType) -> DataType {
+    if *l == DataType::Float || *r == DataType::Float {
+        return DataType::Float;
+    }
+    if matches!(l, DataType::Decimal { .. }) || matches!(r, DataType::Decimal { .. }) {
+        let (p1, s1) = decimal_shape(l);
+        let (p2, s2) = decimal_shape(r);
+        let (precision, scale) = match op {
+            BinaryOp::Multiply => (p1 + p2, s1 + s2),
+            BinaryOp::Divide => {
+                let scale = s1.max(s2) + DIVISION_EXTRA_SCALE;
+                (p1 - s1 + s2 + scale, scale)
+            }
+            _ => {
+                let scale = s1.max(s2);
+                ((p1 - s1).max(p2 - s2) + scale + 1, scale)
+            }
+        };
+        return DataType::Decimal {
+            precision: precision.min(MAX_DECIMAL_PRECISION),
+            scale: scale.min(MAX_DECIMAL_PRECISION),
+        };
+    }
+    let rank = |t: &DataType| match t {
+        DataType::TinyInt => 0,
+        DataType::SmallInt => 1,
+        DataType::BigInt => 3,
+        _ => 2,
+    };
+    match rank(l).max(rank(r)) {
+        0 => DataType::TinyInt,
+        1 => DataType::SmallInt,
+        3 => DataType::BigInt,
+        _ => DataType::Integer,
+    }
+}
+
+/// Precision and scale an integer or decimal type occupies as a decimal
+fn decimal_shape(data_type: &DataType) -> (u8, u8) {
+    match data_type {
+        DataType::Decimal { precision, scale } => (*precision, *scale),
+        DataType::TinyInt => (3, 0),
+        DataType::SmallInt => (5, 0),
+        DataType::BigInt => (MAX_DECIMAL_PRECISION, 0),
+        _ => (19, 0),
+    }
+}
+
+fn integer_with_rank(value: i128, rank: u8) -> Option<Value> {
+    match rank {
+        0 => i8::try_from(value).ok().map(Value::TinyInt),
+        1 => i16::try_from(value).ok().map(Value::SmallInt),
+        2 => i64::try_from(value).ok().map(Value::Integer),
+        _ => Some(Value::BigInt(value)),
+    }
+}
+
+/// Round a float to an integer, or `None` for NaN, infinities and values out of range
+fn float_to_i128(f: f64) -> Option<i128> {
+    let rounded = f.round();
+    // i128::MAX as f64 rounds up to 2^127, which is itself out of range
+    if rounded.is_nan() || rounded < i128::MIN as f64 || rounded >= i128::MAX as f64 {
+        return None;
+    }
+    Some(rounded as i128)
+}
+
+pub(crate) fn as_i64(value: &Value) -> Option<i64> {
+    value.as_i128().and_then(|v| i64::try_from(v).ok())
+}
+
+pub(crate) fn text_arg<'a>(function: &str, value: &'a Value) -> Result<&'a str, StorageError> {
+    match value {
+        Value::String(s) | Value::Text(s) => Ok(s),
+        other => Err(type_error(function, other)),
+    }
+}
+
+pub(crate) fn type_error(context: &str, value: &Value) -> StorageError {
+    StorageError::InvalidOperation(format!(
+        "Invalid argument type for {}: {}",
+        context,
+        value.type_name()
+    ))
+}
+
+pub(crate) fn like_to_regex(pattern: &str, case_insensitive: bool) -> Result<Regex, StorageError> {
+    let mut regex = String::with_capacity(pattern.len() + 8);
+    if case_insensitive {
+        regex.push_str("(?i)");
+    }
+    // `%` and `_` match any character, line breaks included
+    regex.push_str("(?s)^");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => regex.push_str(".*"),
+            '_' => regex.push('.'),
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    regex.push_str(&regex::escape(&escaped.to_string()));
+                }
+            }
+            other => regex.push_str(&regex::escape(&other.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).map_err(|e| {
+        StorageError::InvalidInput(format!("Invalid LIKE pattern '{}': {}", pattern, e))
+    })
+}
@@ -0,0 +1,549 @@
+use crate::operator::expression::{
+    as_i64, binary_op, cast_value, text_arg, type_error, value_data_type, value_to_string,
+};
+use shared_types::decimal::MAX_DECIMAL_PRECISION;
+use shared_types::function::common_type;
+use shared_types::{BinaryOp, Decimal, ScalarFunction, StorageError, Value};
+use std::cmp::Ordering;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MS_PER_SECOND: i64 = 1000;
+const MS_PER_MINUTE: i64 = 60 * MS_PER_SECOND;
+const MS_PER_HOUR: i64 = 60 * MS_PER_MINUTE;
+const MS_PER_DAY: i64 = 24 * MS_PER_HOUR;
+
+/// Evaluate a built-in scalar function. The argument count has already been checked
+/// against the function's signature when the expression was compiled.
+pub fn invoke(func: ScalarFunction, args: &[Value]) -> Result<Value, StorageError> {
+    match func {
+        ScalarFunction::Coalesce => {
+            let first = args.iter().find(|v| !v.is_null()).cloned();
+            return coerce_to_common(first.unwrap_or(Value::Null), args);
+        }
+        ScalarFunction::Greatest => return extreme(args, Ordering::Greater),
+        ScalarFunction::Least => return extreme(args, Ordering::Less),
+        ScalarFunction::Concat => {
+            return Ok(Value::String(
+                args.iter()
+                    .filter(|v| !v.is_null())
+                    .map(value_to_string)
+                    .collect(),
+            ));
+        }
+        ScalarFunction::NullIf => {
+            return match binary_op(&args[0], BinaryOp::Eq, &args[1])? {
+                Value::Boolean(true) => Ok(Value::Null),
+                _ => Ok(args[0].clone()),
+            };
+        }
+        ScalarFunction::Now => return Ok(Value::Timestamp(now_millis())),
+        // Every other function returns NULL for any NULL argument
+        _ if args.iter().any(Value::is_null) => return Ok(Value::Null),
+        _ => {}
+    }
+
+    match func {
+        ScalarFunction::Lower => {
+            text_arg("lower", &args[0]).map(|s| Value::String(s.to_lowercase()))
+        }
+        ScalarFunction::Upper => {
+            text_arg("upper", &args[0]).map(|s| Value::String(s.to_uppercase()))
+        }
+        ScalarFunction::Length => {
+            text_arg("length", &args[0]).map(|s| Value::Integer(s.chars().count() as i64))
+        }
+        ScalarFunction::Substr => substr(args),
+        ScalarFunction::Trim | ScalarFunction::Ltrim | ScalarFunction::Rtrim => trim(func, args),
+        ScalarFunction::Replace => {
+            let text = text_arg("replace", &args[0])?;
+            let from = text_arg("replace", &args[1])?;
+            let to = text_arg("replace", &args[2])?;
+            if from.is_empty() {
+                return Ok(Value::String(text.to_string()));
+            }
+            Ok(Value::String(text.replace(from, to)))
+        }
+        ScalarFunction::Abs => {
+            // The minimum of each integer type has no positive counterpart
+            let abs = match &args[0] {
+                Value::Integer(v) => v.checked_abs().map(Value::Integer),
+                Value::SmallInt(v) => v.checked_abs().map(Value::SmallInt),
+                Value::TinyInt(v) => v.checked_abs().map(Value::TinyInt),
+                Value::BigInt(v) => v.checked_abs().map(Value::BigInt),
+                Value::Float(v) => Some(Value::Float(v.abs())),
+                Value::Decimal(d) => {
+                    return d.checked_abs().map(Value::Decimal).ok_or_else(|| {
+                        StorageError::InvalidOperation("Decimal overflow in abs".to_string())
+                    });
+                }
+                other => return Err(type_error("abs", other)),
+            };
+            abs.ok_or_else(|| integer_overflow("abs"))
+        }
+        ScalarFunction::Round => {
+            let digits = match args.get(1) {
+                Some(value) => round_digits(value)?,
+                None => 0,
+            };
+            round(&args[0], digits)
+        }
+        ScalarFunction::Floor => floor_or_ceil("floor", &args[0], false),
+        ScalarFunction::Ceil => floor_or_ceil("ceil", &args[0], true),
+        ScalarFunction::Power => match (args[0].as_f64(), args[1].as_f64()) {
+            (Some(base), Some(exponent)) => Ok(Value::Float(base.powf(exponent))),
+            (None, _) => Err(type_error("power", &args[0])),
+            (_, None) => Err(type_error("power", &args[1])),
+        },
+        ScalarFunction::Mod => binary_op(&args[0], BinaryOp::Modulo, &args[1]),
+        ScalarFunction::DateTrunc => date_trunc(text_arg("date_trunc", &args[0])?, &args[1]),
+        ScalarFunction::Extract => extract(text_arg("extract", &args[0])?, &args[1]),
+        ScalarFunction::DateAdd => {
+            let amount = as_i64(&args[1]).ok_or_else(|| type_error("date_add", &args[1]))?;
+            let unit = match args.get(2) {
+                Some(unit) => text_arg("date_add", unit)?,
+                None => "day",
+            };
+            date_add(&args[0], amount, unit)
+        }
+        ScalarFunction::Coalesce
+        | ScalarFunction::Greatest
+        | ScalarFunction::Least
+        | ScalarFunction::Concat
+        | ScalarFunction::NullIf
+        | ScalarFunction::Now => unreachable!("handled above"),
+    }
+}
+
+/// Convert a function result to the common type of its arguments, so that for
+/// example `greatest(1, 2.5)` and `greatest(3, 2.5)` are both FLOAT
+fn coerce_to_common(result: Value, args: &[Value]) -> Result<Value, StorageError> {
+    if result.is_null() {
+        return Ok(result);
+    }
+    let mut common = None;
+    for arg in args.iter().filter(|v| !v.is_null()) {
+        let data_type = value_data_type(arg);
+        common = match common {
+            None => Some(data_type),
+            Some(current) => match common_type(&current, &data_type) {
+                Some(widened) => Some(widened),
+                None => return Ok(result),
+            },
+        };
+    }
+    match common {
+        Some(data_type) if data_type != value_data_type(&result) => cast_value(result, &data_type),
+        _ => Ok(result),
+    }
+}
+
+/// GREATEST / LEAST: the extreme non-NULL argument, NULL only when all are NULL
+fn extreme(args: &[Value], wanted: Ordering) -> Result<Value, StorageError> {
+    let mut best: Option<&Value> = None;
+    for value in args.iter().filter(|v| !v.is_null()) {
+        best = match best {
+            Some(current) if value.cmp(current) != wanted => Some(current),
+            _ => Some(value),
+        };
+    }
+    coerce_to_common(best.cloned().unwrap_or(Value::Null), args)
+}
+
+/// SUBSTR(text, start [, length]) with 1-based positions. As in PostgreSQL, a start
+/// before the first character shortens the length instead of shifting the window.
+fn substr(args: &[Value]) -> Result<Value, StorageError> {
+    let text = text_arg("substr", &args[0])?;
+    let start = as_i64(&args[1]).ok_or_else(|| type_error("substr", &args[1]))?;
+    let end = match args.get(2) {
+        Some(length) => {
+            let length = as_i64(length).ok_or_else(|| type_error("substr", length))?;
+            if length < 0 {
+                return Err(StorageError::InvalidInput(
+                    "Negative substring length not allowed".to_string(),
+                ));
+            }
+            start.saturating_add(length)
+        }
+        None => i64::MAX,
+    };
+    let skip = (start.max(1) - 1) as usize;
+    let take = (end.saturating_sub(start.max(1))).max(0) as usize;
+    Ok(Value::String(text.chars().skip(skip).take(take).collect()))
+}
+
+fn trim(func: ScalarFunction, args: &[Value]) -> Result<Value, StorageError> {
+    let name = func.name();
+    let text = text_arg(name, &args[0])?;
+    let characters: Vec<char> = match args.get(1) {
+        Some(characters) => text_arg(name, characters)?.chars().collect(),
+        None => vec![' '],
+    };
+    let matches = |c: char| characters.contains(&c);
+    let trimmed = match func {
+        ScalarFunction::Ltrim => text.trim_start_matches(matches),
+        ScalarFunction::Rtrim => text.trim_end_matches(matches),
+        _ => text.trim_matches(matches),
+    };
+    Ok(Value::String(trimmed.to_string()))
+}
+
+/// FLOOR / CEIL. Integers are already whole; decimals keep their scale.
+fn floor_or_ceil(name: &str, value: &Value, ceil: bool) -> Result<Value, StorageError> {
+    match value {
+        Value::Float(v) => Ok(Value::Float(if ceil { v.ceil() } else { v.floor() })),
+        Value::Decimal(d) => {
+            let unit = 10i128.pow(d.scale() as u32);
+            let mut whole = Some(d.mantissa().div_euclid(unit));
+            if ceil && d.mantissa().rem_euclid(unit) != 0 {
+                whole = whole.and_then(|whole| whole.checked_add(1));
+            }
+            whole
+                .and_then(|whole| whole.checked_mul(unit))
+                .map(|mantissa| Value::Decimal(Decimal::new(mantissa, d.scale())))
+                .ok_or_else(|| {
+                    StorageError::InvalidOperation(format!("Decimal overflow in {}", name))
+                })
+        }
+        value if value.as_i128().is_some() => Ok(value.clone()),
+        other => Err(type_error(name, other)),
+    }
+}
+
+/// ROUND's digits argument. Past the widest decimal there is nothing left to round.
+fn round_digits(value: &Value) -> Result<i32, StorageError> {
+    let digits = as_i64(value).ok_or_else(|| type_error("round", value))?;
+    let limit = MAX_DECIMAL_PRECISION as i64;
+    if !(-limit..=limit).contains(&digits) {
+        return Err(StorageError::InvalidInput(format!(
+            "round digits must be between {} and {}, got {}",
+            -limit, limit, digits
+        )));
+    }
+    Ok(digits as i32)
+}
+
+/// ROUND(value, digits), rounding half away from zero. Negative digits round to tens,
+/// hundreds and so on, for integers as well as decimals.
+fn round(value: &Value, digits: i32) -> Result<Value, StorageError> {
+    match value {
+        Value::Float(v) => {
+            let factor = 10f64.powi(digits);
+            Ok(Value::Float((v * factor).round() / factor))
+        }
+        Value::Decimal(d) if digits >= 0 => Ok(Value::Decimal(d.round(digits as u8))),
+        Value::Decimal(d) => d
+            .round(0)
+            .to_integer()
+            .and_then(|whole| round_to_power_of_ten(whole, digits.unsigned_abs()))
+            .map(|whole| Value::Decimal(Decimal::from_integer(whole)))
+            .ok_or_else(|| StorageError::InvalidOperation("Decimal overflow in round".to_string())),
+        value if digits >= 0 && value.as_i128().is_some() => Ok(value.clone()),
+        value => {
+            let whole = value.as_i128().ok_or_else(|| type_error("round", value))?;
+            let rounded = round_to_power_of_ten(whole, digits.unsigned_abs());
+            let rounded = match (value, rounded) {
+                (Value::Integer(_), Some(r)) => i64::try_from(r).ok().map(Value::Integer),
+                (Value::SmallInt(_), Some(r)) => i16::try_from(r).ok().map(Value::SmallInt),
+                (Value::TinyInt(_), Some(r)) => i8::try_from(r).ok().map(Value::TinyInt),
+                (_, r) => r.map(Value::BigInt),
+            };
+            rounded.ok_or_else(|| integer_overflow("round"))
+        }
+    }
+}
+
+/// Round to a multiple of `10^exponent`, half away from zero; `None` on overflow
+fn round_to_power_of_ten(value: i128, exponent: u32) -> Option<i128> {
+    let Some(factor) = 10i128.checked_pow(exponent) else {
+        // Half of the factor is already beyond any i128
+        return Some(0);
+    };
+    let mut quotient = value / factor;
+    if (value % factor).unsigned_abs() >= factor.unsigned_abs().div_ceil(2) {
+        quotient += value.signum();
+    }
+    quotient.checked_mul(factor)
+}
+
+fn integer_overflow(function: &str) -> StorageError {
+    StorageError::InvalidOperation(format!("Integer overflow in {}", function))
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Unit names are case-insensitive and may be plural (`'days'`)
+fn normalize_unit(unit: &str) -> String {
+    let unit = unit.trim().to_lowercase();
+    match unit.strip_suffix('s') {
+        Some(singular) if !singular.is_empty() => singular.to_string(),
+        _ => unit,
+    }
+}
+
+fn unit_error(function: &str, unit: &str, value: &Value) -> StorageError {
+    StorageError::InvalidInput(format!(
+        "Unit '{}' is not supported by {} for {}",
+        unit,
+        function,
+        value.type_name()
+    ))
+}
+
+/// Split a millisecond timestamp into days since the epoch and milliseconds into the day
+fn split_timestamp(ms: i64) -> (i64, i64) {
+    (ms.div_euclid(MS_PER_DAY), ms.rem_euclid(MS_PER_DAY))
+}
+
+fn with_timestamp_of(value: &Value, ms: i64) -> Value {
+    match value {
+        Value::DateTime(_) => Value::DateTime(ms),
+        _ => Value::Timestamp(ms),
+    }
+}
+
+fn date_value(days: i64) -> Result<Value, StorageError> {
+    i32::try_from(days)
+        .map(Value::Date)
+        .map_err(|_| StorageError::InvalidOperation("Date out of range".to_string()))
+}
+
+/// First day of the `unit` period containing `days`, or `None` for sub-day units
+fn truncate_days(days: i64, unit: &str) -> Option<i64> {
+    let (year, month, _) = civil_from_days(days);
+    match unit {
+        "year" => Some(days_from_civil(year, 1, 1)),
+        "quarter" => Some(days_from_civil(year, (month - 1) / 3 * 3 + 1, 1)),
+        "month" => Some(days_from_civil(year, month, 1)),
+        // Weeks start on Monday
+        "week" => Some(days - (iso_weekday(days) - 1)),
+        "day" => Some(days),
+        _ => None,
+    }
+}
+
+fn truncate_millis(ms_of_day: i64, unit: &str) -> Option<i64> {
+    let step = match unit {
+        "hour" => MS_PER_HOUR,
+        "minute" => MS_PER_MINUTE,
+        "second" => MS_PER_SECOND,
+        _ => return None,
+    };
+    Some(ms_of_day - ms_of_day % step)
+}
+
+/// DATE_TRUNC(unit, value): round a date/time down to the start of its unit
+fn date_trunc(unit: &str, value: &Value) -> Result<Value, StorageError> {
+    let unit = normalize_unit(unit);
+    match value {
+        Value::Date(days) => match truncate_days(*days as i64, &unit) {
+            Some(days) => date_value(days),
+            // A date is already at midnight
+            None if truncate_millis(0, &unit).is_some() => Ok(value.clone()),
+            None => Err(unit_error("date_trunc", &unit, value)),
+        },
+        Value::Timestamp(ms) | Value::DateTime(ms) => {
+            let (days, ms_of_day) = split_timestamp(*ms);
+            let truncated = match truncate_days(days, &unit) {
+                Some(days) => days * MS_PER_DAY,
+                None => match truncate_millis(ms_of_day, &unit) {
+                    Some(ms_of_day) => days * MS_PER_DAY + ms_of_day,
+                    None => return Err(unit_error("date_trunc", &unit, value)),
+                },
+            };
+            Ok(with_timestamp_of(value, truncated))
+        }
+        Value::Time(ms) => truncate_millis(*ms as i64, &unit)
+            .map(|ms| Value::Time(ms as u32))
+            .ok_or_else(|| unit_error("date_trunc", &unit, value)),
+        other => Err(type_error("date_trunc", other)),
+    }
+}
+
+/// EXTRACT(field FROM value)
+fn extract(field: &str, value: &Value) -> Result<Value, StorageError> {
+    let field = field.trim().to_lowercase();
+    let (days, ms_of_day) = match value {
+        Value::Date(days) => (Some(*days as i64), 0),
+        Value::Timestamp(ms) | Value::DateTime(ms) => {
+            let (days, ms_of_day) = split_timestamp(*ms);
+            (Some(days), ms_of_day)
+        }
+        Value::Time(ms) => (None, *ms as i64),
+        other => return Err(type_error("extract", other)),
+    };
+
+    let time_part = match field.as_str() {
+        "hour" => Some(ms_of_day / MS_PER_HOUR),
+        "minute" => Some(ms_of_day % MS_PER_HOUR / MS_PER_MINUTE),
+        "second" => Some(ms_of_day % MS_PER_MINUTE / MS_PER_SECOND),
+        "epoch" => Some((days.unwrap_or(0) * MS_PER_DAY + ms_of_day).div_euclid(MS_PER_SECOND)),
+        _ => None,
+    };
+    if let Some(part) = time_part {
+        return Ok(Value::Integer(part));
+    }
+
+    let days = days.ok_or_else(|| unit_error("extract", &field, value))?;
+    let (year, month, day) = civil_from_days(days);
+    let part = match field.as_str() {
+        "year" => year,
+        "quarter" => (month as i64 - 1) / 3 + 1,
+        "month" => month as i64,
+        "week" => iso_week(days),
+        "day" => day as i64,
+        // Sunday is 0, as in PostgreSQL
+        "dow" => iso_weekday(days) % 7,
+        "doy" => days - days_from_civil(year, 1, 1) + 1,
+        _ => return Err(unit_error("extract", &field, value)),
+    };
+    Ok(Value::Integer(part))
+}
+
+/// DATE_ADD(value, amount, unit): month arithmetic clamps to the end of shorter
+/// months, and times of day wrap around midnight
+fn date_add(value: &Value, amount: i64, unit: &str) -> Result<Value, StorageError> {
+    let unit = normalize_unit(unit);
+    let months = match unit.as_str() {
+        "year" => amount.checked_mul(12),
+        "quarter" => amount.checked_mul(3),
+        "month" => Some(amount),
+        _ => None,
+    };
+    let days = match unit.as_str() {
+        "week" => amount.checked_mul(7),
+        "day" => Some(amount),
+        _ => None,
+    };
+    let millis = match unit.as_str() {
+        "hour" => amount.checked_mul(MS_PER_HOUR),
+        "minute" => amount.checked_mul(MS_PER_MINUTE),
+        "second" => amount.checked_mul(MS_PER_SECOND),
+        _ => None,
+    };
+    let overflow = || StorageError::InvalidOperation("Date/time overflow in date_add".to_string());
+
+    match value {
+        Value::Date(current) => {
+            let current = *current as i64;
+            if let Some(months) = months {
+                return date_value(add_months(current, months).ok_or_else(overflow)?);
+            }
+            match days {
+                Some(days) => date_value(current.checked_add(days).ok_or_else(overflow)?),
+                None => Err(unit_error("date_add", &unit, value)),
+            }
+        }
+        Value::Timestamp(ms) | Value::DateTime(ms) => {
+            let shifted = if let Some(months) = months {
+                let (days, ms_of_day) = split_timestamp(*ms);
+                add_months(days, months)
+                    .and_then(|days| days.checked_mul(MS_PER_DAY))
+                    .and_then(|ms| ms.checked_add(ms_of_day))
+            } else {
+                let delta = match (days, millis) {
+                    (Some(days), _) => days.checked_mul(MS_PER_DAY),
+                    (None, Some(millis)) => Some(millis),
+                    (None, None) => return Err(unit_error("date_add", &unit, value)),
+                };
+                delta.and_then(|delta| ms.checked_add(delta))
+            };
+            Ok(with_timestamp_of(value, shifted.ok_or_else(overflow)?))
+        }
+        Value::Time(ms) => match millis {
+            Some(millis) => Ok(Value::Time(
+                (*ms as i64 + millis.rem_euclid(MS_PER_DAY)).rem_euclid(MS_PER_DAY) as u32,
+            )),
+            None => Err(unit_error("date_add", &unit, value)),
+        },
+        other => Err(type_error("date_add", other)),
+    }
+}
+
+fn add_months(days: i64, months: i64) -> Option<i64> {
+    let (year, month, day) = civil_from_days(days);
+    let total = (year.checked_mul(12)?)
+        .checked_add(month as i64 - 1)?
+        .checked_add(months)?;
+    let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+    Some(days_from_civil(
+        year,
+        month,
+        day.min(days_in_month(year, month)),
+    ))
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Monday is 1 and Sunday is 7; the epoch was a Thursday
+fn iso_weekday(days: i64) -> i64 {
+    (days + 3).rem_euclid(7) + 1
+}
+
+/// ISO 8601 week number: week 1 holds the year's first Thursday
+fn iso_week(days: i64) -> i64 {
+    let (year, _, _) = civil_from_days(days);
+    let ordinal = days - days_from_civil(year, 1, 1) + 1;
+    let week = (ordinal - iso_weekday(days) + 10) / 7;
+    if week < 1 {
+        iso_weeks_in_year(year - 1)
+    } else if week > iso_weeks_in_year(year) {
+        1
+    } else {
+        week
+    }
+}
+
+fn iso_weeks_in_year(year: i64) -> i64 {
+    let p = |y: i64| (y + y.div_euclid(4) - y.div_euclid(100) + y.div_euclid(400)).rem_euclid(7);
+    if p(year) == 4 || p(year - 1) == 3 {
+        53
+    } else {
+        52
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let shifted_month = (month as i64 + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// `(year, month, day)` for a count of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
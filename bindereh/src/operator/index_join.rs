@@ -0,0 +1,142 @@
+use crate::manager::Manager;
+use crate::operator::expression::PhysicalExpr;
+use crate::operator::join::{
+    JoinResult, JoinType, join_output_schema, join_result_schema, merge_rows,
+};
+use crate::operator::tree::TreeOperations;
+use crate::page::Page;
+use shared_types::{Expr, Row, Schema, StorageError, Value};
+use std::sync::Arc;
+
+/// Index nested-loop join. For every outer row, the inner table's B+ tree is descended
+/// with `TreeOperations::find_leaf_for_key` to the leaf holding the row whose key equals
+/// the outer join column, so only the leaves that can match are ever read.
+pub struct IndexNestedLoopJoinOperation {
+    storage_manager: Arc<Manager>,
+    root_page_id: u64,
+    join_type: JoinType,
+    outer_key_column: String,
+    residual: Option<Expr>,
+}
+
+impl IndexNestedLoopJoinOperation {
+    /// Join outer rows to the table rooted at `root_page_id` on `outer_key_column = row key`
+    pub fn new(
+        storage_manager: Arc<Manager>,
+        root_page_id: u64,
+        join_type: JoinType,
+        outer_key_column: impl Into<String>,
+    ) -> Self {
+        Self {
+            storage_manager,
+            root_page_id,
+            join_type,
+            outer_key_column: outer_key_column.into(),
+            residual: None,
+        }
+    }
+
+    /// Extra condition evaluated against the joined row; a pair only matches if it is TRUE
+    pub fn with_residual(mut self, residual: Expr) -> Self {
+        self.residual = Some(residual);
+        self
+    }
+
+    /// Outer rows form the left side of the result and inner table rows the right side
+    pub async fn execute(
+        &self,
+        outer_rows: Vec<Row>,
+        outer_schema: &Schema,
+        inner_schema: &Schema,
+    ) -> Result<JoinResult, StorageError> {
+        let keep_unmatched = match self.join_type {
+            JoinType::Inner | JoinType::LeftSemi => false,
+            JoinType::LeftOuter | JoinType::LeftAnti => true,
+            ref other => {
+                return Err(StorageError::InvalidOperation(format!(
+                    "Index nested-loop join does not support {:?} joins",
+                    other
+                )));
+            }
+        };
+
+        let joined_schema = join_result_schema(outer_schema, inner_schema);
+        let residual = self
+            .residual
+            .as_ref()
+            .map(|expr| PhysicalExpr::compile(expr, &joined_schema))
+            .transpose()?;
+        let result_schema = join_output_schema(&self.join_type, outer_schema, joined_schema);
+        let left_only = self.join_type.returns_left_only();
+        let key_index = outer_schema
+            .get_column_index(&self.outer_key_column)
+            .ok_or_else(|| {
+                StorageError::InvalidOperation(format!(
+                    "Column '{}' not found in schema",
+                    self.outer_key_column
+                ))
+            })?;
+
+        let root = self.storage_manager.read_page(self.root_page_id).await?;
+        let null_inner_row = Row::new(0, vec![Value::Null; inner_schema.column_count()]);
+        // Consecutive outer keys often fall in the same leaf, sorted outer input always does
+        let mut current_leaf: Option<Arc<Page>> = None;
+        let mut inner_rows_fetched = 0;
+        let mut rows = Vec::new();
+
+        for outer_row in &outer_rows {
+            let key = outer_row
+                .get_value(key_index)
+                .and_then(Value::as_i128)
+                .and_then(|key| u64::try_from(key).ok());
+
+            let mut matched = false;
+            if let Some(key) = key {
+                let cached = current_leaf
+                    .as_ref()
+                    .is_some_and(|leaf| leaf.keys.binary_search(&key).is_ok());
+                if !cached {
+                    let leaf_id =
+                        TreeOperations::find_leaf_for_key(&self.storage_manager, key, &root)
+                            .await?;
+                    current_leaf = Some(self.storage_manager.read_page(leaf_id).await?);
+                }
+
+                let leaf = current_leaf.as_ref().expect("leaf was just loaded");
+                if let Ok(position) = leaf.keys.binary_search(&key) {
+                    inner_rows_fetched += 1;
+                    let joined_row = merge_rows(outer_row, &leaf.values[position]);
+                    matched = match &residual {
+                        Some(residual) => residual.evaluate_predicate(&joined_row)?,
+                        None => true,
+                    };
+                    if matched {
+                        match self.join_type {
+                            JoinType::LeftSemi => rows.push(outer_row.clone()),
+                            JoinType::LeftAnti => {}
+                            _ => rows.push(joined_row),
+                        }
+                    }
+                }
+            }
+
+            if !matched && keep_unmatched {
+                if left_only {
+                    rows.push(outer_row.clone());
+                } else {
+                    rows.push(merge_rows(outer_row, &null_inner_row));
+                }
+            }
+        }
+
+        let output_rows = rows.len();
+        Ok(JoinResult {
+            rows,
+            result_schema,
+            left_rows_processed: outer_rows.len(),
+            right_rows_processed: inner_rows_fetched,
+            output_rows,
+            spilled_rows: 0,
+        })
+    }
+}
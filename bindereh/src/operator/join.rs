@@ -1,7 +1,21 @@
+use crate::common::MAX_KEYS_PER_NODE;
 use crate::manager::Manager;
-use shared_types::{Row, Schema, StorageError, Value};
+use crate::operator::expression::PhysicalExpr;
+use crate::spill::{
+    DEFAULT_MEMORY_BUDGET, MAX_SPILL_DEPTH, SpillFile, create_partitions, spill_partition,
+};
+use shared_types::{Expr, Row, RuntimeFilter, Schema, StorageError, Value};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+
+/// Probe rows handed to a worker at a time, and so the size of each output batch
+const DEFAULT_BATCH_SIZE: usize = 4096;
 
 #[derive(Debug, Clone)]
 pub enum JoinType {
@@ -9,6 +23,25 @@ pub enum JoinType {
     LeftOuter,
     RightOuter,
     FullOuter,
+    /// Every pair of rows, with no join key
+    Cross,
+    /// Left rows with at least one match, each returned once (`EXISTS`, `IN`)
+    LeftSemi,
+    /// Left rows without a match (`NOT EXISTS`)
+    LeftAnti,
+    /// Left rows that are definitely unmatched under SQL `NOT IN`: a NULL on either side
+    /// of a key comparison makes it UNKNOWN, which also drops the row
+    NullAwareLeftAnti,
+}
+
+impl JoinType {
+    /// Semi and anti joins return the left rows alone, without any right columns
+    pub fn returns_left_only(&self) -> bool {
+        matches!(
+            self,
+            JoinType::LeftSemi | JoinType::LeftAnti | JoinType::NullAwareLeftAnti
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -24,12 +57,75 @@ pub struct JoinResult {
     pub left_rows_processed: usize,
     pub right_rows_processed: usize,
     pub output_rows: usize,
+    pub spilled_rows: usize,
+}
+
+/// A hash join's output as it was produced: one batch per morsel of probe rows, then
+/// the unmatched build rows of a full outer join
+#[derive(Debug)]
+pub struct JoinBatches {
+    pub batches: Vec<Vec<Row>>,
+    pub result_schema: Schema,
+    pub left_rows_processed: usize,
+    pub right_rows_processed: usize,
+    pub output_rows: usize,
+    pub spilled_rows: usize,
 }
 
+impl JoinBatches {
+    /// Concatenate the batches into a single result
+    pub fn into_result(self) -> JoinResult {
+        JoinResult {
+            rows: self.batches.into_iter().flatten().collect(),
+            result_schema: self.result_schema,
+            left_rows_processed: self.left_rows_processed,
+            right_rows_processed: self.right_rows_processed,
+            output_rows: self.output_rows,
+            spilled_rows: self.spilled_rows,
+        }
+    }
+}
+
+/// Hash join on equality conditions. When the build side is larger than the memory
+/// budget, both inputs are hash-partitioned on the join key into spill files (grace
+/// hash join) and each pair of partitions is joined on its own, re-partitioning
+/// partitions that are still too big.
+#[derive(Clone)]
 pub struct HashJoinOperation {
     storage_manager: Arc<Manager>,
     join_type: JoinType,
     join_conditions: Vec<JoinCondition>,
+    residual: Option<Expr>,
+    memory_budget: usize,
+    spill_dir: PathBuf,
+    thread_pool: Option<Arc<ThreadPool>>,
+    batch_size: usize,
+}
+
+/// A join input read one row at a time
+type RowInput<'a> = dyn Iterator<Item = Result<Row, StorageError>> + Send + 'a;
+
+/// The build input once consumed: held in memory, or hash-partitioned into spill files
+enum BuildSide {
+    InMemory(Vec<Row>),
+    Spilled(Vec<SpillFile>),
+}
+
+/// Build-side hash table split by key hash into independently built partitions
+struct PartitionedHashTable {
+    partitions: Vec<HashMap<Vec<Value>, Vec<usize>>>,
+}
+
+impl PartitionedHashTable {
+    fn partition_of(key: &[Value], partition_count: usize) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % partition_count as u64) as usize
+    }
+
+    fn get(&self, key: &[Value]) -> Option<&Vec<usize>> {
+        self.partitions[Self::partition_of(key, self.partitions.len())].get(key)
+    }
 }
 
 impl HashJoinOperation {
@@ -42,9 +138,83 @@ impl HashJoinOperation {
             storage_manager,
             join_type,
             join_conditions,
+            residual: None,
+            memory_budget: DEFAULT_MEMORY_BUDGET,
+            spill_dir: std::env::temp_dir(),
+            thread_pool: None,
+            batch_size: DEFAULT_BATCH_SIZE,
         }
     }
 
+    /// Extra condition evaluated against the joined row; a pair only matches if it is TRUE
+    pub fn with_residual(mut self, residual: Expr) -> Self {
+        self.residual = Some(residual);
+        self
+    }
+
+    /// Largest build side, in serialized bytes, joined without spilling. A `NOT IN` join
+    /// can't partition its build side, so a larger one is an error.
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    pub fn with_spill_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spill_dir = dir.into();
+        self
+    }
+
+    /// Build and probe on a dedicated pool of `max_workers` threads instead of rayon's global pool
+    pub fn with_max_workers(mut self, max_workers: usize) -> Self {
+        self.thread_pool = ThreadPoolBuilder::new()
+            .num_threads(max_workers.max(1))
+            .build()
+            .ok()
+            .map(Arc::new);
+        self
+    }
+
+    /// Number of probe rows per morsel
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Filters on the left input's join columns built from the right input's keys, for the
+    /// left-side scan to drop rows that cannot find a partner. Only inner, right outer and
+    /// semi joins discard unmatched left rows, so other join types get no filters.
+    pub fn build_runtime_filters(
+        &self,
+        right_rows: &[Row],
+        right_schema: &Schema,
+    ) -> Result<Vec<RuntimeFilter>, StorageError> {
+        if !matches!(
+            self.join_type,
+            JoinType::Inner | JoinType::RightOuter | JoinType::LeftSemi
+        ) {
+            return Ok(Vec::new());
+        }
+
+        self.join_conditions
+            .iter()
+            .map(|condition| {
+                let column_index = right_schema
+                    .get_column_index(&condition.right_column)
+                    .ok_or_else(|| StorageError::InvalidOperation(
+                        format!("Column '{}' not found in schema", condition.right_column)
+                    ))?;
+                let keys: Vec<&Value> = right_rows
+                    .iter()
+                    .filter_map(|row| row.get_value(column_index))
+                    .collect();
+                Ok(RuntimeFilter::from_values(
+                    condition.left_column.clone(),
+                    keys.into_iter(),
+                ))
+            })
+            .collect()
+    }
+
     pub async fn execute(
         &self,
         left_rows: Vec<Row>,
@@ -52,183 +222,478 @@ impl HashJoinOperation {
         left_schema: &Schema,
         right_schema: &Schema,
     ) -> Result<JoinResult, StorageError> {
-        let result_schema = self.build_result_schema(left_schema, right_schema)?;
-        
-        match self.join_type {
-            JoinType::Inner => self.inner_hash_join(left_rows, right_rows, left_schema, right_schema, result_schema).await,
-            JoinType::LeftOuter => self.left_outer_hash_join(left_rows, right_rows, left_schema, right_schema, result_schema).await,
-            JoinType::RightOuter => self.right_outer_hash_join(left_rows, right_rows, left_schema, right_schema, result_schema).await,
-            JoinType::FullOuter => self.full_outer_hash_join(left_rows, right_rows, left_schema, right_schema, result_schema).await,
-        }
+        self.execute_batches(left_rows, right_rows, left_schema, right_schema)
+            .await
+            .map(JoinBatches::into_result)
     }
 
-    async fn inner_hash_join(
+    /// Join like `execute`, handing back the output batches instead of one row list
+    pub async fn execute_batches(
         &self,
         left_rows: Vec<Row>,
         right_rows: Vec<Row>,
         left_schema: &Schema,
         right_schema: &Schema,
-        result_schema: Schema,
-    ) -> Result<JoinResult, StorageError> {
-        let hash_table = self.build_hash_table(&right_rows, right_schema)?;
-        let mut result_rows = Vec::new();
-
-        for left_row in &left_rows {
-            let join_key = self.extract_join_key(left_row, left_schema, true)?;
-            
-            if let Some(matching_right_rows) = hash_table.get(&join_key) {
-                for right_row in matching_right_rows {
-                    let joined_row = self.merge_rows(left_row, right_row, left_schema, right_schema)?;
-                    result_rows.push(joined_row);
+    ) -> Result<JoinBatches, StorageError> {
+        self.execute_streams(
+            left_rows.into_iter().map(Ok),
+            right_rows.into_iter().map(Ok),
+            left_schema,
+            right_schema,
+        )
+        .await
+    }
+
+    /// Join rows pulled from each input as they are produced. The build side is buffered
+    /// until it outgrows the memory budget; from then on it, and the whole probe side, are
+    /// written straight to spill partitions, so neither input is ever held in full.
+    pub async fn execute_streams(
+        &self,
+        left_rows: impl IntoIterator<Item = Result<Row, StorageError>, IntoIter: Send>,
+        right_rows: impl IntoIterator<Item = Result<Row, StorageError>, IntoIter: Send>,
+        left_schema: &Schema,
+        right_schema: &Schema,
+    ) -> Result<JoinBatches, StorageError> {
+        if matches!(self.join_type, JoinType::Cross) {
+            return Err(StorageError::InvalidOperation(
+                "Cross joins have no key to hash; use NestedLoopJoinOperation".into(),
+            ));
+        }
+
+        let joined_schema = self.build_result_schema(left_schema, right_schema)?;
+        let residual = self
+            .residual
+            .as_ref()
+            .map(|expr| PhysicalExpr::compile(expr, &joined_schema))
+            .transpose()?;
+        let residual = residual.as_ref();
+        let result_schema = join_output_schema(&self.join_type, left_schema, joined_schema);
+
+        let build_is_left = matches!(self.join_type, JoinType::RightOuter);
+        let mut left_rows = left_rows.into_iter();
+        let mut right_rows = right_rows.into_iter();
+        let (build_rows, probe_rows, probe_schema): (&mut RowInput<'_>, &mut RowInput<'_>, _) =
+            if build_is_left {
+                (&mut left_rows, &mut right_rows, right_schema)
+            } else {
+                (&mut right_rows, &mut left_rows, left_schema)
+            };
+
+        match self.consume_build(build_rows, build_is_left, left_schema, right_schema)? {
+            BuildSide::InMemory(build) => {
+                let probe = probe_rows.collect::<Result<Vec<_>, _>>()?;
+                let (left_rows, right_rows) = if build_is_left { (build, probe) } else { (probe, build) };
+                self.hash_join(left_rows, right_rows, left_schema, right_schema, result_schema, residual).await
+            }
+            BuildSide::Spilled(build) => {
+                let probe = self.partition_rows(probe_rows, probe_schema, !build_is_left, 0)?;
+                let (left_partitions, right_partitions) =
+                    if build_is_left { (build, probe) } else { (probe, build) };
+                self.grace_hash_join(left_partitions, right_partitions, left_schema, right_schema, result_schema, residual)
+                    .await
+            }
+        }
+    }
+
+    /// Buffer build rows until they outgrow the memory budget, then partition the rows
+    /// buffered so far and the rest of the input into spill files
+    fn consume_build(
+        &self,
+        rows: &mut RowInput<'_>,
+        build_is_left: bool,
+        left_schema: &Schema,
+        right_schema: &Schema,
+    ) -> Result<BuildSide, StorageError> {
+        let schema = if build_is_left { left_schema } else { right_schema };
+        // A NULL build key can turn any NOT IN probe UNKNOWN, which key partitioning can't see
+        let needs_whole_build = matches!(self.join_type, JoinType::NullAwareLeftAnti);
+        let mut buffered = Vec::new();
+        let mut buffered_bytes = 0;
+        while let Some(row) = rows.next() {
+            let row = row?;
+            buffered_bytes += row.serialized_size();
+            buffered.push(row);
+            if buffered_bytes > self.memory_budget {
+                if needs_whole_build {
+                    return Err(StorageError::InvalidOperation(format!(
+                        "NOT IN build side exceeds the join memory budget of {} bytes",
+                        self.memory_budget
+                    )));
                 }
+                let rows = buffered.into_iter().map(Ok).chain(rows);
+                return self
+                    .partition_rows(rows, schema, build_is_left, 0)
+                    .map(BuildSide::Spilled);
             }
         }
+        Ok(BuildSide::InMemory(buffered))
+    }
 
-        let output_rows = result_rows.len();
-        Ok(JoinResult {
-            rows: result_rows,
+    /// Equal keys always land in the same partition pair, and rows with NULL keys never
+    /// match anywhere, so joining the pairs independently preserves every join type's
+    /// semantics, including the unmatched rows emitted by outer and anti joins.
+    async fn grace_hash_join(
+        &self,
+        left_partitions: Vec<SpillFile>,
+        right_partitions: Vec<SpillFile>,
+        left_schema: &Schema,
+        right_schema: &Schema,
+        result_schema: Schema,
+        residual: Option<&PhysicalExpr>,
+    ) -> Result<JoinBatches, StorageError> {
+        let left_rows_processed: usize = left_partitions.iter().map(SpillFile::len).sum();
+        let right_rows_processed: usize = right_partitions.iter().map(SpillFile::len).sum();
+        let mut spilled_rows = left_rows_processed + right_rows_processed;
+
+        let mut pending: Vec<(SpillFile, SpillFile, usize)> = left_partitions
+            .into_iter()
+            .zip(right_partitions)
+            .map(|(left, right)| (left, right, 1))
+            .collect();
+
+        let mut batches = Vec::new();
+        let mut output_rows = 0;
+        while let Some((left, right, depth)) = pending.pop() {
+            if left.is_empty() && right.is_empty() {
+                continue;
+            }
+            let build = match self.join_type {
+                JoinType::RightOuter => &left,
+                _ => &right,
+            };
+
+            if build.size_bytes() > self.memory_budget && depth < MAX_SPILL_DEPTH {
+                spilled_rows += left.len() + right.len();
+                let left_split = self.partition_rows(left.into_reader()?, left_schema, true, depth)?;
+                let right_split = self.partition_rows(right.into_reader()?, right_schema, false, depth)?;
+                pending.extend(
+                    left_split
+                        .into_iter()
+                        .zip(right_split)
+                        .map(|(left, right)| (left, right, depth + 1)),
+                );
+                continue;
+            }
+
+            let left_part = left.into_reader()?.collect::<Result<Vec<_>, _>>()?;
+            let right_part = right.into_reader()?.collect::<Result<Vec<_>, _>>()?;
+            let partition_result = self
+                .hash_join(left_part, right_part, left_schema, right_schema, result_schema.clone(), residual)
+                .await?;
+            output_rows += partition_result.output_rows;
+            batches.extend(partition_result.batches);
+        }
+
+        Ok(JoinBatches {
+            batches,
             result_schema,
-            left_rows_processed: left_rows.len(),
-            right_rows_processed: right_rows.len(),
+            left_rows_processed,
+            right_rows_processed,
             output_rows,
+            spilled_rows,
         })
     }
 
-    async fn left_outer_hash_join(
+    fn partition_rows(
+        &self,
+        rows: impl Iterator<Item = Result<Row, StorageError>>,
+        schema: &Schema,
+        is_left: bool,
+        depth: usize,
+    ) -> Result<Vec<SpillFile>, StorageError> {
+        let mut partitions = create_partitions(&self.spill_dir)?;
+        for row in rows {
+            let row = row?;
+            let join_key = self.extract_join_key(&row, schema, is_left)?;
+            partitions[spill_partition(&join_key, depth)].write_row(&row)?;
+        }
+        Ok(partitions)
+    }
+
+    /// Partitioned hash join run on the rayon pool, from a blocking task so the build
+    /// and probe never hold up the async runtime's workers
+    async fn hash_join(
         &self,
         left_rows: Vec<Row>,
         right_rows: Vec<Row>,
         left_schema: &Schema,
         right_schema: &Schema,
         result_schema: Schema,
-    ) -> Result<JoinResult, StorageError> {
-        let hash_table = self.build_hash_table(&right_rows, right_schema)?;
-        let mut result_rows = Vec::new();
-
-        for left_row in &left_rows {
-            let join_key = self.extract_join_key(left_row, left_schema, true)?;
-            
-            if let Some(matching_right_rows) = hash_table.get(&join_key) {
-                for right_row in matching_right_rows {
-                    let joined_row = self.merge_rows(left_row, right_row, left_schema, right_schema)?;
-                    result_rows.push(joined_row);
-                }
-            } else {
-                let null_right_row = self.create_null_row(right_schema);
-                let joined_row = self.merge_rows(left_row, &null_right_row, left_schema, right_schema)?;
-                result_rows.push(joined_row);
-            }
-        }
-
-        let output_rows = result_rows.len();
-        Ok(JoinResult {
-            rows: result_rows,
-            result_schema,
-            left_rows_processed: left_rows.len(),
-            right_rows_processed: right_rows.len(),
-            output_rows,
+        residual: Option<&PhysicalExpr>,
+    ) -> Result<JoinBatches, StorageError> {
+        let operation = self.clone();
+        let left_schema = left_schema.clone();
+        let right_schema = right_schema.clone();
+        let residual = residual.cloned();
+        tokio::task::spawn_blocking(move || {
+            operation.install(|| {
+                operation.hash_join_batches(
+                    left_rows,
+                    right_rows,
+                    &left_schema,
+                    &right_schema,
+                    result_schema,
+                    residual.as_ref(),
+                )
+            })
         })
+        .await
+        .map_err(|e| StorageError::InvalidOperation(format!("Hash join task failed: {}", e)))?
     }
 
-    async fn right_outer_hash_join(
+    /// The build side is hashed into one sub-table per worker, built in parallel; the
+    /// probe side is split into morsels of `batch_size` rows that are probed in parallel,
+    /// each producing one output batch.
+    fn hash_join_batches(
         &self,
         left_rows: Vec<Row>,
         right_rows: Vec<Row>,
         left_schema: &Schema,
         right_schema: &Schema,
         result_schema: Schema,
-    ) -> Result<JoinResult, StorageError> {
-        let hash_table = self.build_hash_table(&left_rows, left_schema)?;
-        let mut result_rows = Vec::new();
-        let mut matched_left_keys = std::collections::HashSet::new();
-
-        for right_row in &right_rows {
-            let join_key = self.extract_join_key(right_row, right_schema, false)?;
-            
-            if let Some(matching_left_rows) = hash_table.get(&join_key) {
-                matched_left_keys.insert(join_key.clone());
-                for left_row in matching_left_rows {
-                    let joined_row = self.merge_rows(left_row, right_row, left_schema, right_schema)?;
-                    result_rows.push(joined_row);
+        residual: Option<&PhysicalExpr>,
+    ) -> Result<JoinBatches, StorageError> {
+        // Right outer joins build on the left so unmatched right rows surface while probing
+        let build_is_left = matches!(self.join_type, JoinType::RightOuter);
+        let (build_rows, build_schema, probe_rows, probe_schema) = if build_is_left {
+            (&left_rows, left_schema, &right_rows, right_schema)
+        } else {
+            (&right_rows, right_schema, &left_rows, left_schema)
+        };
+        let keep_unmatched_probe = matches!(
+            self.join_type,
+            JoinType::LeftOuter | JoinType::RightOuter | JoinType::FullOuter
+        );
+        let track_build_matches = matches!(self.join_type, JoinType::FullOuter);
+        let null_aware = matches!(self.join_type, JoinType::NullAwareLeftAnti);
+
+        let hash_table = self.build_hash_table(build_rows, build_schema, build_is_left)?;
+        let matched_build: Vec<AtomicBool> = if track_build_matches {
+            build_rows.iter().map(|_| AtomicBool::new(false)).collect()
+        } else {
+            Vec::new()
+        };
+        let probe_key_indices = self.join_key_indices(probe_schema, !build_is_left)?;
+        let null_build_row = self.create_null_row(build_schema);
+        let build_keys = if null_aware {
+            let build_key_indices = self.join_key_indices(build_schema, build_is_left)?;
+            build_rows
+                .iter()
+                .map(|row| Self::key_at(row, &build_key_indices))
+                .collect::<Result<Vec<_>, StorageError>>()?
+        } else {
+            Vec::new()
+        };
+        let null_keyed_build: Vec<usize> = (0..build_keys.len())
+            .filter(|&idx| build_keys[idx].iter().any(Value::is_null))
+            .collect();
+
+        let mut batches = probe_rows
+            .par_chunks(self.batch_size)
+            .map(|morsel| {
+                let mut batch = Vec::with_capacity(morsel.len());
+                for probe_row in morsel {
+                    let join_key = Self::key_at(probe_row, &probe_key_indices)?;
+                    if self.join_type.returns_left_only() {
+                        let found = self.find_left_match(
+                            probe_row,
+                            &join_key,
+                            &hash_table,
+                            build_rows,
+                            &build_keys,
+                            &null_keyed_build,
+                            residual,
+                        )?;
+                        let keep = match self.join_type {
+                            JoinType::LeftSemi => found == Some(true),
+                            JoinType::LeftAnti => found != Some(true),
+                            _ => found == Some(false),
+                        };
+                        if keep {
+                            batch.push(probe_row.clone());
+                        }
+                        continue;
+                    }
+
+                    let mut matched = false;
+
+                    if let Some(matching_build_rows) = hash_table.get(&join_key) {
+                        for &build_idx in matching_build_rows {
+                            let build_row = &build_rows[build_idx];
+                            let (left_row, right_row) = if build_is_left {
+                                (build_row, probe_row)
+                            } else {
+                                (probe_row, build_row)
+                            };
+                            if let Some(joined_row) =
+                                self.join_pair(left_row, right_row, left_schema, right_schema, residual)?
+                            {
+                                matched = true;
+                                if track_build_matches {
+                                    matched_build[build_idx].store(true, AtomicOrdering::Relaxed);
+                                }
+                                batch.push(joined_row);
+                            }
+                        }
+                    }
+
+                    if !matched && keep_unmatched_probe {
+                        let joined_row = if build_is_left {
+                            self.merge_rows(&null_build_row, probe_row, left_schema, right_schema)?
+                        } else {
+                            self.merge_rows(probe_row, &null_build_row, left_schema, right_schema)?
+                        };
+                        batch.push(joined_row);
+                    }
                 }
-            } else {
-                let null_left_row = self.create_null_row(left_schema);
-                let joined_row = self.merge_rows(&null_left_row, right_row, left_schema, right_schema)?;
-                result_rows.push(joined_row);
+                Ok(batch)
+            })
+            .collect::<Result<Vec<_>, StorageError>>()?;
+
+        if track_build_matches {
+            let null_left_row = self.create_null_row(left_schema);
+            let mut unmatched = Vec::new();
+            for (build_row, matched) in build_rows.iter().zip(&matched_build) {
+                if !matched.load(AtomicOrdering::Relaxed) {
+                    unmatched.push(self.merge_rows(&null_left_row, build_row, left_schema, right_schema)?);
+                }
+            }
+            if !unmatched.is_empty() {
+                batches.push(unmatched);
             }
         }
 
-        let output_rows = result_rows.len();
-        Ok(JoinResult {
-            rows: result_rows,
+        let output_rows = batches.iter().map(Vec::len).sum();
+        Ok(JoinBatches {
+            batches,
             result_schema,
             left_rows_processed: left_rows.len(),
             right_rows_processed: right_rows.len(),
             output_rows,
+            spilled_rows: 0,
         })
     }
 
-    async fn full_outer_hash_join(
+    /// Whether a left probe row has a partner on the right: `Some(true)` if some pair passes
+    /// the residual, `Some(false)` if none can, and `None` when a pair's key comparison is
+    /// UNKNOWN because of a NULL. UNKNOWN is only worked out for null-aware anti joins,
+    /// which are the only ones given the build keys.
+    #[allow(clippy::too_many_arguments)]
+    fn find_left_match(
         &self,
-        left_rows: Vec<Row>,
-        right_rows: Vec<Row>,
-        left_schema: &Schema,
-        right_schema: &Schema,
-        result_schema: Schema,
-    ) -> Result<JoinResult, StorageError> {
-        let right_hash_table = self.build_hash_table(&right_rows, right_schema)?;
-        let mut result_rows = Vec::new();
-        let mut matched_right_keys = std::collections::HashSet::new();
-
-        for left_row in &left_rows {
-            let join_key = self.extract_join_key(left_row, left_schema, true)?;
-            
-            if let Some(matching_right_rows) = right_hash_table.get(&join_key) {
-                matched_right_keys.insert(join_key);
-                for right_row in matching_right_rows {
-                    let joined_row = self.merge_rows(left_row, right_row, left_schema, right_schema)?;
-                    result_rows.push(joined_row);
+        probe_row: &Row,
+        join_key: &[Value],
+        hash_table: &PartitionedHashTable,
+        build_rows: &[Row],
+        build_keys: &[Vec<Value>],
+        null_keyed_build: &[usize],
+        residual: Option<&PhysicalExpr>,
+    ) -> Result<Option<bool>, StorageError> {
+        let passes = |build_idx: usize| -> Result<bool, StorageError> {
+            match residual {
+                Some(residual) => {
+                    residual.evaluate_predicate(&merge_rows(probe_row, &build_rows[build_idx]))
+                }
+                None => Ok(true),
+            }
+        };
+
+        if let Some(matching_build_rows) = hash_table.get(join_key) {
+            for &build_idx in matching_build_rows {
+                if passes(build_idx)? {
+                    return Ok(Some(true));
                 }
-            } else {
-                let null_right_row = self.create_null_row(right_schema);
-                let joined_row = self.merge_rows(left_row, &null_right_row, left_schema, right_schema)?;
-                result_rows.push(joined_row);
             }
         }
+        if build_keys.is_empty() {
+            return Ok(Some(false));
+        }
 
-        for right_row in &right_rows {
-            let join_key = self.extract_join_key(right_row, right_schema, false)?;
-            
-            if !matched_right_keys.contains(&join_key) {
-                let null_left_row = self.create_null_row(left_schema);
-                let joined_row = self.merge_rows(&null_left_row, right_row, left_schema, right_schema)?;
-                result_rows.push(joined_row);
+        // Only keys holding a NULL can compare UNKNOWN, so those are all that's left to check
+        let candidates: Box<dyn Iterator<Item = usize>> = if join_key.iter().any(Value::is_null) {
+            Box::new(0..build_rows.len())
+        } else {
+            Box::new(null_keyed_build.iter().copied())
+        };
+        for build_idx in candidates {
+            if keys_may_be_equal(join_key, &build_keys[build_idx]) && passes(build_idx)? {
+                return Ok(None);
             }
         }
+        Ok(Some(false))
+    }
 
-        let output_rows = result_rows.len();
-        Ok(JoinResult {
-            rows: result_rows,
-            result_schema,
-            left_rows_processed: left_rows.len(),
-            right_rows_processed: right_rows.len(),
-            output_rows,
-        })
+    /// Run on the operator's own pool if it has one, otherwise on rayon's global pool
+    fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.thread_pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+
+    fn join_pair(
+        &self,
+        left_row: &Row,
+        right_row: &Row,
+        left_schema: &Schema,
+        right_schema: &Schema,
+        residual: Option<&PhysicalExpr>,
+    ) -> Result<Option<Row>, StorageError> {
+        let joined_row = self.merge_rows(left_row, right_row, left_schema, right_schema)?;
+        match residual {
+            Some(residual) if !residual.evaluate_predicate(&joined_row)? => Ok(None),
+            _ => Ok(Some(joined_row)),
+        }
     }
 
     fn build_hash_table(
         &self,
         rows: &[Row],
         schema: &Schema,
-    ) -> Result<HashMap<Vec<Value>, Vec<Row>>, StorageError> {
-        let mut hash_table: HashMap<Vec<Value>, Vec<Row>> = HashMap::new();
-
-        for row in rows {
-            let join_key = self.extract_join_key(row, schema, false)?;
-            hash_table.entry(join_key).or_insert_with(Vec::new).push(row.clone());
+        is_left: bool,
+    ) -> Result<PartitionedHashTable, StorageError> {
+        let key_indices = self.join_key_indices(schema, is_left)?;
+        let partition_count = rayon::current_num_threads().max(1);
+
+        let keyed = rows
+            .par_iter()
+            .enumerate()
+            .map(|(idx, row)| {
+                let join_key = Self::key_at(row, &key_indices)?;
+                let partition = PartitionedHashTable::partition_of(&join_key, partition_count);
+                Ok((partition, idx, join_key))
+            })
+            .collect::<Result<Vec<_>, StorageError>>()?;
+
+        let mut buckets: Vec<Vec<(usize, Vec<Value>)>> = vec![Vec::new(); partition_count];
+        for (partition, idx, join_key) in keyed {
+            // NULL never equals anything, so rows with a NULL key can't be matched
+            if join_key.iter().any(Value::is_null) {
+                continue;
+            }
+            buckets[partition].push((idx, join_key));
         }
 
-        Ok(hash_table)
+        let partitions = buckets
+            .into_par_iter()
+            .map(|bucket| {
+                let mut table: HashMap<Vec<Value>, Vec<usize>> = HashMap::with_capacity(bucket.len());
+                for (idx, join_key) in bucket {
+                    table.entry(join_key).or_default().push(idx);
+                }
+                table
+            })
+            .collect();
+
+        Ok(PartitionedHashTable { partitions })
+    }
+
+    fn join_key_indices(&self, schema: &Schema, is_left: bool) -> Result<Vec<usize>, StorageError> {
+        join_key_indices(&self.join_conditions, schema, is_left)
+    }
+
+    fn key_at(row: &Row, indices: &[usize]) -> Result<Vec<Value>, StorageError> {
+        key_at(row, indices)
     }
 
     fn extract_join_key(
@@ -237,29 +702,7 @@ impl HashJoinOperation {
         schema: &Schema,
         is_left: bool,
     ) -> Result<Vec<Value>, StorageError> {
-        let mut key = Vec::new();
-
-        for condition in &self.join_conditions {
-            let column_name = if is_left {
-                &condition.left_column
-            } else {
-                &condition.right_column
-            };
-
-            let column_index = schema.get_column_index(column_name)
-                .ok_or_else(|| StorageError::InvalidOperation(
-                    format!("Column '{}' not found in schema", column_name)
-                ))?;
-
-            let value = row.get_value(column_index)
-                .ok_or_else(|| StorageError::InvalidOperation(
-                    format!("Column index {} out of bounds", column_index)
-                ))?;
-
-            key.push(value.clone());
-        }
-
-        Ok(key)
+        Self::key_at(row, &self.join_key_indices(schema, is_left)?)
     }
 
     fn merge_rows(
@@ -269,12 +712,7 @@ impl HashJoinOperation {
         left_schema: &Schema,
         right_schema: &Schema,
     ) -> Result<Row, StorageError> {
-        let mut merged_data = Vec::new();
-        
-        merged_data.extend_from_slice(&left_row.data);
-        merged_data.extend_from_slice(&right_row.data);
-
-        Ok(Row::new(left_row.id, merged_data))
+        Ok(merge_rows(left_row, right_row))
     }
 
     fn create_null_row(&self, schema: &Schema) -> Row {
@@ -287,20 +725,143 @@ impl HashJoinOperation {
         left_schema: &Schema,
         right_schema: &Schema,
     ) -> Result<Schema, StorageError> {
-        let mut result_columns = Vec::new();
-        
-        for column in &left_schema.columns {
-            result_columns.push(column.clone());
-        }
-        
-        for column in &right_schema.columns {
-            let mut new_column = column.clone();
-            if left_schema.has_column(&column.name) {
-                new_column.name = format!("right_{}", column.name);
-            }
-            result_columns.push(new_column);
-        }
+        Ok(join_result_schema(left_schema, right_schema))
+    }
+}
+
+/// Physical join algorithm picked by the planner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinAlgorithm {
+    Hash,
+    SortMerge,
+    IndexNestedLoop,
+    NestedLoop,
+}
+
+/// What the planner knows about one join input
+#[derive(Debug, Clone, Default)]
+pub struct JoinInputProfile {
+    /// Estimated number of rows the input produces
+    pub estimated_rows: usize,
+    /// The input arrives ordered on its join columns, e.g. a scan joined on its B+ tree key
+    pub sorted_on_key: bool,
+    /// The input is a table whose B+ tree key is the join column, so it can be probed by key
+    pub indexed_on_key: bool,
+}
+
+/// Rough number of rows scanned per B+ tree descent: one leaf's worth plus the internal pages
+const INDEX_PROBE_COST: usize = 2 * MAX_KEYS_PER_NODE;
+
+/// Pick a join algorithm for `left JOIN right`.
+///
+/// Joins without equality conditions can only be evaluated pair by pair.
+///
+/// An index nested-loop join is chosen when the right input can be probed by key and
+/// the left input is small enough that one descent per left row is cheaper than
+/// scanning the right table.
+///
+/// A sort-merge join is chosen when both inputs already arrive ordered on the join
+/// key, so the merge costs no more than a hash build. Everything else is hashed.
+pub fn choose_join_algorithm(
+    join_type: &JoinType,
+    join_conditions: &[JoinCondition],
+    left: &JoinInputProfile,
+    right: &JoinInputProfile,
+) -> JoinAlgorithm {
+    if join_conditions.is_empty() || matches!(join_type, JoinType::Cross) {
+        return JoinAlgorithm::NestedLoop;
+    }
+
+    let single_key = join_conditions.len() == 1;
+    let probes_right = matches!(
+        join_type,
+        JoinType::Inner | JoinType::LeftOuter | JoinType::LeftSemi | JoinType::LeftAnti
+    );
+    if single_key
+        && probes_right
+        && right.indexed_on_key
+        && left.estimated_rows.saturating_mul(INDEX_PROBE_COST) < right.estimated_rows
+    {
+        return JoinAlgorithm::IndexNestedLoop;
+    }
+
+    let null_aware = matches!(join_type, JoinType::NullAwareLeftAnti);
+    if left.sorted_on_key && right.sorted_on_key && !null_aware {
+        return JoinAlgorithm::SortMerge;
+    }
 
-        Ok(Schema::new(result_columns))
+    JoinAlgorithm::Hash
+}
+
+/// Column positions of the join keys on one side of the conditions
+pub(crate) fn join_key_indices(
+    join_conditions: &[JoinCondition],
+    schema: &Schema,
+    is_left: bool,
+) -> Result<Vec<usize>, StorageError> {
+    join_conditions
+        .iter()
+        .map(|condition| {
+            let column_name = if is_left {
+                &condition.left_column
+            } else {
+                &condition.right_column
+            };
+
+            schema.get_column_index(column_name).ok_or_else(|| {
+                StorageError::InvalidOperation(format!("Column '{}' not found in schema", column_name))
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn key_at(row: &Row, indices: &[usize]) -> Result<Vec<Value>, StorageError> {
+    indices
+        .iter()
+        .map(|&column_index| {
+            row.get_value(column_index).cloned().ok_or_else(|| {
+                StorageError::InvalidOperation(format!("Column index {} out of bounds", column_index))
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn merge_rows(left_row: &Row, right_row: &Row) -> Row {
+    let mut merged_data = Vec::with_capacity(left_row.data.len() + right_row.data.len());
+    merged_data.extend_from_slice(&left_row.data);
+    merged_data.extend_from_slice(&right_row.data);
+    Row::new(left_row.id, merged_data)
+}
+
+/// Whether two keys can compare equal, i.e. every component that isn't NULL on either side matches
+pub(crate) fn keys_may_be_equal(left: &[Value], right: &[Value]) -> bool {
+    left.iter()
+        .zip(right)
+        .all(|(l, r)| l.is_null() || r.is_null() || l == r)
+}
+
+/// Schema of the rows a join returns, given the schema of its joined pairs
+pub(crate) fn join_output_schema(
+    join_type: &JoinType,
+    left_schema: &Schema,
+    joined_schema: Schema,
+) -> Schema {
+    if join_type.returns_left_only() {
+        left_schema.clone()
+    } else {
+        joined_schema
+    }
+}
+
+/// Left columns followed by right columns, with clashing right names prefixed by `right_`
+pub(crate) fn join_result_schema(left_schema: &Schema, right_schema: &Schema) -> Schema {
+    let mut result_columns = left_schema.columns.clone();
+    for column in &right_schema.columns {
+        let mut new_column = column.clone();
+        if left_schema.has_column(&column.name) {
+            new_column.name = format!("right_{}", column.name);
+        }
+        result_columns.push(new_column);
     }
+    Schema::new(result_columns)
 }
\ No newline at end of file
@@ -0,0 +1,229 @@
+use crate::operator::compare::sort_rows;
+use crate::operator::expression::PhysicalExpr;
+use crate::operator::join::{
+    JoinCondition, JoinResult, JoinType, join_key_indices, join_output_schema, join_result_schema,
+    key_at, merge_rows,
+};
+use shared_types::{Expr, OrderBy, Row, Schema, SortDirection, StorageError, Value};
+use std::cmp::Ordering;
+
+/// Sort-merge join. Both inputs are sorted on their join columns (unless the caller
+/// says they already are) and merged in one pass, joining each run of equal keys on
+/// the left with the matching run on the right.
+#[derive(Clone)]
+pub struct SortMergeJoinOperation {
+    join_type: JoinType,
+    join_conditions: Vec<JoinCondition>,
+    residual: Option<Expr>,
+    inputs_sorted: bool,
+}
+
+impl SortMergeJoinOperation {
+    pub fn new(join_type: JoinType, join_conditions: Vec<JoinCondition>) -> Self {
+        Self {
+            join_type,
+            join_conditions,
+            residual: None,
+            inputs_sorted: false,
+        }
+    }
+
+    /// Extra condition evaluated against the joined row; a pair only matches if it is TRUE
+    pub fn with_residual(mut self, residual: Expr) -> Self {
+        self.residual = Some(residual);
+        self
+    }
+
+    /// Skip sorting because both inputs already arrive ascending on their join columns
+    pub fn with_sorted_inputs(mut self) -> Self {
+        self.inputs_sorted = true;
+        self
+    }
+
+    pub async fn execute(
+        &self,
+        left_rows: Vec<Row>,
+        right_rows: Vec<Row>,
+        left_schema: &Schema,
+        right_schema: &Schema,
+    ) -> Result<JoinResult, StorageError> {
+        // Sorting and merging are CPU-bound, so they run off the async runtime's workers
+        let operation = self.clone();
+        let left_schema = left_schema.clone();
+        let right_schema = right_schema.clone();
+        tokio::task::spawn_blocking(move || {
+            operation.sort_merge_join(left_rows, right_rows, &left_schema, &right_schema)
+        })
+        .await
+        .map_err(|e| StorageError::InvalidOperation(format!("Sort-merge join task failed: {}", e)))?
+    }
+
+    fn sort_merge_join(
+        &self,
+        mut left_rows: Vec<Row>,
+        mut right_rows: Vec<Row>,
+        left_schema: &Schema,
+        right_schema: &Schema,
+    ) -> Result<JoinResult, StorageError> {
+        if matches!(
+            self.join_type,
+            JoinType::Cross | JoinType::NullAwareLeftAnti
+        ) {
+            return Err(StorageError::InvalidOperation(format!(
+                "Sort-merge join does not support {:?} joins",
+                self.join_type
+            )));
+        }
+
+        let joined_schema = join_result_schema(left_schema, right_schema);
+        let residual = self
+            .residual
+            .as_ref()
+            .map(|expr| PhysicalExpr::compile(expr, &joined_schema))
+            .transpose()?;
+        let result_schema = join_output_schema(&self.join_type, left_schema, joined_schema);
+
+        if !self.inputs_sorted {
+            sort_rows(&mut left_rows, &self.key_order(true), left_schema);
+            sort_rows(&mut right_rows, &self.key_order(false), right_schema);
+        }
+
+        let left_keys = self.keys(&left_rows, left_schema, true)?;
+        let right_keys = self.keys(&right_rows, right_schema, false)?;
+        let keep_left = matches!(self.join_type, JoinType::LeftOuter | JoinType::FullOuter);
+        let keep_right = matches!(self.join_type, JoinType::RightOuter | JoinType::FullOuter);
+        let anti = matches!(self.join_type, JoinType::LeftAnti);
+        let semi = matches!(self.join_type, JoinType::LeftSemi);
+        let null_left_row = vec![Value::Null; left_schema.column_count()];
+        let null_right_row = vec![Value::Null; right_schema.column_count()];
+
+        let mut rows = Vec::new();
+        let emit_left = |rows: &mut Vec<Row>, row: &Row| {
+            if keep_left {
+                rows.push(merge_rows(row, &Row::new(0, null_right_row.clone())));
+            } else if anti {
+                rows.push(row.clone());
+            }
+        };
+        let emit_right = |rows: &mut Vec<Row>, row: &Row| {
+            if keep_right {
+                rows.push(merge_rows(&Row::new(0, null_left_row.clone()), row));
+            }
+        };
+
+        let (mut i, mut j) = (0, 0);
+        while i < left_rows.len() && j < right_rows.len() {
+            // NULL never equals anything, so NULL keys are unmatched on either side
+            if has_null(&left_keys[i]) {
+                emit_left(&mut rows, &left_rows[i]);
+                i += 1;
+                continue;
+            }
+            if has_null(&right_keys[j]) {
+                emit_right(&mut rows, &right_rows[j]);
+                j += 1;
+                continue;
+            }
+
+            match left_keys[i].cmp(&right_keys[j]) {
+                Ordering::Less => {
+                    emit_left(&mut rows, &left_rows[i]);
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    emit_right(&mut rows, &right_rows[j]);
+                    j += 1;
+                }
+                Ordering::Equal => {
+                    let left_end = run_end(&left_keys, i);
+                    let right_end = run_end(&right_keys, j);
+                    let mut right_matched = vec![false; right_end - j];
+
+                    for left_row in &left_rows[i..left_end] {
+                        let mut left_matched = false;
+                        for (offset, right_row) in right_rows[j..right_end].iter().enumerate() {
+                            let joined_row = merge_rows(left_row, right_row);
+                            let matches = match &residual {
+                                Some(residual) => residual.evaluate_predicate(&joined_row)?,
+                                None => true,
+                            };
+                            if matches {
+                                left_matched = true;
+                                right_matched[offset] = true;
+                                if semi || anti {
+                                    break;
+                                }
+                                rows.push(joined_row);
+                            }
+                        }
+                        if !left_matched {
+                            emit_left(&mut rows, left_row);
+                        } else if semi {
+                            rows.push(left_row.clone());
+                        }
+                    }
+                    for (right_row, matched) in right_rows[j..right_end].iter().zip(right_matched) {
+                        if !matched {
+                            emit_right(&mut rows, right_row);
+                        }
+                    }
+
+                    i = left_end;
+                    j = right_end;
+                }
+            }
+        }
+        for left_row in &left_rows[i..] {
+            emit_left(&mut rows, left_row);
+        }
+        for right_row in &right_rows[j..] {
+            emit_right(&mut rows, right_row);
+        }
+
+        let output_rows = rows.len();
+        Ok(JoinResult {
+            rows,
+            result_schema,
+            left_rows_processed: left_rows.len(),
+            right_rows_processed: right_rows.len(),
+            output_rows,
+            spilled_rows: 0,
+        })
+    }
+
+    fn key_order(&self, is_left: bool) -> Vec<OrderBy> {
+        self.join_conditions
+            .iter()
+            .map(|condition| {
+                let column = if is_left {
+                    &condition.left_column
+                } else {
+                    &condition.right_column
+                };
+                OrderBy::new(column.clone(), SortDirection::Ascending)
+            })
+            .collect()
+    }
+
+    fn keys(
+        &self,
+        rows: &[Row],
+        schema: &Schema,
+        is_left: bool,
+    ) -> Result<Vec<Vec<Value>>, StorageError> {
+        let indices = join_key_indices(&self.join_conditions, schema, is_left)?;
+        rows.iter().map(|row| key_at(row, &indices)).collect()
+    }
+}
+
+fn has_null(key: &[Value]) -> bool {
+    key.iter().any(Value::is_null)
+}
+
+/// End of the run of keys equal to `keys[start]`
+fn run_end(keys: &[Vec<Value>], start: usize) -> usize {
+    keys[start..]
+        .iter()
+        .position(|key| key != &keys[start])
+        .map_or(keys.len(), |len| start + len)
+}
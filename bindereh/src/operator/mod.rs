@@ -1,9 +1,19 @@
 pub mod aggregate;
 pub mod compare;
 pub mod delete;
+pub mod expression;
+pub mod function;
+pub mod index_join;
 pub mod insert;
 pub mod join;
+pub mod merge_join;
+pub mod nested_loop_join;
 pub mod print;
+pub mod recursive;
+pub mod set_operation;
 pub mod scan;
+pub mod sort;
+pub mod top_n;
 pub mod tree;
-pub mod update;
\ No newline at end of file
+pub mod update;
+pub mod window;
\ No newline at end of file
@@ -0,0 +1,186 @@
+use crate::operator::expression::PhysicalExpr;
+use crate::operator::join::{
+    JoinResult, JoinType, join_output_schema, join_result_schema, merge_rows,
+};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use shared_types::{Expr, Row, Schema, StorageError, Value};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+
+/// Left rows handed to a worker at a time
+const DEFAULT_BATCH_SIZE: usize = 256;
+
+/// Nested-loop join, evaluating the join condition on every pair of rows. This is the
+/// only algorithm for cross joins and for conditions without an equality to hash or
+/// sort on, such as `a.ts BETWEEN b.start AND b.end`. Morsels of left rows are joined
+/// against the whole right input in parallel.
+#[derive(Clone)]
+pub struct NestedLoopJoinOperation {
+    join_type: JoinType,
+    condition: Option<Expr>,
+    thread_pool: Option<Arc<ThreadPool>>,
+    batch_size: usize,
+}
+
+impl NestedLoopJoinOperation {
+    pub fn new(join_type: JoinType) -> Self {
+        Self {
+            join_type,
+            condition: None,
+            thread_pool: None,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Condition evaluated against each joined pair; without one every pair matches
+    pub fn with_condition(mut self, condition: Expr) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Join on a dedicated pool of `max_workers` threads instead of rayon's global pool
+    pub fn with_max_workers(mut self, max_workers: usize) -> Self {
+        self.thread_pool = ThreadPoolBuilder::new()
+            .num_threads(max_workers.max(1))
+            .build()
+            .ok()
+            .map(Arc::new);
+        self
+    }
+
+    /// Number of left rows per morsel
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// A null-aware anti join drops a left row when the condition is TRUE or UNKNOWN for
+    /// any right row, which is `NOT IN` when the condition is the key comparison.
+    pub async fn execute(
+        &self,
+        left_rows: Vec<Row>,
+        right_rows: Vec<Row>,
+        left_schema: &Schema,
+        right_schema: &Schema,
+    ) -> Result<JoinResult, StorageError> {
+        // Every pair is compared, so run the loop where it can't hold up the async runtime
+        let operation = self.clone();
+        let left_schema = left_schema.clone();
+        let right_schema = right_schema.clone();
+        tokio::task::spawn_blocking(move || {
+            operation.nested_loop_join(left_rows, right_rows, &left_schema, &right_schema)
+        })
+        .await
+        .map_err(|e| {
+            StorageError::InvalidOperation(format!("Nested-loop join task failed: {}", e))
+        })?
+    }
+
+    fn nested_loop_join(
+        &self,
+        left_rows: Vec<Row>,
+        right_rows: Vec<Row>,
+        left_schema: &Schema,
+        right_schema: &Schema,
+    ) -> Result<JoinResult, StorageError> {
+        let joined_schema = join_result_schema(left_schema, right_schema);
+        let condition = self
+            .condition
+            .as_ref()
+            .map(|expr| PhysicalExpr::compile(expr, &joined_schema))
+            .transpose()?;
+        let condition = condition.as_ref();
+        let result_schema = join_output_schema(&self.join_type, left_schema, joined_schema);
+
+        let keep_unmatched_left =
+            matches!(self.join_type, JoinType::LeftOuter | JoinType::FullOuter);
+        let track_right_matches =
+            matches!(self.join_type, JoinType::RightOuter | JoinType::FullOuter);
+        let null_right_row = Row::new(0, vec![Value::Null; right_schema.column_count()]);
+
+        let rows = self.install(|| -> Result<Vec<Row>, StorageError> {
+            let matched_right: Vec<AtomicBool> = if track_right_matches {
+                right_rows.iter().map(|_| AtomicBool::new(false)).collect()
+            } else {
+                Vec::new()
+            };
+
+            let batches = left_rows
+                .par_chunks(self.batch_size)
+                .map(|morsel| {
+                    let mut batch = Vec::new();
+                    for left_row in morsel {
+                        let mut matched = false;
+                        let mut unknown = false;
+                        for (right_idx, right_row) in right_rows.iter().enumerate() {
+                            let joined_row = merge_rows(left_row, right_row);
+                            let outcome = match condition {
+                                Some(condition) => condition.evaluate_tristate(&joined_row)?,
+                                None => Some(true),
+                            };
+                            match outcome {
+                                Some(true) => matched = true,
+                                Some(false) => continue,
+                                None => {
+                                    unknown = true;
+                                    continue;
+                                }
+                            }
+
+                            if self.join_type.returns_left_only() {
+                                break;
+                            }
+                            if track_right_matches {
+                                matched_right[right_idx].store(true, AtomicOrdering::Relaxed);
+                            }
+                            batch.push(joined_row);
+                        }
+
+                        let keep_left_only = match self.join_type {
+                            JoinType::LeftSemi => matched,
+                            JoinType::LeftAnti => !matched,
+                            JoinType::NullAwareLeftAnti => !matched && !unknown,
+                            _ => false,
+                        };
+                        if keep_left_only {
+                            batch.push(left_row.clone());
+                        } else if !matched && keep_unmatched_left {
+                            batch.push(merge_rows(left_row, &null_right_row));
+                        }
+                    }
+                    Ok(batch)
+                })
+                .collect::<Result<Vec<_>, StorageError>>()?;
+
+            let mut rows: Vec<Row> = batches.into_iter().flatten().collect();
+            if track_right_matches {
+                let null_left_row = Row::new(0, vec![Value::Null; left_schema.column_count()]);
+                for (right_row, matched) in right_rows.iter().zip(&matched_right) {
+                    if !matched.load(AtomicOrdering::Relaxed) {
+                        rows.push(merge_rows(&null_left_row, right_row));
+                    }
+                }
+            }
+            Ok(rows)
+        })?;
+
+        let output_rows = rows.len();
+        Ok(JoinResult {
+            rows,
+            result_schema,
+            left_rows_processed: left_rows.len(),
+            right_rows_processed: right_rows.len(),
+            output_rows,
+            spilled_rows: 0,
+        })
+    }
+
+    /// Run on the operator's own pool if it has one, otherwise on rayon's global pool
+    fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.thread_pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+}
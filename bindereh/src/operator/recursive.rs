@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+
+use shared_types::{Row, StorageError, Value};
+
+/// Iterations after which a recursive query is assumed never to reach its fixpoint
+const DEFAULT_MAX_ITERATIONS: usize = 10_000;
+
+#[derive(Debug)]
+pub struct RecursiveResult {
+    pub rows: Vec<Row>,
+    /// Runs of the recursive term, including the last one that produced nothing new
+    pub iterations: usize,
+}
+
+/// `WITH RECURSIVE` as an iterative fixpoint. The rows of the non-recursive term form
+/// the first working table; each iteration runs the recursive term against the
+/// working table alone, and the rows it yields become the next working table, until
+/// an iteration yields none. Under UNION, as opposed to UNION ALL, rows that were
+/// already produced are dropped, which is also what lets queries over cyclic graphs
+/// terminate.
+pub struct RecursiveUnionOperation {
+    all: bool,
+    max_iterations: usize,
+}
+
+impl RecursiveUnionOperation {
+    pub fn new(all: bool) -> Self {
+        Self {
+            all,
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+        }
+    }
+
+    /// Fail instead of running the recursive term more than `max_iterations` times
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// `step` runs the recursive term with the CTE bound to the given working table
+    pub async fn execute<F>(
+        &self,
+        seed: Vec<Row>,
+        mut step: F,
+    ) -> Result<RecursiveResult, StorageError>
+    where
+        F: AsyncFnMut(&[Row]) -> Result<Vec<Row>, StorageError>,
+    {
+        let mut seen: HashSet<Vec<Value>> = HashSet::new();
+        let mut rows = Vec::new();
+        let mut working_table = self.new_rows(seed, &mut seen);
+        let mut iterations = 0;
+
+        while !working_table.is_empty() {
+            if iterations == self.max_iterations {
+                return Err(StorageError::InvalidOperation(format!(
+                    "Recursive query did not reach a fixpoint within {} iterations",
+                    self.max_iterations
+                )));
+            }
+            iterations += 1;
+            let produced = self.new_rows(step(&working_table).await?, &mut seen);
+            rows.append(&mut working_table);
+            working_table = produced;
+        }
+
+        Ok(RecursiveResult { rows, iterations })
+    }
+
+    /// `rows` without those produced before; UNION ALL keeps every row
+    fn new_rows(&self, rows: Vec<Row>, seen: &mut HashSet<Vec<Value>>) -> Vec<Row> {
+        if self.all {
+            return rows;
+        }
+        rows.into_iter()
+            .filter(|row| seen.insert(row.data.clone()))
+            .collect()
+    }
+}
@@ -2,8 +2,13 @@ use crate::operator::compare::{
     evaluate_predicate_optimized, evaluate_predicate_optimized_static,
     extract_predicate_column_indices, sort_rows, evaluate_predicate_fast,
 };
+use crate::operator::aggregate::{
+    AggregateResult, CompiledAggregate, HashAggregateOperation, PartialAggregate,
+};
+use crate::operator::expression::PhysicalExpr;
+use crate::operator::top_n::TopN;
 use crate::{manager::Manager, operator::tree::TreeOperations, page::Page};
-use shared_types::{ScanOptions, ScanResult, Schema, StorageError};
+use shared_types::{Row, RuntimeFilter, ScanOptions, ScanResult, Schema, StorageError};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
 use std::time::Instant;
 use std::{
@@ -12,6 +17,29 @@ use std::{
 };
 use tokio::task::JoinSet;
 
+/// Where a scan worker sends the rows that pass its filters
+enum WorkerSink {
+    Collect,
+    Aggregate(PartialAggregate),
+    TopN(TopN),
+}
+
+/// What every worker of one parallel scan shares: the compiled filters, the
+/// projection and the limit, along with the counters they stop on together
+#[derive(Clone)]
+struct WorkerContext {
+    storage_manager: Arc<Manager>,
+    options: ScanOptions,
+    projection_indices: Option<Vec<usize>>,
+    predicate_column_indices: Option<HashMap<String, usize>>,
+    filter: Option<PhysicalExpr>,
+    runtime_filter_indices: Vec<usize>,
+    effective_limit: Option<usize>,
+    total_rows_found: Arc<AtomicUsize>,
+    should_stop: Arc<AtomicBool>,
+    buffer_hits: Arc<AtomicUsize>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ReadAheadConfig {
     pub buffer_size: usize,
@@ -154,12 +182,15 @@ impl ScanOperation {
         &self,
         start_page_id: u64,
         count: usize,
+        buffer_hits: &AtomicUsize,
     ) -> Result<Vec<Arc<Page>>, StorageError> {
         if count == 0 {
             return Ok(Vec::new());
         }
 
-        let prefetch_future = self.storage_manager.read_leaf_chain(start_page_id, count);
+        let prefetch_future =
+            self.storage_manager
+                .read_leaf_chain_counting_hits(start_page_id, count, buffer_hits);
 
         match tokio::time::timeout(std::time::Duration::from_secs(30), prefetch_future).await {
             Ok(Ok((pages, _))) => Ok(pages),
@@ -176,14 +207,61 @@ impl ScanOperation {
         }
     }
 
-    async fn safe_read_page(&self, page_id: u64) -> Result<Arc<Page>, StorageError> {
-        match self.storage_manager.read_page(page_id).await {
+    fn compile_filter(options: &ScanOptions) -> Result<Option<PhysicalExpr>, StorageError> {
+        match (&options.filter, &options.schema) {
+            (Some(filter), Some(schema)) => Ok(Some(PhysicalExpr::compile(filter, schema)?)),
+            (Some(_), None) => Err(StorageError::InvalidInput(
+                "Scan filter expression requires a schema".to_string(),
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /// Resolve each runtime filter's column against the scan schema
+    fn runtime_filter_indices(options: &ScanOptions) -> Result<Vec<usize>, StorageError> {
+        if options.runtime_filters.is_empty() {
+            return Ok(Vec::new());
+        }
+        let schema = options.schema.as_ref().ok_or_else(|| {
+            StorageError::InvalidInput("Runtime filters require a schema".to_string())
+        })?;
+        options
+            .runtime_filters
+            .iter()
+            .map(|filter| {
+                schema.get_column_index(&filter.column).ok_or_else(|| {
+                    StorageError::InvalidInput(format!(
+                        "Runtime filter column '{}' not found in schema",
+                        filter.column
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    fn passes_runtime_filters(filters: &[RuntimeFilter], indices: &[usize], row: &Row) -> bool {
+        filters
+            .iter()
+            .zip(indices)
+            .all(|(filter, &idx)| row.data.get(idx).is_some_and(|value| filter.might_match(value)))
+    }
+
+    async fn safe_read_page(
+        &self,
+        page_id: u64,
+        buffer_hits: &AtomicUsize,
+    ) -> Result<Arc<Page>, StorageError> {
+        match self
+            .storage_manager
+            .read_page_counting_hits(page_id, buffer_hits)
+            .await
+        {
             Ok(page) => Ok(page),
             Err(e) => {
                 eprintln!("Page read error for page {}: {:?}, retrying...", page_id, e);
                 tokio::time::sleep(std::time::Duration::from_millis(100)).await;
                 self.storage_manager
-                    .read_page(page_id)
+                    .read_page_counting_hits(page_id, buffer_hits)
                     .await
                     .map_err(|retry_err| {
                         eprintln!(
@@ -227,6 +305,7 @@ impl ScanOperation {
         let mut pages_read = 0;
         let mut total_scanned = 0;
         let mut filtered_count = 0;
+        let buffer_hits = AtomicUsize::new(0);
         let mut current_leaf_id = Some(start_leaf_id);
 
         let projection_indices =
@@ -254,15 +333,19 @@ impl ScanOperation {
                 None
             };
 
-        let effective_limit = match (options.limit, options.offset) {
-            (Some(limit), Some(offset)) => Some(limit + offset),
-            (Some(limit), None) => Some(limit),
-            _ => None,
-        };
+        let compiled_filter = Self::compile_filter(&options)?;
+        let runtime_filter_indices = Self::runtime_filter_indices(&options)?;
+
+        let mut top_n = Self::top_n_for(&options, &result_schema);
+        let effective_limit = Self::effective_limit(&options);
 
         if self.read_ahead_config.enabled {
             if let Ok(initial_pages) = self
-                .prefetch_pages(start_leaf_id, self.read_ahead_config.buffer_size)
+                .prefetch_pages(
+                    start_leaf_id,
+                    self.read_ahead_config.buffer_size,
+                    &buffer_hits,
+                )
                 .await
             {
                 read_ahead_buffer.add_pages(initial_pages);
@@ -273,7 +356,7 @@ impl ScanOperation {
             let leaf_page = if let Some(buffered_page) = read_ahead_buffer.get_page(leaf_id) {
                 buffered_page
             } else {
-                match self.safe_read_page(leaf_id).await {
+                match self.safe_read_page(leaf_id, &buffer_hits).await {
                     Ok(page) => page,
                     Err(e) => {
                         eprintln!("Failed to read page {} during scan: {:?}", leaf_id, e);
@@ -303,6 +386,10 @@ impl ScanOperation {
                     }
                 }
 
+                if !Self::passes_runtime_filters(&options.runtime_filters, &runtime_filter_indices, row) {
+                    continue;
+                }
+
                 // Fast predicate evaluation - fail fast on first condition
                 if let Some(ref predicate) = options.predicate {
                     if let Some(ref schema) = options.schema {
@@ -317,6 +404,12 @@ impl ScanOperation {
                     }
                 }
 
+                if let Some(ref filter) = compiled_filter {
+                    if !filter.evaluate_predicate(row)? {
+                        continue;
+                    }
+                }
+
                 filtered_count += 1;
                 batch_filtered.push(row);
                 
@@ -332,7 +425,7 @@ impl ScanOperation {
                         } else {
                             batch_row.clone()
                         };
-                        result_rows.push(projected_row);
+                        Self::collect_row(&mut result_rows, &mut top_n, projected_row);
                     }
                 }
             }
@@ -348,7 +441,7 @@ impl ScanOperation {
                 } else {
                     batch_row.clone()
                 };
-                result_rows.push(projected_row);
+                Self::collect_row(&mut result_rows, &mut top_n, projected_row);
             }
 
             if let Some(eff_limit) = effective_limit {
@@ -372,7 +465,9 @@ impl ScanOperation {
             }
         }
 
-        if let Some(ref order_by) = options.order_by {
+        if let Some(top_n) = top_n {
+            result_rows = top_n.into_sorted_rows();
+        } else if let Some(ref order_by) = options.order_by {
             if let Some(ref schema) = result_schema {
                 if !result_rows.is_empty() {
                     sort_rows(&mut result_rows, order_by, schema);
@@ -402,6 +497,8 @@ impl ScanOperation {
             pages_read,
             filtered_count,
             result_schema,
+            buffer_hits: buffer_hits.into_inner(),
+            workers: 1,
         })
     }
 
@@ -415,13 +512,7 @@ impl ScanOperation {
         );
 
         if all_leaf_page_ids.is_empty() {
-            return Ok(ScanResult {
-                rows: Vec::new(),
-                total_scanned: 0,
-                pages_read: 0,
-                filtered_count: 0,
-                result_schema: options.schema,
-            });
+            return Ok(ScanResult::new(Vec::new(), 0, 0, 0, options.schema));
         }
 
         let projection_indices =
@@ -453,12 +544,18 @@ impl ScanOperation {
         let pages_per_worker = (total_pages + self.max_workers - 1) / self.max_workers;
         let pages_per_worker = std::cmp::max(pages_per_worker, 1);
 
-        let total_rows_found = Arc::new(AtomicUsize::new(0));
-        let should_stop = Arc::new(AtomicBool::new(false));
-        let effective_limit = match (options.limit, options.offset) {
-            (Some(limit), Some(offset)) => Some(limit + offset),
-            (Some(limit), None) => Some(limit),
-            _ => None,
+        let mut top_n = Self::top_n_for(&options, &result_schema);
+        let context = WorkerContext {
+            storage_manager: Arc::clone(&self.storage_manager),
+            projection_indices,
+            predicate_column_indices,
+            filter: Self::compile_filter(&options)?,
+            runtime_filter_indices: Self::runtime_filter_indices(&options)?,
+            effective_limit: Self::effective_limit(&options),
+            total_rows_found: Arc::new(AtomicUsize::new(0)),
+            should_stop: Arc::new(AtomicBool::new(false)),
+            buffer_hits: Arc::new(AtomicUsize::new(0)),
+            options: options.clone(),
         };
 
         let mut join_set = JoinSet::new();
@@ -477,28 +574,18 @@ impl ScanOperation {
                 continue;
             }
 
-            let storage_manager = Arc::clone(&self.storage_manager);
-            let worker_options = options.clone();
-            let worker_projection_indices = projection_indices.clone();
-            let worker_predicate_indices = predicate_column_indices.clone();
-            let worker_total_rows = Arc::clone(&total_rows_found);
-            let worker_should_stop = Arc::clone(&should_stop);
-
-            join_set.spawn(async move {
-                Self::registry_worker_scan_with_limit(
-                    storage_manager,
-                    worker_page_ids,
-                    worker_options,
-                    worker_projection_indices,
-                    worker_predicate_indices,
-                    worker_total_rows,
-                    worker_should_stop,
-                    effective_limit,
-                )
-                .await
-            });
+            let sink = match &top_n {
+                Some(top_n) => WorkerSink::TopN(top_n.for_partition(worker_id)),
+                None => WorkerSink::Collect,
+            };
+            join_set.spawn(Self::registry_worker_scan_with_limit(
+                context.clone(),
+                worker_page_ids,
+                sink,
+            ));
         }
 
+        let workers = join_set.len();
         let duration = now.elapsed();
         println!("✅ join_set.spawn {:.2}ms", duration.as_secs_f64() * 1000.0);
 
@@ -519,13 +606,16 @@ impl ScanOperation {
                     if let Some(result) = result {
                         pending_tasks -= 1;
                         match result {
-                            Ok(Ok(worker_result)) => {
-                                all_rows.extend(worker_result.rows);
+                            Ok(Ok((worker_result, sink))) => {
+                                match (&mut top_n, sink) {
+                                    (Some(top_n), WorkerSink::TopN(partial)) => top_n.merge(partial),
+                                    _ => all_rows.extend(worker_result.rows),
+                                }
                                 total_pages_read += worker_result.pages_read;
                                 total_scanned += worker_result.total_scanned;
                                 total_filtered += worker_result.filtered_count;
 
-                                if let Some(effective_limit) = effective_limit {
+                                if let Some(effective_limit) = context.effective_limit {
                                     if total_filtered >= effective_limit {
                                         join_set.shutdown().await;
                                         break;
@@ -551,7 +641,9 @@ impl ScanOperation {
         let duration = now.elapsed();
         println!("✅ collect_result {:.2}ms", duration.as_secs_f64() * 1000.0);
 
-        if let Some(ref order_by) = options.order_by {
+        if let Some(top_n) = top_n {
+            all_rows = top_n.into_sorted_rows();
+        } else if let Some(ref order_by) = options.order_by {
             if let Some(ref schema) = result_schema {
                 if !all_rows.is_empty() {
                     sort_rows(&mut all_rows, order_by, schema);
@@ -579,19 +671,139 @@ impl ScanOperation {
             pages_read: total_pages_read,
             filtered_count: total_filtered,
             result_schema,
+            buffer_hits: context.buffer_hits.load(AtomicOrdering::Relaxed),
+            workers,
         })
     }
 
+    /// Scan and aggregate in one pass. Every worker folds the rows that pass the
+    /// predicate and filter into its own partial group table, and the partial tables
+    /// are merged at the end, so qualifying rows are never materialized.
+    /// The workers split the aggregate's memory budget and spill once over their share.
+    /// Projection, ordering, limit and offset in `options` are ignored; they apply to
+    /// the aggregate output instead.
+    pub async fn execute_aggregate(
+        &self,
+        options: ScanOptions,
+        aggregate: &HashAggregateOperation,
+    ) -> Result<AggregateResult, StorageError> {
+        let schema = options.schema.clone().ok_or_else(|| {
+            StorageError::InvalidInput("Aggregating scan requires a schema".to_string())
+        })?;
+        let compiled = Arc::new(CompiledAggregate::compile(
+            &aggregate.group_exprs(),
+            &aggregate.functions(),
+            &schema,
+        )?);
+        let predicate_column_indices = options
+            .predicate
+            .as_ref()
+            .map(|predicate| extract_predicate_column_indices(predicate, &schema));
+
+        let all_leaf_page_ids = self.storage_manager.get_all_leaf_page_ids().await?;
+        let workers = if options.parallel { self.max_workers.max(1) } else { 1 };
+        let pages_per_worker = std::cmp::max(all_leaf_page_ids.len().div_ceil(workers), 1);
+
+        let context = WorkerContext {
+            storage_manager: Arc::clone(&self.storage_manager),
+            projection_indices: None,
+            predicate_column_indices,
+            filter: Self::compile_filter(&options)?,
+            runtime_filter_indices: Self::runtime_filter_indices(&options)?,
+            effective_limit: None,
+            total_rows_found: Arc::new(AtomicUsize::new(0)),
+            should_stop: Arc::new(AtomicBool::new(false)),
+            buffer_hits: Arc::new(AtomicUsize::new(0)),
+            options,
+        };
+        let mut join_set = JoinSet::new();
+        for worker_page_ids in all_leaf_page_ids.chunks(pages_per_worker) {
+            let partial = aggregate.partial(Arc::clone(&compiled), workers);
+            join_set.spawn(Self::registry_worker_scan_with_limit(
+                context.clone(),
+                worker_page_ids.to_vec(),
+                WorkerSink::Aggregate(partial),
+            ));
+        }
+
+        let mut partials = Vec::with_capacity(join_set.len());
+        let mut input_rows = 0;
+        while let Some(result) = join_set.join_next().await {
+            match result {
+                Ok(Ok((worker_result, sink))) => {
+                    input_rows += worker_result.filtered_count;
+                    if let WorkerSink::Aggregate(partial) = sink {
+                        partials.push(partial);
+                    }
+                }
+                Ok(Err(e)) => return Err(e),
+                Err(e) => {
+                    return Err(StorageError::InvalidOperation(format!(
+                        "Registry worker task failed: {}",
+                        e
+                    )));
+                }
+            }
+        }
+
+        let aggregate = aggregate.clone();
+        let mut result =
+            tokio::task::spawn_blocking(move || aggregate.merge_partials(partials, &schema))
+                .await
+                .map_err(|e| {
+                    StorageError::InvalidOperation(format!("Aggregate merge task failed: {}", e))
+                })??;
+        result.input_rows = input_rows;
+        Ok(result)
+    }
+
+    /// Bounded heap replacing the full sort when the scan has both ORDER BY and LIMIT
+    fn top_n_for(options: &ScanOptions, result_schema: &Option<Schema>) -> Option<TopN> {
+        match (&options.order_by, options.limit, result_schema) {
+            (Some(order_by), Some(limit), Some(schema)) => Some(TopN::new(
+                order_by,
+                schema,
+                limit.saturating_add(options.offset.unwrap_or(0)),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Rows after which the scan can stop early. With ORDER BY every row has to be
+    /// seen, since any of them may sort first.
+    fn effective_limit(options: &ScanOptions) -> Option<usize> {
+        if options.order_by.is_some() {
+            return None;
+        }
+        options
+            .limit
+            .map(|limit| limit.saturating_add(options.offset.unwrap_or(0)))
+    }
+
+    fn collect_row(rows: &mut Vec<Row>, top_n: &mut Option<TopN>, row: Row) {
+        match top_n {
+            Some(top_n) => top_n.push(row),
+            None => rows.push(row),
+        }
+    }
+
     async fn registry_worker_scan_with_limit(
-        storage_manager: Arc<Manager>,
+        context: WorkerContext,
         page_ids: Vec<u64>,
-        options: ScanOptions,
-        projection_indices: Option<Vec<usize>>,
-        predicate_column_indices: Option<HashMap<String, usize>>,
-        total_rows_found: Arc<AtomicUsize>,
-        should_stop: Arc<AtomicBool>,
-        effective_limit: Option<usize>,
-    ) -> Result<ScanResult, StorageError> {
+        mut sink: WorkerSink,
+    ) -> Result<(ScanResult, WorkerSink), StorageError> {
+        let WorkerContext {
+            storage_manager,
+            options,
+            projection_indices,
+            predicate_column_indices,
+            filter,
+            runtime_filter_indices,
+            effective_limit,
+            total_rows_found,
+            should_stop,
+            buffer_hits,
+        } = context;
         let mut result_rows = Vec::new();
         let mut pages_read = 0;
         let mut total_scanned = 0;
@@ -613,7 +825,9 @@ impl ScanOperation {
                 }
             }
 
-            let leaf_page = storage_manager.read_page(page_id).await?;
+            let leaf_page = storage_manager
+                .read_page_counting_hits(page_id, &buffer_hits)
+                .await?;
             pages_read += 1;
 
             for row in &leaf_page.values {
@@ -626,6 +840,10 @@ impl ScanOperation {
                     }
                 }
 
+                if !Self::passes_runtime_filters(&options.runtime_filters, &runtime_filter_indices, row) {
+                    continue;
+                }
+
                 if let Some(ref predicate) = options.predicate {
                     if let Some(ref schema) = options.schema {
                         if let Some(ref indices) = predicate_column_indices {
@@ -643,7 +861,20 @@ impl ScanOperation {
                     }
                 }
 
+                if let Some(ref filter) = filter {
+                    if !filter.evaluate_predicate(row)? {
+                        continue;
+                    }
+                }
+
                 filtered_count += 1;
+
+                // Aggregating workers fold rows into their partial state instead of collecting them
+                if let WorkerSink::Aggregate(ref mut partial) = sink {
+                    partial.update(row)?;
+                    continue;
+                }
+
                 total_rows_found.fetch_add(1, AtomicOrdering::Relaxed);
 
                 let projected_row = if let Some(ref indices) = projection_indices {
@@ -656,7 +887,10 @@ impl ScanOperation {
                     row.clone()
                 };
 
-                result_rows.push(projected_row);
+                match sink {
+                    WorkerSink::TopN(ref mut top_n) => top_n.push(projected_row),
+                    _ => result_rows.push(projected_row),
+                }
             }
 
             if should_stop.load(AtomicOrdering::Relaxed) {
@@ -664,12 +898,9 @@ impl ScanOperation {
             }
         }
 
-        Ok(ScanResult {
-            rows: result_rows,
-            total_scanned,
-            pages_read,
-            filtered_count,
-            result_schema: None,
-        })
+        Ok((
+            ScanResult::new(result_rows, total_scanned, pages_read, filtered_count, None),
+            sink,
+        ))
     }
 }
\ No newline at end of file
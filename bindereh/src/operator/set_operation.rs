@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet};
+
+use shared_types::{Column, Row, Schema, StorageError, Value, function::common_type};
+
+use crate::operator::expression::cast_value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperator {
+    Union,
+    /// Rows found in both inputs
+    Intersect,
+    /// Left rows not found in the right input
+    Except,
+}
+
+#[derive(Debug)]
+pub struct SetOperationResult {
+    pub rows: Vec<Row>,
+    pub result_schema: Schema,
+    pub left_rows_processed: usize,
+    pub right_rows_processed: usize,
+    pub output_rows: usize,
+}
+
+/// UNION, INTERSECT and EXCEPT over two inputs with the same number of columns, in
+/// left-then-right order of first occurrence. Rows are compared as a whole and NULLs
+/// compare equal to each other, as in `IS NOT DISTINCT FROM`. Without `all` the result
+/// has no duplicates; with it, a row that occurs `m` times on the left and `n` times on
+/// the right occurs `m + n` times in UNION ALL, `min(m, n)` times in INTERSECT ALL and
+/// `max(m - n, 0)` times in EXCEPT ALL.
+pub struct HashSetOperation {
+    op: SetOperator,
+    all: bool,
+}
+
+impl HashSetOperation {
+    pub fn new(op: SetOperator, all: bool) -> Self {
+        Self { op, all }
+    }
+
+    /// Columns of the result: the left input's names, with each column widened to the
+    /// type both inputs can be converted to
+    pub fn output_schema(
+        &self,
+        left_schema: &Schema,
+        right_schema: &Schema,
+    ) -> Result<Schema, StorageError> {
+        if left_schema.column_count() != right_schema.column_count() {
+            return Err(StorageError::InvalidInput(format!(
+                "{:?} inputs must have the same number of columns, got {} and {}",
+                self.op,
+                left_schema.column_count(),
+                right_schema.column_count()
+            )));
+        }
+
+        let columns = left_schema
+            .columns
+            .iter()
+            .zip(&right_schema.columns)
+            .map(|(left, right)| {
+                let data_type =
+                    common_type(&left.data_type, &right.data_type).ok_or_else(|| {
+                        StorageError::InvalidInput(format!(
+                            "{:?} column {} has incompatible types {:?} and {:?}",
+                            self.op, left.name, left.data_type, right.data_type
+                        ))
+                    })?;
+                // INTERSECT can only return NULLs that both sides have
+                let nullable = match self.op {
+                    SetOperator::Union => left.nullable || right.nullable,
+                    SetOperator::Intersect => left.nullable && right.nullable,
+                    SetOperator::Except => left.nullable,
+                };
+                Ok(Column::new(left.name.clone(), data_type, nullable, false))
+            })
+            .collect::<Result<Vec<_>, StorageError>>()?;
+        Ok(Schema::new(columns))
+    }
+
+    pub fn execute(
+        &self,
+        left_rows: Vec<Row>,
+        right_rows: Vec<Row>,
+        left_schema: &Schema,
+        right_schema: &Schema,
+    ) -> Result<SetOperationResult, StorageError> {
+        let result_schema = self.output_schema(left_schema, right_schema)?;
+        let left_rows_processed = left_rows.len();
+        let right_rows_processed = right_rows.len();
+        let left_rows = coerce_rows(left_rows, left_schema, &result_schema)?;
+        let right_rows = coerce_rows(right_rows, right_schema, &result_schema)?;
+
+        let rows = match self.op {
+            SetOperator::Union => self.union(left_rows, right_rows),
+            SetOperator::Intersect | SetOperator::Except => {
+                self.intersect_or_except(left_rows, right_rows)
+            }
+        };
+
+        let output_rows = rows.len();
+        Ok(SetOperationResult {
+            rows,
+            result_schema,
+            left_rows_processed,
+            right_rows_processed,
+            output_rows,
+        })
+    }
+
+    fn union(&self, left_rows: Vec<Row>, right_rows: Vec<Row>) -> Vec<Row> {
+        let rows = left_rows.into_iter().chain(right_rows);
+        if self.all {
+            return rows.collect();
+        }
+        let mut seen: HashSet<Vec<Value>> = HashSet::new();
+        rows.filter(|row| seen.insert(row.data.clone())).collect()
+    }
+
+    /// Count the right rows, then stream the left rows against the counts
+    fn intersect_or_except(&self, left_rows: Vec<Row>, right_rows: Vec<Row>) -> Vec<Row> {
+        let mut right_counts: HashMap<Vec<Value>, usize> = HashMap::new();
+        for row in right_rows {
+            *right_counts.entry(row.data).or_insert(0) += 1;
+        }
+
+        let intersect = self.op == SetOperator::Intersect;
+        let mut emitted: HashSet<Vec<Value>> = HashSet::new();
+        let mut rows = Vec::new();
+        for row in left_rows {
+            let keep = if self.all {
+                // Each left occurrence uses up one right occurrence
+                match right_counts.get_mut(&row.data) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        intersect
+                    }
+                    _ => !intersect,
+                }
+            } else {
+                right_counts.contains_key(&row.data) == intersect
+                    && emitted.insert(row.data.clone())
+            };
+            if keep {
+                rows.push(row);
+            }
+        }
+        rows
+    }
+}
+
+/// Convert each value to its column's type in `target`, so that equal values of
+/// different types, such as INTEGER 1 and FLOAT 1.0, hash alike
+fn coerce_rows(rows: Vec<Row>, schema: &Schema, target: &Schema) -> Result<Vec<Row>, StorageError> {
+    let casts: Vec<bool> = schema
+        .columns
+        .iter()
+        .zip(&target.columns)
+        .map(|(column, target)| column.data_type != target.data_type)
+        .collect();
+    if !casts.contains(&true) {
+        return Ok(rows);
+    }
+
+    rows.into_iter()
+        .map(|row| {
+            let data = row
+                .data
+                .into_iter()
+                .zip(&casts)
+                .zip(&target.columns)
+                .map(|((value, cast), column)| {
+                    if *cast {
+                        cast_value(value, &column.data_type)
+                    } else {
+                        Ok(value)
+                    }
+                })
+                .collect::<Result<Vec<_>, StorageError>>()?;
+            Ok(Row::new(row.id, data))
+        })
+        .collect()
+}
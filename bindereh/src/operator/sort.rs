@@ -0,0 +1,259 @@
+use crate::operator::compare::RowComparator;
+use crate::spill::{DEFAULT_MEMORY_BUDGET, SpillFile, SpillReader};
+use shared_types::{OrderBy, Row, Schema, StorageError};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Most runs merged at once; more runs are first merged into longer ones
+pub const MAX_MERGE_FANIN: usize = 64;
+
+pub struct SortResult {
+    /// Rows in order, merged from the runs as they are read rather than all at once
+    pub rows: SortedRows,
+    /// Sorted runs written to disk, zero when the input fit in memory
+    pub runs: usize,
+    pub spilled_rows: usize,
+}
+
+/// External merge sort for ORDER BY. Rows are buffered until the memory budget is
+/// reached, then the buffer is sorted and written out as a run; the runs are k-way
+/// merged at the end. Inputs that fit the budget are sorted entirely in memory.
+pub struct ExternalSortOperation {
+    order_by: Vec<OrderBy>,
+    memory_budget: usize,
+    spill_dir: PathBuf,
+    merge_fanin: usize,
+}
+
+impl ExternalSortOperation {
+    pub fn new(order_by: Vec<OrderBy>) -> Self {
+        Self {
+            order_by,
+            memory_budget: DEFAULT_MEMORY_BUDGET,
+            spill_dir: std::env::temp_dir(),
+            merge_fanin: MAX_MERGE_FANIN,
+        }
+    }
+
+    /// Serialized bytes of rows buffered before a sorted run is spilled
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    pub fn with_spill_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spill_dir = dir.into();
+        self
+    }
+
+    /// Most runs read at once by a merge pass, at least two
+    pub fn with_merge_fanin(mut self, fanin: usize) -> Self {
+        self.merge_fanin = fanin.max(2);
+        self
+    }
+
+    pub fn order_by(&self) -> &[OrderBy] {
+        &self.order_by
+    }
+
+    /// Start an incremental sort for rows of `schema`
+    pub fn sorter(&self, schema: &Schema) -> Sorter {
+        Sorter {
+            comparator: Arc::new(RowComparator::new(&self.order_by, schema)),
+            memory_budget: self.memory_budget,
+            spill_dir: self.spill_dir.clone(),
+            merge_fanin: self.merge_fanin,
+            buffer: Vec::new(),
+            buffered_bytes: 0,
+            runs: Vec::new(),
+            spilled_rows: 0,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        rows: impl IntoIterator<Item = Row>,
+        schema: &Schema,
+    ) -> Result<SortResult, StorageError> {
+        let mut sorter = self.sorter(schema);
+        for row in rows {
+            sorter.push(row)?;
+        }
+        let runs = sorter.runs();
+        let spilled_rows = sorter.spilled_rows();
+        let rows = sorter.finish()?;
+        Ok(SortResult {
+            rows,
+            runs,
+            spilled_rows,
+        })
+    }
+}
+
+/// Accepts rows one at a time, spilling sorted runs as the buffer fills up
+pub struct Sorter {
+    comparator: Arc<RowComparator>,
+    memory_budget: usize,
+    spill_dir: PathBuf,
+    merge_fanin: usize,
+    buffer: Vec<Row>,
+    buffered_bytes: usize,
+    runs: Vec<SpillFile>,
+    spilled_rows: usize,
+}
+
+impl Sorter {
+    pub fn push(&mut self, row: Row) -> Result<(), StorageError> {
+        self.buffered_bytes += row.serialized_size();
+        self.buffer.push(row);
+        if self.buffered_bytes > self.memory_budget {
+            self.spill_run()?;
+        }
+        Ok(())
+    }
+
+    /// Runs written to disk so far
+    pub fn runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn spilled_rows(&self) -> usize {
+        self.spilled_rows
+    }
+
+    /// Sorted rows. The rows still buffered become the last run, kept in memory.
+    pub fn finish(mut self) -> Result<SortedRows, StorageError> {
+        self.sort_buffer();
+        // Merge consecutive groups of runs so equal keys keep their input order
+        while self.runs.len() + 1 > self.merge_fanin {
+            let mut merged_runs = Vec::new();
+            let mut runs = std::mem::take(&mut self.runs).into_iter().peekable();
+            while runs.peek().is_some() {
+                let group: Vec<SpillFile> = runs.by_ref().take(self.merge_fanin).collect();
+                let mut merged = SpillFile::create(&self.spill_dir)?;
+                for row in self.merge(group, Vec::new())? {
+                    merged.write_row(&row?)?;
+                }
+                merged_runs.push(merged);
+            }
+            self.runs = merged_runs;
+        }
+        let buffer = std::mem::take(&mut self.buffer);
+        let runs = std::mem::take(&mut self.runs);
+        self.merge(runs, buffer)
+    }
+
+    fn sort_buffer(&mut self) {
+        let comparator = &self.comparator;
+        // Stable, so rows with equal keys keep their input order within a run
+        self.buffer.sort_by(|a, b| comparator.compare(a, b));
+    }
+
+    fn spill_run(&mut self) -> Result<(), StorageError> {
+        self.sort_buffer();
+        let mut run = SpillFile::create(&self.spill_dir)?;
+        for row in &self.buffer {
+            run.write_row(row)?;
+        }
+        self.spilled_rows += self.buffer.len();
+        self.runs.push(run);
+        self.buffer.clear();
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+
+    fn merge(&self, runs: Vec<SpillFile>, in_memory: Vec<Row>) -> Result<SortedRows, StorageError> {
+        let mut sources: Vec<RunSource> = runs
+            .into_iter()
+            .map(|run| run.into_reader().map(RunSource::Spilled))
+            .collect::<Result<_, _>>()?;
+        sources.push(RunSource::Memory(in_memory.into_iter()));
+
+        let mut heap = BinaryHeap::with_capacity(sources.len());
+        for (source, run) in sources.iter_mut().enumerate() {
+            if let Some(row) = run.next_row()? {
+                heap.push(MergeEntry {
+                    row,
+                    source,
+                    comparator: self.comparator.clone(),
+                });
+            }
+        }
+        Ok(SortedRows { sources, heap })
+    }
+}
+
+enum RunSource {
+    Spilled(SpillReader),
+    Memory(std::vec::IntoIter<Row>),
+}
+
+impl RunSource {
+    fn next_row(&mut self) -> Result<Option<Row>, StorageError> {
+        match self {
+            RunSource::Spilled(reader) => reader.next().transpose(),
+            RunSource::Memory(rows) => Ok(rows.next()),
+        }
+    }
+}
+
+/// Head row of one run; the heap pops the smallest, ties going to the earlier run
+struct MergeEntry {
+    row: Row,
+    source: usize,
+    comparator: Arc<RowComparator>,
+}
+
+impl Ord for MergeEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap, so the order is reversed
+        self.comparator
+            .compare(&self.row, &other.row)
+            .then(self.source.cmp(&other.source))
+            .reverse()
+    }
+}
+
+impl PartialOrd for MergeEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MergeEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MergeEntry {}
+
+/// K-way merge over sorted runs, yielding rows in order
+pub struct SortedRows {
+    sources: Vec<RunSource>,
+    heap: BinaryHeap<MergeEntry>,
+}
+
+impl Iterator for SortedRows {
+    type Item = Result<Row, StorageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let MergeEntry {
+            row,
+            source,
+            comparator,
+        } = self.heap.pop()?;
+        match self.sources[source].next_row() {
+            Ok(Some(next)) => self.heap.push(MergeEntry {
+                row: next,
+                source,
+                comparator,
+            }),
+            Ok(None) => {}
+            Err(e) => return Some(Err(e)),
+        }
+        Some(Ok(row))
+    }
+}
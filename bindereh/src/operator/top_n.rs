@@ -0,0 +1,179 @@
+use crate::operator::compare::RowComparator;
+use rayon::prelude::*;
+use shared_types::{OrderBy, Row, Schema, StorageError};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+
+/// Rows handed to a worker at a time by `TopNOperation`
+const DEFAULT_CHUNK_SIZE: usize = 16 * 1024;
+
+/// The first `limit` rows in ORDER BY order, kept in a bounded max-heap whose top is the
+/// current cut-off row. Memory stays O(limit) however many rows are pushed, and partial
+/// heaps built by separate workers merge into the same result a single heap would give.
+pub struct TopN {
+    comparator: Arc<RowComparator>,
+    limit: usize,
+    partition: usize,
+    next_seq: u64,
+    heap: BinaryHeap<TopNEntry>,
+}
+
+impl TopN {
+    pub fn new(order_by: &[OrderBy], schema: &Schema, limit: usize) -> Self {
+        Self {
+            comparator: Arc::new(RowComparator::new(order_by, schema)),
+            limit,
+            partition: 0,
+            next_seq: 0,
+            heap: BinaryHeap::with_capacity(limit.saturating_add(1).min(1 << 16)),
+        }
+    }
+
+    /// Heap for the `partition`-th slice of the input. Rows with equal sort keys are
+    /// ranked by partition and then by arrival, so ties resolve in input order.
+    pub fn for_partition(&self, partition: usize) -> Self {
+        Self {
+            comparator: Arc::clone(&self.comparator),
+            limit: self.limit,
+            partition,
+            next_seq: 0,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Row) {
+        let entry = TopNEntry {
+            row,
+            rank: (self.partition, self.next_seq),
+            comparator: Arc::clone(&self.comparator),
+        };
+        self.next_seq += 1;
+        self.offer(entry);
+    }
+
+    /// Fold another partial heap into this one
+    pub fn merge(&mut self, other: TopN) {
+        for entry in other.heap {
+            self.offer(entry);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// The kept rows, best first
+    pub fn into_sorted_rows(self) -> Vec<Row> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|entry| entry.row)
+            .collect()
+    }
+
+    fn offer(&mut self, entry: TopNEntry) {
+        if self.heap.len() < self.limit {
+            self.heap.push(entry);
+        } else if let Some(mut worst) = self.heap.peek_mut()
+            && entry < *worst
+        {
+            *worst = entry;
+        }
+    }
+}
+
+struct TopNEntry {
+    row: Row,
+    rank: (usize, u64),
+    comparator: Arc<RowComparator>,
+}
+
+impl Ord for TopNEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.comparator
+            .compare(&self.row, &other.row)
+            .then(self.rank.cmp(&other.rank))
+    }
+}
+
+impl PartialOrd for TopNEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TopNEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TopNEntry {}
+
+/// `ORDER BY ... LIMIT n OFFSET m` over materialized rows: chunks of the input are
+/// reduced to their own top `n + m` rows in parallel and the partial heaps merged.
+#[derive(Clone)]
+pub struct TopNOperation {
+    order_by: Vec<OrderBy>,
+    limit: usize,
+    offset: usize,
+}
+
+impl TopNOperation {
+    pub fn new(order_by: Vec<OrderBy>, limit: usize) -> Self {
+        Self {
+            order_by,
+            limit,
+            offset: 0,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub async fn execute(&self, rows: Vec<Row>, schema: &Schema) -> Result<Vec<Row>, StorageError> {
+        // The heaps are built on the rayon pool from a blocking task, not on the async runtime
+        let operation = self.clone();
+        let schema = schema.clone();
+        tokio::task::spawn_blocking(move || operation.top_n(rows, &schema))
+            .await
+            .map_err(|e| StorageError::InvalidOperation(format!("Top-N task failed: {}", e)))
+    }
+
+    fn top_n(&self, rows: Vec<Row>, schema: &Schema) -> Vec<Row> {
+        let template = TopN::new(
+            &self.order_by,
+            schema,
+            self.limit.saturating_add(self.offset),
+        );
+        let partials: Vec<TopN> = rows
+            .into_par_iter()
+            .chunks(DEFAULT_CHUNK_SIZE)
+            .enumerate()
+            .map(|(partition, chunk)| {
+                let mut top_n = template.for_partition(partition);
+                for row in chunk {
+                    top_n.push(row);
+                }
+                top_n
+            })
+            .collect();
+
+        let mut merged = template;
+        for partial in partials {
+            merged.merge(partial);
+        }
+        merged
+            .into_sorted_rows()
+            .into_iter()
+            .skip(self.offset)
+            .collect()
+    }
+}
@@ -0,0 +1,590 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+use shared_types::{
+    Column, DataType, Expr, OrderBy, Row, Schema, SortDirection, StorageError, Value,
+};
+
+use crate::operator::aggregate::{Accumulator, AggregateFunction};
+use crate::operator::compare::RowComparator;
+use crate::operator::expression::{PhysicalExpr, expr_data_type};
+
+#[derive(Debug, Clone)]
+pub enum WindowFunction {
+    RowNumber,
+    /// Rank with gaps after ties: 1, 1, 3
+    Rank,
+    /// Rank without gaps: 1, 1, 2
+    DenseRank,
+    /// `expr` from `offset` rows before the current row, or `default` past the partition start
+    Lag {
+        expr: Expr,
+        offset: usize,
+        default: Value,
+    },
+    /// `expr` from `offset` rows after the current row, or `default` past the partition end
+    Lead {
+        expr: Expr,
+        offset: usize,
+        default: Value,
+    },
+    /// Aggregate over the rows of the current row's frame
+    Aggregate(AggregateFunction),
+}
+
+impl WindowFunction {
+    pub fn argument(&self) -> Option<&Expr> {
+        match self {
+            WindowFunction::RowNumber | WindowFunction::Rank | WindowFunction::DenseRank => None,
+            WindowFunction::Lag { expr, .. } | WindowFunction::Lead { expr, .. } => Some(expr),
+            WindowFunction::Aggregate(function) => function.argument(),
+        }
+    }
+
+    /// Type of the function's result over rows of `schema`
+    pub fn result_type(&self, schema: &Schema) -> Result<DataType, StorageError> {
+        match self {
+            WindowFunction::RowNumber | WindowFunction::Rank | WindowFunction::DenseRank => {
+                Ok(DataType::Integer)
+            }
+            WindowFunction::Lag { expr, .. } | WindowFunction::Lead { expr, .. } => {
+                expr_data_type(expr, schema)
+            }
+            WindowFunction::Aggregate(function) => function.result_type(schema),
+        }
+    }
+}
+
+/// Whether frame offsets count rows or distance along the ORDER BY key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameUnits {
+    Rows,
+    Range,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameBound {
+    UnboundedPreceding,
+    Preceding(u64),
+    CurrentRow,
+    Following(u64),
+    UnboundedFollowing,
+}
+
+/// `ROWS | RANGE BETWEEN start AND end`. For RANGE, `CURRENT ROW` covers the
+/// current row's peers and offsets are measured on the single numeric, date or
+/// time ORDER BY key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowFrame {
+    pub units: FrameUnits,
+    pub start: FrameBound,
+    pub end: FrameBound,
+}
+
+impl WindowFrame {
+    pub fn rows(start: FrameBound, end: FrameBound) -> Self {
+        Self {
+            units: FrameUnits::Rows,
+            start,
+            end,
+        }
+    }
+
+    pub fn range(start: FrameBound, end: FrameBound) -> Self {
+        Self {
+            units: FrameUnits::Range,
+            start,
+            end,
+        }
+    }
+
+    /// From the partition start to the current row's last peer
+    pub fn running() -> Self {
+        Self::range(FrameBound::UnboundedPreceding, FrameBound::CurrentRow)
+    }
+
+    pub fn whole_partition() -> Self {
+        Self::rows(
+            FrameBound::UnboundedPreceding,
+            FrameBound::UnboundedFollowing,
+        )
+    }
+
+    fn has_range_offset(&self) -> bool {
+        self.units == FrameUnits::Range
+            && [self.start, self.end]
+                .iter()
+                .any(|bound| matches!(bound, FrameBound::Preceding(_) | FrameBound::Following(_)))
+    }
+}
+
+/// `OVER (PARTITION BY ... ORDER BY ... frame)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowSpec {
+    pub partition_by: Vec<Expr>,
+    pub order_by: Vec<OrderBy>,
+    pub frame: Option<WindowFrame>,
+}
+
+impl WindowSpec {
+    pub fn new(partition_by: Vec<Expr>, order_by: Vec<OrderBy>) -> Self {
+        Self {
+            partition_by,
+            order_by,
+            frame: None,
+        }
+    }
+
+    pub fn with_frame(mut self, frame: WindowFrame) -> Self {
+        self.frame = Some(frame);
+        self
+    }
+
+    /// The explicit frame, or the SQL default: a running frame when the window is
+    /// ordered and the whole partition otherwise
+    pub fn frame(&self) -> WindowFrame {
+        self.frame.unwrap_or_else(|| {
+            if self.order_by.is_empty() {
+                WindowFrame::whole_partition()
+            } else {
+                WindowFrame::running()
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowExpr {
+    pub function: WindowFunction,
+    pub spec: WindowSpec,
+    pub alias: String,
+}
+
+impl WindowExpr {
+    pub fn new(function: WindowFunction, spec: WindowSpec, alias: impl Into<String>) -> Self {
+        Self {
+            function,
+            spec,
+            alias: alias.into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WindowResult {
+    pub rows: Vec<Row>,
+    pub result_schema: Schema,
+    pub input_rows: usize,
+    /// Partitions of the first window expression
+    pub partitions: usize,
+}
+
+/// Evaluates window functions, appending one column per window expression to every
+/// input row. Rows are hash-partitioned on the PARTITION BY keys, each partition is
+/// sorted on the ORDER BY keys and partitions are evaluated in parallel. Windows that
+/// share a specification share the partitioning. Output follows the first window's
+/// order: partitions in order of first appearance, rows sorted within each.
+pub struct WindowOperation {
+    windows: Vec<WindowExpr>,
+}
+
+impl WindowOperation {
+    pub fn new(windows: Vec<WindowExpr>) -> Self {
+        Self { windows }
+    }
+
+    pub fn output_schema(&self, input_schema: &Schema) -> Result<Schema, StorageError> {
+        let mut columns = input_schema.columns.clone();
+        for window in &self.windows {
+            columns.push(Column::nullable(
+                window.alias.clone(),
+                window.function.result_type(input_schema)?,
+            ));
+        }
+        Ok(Schema::new(columns))
+    }
+
+    pub async fn execute(
+        &self,
+        rows: Vec<Row>,
+        schema: &Schema,
+    ) -> Result<WindowResult, StorageError> {
+        let result_schema = self.output_schema(schema)?;
+        let input_rows = rows.len();
+
+        let mut layouts: Vec<(&WindowSpec, Arc<Vec<Vec<usize>>>)> = Vec::new();
+        let mut columns = Vec::with_capacity(self.windows.len());
+        for window in &self.windows {
+            let partitions = match layouts.iter().find(|(spec, _)| **spec == window.spec) {
+                Some((_, partitions)) => Arc::clone(partitions),
+                None => {
+                    let partitions = Arc::new(partition_rows(&rows, &window.spec, schema)?);
+                    layouts.push((&window.spec, Arc::clone(&partitions)));
+                    partitions
+                }
+            };
+            columns.push(evaluate_window(window, &rows, &partitions, schema)?);
+        }
+
+        let Some((_, order)) = layouts.first() else {
+            return Ok(WindowResult {
+                rows,
+                result_schema,
+                input_rows,
+                partitions: 0,
+            });
+        };
+        let partitions = order.len();
+
+        let mut rows = rows;
+        let mut output = Vec::with_capacity(input_rows);
+        for &idx in order.iter().flatten() {
+            let row = &mut rows[idx];
+            let mut data = std::mem::take(&mut row.data);
+            data.extend(
+                columns
+                    .iter_mut()
+                    .map(|column| std::mem::replace(&mut column[idx], Value::Null)),
+            );
+            output.push(Row::new(row.id, data));
+        }
+
+        Ok(WindowResult {
+            rows: output,
+            result_schema,
+            input_rows,
+            partitions,
+        })
+    }
+}
+
+/// Row indices grouped by partition key in order of first appearance, each
+/// partition stably sorted on the window's ORDER BY
+fn partition_rows(
+    rows: &[Row],
+    spec: &WindowSpec,
+    schema: &Schema,
+) -> Result<Vec<Vec<usize>>, StorageError> {
+    if let Some(order) = spec
+        .order_by
+        .iter()
+        .find(|order| schema.get_column_index(&order.column).is_none())
+    {
+        return Err(StorageError::InvalidInput(format!(
+            "Window ORDER BY column '{}' not found",
+            order.column
+        )));
+    }
+
+    let keys = spec
+        .partition_by
+        .iter()
+        .map(|expr| PhysicalExpr::compile(expr, schema))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut partitions: Vec<Vec<usize>> = Vec::new();
+    if keys.is_empty() {
+        if !rows.is_empty() {
+            partitions.push((0..rows.len()).collect());
+        }
+    } else {
+        let mut slots: HashMap<Vec<Value>, usize> = HashMap::new();
+        for (idx, row) in rows.iter().enumerate() {
+            let key = keys
+                .iter()
+                .map(|expr| expr.evaluate(row))
+                .collect::<Result<Vec<_>, _>>()?;
+            let slot = *slots.entry(key).or_insert_with(|| {
+                partitions.push(Vec::new());
+                partitions.len() - 1
+            });
+            partitions[slot].push(idx);
+        }
+    }
+
+    if !spec.order_by.is_empty() {
+        let comparator = RowComparator::new(&spec.order_by, schema);
+        partitions.par_iter_mut().for_each(|partition| {
+            partition.sort_by(|&a, &b| comparator.compare(&rows[a], &rows[b]))
+        });
+    }
+    Ok(partitions)
+}
+
+/// One value per input row, indexed like `rows`
+fn evaluate_window(
+    window: &WindowExpr,
+    rows: &[Row],
+    partitions: &[Vec<usize>],
+    schema: &Schema,
+) -> Result<Vec<Value>, StorageError> {
+    let argument = window
+        .function
+        .argument()
+        .map(|expr| PhysicalExpr::compile(expr, schema))
+        .transpose()?;
+    let frame = window.spec.frame();
+    let range_key = if frame.has_range_offset() {
+        match window.spec.order_by.as_slice() {
+            [order] => Some((
+                schema.get_column_index(&order.column).unwrap_or_default(),
+                order.direction,
+            )),
+            _ => {
+                return Err(StorageError::InvalidInput(
+                    "RANGE with an offset requires exactly one ORDER BY key".to_string(),
+                ));
+            }
+        }
+    } else {
+        None
+    };
+    let evaluator = PartitionEvaluator {
+        function: &window.function,
+        frame,
+        comparator: RowComparator::new(&window.spec.order_by, schema),
+        argument: argument.as_ref(),
+        range_key,
+    };
+
+    let evaluated = partitions
+        .par_iter()
+        .map(|partition| evaluator.evaluate(partition, rows))
+        .collect::<Result<Vec<_>, StorageError>>()?;
+
+    let mut values = vec![Value::Null; rows.len()];
+    for (partition, partition_values) in partitions.iter().zip(evaluated) {
+        for (&idx, value) in partition.iter().zip(partition_values) {
+            values[idx] = value;
+        }
+    }
+    Ok(values)
+}
+
+struct PartitionEvaluator<'a> {
+    function: &'a WindowFunction,
+    frame: WindowFrame,
+    comparator: RowComparator,
+    argument: Option<&'a PhysicalExpr>,
+    range_key: Option<(usize, SortDirection)>,
+}
+
+impl PartitionEvaluator<'_> {
+    /// Values for the rows of one sorted partition, in partition order
+    fn evaluate(&self, partition: &[usize], rows: &[Row]) -> Result<Vec<Value>, StorageError> {
+        let partition_rows: Vec<&Row> = partition.iter().map(|&idx| &rows[idx]).collect();
+        let peers = self.peer_groups(&partition_rows);
+        let arguments = partition_rows
+            .iter()
+            .map(|row| match self.argument {
+                Some(expr) => expr.evaluate(row),
+                None => Ok(Value::Null),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let len = partition_rows.len();
+
+        Ok(match self.function {
+            WindowFunction::RowNumber => (1..=len as i64).map(Value::Integer).collect(),
+            WindowFunction::Rank => peers
+                .iter()
+                .map(|&(start, _)| Value::Integer(start as i64 + 1))
+                .collect(),
+            WindowFunction::DenseRank => {
+                let mut rank = 0;
+                let mut values = Vec::with_capacity(len);
+                for (pos, &(start, _)) in peers.iter().enumerate() {
+                    if start == pos {
+                        rank += 1;
+                    }
+                    values.push(Value::Integer(rank));
+                }
+                values
+            }
+            WindowFunction::Lag {
+                offset, default, ..
+            } => (0..len)
+                .map(|pos| match pos.checked_sub(*offset) {
+                    Some(source) => arguments[source].clone(),
+                    None => default.clone(),
+                })
+                .collect(),
+            WindowFunction::Lead {
+                offset, default, ..
+            } => (0..len)
+                .map(
+                    |pos| match pos.checked_add(*offset).filter(|&source| source < len) {
+                        Some(source) => arguments[source].clone(),
+                        None => default.clone(),
+                    },
+                )
+                .collect(),
+            WindowFunction::Aggregate(function) => {
+                let positions = self.range_positions(&partition_rows)?;
+                let frames: Vec<(usize, usize)> = (0..len)
+                    .map(|pos| self.frame_bounds(pos, len, &peers, &positions))
+                    .collect();
+                aggregate_frames(function, &arguments, &frames, self.frame.start)?
+            }
+        })
+    }
+
+    /// For each position, the half-open range of its peers: rows that compare equal on
+    /// the ORDER BY keys. Without an ORDER BY the whole partition is one peer group.
+    fn peer_groups(&self, rows: &[&Row]) -> Vec<(usize, usize)> {
+        let mut peers = vec![(0, 0); rows.len()];
+        let mut start = 0;
+        while start < rows.len() {
+            let mut end = start + 1;
+            while end < rows.len()
+                && self.comparator.compare(rows[start], rows[end]) == Ordering::Equal
+            {
+                end += 1;
+            }
+            peers[start..end].fill((start, end));
+            start = end;
+        }
+        peers
+    }
+
+    /// ORDER BY key of every row as a position that increases along the sort, for
+    /// RANGE offsets. NULL keys have no position.
+    fn range_positions(&self, rows: &[&Row]) -> Result<Vec<Option<f64>>, StorageError> {
+        let Some((column, direction)) = self.range_key else {
+            return Ok(Vec::new());
+        };
+        rows.iter()
+            .map(|row| {
+                let value = &row.data[column];
+                let position = match value {
+                    Value::Null => return Ok(None),
+                    Value::Date(days) => *days as f64,
+                    Value::Time(ms) => *ms as f64,
+                    Value::Timestamp(ms) | Value::DateTime(ms) => *ms as f64,
+                    other => other.as_f64().ok_or_else(|| {
+                        StorageError::InvalidInput(format!(
+                            "RANGE offset needs a numeric or temporal ORDER BY key, got {:?}",
+                            other
+                        ))
+                    })?,
+                };
+                Ok(Some(match direction {
+                    SortDirection::Ascending => position,
+                    SortDirection::Descending => -position,
+                }))
+            })
+            .collect()
+    }
+
+    /// Half-open range of partition positions in the frame of the row at `pos`
+    fn frame_bounds(
+        &self,
+        pos: usize,
+        len: usize,
+        peers: &[(usize, usize)],
+        positions: &[Option<f64>],
+    ) -> (usize, usize) {
+        let (start, end) = match self.frame.units {
+            FrameUnits::Rows => (
+                Self::rows_bound(self.frame.start, pos, len, false),
+                Self::rows_bound(self.frame.end, pos, len, true),
+            ),
+            FrameUnits::Range => (
+                self.range_bound(self.frame.start, pos, len, peers, positions, false),
+                self.range_bound(self.frame.end, pos, len, peers, positions, true),
+            ),
+        };
+        (start, end.max(start))
+    }
+
+    fn rows_bound(bound: FrameBound, pos: usize, len: usize, is_end: bool) -> usize {
+        let inclusive_end = usize::from(is_end);
+        match bound {
+            FrameBound::UnboundedPreceding => 0,
+            FrameBound::Preceding(n) => {
+                (pos + inclusive_end).saturating_sub(usize::try_from(n).unwrap_or(usize::MAX))
+            }
+            FrameBound::CurrentRow => pos + inclusive_end,
+            FrameBound::Following(n) => pos
+                .saturating_add(usize::try_from(n).unwrap_or(usize::MAX))
+                .saturating_add(inclusive_end)
+                .min(len),
+            FrameBound::UnboundedFollowing => len,
+        }
+    }
+
+    fn range_bound(
+        &self,
+        bound: FrameBound,
+        pos: usize,
+        len: usize,
+        peers: &[(usize, usize)],
+        positions: &[Option<f64>],
+        is_end: bool,
+    ) -> usize {
+        let (peer_start, peer_end) = peers[pos];
+        let current_row = if is_end { peer_end } else { peer_start };
+        let target = match (bound, positions.get(pos).copied().flatten()) {
+            (FrameBound::UnboundedPreceding, _) => return 0,
+            (FrameBound::UnboundedFollowing, _) => return len,
+            (FrameBound::CurrentRow, _) => return current_row,
+            // A NULL key's frame is its peers, the other NULLs
+            (_, None) => return current_row,
+            (FrameBound::Preceding(n), Some(position)) => position - n as f64,
+            (FrameBound::Following(n), Some(position)) => position + n as f64,
+        };
+
+        // Non-NULL keys are contiguous and ascending in position
+        let first = positions.iter().position(Option::is_some).unwrap_or(len);
+        let last = positions
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(first, |p| p + 1);
+        let keyed = &positions[first..last];
+        first
+            + keyed.partition_point(|position| {
+                let position = position.unwrap_or_default();
+                if is_end {
+                    position <= target
+                } else {
+                    position < target
+                }
+            })
+    }
+}
+
+/// Aggregate over each row's frame. Frames anchored at the partition start only
+/// grow, so they are accumulated incrementally; sliding frames are recomputed.
+fn aggregate_frames(
+    function: &AggregateFunction,
+    arguments: &[Value],
+    frames: &[(usize, usize)],
+    start: FrameBound,
+) -> Result<Vec<Value>, StorageError> {
+    if start == FrameBound::UnboundedPreceding {
+        let mut accumulator = Accumulator::new(function);
+        let mut accumulated = 0;
+        return frames
+            .iter()
+            .map(|&(_, end)| {
+                for argument in &arguments[accumulated..end.max(accumulated)] {
+                    accumulator.update(argument)?;
+                }
+                accumulated = accumulated.max(end);
+                Ok(accumulator.finish())
+            })
+            .collect();
+    }
+
+    frames
+        .iter()
+        .map(|&(start, end)| {
+            let mut accumulator = Accumulator::new(function);
+            for argument in &arguments[start..end] {
+                accumulator.update(argument)?;
+            }
+            Ok(accumulator.finish())
+        })
+        .collect()
+}
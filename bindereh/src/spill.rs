@@ -0,0 +1,145 @@
+//! Temporary on-disk row storage for operators that outgrow their memory budget
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use shared_types::{Row, StorageError, Value};
+
+static NEXT_SPILL_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Default memory budget for a single operator before it starts spilling
+pub const DEFAULT_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+
+/// Number of partitions an operator splits its input into when it spills
+pub const SPILL_FANOUT: usize = 8;
+
+/// Past this many re-partitioning rounds, a partition is processed in memory regardless of budget
+pub const MAX_SPILL_DEPTH: usize = 4;
+
+/// Partition for a spilled key; the depth is mixed in so each round splits differently
+pub fn spill_partition(key: &[Value], depth: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    depth.hash(&mut hasher);
+    key.hash(&mut hasher);
+    (hasher.finish() % SPILL_FANOUT as u64) as usize
+}
+
+/// Create one spill file per partition
+pub fn create_partitions(dir: &Path) -> Result<Vec<SpillFile>, StorageError> {
+    (0..SPILL_FANOUT).map(|_| SpillFile::create(dir)).collect()
+}
+
+/// Append-only file of rows, removed from disk when dropped
+#[derive(Debug)]
+pub struct SpillFile {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    rows: usize,
+    bytes: usize,
+}
+
+impl SpillFile {
+    pub fn create(dir: &Path) -> Result<Self, StorageError> {
+        let id = NEXT_SPILL_ID.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("bambang-spill-{}-{}.tmp", std::process::id(), id));
+        let file = File::create(&path).map_err(|e| spill_error(&path, e))?;
+        Ok(Self {
+            path,
+            writer: Some(BufWriter::new(file)),
+            rows: 0,
+            bytes: 0,
+        })
+    }
+
+    pub fn write_row(&mut self, row: &Row) -> Result<(), StorageError> {
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| StorageError::InvalidOperation("Spill file is already sealed".into()))?;
+        let bytes = row.to_bytes();
+        writer
+            .write_all(&(bytes.len() as u32).to_le_bytes())
+            .and_then(|_| writer.write_all(&bytes))
+            .map_err(|e| spill_error(&self.path, e))?;
+        self.rows += 1;
+        self.bytes += bytes.len() + 4;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Flush pending writes and read the rows back in insertion order
+    pub fn into_reader(mut self) -> Result<SpillReader, StorageError> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush().map_err(|e| spill_error(&self.path, e))?;
+        }
+        let file = File::open(&self.path).map_err(|e| spill_error(&self.path, e))?;
+        Ok(SpillReader {
+            reader: BufReader::new(file),
+            buffer: Vec::new(),
+            remaining: self.rows,
+            file: self,
+        })
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        self.writer.take();
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Iterator over the rows of a sealed spill file
+#[derive(Debug)]
+pub struct SpillReader {
+    reader: BufReader<File>,
+    buffer: Vec<u8>,
+    remaining: usize,
+    file: SpillFile,
+}
+
+impl SpillReader {
+    fn read_row(&mut self) -> Result<Row, StorageError> {
+        let mut len = [0u8; 4];
+        self.reader
+            .read_exact(&mut len)
+            .map_err(|e| spill_error(&self.file.path, e))?;
+        self.buffer.resize(u32::from_le_bytes(len) as usize, 0);
+        self.reader
+            .read_exact(&mut self.buffer)
+            .map_err(|e| spill_error(&self.file.path, e))?;
+        let mut offset = 0;
+        Row::from_bytes(&self.buffer, &mut offset)
+    }
+}
+
+impl Iterator for SpillReader {
+    type Item = Result<Row, StorageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(self.read_row())
+    }
+}
+
+fn spill_error(path: &Path, err: std::io::Error) -> StorageError {
+    StorageError::IoError(format!("Spill file {}: {}", path.display(), err))
+}
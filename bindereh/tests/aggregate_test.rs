@@ -0,0 +1,372 @@
+mod common;
+
+use bindereh::operator::aggregate::{AggregateFunction, HashAggregateOperation};
+use bindereh::spill::SpillFile;
+use shared_types::{BinaryOp, Column, DataType, Expr, Row, Schema, Value};
+
+use common::sorted_data;
+
+fn sales_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::nullable("region".to_string(), DataType::String),
+        Column::nullable("year".to_string(), DataType::Integer),
+        Column::nullable("amount".to_string(), DataType::Integer),
+    ])
+}
+
+fn sales_rows(count: i64) -> Vec<Row> {
+    (0..count)
+        .map(|i| {
+            let amount = if i % 10 == 9 {
+                Value::Null
+            } else {
+                Value::Integer(i % 7)
+            };
+            Row::new(
+                i as u64,
+                vec![
+                    Value::Integer(i),
+                    Value::String(format!("region-{}", i % 3)),
+                    Value::Integer(1992 + i % 4),
+                    amount,
+                ],
+            )
+        })
+        .collect()
+}
+
+fn region_year_aggregate() -> HashAggregateOperation {
+    HashAggregateOperation::new(
+        vec![
+            (Expr::column("region"), "region".to_string()),
+            (Expr::column("year"), "year".to_string()),
+        ],
+        vec![
+            (AggregateFunction::Count, "cnt".to_string()),
+            (
+                AggregateFunction::Sum {
+                    expr: Expr::column("amount"),
+                },
+                "total".to_string(),
+            ),
+            (
+                AggregateFunction::Min {
+                    expr: Expr::column("amount"),
+                },
+                "low".to_string(),
+            ),
+            (
+                AggregateFunction::CountDistinct {
+                    expr: Expr::column("amount"),
+                },
+                "distinct_amounts".to_string(),
+            ),
+        ],
+    )
+}
+
+#[tokio::test]
+async fn test_group_by_multiple_keys() {
+    let rows = sales_rows(120);
+    let result = region_year_aggregate()
+        .execute(rows.clone(), &sales_schema())
+        .await
+        .unwrap();
+
+    // region cycles with period 3 and year with period 4, so there are 12 groups of 10 rows
+    assert_eq!(result.groups, 12);
+    assert_eq!(result.rows.len(), 12);
+    assert_eq!(result.input_rows, 120);
+    assert_eq!(result.spilled_rows, 0);
+    assert_eq!(result.result_schema.columns[3].name, "total");
+    assert_eq!(result.result_schema.columns[3].data_type, DataType::BigInt);
+
+    for row in &result.rows {
+        assert_eq!(row.data[2], Value::Integer(10));
+        let region = &row.data[0];
+        let year = &row.data[1];
+        let expected: i64 = rows
+            .iter()
+            .filter(|r| &r.data[1] == region && &r.data[2] == year)
+            .filter_map(|r| match r.data[3] {
+                Value::Integer(amount) => Some(amount),
+                _ => None,
+            })
+            .sum();
+        // Integer sums are BIGINT values, not just equal to them
+        assert!(matches!(row.data[3], Value::BigInt(total) if total == expected as i128));
+    }
+}
+
+#[tokio::test]
+async fn test_having_filters_groups() {
+    let operation = HashAggregateOperation::new(
+        vec![(Expr::column("year"), "year".to_string())],
+        vec![(
+            AggregateFunction::Sum {
+                expr: Expr::column("amount"),
+            },
+            "total".to_string(),
+        )],
+    )
+    .with_having(Expr::binary(
+        Expr::column("total"),
+        BinaryOp::Gt,
+        Expr::literal(Value::Integer(40)),
+    ));
+
+    let result = operation
+        .execute(sales_rows(60), &sales_schema())
+        .await
+        .unwrap();
+    // Per-year totals are 1992: 42, 1993: 40, 1994: 44, 1995: 33
+    assert_eq!(result.groups, 4);
+    let mut years: Vec<Value> = result.rows.iter().map(|row| row.data[0].clone()).collect();
+    years.sort();
+    assert_eq!(years, vec![Value::Integer(1992), Value::Integer(1994)]);
+}
+
+#[tokio::test]
+async fn test_count_of_expression_skips_nulls() {
+    let count_amount = || AggregateFunction::CountNonNull {
+        expr: Expr::column("amount"),
+    };
+    let operation = HashAggregateOperation::new(
+        vec![(Expr::column("year"), "year".to_string())],
+        vec![
+            (AggregateFunction::Count, "cnt".to_string()),
+            (count_amount(), "amounts".to_string()),
+        ],
+    );
+    let result = operation
+        .execute(sales_rows(120), &sales_schema())
+        .await
+        .unwrap();
+    assert_eq!(result.result_schema.columns[2].data_type, DataType::Integer);
+    // Every tenth amount is NULL; those rows fall in 1993 and 1995
+    assert_eq!(
+        sorted_data(result.rows),
+        vec![
+            vec![Value::Integer(1992), Value::Integer(30), Value::Integer(30)],
+            vec![Value::Integer(1993), Value::Integer(30), Value::Integer(24)],
+            vec![Value::Integer(1994), Value::Integer(30), Value::Integer(30)],
+            vec![Value::Integer(1995), Value::Integer(30), Value::Integer(24)],
+        ]
+    );
+
+    // Rows that are all NULL count for COUNT(*) only
+    let nulls: Vec<Row> = sales_rows(20)
+        .into_iter()
+        .filter(|row| row.data[3].is_null())
+        .collect();
+    let global = HashAggregateOperation::new(
+        Vec::new(),
+        vec![
+            (AggregateFunction::Count, "cnt".to_string()),
+            (count_amount(), "amounts".to_string()),
+        ],
+    );
+    let result = global.execute(nulls, &sales_schema()).await.unwrap();
+    assert_eq!(
+        result.rows[0].data,
+        vec![Value::Integer(2), Value::Integer(0)]
+    );
+}
+
+#[tokio::test]
+async fn test_sum_overflow_is_an_error() {
+    let schema = Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::nullable("big".to_string(), DataType::BigInt),
+        Column::nullable(
+            "price".to_string(),
+            DataType::Decimal {
+                precision: 38,
+                scale: 2,
+            },
+        ),
+    ]);
+    let rows = |big: i128, price: &str| -> Vec<Row> {
+        (0..3)
+            .map(|i| {
+                Row::new(
+                    i,
+                    vec![
+                        Value::Integer(i as i64),
+                        Value::BigInt(big),
+                        Value::Decimal(price.parse().unwrap()),
+                    ],
+                )
+            })
+            .collect()
+    };
+    let sum = |column: &str| {
+        HashAggregateOperation::new(
+            Vec::new(),
+            vec![(
+                AggregateFunction::Sum {
+                    expr: Expr::column(column),
+                },
+                "total".to_string(),
+            )],
+        )
+    };
+
+    // Past i64 is still exact
+    let result = sum("big")
+        .execute(rows(i64::MAX as i128, "1"), &schema)
+        .await
+        .unwrap();
+    assert!(matches!(
+        result.rows[0].data[0],
+        Value::BigInt(total) if total == 3 * i64::MAX as i128
+    ));
+
+    let error = sum("big")
+        .execute(rows(i128::MAX / 2, "1"), &schema)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("Integer overflow in SUM"),
+        "{}",
+        error
+    );
+
+    // 36 integer digits and 2 fractional ones fill DECIMAL(38, 2)
+    let price = format!("{}.99", "9".repeat(36));
+    let error = sum("price")
+        .execute(rows(0, &price), &schema)
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("Decimal overflow in SUM"),
+        "{}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn test_spilled_aggregation_matches_in_memory() {
+    let rows = sales_rows(3000);
+    let operation = HashAggregateOperation::new(
+        vec![(
+            Expr::binary(
+                Expr::column("id"),
+                BinaryOp::Modulo,
+                Expr::literal(Value::Integer(500)),
+            ),
+            "bucket".to_string(),
+        )],
+        vec![
+            (AggregateFunction::Count, "cnt".to_string()),
+            (
+                AggregateFunction::CountNonNull {
+                    expr: Expr::column("amount"),
+                },
+                "amounts".to_string(),
+            ),
+            (
+                AggregateFunction::Avg {
+                    expr: Expr::column("amount"),
+                },
+                "avg_amount".to_string(),
+            ),
+            (
+                AggregateFunction::Max {
+                    expr: Expr::column("region"),
+                },
+                "max_region".to_string(),
+            ),
+        ],
+    );
+    let in_memory = operation
+        .execute(rows.clone(), &sales_schema())
+        .await
+        .unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let spilling = HashAggregateOperation::new(
+        vec![(
+            Expr::binary(
+                Expr::column("id"),
+                BinaryOp::Modulo,
+                Expr::literal(Value::Integer(500)),
+            ),
+            "bucket".to_string(),
+        )],
+        vec![
+            (AggregateFunction::Count, "cnt".to_string()),
+            (
+                AggregateFunction::CountNonNull {
+                    expr: Expr::column("amount"),
+                },
+                "amounts".to_string(),
+            ),
+            (
+                AggregateFunction::Avg {
+                    expr: Expr::column("amount"),
+                },
+                "avg_amount".to_string(),
+            ),
+            (
+                AggregateFunction::Max {
+                    expr: Expr::column("region"),
+                },
+                "max_region".to_string(),
+            ),
+        ],
+    )
+    .with_memory_budget(2048)
+    .with_spill_dir(dir.path());
+    let spilled = spilling.execute(rows, &sales_schema()).await.unwrap();
+
+    assert!(spilled.spilled_rows > 0);
+    assert_eq!(spilled.groups, 500);
+    assert_eq!(sorted_data(spilled.rows), sorted_data(in_memory.rows));
+    // Spill files are removed once their partition has been aggregated
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_global_aggregate_over_empty_input() {
+    let operation = HashAggregateOperation::new(
+        Vec::new(),
+        vec![
+            (AggregateFunction::Count, "cnt".to_string()),
+            (
+                AggregateFunction::Sum {
+                    expr: Expr::column("amount"),
+                },
+                "total".to_string(),
+            ),
+        ],
+    );
+    let result = operation
+        .execute(Vec::new(), &sales_schema())
+        .await
+        .unwrap();
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(result.rows[0].data, vec![Value::Integer(0), Value::Null]);
+
+    let grouped = region_year_aggregate()
+        .execute(Vec::new(), &sales_schema())
+        .await
+        .unwrap();
+    assert!(grouped.rows.is_empty());
+}
+
+#[test]
+fn test_spill_file_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut file = SpillFile::create(dir.path()).unwrap();
+    let rows = sales_rows(50);
+    for row in &rows {
+        file.write_row(row).unwrap();
+    }
+    assert_eq!(file.len(), 50);
+
+    let read: Vec<Row> = file.into_reader().unwrap().map(Result::unwrap).collect();
+    assert_eq!(read, rows);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
@@ -0,0 +1,18 @@
+// Each test crate uses only some of these helpers
+#![allow(dead_code)]
+
+use shared_types::{Row, Value};
+
+/// Row values in sorted order, for comparing results whose order isn't defined
+pub fn sorted_data(rows: Vec<Row>) -> Vec<Vec<Value>> {
+    let mut data: Vec<Vec<Value>> = rows.into_iter().map(|row| row.data).collect();
+    data.sort();
+    data
+}
+
+/// Like `sorted_data`, but keeps each row's id alongside its values
+pub fn sorted_rows(rows: Vec<Row>) -> Vec<(u64, Vec<Value>)> {
+    let mut data: Vec<(u64, Vec<Value>)> = rows.into_iter().map(|row| (row.id, row.data)).collect();
+    data.sort();
+    data
+}
@@ -0,0 +1,206 @@
+use bindereh::operator::{
+    aggregate::{AggregateFunction, AggregateProcessor},
+    expression::{PhysicalExpr, expr_data_type},
+};
+use shared_types::{BinaryOp, Column, DataType, Decimal, Expr, Row, Schema, UnaryOp, Value};
+
+fn dec(text: &str) -> Decimal {
+    text.parse().unwrap()
+}
+
+#[test]
+fn test_parse_and_display() {
+    assert_eq!(dec("123.450").to_string(), "123.450");
+    assert_eq!(dec("-0.05").to_string(), "-0.05");
+    assert_eq!(dec("7").to_string(), "7");
+    assert_eq!(dec(".5").to_string(), "0.5");
+    assert!("1.2.3".parse::<Decimal>().is_err());
+    assert!("abc".parse::<Decimal>().is_err());
+}
+
+#[test]
+fn test_exact_arithmetic() {
+    assert_eq!(dec("0.1").checked_add(&dec("0.2")).unwrap(), dec("0.3"));
+    assert_eq!(
+        dec("1.10").checked_sub(&dec("0.2")).unwrap().to_string(),
+        "0.90"
+    );
+    assert_eq!(
+        dec("1.5").checked_mul(&dec("-2.25")).unwrap().to_string(),
+        "-3.375"
+    );
+    assert_eq!(
+        dec("1").checked_div(&dec("3")).unwrap().to_string(),
+        "0.333333"
+    );
+    assert_eq!(
+        dec("2").checked_div(&dec("3")).unwrap().to_string(),
+        "0.666667"
+    );
+    assert!(dec("1").checked_div(&dec("0")).is_none());
+    assert_eq!(dec("7.5").checked_rem(&dec("2")).unwrap(), dec("1.5"));
+}
+
+#[test]
+fn test_rounding_and_fit() {
+    assert_eq!(dec("2.345").round(2).to_string(), "2.35");
+    assert_eq!(dec("-2.345").round(2).to_string(), "-2.35");
+    assert_eq!(dec("2.344").round(2).to_string(), "2.34");
+    assert_eq!(dec("12.5").fit(4, 2).unwrap().to_string(), "12.50");
+    assert!(dec("123.5").fit(4, 2).is_err());
+    assert!(dec("99.995").fit(4, 2).is_err());
+}
+
+#[test]
+fn test_scale_beyond_maximum_keeps_value() {
+    // 0.15 written with 39 fractional digits
+    let wide = Decimal::new(15 * 10i128.pow(37), 39);
+    assert_eq!(wide.scale(), 38);
+    assert_eq!(wide, dec("0.15"));
+
+    let rounded = Decimal::new(25, 40);
+    assert_eq!(rounded.mantissa(), 0);
+    assert_eq!(Decimal::new(250, 40).mantissa(), 3);
+    assert_eq!(Decimal::new(i128::MAX, 120).mantissa(), 0);
+}
+
+#[test]
+fn test_exact_float_conversion() {
+    assert_eq!(Decimal::from_f64_exact(-0.375), Some(dec("-0.375")));
+    assert_eq!(Decimal::from_f64_exact(1024.0), Some(dec("1024")));
+    assert_eq!(Decimal::from_f64_exact(0.0), Some(dec("0")));
+    // 0.1 has no finite binary form, and 2^-39 needs 39 fractional digits
+    assert_eq!(Decimal::from_f64_exact(0.1), None);
+    assert_eq!(Decimal::from_f64_exact(2f64.powi(-39)), None);
+    assert!(Decimal::from_f64_exact(2f64.powi(-38)).is_some());
+    assert_eq!(Decimal::from_f64_exact(1e300), None);
+    assert_eq!(Decimal::from_f64_exact(f64::NAN), None);
+}
+
+#[test]
+fn test_negate_and_abs_overflow() {
+    assert_eq!(dec("-1.25").checked_abs().unwrap(), dec("1.25"));
+    assert_eq!(dec("1.25").checked_neg().unwrap(), dec("-1.25"));
+    let min = Decimal::new(i128::MIN, 2);
+    assert!(min.checked_neg().is_none());
+    assert!(min.checked_abs().is_none());
+
+    let schema = Schema::new(vec![Column::nullable(
+        "d".to_string(),
+        DataType::Decimal {
+            precision: 38,
+            scale: 2,
+        },
+    )]);
+    let row = Row::new(1, vec![Value::Decimal(min)]);
+    let negate =
+        PhysicalExpr::compile(&Expr::unary(UnaryOp::Minus, Expr::column("d")), &schema).unwrap();
+    assert!(negate.evaluate(&row).is_err());
+    let abs =
+        PhysicalExpr::compile(&Expr::function("abs", vec![Expr::column("d")]), &schema).unwrap();
+    assert!(abs.evaluate(&row).is_err());
+}
+
+#[test]
+fn test_comparison_across_scales_and_types() {
+    assert_eq!(dec("1.50"), dec("1.5"));
+    assert!(dec("1.49") < dec("1.5"));
+    assert_eq!(Value::Decimal(dec("3.00")), Value::Integer(3));
+    assert!(Value::Decimal(dec("3.01")) > Value::BigInt(3));
+    assert!(Value::Decimal(dec("0.1")) < Value::Float(0.2));
+}
+
+#[test]
+fn test_expression_arithmetic_and_cast() {
+    let schema = Schema::new(vec![
+        Column::not_null(
+            "price".to_string(),
+            DataType::Decimal {
+                precision: 15,
+                scale: 2,
+            },
+        ),
+        Column::not_null("qty".to_string(), DataType::Integer),
+    ]);
+    let row = Row::new(1, vec![Value::Decimal(dec("19.99")), Value::Integer(3)]);
+
+    let total = Expr::multiply(Expr::column("price"), Expr::column("qty"));
+    let compiled = PhysicalExpr::compile(&total, &schema).unwrap();
+    assert_eq!(
+        compiled.evaluate(&row).unwrap(),
+        Value::Decimal(dec("59.97"))
+    );
+    assert_eq!(
+        expr_data_type(&total, &schema).unwrap(),
+        DataType::Decimal {
+            precision: 34,
+            scale: 2
+        }
+    );
+
+    let cast = Expr::cast(
+        Expr::literal(Value::String("3.14159".to_string())),
+        DataType::Decimal {
+            precision: 5,
+            scale: 2,
+        },
+    );
+    let compiled = PhysicalExpr::compile(&cast, &schema).unwrap();
+    assert_eq!(
+        compiled.evaluate(&row).unwrap(),
+        Value::Decimal(dec("3.14"))
+    );
+
+    let overflow = Expr::cast(
+        Expr::literal(Value::Integer(1000)),
+        DataType::Decimal {
+            precision: 5,
+            scale: 2,
+        },
+    );
+    let compiled = PhysicalExpr::compile(&overflow, &schema).unwrap();
+    assert!(compiled.evaluate(&row).is_err());
+
+    let divide = Expr::binary(
+        Expr::column("price"),
+        BinaryOp::Divide,
+        Expr::literal(Value::Integer(0)),
+    );
+    let compiled = PhysicalExpr::compile(&divide, &schema).unwrap();
+    assert!(compiled.evaluate(&row).is_err());
+}
+
+#[test]
+fn test_exact_sum_and_avg() {
+    let schema = Schema::new(vec![Column::not_null(
+        "amount".to_string(),
+        DataType::Decimal {
+            precision: 10,
+            scale: 2,
+        },
+    )]);
+    let rows: Vec<Row> = (0..10)
+        .map(|i| Row::new(i, vec![Value::Decimal(dec("0.10"))]))
+        .chain(std::iter::once(Row::new(10, vec![Value::Null])))
+        .collect();
+
+    let result = AggregateProcessor::process_aggregates(
+        &rows,
+        &[
+            AggregateFunction::Sum {
+                expr: Expr::column("amount"),
+            },
+            AggregateFunction::Avg {
+                expr: Expr::column("amount"),
+            },
+        ],
+        &schema,
+    )
+    .unwrap();
+
+    assert_eq!(result.data[0], Value::Decimal(dec("1.00")));
+    match &result.data[1] {
+        Value::Decimal(avg) => assert_eq!(*avg, dec("0.1")),
+        other => panic!("expected decimal average, got {:?}", other),
+    }
+}
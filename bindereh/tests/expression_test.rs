@@ -0,0 +1,300 @@
+use bindereh::operator::{
+    aggregate::{AggregateFunction, AggregateProcessor},
+    expression::{PhysicalExpr, filter_rows, project_rows},
+};
+use shared_types::{BinaryOp, Column, DataType, Expr, Row, Schema, UnaryOp, Value};
+
+fn test_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::nullable("price".to_string(), DataType::Float),
+        Column::nullable("discount".to_string(), DataType::Integer),
+        Column::nullable("name".to_string(), DataType::String),
+    ])
+}
+
+fn test_rows() -> Vec<Row> {
+    vec![
+        Row::new(
+            1,
+            vec![
+                Value::Integer(1),
+                Value::Float(100.0),
+                Value::Integer(5),
+                Value::String("apple".to_string()),
+            ],
+        ),
+        Row::new(
+            2,
+            vec![
+                Value::Integer(2),
+                Value::Float(200.0),
+                Value::Integer(10),
+                Value::String("banana".to_string()),
+            ],
+        ),
+        Row::new(
+            3,
+            vec![
+                Value::Integer(3),
+                Value::Float(50.0),
+                Value::Null,
+                Value::String("cherry".to_string()),
+            ],
+        ),
+    ]
+}
+
+fn eval(expr: &Expr, row: &Row) -> Value {
+    PhysicalExpr::compile(expr, &test_schema())
+        .unwrap()
+        .evaluate(row)
+        .unwrap()
+}
+
+#[test]
+fn test_arithmetic_and_column_comparison() {
+    let rows = test_rows();
+    let revenue = Expr::multiply(Expr::column("price"), Expr::column("discount"));
+    assert_eq!(eval(&revenue, &rows[0]), Value::Float(500.0));
+    assert_eq!(eval(&revenue, &rows[2]), Value::Null);
+
+    let price_gt_id = Expr::binary(
+        Expr::column("price"),
+        BinaryOp::Gt,
+        Expr::column("discount"),
+    );
+    assert_eq!(eval(&price_gt_id, &rows[1]), Value::Boolean(true));
+
+    let int_math = Expr::binary(
+        Expr::column("discount"),
+        BinaryOp::Modulo,
+        Expr::literal(Value::Integer(3)),
+    );
+    assert_eq!(eval(&int_math, &rows[1]), Value::Integer(1));
+}
+
+#[test]
+fn test_boolean_logic_with_nulls() {
+    let rows = test_rows();
+    let null_cmp = Expr::binary(
+        Expr::column("discount"),
+        BinaryOp::Gt,
+        Expr::literal(Value::Integer(1)),
+    );
+    let false_cmp = Expr::equals(Expr::column("id"), Expr::literal(Value::Integer(99)));
+    let true_cmp = Expr::equals(Expr::column("id"), Expr::literal(Value::Integer(3)));
+
+    assert_eq!(
+        eval(&Expr::and(null_cmp.clone(), false_cmp), &rows[2]),
+        Value::Boolean(false)
+    );
+    assert_eq!(
+        eval(&Expr::or(null_cmp.clone(), true_cmp.clone()), &rows[2]),
+        Value::Boolean(true)
+    );
+    assert_eq!(eval(&Expr::and(null_cmp, true_cmp), &rows[2]), Value::Null);
+}
+
+#[test]
+fn test_case_cast_and_functions() {
+    let rows = test_rows();
+    let case = Expr::Case {
+        operand: None,
+        when_then: vec![(
+            Expr::binary(
+                Expr::column("price"),
+                BinaryOp::GtEq,
+                Expr::literal(Value::Float(100.0)),
+            ),
+            Expr::literal(Value::String("expensive".to_string())),
+        )],
+        else_expr: Some(Box::new(Expr::literal(Value::String("cheap".to_string())))),
+    };
+    assert_eq!(
+        eval(&case, &rows[0]),
+        Value::String("expensive".to_string())
+    );
+    assert_eq!(eval(&case, &rows[2]), Value::String("cheap".to_string()));
+
+    let cast = Expr::cast(Expr::column("price"), DataType::Integer);
+    assert_eq!(eval(&cast, &rows[1]), Value::Integer(200));
+
+    let upper = Expr::function("upper", vec![Expr::column("name")]);
+    assert_eq!(eval(&upper, &rows[0]), Value::String("APPLE".to_string()));
+
+    let coalesce = Expr::function(
+        "coalesce",
+        vec![Expr::column("discount"), Expr::literal(Value::Integer(0))],
+    );
+    assert_eq!(eval(&coalesce, &rows[2]), Value::Integer(0));
+
+    assert!(PhysicalExpr::compile(&Expr::function("nope", vec![]), &test_schema()).is_err());
+    assert!(PhysicalExpr::compile(&Expr::column("missing"), &test_schema()).is_err());
+}
+
+#[test]
+fn test_negating_integer_minimum_overflows() {
+    let row = &test_rows()[0];
+    let negate = |value: Value| {
+        PhysicalExpr::compile(
+            &Expr::unary(UnaryOp::Minus, Expr::literal(value)),
+            &test_schema(),
+        )
+        .unwrap()
+        .evaluate(row)
+    };
+    assert_eq!(negate(Value::TinyInt(-127)).unwrap(), Value::TinyInt(127));
+    assert!(negate(Value::TinyInt(i8::MIN)).is_err());
+    assert!(negate(Value::SmallInt(i16::MIN)).is_err());
+    assert!(negate(Value::Integer(i64::MIN)).is_err());
+    assert!(negate(Value::BigInt(i128::MIN)).is_err());
+    assert_eq!(negate(Value::Null).unwrap(), Value::Null);
+}
+
+#[test]
+fn test_cast_to_integers_checks_range() {
+    let row = &test_rows()[0];
+    let cast = |value: Value, data_type: DataType| {
+        PhysicalExpr::compile(&Expr::cast(Expr::literal(value), data_type), &test_schema())
+            .unwrap()
+            .evaluate(row)
+    };
+
+    let wide = i64::MAX as i128 * 4;
+    assert_eq!(
+        cast(Value::BigInt(wide), DataType::BigInt).unwrap(),
+        Value::BigInt(wide)
+    );
+    assert_eq!(
+        cast(Value::String(wide.to_string()), DataType::BigInt).unwrap(),
+        Value::BigInt(wide)
+    );
+    assert!(cast(Value::BigInt(wide), DataType::Integer).is_err());
+    assert!(cast(Value::Integer(300), DataType::TinyInt).is_err());
+
+    assert_eq!(
+        cast(Value::Float(2.5), DataType::Integer).unwrap(),
+        Value::Integer(3)
+    );
+    assert_eq!(
+        cast(Value::Float(1e30), DataType::BigInt).unwrap(),
+        Value::BigInt(1e30 as i128)
+    );
+    assert!(cast(Value::Float(1e30), DataType::Integer).is_err());
+    assert!(cast(Value::Float(f64::NAN), DataType::Integer).is_err());
+    assert!(cast(Value::Float(f64::INFINITY), DataType::BigInt).is_err());
+    assert!(cast(Value::Float(1e40), DataType::BigInt).is_err());
+}
+
+#[test]
+fn test_like_escapes_regex_characters() {
+    let row = Row::new(
+        1,
+        vec![
+            Value::Integer(1),
+            Value::Float(1.0),
+            Value::Integer(1),
+            Value::String("a.c".to_string()),
+        ],
+    );
+    let like = |pattern: &str| Expr::Like {
+        expr: Box::new(Expr::column("name")),
+        pattern: Box::new(Expr::literal(Value::String(pattern.to_string()))),
+        negated: false,
+        case_insensitive: false,
+    };
+    assert_eq!(eval(&like("a.c"), &row), Value::Boolean(true));
+    assert_eq!(eval(&like("a_c"), &row), Value::Boolean(true));
+    assert_eq!(eval(&like("A%"), &row), Value::Boolean(false));
+
+    let other = Row::new(
+        2,
+        vec![
+            Value::Integer(2),
+            Value::Float(1.0),
+            Value::Integer(1),
+            Value::String("abc".to_string()),
+        ],
+    );
+    assert_eq!(eval(&like("a.c"), &other), Value::Boolean(false));
+}
+
+#[test]
+fn test_like_wildcards_match_newlines() {
+    let row = Row::new(
+        1,
+        vec![
+            Value::Integer(1),
+            Value::Float(1.0),
+            Value::Integer(1),
+            Value::String("a\nb".to_string()),
+        ],
+    );
+    let like = |pattern: &str, case_insensitive: bool| Expr::Like {
+        expr: Box::new(Expr::column("name")),
+        pattern: Box::new(Expr::literal(Value::String(pattern.to_string()))),
+        negated: false,
+        case_insensitive,
+    };
+    assert_eq!(eval(&like("a%", false), &row), Value::Boolean(true));
+    assert_eq!(eval(&like("a_b", false), &row), Value::Boolean(true));
+    assert_eq!(eval(&like("A%B", true), &row), Value::Boolean(true));
+    assert_eq!(eval(&like("a%c", false), &row), Value::Boolean(false));
+}
+
+#[test]
+fn test_filter_and_project_rows() {
+    let schema = test_schema();
+    let filtered = filter_rows(
+        test_rows(),
+        &Expr::binary(
+            Expr::column("discount"),
+            BinaryOp::GtEq,
+            Expr::literal(Value::Integer(5)),
+        ),
+        &schema,
+    )
+    .unwrap();
+    assert_eq!(filtered.len(), 2);
+
+    let (projected, projected_schema) = project_rows(
+        &filtered,
+        &[(
+            Expr::binary(
+                Expr::column("price"),
+                BinaryOp::Minus,
+                Expr::literal(Value::Integer(50)),
+            ),
+            "net".to_string(),
+        )],
+        &schema,
+    )
+    .unwrap();
+    assert_eq!(projected_schema.columns[0].data_type, DataType::Float);
+    assert_eq!(projected[1].data, vec![Value::Float(150.0)]);
+}
+
+#[test]
+fn test_aggregate_over_expression() {
+    let revenue = Expr::divide(
+        Expr::multiply(Expr::column("price"), Expr::column("discount")),
+        Expr::literal(Value::Float(100.0)),
+    );
+    let result = AggregateProcessor::process_aggregates(
+        &test_rows(),
+        &[
+            AggregateFunction::Sum { expr: revenue },
+            AggregateFunction::Max {
+                expr: Expr::column("price"),
+            },
+            AggregateFunction::Count,
+        ],
+        &test_schema(),
+    )
+    .unwrap();
+    assert_eq!(
+        result.data,
+        vec![Value::Float(25.0), Value::Float(200.0), Value::Integer(3)]
+    );
+}
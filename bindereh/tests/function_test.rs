@@ -0,0 +1,350 @@
+use bindereh::operator::expression::{PhysicalExpr, expr_data_type};
+use shared_types::{
+    Column, DataType, Decimal, Expr, Row, ScalarFunction, Schema, SignatureError, StorageError,
+    Value,
+};
+
+// 2024-01-31 and 2024-05-15 13:45:30.250
+const JAN_31_2024: i32 = 19753;
+const MAY_15_2024_AFTERNOON: i64 = 1_715_780_730_250;
+
+fn test_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::nullable("name".to_string(), DataType::String),
+        Column::nullable(
+            "price".to_string(),
+            DataType::Decimal {
+                precision: 10,
+                scale: 2,
+            },
+        ),
+        Column::nullable("shipped".to_string(), DataType::Date),
+        Column::nullable("updated".to_string(), DataType::Timestamp),
+    ])
+}
+
+fn test_row() -> Row {
+    Row::new(
+        1,
+        vec![
+            Value::Integer(1),
+            Value::String("  Widget  ".to_string()),
+            Value::Decimal(Decimal::new(-1250, 2)),
+            Value::Date(JAN_31_2024),
+            Value::Timestamp(MAY_15_2024_AFTERNOON),
+        ],
+    )
+}
+
+fn call(name: &str, args: Vec<Expr>) -> Value {
+    PhysicalExpr::compile(&Expr::function(name, args), &test_schema())
+        .unwrap()
+        .evaluate(&test_row())
+        .unwrap()
+}
+
+fn try_call(name: &str, args: Vec<Expr>) -> Result<Value, StorageError> {
+    PhysicalExpr::compile(&Expr::function(name, args), &test_schema())
+        .unwrap()
+        .evaluate(&test_row())
+}
+
+fn int(value: i64) -> Expr {
+    Expr::literal(Value::Integer(value))
+}
+
+fn text(value: &str) -> Expr {
+    Expr::literal(Value::String(value.to_string()))
+}
+
+fn string(value: &str) -> Value {
+    Value::String(value.to_string())
+}
+
+#[test]
+fn test_string_functions() {
+    let name = || Expr::column("name");
+    assert_eq!(call("trim", vec![name()]), string("Widget"));
+    assert_eq!(call("ltrim", vec![name()]), string("Widget  "));
+    assert_eq!(
+        call("rtrim", vec![text("xxabxx"), text("x")]),
+        string("xxab")
+    );
+    assert_eq!(
+        call("substr", vec![text("database"), int(5)]),
+        string("base")
+    );
+    assert_eq!(
+        call("substring", vec![text("database"), int(2), int(3)]),
+        string("ata")
+    );
+    // A start before the first character eats into the length
+    assert_eq!(
+        call("substr", vec![text("database"), int(-1), int(4)]),
+        string("da")
+    );
+    assert_eq!(call("length", vec![text("héllo")]), Value::Integer(5));
+    assert_eq!(
+        call(
+            "concat",
+            vec![text("a"), Expr::literal(Value::Null), int(1)]
+        ),
+        string("a1")
+    );
+    assert_eq!(
+        call("replace", vec![text("a-b-c"), text("-"), text("+")]),
+        string("a+b+c")
+    );
+    assert_eq!(call("upper", vec![Expr::literal(Value::Null)]), Value::Null);
+}
+
+#[test]
+fn test_math_functions() {
+    let price = || Expr::column("price");
+    assert_eq!(
+        call("abs", vec![price()]),
+        Value::Decimal(Decimal::new(1250, 2))
+    );
+    assert_eq!(
+        call("floor", vec![price()]),
+        Value::Decimal(Decimal::new(-1300, 2))
+    );
+    assert_eq!(
+        call("ceil", vec![price()]),
+        Value::Decimal(Decimal::new(-1200, 2))
+    );
+    assert_eq!(
+        call("ceiling", vec![Expr::literal(Value::Float(1.2))]),
+        Value::Float(2.0)
+    );
+    assert_eq!(call("floor", vec![int(7)]), Value::Integer(7));
+    assert_eq!(
+        call("round", vec![Expr::literal(Value::Float(2.345)), int(1)]),
+        Value::Float(2.3)
+    );
+    assert_eq!(call("power", vec![int(2), int(10)]), Value::Float(1024.0));
+    assert_eq!(call("mod", vec![int(17), int(5)]), Value::Integer(2));
+
+    let by_zero =
+        PhysicalExpr::compile(&Expr::function("mod", vec![int(1), int(0)]), &test_schema())
+            .unwrap()
+            .evaluate(&test_row());
+    assert!(by_zero.is_err());
+}
+
+#[test]
+fn test_round_to_negative_digits() {
+    let decimal = |mantissa, scale| Expr::literal(Value::Decimal(Decimal::new(mantissa, scale)));
+    assert_eq!(
+        call("round", vec![int(1234), int(-2)]),
+        Value::Integer(1200)
+    );
+    assert_eq!(
+        call("round", vec![int(1250), int(-2)]),
+        Value::Integer(1300)
+    );
+    assert_eq!(
+        call("round", vec![int(-1250), int(-2)]),
+        Value::Integer(-1300)
+    );
+    assert_eq!(call("round", vec![int(1234), int(2)]), Value::Integer(1234));
+    assert_eq!(
+        call("round", vec![Expr::literal(Value::SmallInt(449)), int(-1)]),
+        Value::SmallInt(450)
+    );
+    // Decimals round to whole tens, not to zero fractional digits
+    assert_eq!(
+        call("round", vec![Expr::column("price"), int(-1)]),
+        Value::Decimal(Decimal::from_integer(-10))
+    );
+    assert_eq!(
+        call("round", vec![decimal(123_456, 3), int(-2)]),
+        Value::Decimal(Decimal::from_integer(100))
+    );
+    assert_eq!(
+        call("round", vec![Expr::literal(Value::Float(1250.0)), int(-2)]),
+        Value::Float(1300.0)
+    );
+    assert_eq!(call("round", vec![int(7), int(-38)]), Value::Integer(0));
+}
+
+#[test]
+fn test_round_digits_out_of_range() {
+    for digits in [39, -39, 1000, i64::MAX] {
+        assert!(matches!(
+            try_call("round", vec![Expr::column("price"), int(digits)]),
+            Err(StorageError::InvalidInput(_))
+        ));
+    }
+    // Rounding up past the type's maximum is an overflow, not a wrap
+    assert!(try_call("round", vec![int(i64::MAX), int(-1)]).is_err());
+    assert!(try_call("round", vec![Expr::literal(Value::TinyInt(126)), int(-1)]).is_err());
+}
+
+#[test]
+fn test_abs_and_ceil_overflow() {
+    assert!(try_call("abs", vec![int(i64::MIN)]).is_err());
+    assert!(try_call("abs", vec![Expr::literal(Value::TinyInt(i8::MIN))]).is_err());
+    assert!(try_call("abs", vec![Expr::literal(Value::BigInt(i128::MIN))]).is_err());
+    assert_eq!(call("abs", vec![int(-5)]), Value::Integer(5));
+
+    let huge = Expr::literal(Value::Decimal(Decimal::new(i128::MAX, 2)));
+    assert!(try_call("ceil", vec![huge.clone()]).is_err());
+    assert!(try_call("floor", vec![huge]).is_ok());
+}
+
+#[test]
+fn test_conditional_functions() {
+    let null = || Expr::literal(Value::Null);
+    assert_eq!(
+        call("coalesce", vec![null(), int(3), int(4)]),
+        Value::Integer(3)
+    );
+    assert_eq!(call("nullif", vec![int(3), int(3)]), Value::Null);
+    assert_eq!(call("nullif", vec![int(3), int(4)]), Value::Integer(3));
+    assert_eq!(call("nullif", vec![int(3), null()]), Value::Integer(3));
+
+    // NULLs are ignored, and the result takes the arguments' common type
+    assert_eq!(
+        call(
+            "greatest",
+            vec![int(3), null(), Expr::literal(Value::Float(2.5))]
+        ),
+        Value::Float(3.0)
+    );
+    assert_eq!(
+        call("least", vec![int(3), null(), int(-1)]),
+        Value::Integer(-1)
+    );
+    assert_eq!(call("greatest", vec![null(), null()]), Value::Null);
+}
+
+#[test]
+fn test_date_time_functions() {
+    let shipped = || Expr::column("shipped");
+    let updated = || Expr::column("updated");
+
+    // Month arithmetic clamps to the end of the month, including leap years
+    assert_eq!(
+        call("date_add", vec![shipped(), int(1), text("month")]),
+        Value::Date(19782)
+    );
+    assert_eq!(
+        call("date_add", vec![shipped(), int(13), text("months")]),
+        Value::Date(20147)
+    );
+    assert_eq!(
+        call("date_add", vec![shipped(), int(-30)]),
+        Value::Date(19723)
+    );
+    assert_eq!(
+        call("date_add", vec![updated(), int(1), text("month")]),
+        Value::Timestamp(1_718_459_130_250)
+    );
+    assert_eq!(
+        call(
+            "date_add",
+            vec![
+                Expr::literal(Value::Time(23 * 3_600_000)),
+                int(2),
+                text("hour")
+            ]
+        ),
+        Value::Time(3_600_000)
+    );
+
+    assert_eq!(
+        call("date_trunc", vec![text("hour"), updated()]),
+        Value::Timestamp(1_715_778_000_000)
+    );
+    // Weeks start on Monday
+    assert_eq!(
+        call("date_trunc", vec![text("week"), updated()]),
+        Value::Timestamp(1_715_558_400_000)
+    );
+    assert_eq!(
+        call("date_trunc", vec![text("quarter"), shipped()]),
+        Value::Date(19723)
+    );
+
+    let extract = |field: &str, value: Expr| call("extract", vec![text(field), value]);
+    assert_eq!(extract("year", shipped()), Value::Integer(2024));
+    assert_eq!(extract("day", shipped()), Value::Integer(31));
+    assert_eq!(extract("dow", shipped()), Value::Integer(3));
+    assert_eq!(extract("doy", updated()), Value::Integer(136));
+    assert_eq!(
+        extract("week", Expr::literal(Value::Date(18630))),
+        Value::Integer(53)
+    );
+    assert_eq!(extract("hour", updated()), Value::Integer(13));
+    assert_eq!(extract("second", updated()), Value::Integer(30));
+    assert_eq!(extract("epoch", updated()), Value::Integer(1_715_780_730));
+
+    assert!(matches!(call("now", vec![]), Value::Timestamp(ms) if ms > MAY_15_2024_AFTERNOON));
+
+    let sub_day_on_date = PhysicalExpr::compile(
+        &Expr::function("date_add", vec![shipped(), int(1), text("hour")]),
+        &test_schema(),
+    )
+    .unwrap()
+    .evaluate(&test_row());
+    assert!(sub_day_on_date.is_err());
+}
+
+#[test]
+fn test_signatures_drive_result_types_and_checks() {
+    let schema = test_schema();
+    let data_type = |expr: Expr| expr_data_type(&expr, &schema).unwrap();
+    assert_eq!(
+        data_type(Expr::function("floor", vec![Expr::column("price")])),
+        DataType::Decimal {
+            precision: 10,
+            scale: 2
+        }
+    );
+    assert_eq!(
+        data_type(Expr::function(
+            "date_trunc",
+            vec![text("day"), Expr::column("updated")]
+        )),
+        DataType::Timestamp
+    );
+    assert_eq!(
+        data_type(Expr::function(
+            "coalesce",
+            vec![Expr::literal(Value::Null), int(1)]
+        )),
+        DataType::Integer
+    );
+    assert_eq!(
+        data_type(Expr::function(
+            "greatest",
+            vec![int(1), Expr::literal(Value::Float(1.5))]
+        )),
+        DataType::Float
+    );
+
+    assert!(
+        expr_data_type(
+            &Expr::function("upper", vec![Expr::column("price")]),
+            &schema
+        )
+        .is_err()
+    );
+    assert!(PhysicalExpr::compile(&Expr::function("substr", vec![text("x")]), &schema).is_err());
+    assert!(PhysicalExpr::compile(&Expr::function("now", vec![int(1)]), &schema).is_err());
+
+    assert_eq!(
+        ScalarFunction::from_name("POW"),
+        Some(ScalarFunction::Power)
+    );
+    assert!(matches!(
+        ScalarFunction::Extract.return_type(&[Some(DataType::String), Some(DataType::Integer)]),
+        Err(SignatureError::TypeMismatch { position: 1, .. })
+    ));
+    assert!(matches!(
+        ScalarFunction::Replace.check_argument_count(2),
+        Err(SignatureError::WrongArgumentCount { found: 2, .. })
+    ));
+}
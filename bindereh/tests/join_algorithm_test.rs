@@ -0,0 +1,300 @@
+mod common;
+
+use std::sync::Arc;
+
+use bindereh::{
+    executor::Executor,
+    manager::Manager,
+    operator::{
+        index_join::IndexNestedLoopJoinOperation,
+        join::{
+            HashJoinOperation, JoinAlgorithm, JoinCondition, JoinInputProfile, JoinType,
+            choose_join_algorithm,
+        },
+        merge_join::SortMergeJoinOperation,
+    },
+    page::Page,
+};
+use shared_types::{BinaryOp, Column, DataType, Expr, Row, Schema, Value};
+use tempfile::tempdir;
+
+use common::sorted_rows;
+
+fn condition(left: &str, right: &str) -> Vec<JoinCondition> {
+    vec![JoinCondition {
+        left_column: left.to_string(),
+        right_column: right.to_string(),
+    }]
+}
+
+fn keyed_schema(prefix: &str) -> Schema {
+    Schema::new(vec![
+        Column::nullable(format!("{}_key", prefix), DataType::Integer),
+        Column::nullable(format!("{}_payload", prefix), DataType::String),
+    ])
+}
+
+/// Rows in descending key order with duplicates and NULL keys
+fn keyed_rows(count: i64, modulus: i64, offset: i64, payload: &str) -> Vec<Row> {
+    (0..count)
+        .rev()
+        .map(|i| {
+            let key = if i % 13 == 0 {
+                Value::Null
+            } else {
+                Value::Integer(i % modulus + offset)
+            };
+            Row::new(
+                i as u64 + 1,
+                vec![key, Value::String(format!("{}-{}", payload, i))],
+            )
+        })
+        .collect()
+}
+
+async fn dimension_table(rows: i64) -> (tempfile::TempDir, Executor) {
+    let dir = tempdir().unwrap();
+    let manager = Arc::new(Manager::new(dir.path().join("dim.db"), 64).await.unwrap());
+    let root_page_id = manager.allocate_page().await;
+    let root = Page {
+        page_id: root_page_id,
+        is_leaf: true,
+        parent_page_id: None,
+        keys: vec![],
+        values: vec![],
+        child_page_ids: vec![],
+        next_leaf_page_id: None,
+        is_dirty: true,
+    };
+    manager.write_page(&root).await.unwrap();
+    manager.register_leaf_page(root_page_id).await.unwrap();
+
+    let executor = Executor::new(manager, root_page_id, 2);
+    // Keys are spaced out so probes for odd keys miss
+    let data = (1..=rows)
+        .map(|i| {
+            Row::new(
+                (i * 2) as u64,
+                vec![Value::Integer(i * 2), Value::String(format!("dim-{}", i))],
+            )
+        })
+        .collect();
+    executor.insert_batch(data).await.unwrap();
+    (dir, executor)
+}
+
+#[tokio::test]
+async fn test_sort_merge_join_matches_hash_join() {
+    let dir = tempdir().unwrap();
+    let manager = Arc::new(Manager::new(dir.path().join("join.db"), 16).await.unwrap());
+    let left_schema = keyed_schema("l");
+    let right_schema = keyed_schema("r");
+    let left_rows = keyed_rows(600, 90, 0, "left");
+    let right_rows = keyed_rows(400, 90, 45, "right");
+    let residual = Expr::binary(
+        Expr::column("r_payload"),
+        BinaryOp::Lt,
+        Expr::literal(Value::String("right-3".to_string())),
+    );
+
+    for join_type in [
+        JoinType::Inner,
+        JoinType::LeftOuter,
+        JoinType::RightOuter,
+        JoinType::FullOuter,
+    ] {
+        let hashed = HashJoinOperation::new(
+            manager.clone(),
+            join_type.clone(),
+            condition("l_key", "r_key"),
+        )
+        .with_residual(residual.clone())
+        .execute(
+            left_rows.clone(),
+            right_rows.clone(),
+            &left_schema,
+            &right_schema,
+        )
+        .await
+        .unwrap();
+        let merged = SortMergeJoinOperation::new(join_type.clone(), condition("l_key", "r_key"))
+            .with_residual(residual.clone())
+            .execute(
+                left_rows.clone(),
+                right_rows.clone(),
+                &left_schema,
+                &right_schema,
+            )
+            .await
+            .unwrap();
+
+        assert!(hashed.output_rows > 0);
+        assert_eq!(merged.output_rows, hashed.output_rows, "{:?}", join_type);
+        assert_eq!(
+            sorted_rows(merged.rows),
+            sorted_rows(hashed.rows),
+            "{:?}",
+            join_type
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_sort_merge_join_on_presorted_inputs() {
+    let left_schema = keyed_schema("l");
+    let right_schema = keyed_schema("r");
+    let left_rows = vec![
+        Row::new(1, vec![Value::Integer(1), Value::String("a".to_string())]),
+        Row::new(2, vec![Value::Integer(2), Value::String("b".to_string())]),
+        Row::new(3, vec![Value::Integer(2), Value::String("c".to_string())]),
+    ];
+    let right_rows = vec![
+        Row::new(1, vec![Value::BigInt(2), Value::String("x".to_string())]),
+        Row::new(2, vec![Value::BigInt(2), Value::String("y".to_string())]),
+        Row::new(3, vec![Value::BigInt(3), Value::String("z".to_string())]),
+    ];
+
+    let result = SortMergeJoinOperation::new(JoinType::Inner, condition("l_key", "r_key"))
+        .with_sorted_inputs()
+        .execute(left_rows, right_rows, &left_schema, &right_schema)
+        .await
+        .unwrap();
+
+    // Both runs of key 2 join pairwise, and INTEGER 2 equals BIGINT 2
+    assert_eq!(result.output_rows, 4);
+    assert!(
+        result
+            .rows
+            .iter()
+            .all(|row| row.data[0] == Value::Integer(2))
+    );
+}
+
+#[tokio::test]
+async fn test_index_nested_loop_join_probes_tree() {
+    let (_dir, executor) = dimension_table(1000).await;
+    let root_page_id = *executor.root_page_id.lock().unwrap();
+    let outer_schema = keyed_schema("o");
+    let inner_schema = keyed_schema("d");
+    let outer_rows = vec![
+        Row::new(
+            1,
+            vec![Value::Integer(2), Value::String("first".to_string())],
+        ),
+        Row::new(2, vec![Value::Integer(3), Value::String("odd".to_string())]),
+        Row::new(3, vec![Value::Null, Value::String("null".to_string())]),
+        Row::new(
+            4,
+            vec![Value::BigInt(1998), Value::String("deep".to_string())],
+        ),
+        Row::new(
+            5,
+            vec![Value::Integer(-4), Value::String("negative".to_string())],
+        ),
+        Row::new(
+            6,
+            vec![Value::Integer(5000), Value::String("past".to_string())],
+        ),
+    ];
+
+    let inner = IndexNestedLoopJoinOperation::new(
+        executor.storage_manager.clone(),
+        root_page_id,
+        JoinType::Inner,
+        "o_key",
+    )
+    .execute(outer_rows.clone(), &outer_schema, &inner_schema)
+    .await
+    .unwrap();
+    assert_eq!(inner.output_rows, 2);
+    assert_eq!(inner.right_rows_processed, 2);
+    assert_eq!(
+        inner.rows[1].data,
+        vec![
+            Value::BigInt(1998),
+            Value::String("deep".to_string()),
+            Value::Integer(1998),
+            Value::String("dim-999".to_string()),
+        ]
+    );
+
+    let outer = IndexNestedLoopJoinOperation::new(
+        executor.storage_manager.clone(),
+        root_page_id,
+        JoinType::LeftOuter,
+        "o_key",
+    )
+    .with_residual(Expr::binary(
+        Expr::column("d_payload"),
+        BinaryOp::NotEq,
+        Expr::literal(Value::String("dim-1".to_string())),
+    ))
+    .execute(outer_rows, &outer_schema, &inner_schema)
+    .await
+    .unwrap();
+    // Every outer row survives; the key 2 match is rejected by the residual
+    assert_eq!(outer.output_rows, 6);
+    assert_eq!(outer.rows[0].data[2], Value::Null);
+    assert_eq!(outer.rows[3].data[3], Value::String("dim-999".to_string()));
+
+    let unsupported = IndexNestedLoopJoinOperation::new(
+        executor.storage_manager.clone(),
+        root_page_id,
+        JoinType::FullOuter,
+        "o_key",
+    )
+    .execute(Vec::new(), &outer_schema, &inner_schema)
+    .await;
+    assert!(unsupported.is_err());
+}
+
+#[test]
+fn test_choose_join_algorithm() {
+    let small = JoinInputProfile {
+        estimated_rows: 10,
+        ..Default::default()
+    };
+    let indexed = JoinInputProfile {
+        estimated_rows: 100_000,
+        indexed_on_key: true,
+        sorted_on_key: true,
+    };
+    let sorted = JoinInputProfile {
+        estimated_rows: 50_000,
+        sorted_on_key: true,
+        ..Default::default()
+    };
+    let unsorted = JoinInputProfile {
+        estimated_rows: 50_000,
+        ..Default::default()
+    };
+    let keys = condition("a", "b");
+
+    assert_eq!(
+        choose_join_algorithm(&JoinType::Inner, &keys, &small, &indexed),
+        JoinAlgorithm::IndexNestedLoop
+    );
+    // Unmatched inner rows can't be found by probing
+    assert_eq!(
+        choose_join_algorithm(&JoinType::FullOuter, &keys, &small, &indexed),
+        JoinAlgorithm::Hash
+    );
+    // Too many probes: merging the sorted inputs is cheaper
+    assert_eq!(
+        choose_join_algorithm(&JoinType::Inner, &keys, &sorted, &indexed),
+        JoinAlgorithm::SortMerge
+    );
+    assert_eq!(
+        choose_join_algorithm(&JoinType::LeftOuter, &keys, &unsorted, &sorted),
+        JoinAlgorithm::Hash
+    );
+    // Without an equality there is nothing to hash, sort or probe on
+    assert_eq!(
+        choose_join_algorithm(&JoinType::Inner, &[], &small, &indexed),
+        JoinAlgorithm::NestedLoop
+    );
+    assert_eq!(
+        choose_join_algorithm(&JoinType::LeftSemi, &keys, &small, &indexed),
+        JoinAlgorithm::IndexNestedLoop
+    );
+}
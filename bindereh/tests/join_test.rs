@@ -0,0 +1,747 @@
+mod common;
+
+use std::sync::Arc;
+
+use bindereh::{
+    manager::Manager,
+    operator::{
+        join::{HashJoinOperation, JoinCondition, JoinType},
+        merge_join::SortMergeJoinOperation,
+        nested_loop_join::NestedLoopJoinOperation,
+    },
+};
+use shared_types::{BinaryOp, Column, DataType, Expr, Row, Schema, StorageError, Value};
+use tempfile::tempdir;
+
+use common::sorted_rows;
+
+async fn test_manager() -> (tempfile::TempDir, Arc<Manager>) {
+    let dir = tempdir().unwrap();
+    let manager = Manager::new(dir.path().join("join.db"), 16).await.unwrap();
+    (dir, Arc::new(manager))
+}
+
+fn condition(left: &str, right: &str) -> Vec<JoinCondition> {
+    vec![JoinCondition {
+        left_column: left.to_string(),
+        right_column: right.to_string(),
+    }]
+}
+
+#[tokio::test]
+async fn test_join_on_mixed_integer_widths() {
+    let (_dir, manager) = test_manager().await;
+    let left_schema = Schema::new(vec![Column::not_null(
+        "l_key".to_string(),
+        DataType::Integer,
+    )]);
+    let right_schema = Schema::new(vec![Column::not_null(
+        "r_key".to_string(),
+        DataType::BigInt,
+    )]);
+
+    let left_rows = vec![
+        Row::new(1, vec![Value::Integer(1)]),
+        Row::new(2, vec![Value::Integer(2)]),
+    ];
+    let right_rows = vec![
+        Row::new(1, vec![Value::BigInt(1)]),
+        Row::new(2, vec![Value::BigInt(3)]),
+    ];
+
+    let result = HashJoinOperation::new(manager, JoinType::Inner, condition("l_key", "r_key"))
+        .execute(left_rows, right_rows, &left_schema, &right_schema)
+        .await
+        .unwrap();
+
+    assert_eq!(result.output_rows, 1);
+    assert_eq!(
+        result.rows[0].data,
+        vec![Value::Integer(1), Value::BigInt(1)]
+    );
+}
+
+#[tokio::test]
+async fn test_full_outer_join_tracks_matched_rows() {
+    let (_dir, manager) = test_manager().await;
+    let left_schema = Schema::new(vec![Column::not_null(
+        "l_key".to_string(),
+        DataType::Integer,
+    )]);
+    let right_schema = Schema::new(vec![Column::not_null("r_key".to_string(), DataType::Float)]);
+
+    let left_rows = vec![
+        Row::new(1, vec![Value::Integer(1)]),
+        Row::new(2, vec![Value::Integer(2)]),
+    ];
+    let right_rows = vec![
+        Row::new(1, vec![Value::Float(2.0)]),
+        Row::new(2, vec![Value::Float(4.0)]),
+    ];
+
+    let result = HashJoinOperation::new(manager, JoinType::FullOuter, condition("l_key", "r_key"))
+        .execute(left_rows, right_rows, &left_schema, &right_schema)
+        .await
+        .unwrap();
+
+    assert_eq!(result.output_rows, 3);
+    assert!(
+        result
+            .rows
+            .contains(&Row::new(1, vec![Value::Integer(1), Value::Null]))
+    );
+    assert!(
+        result
+            .rows
+            .contains(&Row::new(2, vec![Value::Integer(2), Value::Float(2.0)]))
+    );
+    assert!(
+        result
+            .rows
+            .contains(&Row::new(0, vec![Value::Null, Value::Float(4.0)]))
+    );
+}
+
+#[tokio::test]
+async fn test_null_join_keys_never_match() {
+    let (_dir, manager) = test_manager().await;
+    let left_schema = Schema::new(vec![Column::nullable(
+        "l_key".to_string(),
+        DataType::Integer,
+    )]);
+    let right_schema = Schema::new(vec![Column::nullable(
+        "r_key".to_string(),
+        DataType::Integer,
+    )]);
+
+    let left_rows = vec![Row::new(1, vec![Value::Null])];
+    let right_rows = vec![Row::new(1, vec![Value::Null])];
+
+    let result = HashJoinOperation::new(manager, JoinType::LeftOuter, condition("l_key", "r_key"))
+        .execute(left_rows, right_rows, &left_schema, &right_schema)
+        .await
+        .unwrap();
+
+    assert_eq!(result.output_rows, 1);
+    assert_eq!(result.rows[0].data, vec![Value::Null, Value::Null]);
+}
+
+fn skewed_rows(count: i64, modulus: i64, payload: &str) -> Vec<Row> {
+    (0..count)
+        .map(|i| {
+            let key = if i % 17 == 0 {
+                Value::Null
+            } else {
+                Value::Integer(i % modulus)
+            };
+            Row::new(
+                i as u64 + 1,
+                vec![key, Value::String(format!("{}-{}", payload, i))],
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_grace_hash_join_matches_in_memory_join() {
+    let (dir, manager) = test_manager().await;
+    let left_schema = Schema::new(vec![
+        Column::nullable("l_key".to_string(), DataType::Integer),
+        Column::nullable("l_payload".to_string(), DataType::String),
+    ]);
+    let right_schema = Schema::new(vec![
+        Column::nullable("r_key".to_string(), DataType::Integer),
+        Column::nullable("r_payload".to_string(), DataType::String),
+    ]);
+    // Keys 0..300 on the left and 150..450 on the right leave unmatched rows on both sides
+    let left_rows = skewed_rows(900, 300, "left");
+    let right_rows: Vec<Row> = skewed_rows(600, 300, "right")
+        .into_iter()
+        .map(|mut row| {
+            if let Value::Integer(key) = row.data[0] {
+                row.data[0] = Value::Integer(key + 150);
+            }
+            row
+        })
+        .collect();
+
+    for join_type in [
+        JoinType::Inner,
+        JoinType::LeftOuter,
+        JoinType::RightOuter,
+        JoinType::FullOuter,
+        JoinType::LeftSemi,
+        JoinType::LeftAnti,
+    ] {
+        let in_memory = HashJoinOperation::new(
+            manager.clone(),
+            join_type.clone(),
+            condition("l_key", "r_key"),
+        )
+        .execute(
+            left_rows.clone(),
+            right_rows.clone(),
+            &left_schema,
+            &right_schema,
+        )
+        .await
+        .unwrap();
+
+        let spilled = HashJoinOperation::new(
+            manager.clone(),
+            join_type.clone(),
+            condition("l_key", "r_key"),
+        )
+        .with_memory_budget(1024)
+        .with_spill_dir(dir.path())
+        .execute(
+            left_rows.clone(),
+            right_rows.clone(),
+            &left_schema,
+            &right_schema,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(in_memory.spilled_rows, 0);
+        assert!(spilled.spilled_rows > 0, "{:?} did not spill", join_type);
+        assert_eq!(
+            spilled.output_rows, in_memory.output_rows,
+            "{:?}",
+            join_type
+        );
+        assert_eq!(
+            sorted_rows(spilled.rows),
+            sorted_rows(in_memory.rows),
+            "{:?}",
+            join_type
+        );
+    }
+
+    // Only the database files remain; every spill file has been cleaned up
+    let leftover = std::fs::read_dir(dir.path())
+        .unwrap()
+        .filter(|entry| {
+            let name = entry.as_ref().unwrap().file_name();
+            name.to_string_lossy().contains("spill")
+        })
+        .count();
+    assert_eq!(leftover, 0);
+}
+
+#[tokio::test]
+async fn test_streamed_inputs_are_partitioned_as_they_are_read() {
+    let (dir, manager) = test_manager().await;
+    let left_schema = Schema::new(vec![
+        Column::nullable("l_key".to_string(), DataType::Integer),
+        Column::nullable("l_payload".to_string(), DataType::String),
+    ]);
+    let right_schema = Schema::new(vec![
+        Column::nullable("r_key".to_string(), DataType::Integer),
+        Column::nullable("r_payload".to_string(), DataType::String),
+    ]);
+    let left_rows = skewed_rows(600, 200, "left");
+    let right_rows = skewed_rows(400, 100, "right");
+
+    for join_type in [JoinType::Inner, JoinType::RightOuter, JoinType::FullOuter] {
+        let operation = HashJoinOperation::new(
+            manager.clone(),
+            join_type.clone(),
+            condition("l_key", "r_key"),
+        )
+        .with_memory_budget(1024)
+        .with_spill_dir(dir.path());
+        let in_memory = operation
+            .clone()
+            .with_memory_budget(usize::MAX)
+            .execute(
+                left_rows.clone(),
+                right_rows.clone(),
+                &left_schema,
+                &right_schema,
+            )
+            .await
+            .unwrap();
+        let streamed = operation
+            .execute_streams(
+                left_rows.clone().into_iter().map(Ok),
+                right_rows.clone().into_iter().map(Ok),
+                &left_schema,
+                &right_schema,
+            )
+            .await
+            .unwrap()
+            .into_result();
+
+        // Both inputs went to disk in full, and some partitions again when split further
+        assert!(streamed.spilled_rows >= 1000, "{:?}", join_type);
+        assert_eq!(streamed.left_rows_processed, 600);
+        assert_eq!(streamed.right_rows_processed, 400);
+        assert_eq!(
+            sorted_rows(streamed.rows),
+            sorted_rows(in_memory.rows),
+            "{:?}",
+            join_type
+        );
+    }
+
+    // A failing input stops the join with its error
+    let failing =
+        right_rows
+            .into_iter()
+            .map(Ok)
+            .chain(std::iter::once(Err(StorageError::InvalidOperation(
+                "scan failed".into(),
+            ))));
+    let result = HashJoinOperation::new(
+        manager.clone(),
+        JoinType::Inner,
+        condition("l_key", "r_key"),
+    )
+    .with_memory_budget(1024)
+    .with_spill_dir(dir.path())
+    .execute_streams(
+        left_rows.into_iter().map(Ok),
+        failing,
+        &left_schema,
+        &right_schema,
+    )
+    .await;
+    assert!(
+        matches!(result, Err(StorageError::InvalidOperation(message)) if message == "scan failed")
+    );
+}
+
+#[tokio::test]
+async fn test_grace_hash_join_applies_residual_per_partition() {
+    let (dir, manager) = test_manager().await;
+    let left_schema = Schema::new(vec![
+        Column::nullable("l_key".to_string(), DataType::Integer),
+        Column::nullable("l_payload".to_string(), DataType::String),
+    ]);
+    let right_schema = Schema::new(vec![
+        Column::nullable("r_key".to_string(), DataType::Integer),
+        Column::nullable("r_payload".to_string(), DataType::String),
+    ]);
+    let left_rows = skewed_rows(400, 50, "left");
+    let right_rows = skewed_rows(400, 50, "right");
+    let residual = Expr::binary(
+        Expr::column("r_payload"),
+        BinaryOp::Lt,
+        Expr::literal(Value::String("right-2".to_string())),
+    );
+
+    let run = |budget: usize| {
+        HashJoinOperation::new(
+            manager.clone(),
+            JoinType::LeftOuter,
+            condition("l_key", "r_key"),
+        )
+        .with_residual(residual.clone())
+        .with_memory_budget(budget)
+        .with_spill_dir(dir.path())
+    };
+    let in_memory = run(usize::MAX)
+        .execute(
+            left_rows.clone(),
+            right_rows.clone(),
+            &left_schema,
+            &right_schema,
+        )
+        .await
+        .unwrap();
+    let spilled = run(512)
+        .execute(left_rows, right_rows, &left_schema, &right_schema)
+        .await
+        .unwrap();
+
+    assert!(spilled.spilled_rows > 0);
+    assert_eq!(sorted_rows(spilled.rows), sorted_rows(in_memory.rows));
+}
+
+#[tokio::test]
+async fn test_parallel_join_matches_single_worker() {
+    let (_dir, manager) = test_manager().await;
+    let left_schema = Schema::new(vec![
+        Column::nullable("l_key".to_string(), DataType::Integer),
+        Column::nullable("l_payload".to_string(), DataType::String),
+    ]);
+    let right_schema = Schema::new(vec![
+        Column::nullable("r_key".to_string(), DataType::Integer),
+        Column::nullable("r_payload".to_string(), DataType::String),
+    ]);
+    let left_rows = skewed_rows(5000, 700, "left");
+    let right_rows = skewed_rows(1200, 900, "right");
+
+    for join_type in [
+        JoinType::Inner,
+        JoinType::LeftOuter,
+        JoinType::RightOuter,
+        JoinType::FullOuter,
+    ] {
+        let run = |workers: usize| {
+            HashJoinOperation::new(
+                manager.clone(),
+                join_type.clone(),
+                condition("l_key", "r_key"),
+            )
+            .with_max_workers(workers)
+            .with_batch_size(128)
+        };
+        let single = run(1)
+            .execute(
+                left_rows.clone(),
+                right_rows.clone(),
+                &left_schema,
+                &right_schema,
+            )
+            .await
+            .unwrap();
+        let parallel = run(4)
+            .execute(
+                left_rows.clone(),
+                right_rows.clone(),
+                &left_schema,
+                &right_schema,
+            )
+            .await
+            .unwrap();
+
+        assert!(single.output_rows > 0);
+        // Morsel batches are concatenated in probe order, so the output is deterministic
+        assert_eq!(parallel.rows, single.rows, "{:?}", join_type);
+    }
+}
+
+#[tokio::test]
+async fn test_hash_join_returns_probe_batches() {
+    let (_dir, manager) = test_manager().await;
+    let left_schema = int_schema("l_key");
+    let right_schema = int_schema("r_key");
+    let left_rows: Vec<Row> = (0..1000)
+        .map(|i| Row::new(i as u64, vec![Value::Integer(i)]))
+        .collect();
+    let right_rows: Vec<Row> = (0..1000)
+        .step_by(2)
+        .chain([5000, 5001])
+        .map(|i| Row::new(i as u64, vec![Value::Integer(i)]))
+        .collect();
+
+    let join = |join_type: JoinType| {
+        HashJoinOperation::new(manager.clone(), join_type, condition("l_key", "r_key"))
+            .with_batch_size(300)
+    };
+
+    let inner = join(JoinType::Inner)
+        .execute_batches(
+            left_rows.clone(),
+            right_rows.clone(),
+            &left_schema,
+            &right_schema,
+        )
+        .await
+        .unwrap();
+    // One batch per morsel of 300 probe rows
+    let sizes: Vec<usize> = inner.batches.iter().map(Vec::len).collect();
+    assert_eq!(sizes, vec![150, 150, 150, 50]);
+    assert_eq!(inner.output_rows, 500);
+
+    let full = join(JoinType::FullOuter)
+        .execute_batches(
+            left_rows.clone(),
+            right_rows.clone(),
+            &left_schema,
+            &right_schema,
+        )
+        .await
+        .unwrap();
+    assert_eq!(full.batches.len(), 5);
+    // The unmatched build rows come last, in a batch of their own
+    let unmatched: Vec<Value> = full.batches[4]
+        .iter()
+        .map(|row| row.data[1].clone())
+        .collect();
+    assert_eq!(unmatched, vec![Value::Integer(5000), Value::Integer(5001)]);
+
+    let flattened = join(JoinType::FullOuter)
+        .execute(left_rows, right_rows, &left_schema, &right_schema)
+        .await
+        .unwrap();
+    assert_eq!(flattened.output_rows, 1002);
+    assert_eq!(flattened.rows, full.into_result().rows);
+}
+
+fn int_schema(name: &str) -> Schema {
+    Schema::new(vec![Column::nullable(name.to_string(), DataType::Integer)])
+}
+
+fn int_rows(values: &[Option<i64>]) -> Vec<Row> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let value = value.map_or(Value::Null, Value::Integer);
+            Row::new(i as u64 + 1, vec![value])
+        })
+        .collect()
+}
+
+fn left_keys(rows: &[Row]) -> Vec<Value> {
+    let mut keys: Vec<Value> = rows.iter().map(|row| row.data[0].clone()).collect();
+    keys.sort();
+    keys
+}
+
+async fn hash_join_keys(
+    manager: &Arc<Manager>,
+    join_type: JoinType,
+    left_rows: &[Row],
+    right_rows: &[Row],
+) -> Vec<Value> {
+    let result = HashJoinOperation::new(manager.clone(), join_type, condition("l_key", "r_key"))
+        .execute(
+            left_rows.to_vec(),
+            right_rows.to_vec(),
+            &int_schema("l_key"),
+            &int_schema("r_key"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.result_schema.column_count(), 1);
+    left_keys(&result.rows)
+}
+
+#[tokio::test]
+async fn test_semi_and_anti_joins_return_left_rows_once() {
+    let (_dir, manager) = test_manager().await;
+    let left_rows = int_rows(&[Some(1), Some(2), Some(3), None]);
+    let right_rows = int_rows(&[Some(2), Some(2), Some(3), Some(3), Some(4), None]);
+
+    let semi = hash_join_keys(&manager, JoinType::LeftSemi, &left_rows, &right_rows).await;
+    assert_eq!(semi, vec![Value::Integer(2), Value::Integer(3)]);
+
+    // NOT EXISTS keeps rows whose key is NULL: they have no partner
+    let anti = hash_join_keys(&manager, JoinType::LeftAnti, &left_rows, &right_rows).await;
+    assert_eq!(anti, vec![Value::Null, Value::Integer(1)]);
+
+    let merged = SortMergeJoinOperation::new(JoinType::LeftAnti, condition("l_key", "r_key"))
+        .execute(
+            left_rows,
+            right_rows,
+            &int_schema("l_key"),
+            &int_schema("r_key"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(left_keys(&merged.rows), anti);
+}
+
+#[tokio::test]
+async fn test_null_aware_anti_join_follows_not_in() {
+    let (_dir, manager) = test_manager().await;
+    let left_rows = int_rows(&[Some(1), Some(2), None]);
+    let not_in = |right_rows: Vec<Row>| {
+        let manager = manager.clone();
+        let left_rows = left_rows.clone();
+        async move {
+            let hashed = hash_join_keys(
+                &manager,
+                JoinType::NullAwareLeftAnti,
+                &left_rows,
+                &right_rows,
+            )
+            .await;
+            let looped = NestedLoopJoinOperation::new(JoinType::NullAwareLeftAnti)
+                .with_condition(Expr::binary(
+                    Expr::column("l_key"),
+                    BinaryOp::Eq,
+                    Expr::column("r_key"),
+                ))
+                .execute(
+                    left_rows,
+                    right_rows,
+                    &int_schema("l_key"),
+                    &int_schema("r_key"),
+                )
+                .await
+                .unwrap();
+            assert_eq!(left_keys(&looped.rows), hashed);
+            hashed
+        }
+    };
+
+    // x NOT IN (2): only 1 survives, NULL NOT IN (...) is UNKNOWN
+    assert_eq!(not_in(int_rows(&[Some(2)])).await, vec![Value::Integer(1)]);
+    // A NULL in the subquery makes every comparison that isn't a match UNKNOWN
+    assert!(not_in(int_rows(&[Some(2), None])).await.is_empty());
+    // Nothing is IN an empty set, not even NULL
+    assert_eq!(
+        not_in(Vec::new()).await,
+        vec![Value::Null, Value::Integer(1), Value::Integer(2)]
+    );
+}
+
+#[tokio::test]
+async fn test_null_aware_anti_join_over_budget_is_an_error() {
+    let (_dir, manager) = test_manager().await;
+    let left_rows = int_rows(&[Some(1), Some(2), None]);
+    let right_rows = int_rows(&(0..200).map(Some).collect::<Vec<_>>());
+    let not_in = |budget: usize| {
+        HashJoinOperation::new(
+            manager.clone(),
+            JoinType::NullAwareLeftAnti,
+            condition("l_key", "r_key"),
+        )
+        .with_memory_budget(budget)
+    };
+
+    let result = not_in(256)
+        .execute(
+            left_rows.clone(),
+            right_rows.clone(),
+            &int_schema("l_key"),
+            &int_schema("r_key"),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(StorageError::InvalidOperation(message)) if message.contains("memory budget")
+    ));
+
+    let result = not_in(usize::MAX)
+        .execute(
+            left_rows,
+            right_rows,
+            &int_schema("l_key"),
+            &int_schema("r_key"),
+        )
+        .await
+        .unwrap();
+    assert!(result.rows.is_empty());
+}
+
+#[tokio::test]
+async fn test_cross_join_pairs_every_row() {
+    let (_dir, manager) = test_manager().await;
+    let (left_schema, right_schema) = (int_schema("l_key"), int_schema("r_key"));
+    let left_rows = int_rows(&[Some(1), Some(2), Some(3)]);
+    let right_rows = int_rows(&[Some(10), None]);
+
+    let result = NestedLoopJoinOperation::new(JoinType::Cross)
+        .with_batch_size(2)
+        .execute(
+            left_rows.clone(),
+            right_rows.clone(),
+            &left_schema,
+            &right_schema,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.output_rows, 6);
+    assert_eq!(result.result_schema.column_count(), 2);
+
+    let hashed = HashJoinOperation::new(manager, JoinType::Cross, Vec::new())
+        .execute(left_rows, right_rows, &left_schema, &right_schema)
+        .await;
+    assert!(hashed.is_err());
+}
+
+#[tokio::test]
+async fn test_band_join_on_between_condition() {
+    let events_schema = Schema::new(vec![
+        Column::not_null("event_id".to_string(), DataType::Integer),
+        Column::nullable("ts".to_string(), DataType::Integer),
+    ]);
+    let windows_schema = Schema::new(vec![
+        Column::not_null("window_id".to_string(), DataType::Integer),
+        Column::not_null("start_ts".to_string(), DataType::Integer),
+        Column::not_null("end_ts".to_string(), DataType::Integer),
+    ]);
+    let events: Vec<Row> = (0..50)
+        .map(|i| {
+            let ts = if i == 7 {
+                Value::Null
+            } else {
+                Value::Integer(i * 3)
+            };
+            Row::new(i as u64 + 1, vec![Value::Integer(i), ts])
+        })
+        .collect();
+    let windows: Vec<Row> = (0..6)
+        .map(|w| {
+            Row::new(
+                w as u64 + 1,
+                vec![
+                    Value::Integer(w),
+                    Value::Integer(w * 40),
+                    Value::Integer(w * 40 + 20),
+                ],
+            )
+        })
+        .collect();
+    let between = Expr::between(
+        Expr::column("ts"),
+        Expr::column("start_ts"),
+        Expr::column("end_ts"),
+        false,
+    );
+
+    for join_type in [JoinType::Inner, JoinType::LeftOuter, JoinType::RightOuter] {
+        let result = NestedLoopJoinOperation::new(join_type.clone())
+            .with_condition(between.clone())
+            .with_max_workers(3)
+            .with_batch_size(8)
+            .execute(
+                events.clone(),
+                windows.clone(),
+                &events_schema,
+                &windows_schema,
+            )
+            .await
+            .unwrap();
+
+        let mut expected = Vec::new();
+        let mut matched_windows = vec![false; windows.len()];
+        for event in &events {
+            let mut matched = false;
+            if let Value::Integer(ts) = event.data[1] {
+                for (w, window) in windows.iter().enumerate() {
+                    let (Value::Integer(start), Value::Integer(end)) =
+                        (&window.data[1], &window.data[2])
+                    else {
+                        unreachable!()
+                    };
+                    if *start <= ts && ts <= *end {
+                        matched = true;
+                        matched_windows[w] = true;
+                        let mut data = event.data.clone();
+                        data.extend(window.data.clone());
+                        expected.push(Row::new(event.id, data));
+                    }
+                }
+            }
+            if !matched && matches!(join_type, JoinType::LeftOuter) {
+                let mut data = event.data.clone();
+                data.extend(vec![Value::Null; 3]);
+                expected.push(Row::new(event.id, data));
+            }
+        }
+        if matches!(join_type, JoinType::RightOuter) {
+            for (window, matched) in windows.iter().zip(matched_windows) {
+                if !matched {
+                    let mut data = vec![Value::Null; 2];
+                    data.extend(window.data.clone());
+                    expected.push(Row::new(0, data));
+                }
+            }
+        }
+
+        assert!(result.output_rows > 0);
+        assert_eq!(
+            sorted_rows(result.rows),
+            sorted_rows(expected),
+            "{:?}",
+            join_type
+        );
+    }
+}
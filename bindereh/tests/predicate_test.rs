@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use bindereh::operator::{
+    compare::{evaluate_predicate_fast, evaluate_predicate_tristate},
+    expression::PhysicalExpr,
+};
+use shared_types::{BinaryOp, Column, DataType, Expr, Predicate, Row, Schema, Value};
+
+fn indices() -> HashMap<String, usize> {
+    HashMap::from([("x".to_string(), 0)])
+}
+
+fn row(value: Value) -> Row {
+    Row::new(1, vec![value])
+}
+
+#[test]
+fn test_comparison_with_null_is_unknown() {
+    let null_row = row(Value::Null);
+    let equals_null = Predicate::column_equals("x".to_string(), Value::Null);
+    assert_eq!(
+        evaluate_predicate_tristate(&equals_null, &null_row, &indices()),
+        None
+    );
+    assert!(!evaluate_predicate_fast(
+        &equals_null,
+        &null_row,
+        &indices()
+    ));
+
+    let gt = Predicate::column_gt("x".to_string(), Value::Integer(5));
+    let not_gt = Predicate::not(gt.clone());
+    assert_eq!(
+        evaluate_predicate_tristate(&not_gt, &null_row, &indices()),
+        None
+    );
+    assert!(!evaluate_predicate_fast(&not_gt, &null_row, &indices()));
+    assert!(evaluate_predicate_fast(
+        &not_gt,
+        &row(Value::Integer(3)),
+        &indices()
+    ));
+}
+
+#[test]
+fn test_kleene_and_or() {
+    let null_row = row(Value::Null);
+    let unknown = Predicate::column_gt("x".to_string(), Value::Integer(5));
+    let is_null = Predicate::column_is_null("x".to_string());
+    let not_null = Predicate::ColumnIsNotNull {
+        column: "x".to_string(),
+    };
+
+    let or = Predicate::or(unknown.clone(), is_null);
+    assert_eq!(
+        evaluate_predicate_tristate(&or, &null_row, &indices()),
+        Some(true)
+    );
+
+    let and = Predicate::and(unknown.clone(), not_null);
+    assert_eq!(
+        evaluate_predicate_tristate(&and, &null_row, &indices()),
+        Some(false)
+    );
+
+    let and_unknown = Predicate::and(unknown.clone(), Predicate::column_is_null("x".to_string()));
+    assert_eq!(
+        evaluate_predicate_tristate(&and_unknown, &null_row, &indices()),
+        None
+    );
+}
+
+#[test]
+fn test_not_in_with_nulls() {
+    let not_in = Predicate::ColumnNotIn {
+        column: "x".to_string(),
+        values: vec![Value::Integer(1), Value::Null],
+    };
+    // 2 NOT IN (1, NULL) is UNKNOWN, 1 NOT IN (1, NULL) is FALSE
+    assert_eq!(
+        evaluate_predicate_tristate(&not_in, &row(Value::Integer(2)), &indices()),
+        None
+    );
+    assert_eq!(
+        evaluate_predicate_tristate(&not_in, &row(Value::Integer(1)), &indices()),
+        Some(false)
+    );
+
+    let in_list = Predicate::column_in("x".to_string(), vec![Value::Integer(1), Value::Null]);
+    assert_eq!(
+        evaluate_predicate_tristate(&in_list, &row(Value::Integer(1)), &indices()),
+        Some(true)
+    );
+    assert_eq!(
+        evaluate_predicate_tristate(&in_list, &row(Value::Integer(2)), &indices()),
+        None
+    );
+}
+
+#[test]
+fn test_is_distinct_from() {
+    let distinct = Predicate::column_is_distinct_from("x".to_string(), Value::Null);
+    assert!(!evaluate_predicate_fast(
+        &distinct,
+        &row(Value::Null),
+        &indices()
+    ));
+    assert!(evaluate_predicate_fast(
+        &distinct,
+        &row(Value::Integer(1)),
+        &indices()
+    ));
+
+    let schema = Schema::new(vec![Column::nullable("x".to_string(), DataType::Integer)]);
+    let not_distinct = PhysicalExpr::compile(
+        &Expr::binary(
+            Expr::column("x"),
+            BinaryOp::IsNotDistinctFrom,
+            Expr::literal(Value::Null),
+        ),
+        &schema,
+    )
+    .unwrap();
+    assert_eq!(
+        not_distinct.evaluate(&row(Value::Null)).unwrap(),
+        Value::Boolean(true)
+    );
+    assert_eq!(
+        not_distinct.evaluate(&row(Value::Integer(1))).unwrap(),
+        Value::Boolean(false)
+    );
+
+    let equals = PhysicalExpr::compile(
+        &Expr::equals(Expr::column("x"), Expr::literal(Value::Null)),
+        &schema,
+    )
+    .unwrap();
+    assert_eq!(equals.evaluate(&row(Value::Null)).unwrap(), Value::Null);
+    assert!(!equals.evaluate_predicate(&row(Value::Null)).unwrap());
+}
+
+#[test]
+fn test_column_like_matches_like_expressions() {
+    let schema = Schema::new(vec![Column::nullable("x".to_string(), DataType::String)]);
+    let values = ["a.c", "abc", "(a+)", "aa+)", "100%", "1000", "a_c", "a\nc"];
+    let patterns = ["a.c", "a_c", "(a+)", "%+)", "100\\%", "100%", "a\\_c", "A%", "a%"];
+
+    for pattern in patterns {
+        let predicate = Predicate::ColumnLike {
+            column: "x".to_string(),
+            pattern: pattern.to_string(),
+        };
+        let like = PhysicalExpr::compile(
+            &Expr::Like {
+                expr: Box::new(Expr::column("x")),
+                pattern: Box::new(Expr::literal(Value::String(pattern.to_string()))),
+                negated: false,
+                case_insensitive: false,
+            },
+            &schema,
+        )
+        .unwrap();
+        for value in values {
+            let value_row = row(Value::String(value.to_string()));
+            assert_eq!(
+                evaluate_predicate_fast(&predicate, &value_row, &indices()),
+                like.evaluate_predicate(&value_row).unwrap(),
+                "{} LIKE {}",
+                value,
+                pattern
+            );
+        }
+    }
+
+    let dot = Predicate::ColumnLike {
+        column: "x".to_string(),
+        pattern: "a.c".to_string(),
+    };
+    assert!(!evaluate_predicate_fast(
+        &dot,
+        &row(Value::String("abc".to_string())),
+        &indices()
+    ));
+    assert_eq!(
+        evaluate_predicate_tristate(&dot, &row(Value::Null), &indices()),
+        None
+    );
+}
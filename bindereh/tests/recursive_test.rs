@@ -0,0 +1,140 @@
+use bindereh::operator::join::JoinType;
+use bindereh::operator::nested_loop_join::NestedLoopJoinOperation;
+use bindereh::operator::recursive::RecursiveUnionOperation;
+use shared_types::{Column, DataType, Expr, Row, Schema, StorageError, Value};
+
+fn employee_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::nullable("manager".to_string(), DataType::Integer),
+    ])
+}
+
+fn chain_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("boss".to_string(), DataType::Integer),
+        Column::nullable("depth".to_string(), DataType::Integer),
+    ])
+}
+
+fn employee(id: i64, manager: Option<i64>) -> Row {
+    Row::new(
+        id as u64,
+        vec![
+            Value::Integer(id),
+            manager.map_or(Value::Null, Value::Integer),
+        ],
+    )
+}
+
+/// 1 manages 2 and 3, 3 manages 4, 4 manages 5
+fn org_chart() -> Vec<Row> {
+    vec![
+        employee(1, None),
+        employee(2, Some(1)),
+        employee(3, Some(1)),
+        employee(4, Some(3)),
+        employee(5, Some(4)),
+    ]
+}
+
+fn pair(a: i64, b: i64) -> Row {
+    Row::new(a as u64, vec![Value::Integer(a), Value::Integer(b)])
+}
+
+fn pairs(rows: &[Row]) -> Vec<(i64, i64)> {
+    let mut pairs: Vec<(i64, i64)> = rows
+        .iter()
+        .map(|row| match (&row.data[0], &row.data[1]) {
+            (Value::Integer(a), Value::Integer(b)) => (*a, *b),
+            other => panic!("unexpected row {:?}", other),
+        })
+        .collect();
+    pairs.sort();
+    pairs
+}
+
+/// `SELECT e.id, c.depth + 1 FROM employees e JOIN chain c ON e.manager = c.boss`
+async fn next_level(employees: &[Row], chain: &[Row]) -> Result<Vec<Row>, StorageError> {
+    let joined = NestedLoopJoinOperation::new(JoinType::Inner)
+        .with_condition(Expr::equals(Expr::column("manager"), Expr::column("boss")))
+        .execute(
+            employees.to_vec(),
+            chain.to_vec(),
+            &employee_schema(),
+            &chain_schema(),
+        )
+        .await?;
+    Ok(joined
+        .rows
+        .into_iter()
+        .map(|row| match (&row.data[0], &row.data[3]) {
+            (Value::Integer(id), Value::Integer(depth)) => pair(*id, depth + 1),
+            other => panic!("unexpected row {:?}", other),
+        })
+        .collect())
+}
+
+#[tokio::test]
+async fn test_org_chart_depths() {
+    let employees = org_chart();
+    let result = RecursiveUnionOperation::new(true)
+        .execute(vec![pair(1, 0)], async |chain: &[Row]| {
+            next_level(&employees, chain).await
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        pairs(&result.rows),
+        vec![(1, 0), (2, 1), (3, 1), (4, 2), (5, 3)]
+    );
+    // Three levels below the root, and a fourth run that finds nobody
+    assert_eq!(result.iterations, 4);
+}
+
+/// Reachability over `edges` from node 1, where 3 links back to 1
+async fn reachable(all: bool, max_iterations: usize) -> Result<Vec<Row>, StorageError> {
+    let edges = [(1, 2), (2, 3), (3, 1), (3, 4)];
+    RecursiveUnionOperation::new(all)
+        .with_max_iterations(max_iterations)
+        .execute(vec![pair(1, 1)], async |frontier: &[Row]| {
+            Ok(frontier
+                .iter()
+                .flat_map(|row| {
+                    edges
+                        .iter()
+                        .filter(move |(from, _)| row.data[1] == Value::Integer(*from))
+                        .map(|(_, to)| pair(1, *to))
+                })
+                .collect())
+        })
+        .await
+        .map(|result| result.rows)
+}
+
+#[tokio::test]
+async fn test_union_reaches_fixpoint_on_cycles() {
+    let rows = reachable(false, 100).await.unwrap();
+    assert_eq!(pairs(&rows), vec![(1, 1), (1, 2), (1, 3), (1, 4)]);
+
+    // UNION ALL keeps going around the cycle until the iteration limit
+    let error = reachable(true, 100).await.unwrap_err();
+    assert!(
+        matches!(error, StorageError::InvalidOperation(message) if message.contains("100 iterations"))
+    );
+}
+
+#[tokio::test]
+async fn test_union_drops_duplicate_seed_rows() {
+    let result = RecursiveUnionOperation::new(false)
+        .execute(
+            vec![pair(1, 1), pair(1, 1), pair(2, 2)],
+            async |_: &[Row]| Ok(vec![pair(2, 2)]),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(pairs(&result.rows), vec![(1, 1), (2, 2)]);
+    assert_eq!(result.iterations, 1);
+}
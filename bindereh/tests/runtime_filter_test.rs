@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use bindereh::{
+    executor::Executor,
+    manager::Manager,
+    operator::join::{HashJoinOperation, JoinCondition, JoinType},
+    page::Page,
+};
+use shared_types::{BloomFilter, Column, DataType, Row, RuntimeFilter, ScanOptions, Schema, Value};
+use tempfile::tempdir;
+
+fn fact_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::not_null("date_key".to_string(), DataType::Integer),
+    ])
+}
+
+fn dim_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("d_key".to_string(), DataType::BigInt),
+        Column::not_null("d_year".to_string(), DataType::Integer),
+    ])
+}
+
+async fn fact_executor(rows: i64) -> (tempfile::TempDir, Executor) {
+    let dir = tempdir().unwrap();
+    let manager = Arc::new(Manager::new(dir.path().join("fact.db"), 128).await.unwrap());
+    let root_page_id = manager.allocate_page().await;
+    let root = Page {
+        page_id: root_page_id,
+        is_leaf: true,
+        parent_page_id: None,
+        keys: vec![],
+        values: vec![],
+        child_page_ids: vec![],
+        next_leaf_page_id: None,
+        is_dirty: true,
+    };
+    manager.write_page(&root).await.unwrap();
+    manager.register_leaf_page(root_page_id).await.unwrap();
+
+    let executor = Executor::new(manager, root_page_id, 4);
+    let data = (1..=rows)
+        .map(|i| Row::new(i as u64, vec![Value::Integer(i), Value::Integer(i % 700)]))
+        .collect();
+    executor.insert_batch(data).await.unwrap();
+    (dir, executor)
+}
+
+fn condition() -> Vec<JoinCondition> {
+    vec![JoinCondition {
+        left_column: "date_key".to_string(),
+        right_column: "d_key".to_string(),
+    }]
+}
+
+#[test]
+fn test_bloom_filter_has_no_false_negatives() {
+    let mut bloom = BloomFilter::with_capacity(5000);
+    for i in 0..5000i64 {
+        bloom.insert(&Value::Integer(i * 3));
+    }
+    for i in 0..5000i64 {
+        assert!(bloom.might_contain(&Value::Integer(i * 3)));
+        // Hashing follows value equality across integer widths
+        assert!(bloom.might_contain(&Value::BigInt(i as i128 * 3)));
+    }
+
+    let false_positives = (0..5000i64)
+        .filter(|i| bloom.might_contain(&Value::Integer(i * 3 + 1)))
+        .count();
+    assert!(false_positives < 250, "{} false positives", false_positives);
+}
+
+#[test]
+fn test_runtime_filter_range_and_nulls() {
+    let keys = [
+        Value::Integer(10),
+        Value::Null,
+        Value::Integer(20),
+        Value::Integer(15),
+    ];
+    let filter = RuntimeFilter::from_values("k", keys.iter());
+    assert_eq!(filter.min, Some(Value::Integer(10)));
+    assert_eq!(filter.max, Some(Value::Integer(20)));
+    assert!(filter.might_match(&Value::Integer(15)));
+    assert!(!filter.might_match(&Value::Integer(9)));
+    assert!(!filter.might_match(&Value::Integer(21)));
+    assert!(!filter.might_match(&Value::Null));
+
+    let empty = RuntimeFilter::from_values("k", [Value::Null].iter());
+    assert!(!empty.might_match(&Value::Integer(10)));
+}
+
+#[tokio::test]
+async fn test_join_filters_only_for_joins_that_drop_unmatched_probe_rows() {
+    let dir = tempdir().unwrap();
+    let manager = Arc::new(Manager::new(dir.path().join("j.db"), 16).await.unwrap());
+    let dims = vec![Row::new(1, vec![Value::BigInt(5), Value::Integer(1993)])];
+
+    let inner = HashJoinOperation::new(manager.clone(), JoinType::Inner, condition());
+    let filters = inner.build_runtime_filters(&dims, &dim_schema()).unwrap();
+    assert_eq!(filters.len(), 1);
+    assert_eq!(filters[0].column, "date_key");
+
+    let left = HashJoinOperation::new(manager.clone(), JoinType::LeftOuter, condition());
+    assert!(
+        left.build_runtime_filters(&dims, &dim_schema())
+            .unwrap()
+            .is_empty()
+    );
+    let full = HashJoinOperation::new(manager, JoinType::FullOuter, condition());
+    assert!(
+        full.build_runtime_filters(&dims, &dim_schema())
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_scan_applies_runtime_filter_before_join() {
+    let (_dir, executor) = fact_executor(2100).await;
+    let dims: Vec<Row> = [7i128, 250, 699]
+        .iter()
+        .enumerate()
+        .map(|(i, key)| Row::new(i as u64, vec![Value::BigInt(*key), Value::Integer(1993)]))
+        .collect();
+
+    let join = HashJoinOperation::new(
+        executor.storage_manager.clone(),
+        JoinType::Inner,
+        condition(),
+    );
+    let filters = join.build_runtime_filters(&dims, &dim_schema()).unwrap();
+
+    for parallel in [false, true] {
+        let mut options = ScanOptions::new()
+            .with_schema(fact_schema())
+            .with_parallel(parallel);
+        for filter in filters.clone() {
+            options = options.with_runtime_filter(filter);
+        }
+        let scanned = executor.scan(options).await.unwrap();
+
+        // Every key appears 3 times in 2100 rows; the bloom filter may let a few extra through
+        assert!(scanned.rows.len() >= 9);
+        assert!(
+            scanned.rows.len() < 60,
+            "{} rows passed",
+            scanned.rows.len()
+        );
+
+        let joined = join
+            .execute(scanned.rows, dims.clone(), &fact_schema(), &dim_schema())
+            .await
+            .unwrap();
+        assert_eq!(joined.output_rows, 9);
+    }
+}
+
+#[tokio::test]
+async fn test_runtime_filter_on_unknown_column_is_rejected() {
+    let (_dir, executor) = fact_executor(10).await;
+    let filter = RuntimeFilter::from_values("missing", [Value::Integer(1)].iter());
+    let options = ScanOptions::new()
+        .with_schema(fact_schema())
+        .with_runtime_filter(filter);
+    assert!(executor.scan(options).await.is_err());
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use std::sync::Arc;
+
+use bindereh::{
+    executor::Executor,
+    manager::Manager,
+    operator::aggregate::{AggregateFunction, HashAggregateOperation},
+    page::Page,
+};
+use shared_types::{
+    BinaryOp, Column, DataType, Decimal, Expr, Predicate, Row, ScanOptions, Schema, Value,
+};
+use tempfile::tempdir;
+
+use common::sorted_data;
+
+const ROWS: i64 = 2000;
+
+fn orders_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::not_null("customer".to_string(), DataType::Integer),
+        Column::not_null(
+            "price".to_string(),
+            DataType::Decimal {
+                precision: 10,
+                scale: 2,
+            },
+        ),
+        Column::not_null("quantity".to_string(), DataType::Integer),
+    ])
+}
+
+async fn orders_executor(workers: usize) -> (tempfile::TempDir, Executor) {
+    let dir = tempdir().unwrap();
+    let manager = Arc::new(
+        Manager::new(dir.path().join("orders.db"), 256)
+            .await
+            .unwrap(),
+    );
+
+    let root_page_id = manager.allocate_page().await;
+    let root = Page {
+        page_id: root_page_id,
+        is_leaf: true,
+        parent_page_id: None,
+        keys: vec![],
+        values: vec![],
+        child_page_ids: vec![],
+        next_leaf_page_id: None,
+        is_dirty: true,
+    };
+    manager.write_page(&root).await.unwrap();
+    manager.register_leaf_page(root_page_id).await.unwrap();
+
+    let executor = Executor::new(manager, root_page_id, workers);
+    let rows = (1..=ROWS)
+        .map(|i| {
+            Row::new(
+                i as u64,
+                vec![
+                    Value::Integer(i),
+                    Value::Integer(i % 13),
+                    Value::Decimal(Decimal::new((i % 1000) as i128 * 7, 2)),
+                    Value::Integer(1 + i % 50),
+                ],
+            )
+        })
+        .collect();
+    executor.insert_batch(rows).await.unwrap();
+    (dir, executor)
+}
+
+fn revenue_by_customer() -> HashAggregateOperation {
+    HashAggregateOperation::new(
+        vec![(Expr::column("customer"), "customer".to_string())],
+        vec![
+            (AggregateFunction::Count, "orders".to_string()),
+            (
+                AggregateFunction::Sum {
+                    expr: Expr::multiply(Expr::column("price"), Expr::column("quantity")),
+                },
+                "revenue".to_string(),
+            ),
+            (
+                AggregateFunction::Max {
+                    expr: Expr::column("quantity"),
+                },
+                "max_quantity".to_string(),
+            ),
+        ],
+    )
+}
+
+fn small_orders() -> Predicate {
+    Predicate::ColumnLessThan {
+        column: "quantity".to_string(),
+        value: Value::Integer(25),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_parallel_partial_aggregation_matches_row_at_a_time() {
+    let (_dir, executor) = orders_executor(4).await;
+    let options = ScanOptions::new()
+        .with_schema(orders_schema())
+        .with_predicate(small_orders())
+        .with_parallel(true);
+
+    let pushed_down = executor
+        .aggregate(options.clone(), &revenue_by_customer())
+        .await
+        .unwrap();
+
+    let scanned = executor.scan(options).await.unwrap();
+    let expected = revenue_by_customer()
+        .execute(scanned.rows, &orders_schema())
+        .await
+        .unwrap();
+
+    assert_eq!(pushed_down.input_rows, scanned.filtered_count);
+    assert_eq!(pushed_down.groups, 13);
+    assert_eq!(sorted_data(pushed_down.rows), sorted_data(expected.rows));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_aggregating_scan_spills_over_memory_budget() {
+    let (dir, executor) = orders_executor(4).await;
+    let options = ScanOptions::new()
+        .with_schema(orders_schema())
+        .with_parallel(true);
+    // One group per order, so the workers' partial tables outgrow a few kilobytes
+    let per_order = || {
+        HashAggregateOperation::new(
+            vec![(Expr::column("id"), "id".to_string())],
+            vec![(
+                AggregateFunction::Sum {
+                    expr: Expr::column("quantity"),
+                },
+                "quantity".to_string(),
+            )],
+        )
+    };
+
+    let spilled = executor
+        .aggregate(
+            options.clone(),
+            &per_order()
+                .with_memory_budget(4 * 1024)
+                .with_spill_dir(dir.path()),
+        )
+        .await
+        .unwrap();
+    let in_memory = executor.aggregate(options, &per_order()).await.unwrap();
+
+    assert!(spilled.spilled_rows > 0);
+    assert_eq!(in_memory.spilled_rows, 0);
+    assert_eq!(spilled.groups, ROWS as usize);
+    assert_eq!(spilled.input_rows, ROWS as usize);
+    assert_eq!(sorted_data(spilled.rows), sorted_data(in_memory.rows));
+}
+
+#[tokio::test]
+async fn test_sequential_global_aggregate_with_filter() {
+    let (_dir, executor) = orders_executor(1).await;
+    let options = ScanOptions::new()
+        .with_schema(orders_schema())
+        .with_filter(Expr::binary(
+            Expr::column("customer"),
+            BinaryOp::Eq,
+            Expr::literal(Value::Integer(0)),
+        ));
+    let aggregate = HashAggregateOperation::new(
+        Vec::new(),
+        vec![
+            (AggregateFunction::Count, "cnt".to_string()),
+            (
+                AggregateFunction::Sum {
+                    expr: Expr::column("id"),
+                },
+                "id_sum".to_string(),
+            ),
+        ],
+    );
+
+    let result = executor.aggregate(options, &aggregate).await.unwrap();
+    let multiples: Vec<i64> = (1..=ROWS).filter(|i| i % 13 == 0).collect();
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(
+        result.rows[0].data,
+        vec![
+            Value::Integer(multiples.len() as i64),
+            Value::Integer(multiples.iter().sum()),
+        ]
+    );
+    assert!(matches!(result.rows[0].data[1], Value::BigInt(_)));
+}
+
+#[tokio::test]
+async fn test_aggregating_scan_requires_schema() {
+    let (_dir, executor) = orders_executor(2).await;
+    assert!(
+        executor
+            .aggregate(ScanOptions::new(), &revenue_by_customer())
+            .await
+            .is_err()
+    );
+}
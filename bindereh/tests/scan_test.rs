@@ -0,0 +1,93 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use bindereh::{executor::Executor, manager::Manager, page::Page};
+use shared_types::{Column, DataType, Row, ScanOptions, Schema, Value};
+use tempfile::tempdir;
+
+const ROWS: i64 = 3000;
+
+fn items_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::not_null("quantity".to_string(), DataType::Integer),
+    ])
+}
+
+async fn items_executor(workers: usize) -> (tempfile::TempDir, Arc<Manager>, Executor) {
+    let dir = tempdir().unwrap();
+    let manager = Arc::new(
+        Manager::new(dir.path().join("items.db"), 1024)
+            .await
+            .unwrap(),
+    );
+    let root_page_id = manager.allocate_page().await;
+    let root = Page {
+        page_id: root_page_id,
+        is_leaf: true,
+        parent_page_id: None,
+        keys: vec![],
+        values: vec![],
+        child_page_ids: vec![],
+        next_leaf_page_id: None,
+        is_dirty: true,
+    };
+    manager.write_page(&root).await.unwrap();
+    manager.register_leaf_page(root_page_id).await.unwrap();
+
+    let executor = Executor::new(Arc::clone(&manager), root_page_id, workers);
+    let rows = (1..=ROWS)
+        .map(|i| Row::new(i as u64, vec![Value::Integer(i), Value::Integer(i % 7)]))
+        .collect();
+    executor.insert_batch(rows).await.unwrap();
+    (dir, manager, executor)
+}
+
+#[tokio::test]
+async fn test_read_page_counts_hits_for_its_caller() {
+    let (_dir, manager, _executor) = items_executor(1).await;
+    let page_ids = manager.get_all_leaf_page_ids().await.unwrap();
+
+    let mine = AtomicUsize::new(0);
+    let theirs = AtomicUsize::new(0);
+    for &page_id in &page_ids {
+        manager.read_page(page_id).await.unwrap();
+        manager
+            .read_page_counting_hits(page_id, &mine)
+            .await
+            .unwrap();
+    }
+    manager
+        .read_leaf_chain_counting_hits(page_ids[0], 2, &theirs)
+        .await
+        .unwrap();
+
+    assert_eq!(mine.load(Ordering::Relaxed), page_ids.len());
+    assert_eq!(theirs.load(Ordering::Relaxed), 2.min(page_ids.len()));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_scans_count_only_their_own_buffer_hits() {
+    let (_dir, _manager, executor) = items_executor(4).await;
+    let sequential = || executor.scan(ScanOptions::new().with_schema(items_schema()));
+    let parallel = || {
+        executor.scan(
+            ScanOptions::new()
+                .with_schema(items_schema())
+                .with_parallel(true),
+        )
+    };
+
+    // Warm the buffer pool, so that every later read is a hit
+    let warm = sequential().await.unwrap();
+    assert!(warm.pages_read > 1);
+
+    let (a, b, c) = tokio::join!(sequential(), parallel(), sequential());
+    for result in [a.unwrap(), b.unwrap(), c.unwrap()] {
+        assert_eq!(result.row_count(), ROWS as usize);
+        assert_eq!(result.pages_read, warm.pages_read);
+        assert_eq!(result.buffer_hits, result.pages_read);
+    }
+}
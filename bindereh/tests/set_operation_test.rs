@@ -0,0 +1,102 @@
+use bindereh::operator::set_operation::{HashSetOperation, SetOperator};
+use shared_types::{Column, DataType, Row, Schema, StorageError, Value};
+
+fn schema(data_type: DataType) -> Schema {
+    Schema::new(vec![Column::nullable("value".to_string(), data_type)])
+}
+
+fn rows(values: &[Option<i64>]) -> Vec<Row> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| Row::new(i as u64, vec![value.map_or(Value::Null, Value::Integer)]))
+        .collect()
+}
+
+fn run(op: SetOperator, all: bool) -> Vec<Option<i64>> {
+    // 1 twice, 2 three times and NULL twice on the left; 2 twice, 3 and NULL on the right
+    let left = rows(&[Some(1), Some(2), Some(2), None, Some(1), Some(2), None]);
+    let right = rows(&[Some(2), Some(3), None, Some(2)]);
+    let integer = schema(DataType::Integer);
+    HashSetOperation::new(op, all)
+        .execute(left, right, &integer, &integer)
+        .unwrap()
+        .rows
+        .into_iter()
+        .map(|row| match row.data[0] {
+            Value::Integer(value) => Some(value),
+            Value::Null => None,
+            ref other => panic!("unexpected value {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn test_union() {
+    assert_eq!(
+        run(SetOperator::Union, false),
+        vec![Some(1), Some(2), None, Some(3)]
+    );
+    assert_eq!(run(SetOperator::Union, true).len(), 11);
+}
+
+#[test]
+fn test_intersect_treats_nulls_as_equal() {
+    assert_eq!(run(SetOperator::Intersect, false), vec![Some(2), None]);
+    // min(3, 2) twos and min(2, 1) NULLs
+    assert_eq!(
+        run(SetOperator::Intersect, true),
+        vec![Some(2), Some(2), None]
+    );
+}
+
+#[test]
+fn test_except() {
+    assert_eq!(run(SetOperator::Except, false), vec![Some(1)]);
+    // Two ones, 3 - 2 twos and 2 - 1 NULLs
+    assert_eq!(
+        run(SetOperator::Except, true),
+        vec![Some(1), Some(1), Some(2), None]
+    );
+}
+
+#[test]
+fn test_inputs_are_coerced_to_a_common_type() {
+    let left = vec![
+        Row::new(1, vec![Value::Integer(1)]),
+        Row::new(2, vec![Value::Integer(2)]),
+    ];
+    let right = vec![Row::new(1, vec![Value::Float(1.0)])];
+    let result = HashSetOperation::new(SetOperator::Except, false)
+        .execute(
+            left,
+            right,
+            &schema(DataType::Integer),
+            &schema(DataType::Float),
+        )
+        .unwrap();
+    assert_eq!(result.result_schema.columns[0].data_type, DataType::Float);
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(result.rows[0].data, vec![Value::Float(2.0)]);
+
+    let text = schema(DataType::String);
+    let mismatch = HashSetOperation::new(SetOperator::Union, true).execute(
+        vec![],
+        vec![],
+        &schema(DataType::Integer),
+        &text,
+    );
+    assert!(matches!(mismatch, Err(StorageError::InvalidInput(_))));
+
+    let two_columns = Schema::new(vec![
+        Column::nullable("a".to_string(), DataType::Integer),
+        Column::nullable("b".to_string(), DataType::Integer),
+    ]);
+    let arity = HashSetOperation::new(SetOperator::Intersect, false).execute(
+        vec![],
+        vec![],
+        &schema(DataType::Integer),
+        &two_columns,
+    );
+    assert!(matches!(arity, Err(StorageError::InvalidInput(_))));
+}
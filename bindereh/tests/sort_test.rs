@@ -0,0 +1,126 @@
+use bindereh::operator::compare::sort_rows;
+use bindereh::operator::sort::ExternalSortOperation;
+use shared_types::{Column, DataType, OrderBy, Row, Schema, Value};
+
+fn orders_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::nullable("priority".to_string(), DataType::Integer),
+        Column::nullable("customer".to_string(), DataType::String),
+    ])
+}
+
+fn order_rows(count: i64) -> Vec<Row> {
+    (0..count)
+        .map(|i| {
+            // A multiplicative permutation so the input arrives far from sorted
+            let n = (i * 7919) % count;
+            let priority = if n % 11 == 0 {
+                Value::Null
+            } else {
+                Value::Integer(n % 5)
+            };
+            let customer = if n % 13 == 0 {
+                Value::Null
+            } else {
+                Value::String(format!("customer-{:04}", n % 97))
+            };
+            Row::new(n as u64, vec![Value::Integer(n), priority, customer])
+        })
+        .collect()
+}
+
+fn spill_file_count(dir: &std::path::Path) -> usize {
+    std::fs::read_dir(dir).unwrap().count()
+}
+
+#[tokio::test]
+async fn test_external_sort_matches_in_memory_sort() {
+    let rows = order_rows(3000);
+    let order_by = vec![
+        OrderBy::desc("priority".to_string()).nulls_last(),
+        OrderBy::asc("customer".to_string()).nulls_first(),
+        OrderBy::asc("id".to_string()),
+    ];
+    let mut expected = rows.clone();
+    sort_rows(&mut expected, &order_by, &orders_schema());
+
+    let dir = tempfile::tempdir().unwrap();
+    let result = ExternalSortOperation::new(order_by)
+        .with_memory_budget(4096)
+        .with_spill_dir(dir.path())
+        .execute(rows, &orders_schema())
+        .await
+        .unwrap();
+
+    assert!(result.runs > 1);
+    assert!(result.spilled_rows > 0);
+    // The runs stay on disk until the merge has read them
+    assert!(spill_file_count(dir.path()) > 0);
+    let sorted = result.rows.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(sorted, expected);
+    assert_eq!(spill_file_count(dir.path()), 0);
+}
+
+#[tokio::test]
+async fn test_multi_pass_merge_keeps_equal_keys_in_input_order() {
+    let rows = order_rows(2000);
+    let dir = tempfile::tempdir().unwrap();
+    let result = ExternalSortOperation::new(vec![OrderBy::asc("priority".to_string())])
+        .with_memory_budget(1024)
+        .with_merge_fanin(3)
+        .with_spill_dir(dir.path())
+        .execute(rows.clone(), &orders_schema())
+        .await
+        .unwrap();
+
+    // Far more runs than the fan-in, so intermediate merge passes were needed
+    assert!(result.runs > 9);
+    let mut expected = rows;
+    expected.sort_by_key(|row| row.data[1].clone());
+    let sorted = result.rows.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(sorted, expected);
+    assert_eq!(spill_file_count(dir.path()), 0);
+}
+
+#[tokio::test]
+async fn test_nulls_first_and_last_in_both_directions() {
+    let schema = orders_schema();
+    let rows = vec![
+        Row::new(1, vec![Value::Integer(1), Value::Integer(2), Value::Null]),
+        Row::new(2, vec![Value::Integer(2), Value::Null, Value::Null]),
+        Row::new(3, vec![Value::Integer(3), Value::Integer(1), Value::Null]),
+        Row::new(4, vec![Value::Integer(4), Value::Integer(3), Value::Null]),
+    ];
+    let sorted_ids = |order_by: OrderBy| {
+        let rows = rows.clone();
+        let schema = schema.clone();
+        async move {
+            ExternalSortOperation::new(vec![order_by])
+                .execute(rows, &schema)
+                .await
+                .unwrap()
+                .rows
+                .map(|row| row.unwrap().id)
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // By default NULL is the smallest value
+    assert_eq!(
+        sorted_ids(OrderBy::asc("priority".to_string())).await,
+        vec![2, 3, 1, 4]
+    );
+    assert_eq!(
+        sorted_ids(OrderBy::desc("priority".to_string())).await,
+        vec![4, 1, 3, 2]
+    );
+    assert_eq!(
+        sorted_ids(OrderBy::asc("priority".to_string()).nulls_last()).await,
+        vec![3, 1, 4, 2]
+    );
+    assert_eq!(
+        sorted_ids(OrderBy::desc("priority".to_string()).nulls_first()).await,
+        vec![2, 4, 1, 3]
+    );
+}
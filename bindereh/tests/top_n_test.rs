@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use bindereh::{
+    executor::Executor,
+    manager::Manager,
+    operator::{
+        compare::sort_rows,
+        top_n::{TopN, TopNOperation},
+    },
+    page::Page,
+};
+use shared_types::{Column, DataType, OrderBy, Row, ScanOptions, Schema, Value};
+use tempfile::tempdir;
+
+fn revenue_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::nullable("revenue".to_string(), DataType::Integer),
+    ])
+}
+
+fn revenue_rows(count: i64) -> Vec<Row> {
+    (1..=count)
+        .map(|i| {
+            // Plenty of ties and a few NULLs, in no particular order
+            let revenue = if i % 23 == 0 {
+                Value::Null
+            } else {
+                Value::Integer((i * 37) % 101)
+            };
+            Row::new(i as u64, vec![Value::Integer(i), revenue])
+        })
+        .collect()
+}
+
+fn expected_top(rows: &[Row], order_by: &[OrderBy], offset: usize, limit: usize) -> Vec<Row> {
+    let mut sorted = rows.to_vec();
+    sort_rows(&mut sorted, order_by, &revenue_schema());
+    sorted.into_iter().skip(offset).take(limit).collect()
+}
+
+async fn revenue_executor(rows: i64) -> (tempfile::TempDir, Executor) {
+    let dir = tempdir().unwrap();
+    let manager = Arc::new(
+        Manager::new(dir.path().join("revenue.db"), 128)
+            .await
+            .unwrap(),
+    );
+    let root_page_id = manager.allocate_page().await;
+    let root = Page {
+        page_id: root_page_id,
+        is_leaf: true,
+        parent_page_id: None,
+        keys: vec![],
+        values: vec![],
+        child_page_ids: vec![],
+        next_leaf_page_id: None,
+        is_dirty: true,
+    };
+    manager.write_page(&root).await.unwrap();
+    manager.register_leaf_page(root_page_id).await.unwrap();
+
+    let executor = Executor::new(manager, root_page_id, 4);
+    executor.insert_batch(revenue_rows(rows)).await.unwrap();
+    (dir, executor)
+}
+
+#[test]
+fn test_merged_partial_heaps_match_single_heap() {
+    let rows = revenue_rows(1000);
+    let order_by = vec![OrderBy::desc("revenue".to_string()).nulls_last()];
+    let schema = revenue_schema();
+
+    let mut single = TopN::new(&order_by, &schema, 25);
+    for row in rows.iter().cloned() {
+        single.push(row);
+    }
+    assert_eq!(single.len(), 25);
+
+    let template = TopN::new(&order_by, &schema, 25);
+    let mut merged = template.for_partition(0);
+    for (partition, chunk) in rows.chunks(150).enumerate().rev() {
+        let mut partial = template.for_partition(partition);
+        for row in chunk.iter().cloned() {
+            partial.push(row);
+        }
+        merged.merge(partial);
+    }
+
+    let expected = expected_top(&rows, &order_by, 0, 25);
+    assert_eq!(single.into_sorted_rows(), expected);
+    // Ties are broken by input position, whatever order the partials were merged in
+    assert_eq!(merged.into_sorted_rows(), expected);
+}
+
+#[tokio::test]
+async fn test_top_n_operation_applies_offset() {
+    let rows = revenue_rows(50_000);
+    let order_by = vec![
+        OrderBy::asc("revenue".to_string()).nulls_last(),
+        OrderBy::desc("id".to_string()),
+    ];
+    let result = TopNOperation::new(order_by.clone(), 10)
+        .with_offset(5)
+        .execute(rows.clone(), &revenue_schema())
+        .await
+        .unwrap();
+    assert_eq!(result, expected_top(&rows, &order_by, 5, 10));
+
+    let empty = TopNOperation::new(order_by, 0)
+        .execute(rows, &revenue_schema())
+        .await
+        .unwrap();
+    assert!(empty.is_empty());
+}
+
+#[tokio::test]
+async fn test_scan_order_by_limit_sees_every_row() {
+    let (_dir, executor) = revenue_executor(3000).await;
+    let rows = revenue_rows(3000);
+    let order_by = vec![OrderBy::desc("revenue".to_string()).nulls_last()];
+
+    for parallel in [false, true] {
+        let result = executor
+            .scan(ScanOptions {
+                schema: Some(revenue_schema()),
+                order_by: Some(order_by.clone()),
+                limit: Some(10),
+                offset: Some(3),
+                parallel,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // Every row is scanned even though only 13 are kept
+        assert_eq!(result.total_scanned, 3000, "parallel: {}", parallel);
+        let revenues: Vec<Value> = result.rows.iter().map(|row| row.data[1].clone()).collect();
+        let expected: Vec<Value> = expected_top(&rows, &order_by, 3, 10)
+            .iter()
+            .map(|row| row.data[1].clone())
+            .collect();
+        assert_eq!(revenues, expected, "parallel: {}", parallel);
+    }
+}
@@ -1,4 +1,4 @@
-use shared_types::Value;
+use shared_types::{Decimal, Value};
 
 #[test]
 fn test_null_serialization() {
@@ -112,11 +112,43 @@ fn test_bigint_serialization() {
 
 #[test]
 fn test_decimal_serialization() {
-    let value = Value::Decimal("123.456789".to_string());
+    let value = Value::Decimal("123.456789".parse().unwrap());
     let bytes = value.to_bytes();
     let mut offset = 0;
     let deserialized = Value::from_bytes(&bytes, &mut offset).unwrap();
     assert_eq!(value, deserialized);
+    assert_eq!(offset, bytes.len());
+    assert_eq!(bytes.len(), value.serialized_size());
+
+    // Negative and wide mantissas round-trip through the compact encoding
+    for text in ["-0.01", "0", "-99999999999999999999999999999999999.999"] {
+        let decimal: Decimal = text.parse().unwrap();
+        let value = Value::Decimal(decimal);
+        let bytes = value.to_bytes();
+        let mut offset = 0;
+        match Value::from_bytes(&bytes, &mut offset).unwrap() {
+            Value::Decimal(d) => {
+                assert_eq!(d.to_string(), text);
+                assert_eq!(d.scale(), decimal.scale());
+            }
+            other => panic!("expected decimal, got {:?}", other),
+        }
+        assert_eq!(bytes.len(), value.serialized_size());
+    }
+}
+
+#[test]
+fn test_decimal_compact_encoding_and_legacy_text() {
+    let small = Value::Decimal("12.34".parse().unwrap());
+    // type + scale + length + 2 mantissa bytes
+    assert_eq!(small.to_bytes().len(), 5);
+
+    // Pages written before the fixed-point encoding stored decimals as text
+    let mut legacy = vec![7u8];
+    legacy.extend_from_slice(&5u32.to_le_bytes());
+    legacy.extend_from_slice(b"12.34");
+    let mut offset = 0;
+    assert_eq!(Value::from_bytes(&legacy, &mut offset).unwrap(), small);
 }
 
 #[test]
@@ -309,3 +341,103 @@ fn test_multiple_values_serialization() {
     assert_eq!(values, deserialized);
     assert_eq!(offset, all_bytes.len());
 }
+
+fn hash_of(value: &Value) -> u64 {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn test_numeric_tower_equality_and_hash() {
+    let ones = [
+        Value::TinyInt(1),
+        Value::SmallInt(1),
+        Value::Integer(1),
+        Value::BigInt(1),
+        Value::Float(1.0),
+        Value::Decimal("1.00".parse().unwrap()),
+    ];
+    for a in &ones {
+        for b in &ones {
+            assert_eq!(a, b);
+            assert_eq!(hash_of(a), hash_of(b));
+        }
+    }
+
+    assert_eq!(Value::Float(2.5), Value::Decimal("2.5".parse().unwrap()));
+    assert_eq!(
+        hash_of(&Value::Float(2.5)),
+        hash_of(&Value::Decimal("2.5".parse().unwrap()))
+    );
+    assert_ne!(Value::Integer(2), Value::Float(2.5));
+    assert!(Value::Integer(2) < Value::Float(2.5));
+    assert!(Value::BigInt(i128::from(i64::MAX) + 1) > Value::Integer(i64::MAX));
+    assert!(Value::TinyInt(-1) < Value::SmallInt(0));
+}
+
+#[test]
+fn test_equal_decimals_hash_alike_at_any_scale() {
+    // More significant digits than a float holds, so converting to f64 rounds
+    for (a, b) in [
+        ("385915501061407.3", "385915501061407.30"),
+        ("-1247.75948028053977", "-1247.7594802805397700000"),
+        ("0.30000000000000000001", "0.300000000000000000010"),
+    ] {
+        let a = Value::Decimal(a.parse().unwrap());
+        let b = Value::Decimal(b.parse().unwrap());
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b), "{:?} {:?}", a, b);
+    }
+    assert_ne!(
+        Value::Decimal("0.30000000000000000001".parse().unwrap()),
+        Value::Decimal("0.3".parse().unwrap())
+    );
+
+    // A float hashes as the decimal it is exactly equal to
+    assert_eq!(
+        hash_of(&Value::Float(-0.375)),
+        hash_of(&Value::Decimal("-0.37500".parse().unwrap()))
+    );
+}
+
+#[test]
+fn test_equivalent_text_and_timestamp_types() {
+    let string = Value::String("abc".to_string());
+    let text = Value::Text("abc".to_string());
+    assert_eq!(string, text);
+    assert_eq!(hash_of(&string), hash_of(&text));
+    assert!(Value::String("abc".to_string()) < Value::Text("abd".to_string()));
+
+    assert_eq!(Value::Timestamp(1_000), Value::DateTime(1_000));
+    assert_eq!(
+        hash_of(&Value::Timestamp(1_000)),
+        hash_of(&Value::DateTime(1_000))
+    );
+    assert!(Value::Timestamp(999) < Value::DateTime(1_000));
+}
+
+#[test]
+fn test_cross_type_ordering_is_total() {
+    let mut values = vec![
+        Value::String("a".to_string()),
+        Value::Float(0.5),
+        Value::Null,
+        Value::Integer(3),
+        Value::Boolean(true),
+        Value::SmallInt(-2),
+    ];
+    values.sort();
+    assert_eq!(
+        values,
+        vec![
+            Value::Null,
+            Value::Boolean(true),
+            Value::SmallInt(-2),
+            Value::Float(0.5),
+            Value::Integer(3),
+            Value::String("a".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,261 @@
+use bindereh::operator::aggregate::AggregateFunction;
+use bindereh::operator::window::{
+    FrameBound, WindowExpr, WindowFrame, WindowFunction, WindowOperation, WindowSpec,
+};
+use shared_types::{Column, DataType, Expr, OrderBy, Row, Schema, Value};
+
+fn sales_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::new("region".to_string(), DataType::String, false, false),
+        Column::nullable("month".to_string(), DataType::Integer),
+        Column::nullable("amount".to_string(), DataType::Integer),
+    ])
+}
+
+fn sale(id: i64, region: &str, month: Option<i64>, amount: i64) -> Row {
+    Row::new(
+        id as u64,
+        vec![
+            Value::Integer(id),
+            Value::String(region.to_string()),
+            month.map_or(Value::Null, Value::Integer),
+            Value::Integer(amount),
+        ],
+    )
+}
+
+/// Interleaved regions, arriving out of month order, with a tie in east's month 2
+fn sales_rows() -> Vec<Row> {
+    vec![
+        sale(1, "east", Some(3), 30),
+        sale(2, "west", Some(1), 5),
+        sale(3, "east", Some(1), 10),
+        sale(4, "east", Some(2), 20),
+        sale(5, "west", Some(2), 7),
+        sale(6, "east", Some(2), 25),
+        sale(7, "east", Some(5), 50),
+    ]
+}
+
+fn by_region_month() -> WindowSpec {
+    WindowSpec::new(
+        vec![Expr::column("region")],
+        vec![OrderBy::asc("month".to_string())],
+    )
+}
+
+fn amount() -> Expr {
+    Expr::column("amount")
+}
+
+/// `(id, window columns...)` per output row
+fn project(rows: &[Row], first_window_column: usize) -> Vec<(i64, Vec<Value>)> {
+    rows.iter()
+        .map(|row| {
+            let id = match row.data[0] {
+                Value::Integer(id) => id,
+                ref other => panic!("unexpected id {:?}", other),
+            };
+            (id, row.data[first_window_column..].to_vec())
+        })
+        .collect()
+}
+
+fn ints(values: &[i64]) -> Vec<Value> {
+    values.iter().copied().map(Value::Integer).collect()
+}
+
+#[tokio::test]
+async fn test_ranking_functions_per_partition() {
+    let result = WindowOperation::new(vec![
+        WindowExpr::new(WindowFunction::RowNumber, by_region_month(), "row_number"),
+        WindowExpr::new(WindowFunction::Rank, by_region_month(), "rank"),
+        WindowExpr::new(WindowFunction::DenseRank, by_region_month(), "dense_rank"),
+    ])
+    .execute(sales_rows(), &sales_schema())
+    .await
+    .unwrap();
+
+    assert_eq!(result.input_rows, 7);
+    assert_eq!(result.partitions, 2);
+    assert_eq!(result.result_schema.column_count(), 7);
+    assert_eq!(
+        result.result_schema.get_column("rank").unwrap().data_type,
+        DataType::Integer
+    );
+
+    // Partitions in order of first appearance, months ascending, ties in input order
+    assert_eq!(
+        project(&result.rows, 4),
+        vec![
+            (3, ints(&[1, 1, 1])),
+            (4, ints(&[2, 2, 2])),
+            (6, ints(&[3, 2, 2])),
+            (1, ints(&[4, 4, 3])),
+            (7, ints(&[5, 5, 4])),
+            (2, ints(&[1, 1, 1])),
+            (5, ints(&[2, 2, 2])),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_aggregates_over_rows_and_range_frames() {
+    let moving = by_region_month().with_frame(WindowFrame::rows(
+        FrameBound::Preceding(1),
+        FrameBound::Following(1),
+    ));
+    let whole = WindowSpec::new(vec![Expr::column("region")], vec![]);
+    let result = WindowOperation::new(vec![
+        // Default frame: RANGE UNBOUNDED PRECEDING, so peers share the running total
+        WindowExpr::new(
+            WindowFunction::Aggregate(AggregateFunction::Sum { expr: amount() }),
+            by_region_month(),
+            "running_total",
+        ),
+        WindowExpr::new(
+            WindowFunction::Aggregate(AggregateFunction::Sum { expr: amount() }),
+            moving,
+            "moving_sum",
+        ),
+        WindowExpr::new(
+            WindowFunction::Aggregate(AggregateFunction::Count),
+            whole,
+            "region_count",
+        ),
+    ])
+    .execute(sales_rows(), &sales_schema())
+    .await
+    .unwrap();
+
+    assert_eq!(
+        project(&result.rows, 4),
+        vec![
+            (3, ints(&[10, 30, 5])),
+            (4, ints(&[55, 55, 5])),
+            (6, ints(&[55, 75, 5])),
+            (1, ints(&[85, 105, 5])),
+            (7, ints(&[135, 80, 5])),
+            (2, ints(&[5, 12, 2])),
+            (5, ints(&[12, 12, 2])),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_lag_and_lead_for_period_deltas() {
+    let result = WindowOperation::new(vec![
+        WindowExpr::new(
+            WindowFunction::Lag {
+                expr: amount(),
+                offset: 1,
+                default: Value::Null,
+            },
+            by_region_month(),
+            "previous",
+        ),
+        WindowExpr::new(
+            WindowFunction::Lead {
+                expr: amount(),
+                offset: 2,
+                default: Value::Integer(0),
+            },
+            by_region_month(),
+            "after_next",
+        ),
+    ])
+    .execute(sales_rows(), &sales_schema())
+    .await
+    .unwrap();
+
+    assert_eq!(
+        project(&result.rows, 4),
+        vec![
+            (3, vec![Value::Null, Value::Integer(25)]),
+            (4, ints(&[10, 30])),
+            (6, ints(&[20, 50])),
+            (1, ints(&[25, 0])),
+            (7, ints(&[30, 0])),
+            (2, vec![Value::Null, Value::Integer(0)]),
+            (5, ints(&[5, 0])),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_range_offset_frame_measures_key_distance() {
+    let mut rows = sales_rows();
+    rows.push(sale(8, "east", None, 1));
+    rows.push(sale(9, "east", None, 2));
+
+    // Sum over the months within one of the current row's month
+    let spec = by_region_month().with_frame(WindowFrame::range(
+        FrameBound::Preceding(1),
+        FrameBound::Following(1),
+    ));
+    let descending = WindowSpec::new(
+        vec![Expr::column("region")],
+        vec![OrderBy::desc("month".to_string())],
+    )
+    .with_frame(WindowFrame::range(
+        FrameBound::CurrentRow,
+        FrameBound::Following(2),
+    ));
+    let result = WindowOperation::new(vec![
+        WindowExpr::new(
+            WindowFunction::Aggregate(AggregateFunction::Sum { expr: amount() }),
+            spec,
+            "nearby",
+        ),
+        WindowExpr::new(
+            WindowFunction::Aggregate(AggregateFunction::Max { expr: amount() }),
+            descending,
+            "max_next_two_months_down",
+        ),
+    ])
+    .execute(rows, &sales_schema())
+    .await
+    .unwrap();
+
+    // NULL months sort first ascending; their frame is the other NULLs
+    assert_eq!(
+        project(&result.rows, 4),
+        vec![
+            (8, ints(&[3, 2])),
+            (9, ints(&[3, 2])),
+            (3, ints(&[55, 10])),
+            (4, ints(&[85, 25])),
+            (6, ints(&[85, 25])),
+            (1, ints(&[75, 30])),
+            (7, ints(&[50, 50])),
+            (2, ints(&[12, 5])),
+            (5, ints(&[12, 7])),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_windows_are_rejected() {
+    let missing_order = WindowSpec::new(vec![], vec![OrderBy::asc("quarter".to_string())]);
+    let result = WindowOperation::new(vec![WindowExpr::new(
+        WindowFunction::RowNumber,
+        missing_order,
+        "row_number",
+    )])
+    .execute(sales_rows(), &sales_schema())
+    .await;
+    assert!(result.is_err());
+
+    let by_region = WindowSpec::new(vec![], vec![OrderBy::asc("region".to_string())]).with_frame(
+        WindowFrame::range(FrameBound::Preceding(1), FrameBound::CurrentRow),
+    );
+    let result = WindowOperation::new(vec![WindowExpr::new(
+        WindowFunction::Aggregate(AggregateFunction::Count),
+        by_region,
+        "count",
+    )])
+    .execute(sales_rows(), &sales_schema())
+    .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,175 @@
+//! Name resolution against the catalog
+//!
+//! The binder loads table schemas from a `matan::CatalogManager` and resolves the
+//! column names of a query against the tables in its FROM clauses. Unknown tables and
+//! columns, and unqualified names that match more than one column, are reported with
+//! their position in the SQL text.
+
+use std::collections::HashMap;
+
+use matan::manager::CatalogManager;
+use shared_types::Schema;
+use sqlparser::ast::{Ident, ObjectName, Spanned};
+use sqlparser::tokenizer::Span;
+
+use crate::{
+    common::LogicalPlanError,
+    logical_plan::LogicalPlan,
+    types::{ColumnDef, ColumnRef, LogicalSchema},
+    utils::object_name_to_string,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Binder {
+    tables: HashMap<String, LogicalSchema>,
+}
+
+impl Binder {
+    /// Snapshot the schemas of every table in `catalog`
+    pub fn new(catalog: &CatalogManager) -> Self {
+        let tables = catalog
+            .list_tables()
+            .into_iter()
+            .filter_map(|name| {
+                let schema = logical_schema(catalog.get_schema(&name)?);
+                Some((name, schema))
+            })
+            .collect();
+        Self { tables }
+    }
+
+    pub fn table_schemas(&self) -> &HashMap<String, LogicalSchema> {
+        &self.tables
+    }
+
+    /// Schema of the catalog table `name`, with its columns qualified by `qualifier`,
+    /// the table's alias or name
+    pub fn bind_table(
+        &self,
+        name: &ObjectName,
+        qualifier: &str,
+    ) -> Result<LogicalSchema, LogicalPlanError> {
+        let table_name = object_name_to_string(name);
+        let schema = self.tables.get(&table_name).ok_or_else(|| {
+            LogicalPlanError::TableNotFound(format!("{}{}", table_name, position(name.span())))
+        })?;
+        Ok(qualify(schema, qualifier))
+    }
+
+    /// Resolve `qualifier.name`, or a bare `name`, against `scopes`, the FROM rows of
+    /// the enclosing queries from outermost to innermost. The innermost scope with a
+    /// match wins; a match there is bound to its position in the row, while a match in
+    /// an enclosing query is a correlated reference and stays unbound.
+    pub fn bind_column(
+        &self,
+        scopes: &[LogicalSchema],
+        qualifier: Option<&Ident>,
+        name: &Ident,
+    ) -> Result<ColumnRef, LogicalPlanError> {
+        let display = match qualifier {
+            Some(table) => format!("{}.{}", table.value, name.value),
+            None => name.value.clone(),
+        };
+
+        for (depth, scope) in scopes.iter().enumerate().rev() {
+            let matches: Vec<(usize, &ColumnDef)> = scope
+                .columns
+                .iter()
+                .enumerate()
+                .filter(|(_, column)| {
+                    column.name == name.value
+                        && qualifier
+                            .is_none_or(|table| column.table.as_deref() == Some(&table.value))
+                })
+                .collect();
+
+            match matches.as_slice() {
+                [] => continue,
+                [(index, column)] => {
+                    let column_ref = match &column.table {
+                        Some(table) => ColumnRef::with_table(table, &column.name),
+                        None => ColumnRef::new(&column.name),
+                    };
+                    return Ok(if depth == scopes.len() - 1 {
+                        column_ref.at_index(*index)
+                    } else {
+                        column_ref
+                    });
+                }
+                _ => {
+                    let candidates: Vec<String> = matches
+                        .iter()
+                        .map(|(_, column)| match &column.table {
+                            Some(table) => format!("{}.{}", table, column.name),
+                            None => column.name.clone(),
+                        })
+                        .collect();
+                    return Err(LogicalPlanError::AmbiguousColumn(format!(
+                        "{}{} could refer to {}",
+                        display,
+                        position(name.span),
+                        candidates.join(" or ")
+                    )));
+                }
+            }
+        }
+
+        Err(LogicalPlanError::ColumnNotFound(format!(
+            "{}{}",
+            display,
+            position(name.span)
+        )))
+    }
+}
+
+/// Columns of the row a FROM clause produces, qualified the way queries refer to
+/// them: derived tables by their alias rather than by the tables inside them
+pub fn from_scope(plan: &LogicalPlan) -> LogicalSchema {
+    match plan {
+        LogicalPlan::Join(join) if !join.join_type.returns_left_only() => {
+            let mut columns = from_scope(&join.left).columns;
+            columns.extend(from_scope(&join.right).columns);
+            LogicalSchema::new(columns)
+        }
+        LogicalPlan::Subquery(subquery) => match &subquery.alias {
+            Some(alias) => qualify(subquery.subquery.schema(), alias),
+            None => subquery.subquery.schema().clone(),
+        },
+        _ => plan.schema().clone(),
+    }
+}
+
+/// Logical schema of a catalog table, with unqualified columns
+pub fn logical_schema(schema: &Schema) -> LogicalSchema {
+    LogicalSchema::new(
+        schema
+            .columns
+            .iter()
+            .map(|column| {
+                let def = ColumnDef::new(&column.name, column.data_type.clone());
+                if column.nullable { def } else { def.not_null() }
+            })
+            .collect(),
+    )
+}
+
+fn qualify(schema: &LogicalSchema, qualifier: &str) -> LogicalSchema {
+    LogicalSchema::new(
+        schema
+            .columns
+            .iter()
+            .map(|column| ColumnDef {
+                table: Some(qualifier.to_string()),
+                ..column.clone()
+            })
+            .collect(),
+    )
+}
+
+/// ` at line L, column C`, or nothing for identifiers that didn't come from SQL text
+fn position(span: Span) -> String {
+    if span.start.line == 0 {
+        return String::new();
+    }
+    format!(" at line {}, column {}", span.start.line, span.start.column)
+}
@@ -14,6 +14,9 @@ pub enum LogicalPlanError {
     #[error("Column not found: {0}")]
     ColumnNotFound(String),
 
+    #[error("Ambiguous column: {0}")]
+    AmbiguousColumn(String),
+
     #[error("Table not found: {0}")]
     TableNotFound(String),
 
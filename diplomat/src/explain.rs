@@ -0,0 +1,254 @@
+//! EXPLAIN output: the optimized logical plan and, node by node, the operator the
+//! planner picked to execute it, with the optimizer's estimates. Nothing is run, so
+//! the rows and costs shown are estimates only.
+
+use std::fmt::Write;
+
+use crate::{
+    expression::Expression,
+    logical_plan::{AggregateNode, ExplainNode, JoinNode, LogicalPlan, TableScanNode},
+    types::{ExplainFormat, JoinAlgorithm, JoinStrategy, SortExpr, SortOrder},
+};
+
+/// Render the plan of `explain` in its format
+pub fn explain(explain: &ExplainNode) -> String {
+    match explain.format {
+        ExplainFormat::Text => text(&explain.plan),
+        ExplainFormat::Json => json(&explain.plan),
+    }
+}
+
+fn text(plan: &LogicalPlan) -> String {
+    let mut out = String::from("Logical Plan:\n");
+    logical_text(plan, 1, &mut out);
+    out.push_str("Physical Plan:\n");
+    physical_text(plan, 1, &mut out);
+    out
+}
+
+fn logical_text(plan: &LogicalPlan, depth: usize, out: &mut String) {
+    writeln!(out, "{}{}", "  ".repeat(depth), logical_label(plan)).unwrap();
+    for child in plan.children() {
+        logical_text(child, depth + 1, out);
+    }
+}
+
+fn physical_text(plan: &LogicalPlan, depth: usize, out: &mut String) {
+    let statistics = plan.statistics();
+    let rows = statistics
+        .row_count
+        .map_or("?".to_string(), |rows| rows.to_string());
+    let cost = statistics
+        .cost
+        .map_or("?".to_string(), |cost| format!("{:.2}", cost));
+    writeln!(
+        out,
+        "{}{}  (rows={} cost={})",
+        "  ".repeat(depth),
+        physical_label(plan),
+        rows,
+        cost
+    )
+    .unwrap();
+
+    for child in plan.children() {
+        physical_text(child, depth + 1, out);
+    }
+}
+
+fn json(plan: &LogicalPlan) -> String {
+    let mut out = String::from("{\"logical_plan\": ");
+    logical_json(plan, &mut out);
+    out.push_str(", \"physical_plan\": ");
+    physical_json(plan, &mut out);
+    out.push('}');
+    out
+}
+
+fn logical_json(plan: &LogicalPlan, out: &mut String) {
+    write!(
+        out,
+        "{{\"node\": {}, \"children\": [",
+        json_string(&logical_label(plan))
+    )
+    .unwrap();
+    for (index, child) in plan.children().into_iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        logical_json(child, out);
+    }
+    out.push_str("]}");
+}
+
+fn physical_json(plan: &LogicalPlan, out: &mut String) {
+    let statistics = plan.statistics();
+    let rows = statistics
+        .row_count
+        .map_or("null".to_string(), |rows| rows.to_string());
+    let cost = statistics
+        .cost
+        .map_or("null".to_string(), |cost| format!("{:.2}", cost));
+    write!(
+        out,
+        "{{\"operator\": {}, \"estimated_rows\": {}, \"estimated_cost\": {}",
+        json_string(&physical_label(plan)),
+        rows,
+        cost
+    )
+    .unwrap();
+    out.push_str(", \"children\": [");
+    for (index, child) in plan.children().into_iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        physical_json(child, out);
+    }
+    out.push_str("]}");
+}
+
+fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The node as the optimizer sees it
+fn logical_label(plan: &LogicalPlan) -> String {
+    match plan {
+        LogicalPlan::TableScan(scan) => format!("{}{}", plan.description(), scan_details(scan)),
+        LogicalPlan::Projection(node) => format!("Projection: {}", list(&node.expressions)),
+        LogicalPlan::Join(join) => format!("Join: {:?}{}", join.join_type, join_details(join)),
+        LogicalPlan::Aggregate(node) => aggregate_label("Aggregate", node),
+        LogicalPlan::Window(node) => format!("Window: {}", list(&node.window_expr)),
+        LogicalPlan::Sort(node) => format!("Sort: {}", sort_keys(&node.expressions)),
+        _ => plan.description(),
+    }
+}
+
+/// The operator that executes the node
+fn physical_label(plan: &LogicalPlan) -> String {
+    match plan {
+        LogicalPlan::TableScan(scan) => {
+            let name = scan.table.effective_name();
+            let scan_label = match scan.parallelism {
+                Some(workers) if workers > 1 => {
+                    format!("ParallelScan: {} workers={}", name, workers)
+                }
+                _ => format!("SeqScan: {}", name),
+            };
+            format!("{}{}", scan_label, scan_details(scan))
+        }
+        LogicalPlan::Projection(node) => format!("Projection: {}", list(&node.expressions)),
+        LogicalPlan::Join(join) => {
+            let join_label = match &join.strategy {
+                Some(JoinStrategy {
+                    algorithm: JoinAlgorithm::Hash,
+                    build_side,
+                }) => format!(
+                    "HashJoin: {:?} build={}",
+                    join.join_type,
+                    format!("{:?}", build_side).to_lowercase()
+                ),
+                Some(JoinStrategy {
+                    algorithm: JoinAlgorithm::SortMerge,
+                    ..
+                }) => format!("MergeJoin: {:?}", join.join_type),
+                Some(JoinStrategy {
+                    algorithm: JoinAlgorithm::NestedLoop,
+                    build_side,
+                }) => format!(
+                    "NestedLoopJoin: {:?} inner={}",
+                    join.join_type,
+                    format!("{:?}", build_side).to_lowercase()
+                ),
+                None => format!("NestedLoopJoin: {:?}", join.join_type),
+            };
+            format!("{}{}", join_label, join_details(join))
+        }
+        LogicalPlan::Aggregate(node) if node.group_expr.is_empty() => {
+            aggregate_label("Aggregate", node)
+        }
+        LogicalPlan::Aggregate(node) => aggregate_label("HashAggregate", node),
+        LogicalPlan::Window(node) => format!("WindowAgg: {}", list(&node.window_expr)),
+        LogicalPlan::Sort(node) => format!("Sort: {}", sort_keys(&node.expressions)),
+        LogicalPlan::Distinct(_) => "HashDistinct".to_string(),
+        LogicalPlan::Union(node) if node.all => "Append".to_string(),
+        LogicalPlan::Union(_) => "HashUnion".to_string(),
+        LogicalPlan::Intersect(node) => format!("HashIntersect: all={}", node.all),
+        LogicalPlan::Except(node) => format!("HashExcept: all={}", node.all),
+        LogicalPlan::Subquery(node) => match &node.alias {
+            Some(alias) => format!("SubqueryScan: {}", alias),
+            None => "SubqueryScan".to_string(),
+        },
+        LogicalPlan::RecursiveQuery(node) => {
+            format!("RecursiveUnion: {}, all={}", node.name, node.all)
+        }
+        _ => plan.description(),
+    }
+}
+
+fn scan_details(scan: &TableScanNode) -> String {
+    let mut details = String::new();
+    if !scan.filters.is_empty() {
+        write!(details, " filters=[{}]", list(&scan.filters)).unwrap();
+    }
+    if let Some(columns) = &scan.projected_columns {
+        write!(details, " columns=[{}]", columns.join(", ")).unwrap();
+    }
+    details
+}
+
+fn join_details(join: &JoinNode) -> String {
+    match &join.join_constraint {
+        Some(constraint) => format!(" on {}", constraint),
+        None => String::new(),
+    }
+}
+
+fn aggregate_label(name: &str, node: &AggregateNode) -> String {
+    format!(
+        "{}: group=[{}] aggregates=[{}]",
+        name,
+        list(&node.group_expr),
+        list(&node.aggr_expr)
+    )
+}
+
+fn list(exprs: &[Expression]) -> String {
+    exprs
+        .iter()
+        .map(|expr| expr.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn sort_keys(keys: &[SortExpr]) -> String {
+    keys.iter()
+        .map(|key| {
+            let order = match key.order {
+                SortOrder::Ascending => "ASC",
+                SortOrder::Descending => "DESC",
+            };
+            let nulls = if key.nulls_first {
+                "NULLS FIRST"
+            } else {
+                "NULLS LAST"
+            };
+            format!("{} {} {}", key.expr, order, nulls)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,4 +1,7 @@
-use crate::types::{AggregateFunction, ColumnRef};
+use crate::types::{
+    AggregateFunction, ColumnRef, SortExpr, SortOrder, WindowFrame, WindowFrameBound,
+    WindowFrameUnits, WindowFunction,
+};
 use serde::{Deserialize, Serialize};
 use shared_types::{DataType, Value};
 use std::fmt;
@@ -22,6 +25,13 @@ pub enum Expression {
         expr: Option<Box<Expression>>,
         distinct: bool,
     },
+    Window {
+        func: WindowFunction,
+        args: Vec<Expression>,
+        partition_by: Vec<Expression>,
+        order_by: Vec<SortExpr>,
+        frame: WindowFrame,
+    },
     Case {
         expr: Option<Box<Expression>>,
         when_clauses: Vec<(Expression, Expression)>,
@@ -52,9 +62,19 @@ pub enum Expression {
     },
     Wildcard { table: Option<String> },
     Alias { expr: Box<Expression>, name: String },
+    /// Scalar subquery, yielding the single value of its single column
     Subquery {
         subquery: Box<crate::logical_plan::LogicalPlan>,
     },
+    Exists {
+        subquery: Box<crate::logical_plan::LogicalPlan>,
+        negated: bool,
+    },
+    InSubquery {
+        expr: Box<Expression>,
+        subquery: Box<crate::logical_plan::LogicalPlan>,
+        negated: bool,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -78,6 +98,8 @@ pub enum BinaryOperator {
     BitwiseXor,
     BitwiseShiftLeft,
     BitwiseShiftRight,
+    IsDistinctFrom,
+    IsNotDistinctFrom,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -151,6 +173,30 @@ impl Expression {
             distinct,
         }
     }
+    /// Window function call. Without an explicit frame, windows with an ORDER BY run
+    /// from the partition start to the current row's last peer, others span the partition.
+    pub fn window(
+        func: WindowFunction,
+        args: Vec<Expression>,
+        partition_by: Vec<Expression>,
+        order_by: Vec<SortExpr>,
+        frame: Option<WindowFrame>,
+    ) -> Self {
+        let frame = frame.unwrap_or_else(|| {
+            if order_by.is_empty() {
+                WindowFrame::whole_partition()
+            } else {
+                WindowFrame::running()
+            }
+        });
+        Expression::Window {
+            func,
+            args,
+            partition_by,
+            order_by,
+            frame,
+        }
+    }
     pub fn cast(expr: Expression, data_type: DataType) -> Self {
         Expression::Cast {
             expr: Box::new(expr),
@@ -191,6 +237,55 @@ impl Expression {
             case_insensitive,
         }
     }
+    pub fn scalar_subquery(subquery: crate::logical_plan::LogicalPlan) -> Self {
+        Expression::Subquery {
+            subquery: Box::new(subquery),
+        }
+    }
+    pub fn exists(subquery: crate::logical_plan::LogicalPlan, negated: bool) -> Self {
+        Expression::Exists {
+            subquery: Box::new(subquery),
+            negated,
+        }
+    }
+    pub fn in_subquery(
+        expr: Expression,
+        subquery: crate::logical_plan::LogicalPlan,
+        negated: bool,
+    ) -> Self {
+        Expression::InSubquery {
+            expr: Box::new(expr),
+            subquery: Box::new(subquery),
+            negated,
+        }
+    }
+    /// AND of all `predicates`, or `None` when there are none
+    pub fn conjunction(predicates: Vec<Expression>) -> Option<Self> {
+        predicates
+            .into_iter()
+            .reduce(|left, right| Expression::binary_op(left, BinaryOperator::And, right))
+    }
+    /// The operands of a chain of ANDs; any other expression is a single conjunct
+    pub fn split_conjunction(self) -> Vec<Expression> {
+        match self {
+            Expression::BinaryOp {
+                left,
+                op: BinaryOperator::And,
+                right,
+            } => {
+                let mut conjuncts = left.split_conjunction();
+                conjuncts.extend(right.split_conjunction());
+                conjuncts
+            }
+            other => vec![other],
+        }
+    }
+    pub fn is_subquery(&self) -> bool {
+        matches!(
+            self,
+            Expression::Subquery { .. } | Expression::Exists { .. } | Expression::InSubquery { .. }
+        )
+    }
     pub fn wildcard() -> Self {
         Expression::Wildcard { table: None }
     }
@@ -199,6 +294,92 @@ impl Expression {
             table: Some(table.into()),
         }
     }
+    /// The direct operands of the expression. Subquery plans are not operands, they
+    /// are reached through `subquery_plans_mut`.
+    pub fn children(&self) -> Vec<&Expression> {
+        match self {
+            Expression::BinaryOp { left, right, .. } => vec![left, right],
+            Expression::UnaryOp { expr, .. }
+            | Expression::Cast { expr, .. }
+            | Expression::Alias { expr, .. }
+            | Expression::InSubquery { expr, .. } => vec![expr],
+            Expression::IsNull(expr) | Expression::IsNotNull(expr) => vec![expr],
+            Expression::Function { args, .. } => args.iter().collect(),
+            Expression::Aggregate { expr, .. } => expr.iter().map(|expr| expr.as_ref()).collect(),
+            Expression::Window {
+                args,
+                partition_by,
+                order_by,
+                ..
+            } => args
+                .iter()
+                .chain(partition_by)
+                .chain(order_by.iter().map(|sort_expr| sort_expr.expr.as_ref()))
+                .collect(),
+            Expression::Case {
+                expr,
+                when_clauses,
+                else_clause,
+            } => expr
+                .iter()
+                .map(|expr| expr.as_ref())
+                .chain(when_clauses.iter().flat_map(|(when_expr, then_expr)| [when_expr, then_expr]))
+                .chain(else_clause.iter().map(|expr| expr.as_ref()))
+                .collect(),
+            Expression::In { expr, list, .. } => std::iter::once(expr.as_ref()).chain(list).collect(),
+            Expression::Between {
+                expr, low, high, ..
+            } => vec![expr, low, high],
+            Expression::Like { expr, pattern, .. } => vec![expr, pattern],
+            Expression::Literal(_)
+            | Expression::Column(_)
+            | Expression::Wildcard { .. }
+            | Expression::Subquery { .. }
+            | Expression::Exists { .. } => vec![],
+        }
+    }
+    pub fn children_mut(&mut self) -> Vec<&mut Expression> {
+        match self {
+            Expression::BinaryOp { left, right, .. } => vec![left, right],
+            Expression::UnaryOp { expr, .. }
+            | Expression::Cast { expr, .. }
+            | Expression::Alias { expr, .. }
+            | Expression::InSubquery { expr, .. } => vec![expr],
+            Expression::IsNull(expr) | Expression::IsNotNull(expr) => vec![expr],
+            Expression::Function { args, .. } => args.iter_mut().collect(),
+            Expression::Aggregate { expr, .. } => expr.iter_mut().map(|expr| expr.as_mut()).collect(),
+            Expression::Window {
+                args,
+                partition_by,
+                order_by,
+                ..
+            } => args
+                .iter_mut()
+                .chain(partition_by)
+                .chain(order_by.iter_mut().map(|sort_expr| sort_expr.expr.as_mut()))
+                .collect(),
+            Expression::Case {
+                expr,
+                when_clauses,
+                else_clause,
+            } => expr
+                .iter_mut()
+                .map(|expr| expr.as_mut())
+                .chain(when_clauses.iter_mut().flat_map(|(when_expr, then_expr)| [when_expr, then_expr]))
+                .chain(else_clause.iter_mut().map(|expr| expr.as_mut()))
+                .collect(),
+            Expression::In { expr, list, .. } => std::iter::once(expr.as_mut()).chain(list).collect(),
+            Expression::Between {
+                expr, low, high, ..
+            } => vec![expr, low, high],
+            Expression::Like { expr, pattern, .. } => vec![expr, pattern],
+            Expression::Literal(_)
+            | Expression::Column(_)
+            | Expression::Wildcard { .. }
+            | Expression::Subquery { .. }
+            | Expression::Exists { .. } => vec![],
+        }
+    }
     pub fn column_refs(&self) -> Vec<&ColumnRef> {
         let mut refs = Vec::new();
         self.collect_column_refs(&mut refs);
@@ -207,62 +388,72 @@ impl Expression {
     fn collect_column_refs<'a>(&'a self, refs: &mut Vec<&'a ColumnRef>) {
         match self {
             Expression::Column(col_ref) => refs.push(col_ref),
-            Expression::BinaryOp { left, right, .. } => {
-                left.collect_column_refs(refs);
-                right.collect_column_refs(refs);
-            }
-            Expression::UnaryOp { expr, .. } => expr.collect_column_refs(refs),
-            Expression::Function { args, .. } => {
-                for arg in args {
-                    arg.collect_column_refs(refs);
+            _ => {
+                for child in self.children() {
+                    child.collect_column_refs(refs);
                 }
             }
-            Expression::Aggregate { expr, .. } => {
-                if let Some(expr) = expr {
-                    expr.collect_column_refs(refs);
+        }
+    }
+    /// Mutable counterpart of `column_refs`, for rebinding columns after a rewrite
+    pub fn column_refs_mut(&mut self) -> Vec<&mut ColumnRef> {
+        let mut refs = Vec::new();
+        self.collect_column_refs_mut(&mut refs);
+        refs
+    }
+    fn collect_column_refs_mut<'a>(&'a mut self, refs: &mut Vec<&'a mut ColumnRef>) {
+        match self {
+            Expression::Column(col_ref) => refs.push(col_ref),
+            _ => {
+                for child in self.children_mut() {
+                    child.collect_column_refs_mut(refs);
                 }
             }
-            Expression::Case {
-                expr,
-                when_clauses,
-                else_clause,
-            } => {
-                if let Some(expr) = expr {
-                    expr.collect_column_refs(refs);
-                }
-                for (when_expr, then_expr) in when_clauses {
-                    when_expr.collect_column_refs(refs);
-                    then_expr.collect_column_refs(refs);
-                }
-                if let Some(else_expr) = else_clause {
-                    else_expr.collect_column_refs(refs);
-                }
+        }
+    }
+    /// Plans of every subquery in the expression, outermost first
+    pub fn subquery_plans_mut(&mut self) -> Vec<&mut crate::logical_plan::LogicalPlan> {
+        let mut plans = Vec::new();
+        self.collect_subquery_plans_mut(&mut plans);
+        plans
+    }
+    fn collect_subquery_plans_mut<'a>(
+        &'a mut self,
+        plans: &mut Vec<&'a mut crate::logical_plan::LogicalPlan>,
+    ) {
+        match self {
+            Expression::Subquery { subquery } | Expression::Exists { subquery, .. } => {
+                plans.push(subquery)
             }
-            Expression::Cast { expr, .. } => expr.collect_column_refs(refs),
-            Expression::IsNull(expr) | Expression::IsNotNull(expr) => {
-                expr.collect_column_refs(refs)
+            Expression::InSubquery { expr, subquery, .. } => {
+                plans.push(subquery);
+                expr.collect_subquery_plans_mut(plans);
             }
-            Expression::In { expr, list, .. } => {
-                expr.collect_column_refs(refs);
-                for item in list {
-                    item.collect_column_refs(refs);
+            _ => {
+                for child in self.children_mut() {
+                    child.collect_subquery_plans_mut(plans);
                 }
             }
-            Expression::Between {
-                expr, low, high, ..
-            } => {
-                expr.collect_column_refs(refs);
-                low.collect_column_refs(refs);
-                high.collect_column_refs(refs);
-            }
-            Expression::Like { expr, pattern, .. } => {
-                expr.collect_column_refs(refs);
-                pattern.collect_column_refs(refs);
-            }
-            Expression::Alias { expr, .. } => expr.collect_column_refs(refs),
-            Expression::Subquery { .. } => {
-            }
-            Expression::Literal(_) | Expression::Wildcard { .. } => {
+        }
+    }
+    pub fn is_window(&self) -> bool {
+        matches!(self, Expression::Window { .. })
+    }
+    /// Window function calls in this expression, outermost first. Window calls
+    /// cannot nest, so their arguments are not searched.
+    pub fn window_exprs(&self) -> Vec<&Expression> {
+        let mut windows = Vec::new();
+        self.collect_window_exprs(&mut windows);
+        windows
+    }
+    fn collect_window_exprs<'a>(&'a self, windows: &mut Vec<&'a Expression>) {
+        match self {
+            Expression::Window { .. } => windows.push(self),
+            Expression::Aggregate { .. } => {}
+            _ => {
+                for child in self.children() {
+                    child.collect_window_exprs(windows);
+                }
             }
         }
     }
@@ -284,6 +475,15 @@ impl Expression {
             Expression::Aggregate { expr, .. } => {
                 expr.as_ref().map_or(true, |e| e.is_deterministic())
             }
+            Expression::Window {
+                args,
+                partition_by,
+                order_by,
+                ..
+            } => {
+                args.iter().chain(partition_by).all(|e| e.is_deterministic())
+                    && order_by.iter().all(|s| s.expr.is_deterministic())
+            }
             Expression::Case {
                 expr,
                 when_clauses,
@@ -308,7 +508,9 @@ impl Expression {
                 expr.is_deterministic() && pattern.is_deterministic()
             }
             Expression::Alias { expr, .. } => expr.is_deterministic(),
-            Expression::Subquery { .. } => {
+            Expression::Subquery { .. }
+            | Expression::Exists { .. }
+            | Expression::InSubquery { .. } => {
                 false
             }
         }
@@ -357,8 +559,122 @@ impl fmt::Display for Expression {
                     write!(f, "*")
                 }
             }
+            Expression::Window {
+                func,
+                args,
+                partition_by,
+                order_by,
+                frame,
+            } => {
+                match func {
+                    WindowFunction::Aggregate(func) => write!(f, "{:?}(", func)?,
+                    other => write!(f, "{:?}(", other)?,
+                }
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ") OVER (")?;
+                if !partition_by.is_empty() {
+                    write!(f, "PARTITION BY ")?;
+                    for (i, expr) in partition_by.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", expr)?;
+                    }
+                    write!(f, " ")?;
+                }
+                if !order_by.is_empty() {
+                    write!(f, "ORDER BY ")?;
+                    for (i, sort_expr) in order_by.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        let order = match sort_expr.order {
+                            SortOrder::Ascending => "ASC",
+                            SortOrder::Descending => "DESC",
+                        };
+                        write!(f, "{} {}", sort_expr.expr, order)?;
+                    }
+                    write!(f, " ")?;
+                }
+                let units = match frame.units {
+                    WindowFrameUnits::Rows => "ROWS",
+                    WindowFrameUnits::Range => "RANGE",
+                };
+                write!(f, "{} BETWEEN {} AND {})", units, frame.start, frame.end)
+            }
             Expression::Alias { expr, name } => write!(f, "{} AS {}", expr, name),
-            _ => write!(f, "<complex_expression>"),
+            Expression::Subquery { subquery } => write!(f, "({})", subquery),
+            Expression::Exists { subquery, negated } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{}EXISTS ({})", not, subquery)
+            }
+            Expression::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{} {}IN ({})", expr, not, subquery)
+            }
+            Expression::Case {
+                expr,
+                when_clauses,
+                else_clause,
+            } => {
+                write!(f, "CASE")?;
+                if let Some(expr) = expr {
+                    write!(f, " {}", expr)?;
+                }
+                for (condition, result) in when_clauses {
+                    write!(f, " WHEN {} THEN {}", condition, result)?;
+                }
+                if let Some(else_clause) = else_clause {
+                    write!(f, " ELSE {}", else_clause)?;
+                }
+                write!(f, " END")
+            }
+            Expression::Cast { expr, data_type } => write!(f, "CAST({} AS {:?})", expr, data_type),
+            Expression::IsNull(expr) => write!(f, "({} IS NULL)", expr),
+            Expression::IsNotNull(expr) => write!(f, "({} IS NOT NULL)", expr),
+            Expression::In {
+                expr,
+                list,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "({} {}IN (", expr, not)?;
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "))")
+            }
+            Expression::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "({} {}BETWEEN {} AND {})", expr, not, low, high)
+            }
+            Expression::Like {
+                expr,
+                pattern,
+                negated,
+                case_insensitive,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                let like = if *case_insensitive { "ILIKE" } else { "LIKE" };
+                write!(f, "({} {}{} {})", expr, not, like, pattern)
+            }
         }
     }
 }
@@ -385,6 +701,8 @@ impl fmt::Display for BinaryOperator {
             BinaryOperator::BitwiseXor => "^",
             BinaryOperator::BitwiseShiftLeft => "<<",
             BinaryOperator::BitwiseShiftRight => ">>",
+            BinaryOperator::IsDistinctFrom => "IS DISTINCT FROM",
+            BinaryOperator::IsNotDistinctFrom => "IS NOT DISTINCT FROM",
         };
         write!(f, "{}", op_str)
     }
@@ -400,4 +718,16 @@ impl fmt::Display for UnaryOperator {
         };
         write!(f, "{}", op_str)
     }
+}
+
+impl fmt::Display for WindowFrameBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindowFrameBound::UnboundedPreceding => write!(f, "UNBOUNDED PRECEDING"),
+            WindowFrameBound::Preceding(n) => write!(f, "{} PRECEDING", n),
+            WindowFrameBound::CurrentRow => write!(f, "CURRENT ROW"),
+            WindowFrameBound::Following(n) => write!(f, "{} FOLLOWING", n),
+            WindowFrameBound::UnboundedFollowing => write!(f, "UNBOUNDED FOLLOWING"),
+        }
+    }
 }
\ No newline at end of file
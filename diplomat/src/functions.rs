@@ -0,0 +1,136 @@
+//! Planner-side checking of scalar function calls against the built-in catalog
+
+use shared_types::{DataType, ScalarFunction, SignatureError};
+
+use crate::{common::LogicalPlanError, expression::Expression, types::LogicalSchema};
+
+/// Check every scalar function call in `expr`: the function must exist and its
+/// arguments must match the signature wherever their types are known
+pub fn check_function_calls(
+    expr: &Expression,
+    schema: &LogicalSchema,
+) -> Result<(), LogicalPlanError> {
+    match expr {
+        Expression::Function { .. } => known_type(expr, schema).map(|_| ()),
+        Expression::BinaryOp { left, right, .. } => {
+            check_function_calls(left, schema)?;
+            check_function_calls(right, schema)
+        }
+        Expression::UnaryOp { expr, .. }
+        | Expression::Cast { expr, .. }
+        | Expression::Alias { expr, .. }
+        | Expression::IsNull(expr)
+        | Expression::IsNotNull(expr) => check_function_calls(expr, schema),
+        Expression::Aggregate { expr, .. } => match expr {
+            Some(expr) => check_function_calls(expr, schema),
+            None => Ok(()),
+        },
+        Expression::Window {
+            args,
+            partition_by,
+            order_by,
+            ..
+        } => {
+            for arg in args.iter().chain(partition_by) {
+                check_function_calls(arg, schema)?;
+            }
+            for sort_expr in order_by {
+                check_function_calls(&sort_expr.expr, schema)?;
+            }
+            Ok(())
+        }
+        Expression::Case {
+            expr,
+            when_clauses,
+            else_clause,
+        } => {
+            if let Some(expr) = expr {
+                check_function_calls(expr, schema)?;
+            }
+            for (when_expr, then_expr) in when_clauses {
+                check_function_calls(when_expr, schema)?;
+                check_function_calls(then_expr, schema)?;
+            }
+            match else_clause {
+                Some(else_expr) => check_function_calls(else_expr, schema),
+                None => Ok(()),
+            }
+        }
+        Expression::In { expr, list, .. } => {
+            check_function_calls(expr, schema)?;
+            for item in list {
+                check_function_calls(item, schema)?;
+            }
+            Ok(())
+        }
+        Expression::Between {
+            expr, low, high, ..
+        } => {
+            check_function_calls(expr, schema)?;
+            check_function_calls(low, schema)?;
+            check_function_calls(high, schema)
+        }
+        Expression::Like { expr, pattern, .. } => {
+            check_function_calls(expr, schema)?;
+            check_function_calls(pattern, schema)
+        }
+        // Subqueries were checked against their own inputs when they were planned
+        Expression::InSubquery { expr, .. } => check_function_calls(expr, schema),
+        Expression::Literal(_)
+        | Expression::Column(_)
+        | Expression::Wildcard { .. }
+        | Expression::Subquery { .. }
+        | Expression::Exists { .. } => Ok(()),
+    }
+}
+
+/// Type of `expr` where it can be told without running the query: literals, columns
+/// of `schema`, casts and function calls. Function calls are checked along the way.
+pub fn known_type(
+    expr: &Expression,
+    schema: &LogicalSchema,
+) -> Result<Option<DataType>, LogicalPlanError> {
+    match expr {
+        Expression::Literal(value) => Ok(value.data_type()),
+        Expression::Column(column) => Ok(schema
+            .find_column(&column.name)
+            .map(|column| column.data_type.clone())),
+        Expression::Cast { data_type, .. } => Ok(Some(data_type.clone())),
+        Expression::Alias { expr, .. } => known_type(expr, schema),
+        Expression::Subquery { subquery } => Ok(subquery
+            .schema()
+            .columns
+            .first()
+            .map(|column| column.data_type.clone())),
+        Expression::Function { name, args } => {
+            let func = ScalarFunction::from_name(name)
+                .ok_or_else(|| signature_error(SignatureError::UnknownFunction(name.clone())))?;
+            let arg_types = args
+                .iter()
+                .map(|arg| {
+                    if !matches!(arg, Expression::Function { .. }) {
+                        check_function_calls(arg, schema)?;
+                    }
+                    known_type(arg, schema)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            func.return_type(&arg_types).map_err(signature_error)
+        }
+        _ => Ok(None),
+    }
+}
+
+fn signature_error(error: SignatureError) -> LogicalPlanError {
+    match error {
+        SignatureError::TypeMismatch {
+            function,
+            position,
+            expected,
+            found,
+        } => LogicalPlanError::TypeMismatch {
+            expected: format!("{} for argument {} of {}", expected, position + 1, function),
+            found: format!("{:?}", found),
+        },
+        other => LogicalPlanError::InvalidExpression(other.to_string()),
+    }
+}
@@ -1,9 +1,14 @@
+pub mod binder;
 pub mod common;
+pub mod explain;
 pub mod expression;
+pub mod functions;
 pub mod logical_plan;
 pub mod operator;
 pub mod optimizer;
 pub mod plan_builder;
 pub mod sql_parser;
+pub mod type_check;
 pub mod types;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+pub mod visitor;
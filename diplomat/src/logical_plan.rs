@@ -4,7 +4,9 @@ use shared_types::DataType;
 use crate::{
     common::LogicalPlanError,
     expression::Expression,
-    types::{JoinType, LogicalSchema, PlanStatistics, SortExpr, TableRef},
+    types::{
+        ExplainFormat, JoinStrategy, JoinType, LogicalSchema, PlanStatistics, SortExpr, TableRef,
+    },
 };
 
 pub trait LogicalPlanNode: fmt::Debug + Clone {
@@ -27,6 +29,7 @@ pub enum LogicalPlan {
     Filter(FilterNode),
     Join(JoinNode),
     Aggregate(AggregateNode),
+    Window(WindowNode),
     Sort(SortNode),
     Limit(LimitNode),
     Insert(InsertNode),
@@ -34,10 +37,17 @@ pub enum LogicalPlan {
     Delete(DeleteNode),
     CreateTable(CreateTableNode),
     DropTable(DropTableNode),
+    Analyze(AnalyzeNode),
+    Explain(ExplainNode),
     Union(UnionNode),
+    Intersect(IntersectNode),
+    Except(ExceptNode),
     Distinct(DistinctNode),
     Values(ValuesNode),
     Subquery(SubqueryNode),
+    CteScan(CteScanNode),
+    With(WithNode),
+    RecursiveQuery(RecursiveQueryNode),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -46,6 +56,9 @@ pub struct TableScanNode {
     pub schema: LogicalSchema,
     pub projected_columns: Option<Vec<String>>,
     pub filters: Vec<Expression>,
+    /// Number of workers the scan is split across, chosen by the cost-based optimizer
+    #[serde(default)]
+    pub parallelism: Option<usize>,
     pub statistics: PlanStatistics,
 }
 
@@ -70,6 +83,9 @@ pub struct JoinNode {
     pub right: Box<LogicalPlan>,
     pub join_type: JoinType,
     pub join_constraint: Option<Expression>,
+    /// Algorithm and build side, chosen by the cost-based optimizer
+    #[serde(default)]
+    pub strategy: Option<JoinStrategy>,
     pub schema: LogicalSchema,
     pub statistics: PlanStatistics,
 }
@@ -83,6 +99,15 @@ pub struct AggregateNode {
     pub statistics: PlanStatistics,
 }
 
+/// Evaluates window functions, appending one column per window expression to the input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowNode {
+    pub window_expr: Vec<Expression>,
+    pub input: Box<LogicalPlan>,
+    pub schema: LogicalSchema,
+    pub statistics: PlanStatistics,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SortNode {
     pub expressions: Vec<SortExpr>,
@@ -185,6 +210,25 @@ pub struct DropTableNode {
     pub statistics: PlanStatistics,
 }
 
+/// Gathers the statistics of `tables` from a sampled scan of each, for the optimizer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalyzeNode {
+    pub tables: Vec<TableRef>,
+    /// Columns to gather statistics for, or every column
+    pub columns: Option<Vec<String>>,
+    pub schema: LogicalSchema,
+    pub statistics: PlanStatistics,
+}
+
+/// Shows how `plan` is executed instead of running it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExplainNode {
+    pub plan: Box<LogicalPlan>,
+    pub format: ExplainFormat,
+    pub schema: LogicalSchema,
+    pub statistics: PlanStatistics,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UnionNode {
     pub left: Box<LogicalPlan>,
@@ -194,6 +238,26 @@ pub struct UnionNode {
     pub statistics: PlanStatistics,
 }
 
+/// Rows found in both inputs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntersectNode {
+    pub left: Box<LogicalPlan>,
+    pub right: Box<LogicalPlan>,
+    pub all: bool,
+    pub schema: LogicalSchema,
+    pub statistics: PlanStatistics,
+}
+
+/// Left rows not found in the right input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExceptNode {
+    pub left: Box<LogicalPlan>,
+    pub right: Box<LogicalPlan>,
+    pub all: bool,
+    pub schema: LogicalSchema,
+    pub statistics: PlanStatistics,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DistinctNode {
     pub input: Box<LogicalPlan>,
@@ -214,6 +278,51 @@ pub struct SubqueryNode {
     pub statistics: PlanStatistics,
 }
 
+/// Reads a common table expression materialized by an enclosing `With`. Inside the
+/// recursive term of a `RecursiveQuery` of the same name, it reads the rows produced
+/// by the previous iteration instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CteScanNode {
+    pub name: String,
+    pub alias: Option<String>,
+    pub schema: LogicalSchema,
+    pub statistics: PlanStatistics,
+}
+
+impl CteScanNode {
+    pub fn effective_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Materializes each common table expression once, in order, then runs `input`,
+/// whose `CteScan`s read the materialized rows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WithNode {
+    pub ctes: Vec<CteDefinition>,
+    pub input: Box<LogicalPlan>,
+    pub statistics: PlanStatistics,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CteDefinition {
+    pub name: String,
+    pub plan: LogicalPlan,
+}
+
+/// `WITH RECURSIVE` fixpoint: `static_term` runs once, then `recursive_term` runs
+/// against the rows of the previous iteration until it yields no new rows. Without
+/// `all`, rows already produced are discarded as duplicates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecursiveQueryNode {
+    pub name: String,
+    pub static_term: Box<LogicalPlan>,
+    pub recursive_term: Box<LogicalPlan>,
+    pub all: bool,
+    pub schema: LogicalSchema,
+    pub statistics: PlanStatistics,
+}
+
 impl LogicalPlanNode for TableScanNode {
     fn schema(&self) -> &LogicalSchema {
         &self.schema
@@ -324,6 +433,53 @@ impl LogicalPlanNode for FilterNode {
     }
 }
 
+impl LogicalPlanNode for WindowNode {
+    fn schema(&self) -> &LogicalSchema {
+        &self.schema
+    }
+    fn children(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+    fn children_mut(&mut self) -> Vec<&mut LogicalPlan> {
+        vec![&mut self.input]
+    }
+    fn with_new_children(
+        &self,
+        mut children: Vec<LogicalPlan>,
+    ) -> Result<LogicalPlan, LogicalPlanError> {
+        if children.len() != 1 {
+            return Err(LogicalPlanError::InternalError(
+                "Window should have exactly one child".to_string(),
+            ));
+        }
+        let mut new_node = self.clone();
+        new_node.input = Box::new(children.remove(0));
+        Ok(LogicalPlan::Window(new_node))
+    }
+    fn statistics(&self) -> &PlanStatistics {
+        &self.statistics
+    }
+    fn validate(&self) -> Result<(), LogicalPlanError> {
+        if let Some(expr) = self.window_expr.iter().find(|expr| !expr.is_window()) {
+            return Err(LogicalPlanError::ValidationError(format!(
+                "Window node expression is not a window function: {}",
+                expr
+            )));
+        }
+        if self.schema.column_count() != self.input.schema().column_count() + self.window_expr.len()
+        {
+            return Err(LogicalPlanError::ValidationError(
+                "Window schema must extend the input schema by one column per window expression"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+    fn description(&self) -> String {
+        format!("Window: {} expressions", self.window_expr.len())
+    }
+}
+
 impl LogicalPlan {
     pub fn schema(&self) -> &LogicalSchema {
         match self {
@@ -332,6 +488,7 @@ impl LogicalPlan {
             LogicalPlan::Filter(node) => node.schema(),
             LogicalPlan::Join(node) => &node.schema,
             LogicalPlan::Aggregate(node) => &node.schema,
+            LogicalPlan::Window(node) => node.schema(),
             LogicalPlan::Sort(node) => node.input.schema(),
             LogicalPlan::Limit(node) => node.input.schema(),
             LogicalPlan::Insert(node) => &node.schema,
@@ -339,10 +496,74 @@ impl LogicalPlan {
             LogicalPlan::Delete(node) => &node.schema,
             LogicalPlan::CreateTable(node) => &node.schema,
             LogicalPlan::DropTable(node) => &node.schema,
+            LogicalPlan::Analyze(node) => &node.schema,
+            LogicalPlan::Explain(node) => &node.schema,
             LogicalPlan::Union(node) => &node.schema,
+            LogicalPlan::Intersect(node) => &node.schema,
+            LogicalPlan::Except(node) => &node.schema,
             LogicalPlan::Distinct(node) => node.input.schema(),
             LogicalPlan::Values(node) => &node.schema,
             LogicalPlan::Subquery(node) => node.subquery.schema(),
+            LogicalPlan::CteScan(node) => &node.schema,
+            LogicalPlan::With(node) => node.input.schema(),
+            LogicalPlan::RecursiveQuery(node) => &node.schema,
+        }
+    }
+    /// Estimated output of this node, filled in by the cost-based optimizer
+    pub fn statistics(&self) -> &PlanStatistics {
+        match self {
+            LogicalPlan::TableScan(node) => &node.statistics,
+            LogicalPlan::Projection(node) => &node.statistics,
+            LogicalPlan::Filter(node) => &node.statistics,
+            LogicalPlan::Join(node) => &node.statistics,
+            LogicalPlan::Aggregate(node) => &node.statistics,
+            LogicalPlan::Window(node) => &node.statistics,
+            LogicalPlan::Sort(node) => &node.statistics,
+            LogicalPlan::Limit(node) => &node.statistics,
+            LogicalPlan::Insert(node) => &node.statistics,
+            LogicalPlan::Update(node) => &node.statistics,
+            LogicalPlan::Delete(node) => &node.statistics,
+            LogicalPlan::CreateTable(node) => &node.statistics,
+            LogicalPlan::DropTable(node) => &node.statistics,
+            LogicalPlan::Analyze(node) => &node.statistics,
+            LogicalPlan::Explain(node) => &node.statistics,
+            LogicalPlan::Union(node) => &node.statistics,
+            LogicalPlan::Intersect(node) => &node.statistics,
+            LogicalPlan::Except(node) => &node.statistics,
+            LogicalPlan::Distinct(node) => &node.statistics,
+            LogicalPlan::Values(node) => &node.statistics,
+            LogicalPlan::Subquery(node) => &node.statistics,
+            LogicalPlan::CteScan(node) => &node.statistics,
+            LogicalPlan::With(node) => &node.statistics,
+            LogicalPlan::RecursiveQuery(node) => &node.statistics,
+        }
+    }
+    pub fn statistics_mut(&mut self) -> &mut PlanStatistics {
+        match self {
+            LogicalPlan::TableScan(node) => &mut node.statistics,
+            LogicalPlan::Projection(node) => &mut node.statistics,
+            LogicalPlan::Filter(node) => &mut node.statistics,
+            LogicalPlan::Join(node) => &mut node.statistics,
+            LogicalPlan::Aggregate(node) => &mut node.statistics,
+            LogicalPlan::Window(node) => &mut node.statistics,
+            LogicalPlan::Sort(node) => &mut node.statistics,
+            LogicalPlan::Limit(node) => &mut node.statistics,
+            LogicalPlan::Insert(node) => &mut node.statistics,
+            LogicalPlan::Update(node) => &mut node.statistics,
+            LogicalPlan::Delete(node) => &mut node.statistics,
+            LogicalPlan::CreateTable(node) => &mut node.statistics,
+            LogicalPlan::DropTable(node) => &mut node.statistics,
+            LogicalPlan::Analyze(node) => &mut node.statistics,
+            LogicalPlan::Explain(node) => &mut node.statistics,
+            LogicalPlan::Union(node) => &mut node.statistics,
+            LogicalPlan::Intersect(node) => &mut node.statistics,
+            LogicalPlan::Except(node) => &mut node.statistics,
+            LogicalPlan::Distinct(node) => &mut node.statistics,
+            LogicalPlan::Values(node) => &mut node.statistics,
+            LogicalPlan::Subquery(node) => &mut node.statistics,
+            LogicalPlan::CteScan(node) => &mut node.statistics,
+            LogicalPlan::With(node) => &mut node.statistics,
+            LogicalPlan::RecursiveQuery(node) => &mut node.statistics,
         }
     }
     pub fn children(&self) -> Vec<&LogicalPlan> {
@@ -352,6 +573,7 @@ impl LogicalPlan {
             LogicalPlan::Filter(node) => vec![&node.input],
             LogicalPlan::Join(node) => vec![&node.left, &node.right],
             LogicalPlan::Aggregate(node) => vec![&node.input],
+            LogicalPlan::Window(node) => node.children(),
             LogicalPlan::Sort(node) => vec![&node.input],
             LogicalPlan::Limit(node) => vec![&node.input],
             LogicalPlan::Insert(node) => match &node.source {
@@ -368,10 +590,155 @@ impl LogicalPlan {
             LogicalPlan::Delete(_) => vec![],
             LogicalPlan::CreateTable(_) => vec![],
             LogicalPlan::DropTable(_) => vec![],
+            LogicalPlan::Analyze(_) => vec![],
+            LogicalPlan::Explain(node) => vec![&node.plan],
             LogicalPlan::Union(node) => vec![&node.left, &node.right],
+            LogicalPlan::Intersect(node) => vec![&node.left, &node.right],
+            LogicalPlan::Except(node) => vec![&node.left, &node.right],
             LogicalPlan::Distinct(node) => vec![&node.input],
             LogicalPlan::Values(_) => vec![],
             LogicalPlan::Subquery(node) => vec![&node.subquery],
+            LogicalPlan::CteScan(_) => vec![],
+            LogicalPlan::With(node) => node
+                .ctes
+                .iter()
+                .map(|cte| &cte.plan)
+                .chain(std::iter::once(node.input.as_ref()))
+                .collect(),
+            LogicalPlan::RecursiveQuery(node) => vec![&node.static_term, &node.recursive_term],
+        }
+    }
+    pub fn children_mut(&mut self) -> Vec<&mut LogicalPlan> {
+        match self {
+            LogicalPlan::TableScan(_) => vec![],
+            LogicalPlan::Projection(node) => vec![&mut node.input],
+            LogicalPlan::Filter(node) => vec![&mut node.input],
+            LogicalPlan::Join(node) => vec![&mut node.left, &mut node.right],
+            LogicalPlan::Aggregate(node) => vec![&mut node.input],
+            LogicalPlan::Window(node) => node.children_mut(),
+            LogicalPlan::Sort(node) => vec![&mut node.input],
+            LogicalPlan::Limit(node) => vec![&mut node.input],
+            LogicalPlan::Insert(node) => match &mut node.source {
+                InsertSource::Query(plan) => vec![plan],
+                InsertSource::Values(_) => vec![],
+            },
+            LogicalPlan::Update(node) => {
+                if let Some(from) = &mut node.from {
+                    vec![from]
+                } else {
+                    vec![]
+                }
+            }
+            LogicalPlan::Delete(_) => vec![],
+            LogicalPlan::CreateTable(_) => vec![],
+            LogicalPlan::DropTable(_) => vec![],
+            LogicalPlan::Analyze(_) => vec![],
+            LogicalPlan::Explain(node) => vec![&mut node.plan],
+            LogicalPlan::Union(node) => vec![&mut node.left, &mut node.right],
+            LogicalPlan::Intersect(node) => vec![&mut node.left, &mut node.right],
+            LogicalPlan::Except(node) => vec![&mut node.left, &mut node.right],
+            LogicalPlan::Distinct(node) => vec![&mut node.input],
+            LogicalPlan::Values(_) => vec![],
+            LogicalPlan::Subquery(node) => vec![&mut node.subquery],
+            LogicalPlan::CteScan(_) => vec![],
+            LogicalPlan::With(node) => node
+                .ctes
+                .iter_mut()
+                .map(|cte| &mut cte.plan)
+                .chain(std::iter::once(node.input.as_mut()))
+                .collect(),
+            LogicalPlan::RecursiveQuery(node) => {
+                vec![&mut node.static_term, &mut node.recursive_term]
+            }
+        }
+    }
+    /// The expressions held by this node itself, not by its children
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match self {
+            LogicalPlan::TableScan(node) => node.filters.iter().collect(),
+            LogicalPlan::Projection(node) => node.expressions.iter().collect(),
+            LogicalPlan::Filter(node) => vec![&node.predicate],
+            LogicalPlan::Join(node) => node.join_constraint.iter().collect(),
+            LogicalPlan::Aggregate(node) => node
+                .group_expr
+                .iter()
+                .chain(node.aggr_expr.iter())
+                .collect(),
+            LogicalPlan::Window(node) => node.window_expr.iter().collect(),
+            LogicalPlan::Sort(node) => node
+                .expressions
+                .iter()
+                .map(|sort_expr| sort_expr.expr.as_ref())
+                .collect(),
+            LogicalPlan::Insert(node) => match &node.source {
+                InsertSource::Values(rows) => rows.iter().flatten().collect(),
+                InsertSource::Query(_) => vec![],
+            },
+            LogicalPlan::Update(node) => node
+                .assignments
+                .iter()
+                .map(|assignment| &assignment.value)
+                .chain(node.filter.iter())
+                .collect(),
+            LogicalPlan::Delete(node) => node.filter.iter().collect(),
+            LogicalPlan::Values(node) => node.values.iter().flatten().collect(),
+            LogicalPlan::Limit(_)
+            | LogicalPlan::CreateTable(_)
+            | LogicalPlan::DropTable(_)
+            | LogicalPlan::Analyze(_)
+            | LogicalPlan::Explain(_)
+            | LogicalPlan::Union(_)
+            | LogicalPlan::Intersect(_)
+            | LogicalPlan::Except(_)
+            | LogicalPlan::Distinct(_)
+            | LogicalPlan::Subquery(_)
+            | LogicalPlan::CteScan(_)
+            | LogicalPlan::With(_)
+            | LogicalPlan::RecursiveQuery(_) => vec![],
+        }
+    }
+    pub fn expressions_mut(&mut self) -> Vec<&mut Expression> {
+        match self {
+            LogicalPlan::TableScan(node) => node.filters.iter_mut().collect(),
+            LogicalPlan::Projection(node) => node.expressions.iter_mut().collect(),
+            LogicalPlan::Filter(node) => vec![&mut node.predicate],
+            LogicalPlan::Join(node) => node.join_constraint.iter_mut().collect(),
+            LogicalPlan::Aggregate(node) => node
+                .group_expr
+                .iter_mut()
+                .chain(node.aggr_expr.iter_mut())
+                .collect(),
+            LogicalPlan::Window(node) => node.window_expr.iter_mut().collect(),
+            LogicalPlan::Sort(node) => node
+                .expressions
+                .iter_mut()
+                .map(|sort_expr| sort_expr.expr.as_mut())
+                .collect(),
+            LogicalPlan::Insert(node) => match &mut node.source {
+                InsertSource::Values(rows) => rows.iter_mut().flatten().collect(),
+                InsertSource::Query(_) => vec![],
+            },
+            LogicalPlan::Update(node) => node
+                .assignments
+                .iter_mut()
+                .map(|assignment| &mut assignment.value)
+                .chain(node.filter.iter_mut())
+                .collect(),
+            LogicalPlan::Delete(node) => node.filter.iter_mut().collect(),
+            LogicalPlan::Values(node) => node.values.iter_mut().flatten().collect(),
+            LogicalPlan::Limit(_)
+            | LogicalPlan::CreateTable(_)
+            | LogicalPlan::DropTable(_)
+            | LogicalPlan::Analyze(_)
+            | LogicalPlan::Explain(_)
+            | LogicalPlan::Union(_)
+            | LogicalPlan::Intersect(_)
+            | LogicalPlan::Except(_)
+            | LogicalPlan::Distinct(_)
+            | LogicalPlan::Subquery(_)
+            | LogicalPlan::CteScan(_)
+            | LogicalPlan::With(_)
+            | LogicalPlan::RecursiveQuery(_) => vec![],
         }
     }
     pub fn description(&self) -> String {
@@ -385,6 +752,7 @@ impl LogicalPlan {
                 node.group_expr.len(),
                 node.aggr_expr.len()
             ),
+            LogicalPlan::Window(node) => node.description(),
             LogicalPlan::Sort(node) => format!("Sort: {} expressions", node.expressions.len()),
             LogicalPlan::Limit(node) => {
                 format!("Limit: skip={:?}, fetch={:?}", node.skip, node.fetch)
@@ -394,10 +762,19 @@ impl LogicalPlan {
             LogicalPlan::Delete(node) => format!("Delete: {}", node.table.name),
             LogicalPlan::CreateTable(node) => format!("CreateTable: {}", node.table.name),
             LogicalPlan::DropTable(node) => format!("DropTable: {} tables", node.tables.len()),
+            LogicalPlan::Analyze(node) => format!("Analyze: {} tables", node.tables.len()),
+            LogicalPlan::Explain(node) => format!("Explain: {:?}", node.format),
             LogicalPlan::Union(node) => format!("Union: all={}", node.all),
+            LogicalPlan::Intersect(node) => format!("Intersect: all={}", node.all),
+            LogicalPlan::Except(node) => format!("Except: all={}", node.all),
             LogicalPlan::Distinct(_) => "Distinct".to_string(),
             LogicalPlan::Values(node) => format!("Values: {} rows", node.values.len()),
             LogicalPlan::Subquery(_) => "Subquery".to_string(),
+            LogicalPlan::CteScan(node) => format!("CteScan: {}", node.name),
+            LogicalPlan::With(node) => format!("With: {} ctes", node.ctes.len()),
+            LogicalPlan::RecursiveQuery(node) => {
+                format!("RecursiveQuery: {}, all={}", node.name, node.all)
+            }
         }
     }
     pub fn validate(&self) -> Result<(), LogicalPlanError> {
@@ -405,6 +782,7 @@ impl LogicalPlan {
             LogicalPlan::TableScan(node) => node.validate(),
             LogicalPlan::Projection(node) => node.validate(),
             LogicalPlan::Filter(node) => node.validate(),
+            LogicalPlan::Window(node) => node.validate(),
             _ => Ok(()),
         }
     }
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use shared_types::DataType;
+use sqlparser::ast::{Ident, ObjectName};
+
+use crate::{
+    common::LogicalPlanError,
+    logical_plan::{AnalyzeNode, LogicalPlan},
+    types::{ColumnDef, LogicalSchema, PlanStatistics, TableRef},
+    utils::object_name_to_string,
+};
+
+pub struct AnalyzePlan {
+    table_schemas: HashMap<String, LogicalSchema>,
+}
+
+impl AnalyzePlan {
+    pub fn new(table_schemas: HashMap<String, LogicalSchema>) -> Self {
+        Self { table_schemas }
+    }
+
+    /// Convert `ANALYZE table [FOR COLUMNS ...]` to a logical plan; without a table,
+    /// every known table is analyzed
+    pub fn generate(
+        &self,
+        table_name: Option<&ObjectName>,
+        columns: &[Ident],
+    ) -> Result<LogicalPlan, LogicalPlanError> {
+        let tables = match table_name {
+            Some(name) => {
+                let table_name = object_name_to_string(name);
+                let schema = self
+                    .table_schemas
+                    .get(&table_name)
+                    .ok_or_else(|| LogicalPlanError::TableNotFound(table_name.clone()))?;
+                for column in columns {
+                    if schema.find_column(&column.value).is_none() {
+                        return Err(LogicalPlanError::ColumnNotFound(format!(
+                            "{} in table {}",
+                            column.value, table_name
+                        )));
+                    }
+                }
+                vec![TableRef::new(table_name)]
+            }
+            None => {
+                let mut names: Vec<&String> = self.table_schemas.keys().collect();
+                names.sort();
+                names.into_iter().map(TableRef::new).collect()
+            }
+        };
+
+        let columns = (!columns.is_empty())
+            .then(|| columns.iter().map(|column| column.value.clone()).collect());
+
+        Ok(LogicalPlan::Analyze(AnalyzeNode {
+            tables,
+            columns,
+            schema: LogicalSchema::new(vec![ColumnDef::new("tables_analyzed", DataType::Integer)]),
+            statistics: PlanStatistics::unknown(),
+        }))
+    }
+}
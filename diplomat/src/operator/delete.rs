@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use shared_types::DataType;
 use sqlparser::ast::{Expr, ObjectName, TableFactor, TableWithJoins};
 
-use crate::{common::LogicalPlanError, logical_plan::{DeleteNode, LogicalPlan}, types::{ColumnDef, LogicalSchema, TableRef}, utils::{expr_to_logical_expr, object_name_to_string}};
+use crate::{common::LogicalPlanError, logical_plan::{DeleteNode, LogicalPlan}, operator::query::QueryPlan, type_check::check_predicate, types::{ColumnDef, LogicalSchema, TableRef}, utils::{expr_to_logical_expr_with, object_name_to_string}};
 
 pub struct DeletePlan {
     table_schemas: HashMap<String, LogicalSchema>,
@@ -48,7 +48,13 @@ impl DeletePlan {
         };
 
         let filter = if let Some(selection) = selection {
-            Some(expr_to_logical_expr(selection)?)
+            // Subqueries in the condition are planned against the same tables
+            let mut query = QueryPlan::new(self.table_schemas.clone());
+            let predicate = expr_to_logical_expr_with(selection, &mut query)?;
+            match self.table_schemas.get(&table_ref.name) {
+                Some(schema) => Some(check_predicate(predicate, schema, "WHERE")?),
+                None => Some(predicate),
+            }
         } else {
             None
         };
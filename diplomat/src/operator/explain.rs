@@ -0,0 +1,115 @@
+use shared_types::DataType;
+use sqlparser::ast::{AnalyzeFormat, Expr, UtilityOption, Value as SqlValue};
+
+use crate::{
+    common::LogicalPlanError,
+    logical_plan::{ExplainNode, LogicalPlan},
+    types::{ColumnDef, ExplainFormat, LogicalSchema, PlanStatistics},
+};
+
+pub struct ExplainPlan {
+    format: ExplainFormat,
+}
+
+impl ExplainPlan {
+    /// `EXPLAIN [FORMAT TEXT | JSON]`, with the format given either as a keyword or in
+    /// parentheses, e.g. `EXPLAIN (FORMAT JSON)`. The planner never runs the plan, so
+    /// `ANALYZE` is rejected.
+    pub fn new(
+        analyze: bool,
+        format: Option<&AnalyzeFormat>,
+        options: Option<&[UtilityOption]>,
+    ) -> Result<Self, LogicalPlanError> {
+        if analyze {
+            return Err(analyze_unsupported());
+        }
+        let mut plan = Self {
+            format: ExplainFormat::Text,
+        };
+        if let Some(format) = format {
+            plan.format = explain_format(&format.to_string())?;
+        }
+        for option in options.unwrap_or_default() {
+            match option.name.value.to_uppercase().as_str() {
+                "ANALYZE" => {
+                    if option_enabled(option)? {
+                        return Err(analyze_unsupported());
+                    }
+                }
+                "FORMAT" => {
+                    let format = match &option.arg {
+                        Some(Expr::Identifier(ident)) => ident.value.clone(),
+                        Some(Expr::Value(value)) => match &value.value {
+                            SqlValue::SingleQuotedString(format) => format.clone(),
+                            other => other.to_string(),
+                        },
+                        Some(other) => other.to_string(),
+                        None => {
+                            return Err(LogicalPlanError::ValidationError(
+                                "EXPLAIN option FORMAT requires a value".to_string(),
+                            ));
+                        }
+                    };
+                    plan.format = explain_format(&format)?;
+                }
+                other => {
+                    return Err(LogicalPlanError::UnsupportedOperation(format!(
+                        "EXPLAIN option {}",
+                        other
+                    )));
+                }
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Wrap the plan of the explained statement; its output is one row per line of
+    /// text, or a single JSON document
+    pub fn generate(&self, plan: LogicalPlan) -> LogicalPlan {
+        LogicalPlan::Explain(ExplainNode {
+            plan: Box::new(plan),
+            format: self.format,
+            schema: LogicalSchema::new(vec![ColumnDef::new("plan", DataType::Text)]),
+            statistics: PlanStatistics::unknown(),
+        })
+    }
+}
+
+fn analyze_unsupported() -> LogicalPlanError {
+    LogicalPlanError::UnsupportedOperation(
+        "EXPLAIN ANALYZE: plans are only explained, not executed".to_string(),
+    )
+}
+
+fn explain_format(format: &str) -> Result<ExplainFormat, LogicalPlanError> {
+    match format.to_uppercase().as_str() {
+        "TEXT" => Ok(ExplainFormat::Text),
+        "JSON" => Ok(ExplainFormat::Json),
+        other => Err(LogicalPlanError::UnsupportedOperation(format!(
+            "EXPLAIN format {}",
+            other
+        ))),
+    }
+}
+
+/// A boolean EXPLAIN option, on when given without a value
+fn option_enabled(option: &UtilityOption) -> Result<bool, LogicalPlanError> {
+    let Some(arg) = &option.arg else {
+        return Ok(true);
+    };
+    let value = match arg {
+        Expr::Value(value) => match &value.value {
+            SqlValue::Boolean(enabled) => return Ok(*enabled),
+            other => other.to_string(),
+        },
+        other => other.to_string(),
+    };
+    match value.to_uppercase().as_str() {
+        "ON" | "TRUE" | "1" => Ok(true),
+        "OFF" | "FALSE" | "0" => Ok(false),
+        _ => Err(LogicalPlanError::ValidationError(format!(
+            "EXPLAIN option {} requires a boolean value, found {}",
+            option.name.value, value
+        ))),
+    }
+}
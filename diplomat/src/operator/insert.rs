@@ -7,6 +7,7 @@ use crate::{
     common::LogicalPlanError,
     logical_plan::{InsertNode, InsertSource, LogicalPlan},
     operator::query::QueryPlan,
+    type_check::{check_assignable, coerce_assignment, coerce_expression},
     types::{ColumnDef, LogicalSchema, TableRef},
     utils::object_name_to_string,
 };
@@ -35,7 +36,11 @@ impl InsertPlan {
             .as_ref()
             .map(|cols| cols.iter().map(|col| col.value.clone()).collect());
 
-        let source_plan = query.generate(source)?;
+        let mut source_plan = query.generate(source)?;
+        if let Some(schema) = self.table_schemas.get(&table.name) {
+            let targets = target_columns(&table.name, schema, columns.as_deref())?;
+            source_plan = coerce_source(source_plan, &targets)?;
+        }
 
         let schema = LogicalSchema::new(vec![ColumnDef::new("rows_affected", DataType::Integer)]);
 
@@ -48,3 +53,71 @@ impl InsertPlan {
         }))
     }
 }
+
+/// Columns of `schema` that the INSERT writes, in the order of its column list
+fn target_columns<'a>(
+    table_name: &str,
+    schema: &'a LogicalSchema,
+    columns: Option<&[Ident]>,
+) -> Result<Vec<&'a ColumnDef>, LogicalPlanError> {
+    match columns {
+        Some(columns) if !columns.is_empty() => columns
+            .iter()
+            .map(|column| {
+                schema.find_column(&column.value).ok_or_else(|| {
+                    LogicalPlanError::ColumnNotFound(format!(
+                        "{} in table {}",
+                        column.value, table_name
+                    ))
+                })
+            })
+            .collect(),
+        _ => Ok(schema.columns.iter().collect()),
+    }
+}
+
+/// Check the source rows against the target columns, converting VALUES to the
+/// column types
+fn coerce_source(
+    source: LogicalPlan,
+    targets: &[&ColumnDef],
+) -> Result<LogicalPlan, LogicalPlanError> {
+    let source_columns = &source.schema().columns;
+    // The shape of a query over tables without a known schema can't be checked
+    if source_columns.iter().any(|column| column.name == "*") {
+        return Ok(source);
+    }
+    if source_columns.len() > targets.len() {
+        return Err(LogicalPlanError::ValidationError(
+            "INSERT has more expressions than target columns".to_string(),
+        ));
+    }
+    if source_columns.len() < targets.len() {
+        return Err(LogicalPlanError::ValidationError(
+            "INSERT has more target columns than expressions".to_string(),
+        ));
+    }
+
+    match source {
+        LogicalPlan::Values(mut values) => {
+            for row in values.values.iter_mut() {
+                for (expr, column) in row.iter_mut().zip(targets) {
+                    let (typed, data_type) =
+                        coerce_expression(expr.clone(), &LogicalSchema::empty())?;
+                    *expr = coerce_assignment(typed, data_type.as_ref(), column)?;
+                }
+            }
+            for (column, target) in values.schema.columns.iter_mut().zip(targets) {
+                column.data_type = target.data_type.clone();
+                column.nullable = target.nullable;
+            }
+            Ok(LogicalPlan::Values(values))
+        }
+        source => {
+            for (column, target) in source.schema().columns.iter().zip(targets) {
+                check_assignable(&column.data_type, target)?;
+            }
+            Ok(source)
+        }
+    }
+}
@@ -1,6 +1,8 @@
+pub mod analyze;
 pub mod create;
 pub mod delete;
 pub mod drop;
+pub mod explain;
 pub mod insert;
 pub mod query;
 pub mod update;
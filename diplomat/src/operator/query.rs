@@ -1,45 +1,262 @@
 use std::collections::HashMap;
 
-use shared_types::DataType;
+use shared_types::{DataType, function::common_type};
 use sqlparser::ast::{
-    Expr, GroupByExpr, Join, JoinConstraint, JoinOperator, LimitClause, OrderByExpr, OrderByKind,
-    Query, Select, SelectItem, SetExpr, SetOperator, SetQuantifier, TableFactor, TableWithJoins,
-    Value as SqlValue,
+    Cte, CteAsMaterialized, Expr, GroupByExpr, Ident, Join, JoinConstraint, JoinOperator,
+    LimitClause, OrderByExpr, OrderByKind, Query, Select, SelectItem, SetExpr, SetOperator,
+    SetQuantifier, TableAlias, TableAliasColumnDef, TableFactor, TableWithJoins, Value as SqlValue,
+    With,
 };
 
 use crate::{
+    binder::{Binder, from_scope},
     common::LogicalPlanError,
     expression::Expression,
+    functions::check_function_calls,
     logical_plan::{
-        AggregateNode, DistinctNode, FilterNode, JoinNode, LimitNode, LogicalPlan, ProjectionNode,
-        SortNode, SubqueryNode, TableScanNode, UnionNode, ValuesNode,
+        AggregateNode, CteDefinition, CteScanNode, DistinctNode, ExceptNode, FilterNode,
+        IntersectNode, JoinNode, LimitNode, LogicalPlan, ProjectionNode, RecursiveQueryNode,
+        SortNode, SubqueryNode, TableScanNode, UnionNode, ValuesNode, WindowNode, WithNode,
     },
-    types::{ColumnDef, JoinType, LogicalSchema, SortExpr, SortOrder, TableRef},
+    type_check::{check_predicate, coerce_expression},
+    types::{AggregateFunction, ColumnDef, JoinType, LogicalSchema, TableRef, WindowFunction},
     utils::{
-        expr_to_column_name, expr_to_logical_expr, has_aggregates, is_aggregate_expr,
-        object_name_to_string, values_to_plan,
+        ExprPlanner, expr_to_column_name, expr_to_logical_expr_with, has_aggregates,
+        is_aggregate_expr, object_name_to_string, order_by_expr_to_sort_expr_with, unbound_column,
+        values_to_plan,
     },
 };
 
 pub struct QueryPlan {
     table_schemas: HashMap<String, LogicalSchema>,
+    /// Common table expressions in scope, one list per enclosing WITH clause
+    ctes: Vec<Vec<CteBinding>>,
+    /// Resolves table and column names when planning against the catalog
+    binder: Option<Binder>,
+    /// FROM rows of the enclosing queries, outermost first
+    scopes: Vec<LogicalSchema>,
+    /// Output columns of the query whose ORDER BY is being planned, which it can
+    /// refer to by name
+    order_by_outputs: Option<LogicalSchema>,
+}
+
+/// A common table expression visible to the query being planned
+struct CteBinding {
+    name: String,
+    plan: LogicalPlan,
+    materialized: Option<CteAsMaterialized>,
+    /// Whether `plan` is a recursive query; reads of its working table are not counted
+    recursive: bool,
+    /// Number of `CteScan`s planned for it so far
+    references: usize,
 }
 
 impl QueryPlan {
     pub fn new(table_schemas: HashMap<String, LogicalSchema>) -> Self {
         Self {
             table_schemas: table_schemas,
+            ctes: Vec::new(),
+            binder: None,
+            scopes: Vec::new(),
+            order_by_outputs: None,
         }
     }
 
+    /// Resolve names with `binder`: unknown tables and columns become errors instead
+    /// of being passed through unresolved
+    pub fn with_binder(mut self, binder: Binder) -> Self {
+        self.binder = Some(binder);
+        self
+    }
+
     pub fn generate(&mut self, query: &Query) -> Result<LogicalPlan, LogicalPlanError> {
+        let Some(with) = &query.with else {
+            return self.query_body_to_plan(query);
+        };
+
+        self.ctes.push(Vec::new());
+        let plan = self
+            .with_to_bindings(with)
+            .and_then(|()| self.query_body_to_plan(query));
+        let bindings = self.ctes.pop().unwrap_or_default();
+        Ok(inline_or_materialize(plan?, bindings))
+    }
+
+    /// Plan each common table expression of a WITH clause into the innermost CTE scope.
+    /// Later CTEs, and the query itself, can refer to earlier ones.
+    fn with_to_bindings(&mut self, with: &With) -> Result<(), LogicalPlanError> {
+        for cte in &with.cte_tables {
+            let name = cte.alias.name.value.clone();
+            if self
+                .ctes
+                .last()
+                .is_some_and(|scope| scope.iter().any(|binding| binding.name == name))
+            {
+                return Err(LogicalPlanError::ValidationError(format!(
+                    "WITH query name \"{}\" specified more than once",
+                    name
+                )));
+            }
+
+            let recursive_plan = if with.recursive {
+                self.recursive_cte_to_plan(cte)?
+            } else {
+                None
+            };
+            let recursive = recursive_plan.is_some();
+            let plan = match recursive_plan {
+                Some(plan) => plan,
+                None => rename_cte_columns(self.generate(&cte.query)?, cte)?,
+            };
+
+            if let Some(scope) = self.ctes.last_mut() {
+                scope.push(CteBinding {
+                    name,
+                    plan,
+                    materialized: cte.materialized.clone(),
+                    recursive,
+                    references: 0,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Plan `name AS (static UNION [ALL] recursive)` as a fixpoint. `None` when the CTE
+    /// is not a union or its second term never refers to the CTE itself.
+    fn recursive_cte_to_plan(
+        &mut self,
+        cte: &Cte,
+    ) -> Result<Option<LogicalPlan>, LogicalPlanError> {
+        let SetExpr::SetOperation {
+            op: SetOperator::Union,
+            set_quantifier,
+            left,
+            right,
+        } = cte.query.body.as_ref()
+        else {
+            return Ok(None);
+        };
+        let name = cte.alias.name.value.clone();
+        // A CTE that turns out not to be recursive is planned again from scratch, so
+        // the references its terms make to earlier CTEs must not count twice
+        let references = self.reference_counts();
+
+        let static_term = rename_cte_columns(self.set_expr_to_plan(left)?, cte)?;
+        let schema = static_term.schema().clone();
+
+        // The recursive term reads the previous iteration's rows under the CTE's name
+        if let Some(scope) = self.ctes.last_mut() {
+            scope.push(CteBinding {
+                name: name.clone(),
+                plan: LogicalPlan::Values(ValuesNode {
+                    values: vec![],
+                    schema: schema.clone(),
+                    statistics: crate::types::PlanStatistics::unknown(),
+                }),
+                materialized: None,
+                recursive: true,
+                references: 0,
+            });
+        }
+        let recursive_term = self.set_expr_to_plan(right);
+        let working_table = self.ctes.last_mut().and_then(|scope| scope.pop());
+        let recursive_term = recursive_term?;
+        if working_table.is_none_or(|binding| binding.references == 0) {
+            self.restore_reference_counts(references);
+            return Ok(None);
+        }
+
+        if cte.query.order_by.is_some() || cte.query.limit_clause.is_some() {
+            return Err(LogicalPlanError::UnsupportedOperation(format!(
+                "ORDER BY and LIMIT are not supported in recursive query \"{}\"",
+                name
+            )));
+        }
+        if recursive_term.schema().column_count() != schema.column_count() {
+            return Err(LogicalPlanError::ValidationError(format!(
+                "Recursive query \"{}\" has {} columns in its non-recursive term but {} in its recursive term",
+                name,
+                schema.column_count(),
+                recursive_term.schema().column_count()
+            )));
+        }
+
+        Ok(Some(LogicalPlan::RecursiveQuery(RecursiveQueryNode {
+            name,
+            static_term: Box::new(static_term),
+            recursive_term: Box::new(recursive_term),
+            all: matches!(set_quantifier, SetQuantifier::All),
+            schema,
+            statistics: crate::types::PlanStatistics::unknown(),
+        })))
+    }
+
+    /// Reference count of every CTE in scope, one list per WITH clause
+    fn reference_counts(&self) -> Vec<Vec<usize>> {
+        self.ctes
+            .iter()
+            .map(|scope| scope.iter().map(|binding| binding.references).collect())
+            .collect()
+    }
+
+    fn restore_reference_counts(&mut self, counts: Vec<Vec<usize>>) {
+        for (scope, counts) in self.ctes.iter_mut().zip(counts) {
+            for (binding, references) in scope.iter_mut().zip(counts) {
+                binding.references = references;
+            }
+        }
+    }
+
+    /// Scan of the innermost common table expression called `name`, if there is one
+    fn cte_scan(&mut self, name: &str, alias: Option<&TableAlias>) -> Option<LogicalPlan> {
+        let binding = self
+            .ctes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.iter_mut().rev().find(|binding| binding.name == name))?;
+        binding.references += 1;
+
+        let alias = alias.map(|alias| alias.name.value.clone());
+        let qualifier = alias.clone().unwrap_or_else(|| name.to_string());
+        let columns = binding
+            .plan
+            .schema()
+            .columns
+            .iter()
+            .map(|column| ColumnDef {
+                table: Some(qualifier.clone()),
+                ..column.clone()
+            })
+            .collect();
+        Some(LogicalPlan::CteScan(CteScanNode {
+            name: name.to_string(),
+            alias,
+            schema: LogicalSchema::new(columns),
+            statistics: crate::types::PlanStatistics::unknown(),
+        }))
+    }
+
+    /// Plan the query body with its ORDER BY and LIMIT, leaving out the WITH clause
+    fn query_body_to_plan(&mut self, query: &Query) -> Result<LogicalPlan, LogicalPlanError> {
+        // The FROM scope of the body stays open for its ORDER BY
+        let depth = self.scopes.len();
+        let plan = self.query_body_in_scope(query);
+        self.scopes.truncate(depth);
+        plan
+    }
+
+    fn query_body_in_scope(&mut self, query: &Query) -> Result<LogicalPlan, LogicalPlanError> {
         let mut plan = self.set_expr_to_plan(&query.body)?;
 
         // Apply ORDER BY
         if let Some(order_by) = &query.order_by {
             match &order_by.kind {
                 OrderByKind::Expressions(exprs) => {
-                    plan = self.apply_order_by(plan, exprs)?;
+                    let outputs = self.order_by_outputs.replace(plan.schema().clone());
+                    let sorted = self.apply_order_by(plan, exprs);
+                    self.order_by_outputs = outputs;
+                    plan = sorted?;
                 }
                 _ => {
                     return Err(LogicalPlanError::UnsupportedOperation(
@@ -71,25 +288,53 @@ impl QueryPlan {
                 left,
                 right,
             } => {
-                let left_plan = self.set_expr_to_plan(left)?;
-                let right_plan = self.set_expr_to_plan(right)?;
+                let depth = self.scopes.len();
+                let left_plan = self.set_expr_to_plan(left);
+                self.scopes.truncate(depth);
+                let left_plan = left_plan?;
+                let right_plan = self.set_expr_to_plan(right);
+                self.scopes.truncate(depth);
+                let right_plan = right_plan?;
+                let (left_plan, right_plan, schema) =
+                    coerce_set_operation_inputs(op, left_plan, right_plan)?;
+                let left = Box::new(left_plan);
+                let right = Box::new(right_plan);
+                let all = match set_quantifier {
+                    SetQuantifier::All => true,
+                    SetQuantifier::None | SetQuantifier::Distinct => false,
+                    _ => {
+                        return Err(LogicalPlanError::UnsupportedOperation(format!(
+                            "Unsupported set quantifier: {}",
+                            set_quantifier
+                        )));
+                    }
+                };
+                let statistics = crate::types::PlanStatistics::unknown();
 
                 match op {
-                    SetOperator::Union => {
-                        let all = matches!(set_quantifier, SetQuantifier::All);
-                        let schema = left_plan.schema().clone();
-                        Ok(LogicalPlan::Union(UnionNode {
-                            left: Box::new(left_plan),
-                            right: Box::new(right_plan),
+                    SetOperator::Union => Ok(LogicalPlan::Union(UnionNode {
+                        left,
+                        right,
+                        all,
+                        schema,
+                        statistics,
+                    })),
+                    SetOperator::Intersect => Ok(LogicalPlan::Intersect(IntersectNode {
+                        left,
+                        right,
+                        all,
+                        schema,
+                        statistics,
+                    })),
+                    SetOperator::Except | SetOperator::Minus => {
+                        Ok(LogicalPlan::Except(ExceptNode {
+                            left,
+                            right,
                             all,
                             schema,
-                            statistics: crate::types::PlanStatistics::unknown(),
+                            statistics,
                         }))
                     }
-                    _ => Err(LogicalPlanError::UnsupportedOperation(format!(
-                        "Unsupported set operation: {:?}",
-                        op
-                    ))),
                 }
             }
             SetExpr::Values(values) => values_to_plan(values),
@@ -113,10 +358,16 @@ impl QueryPlan {
         } else {
             self.from_to_plan(&select.from)?
         };
+        // Expressions up to the projection are typed over the row FROM produces
+        let scope = from_scope(&plan);
+        self.scopes.push(scope.clone());
 
         // Apply WHERE clause
         if let Some(selection) = &select.selection {
-            let predicate = expr_to_logical_expr(selection)?;
+            let predicate = expr_to_logical_expr_with(selection, self)?;
+            reject_window_functions(&predicate, "WHERE")?;
+            check_function_calls(&predicate, &scope)?;
+            let predicate = check_predicate(predicate, &scope, "WHERE")?;
             plan = LogicalPlan::Filter(FilterNode {
                 predicate,
                 input: Box::new(plan),
@@ -131,12 +382,14 @@ impl QueryPlan {
         };
 
         if has_group_by || has_aggregates(&select.projection) {
-            plan = self.apply_group_by(plan, &select.group_by, &select.projection)?;
+            plan = self.apply_group_by(plan, &scope, &select.group_by, &select.projection)?;
         }
 
         // Apply HAVING clause
         if let Some(having) = &select.having {
-            let predicate = expr_to_logical_expr(having)?;
+            let predicate = expr_to_logical_expr_with(having, self)?;
+            reject_window_functions(&predicate, "HAVING")?;
+            let predicate = check_predicate(predicate, &scope, "HAVING")?;
             plan = LogicalPlan::Filter(FilterNode {
                 predicate,
                 input: Box::new(plan),
@@ -144,8 +397,11 @@ impl QueryPlan {
             });
         }
 
+        // Apply window functions, evaluated after grouping and before the projection
+        plan = self.apply_window(plan, &select.projection)?;
+
         // Apply SELECT (projection)
-        plan = self.apply_projection(plan, &select.projection)?;
+        plan = self.apply_projection(plan, &scope, &select.projection)?;
 
         // Apply DISTINCT
         if select.distinct.is_some() {
@@ -191,6 +447,7 @@ impl QueryPlan {
                 right: Box::new(right_plan),
                 join_type: JoinType::Cross,
                 join_constraint: None,
+                strategy: None,
                 schema: LogicalSchema::new(combined_columns),
                 statistics: crate::types::PlanStatistics::unknown(),
             });
@@ -212,31 +469,41 @@ impl QueryPlan {
         match table_factor {
             TableFactor::Table { name, alias, .. } => {
                 let table_name = object_name_to_string(name);
+                if name.0.len() == 1
+                    && let Some(plan) = self.cte_scan(&table_name, alias.as_ref())
+                {
+                    return Ok(plan);
+                }
+
                 let table_ref = if let Some(alias) = alias {
                     TableRef::with_alias(table_name.clone(), alias.name.value.clone())
                 } else {
                     TableRef::new(table_name.clone())
                 };
 
-                // Get schema from registered schemas or create a default one
-                let schema = self
-                    .table_schemas
-                    .get(&table_name)
-                    .cloned()
-                    .unwrap_or_else(|| {
-                        // Create a default schema with unknown columns
-                        LogicalSchema::new(vec![ColumnDef::with_table(
-                            "*",
-                            DataType::String,
-                            table_ref.effective_name().to_string(),
-                        )])
-                    });
+                // Get schema from the catalog, registered schemas or create a default one
+                let schema = match &self.binder {
+                    Some(binder) => binder.bind_table(name, table_ref.effective_name())?,
+                    None => self
+                        .table_schemas
+                        .get(&table_name)
+                        .cloned()
+                        .unwrap_or_else(|| {
+                            // Create a default schema with unknown columns
+                            LogicalSchema::new(vec![ColumnDef::with_table(
+                                "*",
+                                DataType::String,
+                                table_ref.effective_name().to_string(),
+                            )])
+                        }),
+                };
 
                 Ok(LogicalPlan::TableScan(TableScanNode {
                     table: table_ref,
                     schema,
                     projected_columns: None,
                     filters: vec![],
+                    parallelism: None,
                     statistics: crate::types::PlanStatistics::unknown(),
                 }))
             }
@@ -291,12 +558,23 @@ impl QueryPlan {
         };
 
         let join_constraint = match &join.join_operator {
-            JoinOperator::Inner(constraint)
+            JoinOperator::Join(constraint)
+            | JoinOperator::Inner(constraint)
+            | JoinOperator::Left(constraint)
             | JoinOperator::LeftOuter(constraint)
+            | JoinOperator::Right(constraint)
             | JoinOperator::RightOuter(constraint)
             | JoinOperator::FullOuter(constraint) => {
                 match constraint {
-                    JoinConstraint::On(expr) => Some(expr_to_logical_expr(expr)?),
+                    JoinConstraint::On(expr) => {
+                        // ON sees the columns of both sides
+                        let mut scope = from_scope(&left_plan);
+                        scope.columns.extend(from_scope(&right_plan).columns);
+                        self.scopes.push(scope);
+                        let predicate = expr_to_logical_expr_with(expr, self);
+                        let scope = self.scopes.pop().unwrap_or_else(LogicalSchema::empty);
+                        Some(check_predicate(predicate?, &scope, "JOIN")?)
+                    }
                     JoinConstraint::Using(columns) => None, // TODO: Implement using style join
                     JoinConstraint::Natural => None,        // TODO: Implement natural join logic
                     JoinConstraint::None => None,
@@ -317,6 +595,7 @@ impl QueryPlan {
             right: Box::new(right_plan),
             join_type,
             join_constraint,
+            strategy: None,
             schema: LogicalSchema::new(combined_columns),
             statistics: crate::types::PlanStatistics::unknown(),
         }))
@@ -326,6 +605,7 @@ impl QueryPlan {
     fn apply_projection(
         &mut self,
         plan: LogicalPlan,
+        scope: &LogicalSchema,
         projection: &[SelectItem],
     ) -> Result<LogicalPlan, LogicalPlanError> {
         let mut expressions = Vec::new();
@@ -334,15 +614,21 @@ impl QueryPlan {
         for item in projection {
             match item {
                 SelectItem::UnnamedExpr(expr) => {
-                    let logical_expr = expr_to_logical_expr(expr)?;
+                    let logical_expr = expr_to_logical_expr_with(expr, self)?;
+                    check_function_calls(&logical_expr, scope)?;
+                    let (logical_expr, data_type) = coerce_expression(logical_expr, scope)?;
+                    let data_type = data_type.unwrap_or(DataType::String);
                     let column_name = expr_to_column_name(expr);
                     expressions.push(logical_expr);
-                    schema_columns.push(ColumnDef::new(column_name, DataType::String)); // TODO: Infer type
+                    schema_columns.push(ColumnDef::new(column_name, data_type));
                 }
                 SelectItem::ExprWithAlias { expr, alias } => {
-                    let logical_expr = expr_to_logical_expr(expr)?;
+                    let logical_expr = expr_to_logical_expr_with(expr, self)?;
+                    check_function_calls(&logical_expr, scope)?;
+                    let (logical_expr, data_type) = coerce_expression(logical_expr, scope)?;
+                    let data_type = data_type.unwrap_or(DataType::String);
                     expressions.push(logical_expr);
-                    schema_columns.push(ColumnDef::new(&alias.value, DataType::String)); // TODO: Infer type
+                    schema_columns.push(ColumnDef::new(&alias.value, data_type));
                 }
                 SelectItem::Wildcard(_) => {
                     expressions.push(Expression::wildcard());
@@ -366,10 +652,12 @@ impl QueryPlan {
     fn apply_group_by(
         &mut self,
         plan: LogicalPlan,
+        scope: &LogicalSchema,
         group_by: &GroupByExpr,
         projection: &[SelectItem],
     ) -> Result<LogicalPlan, LogicalPlanError> {
         let mut group_expr = Vec::new();
+        let mut schema_columns = Vec::new();
 
         match group_by {
             GroupByExpr::All(all) => {
@@ -377,7 +665,13 @@ impl QueryPlan {
             }
             GroupByExpr::Expressions(exprs, modifier) => {
                 for expr in exprs {
-                    group_expr.push(expr_to_logical_expr(expr)?);
+                    let logical_expr = expr_to_logical_expr_with(expr, self)?;
+                    let (logical_expr, data_type) = coerce_expression(logical_expr, scope)?;
+                    schema_columns.push(ColumnDef::new(
+                        format!("group_{}", group_expr.len()),
+                        data_type.unwrap_or(DataType::String),
+                    ));
+                    group_expr.push(logical_expr);
                 }
             }
         }
@@ -387,29 +681,73 @@ impl QueryPlan {
             match item {
                 SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
                     if is_aggregate_expr(expr) {
-                        aggr_expr.push(expr_to_logical_expr(expr)?);
+                        // Typed as in the projection, so the two still match
+                        let logical_expr = expr_to_logical_expr_with(expr, self)?;
+                        let (logical_expr, data_type) = coerce_expression(logical_expr, scope)?;
+                        schema_columns.push(ColumnDef::new(
+                            format!("aggr_{}", aggr_expr.len()),
+                            data_type.unwrap_or(DataType::String),
+                        ));
+                        aggr_expr.push(logical_expr);
                     }
                 }
                 _ => {}
             }
         }
 
-        // Create schema for aggregate result
-        let mut schema_columns = Vec::new();
+        Ok(LogicalPlan::Aggregate(AggregateNode {
+            group_expr,
+            aggr_expr,
+            input: Box::new(plan),
+            schema: LogicalSchema::new(schema_columns),
+            statistics: crate::types::PlanStatistics::unknown(),
+        }))
+    }
 
-        // Add group by columns
-        for (i, _) in group_expr.iter().enumerate() {
-            schema_columns.push(ColumnDef::new(format!("group_{}", i), DataType::String));
+    /// Add a Window node for the window functions in the projection. Each distinct
+    /// window expression becomes a column after the input's, named after the expression.
+    fn apply_window(
+        &mut self,
+        plan: LogicalPlan,
+        projection: &[SelectItem],
+    ) -> Result<LogicalPlan, LogicalPlanError> {
+        let mut window_expr: Vec<Expression> = Vec::new();
+        for item in projection {
+            match item {
+                SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                    let logical_expr = expr_to_logical_expr_with(expr, self)?;
+                    for window in logical_expr.window_exprs() {
+                        if !window_expr.contains(window) {
+                            window_expr.push(window.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
         }
 
-        // Add aggregate columns
-        for (i, _) in aggr_expr.iter().enumerate() {
-            schema_columns.push(ColumnDef::new(format!("aggr_{}", i), DataType::String));
+        if window_expr.is_empty() {
+            return Ok(plan);
         }
 
-        Ok(LogicalPlan::Aggregate(AggregateNode {
-            group_expr,
-            aggr_expr,
+        let mut schema_columns = plan.schema().columns.clone();
+        for expr in &window_expr {
+            let data_type = match expr {
+                Expression::Window {
+                    func:
+                        WindowFunction::RowNumber
+                        | WindowFunction::Rank
+                        | WindowFunction::DenseRank
+                        | WindowFunction::Aggregate(AggregateFunction::Count),
+                    ..
+                } => DataType::Integer,
+                _ => DataType::String, // TODO: Infer type
+            };
+            schema_columns.push(ColumnDef::new(expr.to_string(), data_type));
+        }
+
+        Ok(LogicalPlan::Window(WindowNode {
+            window_expr,
             input: Box::new(plan),
             schema: LogicalSchema::new(schema_columns),
             statistics: crate::types::PlanStatistics::unknown(),
@@ -425,21 +763,7 @@ impl QueryPlan {
         let mut expressions = Vec::new();
 
         for order_expr in order_by {
-            let expr = expr_to_logical_expr(&order_expr.expr)?;
-
-            // one of the expressions' fields has a field of the same name: `options.`
-            let order = if order_expr.options.asc.unwrap_or(true) {
-                SortOrder::Ascending
-            } else {
-                SortOrder::Descending
-            };
-
-            let mut sort_expr = SortExpr::new(expr, order);
-            if order_expr.options.nulls_first.unwrap_or(false) {
-                sort_expr = sort_expr.nulls_first();
-            }
-
-            expressions.push(sort_expr);
+            expressions.push(order_by_expr_to_sort_expr_with(order_expr, self)?);
         }
 
         Ok(LogicalPlan::Sort(SortNode {
@@ -560,3 +884,336 @@ impl QueryPlan {
         }))
     }
 }
+
+/// Subqueries see the same tables as the query they are nested in, and column names
+/// resolve against the FROM rows in scope, innermost first
+impl ExprPlanner for QueryPlan {
+    fn plan_subquery(&mut self, query: &Query) -> Result<LogicalPlan, LogicalPlanError> {
+        self.generate(query)
+    }
+    fn plan_set_expr(&mut self, set_expr: &SetExpr) -> Result<LogicalPlan, LogicalPlanError> {
+        // A bare SELECT has no query body of its own to close its FROM scope
+        let depth = self.scopes.len();
+        let plan = self.set_expr_to_plan(set_expr);
+        self.scopes.truncate(depth);
+        plan
+    }
+    fn bind_column(
+        &mut self,
+        qualifier: Option<&Ident>,
+        name: &Ident,
+    ) -> Result<Expression, LogicalPlanError> {
+        let Some(binder) = &self.binder else {
+            return Ok(unbound_column(qualifier, name));
+        };
+        // ORDER BY can name an output column, such as an alias of the select list
+        if qualifier.is_none()
+            && let Some(outputs) = &self.order_by_outputs
+            && outputs
+                .columns
+                .iter()
+                .any(|column| column.name == name.value)
+        {
+            return Ok(unbound_column(None, name));
+        }
+        binder
+            .bind_column(&self.scopes, qualifier, name)
+            .map(Expression::Column)
+    }
+}
+
+/// Window functions are evaluated after WHERE, GROUP BY and HAVING, so they can't appear in them
+fn reject_window_functions(expr: &Expression, clause: &str) -> Result<(), LogicalPlanError> {
+    match expr.window_exprs().first() {
+        Some(window) => Err(LogicalPlanError::ValidationError(format!(
+            "Window functions are not allowed in {}: {}",
+            clause, window
+        ))),
+        None => Ok(()),
+    }
+}
+/// Apply a CTE's column list, `name (a, b) AS (...)`, by renaming its output columns
+fn rename_cte_columns(plan: LogicalPlan, cte: &Cte) -> Result<LogicalPlan, LogicalPlanError> {
+    let names = &cte.alias.columns;
+    if names.is_empty() {
+        return Ok(plan);
+    }
+    let columns = &plan.schema().columns;
+    if names.len() != columns.len() {
+        return Err(LogicalPlanError::ValidationError(format!(
+            "WITH query \"{}\" has {} columns available but {} columns specified",
+            cte.alias.name.value,
+            columns.len(),
+            names.len()
+        )));
+    }
+
+    // Rename a select list in place; generated names such as `expr` need not be unique
+    match plan {
+        LogicalPlan::Projection(mut projection)
+            if projection.expressions.len() == names.len()
+                && !projection
+                    .expressions
+                    .iter()
+                    .any(|expr| matches!(expr, Expression::Wildcard { .. })) =>
+        {
+            for ((expr, column), name) in projection
+                .expressions
+                .iter_mut()
+                .zip(&mut projection.schema.columns)
+                .zip(names)
+            {
+                let unaliased = match expr.clone() {
+                    Expression::Alias { expr, .. } => *expr,
+                    other => other,
+                };
+                *expr = Expression::alias(unaliased, &name.name.value);
+                column.name = name.name.value.clone();
+                column.table = None;
+            }
+            Ok(LogicalPlan::Projection(projection))
+        }
+        other => wrap_in_renaming_projection(other, names),
+    }
+}
+
+fn wrap_in_renaming_projection(
+    plan: LogicalPlan,
+    names: &[TableAliasColumnDef],
+) -> Result<LogicalPlan, LogicalPlanError> {
+    let mut expressions = Vec::new();
+    let mut schema_columns = Vec::new();
+    for (column, name) in plan.schema().columns.iter().zip(names) {
+        let reference = match &column.table {
+            Some(table) => Expression::qualified_column(table, &column.name),
+            None => Expression::column(&column.name),
+        };
+        expressions.push(Expression::alias(reference, &name.name.value));
+        schema_columns.push(ColumnDef {
+            name: name.name.value.clone(),
+            table: None,
+            ..column.clone()
+        });
+    }
+    Ok(LogicalPlan::Projection(ProjectionNode {
+        expressions,
+        input: Box::new(plan),
+        schema: LogicalSchema::new(schema_columns),
+        statistics: crate::types::PlanStatistics::unknown(),
+    }))
+}
+
+/// Inline the CTEs of one WITH clause that are read once as derived tables. Recursive
+/// CTEs, those read more than once and those declared `MATERIALIZED` are computed once
+/// by a `With` node instead; unreferenced CTEs are dropped.
+fn inline_or_materialize(mut plan: LogicalPlan, bindings: Vec<CteBinding>) -> LogicalPlan {
+    let mut materialized: Vec<CteDefinition> = Vec::new();
+    // Later CTEs can read earlier ones, so inline from the last one back
+    for binding in bindings.into_iter().rev() {
+        if binding.references == 0 {
+            continue;
+        }
+        let inline = !binding.recursive
+            && match binding.materialized {
+                Some(CteAsMaterialized::Materialized) => false,
+                Some(CteAsMaterialized::NotMaterialized) => true,
+                None => binding.references == 1,
+            };
+        if inline {
+            inline_cte(&mut plan, &binding.name, &binding.plan);
+            for cte in &mut materialized {
+                inline_cte(&mut cte.plan, &binding.name, &binding.plan);
+            }
+        } else {
+            materialized.insert(
+                0,
+                CteDefinition {
+                    name: binding.name,
+                    plan: binding.plan,
+                },
+            );
+        }
+    }
+
+    if materialized.is_empty() {
+        plan
+    } else {
+        LogicalPlan::With(WithNode {
+            ctes: materialized,
+            input: Box::new(plan),
+            statistics: crate::types::PlanStatistics::unknown(),
+        })
+    }
+}
+
+/// Replace every scan of the CTE `name` in `plan`, including scans in subquery
+/// expressions, with `definition` as a derived table
+fn inline_cte(plan: &mut LogicalPlan, name: &str, definition: &LogicalPlan) {
+    match plan {
+        LogicalPlan::CteScan(scan) if scan.name == name => {
+            *plan = LogicalPlan::Subquery(SubqueryNode {
+                subquery: Box::new(definition.clone()),
+                alias: Some(scan.effective_name().to_string()),
+                statistics: crate::types::PlanStatistics::unknown(),
+            });
+            return;
+        }
+        // A nested WITH of the same name hides this CTE from its later CTEs and its query
+        LogicalPlan::With(with) => {
+            if let Some(shadowed_at) = with.ctes.iter().position(|cte| cte.name == name) {
+                for cte in &mut with.ctes[..=shadowed_at] {
+                    inline_cte(&mut cte.plan, name, definition);
+                }
+                return;
+            }
+        }
+        // As does a recursive query of the same name in its recursive term
+        LogicalPlan::RecursiveQuery(recursive) if recursive.name == name => {
+            inline_cte(&mut recursive.static_term, name, definition);
+            return;
+        }
+        _ => {}
+    }
+
+    for expr in plan.expressions_mut() {
+        for subquery in expr.subquery_plans_mut() {
+            inline_cte(subquery, name, definition);
+        }
+    }
+    for child in plan.children_mut() {
+        inline_cte(child, name, definition);
+    }
+}
+
+/// Check that the inputs of a set operation have the same number of columns and
+/// compatible types, and cast columns on either side to the type the two share. The
+/// result takes the left input's column names.
+fn coerce_set_operation_inputs(
+    op: &SetOperator,
+    left: LogicalPlan,
+    right: LogicalPlan,
+) -> Result<(LogicalPlan, LogicalPlan, LogicalSchema), LogicalPlanError> {
+    let left_columns = &left.schema().columns;
+    let right_columns = &right.schema().columns;
+    // A `*` column stands for a table whose columns are not known
+    let unknown = |columns: &[ColumnDef]| columns.iter().any(|column| column.name == "*");
+    if unknown(left_columns) || unknown(right_columns) {
+        let schema = left.schema().clone();
+        return Ok((left, right, schema));
+    }
+    if left_columns.len() != right_columns.len() {
+        return Err(LogicalPlanError::ValidationError(format!(
+            "Each {} query must have the same number of columns, got {} and {}",
+            op,
+            left_columns.len(),
+            right_columns.len()
+        )));
+    }
+
+    let mut schema_columns = Vec::new();
+    for (left_column, right_column) in left_columns.iter().zip(right_columns) {
+        // STRING is also what the planner gives expressions whose type it cannot infer yet
+        let data_type = if left_column.data_type == DataType::String
+            || right_column.data_type == DataType::String
+        {
+            left_column.data_type.clone()
+        } else {
+            common_type(&left_column.data_type, &right_column.data_type).ok_or_else(|| {
+                LogicalPlanError::TypeMismatch {
+                    expected: format!(
+                        "{:?} for {} column {}",
+                        left_column.data_type, op, left_column.name
+                    ),
+                    found: format!("{:?}", right_column.data_type),
+                }
+            })?
+        };
+        // As in `HashSetOperation`: INTERSECT only returns NULLs both sides have, and
+        // EXCEPT only returns rows of its left input
+        let nullable = match op {
+            SetOperator::Intersect => left_column.nullable && right_column.nullable,
+            SetOperator::Except | SetOperator::Minus => left_column.nullable,
+            _ => left_column.nullable || right_column.nullable,
+        };
+        schema_columns.push(ColumnDef {
+            name: left_column.name.clone(),
+            data_type,
+            nullable,
+            table: left_column.table.clone(),
+        });
+    }
+
+    let types: Vec<DataType> = schema_columns
+        .iter()
+        .map(|column| column.data_type.clone())
+        .collect();
+    let left = cast_columns(left, &types);
+    let right = cast_columns(right, &types);
+    Ok((left, right, LogicalSchema::new(schema_columns)))
+}
+
+/// Cast each output column of `plan` whose type differs from `types`, inside its select
+/// list when it has one
+fn cast_columns(plan: LogicalPlan, types: &[DataType]) -> LogicalPlan {
+    let differs = |columns: &[ColumnDef]| {
+        columns.iter().zip(types).any(|(column, data_type)| {
+            &column.data_type != data_type && column.data_type != DataType::String
+        })
+    };
+    if !differs(&plan.schema().columns) {
+        return plan;
+    }
+
+    let cast = |expr: Expression, column: &mut ColumnDef, data_type: &DataType| {
+        if &column.data_type == data_type || column.data_type == DataType::String {
+            return expr;
+        }
+        column.data_type = data_type.clone();
+        match expr {
+            Expression::Alias { expr, name } => {
+                Expression::alias(Expression::cast(*expr, data_type.clone()), name)
+            }
+            other => Expression::cast(other, data_type.clone()),
+        }
+    };
+
+    match plan {
+        LogicalPlan::Projection(mut projection)
+            if projection.expressions.len() == types.len()
+                && !projection
+                    .expressions
+                    .iter()
+                    .any(|expr| matches!(expr, Expression::Wildcard { .. })) =>
+        {
+            let expressions = std::mem::take(&mut projection.expressions);
+            projection.expressions = expressions
+                .into_iter()
+                .zip(&mut projection.schema.columns)
+                .zip(types)
+                .map(|((expr, column), data_type)| cast(expr, column, data_type))
+                .collect();
+            LogicalPlan::Projection(projection)
+        }
+        other => {
+            let mut columns = other.schema().columns.clone();
+            let expressions = columns
+                .iter_mut()
+                .zip(types)
+                .map(|(column, data_type)| {
+                    let reference = match &column.table {
+                        Some(table) => Expression::qualified_column(table, &column.name),
+                        None => Expression::column(&column.name),
+                    };
+                    let name = column.name.clone();
+                    Expression::alias(cast(reference, column, data_type), name)
+                })
+                .collect();
+            LogicalPlan::Projection(ProjectionNode {
+                expressions,
+                input: Box::new(other),
+                schema: LogicalSchema::new(columns),
+                statistics: crate::types::PlanStatistics::unknown(),
+            })
+        }
+    }
+}
@@ -7,8 +7,9 @@ use crate::{
     common::LogicalPlanError,
     logical_plan::{LogicalPlan, UpdateAssignment, UpdateNode},
     operator::query::QueryPlan,
+    type_check::{check_predicate, coerce_assignment, coerce_expression},
     types::{ColumnDef, LogicalSchema, TableRef},
-    utils::{expr_to_logical_expr, object_name_to_string},
+    utils::{expr_to_logical_expr_with, object_name_to_string},
 };
 
 pub struct UpdatePlan {
@@ -49,6 +50,9 @@ impl UpdatePlan {
             }
         };
 
+        // Assignments and the condition are checked against the table, when its schema is known
+        let table_schema = self.table_schemas.get(&table_ref.name).cloned();
+
         let mut update_assignments = Vec::new();
         for assignment in assignments {
             let column = match &assignment.target {
@@ -60,12 +64,26 @@ impl UpdatePlan {
                 }
             };
 
-            let value = expr_to_logical_expr(&assignment.value)?;
+            let mut value = expr_to_logical_expr_with(&assignment.value, &mut query)?;
+            if let Some(schema) = &table_schema {
+                let target = schema.find_column(&column).ok_or_else(|| {
+                    LogicalPlanError::ColumnNotFound(format!(
+                        "{} in table {}",
+                        column, table_ref.name
+                    ))
+                })?;
+                let (typed, data_type) = coerce_expression(value, schema)?;
+                value = coerce_assignment(typed, data_type.as_ref(), target)?;
+            }
             update_assignments.push(UpdateAssignment { column, value });
         }
 
         let filter = if let Some(selection) = selection {
-            Some(expr_to_logical_expr(selection)?)
+            let predicate = expr_to_logical_expr_with(selection, &mut query)?;
+            match &table_schema {
+                Some(schema) => Some(check_predicate(predicate, schema, "WHERE")?),
+                None => Some(predicate),
+            }
         } else {
             None
         };
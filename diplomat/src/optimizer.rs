@@ -1,153 +1,162 @@
+use std::collections::HashMap;
+
 use crate::{
+    binder::from_scope,
     common::LogicalPlanError,
     expression::{BinaryOperator, Expression, UnaryOperator},
-    logical_plan::{FilterNode, LogicalPlan},
+    logical_plan::LogicalPlan,
+    types::{ColumnDef, ColumnRef, LogicalSchema, PlanStatistics},
+    visitor::{Transformed, TreeNode},
 };
+use matan::manager::CatalogManager;
 use shared_types::Value;
 
+mod cost;
+mod decorrelate;
+mod eliminate;
+mod filter;
+mod limit;
+mod prune;
+pub mod rule;
+
+pub use cost::CostModel;
+pub use eliminate::{EliminateProjections, EliminateSorts};
+pub use filter::{MergeFilters, PushDownFilter};
+pub use limit::PushDownLimit;
+pub use prune::PruneColumns;
+pub use rule::{OptimizerRule, RuleApplication};
+
 pub struct Optimizer {
-    // Should have access to catalog and statistic
+    /// Statistics of each table, by table name
+    table_statistics: HashMap<String, PlanStatistics>,
+    cost_model: CostModel,
+    /// Rewrite rules, applied in order until none of them changes the plan
+    rules: Vec<Box<dyn OptimizerRule>>,
+    max_passes: usize,
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self {
+            table_statistics: HashMap::new(),
+            cost_model: CostModel::default(),
+            rules: rule::default_rules(),
+            max_passes: rule::DEFAULT_MAX_PASSES,
+        }
+    }
 }
 
 impl Optimizer {
     pub fn new() -> Self {
-        Self {}
+        Self::default()
+    }
+
+    /// Use the statistics ANALYZE stored in `catalog` for every table that has them
+    pub fn from_catalog(catalog: &CatalogManager) -> Self {
+        catalog
+            .list_tables()
+            .into_iter()
+            .fold(Self::new(), |optimizer, table| {
+                match catalog.get_table_statistics(&table) {
+                    Some(stats) => optimizer.with_table_statistics(table, stats.into()),
+                    None => optimizer,
+                }
+            })
+    }
+
+    /// Statistics of `table` for the cost-based rules; column statistics are keyed by
+    /// column name
+    pub fn with_table_statistics(
+        mut self,
+        table: impl Into<String>,
+        statistics: PlanStatistics,
+    ) -> Self {
+        self.table_statistics.insert(table.into(), statistics);
+        self
+    }
+
+    pub fn with_cost_model(mut self, cost_model: CostModel) -> Self {
+        self.cost_model = cost_model;
+        self
+    }
+
+    /// Apply `rule` after the rules already registered
+    pub fn with_rule(mut self, rule: impl OptimizerRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Replace the rewrite rules, `rule::default_rules()` by default
+    pub fn with_rules(mut self, rules: Vec<Box<dyn OptimizerRule>>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Stop rewriting after `max_passes` passes over the rules, even if they still
+    /// change the plan
+    pub fn with_max_passes(mut self, max_passes: usize) -> Self {
+        self.max_passes = max_passes;
+        self
     }
 
     /// Main optimization entry point
     pub fn optimize(&self, plan: LogicalPlan) -> Result<LogicalPlan, LogicalPlanError> {
+        self.optimize_with_trace(plan).map(|(plan, _)| plan)
+    }
+
+    /// Optimize `plan`, also returning every application of a rewrite rule, in order
+    pub fn optimize_with_trace(
+        &self,
+        plan: LogicalPlan,
+    ) -> Result<(LogicalPlan, Vec<RuleApplication>), LogicalPlanError> {
         let mut optimized_plan = plan;
+        let mut trace = Vec::new();
 
         // Apply optimization rules in order
+        optimized_plan = self.apply_subquery_decorrelation(optimized_plan)?;
         optimized_plan = self.apply_constant_folding(optimized_plan)?;
         optimized_plan = self.apply_condition_simplification(optimized_plan)?;
-        optimized_plan = self.apply_predicate_pushdown(optimized_plan)?;
+        optimized_plan = self.apply_rewrite_rules(optimized_plan, &mut trace)?;
+        optimized_plan = self.apply_cost_based_optimization(optimized_plan)?;
 
-        Ok(optimized_plan)
+        Ok((optimized_plan, trace))
     }
 
-    /// Rule 1: Apply WHERE conditions as close to the data source as possible
-    fn apply_predicate_pushdown(&self, plan: LogicalPlan) -> Result<LogicalPlan, LogicalPlanError> {
-        match plan {
-            LogicalPlan::Filter(filter_node) => {
-                let input = *filter_node.input;
-                match input {
-                    LogicalPlan::TableScan(mut table_scan) => {
-                        // Push filter down to table scan
-                        table_scan.filters.push(filter_node.predicate);
-                        Ok(LogicalPlan::TableScan(table_scan))
-                    }
-                    LogicalPlan::Projection(proj_node) => {
-                        // Try to push filter below projection if possible
-                        if self.can_push_through_projection(
-                            &filter_node.predicate,
-                            &proj_node.expressions,
-                        ) {
-                            let rewritten_predicate = self.rewrite_predicate_for_projection(
-                                &filter_node.predicate,
-                                &proj_node.expressions,
-                            )?;
-
-                            let new_filter = FilterNode {
-                                predicate: rewritten_predicate,
-                                input: proj_node.input.clone(),
-                                statistics: filter_node.statistics,
-                            };
-
-                            let optimized_input =
-                                self.apply_predicate_pushdown(LogicalPlan::Filter(new_filter))?;
-
-                            let new_projection =
-                                LogicalPlan::Projection(crate::logical_plan::ProjectionNode {
-                                    expressions: proj_node.expressions,
-                                    input: Box::new(optimized_input),
-                                    schema: proj_node.schema,
-                                    statistics: proj_node.statistics,
-                                });
-
-                            Ok(new_projection)
-                        } else {
-                            // Can't push through, keep filter above projection
-                            let optimized_input =
-                                self.apply_predicate_pushdown(*proj_node.input)?;
-                            let new_projection =
-                                LogicalPlan::Projection(crate::logical_plan::ProjectionNode {
-                                    expressions: proj_node.expressions,
-                                    input: Box::new(optimized_input),
-                                    schema: proj_node.schema,
-                                    statistics: proj_node.statistics,
-                                });
-
-                            Ok(LogicalPlan::Filter(FilterNode {
-                                predicate: filter_node.predicate,
-                                input: Box::new(new_projection),
-                                statistics: filter_node.statistics,
-                            }))
-                        }
-                    }
-                    _ => {
-                        // Recursively optimize the input
-                        let optimized_input = self.apply_predicate_pushdown(input)?;
-                        Ok(LogicalPlan::Filter(FilterNode {
-                            predicate: filter_node.predicate,
-                            input: Box::new(optimized_input),
-                            statistics: filter_node.statistics,
-                        }))
-                    }
-                }
-            }
-            _ => {
-                // Recursively apply to children
-                let children = plan.children();
-                let mut optimized_children = Vec::new();
-
-                for child in children {
-                    optimized_children.push(self.apply_predicate_pushdown(child.clone())?);
-                }
-
-                self.with_new_children(plan, optimized_children)
-            }
-        }
+    /// Rule 1: Rewrite subqueries in filters into semi, anti and outer joins, so the
+    /// remaining rules see them as ordinary joins
+    fn apply_subquery_decorrelation(
+        &self,
+        plan: LogicalPlan,
+    ) -> Result<LogicalPlan, LogicalPlanError> {
+        decorrelate::Decorrelator::default().decorrelate(plan)
     }
 
     /// Rule 2: Evaluate constant expressions at optimization time
     fn apply_constant_folding(&self, plan: LogicalPlan) -> Result<LogicalPlan, LogicalPlanError> {
-        match plan {
+        plan.transform_up(|plan| match plan {
             LogicalPlan::Filter(mut filter_node) => {
-                filter_node.predicate = self.fold_constants(filter_node.predicate)?;
-                let optimized_input = self.apply_constant_folding(*filter_node.input)?;
-                Ok(LogicalPlan::Filter(FilterNode {
-                    predicate: filter_node.predicate,
-                    input: Box::new(optimized_input),
-                    statistics: filter_node.statistics,
-                }))
+                let predicate = self.fold_constants(filter_node.predicate)?;
+                filter_node.predicate = predicate.data;
+                Ok(Transformed::new(
+                    LogicalPlan::Filter(filter_node),
+                    predicate.transformed,
+                ))
             }
             LogicalPlan::Projection(mut proj_node) => {
+                let mut transformed = false;
                 for expr in &mut proj_node.expressions {
-                    *expr = self.fold_constants(expr.clone())?;
+                    let folded = self.fold_constants(expr.clone())?;
+                    transformed |= folded.transformed;
+                    *expr = folded.data;
                 }
-                let optimized_input = self.apply_constant_folding(*proj_node.input)?;
-                Ok(LogicalPlan::Projection(
-                    crate::logical_plan::ProjectionNode {
-                        expressions: proj_node.expressions,
-                        input: Box::new(optimized_input),
-                        schema: proj_node.schema,
-                        statistics: proj_node.statistics,
-                    },
+                Ok(Transformed::new(
+                    LogicalPlan::Projection(proj_node),
+                    transformed,
                 ))
             }
-            _ => {
-                // Recursively apply to children
-                let children = plan.children();
-                let mut optimized_children = Vec::new();
-
-                for child in children {
-                    optimized_children.push(self.apply_constant_folding(child.clone())?);
-                }
-
-                self.with_new_children(plan, optimized_children)
-            }
-        }
+            other => Ok(Transformed::no(other)),
+        })
+        .map(|plan| plan.data)
     }
 
     /// Rule 3: Remove duplicate or contradictory conditions
@@ -155,87 +164,122 @@ impl Optimizer {
         &self,
         plan: LogicalPlan,
     ) -> Result<LogicalPlan, LogicalPlanError> {
-        match plan {
+        plan.transform_up(|plan| match plan {
             LogicalPlan::Filter(mut filter_node) => {
-                filter_node.predicate = self.simplify_conditions(filter_node.predicate)?;
-                let optimized_input = self.apply_condition_simplification(*filter_node.input)?;
-                Ok(LogicalPlan::Filter(FilterNode {
-                    predicate: filter_node.predicate,
-                    input: Box::new(optimized_input),
-                    statistics: filter_node.statistics,
-                }))
+                let predicate = self.simplify_conditions(filter_node.predicate)?;
+                filter_node.predicate = predicate.data;
+                Ok(Transformed::new(
+                    LogicalPlan::Filter(filter_node),
+                    predicate.transformed,
+                ))
             }
             LogicalPlan::TableScan(mut table_scan) => {
-                table_scan.filters = self.simplify_filter_list(table_scan.filters)?;
-                Ok(LogicalPlan::TableScan(table_scan))
+                let filters = self.simplify_filter_list(table_scan.filters)?;
+                table_scan.filters = filters.data;
+                Ok(Transformed::new(
+                    LogicalPlan::TableScan(table_scan),
+                    filters.transformed,
+                ))
             }
-            _ => {
-                // Recursively apply to children
-                let children = plan.children();
-                let mut optimized_children = Vec::new();
+            other => Ok(Transformed::no(other)),
+        })
+        .map(|plan| plan.data)
+    }
 
-                for child in children {
-                    optimized_children.push(self.apply_condition_simplification(child.clone())?);
-                }
+    /// Rule 4: Push filters and limits down, prune unused columns and drop needless
+    /// sorts and projections, until none of the rewrite rules changes the plan
+    fn apply_rewrite_rules(
+        &self,
+        plan: LogicalPlan,
+        trace: &mut Vec<RuleApplication>,
+    ) -> Result<LogicalPlan, LogicalPlanError> {
+        rule::apply_to_fixpoint(&self.rules, plan, self.max_passes, trace)
+    }
 
-                self.with_new_children(plan, optimized_children)
-            }
-        }
+    /// Rule 5: Reorder inner joins by estimated cardinality, then choose the algorithm
+    /// and build side of every join and the parallelism of every scan
+    fn apply_cost_based_optimization(
+        &self,
+        plan: LogicalPlan,
+    ) -> Result<LogicalPlan, LogicalPlanError> {
+        Ok(cost::CostBasedOptimizer::new(&self.cost_model, &self.table_statistics).optimize(plan))
     }
 
     /// Helper: Fold constant expressions
-    fn fold_constants(&self, expr: Expression) -> Result<Expression, LogicalPlanError> {
+    fn fold_constants(
+        &self,
+        expr: Expression,
+    ) -> Result<Transformed<Expression>, LogicalPlanError> {
         match expr {
             Expression::BinaryOp { left, op, right } => {
                 let left_folded = self.fold_constants(*left)?;
                 let right_folded = self.fold_constants(*right)?;
+                let transformed = left_folded.transformed || right_folded.transformed;
+                let (left_folded, right_folded) = (left_folded.data, right_folded.data);
 
                 // Try to evaluate if both sides are literals
                 if let (Expression::Literal(left_val), Expression::Literal(right_val)) =
                     (&left_folded, &right_folded)
                 {
                     self.evaluate_binary_op(left_val, &op, right_val)
-                        .map(Expression::Literal)
+                        .map(|value| Transformed::yes(Expression::Literal(value)))
                         .or_else(|_| {
-                            Ok(Expression::BinaryOp {
-                                left: Box::new(left_folded),
-                                op,
-                                right: Box::new(right_folded),
-                            })
+                            Ok(Transformed::new(
+                                Expression::BinaryOp {
+                                    left: Box::new(left_folded),
+                                    op,
+                                    right: Box::new(right_folded),
+                                },
+                                transformed,
+                            ))
                         })
                 } else {
-                    Ok(Expression::BinaryOp {
-                        left: Box::new(left_folded),
-                        op,
-                        right: Box::new(right_folded),
-                    })
+                    Ok(Transformed::new(
+                        Expression::BinaryOp {
+                            left: Box::new(left_folded),
+                            op,
+                            right: Box::new(right_folded),
+                        },
+                        transformed,
+                    ))
                 }
             }
             Expression::UnaryOp { op, expr } => {
                 let expr_folded = self.fold_constants(*expr)?;
+                let transformed = expr_folded.transformed;
+                let expr_folded = expr_folded.data;
 
                 if let Expression::Literal(val) = &expr_folded {
                     self.evaluate_unary_op(&op, val)
-                        .map(Expression::Literal)
+                        .map(|value| Transformed::yes(Expression::Literal(value)))
                         .or_else(|_| {
-                            Ok(Expression::UnaryOp {
-                                op,
-                                expr: Box::new(expr_folded),
-                            })
+                            Ok(Transformed::new(
+                                Expression::UnaryOp {
+                                    op,
+                                    expr: Box::new(expr_folded),
+                                },
+                                transformed,
+                            ))
                         })
                 } else {
-                    Ok(Expression::UnaryOp {
-                        op,
-                        expr: Box::new(expr_folded),
-                    })
+                    Ok(Transformed::new(
+                        Expression::UnaryOp {
+                            op,
+                            expr: Box::new(expr_folded),
+                        },
+                        transformed,
+                    ))
                 }
             }
-            _ => Ok(expr), // Return other expressions unchanged
+            _ => Ok(Transformed::no(expr)), // Return other expressions unchanged
         }
     }
 
     /// Helper: Simplify conditions (remove duplicates and contradictions)
-    fn simplify_conditions(&self, expr: Expression) -> Result<Expression, LogicalPlanError> {
+    fn simplify_conditions(
+        &self,
+        expr: Expression,
+    ) -> Result<Transformed<Expression>, LogicalPlanError> {
         match expr {
             Expression::BinaryOp {
                 left,
@@ -244,14 +288,17 @@ impl Optimizer {
             } => {
                 let left_simplified = self.simplify_conditions(*left)?;
                 let right_simplified = self.simplify_conditions(*right)?;
+                let transformed = left_simplified.transformed || right_simplified.transformed;
+                let (left_simplified, right_simplified) =
+                    (left_simplified.data, right_simplified.data);
 
                 // Check for contradictions and duplicates
                 if self.are_conditions_contradictory(&left_simplified, &right_simplified) {
                     // Return FALSE condition
-                    return Ok(Expression::Literal(Value::Boolean(false)));
+                    return Ok(Transformed::yes(Expression::Literal(Value::Boolean(false))));
                 } else if left_simplified == right_simplified {
                     // Remove duplicate
-                    return Ok(left_simplified);
+                    return Ok(Transformed::yes(left_simplified));
                 } else if let Expression::BinaryOp {
                     left: nested_left,
                     op: BinaryOperator::Gt,
@@ -271,22 +318,25 @@ impl Optimizer {
                                 (nested_right.as_ref(), nested_right2.as_ref())
                             {
                                 if self.compare_values(val1, val2)? > 0 {
-                                    return Ok(left_simplified);
+                                    return Ok(Transformed::yes(left_simplified));
                                 } else {
-                                    return Ok(right_simplified);
+                                    return Ok(Transformed::yes(right_simplified));
                                 }
                             }
                         }
                     }
                 }
 
-                Ok(Expression::BinaryOp {
-                    left: Box::new(left_simplified),
-                    op: BinaryOperator::And,
-                    right: Box::new(right_simplified),
-                })
+                Ok(Transformed::new(
+                    Expression::BinaryOp {
+                        left: Box::new(left_simplified),
+                        op: BinaryOperator::And,
+                        right: Box::new(right_simplified),
+                    },
+                    transformed,
+                ))
             }
-            _ => Ok(expr),
+            _ => Ok(Transformed::no(expr)),
         }
     }
 
@@ -294,43 +344,23 @@ impl Optimizer {
     fn simplify_filter_list(
         &self,
         mut filters: Vec<Expression>,
-    ) -> Result<Vec<Expression>, LogicalPlanError> {
+    ) -> Result<Transformed<Vec<Expression>>, LogicalPlanError> {
         // Remove duplicates
+        let sorted = filters.is_sorted_by_key(|filter| format!("{:?}", filter));
+        let count = filters.len();
         filters.sort_by(|a, b| format!("{:?}", a).cmp(&format!("{:?}", b)));
         filters.dedup();
+        let mut transformed = !sorted || filters.len() < count;
 
         // Apply condition simplification to each filter
         let mut simplified_filters = Vec::new();
         for filter in filters {
-            simplified_filters.push(self.simplify_conditions(filter)?);
+            let simplified = self.simplify_conditions(filter)?;
+            transformed |= simplified.transformed;
+            simplified_filters.push(simplified.data);
         }
 
-        Ok(simplified_filters)
-    }
-
-    /// Helper: Check if we can push a predicate through a projection
-    fn can_push_through_projection(
-        &self,
-        predicate: &Expression,
-        _projections: &[Expression],
-    ) -> bool {
-        // Simplified check - in practice, you'd need to verify that all columns
-        // referenced in the predicate are available in the input of the projection
-        matches!(
-            predicate,
-            Expression::Column(_) | Expression::BinaryOp { .. }
-        )
-    }
-
-    /// Helper: Rewrite predicate for projection pushdown
-    fn rewrite_predicate_for_projection(
-        &self,
-        predicate: &Expression,
-        _projections: &[Expression],
-    ) -> Result<Expression, LogicalPlanError> {
-        // Simplified implementation - in practice, you'd need to map column references
-        // from the projection output to the projection input
-        Ok(predicate.clone())
+        Ok(Transformed::new(simplified_filters, transformed))
     }
 
     /// Helper: Evaluate binary operations on literals
@@ -440,41 +470,86 @@ impl Optimizer {
             )),
         }
     }
+}
 
-    /// Helper: Create a new plan with different children
-    fn with_new_children(
-        &self,
-        plan: LogicalPlan,
-        children: Vec<LogicalPlan>,
-    ) -> Result<LogicalPlan, LogicalPlanError> {
-        match plan {
-            LogicalPlan::TableScan(node) => {
-                if !children.is_empty() {
-                    return Err(LogicalPlanError::InternalError(
-                        "TableScan should not have children".to_string(),
-                    ));
-                }
-                Ok(LogicalPlan::TableScan(node))
-            }
-            LogicalPlan::Projection(mut node) => {
-                if children.len() != 1 {
-                    return Err(LogicalPlanError::InternalError(
-                        "Projection should have exactly one child".to_string(),
-                    ));
-                }
-                node.input = Box::new(children.into_iter().next().unwrap());
-                Ok(LogicalPlan::Projection(node))
-            }
-            LogicalPlan::Filter(mut node) => {
-                if children.len() != 1 {
-                    return Err(LogicalPlanError::InternalError(
-                        "Filter should have exactly one child".to_string(),
-                    ));
-                }
-                node.input = Box::new(children.into_iter().next().unwrap());
-                Ok(LogicalPlan::Filter(node))
+/// Columns of the row `plan` produces, qualified the way predicates refer to them.
+/// Unlike `from_scope`, scan columns are qualified by the table even when the plan
+/// was built without a binder.
+fn scope(plan: &LogicalPlan) -> LogicalSchema {
+    match plan {
+        LogicalPlan::TableScan(scan) => LogicalSchema::new(
+            scan.schema
+                .columns
+                .iter()
+                .map(|column| ColumnDef {
+                    table: column
+                        .table
+                        .clone()
+                        .or_else(|| Some(scan.table.effective_name().to_string())),
+                    ..column.clone()
+                })
+                .collect(),
+        ),
+        LogicalPlan::Join(join) if !join.join_type.returns_left_only() => {
+            let mut columns = scope(&join.left).columns;
+            columns.extend(scope(&join.right).columns);
+            LogicalSchema::new(columns)
+        }
+        LogicalPlan::Join(join) => scope(&join.left),
+        LogicalPlan::Filter(filter) => scope(&filter.input),
+        other => from_scope(other),
+    }
+}
+
+fn resolves(scope: &LogicalSchema, column: &ColumnRef) -> bool {
+    scope.columns.iter().any(|def| refers_to(column, def))
+}
+
+/// Position of `column` in `scope`, when it names exactly one of its columns
+fn position(scope: &LogicalSchema, column: &ColumnRef) -> Option<usize> {
+    let mut matches = scope
+        .columns
+        .iter()
+        .enumerate()
+        .filter(|(_, def)| refers_to(column, def));
+    match (matches.next(), matches.next()) {
+        (Some((index, _)), None) => Some(index),
+        _ => None,
+    }
+}
+
+fn refers_to(column: &ColumnRef, def: &ColumnDef) -> bool {
+    def.name == column.name
+        && column
+            .table
+            .as_ref()
+            .is_none_or(|table| def.table.as_ref() == Some(table))
+}
+
+/// Point the columns of `expr` at their position in `scope`; columns it doesn't
+/// contain exactly once are left unbound
+fn bind(expr: &mut Expression, scope: &LogicalSchema) {
+    for column in expr.column_refs_mut() {
+        column.index = position(scope, column);
+    }
+}
+
+/// Replace every column of `expr` with the expression `replace` gives for it.
+/// Subquery plans are left alone, their columns belong to another scope.
+fn replace_columns(expr: &mut Expression, replace: &dyn Fn(&ColumnRef) -> Expression) {
+    match expr {
+        Expression::Column(column) => *expr = replace(column),
+        _ => {
+            for child in expr.children_mut() {
+                replace_columns(child, replace);
             }
-            _ => Ok(plan), // Simplified - you'd need to handle all plan types
         }
     }
 }
+
+fn unaliased(expr: &Expression) -> &Expression {
+    match expr {
+        Expression::Alias { expr, .. } => unaliased(expr),
+        other => other,
+    }
+}
@@ -0,0 +1,1319 @@
+//! Cost-based optimization. Row counts are estimated bottom-up from the statistics of
+//! the tables being scanned, chains of inner joins are reordered to keep intermediate
+//! results small, and every join gets an algorithm and build side and every scan a
+//! degree of parallelism.
+
+use std::collections::{HashMap, HashSet};
+
+use shared_types::Value;
+
+use super::{bind, decorrelate::contains_subquery, resolves, scope};
+use crate::{
+    expression::{BinaryOperator, Expression, UnaryOperator},
+    logical_plan::{FilterNode, JoinNode, LogicalPlan, ProjectionNode},
+    types::{
+        BuildSide, ColumnRef, ColumnStatistics, JoinAlgorithm, JoinStrategy, JoinType,
+        LogicalSchema, PlanStatistics, TableRef,
+    },
+};
+
+/// Rows assumed for tables without statistics
+const DEFAULT_ROW_COUNT: f64 = 1000.0;
+/// Selectivity of `column = value` when the column's distinct count is unknown
+const DEFAULT_EQ_SELECTIVITY: f64 = 0.005;
+/// Selectivity of a range comparison when the column has no histogram or bounds
+const DEFAULT_RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+/// Selectivity of LIKE and of predicates the model does not understand
+const DEFAULT_SELECTIVITY: f64 = 0.25;
+/// Fraction of NULLs assumed for columns without statistics
+const DEFAULT_NULL_FRACTION: f64 = 0.005;
+
+/// Tuning knobs of the cost model. Costs are in abstract units per row.
+#[derive(Debug, Clone)]
+pub struct CostModel {
+    /// Cost of reading a row from a table
+    pub scan_cost: f64,
+    /// Upper bound on the workers a scan is split across
+    pub max_parallelism: usize,
+    /// Rows each scan worker is expected to read
+    pub rows_per_worker: usize,
+    /// Cost of inserting a row into a hash table
+    pub hash_build_cost: f64,
+    /// Cost of looking a row up in a hash table
+    pub hash_probe_cost: f64,
+    /// Cost of comparing two rows, paid for every pair by a nested loop and for every
+    /// sort and merge step by a sort-merge join
+    pub compare_cost: f64,
+    /// Longest chain of inner joins ordered exhaustively; longer ones are ordered greedily
+    pub max_dp_relations: usize,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self {
+            max_parallelism: 8,
+            rows_per_worker: 100_000,
+            scan_cost: 1.0,
+            hash_build_cost: 2.0,
+            hash_probe_cost: 1.0,
+            compare_cost: 1.0,
+            max_dp_relations: 10,
+        }
+    }
+}
+
+impl CostModel {
+    fn nested_loop_cost(&self, left_rows: f64, right_rows: f64) -> f64 {
+        left_rows * right_rows * self.compare_cost
+    }
+
+    fn hash_cost(&self, build_rows: f64, probe_rows: f64) -> f64 {
+        build_rows * self.hash_build_cost + probe_rows * self.hash_probe_cost
+    }
+
+    fn sort_cost(&self, rows: f64) -> f64 {
+        rows * rows.max(2.0).log2() * self.compare_cost
+    }
+
+    /// Cheapest way to join inputs of the given sizes when either may be built
+    fn join_cost(&self, left_rows: f64, right_rows: f64, equi_join: bool) -> f64 {
+        let nested_loop = self.nested_loop_cost(left_rows, right_rows);
+        if !equi_join {
+            return nested_loop;
+        }
+        let hash = self.hash_cost(left_rows.min(right_rows), left_rows.max(right_rows));
+        nested_loop.min(hash)
+    }
+}
+
+pub(super) struct CostBasedOptimizer<'a> {
+    model: &'a CostModel,
+    tables: &'a HashMap<String, PlanStatistics>,
+}
+
+impl<'a> CostBasedOptimizer<'a> {
+    pub(super) fn new(model: &'a CostModel, tables: &'a HashMap<String, PlanStatistics>) -> Self {
+        Self { model, tables }
+    }
+
+    pub(super) fn optimize(&self, plan: LogicalPlan) -> LogicalPlan {
+        let mut plan = self.reorder(plan);
+        self.annotate(&mut plan);
+        plan
+    }
+
+    /// Fill in the statistics of every node, bottom-up, and choose how joins and scans
+    /// are executed
+    fn annotate(&self, plan: &mut LogicalPlan) {
+        for child in plan.children_mut() {
+            self.annotate(child);
+        }
+        for expr in plan.expressions_mut() {
+            for subquery in expr.subquery_plans_mut() {
+                self.annotate(subquery);
+            }
+        }
+
+        let estimate = self.estimate(plan);
+        let mut cost = self.operator_cost(plan);
+        match plan {
+            LogicalPlan::Join(join) => {
+                let (strategy, join_cost) = self.choose_strategy(join);
+                join.strategy = Some(strategy);
+                cost = join_cost;
+            }
+            LogicalPlan::TableScan(scan) => scan.parallelism = Some(self.parallelism(&scan.table)),
+            _ => {}
+        }
+        cost += plan
+            .children()
+            .iter()
+            .filter_map(|child| child.statistics().cost)
+            .sum::<f64>();
+        *plan.statistics_mut() = PlanStatistics {
+            cost: Some(cost),
+            ..estimate.to_statistics()
+        };
+    }
+
+    /// Cost of the work `plan` does itself, once its inputs have produced their rows
+    fn operator_cost(&self, plan: &LogicalPlan) -> f64 {
+        let rows = |plan: &LogicalPlan| Estimate::of(plan.statistics()).rows;
+        let input_rows = plan.children().into_iter().map(rows).sum::<f64>();
+        match plan {
+            LogicalPlan::TableScan(scan) => {
+                self.table_estimate(&scan.table).rows * self.model.scan_cost
+            }
+            LogicalPlan::Filter(_) => input_rows * self.model.compare_cost,
+            LogicalPlan::Sort(_) | LogicalPlan::Window(_) => self.model.sort_cost(input_rows),
+            LogicalPlan::Aggregate(_)
+            | LogicalPlan::Distinct(_)
+            | LogicalPlan::Intersect(_)
+            | LogicalPlan::Except(_) => input_rows * self.model.hash_build_cost,
+            LogicalPlan::Union(union) if !union.all => input_rows * self.model.hash_build_cost,
+            _ => 0.0,
+        }
+    }
+
+    /// Estimated output of `plan` from the statistics of its children
+    fn estimate(&self, plan: &LogicalPlan) -> Estimate {
+        let input = |plan: &LogicalPlan| Estimate::of(plan.statistics());
+        match plan {
+            LogicalPlan::TableScan(scan) => {
+                let table = self.table_estimate(&scan.table);
+                let selectivity: f64 = scan
+                    .filters
+                    .iter()
+                    .map(|filter| selectivity(filter, &table))
+                    .product();
+                let rows = table.rows * selectivity;
+                table.scaled(rows)
+            }
+            LogicalPlan::Filter(node) => {
+                let input = input(&node.input);
+                let rows = input.rows * selectivity(&node.predicate, &input);
+                input.scaled(rows)
+            }
+            LogicalPlan::Projection(node) => {
+                let input = input(&node.input);
+                let columns = node
+                    .expressions
+                    .iter()
+                    .zip(&node.schema.columns)
+                    .filter_map(|(expr, column)| {
+                        let stats = input.column(column_of(expr)?)?;
+                        Some((column.qualified_name(), stats.clone()))
+                    })
+                    .collect();
+                Estimate { columns, ..input }
+            }
+            LogicalPlan::Join(join) => join_estimate(
+                &input(&join.left),
+                &input(&join.right),
+                &join.join_type,
+                join.join_constraint.as_ref(),
+            ),
+            LogicalPlan::Aggregate(node) => {
+                let input = input(&node.input);
+                let groups: Vec<(&Expression, Option<&ColumnStatistics>)> = node
+                    .group_expr
+                    .iter()
+                    .map(|expr| {
+                        (
+                            expr,
+                            column_of(expr).and_then(|column| input.column(column)),
+                        )
+                    })
+                    .collect();
+                let rows = if groups.is_empty() {
+                    1.0
+                } else {
+                    let combinations: f64 = groups
+                        .iter()
+                        .map(
+                            |(_, stats)| match stats.and_then(|stats| stats.distinct_count) {
+                                // NULL forms a group of its own
+                                Some(distinct) => distinct as f64 + 1.0,
+                                None => input.rows.sqrt().max(1.0),
+                            },
+                        )
+                        .product();
+                    combinations.min(input.rows)
+                };
+                let columns = groups
+                    .iter()
+                    .zip(&node.schema.columns)
+                    .filter_map(|((_, stats), column)| {
+                        Some((column.qualified_name(), (*stats)?.clone()))
+                    })
+                    .collect();
+                Estimate { columns, ..input }.scaled(rows)
+            }
+            LogicalPlan::Window(node) => input(&node.input),
+            LogicalPlan::Sort(node) => input(&node.input),
+            LogicalPlan::Limit(node) => {
+                let input = input(&node.input);
+                let mut rows = (input.rows - node.skip.unwrap_or(0) as f64).max(0.0);
+                if let Some(fetch) = node.fetch {
+                    rows = rows.min(fetch as f64);
+                }
+                input.scaled(rows)
+            }
+            LogicalPlan::Distinct(node) => {
+                let input = input(&node.input);
+                let combinations: Option<f64> = node
+                    .input
+                    .schema()
+                    .columns
+                    .iter()
+                    .map(|column| {
+                        let column = ColumnRef {
+                            table: column.table.clone(),
+                            name: column.name.clone(),
+                            index: None,
+                        };
+                        Some(input.column(&column)?.distinct_count? as f64 + 1.0)
+                    })
+                    .product();
+                let rows =
+                    combinations.map_or(input.rows, |combinations| combinations.min(input.rows));
+                input.scaled(rows)
+            }
+            LogicalPlan::Union(node) => {
+                let (left, right) = (input(&node.left), input(&node.right));
+                Estimate {
+                    rows: left.rows + right.rows,
+                    known: left.known && right.known,
+                    columns: HashMap::new(),
+                }
+            }
+            LogicalPlan::Intersect(node) => {
+                let (left, right) = (input(&node.left), input(&node.right));
+                let rows = left.rows.min(right.rows);
+                Estimate {
+                    known: left.known && right.known,
+                    ..left.scaled(rows)
+                }
+            }
+            LogicalPlan::Except(node) => input(&node.left),
+            LogicalPlan::Values(node) => Estimate {
+                rows: node.values.len() as f64,
+                known: true,
+                columns: HashMap::new(),
+            },
+            LogicalPlan::Subquery(node) => {
+                let input = input(&node.subquery);
+                match &node.alias {
+                    Some(alias) => Estimate {
+                        columns: input
+                            .columns
+                            .iter()
+                            .map(|(key, stats)| {
+                                (format!("{}.{}", alias, unqualified(key)), stats.clone())
+                            })
+                            .collect(),
+                        ..input
+                    },
+                    None => input,
+                }
+            }
+            LogicalPlan::With(node) => input(&node.input),
+            LogicalPlan::Insert(_)
+            | LogicalPlan::Update(_)
+            | LogicalPlan::Delete(_)
+            | LogicalPlan::CreateTable(_)
+            | LogicalPlan::DropTable(_)
+            | LogicalPlan::Analyze(_)
+            | LogicalPlan::Explain(_)
+            | LogicalPlan::CteScan(_)
+            | LogicalPlan::RecursiveQuery(_) => input(plan),
+        }
+    }
+
+    /// Statistics of a base table, with its columns keyed by the name the query uses
+    fn table_estimate(&self, table: &TableRef) -> Estimate {
+        let Some(stats) = self.tables.get(&table.name) else {
+            return Estimate::unknown();
+        };
+        let qualifier = table.effective_name();
+        Estimate {
+            columns: stats
+                .column_stats
+                .iter()
+                .map(|(key, stats)| (format!("{}.{}", qualifier, unqualified(key)), stats.clone()))
+                .collect(),
+            ..Estimate::of(stats)
+        }
+    }
+
+    fn parallelism(&self, table: &TableRef) -> usize {
+        let rows = self
+            .tables
+            .get(&table.name)
+            .and_then(|stats| stats.row_count)
+            .unwrap_or(0);
+        rows.div_ceil(self.model.rows_per_worker.max(1))
+            .clamp(1, self.model.max_parallelism.max(1))
+    }
+
+    /// Cheapest algorithm and build side for `join`, whose inputs are already
+    /// annotated, and its cost
+    fn choose_strategy(&self, join: &JoinNode) -> (JoinStrategy, f64) {
+        let rows = |plan: &LogicalPlan| Estimate::of(plan.statistics()).rows;
+        let (left_rows, right_rows) = (rows(&join.left), rows(&join.right));
+        let side_rows = |side: &BuildSide| match side {
+            BuildSide::Left => (left_rows, right_rows),
+            BuildSide::Right => (right_rows, left_rows),
+        };
+
+        // The side whose unmatched rows are returned has to be the probe side
+        let build_sides = match join.join_type {
+            JoinType::Inner | JoinType::Cross | JoinType::Full => {
+                vec![BuildSide::Left, BuildSide::Right]
+            }
+            JoinType::Right => vec![BuildSide::Left],
+            JoinType::Left
+            | JoinType::LeftSemi
+            | JoinType::LeftAnti
+            | JoinType::NullAwareLeftAnti => {
+                vec![BuildSide::Right]
+            }
+        };
+        let smaller_side = build_sides
+            .iter()
+            .copied()
+            .min_by(|a, b| side_rows(a).0.total_cmp(&side_rows(b).0))
+            .unwrap_or(BuildSide::Right);
+
+        let mut best = (
+            self.model.nested_loop_cost(left_rows, right_rows),
+            JoinStrategy {
+                algorithm: JoinAlgorithm::NestedLoop,
+                build_side: smaller_side,
+            },
+        );
+
+        let keys = equi_keys(join);
+        if !keys.is_empty() {
+            for side in &build_sides {
+                let (build_rows, probe_rows) = side_rows(side);
+                let cost = self.model.hash_cost(build_rows, probe_rows);
+                if cost < best.0 {
+                    best = (
+                        cost,
+                        JoinStrategy {
+                            algorithm: JoinAlgorithm::Hash,
+                            build_side: *side,
+                        },
+                    );
+                }
+            }
+
+            let (left_keys, right_keys): (Vec<&ColumnRef>, Vec<&ColumnRef>) =
+                keys.into_iter().unzip();
+            let mut cost = (left_rows + right_rows) * self.model.compare_cost;
+            if !is_sorted_on(&join.left, &left_keys) {
+                cost += self.model.sort_cost(left_rows);
+            }
+            if !is_sorted_on(&join.right, &right_keys) {
+                cost += self.model.sort_cost(right_rows);
+            }
+            if cost < best.0 {
+                best = (
+                    cost,
+                    JoinStrategy {
+                        algorithm: JoinAlgorithm::SortMerge,
+                        build_side: smaller_side,
+                    },
+                );
+            }
+        }
+        (best.1, best.0)
+    }
+
+    /// Rewrite every chain of inner and cross joins, together with the filters right
+    /// above it, into the cheapest join order found
+    fn reorder(&self, mut plan: LogicalPlan) -> LogicalPlan {
+        if is_region_root(&plan)
+            && let Some(reordered) = self.reorder_region(&plan)
+        {
+            return reordered;
+        }
+        for child in plan.children_mut() {
+            *child = self.reorder(child.clone());
+        }
+        plan
+    }
+
+    fn reorder_region(&self, plan: &LogicalPlan) -> Option<LogicalPlan> {
+        let mut leaves = Vec::new();
+        let mut predicates = Vec::new();
+        let original = collect_region(plan, &mut leaves, &mut predicates);
+        if leaves.len() < 2 || leaves.len() > 64 {
+            return None;
+        }
+        let mut leaves: Vec<LogicalPlan> =
+            leaves.into_iter().map(|leaf| self.reorder(leaf)).collect();
+        let scopes: Vec<LogicalSchema> = leaves.iter().map(scope).collect();
+
+        // Predicates are routed to leaves by qualified column name, so every column
+        // needs one
+        let mut seen = HashSet::new();
+        for column in scopes.iter().flat_map(|scope| &scope.columns) {
+            if column.table.is_none() || column.name == "*" || !seen.insert(column.qualified_name())
+            {
+                return None;
+            }
+        }
+
+        // Predicates that can't be placed by the columns they read stay above the joins
+        let mut placed = Vec::new();
+        let mut residual = Vec::new();
+        for predicate in predicates {
+            if contains_subquery(&predicate) || !predicate.is_deterministic() {
+                residual.push(predicate);
+                continue;
+            }
+            let mut mask = 0u64;
+            let mut resolved = true;
+            for column in predicate.column_refs() {
+                let owners: Vec<usize> = (0..scopes.len())
+                    .filter(|i| resolves(&scopes[*i], column))
+                    .collect();
+                match owners.as_slice() {
+                    [owner] => mask |= 1 << owner,
+                    [] => resolved = false,
+                    _ => return None,
+                }
+            }
+            if resolved && mask != 0 {
+                placed.push((predicate, mask));
+            } else {
+                residual.push(predicate);
+            }
+        }
+
+        for leaf in &mut leaves {
+            self.annotate(leaf);
+        }
+        let all = Estimate {
+            rows: 1.0,
+            known: true,
+            columns: leaves
+                .iter()
+                .flat_map(|leaf| leaf.statistics().column_stats.clone())
+                .collect(),
+        };
+        let mut leaf_rows: Vec<f64> = leaves
+            .iter()
+            .map(|leaf| Estimate::of(leaf.statistics()).rows)
+            .collect();
+        let mut join_predicates = Vec::new();
+        for (predicate, mask) in &placed {
+            let leaf_estimate = |i: usize| Estimate::of(leaves[i].statistics());
+            if mask.count_ones() == 1 {
+                let leaf = mask.trailing_zeros() as usize;
+                leaf_rows[leaf] *= selectivity(predicate, &leaf_estimate(leaf));
+            } else {
+                join_predicates.push(JoinPredicate {
+                    mask: *mask,
+                    selectivity: selectivity(predicate, &all),
+                    equi: is_equi_predicate(predicate),
+                });
+            }
+        }
+
+        let graph = JoinGraph {
+            model: self.model,
+            leaf_rows,
+            predicates: join_predicates,
+        };
+        let best = if leaves.len() <= self.model.max_dp_relations {
+            graph.dynamic_programming()
+        } else {
+            graph.greedy()
+        };
+        let tree = if best.0 < graph.cost(&original) {
+            best.1
+        } else {
+            original
+        };
+
+        let mut leaves: Vec<Option<LogicalPlan>> = leaves.into_iter().map(Some).collect();
+        let mut placed: Vec<Option<(Expression, u64)>> = placed.into_iter().map(Some).collect();
+        let (mut joined, _) = build_tree(&tree, &mut leaves, &mut placed);
+
+        if let Some(mut predicate) = Expression::conjunction(residual) {
+            bind(&mut predicate, &scope(&joined));
+            joined = LogicalPlan::Filter(FilterNode {
+                predicate,
+                input: Box::new(joined),
+                statistics: PlanStatistics::unknown(),
+            });
+        }
+
+        // Restore the column order the rest of the query was bound against
+        let original_columns: Vec<_> = scopes
+            .iter()
+            .flat_map(|scope| scope.columns.clone())
+            .collect();
+        let joined_scope = scope(&joined);
+        if joined_scope.columns == original_columns {
+            return Some(joined);
+        }
+        let expressions = original_columns
+            .iter()
+            .map(|column| {
+                let mut column_ref = ColumnRef {
+                    table: column.table.clone(),
+                    name: column.name.clone(),
+                    index: None,
+                };
+                column_ref.index = joined_scope.columns.iter().position(|c| c == column);
+                Expression::Column(column_ref)
+            })
+            .collect();
+        Some(LogicalPlan::Projection(ProjectionNode {
+            expressions,
+            input: Box::new(joined),
+            schema: plan.schema().clone(),
+            statistics: PlanStatistics::unknown(),
+        }))
+    }
+}
+
+/// Working form of `PlanStatistics`, with a row count to compute with even when the
+/// statistics don't have one
+#[derive(Debug, Clone)]
+struct Estimate {
+    rows: f64,
+    /// Whether `rows` derives from table statistics rather than from defaults
+    known: bool,
+    /// Keyed by qualified column name
+    columns: HashMap<String, ColumnStatistics>,
+}
+
+impl Estimate {
+    fn unknown() -> Self {
+        Self {
+            rows: DEFAULT_ROW_COUNT,
+            known: false,
+            columns: HashMap::new(),
+        }
+    }
+
+    fn of(stats: &PlanStatistics) -> Self {
+        Self {
+            rows: stats
+                .row_count
+                .map_or(DEFAULT_ROW_COUNT, |rows| rows as f64),
+            known: stats.row_count.is_some(),
+            columns: stats.column_stats.clone(),
+        }
+    }
+
+    fn to_statistics(&self) -> PlanStatistics {
+        PlanStatistics {
+            row_count: self.known.then(|| self.rows.round() as usize),
+            size_bytes: None,
+            cost: None,
+            column_stats: self.columns.clone(),
+        }
+    }
+
+    fn column(&self, column: &ColumnRef) -> Option<&ColumnStatistics> {
+        if column.table.is_some() {
+            return self.columns.get(&column.qualified_name());
+        }
+        let mut matches = self
+            .columns
+            .iter()
+            .filter(|(key, _)| unqualified(key) == column.name);
+        let (_, stats) = matches.next()?;
+        matches.next().is_none().then_some(stats)
+    }
+
+    fn null_fraction(&self, column: &ColumnRef) -> f64 {
+        self.column(column)
+            .and_then(|stats| stats.null_fraction(self.rows.round() as usize))
+            .unwrap_or(DEFAULT_NULL_FRACTION)
+    }
+
+    /// The same rows filtered or multiplied to `rows`: distinct counts are capped at
+    /// the new row count and null counts change in proportion
+    fn scaled(mut self, rows: f64) -> Self {
+        let ratio = if self.rows > 0.0 {
+            rows / self.rows
+        } else {
+            0.0
+        };
+        for stats in self.columns.values_mut() {
+            stats.null_count = stats
+                .null_count
+                .map(|nulls| (nulls as f64 * ratio).round() as usize);
+            stats.distinct_count = stats
+                .distinct_count
+                .map(|distinct| distinct.min(rows.ceil() as usize));
+        }
+        self.rows = rows;
+        self
+    }
+}
+
+fn join_estimate(
+    left: &Estimate,
+    right: &Estimate,
+    join_type: &JoinType,
+    constraint: Option<&Expression>,
+) -> Estimate {
+    let mut combined = Estimate {
+        rows: left.rows * right.rows,
+        known: left.known && right.known,
+        columns: left.columns.clone(),
+    };
+    combined.columns.extend(right.columns.clone());
+    let selectivity = constraint.map_or(1.0, |constraint| selectivity(constraint, &combined));
+    let inner = combined.rows * selectivity;
+
+    // Fraction of the rows of each side that find at least one match
+    let left_matched = (right.rows * selectivity).min(1.0);
+    let right_matched = (left.rows * selectivity).min(1.0);
+    let rows = match join_type {
+        JoinType::Inner | JoinType::Cross => inner,
+        JoinType::Left => inner.max(left.rows),
+        JoinType::Right => inner.max(right.rows),
+        JoinType::Full => {
+            inner + left.rows * (1.0 - left_matched) + right.rows * (1.0 - right_matched)
+        }
+        JoinType::LeftSemi => left.rows * left_matched,
+        JoinType::LeftAnti | JoinType::NullAwareLeftAnti => left.rows * (1.0 - left_matched),
+    };
+
+    let mut output = left.clone().scaled(rows);
+    if !join_type.returns_left_only() {
+        output.columns.extend(right.clone().scaled(rows).columns);
+        output.known = combined.known;
+    }
+    output
+}
+
+/// Estimated fraction of the rows of `input` for which `predicate` is true
+fn selectivity(predicate: &Expression, input: &Estimate) -> f64 {
+    let selectivity = match predicate {
+        Expression::Literal(Value::Boolean(true)) => 1.0,
+        Expression::Literal(Value::Boolean(false) | Value::Null) => 0.0,
+        Expression::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => selectivity(left, input) * selectivity(right, input),
+        Expression::BinaryOp {
+            left,
+            op: BinaryOperator::Or,
+            right,
+        } => {
+            let (left, right) = (selectivity(left, input), selectivity(right, input));
+            left + right - left * right
+        }
+        Expression::UnaryOp {
+            op: UnaryOperator::Not,
+            expr,
+        } => 1.0 - selectivity(expr, input),
+        Expression::BinaryOp { left, op, right } => comparison_selectivity(left, op, right, input),
+        Expression::IsNull(expr) => {
+            column_of(expr).map_or(DEFAULT_NULL_FRACTION, |column| input.null_fraction(column))
+        }
+        Expression::IsNotNull(expr) => {
+            1.0 - column_of(expr)
+                .map_or(DEFAULT_NULL_FRACTION, |column| input.null_fraction(column))
+        }
+        Expression::In {
+            expr,
+            list,
+            negated,
+        } => {
+            let matches: f64 = list
+                .iter()
+                .map(|item| comparison_selectivity(expr, &BinaryOperator::Eq, item, input))
+                .sum();
+            if *negated {
+                non_null_fraction(expr, input) - matches
+            } else {
+                matches
+            }
+        }
+        Expression::Between {
+            expr,
+            low,
+            high,
+            negated,
+        } => {
+            let between = match (column_of(expr), literal_of(low), literal_of(high)) {
+                (Some(column), Some(low), Some(high)) => input
+                    .column(column)
+                    .and_then(|stats| {
+                        let fraction = fraction_below(stats, high)? - fraction_below(stats, low)?
+                            + rest_equal_fraction(stats);
+                        let (common, rest) = split_common_values(
+                            stats,
+                            1.0 - input.null_fraction(column),
+                            |common| comparable(common, low) && low <= common && common <= high,
+                        );
+                        Some(common + fraction.clamp(0.0, 1.0) * rest)
+                    })
+                    .unwrap_or(DEFAULT_RANGE_SELECTIVITY),
+                _ => DEFAULT_RANGE_SELECTIVITY,
+            };
+            if *negated {
+                non_null_fraction(expr, input) - between
+            } else {
+                between
+            }
+        }
+        Expression::Like { negated, .. } => {
+            if *negated {
+                1.0 - DEFAULT_SELECTIVITY
+            } else {
+                DEFAULT_SELECTIVITY
+            }
+        }
+        Expression::Alias { expr, .. } => selectivity(expr, input),
+        _ => DEFAULT_SELECTIVITY,
+    };
+    selectivity.clamp(0.0, 1.0)
+}
+
+fn comparison_selectivity(
+    left: &Expression,
+    op: &BinaryOperator,
+    right: &Expression,
+    input: &Estimate,
+) -> f64 {
+    // Put the column on the left: `5 < x` is `x > 5`
+    let (column, op, other) = match (column_of(left), column_of(right)) {
+        (Some(column), _) => (column, op.clone(), right),
+        (None, Some(column)) => match flip(op) {
+            Some(op) => (column, op, left),
+            None => return DEFAULT_SELECTIVITY,
+        },
+        (None, None) => return DEFAULT_SELECTIVITY,
+    };
+
+    if let Some(other) = column_of(other) {
+        let equal = equi_join_selectivity(column, other, input);
+        return match op {
+            BinaryOperator::Eq | BinaryOperator::IsNotDistinctFrom => equal,
+            BinaryOperator::NotEq | BinaryOperator::IsDistinctFrom => 1.0 - equal,
+            BinaryOperator::Lt
+            | BinaryOperator::LtEq
+            | BinaryOperator::Gt
+            | BinaryOperator::GtEq => DEFAULT_RANGE_SELECTIVITY,
+            _ => DEFAULT_SELECTIVITY,
+        };
+    }
+
+    let stats = input.column(column);
+    let non_null = 1.0 - input.null_fraction(column);
+    let Some(value) = literal_of(other) else {
+        return match op {
+            BinaryOperator::Eq => DEFAULT_EQ_SELECTIVITY,
+            BinaryOperator::NotEq => non_null - DEFAULT_EQ_SELECTIVITY,
+            BinaryOperator::Lt
+            | BinaryOperator::LtEq
+            | BinaryOperator::Gt
+            | BinaryOperator::GtEq => DEFAULT_RANGE_SELECTIVITY,
+            _ => DEFAULT_SELECTIVITY,
+        };
+    };
+    match op {
+        BinaryOperator::Eq | BinaryOperator::IsNotDistinctFrom => {
+            equal_selectivity(stats, value, non_null)
+        }
+        BinaryOperator::NotEq | BinaryOperator::IsDistinctFrom => {
+            non_null - equal_selectivity(stats, value, non_null)
+        }
+        BinaryOperator::Lt | BinaryOperator::LtEq | BinaryOperator::Gt | BinaryOperator::GtEq => {
+            let Some((stats, below)) =
+                stats.and_then(|stats| Some((stats, fraction_below(stats, value)?)))
+            else {
+                return DEFAULT_RANGE_SELECTIVITY;
+            };
+            let equal = rest_equal_fraction(stats);
+            let fraction = match op {
+                BinaryOperator::Lt => below,
+                BinaryOperator::LtEq => below + equal,
+                BinaryOperator::Gt => 1.0 - below - equal,
+                _ => 1.0 - below,
+            };
+            let (common, rest) = split_common_values(stats, non_null, |common| {
+                comparable(common, value)
+                    && match op {
+                        BinaryOperator::Lt => common < value,
+                        BinaryOperator::LtEq => common <= value,
+                        BinaryOperator::Gt => common > value,
+                        _ => common >= value,
+                    }
+            });
+            common + fraction.clamp(0.0, 1.0) * rest
+        }
+        _ => DEFAULT_SELECTIVITY,
+    }
+}
+
+/// `a = b` matches a row of the cross product with probability 1 / max(ndv(a), ndv(b)),
+/// assuming the values of the side with fewer distinct values all occur on the other
+fn equi_join_selectivity(left: &ColumnRef, right: &ColumnRef, input: &Estimate) -> f64 {
+    let distinct = |column: &ColumnRef| input.column(column).and_then(|stats| stats.distinct_count);
+    let non_null = (1.0 - input.null_fraction(left)) * (1.0 - input.null_fraction(right));
+    match distinct(left).max(distinct(right)) {
+        Some(distinct) => non_null / distinct.max(1) as f64,
+        None => DEFAULT_EQ_SELECTIVITY,
+    }
+}
+
+fn non_null_fraction(expr: &Expression, input: &Estimate) -> f64 {
+    column_of(expr).map_or(1.0, |column| 1.0 - input.null_fraction(column))
+}
+
+/// Fraction of the rows where a column, non-null in `non_null` of them, equals `value`
+fn equal_selectivity(stats: Option<&ColumnStatistics>, value: &Value, non_null: f64) -> f64 {
+    if value.is_null() {
+        return 0.0;
+    }
+    let Some(stats) = stats else {
+        return DEFAULT_EQ_SELECTIVITY;
+    };
+    let out_of_range = |bound: &Option<Value>, outside: std::cmp::Ordering| {
+        bound
+            .as_ref()
+            .is_some_and(|bound| comparable(bound, value) && value.cmp(bound) == outside)
+    };
+    if out_of_range(&stats.min_value, std::cmp::Ordering::Less)
+        || out_of_range(&stats.max_value, std::cmp::Ordering::Greater)
+    {
+        return 0.0;
+    }
+    let (common, rest) = split_common_values(stats, non_null, |common| {
+        comparable(common, value) && common == value
+    });
+    if common > 0.0 {
+        return common;
+    }
+    rest * rest_equal_fraction(stats)
+}
+
+/// Fraction of the rows holding a most common value that `matches`, and fraction of
+/// the non-null rows holding any other value
+fn split_common_values(
+    stats: &ColumnStatistics,
+    non_null: f64,
+    matches: impl Fn(&Value) -> bool,
+) -> (f64, f64) {
+    let common = stats.most_common_values.as_deref().unwrap_or_default();
+    let total: f64 = common.iter().map(|(_, frequency)| frequency).sum();
+    let matching = common
+        .iter()
+        .filter(|(value, _)| matches(value))
+        .map(|(_, frequency)| frequency)
+        .sum();
+    (matching, (non_null - total).max(0.0))
+}
+
+/// Fraction of the values outside the most common ones that equal any one of them
+fn rest_equal_fraction(stats: &ColumnStatistics) -> f64 {
+    let common = stats.most_common_values.as_ref().map_or(0, Vec::len);
+    match stats.distinct_count {
+        Some(distinct) if distinct > common => 1.0 / (distinct - common) as f64,
+        Some(_) => 0.0,
+        None => DEFAULT_EQ_SELECTIVITY,
+    }
+}
+
+/// Fraction of the non-null values of a column below `value`, from its histogram or,
+/// without one, by interpolating between its minimum and maximum
+fn fraction_below(stats: &ColumnStatistics, value: &Value) -> Option<f64> {
+    if let Some(bounds) = &stats.histogram
+        && bounds.len() >= 2
+        && comparable(&bounds[0], value)
+    {
+        if value <= &bounds[0] {
+            return Some(0.0);
+        }
+        if value > &bounds[bounds.len() - 1] {
+            return Some(1.0);
+        }
+        // First bound at or above `value`; the value falls in the bucket ending there
+        let bucket = bounds.partition_point(|bound| bound < value);
+        let within = interpolate(&bounds[bucket - 1], &bounds[bucket], value).unwrap_or(0.5);
+        return Some(((bucket - 1) as f64 + within) / (bounds.len() - 1) as f64);
+    }
+
+    let (min, max) = (stats.min_value.as_ref()?, stats.max_value.as_ref()?);
+    if !comparable(min, value) {
+        return None;
+    }
+    if value <= min {
+        Some(0.0)
+    } else if value > max {
+        Some(1.0)
+    } else {
+        interpolate(min, max, value)
+    }
+}
+
+/// Position of `value` between `low` and `high`, as a fraction
+fn interpolate(low: &Value, high: &Value, value: &Value) -> Option<f64> {
+    let (low, high, value) = (position(low)?, position(high)?, position(value)?);
+    if high <= low {
+        return Some(0.5);
+    }
+    Some(((value - low) / (high - low)).clamp(0.0, 1.0))
+}
+
+/// Numeric position of a value on its type's number line
+fn position(value: &Value) -> Option<f64> {
+    match value {
+        Value::Date(days) => Some(*days as f64),
+        Value::Time(millis) => Some(*millis as f64),
+        Value::Timestamp(millis) | Value::DateTime(millis) => Some(*millis as f64),
+        other => other.as_f64(),
+    }
+}
+
+/// Whether ordering `a` against `b` means anything: both numeric or temporal, or of the
+/// same type
+fn comparable(a: &Value, b: &Value) -> bool {
+    (a.as_f64().is_some() && b.as_f64().is_some())
+        || std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+fn column_of(expr: &Expression) -> Option<&ColumnRef> {
+    match expr {
+        Expression::Column(column) => Some(column),
+        Expression::Alias { expr, .. } => column_of(expr),
+        _ => None,
+    }
+}
+
+fn literal_of(expr: &Expression) -> Option<&Value> {
+    match expr {
+        Expression::Literal(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn flip(op: &BinaryOperator) -> Option<BinaryOperator> {
+    match op {
+        BinaryOperator::Lt => Some(BinaryOperator::Gt),
+        BinaryOperator::LtEq => Some(BinaryOperator::GtEq),
+        BinaryOperator::Gt => Some(BinaryOperator::Lt),
+        BinaryOperator::GtEq => Some(BinaryOperator::LtEq),
+        BinaryOperator::Eq
+        | BinaryOperator::NotEq
+        | BinaryOperator::IsDistinctFrom
+        | BinaryOperator::IsNotDistinctFrom => Some(op.clone()),
+        _ => None,
+    }
+}
+
+/// Column name without its table qualifier
+fn unqualified(key: &str) -> &str {
+    key.rsplit_once('.').map_or(key, |(_, name)| name)
+}
+
+fn is_equi_predicate(predicate: &Expression) -> bool {
+    matches!(
+        predicate,
+        Expression::BinaryOp { left, op: BinaryOperator::Eq, right }
+            if column_of(left).is_some() && column_of(right).is_some()
+    )
+}
+
+/// Pairs of columns, left input first, that the constraint of `join` equates
+fn equi_keys(join: &JoinNode) -> Vec<(&ColumnRef, &ColumnRef)> {
+    let (left, right) = (scope(&join.left), scope(&join.right));
+    let mut keys = Vec::new();
+    let mut conjuncts = join.join_constraint.iter().collect::<Vec<_>>();
+    while let Some(conjunct) = conjuncts.pop() {
+        let Expression::BinaryOp {
+            left: a,
+            op,
+            right: b,
+        } = conjunct
+        else {
+            continue;
+        };
+        match op {
+            BinaryOperator::And => conjuncts.extend([a.as_ref(), b.as_ref()]),
+            BinaryOperator::Eq => {
+                if let (Some(a), Some(b)) = (column_of(a), column_of(b)) {
+                    if resolves(&left, a) && resolves(&right, b) {
+                        keys.push((a, b));
+                    } else if resolves(&left, b) && resolves(&right, a) {
+                        keys.push((b, a));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    keys
+}
+
+/// Whether `plan` is a sort whose leading keys are `keys`, in order
+fn is_sorted_on(plan: &LogicalPlan, keys: &[&ColumnRef]) -> bool {
+    let LogicalPlan::Sort(sort) = plan else {
+        return false;
+    };
+    sort.expressions.len() >= keys.len()
+        && sort.expressions.iter().zip(keys).all(|(sort_expr, key)| {
+            column_of(&sort_expr.expr)
+                .is_some_and(|column| column.name == key.name && column.table == key.table)
+        })
+}
+
+fn is_region_root(plan: &LogicalPlan) -> bool {
+    match plan {
+        LogicalPlan::Join(join) => matches!(join.join_type, JoinType::Inner | JoinType::Cross),
+        LogicalPlan::Filter(filter) => is_region_root(&filter.input),
+        _ => false,
+    }
+}
+
+/// Split a region into its leaves and the conjuncts of its filters and join
+/// constraints, returning its current shape
+fn collect_region(
+    plan: &LogicalPlan,
+    leaves: &mut Vec<LogicalPlan>,
+    predicates: &mut Vec<Expression>,
+) -> JoinTree {
+    match plan {
+        LogicalPlan::Filter(filter) if is_region_root(&filter.input) => {
+            predicates.extend(filter.predicate.clone().split_conjunction());
+            collect_region(&filter.input, leaves, predicates)
+        }
+        LogicalPlan::Join(join) if matches!(join.join_type, JoinType::Inner | JoinType::Cross) => {
+            if let Some(constraint) = &join.join_constraint {
+                predicates.extend(constraint.clone().split_conjunction());
+            }
+            let left = collect_region(&join.left, leaves, predicates);
+            let right = collect_region(&join.right, leaves, predicates);
+            JoinTree::Join(Box::new(left), Box::new(right))
+        }
+        leaf => {
+            leaves.push(leaf.clone());
+            JoinTree::Leaf(leaves.len() - 1)
+        }
+    }
+}
+
+/// Rebuild the joins of `tree`, placing each predicate at the lowest node that has all
+/// its columns. Returns the plan and the leaves it covers.
+fn build_tree(
+    tree: &JoinTree,
+    leaves: &mut [Option<LogicalPlan>],
+    predicates: &mut [Option<(Expression, u64)>],
+) -> (LogicalPlan, u64) {
+    let (mut plan, mask) = match tree {
+        JoinTree::Leaf(leaf) => {
+            let plan = leaves[*leaf]
+                .take()
+                .expect("each leaf appears once in a join tree");
+            (plan, 1 << leaf)
+        }
+        JoinTree::Join(left, right) => {
+            let (left, left_mask) = build_tree(left, leaves, predicates);
+            let (right, right_mask) = build_tree(right, leaves, predicates);
+            let mut columns = left.schema().columns.clone();
+            columns.extend(right.schema().columns.clone());
+            let join = LogicalPlan::Join(JoinNode {
+                left: Box::new(left),
+                right: Box::new(right),
+                join_type: JoinType::Cross,
+                join_constraint: None,
+                strategy: None,
+                schema: LogicalSchema::new(columns),
+                statistics: PlanStatistics::unknown(),
+            });
+            (join, left_mask | right_mask)
+        }
+    };
+
+    let covered: Vec<Expression> = predicates
+        .iter_mut()
+        .filter(|slot| slot.as_ref().is_some_and(|(_, needed)| needed & !mask == 0))
+        .filter_map(|slot| slot.take().map(|(predicate, _)| predicate))
+        .collect();
+    if covered.is_empty() {
+        return (plan, mask);
+    }
+    let plan_scope = scope(&plan);
+    let mut covered = covered;
+    for predicate in &mut covered {
+        bind(predicate, &plan_scope);
+    }
+
+    let plan = match plan {
+        LogicalPlan::Join(ref mut join) => {
+            join.join_type = JoinType::Inner;
+            join.join_constraint = Expression::conjunction(covered);
+            plan
+        }
+        LogicalPlan::TableScan(mut scan) => {
+            scan.filters.extend(covered);
+            LogicalPlan::TableScan(scan)
+        }
+        leaf => LogicalPlan::Filter(FilterNode {
+            predicate: Expression::conjunction(covered).expect("covered is not empty"),
+            input: Box::new(leaf),
+            statistics: PlanStatistics::unknown(),
+        }),
+    };
+    (plan, mask)
+}
+
+/// Shape of a join order over the leaves of a region
+#[derive(Debug, Clone)]
+enum JoinTree {
+    Leaf(usize),
+    Join(Box<JoinTree>, Box<JoinTree>),
+}
+
+impl JoinTree {
+    fn mask(&self) -> u64 {
+        match self {
+            JoinTree::Leaf(leaf) => 1 << leaf,
+            JoinTree::Join(left, right) => left.mask() | right.mask(),
+        }
+    }
+}
+
+/// A predicate that joins two or more leaves
+struct JoinPredicate {
+    mask: u64,
+    selectivity: f64,
+    equi: bool,
+}
+
+/// Leaves and join predicates of a region, for costing join orders
+struct JoinGraph<'a> {
+    model: &'a CostModel,
+    /// Rows of each leaf after its own predicates
+    leaf_rows: Vec<f64>,
+    predicates: Vec<JoinPredicate>,
+}
+
+impl JoinGraph<'_> {
+    /// Rows from joining the leaves in `set`, whatever the order
+    fn rows(&self, set: u64) -> f64 {
+        let leaves: f64 = (0..self.leaf_rows.len())
+            .filter(|leaf| set & (1 << leaf) != 0)
+            .map(|leaf| self.leaf_rows[leaf])
+            .product();
+        let selectivity: f64 = self
+            .predicates
+            .iter()
+            .filter(|predicate| predicate.mask & !set == 0)
+            .map(|predicate| predicate.selectivity)
+            .product();
+        leaves * selectivity
+    }
+
+    /// Predicates that join `left` to `right`
+    fn connecting(&self, left: u64, right: u64) -> impl Iterator<Item = &JoinPredicate> {
+        self.predicates.iter().filter(move |predicate| {
+            predicate.mask & !(left | right) == 0
+                && predicate.mask & left != 0
+                && predicate.mask & right != 0
+        })
+    }
+
+    fn is_connected(&self, left: u64, right: u64) -> bool {
+        self.connecting(left, right).next().is_some()
+    }
+
+    /// Cost of joining `left` to `right`, including the rows it produces
+    fn join_cost(&self, left: u64, right: u64) -> f64 {
+        let equi = self.connecting(left, right).any(|predicate| predicate.equi);
+        self.model
+            .join_cost(self.rows(left), self.rows(right), equi)
+            + self.rows(left | right)
+    }
+
+    fn cost(&self, tree: &JoinTree) -> f64 {
+        match tree {
+            JoinTree::Leaf(_) => 0.0,
+            JoinTree::Join(left, right) => {
+                self.cost(left) + self.cost(right) + self.join_cost(left.mask(), right.mask())
+            }
+        }
+    }
+
+    /// Cheapest bushy join order, built up over every subset of the leaves. Joins
+    /// without a predicate are only considered when a subset can't be split otherwise.
+    fn dynamic_programming(&self) -> (f64, JoinTree) {
+        let all = (1u64 << self.leaf_rows.len()) - 1;
+        let mut best: Vec<Option<(f64, JoinTree)>> = vec![None; all as usize + 1];
+        for leaf in 0..self.leaf_rows.len() {
+            best[1 << leaf] = Some((0.0, JoinTree::Leaf(leaf)));
+        }
+
+        for set in 1..=all {
+            if set.count_ones() < 2 {
+                continue;
+            }
+            // Keep the lowest leaf on the left so equal-cost orders stay stable
+            let lowest = set & set.wrapping_neg();
+            let mut splits = Vec::new();
+            let mut left = (set - 1) & set;
+            while left > 0 {
+                if left & lowest != 0 {
+                    splits.push((left, set ^ left));
+                }
+                left = (left - 1) & set;
+            }
+            let connected = splits
+                .iter()
+                .any(|(left, right)| self.is_connected(*left, *right));
+
+            let mut cheapest: Option<(f64, JoinTree)> = None;
+            for (left, right) in splits {
+                if connected && !self.is_connected(left, right) {
+                    continue;
+                }
+                let (Some((left_cost, left_tree)), Some((right_cost, right_tree))) =
+                    (&best[left as usize], &best[right as usize])
+                else {
+                    continue;
+                };
+                let cost = left_cost + right_cost + self.join_cost(left, right);
+                if cheapest
+                    .as_ref()
+                    .is_none_or(|(cheapest, _)| cost < *cheapest)
+                {
+                    cheapest = Some((
+                        cost,
+                        JoinTree::Join(Box::new(left_tree.clone()), Box::new(right_tree.clone())),
+                    ));
+                }
+            }
+            best[set as usize] = cheapest;
+        }
+        best[all as usize]
+            .take()
+            .expect("every set of two or more leaves has a split")
+    }
+
+    /// Repeatedly join the two connected subtrees with the smallest result, for
+    /// regions too large to enumerate
+    fn greedy(&self) -> (f64, JoinTree) {
+        let mut trees: Vec<(f64, JoinTree)> = (0..self.leaf_rows.len())
+            .map(|leaf| (0.0, JoinTree::Leaf(leaf)))
+            .collect();
+        while trees.len() > 1 {
+            let mut pick = (0, 1);
+            let (mut pick_connected, mut pick_rows) = (false, f64::INFINITY);
+            for i in 0..trees.len() {
+                for j in i + 1..trees.len() {
+                    let (left, right) = (trees[i].1.mask(), trees[j].1.mask());
+                    let (connected, rows) =
+                        (self.is_connected(left, right), self.rows(left | right));
+                    if (connected && !pick_connected)
+                        || (connected == pick_connected && rows < pick_rows)
+                    {
+                        pick = (i, j);
+                        (pick_connected, pick_rows) = (connected, rows);
+                    }
+                }
+            }
+            let (right_cost, right) = trees.remove(pick.1);
+            let (left_cost, left) = trees.remove(pick.0);
+            let cost = left_cost + right_cost + self.join_cost(left.mask(), right.mask());
+            trees.insert(
+                pick.0,
+                (cost, JoinTree::Join(Box::new(left), Box::new(right))),
+            );
+        }
+        trees.pop().expect("a region has at least two leaves")
+    }
+}
@@ -0,0 +1,590 @@
+//! Subquery decorrelation. `EXISTS` and `IN` subqueries in a filter become semi and
+//! anti joins, and correlated scalar aggregates become a left join against the
+//! aggregate grouped by its correlation keys. Subqueries of any other shape stay in the
+//! filter and are evaluated once per outer row, as a nested loop.
+
+use crate::{
+    common::LogicalPlanError,
+    expression::{BinaryOperator, Expression, UnaryOperator},
+    logical_plan::{
+        AggregateNode, FilterNode, JoinNode, LogicalPlan, ProjectionNode, SubqueryNode,
+    },
+    types::{AggregateFunction, ColumnDef, ColumnRef, JoinType, LogicalSchema, PlanStatistics},
+    visitor::{Transformed, TreeNode},
+};
+use shared_types::{DataType, Value};
+
+/// Alias prefix of the derived tables that scalar subqueries are rewritten into
+const SCALAR_SUBQUERY_ALIAS: &str = "__scalar_subquery";
+
+#[derive(Default)]
+pub(super) struct Decorrelator {
+    next_alias: usize,
+}
+
+impl Decorrelator {
+    pub(super) fn decorrelate(
+        &mut self,
+        mut plan: LogicalPlan,
+    ) -> Result<LogicalPlan, LogicalPlanError> {
+        for child in plan.children_mut() {
+            *child = self.decorrelate(child.clone())?;
+        }
+        match plan {
+            LogicalPlan::Filter(filter) => self.decorrelate_filter(filter),
+            other => Ok(other),
+        }
+    }
+
+    fn decorrelate_filter(&mut self, filter: FilterNode) -> Result<LogicalPlan, LogicalPlanError> {
+        let FilterNode {
+            predicate,
+            input,
+            statistics,
+        } = filter;
+        let mut input = *input;
+        let output_schema = input.schema().clone();
+        let output_columns = Scope::of(&input).to_expressions();
+
+        let mut remaining = Vec::new();
+        for conjunct in predicate.split_conjunction() {
+            // Subqueries nested inside this one are rewritten first
+            let conjunct =
+                map_subqueries(conjunct, &mut |expr| self.decorrelate_subquery_plans(expr))?;
+
+            if let Some((key, subquery, join_type)) = as_semi_join(&conjunct)
+                && let Some(join) = semi_join(&input, key, subquery, join_type)
+            {
+                input = join;
+                continue;
+            }
+
+            let conjunct = map_subqueries(conjunct, &mut |expr| match expr {
+                Expression::Subquery { subquery } => match self.scalar_join(&input, &subquery) {
+                    Some((join, value)) => {
+                        input = join;
+                        Ok(value)
+                    }
+                    None => Ok(Expression::Subquery { subquery }),
+                },
+                other => Ok(other),
+            })?;
+            remaining.push(conjunct);
+        }
+
+        let mut plan = match Expression::conjunction(remaining) {
+            Some(predicate) => LogicalPlan::Filter(FilterNode {
+                predicate,
+                input: Box::new(input),
+                statistics,
+            }),
+            None => input,
+        };
+
+        // Scalar subquery joins append columns, which the filter must not pass on
+        if plan.schema().column_count() != output_schema.column_count() {
+            plan = LogicalPlan::Projection(ProjectionNode {
+                expressions: output_columns,
+                input: Box::new(plan),
+                schema: output_schema,
+                statistics: PlanStatistics::unknown(),
+            });
+        }
+        Ok(plan)
+    }
+
+    fn decorrelate_subquery_plans(
+        &mut self,
+        expr: Expression,
+    ) -> Result<Expression, LogicalPlanError> {
+        Ok(match expr {
+            Expression::Subquery { subquery } => {
+                Expression::scalar_subquery(self.decorrelate(*subquery)?)
+            }
+            Expression::Exists { subquery, negated } => {
+                Expression::exists(self.decorrelate(*subquery)?, negated)
+            }
+            Expression::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => Expression::in_subquery(*expr, self.decorrelate(*subquery)?, negated),
+            other => other,
+        })
+    }
+
+    /// Rewrite a correlated scalar aggregate, `(SELECT agg FROM ... WHERE inner = outer)`,
+    /// into a left join with the aggregate grouped by the inner correlation keys. Returns
+    /// the join and the expression that replaces the subquery.
+    fn scalar_join(
+        &mut self,
+        outer: &LogicalPlan,
+        subquery: &LogicalPlan,
+    ) -> Option<(LogicalPlan, Expression)> {
+        let LogicalPlan::Projection(projection) = subquery else {
+            return None;
+        };
+        let [value] = projection.expressions.as_slice() else {
+            return None;
+        };
+        let value = strip_alias(value.clone());
+        let LogicalPlan::Aggregate(aggregate) = projection.input.as_ref() else {
+            return None;
+        };
+        if !aggregate.group_expr.is_empty() {
+            return None;
+        }
+
+        let outer_scope = Scope::of(outer);
+        let shape = CorrelatedShape::split((*aggregate.input).clone(), &outer_scope)?;
+        // Uncorrelated subqueries are evaluated once and need no join
+        if shape.correlated.is_empty() {
+            return None;
+        }
+        let inner_scope = Scope::of(&shape.core);
+        let is_inner = |expr: &Expression| {
+            expr.column_refs()
+                .iter()
+                .all(|column| inner_scope.resolves(column))
+        };
+        let is_outer = |expr: &Expression| {
+            let columns = expr.column_refs();
+            !columns.is_empty() && columns.iter().all(|column| outer_scope.resolves(column))
+        };
+        if !is_inner(&value) || !aggregate.aggr_expr.iter().all(is_inner) {
+            return None;
+        }
+
+        // Only equality correlations can become grouping keys
+        let mut inner_keys = Vec::new();
+        let mut outer_keys = Vec::new();
+        for condition in &shape.correlated {
+            let Expression::BinaryOp {
+                left,
+                op: BinaryOperator::Eq,
+                right,
+            } = condition
+            else {
+                return None;
+            };
+            if is_inner(left) && is_outer(right) {
+                inner_keys.push(left.as_ref().clone());
+                outer_keys.push(right.as_ref().clone());
+            } else if is_outer(left) && is_inner(right) {
+                inner_keys.push(right.as_ref().clone());
+                outer_keys.push(left.as_ref().clone());
+            } else {
+                return None;
+            }
+        }
+
+        let alias = format!("{}_{}", SCALAR_SUBQUERY_ALIAS, self.next_alias);
+        self.next_alias += 1;
+
+        let mut aggregate_columns: Vec<ColumnDef> = (0..inner_keys.len())
+            .map(|i| ColumnDef::new(format!("group_{}", i), DataType::String))
+            .collect();
+        aggregate_columns.extend(
+            (0..aggregate.aggr_expr.len())
+                .map(|i| ColumnDef::new(format!("aggr_{}", i), DataType::String)),
+        );
+        let grouped = LogicalPlan::Aggregate(AggregateNode {
+            group_expr: inner_keys.clone(),
+            aggr_expr: aggregate.aggr_expr.clone(),
+            input: Box::new(shape.into_input()),
+            schema: LogicalSchema::new(aggregate_columns),
+            statistics: PlanStatistics::unknown(),
+        });
+
+        let value_type = projection
+            .schema
+            .columns
+            .first()
+            .map(|column| column.data_type.clone())
+            .unwrap_or(DataType::String);
+        let mut expressions = vec![Expression::alias(value.clone(), "value")];
+        let mut columns = vec![ColumnDef::with_table("value", value_type, alias.clone())];
+        for (i, key) in inner_keys.into_iter().enumerate() {
+            let name = format!("key_{}", i);
+            expressions.push(Expression::alias(key, name.clone()));
+            columns.push(ColumnDef::with_table(name, DataType::String, alias.clone()));
+        }
+        let derived = LogicalPlan::Subquery(SubqueryNode {
+            subquery: Box::new(LogicalPlan::Projection(ProjectionNode {
+                expressions,
+                input: Box::new(grouped),
+                schema: LogicalSchema::new(columns.clone()),
+                statistics: PlanStatistics::unknown(),
+            })),
+            alias: Some(alias.clone()),
+            statistics: PlanStatistics::unknown(),
+        });
+
+        let join_constraint = Expression::conjunction(
+            outer_keys
+                .into_iter()
+                .enumerate()
+                .map(|(i, key)| {
+                    Expression::binary_op(
+                        key,
+                        BinaryOperator::Eq,
+                        Expression::qualified_column(&alias, format!("key_{}", i)),
+                    )
+                })
+                .collect(),
+        );
+        let mut join_columns = outer.schema().columns.clone();
+        join_columns.extend(columns);
+        let join = LogicalPlan::Join(JoinNode {
+            left: Box::new(outer.clone()),
+            right: Box::new(derived),
+            join_type: JoinType::Left,
+            join_constraint,
+            strategy: None,
+            schema: LogicalSchema::new(join_columns),
+            statistics: PlanStatistics::unknown(),
+        });
+
+        // Outer rows without a group get NULL from the join, where COUNT is 0
+        let mut replacement = Expression::qualified_column(&alias, "value");
+        if matches!(
+            value,
+            Expression::Aggregate {
+                func: AggregateFunction::Count | AggregateFunction::CountDistinct,
+                ..
+            }
+        ) {
+            replacement = Expression::function(
+                "coalesce",
+                vec![replacement, Expression::literal(Value::Integer(0))],
+            );
+        }
+        Some((join, replacement))
+    }
+}
+
+/// The parts of an `EXISTS`/`IN` conjunct: the outer key for `IN`, the subquery and
+/// the join that filters on it
+fn as_semi_join(expr: &Expression) -> Option<(Option<&Expression>, &LogicalPlan, JoinType)> {
+    match expr {
+        Expression::Exists { subquery, negated } => {
+            let join_type = if *negated {
+                JoinType::LeftAnti
+            } else {
+                JoinType::LeftSemi
+            };
+            Some((None, subquery, join_type))
+        }
+        Expression::InSubquery {
+            expr,
+            subquery,
+            negated,
+        } => {
+            let join_type = if *negated {
+                JoinType::NullAwareLeftAnti
+            } else {
+                JoinType::LeftSemi
+            };
+            Some((Some(expr), subquery, join_type))
+        }
+        Expression::UnaryOp {
+            op: UnaryOperator::Not,
+            expr,
+        } => {
+            let (key, subquery, join_type) = as_semi_join(expr)?;
+            let join_type = match join_type {
+                JoinType::LeftSemi if key.is_some() => JoinType::NullAwareLeftAnti,
+                JoinType::LeftSemi => JoinType::LeftAnti,
+                _ => JoinType::LeftSemi,
+            };
+            Some((key, subquery, join_type))
+        }
+        _ => None,
+    }
+}
+
+fn semi_join(
+    outer: &LogicalPlan,
+    key: Option<&Expression>,
+    subquery: &LogicalPlan,
+    join_type: JoinType,
+) -> Option<LogicalPlan> {
+    let outer_scope = Scope::of(outer);
+    if let Some(key) = key
+        && (contains_subquery(key)
+            || !key
+                .column_refs()
+                .iter()
+                .all(|column| outer_scope.resolves(column)))
+    {
+        return None;
+    }
+
+    let (output, input) = peel_projection(subquery.clone())?;
+    let shape = CorrelatedShape::split(input, &outer_scope)?;
+    // NOT IN compares the key against every inner row, so its NULL handling does not
+    // survive extra correlated conditions
+    if join_type == JoinType::NullAwareLeftAnti && !shape.correlated.is_empty() {
+        return None;
+    }
+
+    let mut conditions = Vec::new();
+    if let Some(key) = key {
+        let [inner_key] = output.as_slice() else {
+            return None;
+        };
+        let inner_key = strip_alias(inner_key.clone());
+        let inner_scope = Scope::of(&shape.core);
+        if matches!(inner_key, Expression::Wildcard { .. })
+            || !inner_key
+                .column_refs()
+                .iter()
+                .all(|column| inner_scope.resolves(column))
+        {
+            return None;
+        }
+        conditions.push(Expression::binary_op(
+            key.clone(),
+            BinaryOperator::Eq,
+            inner_key,
+        ));
+    }
+    conditions.extend(shape.correlated.iter().cloned());
+
+    Some(LogicalPlan::Join(JoinNode {
+        left: Box::new(outer.clone()),
+        right: Box::new(shape.into_input()),
+        join_type,
+        join_constraint: Expression::conjunction(conditions),
+        strategy: None,
+        schema: outer.schema().clone(),
+        statistics: PlanStatistics::unknown(),
+    }))
+}
+
+/// The subquery's select list and its input. Ordering and duplicates do not change
+/// which rows exist, so `ORDER BY` and `DISTINCT` are dropped; `LIMIT` does.
+fn peel_projection(plan: LogicalPlan) -> Option<(Vec<Expression>, LogicalPlan)> {
+    match plan {
+        LogicalPlan::Distinct(node) => peel_projection(*node.input),
+        LogicalPlan::Sort(node) => peel_projection(*node.input),
+        LogicalPlan::Projection(node) => Some((node.expressions, *node.input)),
+        LogicalPlan::Limit(_) => None,
+        other => Some((Vec::new(), other)),
+    }
+}
+
+fn strip_alias(expr: Expression) -> Expression {
+    match expr {
+        Expression::Alias { expr, .. } => strip_alias(*expr),
+        other => other,
+    }
+}
+
+/// A subquery's input split at its `WHERE` clause
+struct CorrelatedShape {
+    /// The subquery below its filters, free of outer references
+    core: LogicalPlan,
+    /// Conditions on the subquery's own columns
+    local: Vec<Expression>,
+    /// Conditions that reference the outer query
+    correlated: Vec<Expression>,
+}
+
+impl CorrelatedShape {
+    /// `None` when outer columns are referenced anywhere but the top filters, or a
+    /// column resolves in neither scope (it belongs to a query further out)
+    fn split(mut plan: LogicalPlan, outer: &Scope) -> Option<Self> {
+        let mut conditions = Vec::new();
+        while let LogicalPlan::Filter(filter) = plan {
+            conditions.extend(filter.predicate.split_conjunction());
+            plan = *filter.input;
+        }
+        if !is_self_contained(&plan) {
+            return None;
+        }
+
+        let inner = Scope::of(&plan);
+        let mut local = Vec::new();
+        let mut correlated = Vec::new();
+        for condition in conditions {
+            if contains_subquery(&condition) {
+                return None;
+            }
+            let columns = condition.column_refs();
+            if columns.iter().all(|column| inner.resolves(column)) {
+                local.push(condition);
+            } else if columns
+                .iter()
+                .all(|column| inner.resolves(column) || outer.resolves(column))
+            {
+                correlated.push(condition);
+            } else {
+                return None;
+            }
+        }
+        Some(Self {
+            core: plan,
+            local,
+            correlated,
+        })
+    }
+
+    fn into_input(self) -> LogicalPlan {
+        match Expression::conjunction(self.local) {
+            Some(predicate) => LogicalPlan::Filter(FilterNode {
+                predicate,
+                input: Box::new(self.core),
+                statistics: PlanStatistics::unknown(),
+            }),
+            None => self.core,
+        }
+    }
+}
+
+/// Whether every column `plan` references is produced inside it, and none of its
+/// expressions hold subqueries of their own
+fn is_self_contained(plan: &LogicalPlan) -> bool {
+    let mut scope = Scope::default();
+    collect_deep_scope(plan, &mut scope);
+    let mut expressions = Vec::new();
+    collect_plan_expressions(plan, &mut expressions);
+    expressions.iter().all(|expr| {
+        !contains_subquery(expr)
+            && expr
+                .column_refs()
+                .iter()
+                .all(|column| scope.resolves(column))
+    })
+}
+
+fn collect_deep_scope(plan: &LogicalPlan, scope: &mut Scope) {
+    scope.columns.extend(Scope::of(plan).columns);
+    scope.columns.extend(
+        plan.schema()
+            .columns
+            .iter()
+            .map(|column| (column.table.clone(), column.name.clone())),
+    );
+    for child in plan.children() {
+        collect_deep_scope(child, scope);
+    }
+}
+
+fn collect_plan_expressions<'a>(plan: &'a LogicalPlan, expressions: &mut Vec<&'a Expression>) {
+    match plan {
+        LogicalPlan::TableScan(node) => expressions.extend(&node.filters),
+        LogicalPlan::Projection(node) => expressions.extend(&node.expressions),
+        LogicalPlan::Filter(node) => expressions.push(&node.predicate),
+        LogicalPlan::Join(node) => expressions.extend(&node.join_constraint),
+        LogicalPlan::Aggregate(node) => {
+            expressions.extend(node.group_expr.iter().chain(&node.aggr_expr))
+        }
+        LogicalPlan::Window(node) => expressions.extend(&node.window_expr),
+        LogicalPlan::Sort(node) => expressions.extend(
+            node.expressions
+                .iter()
+                .map(|sort_expr| sort_expr.expr.as_ref()),
+        ),
+        LogicalPlan::Values(node) => expressions.extend(node.values.iter().flatten()),
+        _ => {}
+    }
+    for child in plan.children() {
+        collect_plan_expressions(child, expressions);
+    }
+}
+
+/// Columns visible above a plan node, as `(qualifier, name)` pairs. A `*` column
+/// stands for a table whose columns are not known.
+#[derive(Default)]
+struct Scope {
+    columns: Vec<(Option<String>, String)>,
+}
+
+impl Scope {
+    fn of(plan: &LogicalPlan) -> Self {
+        match plan {
+            LogicalPlan::TableScan(node) => {
+                let table = node.table.effective_name();
+                Self::qualified(Some(table), &node.schema)
+            }
+            LogicalPlan::Subquery(node) => {
+                Self::qualified(node.alias.as_deref(), node.subquery.schema())
+            }
+            LogicalPlan::Join(node) => {
+                let mut scope = Self::of(&node.left);
+                if !node.join_type.returns_left_only() {
+                    scope.columns.extend(Self::of(&node.right).columns);
+                }
+                scope
+            }
+            LogicalPlan::Filter(node) => Self::of(&node.input),
+            LogicalPlan::Sort(node) => Self::of(&node.input),
+            LogicalPlan::Limit(node) => Self::of(&node.input),
+            LogicalPlan::Distinct(node) => Self::of(&node.input),
+            other => Self {
+                columns: other
+                    .schema()
+                    .columns
+                    .iter()
+                    .map(|column| (column.table.clone(), column.name.clone()))
+                    .collect(),
+            },
+        }
+    }
+
+    fn qualified(table: Option<&str>, schema: &LogicalSchema) -> Self {
+        Self {
+            columns: schema
+                .columns
+                .iter()
+                .map(|column| (table.map(str::to_string), column.name.clone()))
+                .collect(),
+        }
+    }
+
+    fn resolves(&self, column: &ColumnRef) -> bool {
+        self.columns.iter().any(|(table, name)| {
+            (name == &column.name || name == "*")
+                && (column.table.is_none() || table.as_ref() == column.table.as_ref())
+        })
+    }
+
+    /// References to every column, for a projection that restores them
+    fn to_expressions(&self) -> Vec<Expression> {
+        self.columns
+            .iter()
+            .map(|(table, name)| match (table, name.as_str()) {
+                (table, "*") => Expression::Wildcard {
+                    table: table.clone(),
+                },
+                (Some(table), name) => Expression::qualified_column(table, name),
+                (None, name) => Expression::column(name),
+            })
+            .collect()
+    }
+}
+
+pub(super) fn contains_subquery(expr: &Expression) -> bool {
+    expr.exists(Expression::is_subquery)
+}
+
+pub(super) fn contains_aggregate(expr: &Expression) -> bool {
+    expr.exists(|expr| matches!(expr, Expression::Aggregate { .. }))
+}
+
+/// Rebuild `expr` with every subquery expression in it replaced by `f`, innermost
+/// first. Plans inside the subqueries are left to `f`.
+fn map_subqueries(
+    expr: Expression,
+    f: &mut impl FnMut(Expression) -> Result<Expression, LogicalPlanError>,
+) -> Result<Expression, LogicalPlanError> {
+    expr.transform_up(|expr| {
+        if expr.is_subquery() {
+            f(expr).map(Transformed::yes)
+        } else {
+            Ok(Transformed::no(expr))
+        }
+    })
+    .map(|expr| expr.data)
+}
@@ -0,0 +1,205 @@
+//! Rules that remove sorts and projections whose work is lost or has no effect.
+
+use super::{
+    decorrelate::contains_aggregate, position, replace_columns, rule::OptimizerRule, unaliased,
+};
+use crate::{
+    common::LogicalPlanError,
+    expression::Expression,
+    logical_plan::{LogicalPlan, ProjectionNode},
+    visitor::Transformed,
+};
+
+/// Removes sorts whose order is lost: below another sort, an aggregation, a set
+/// operation, or at the top of a derived table or subquery without a limit
+pub struct EliminateSorts;
+
+impl OptimizerRule for EliminateSorts {
+    fn name(&self) -> &'static str {
+        "eliminate_sorts"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+        eliminate_sorts(plan)
+    }
+}
+
+fn eliminate_sorts(plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+    plan.transform_down_with_subqueries(|plan| {
+        let plan = without_empty_sorts(plan);
+        let mut transformed = plan.transformed;
+        let mut unorder = |input: Box<LogicalPlan>| {
+            let input = unordered(*input);
+            transformed |= input.transformed;
+            Box::new(input.data)
+        };
+        let plan = match plan.data {
+            LogicalPlan::Sort(mut sort) => {
+                sort.input = unorder(sort.input);
+                LogicalPlan::Sort(sort)
+            }
+            LogicalPlan::Aggregate(mut aggregate) => {
+                aggregate.input = unorder(aggregate.input);
+                LogicalPlan::Aggregate(aggregate)
+            }
+            LogicalPlan::Subquery(mut subquery) => {
+                subquery.subquery = unorder(subquery.subquery);
+                LogicalPlan::Subquery(subquery)
+            }
+            LogicalPlan::Union(mut union) => {
+                union.left = unorder(union.left);
+                union.right = unorder(union.right);
+                LogicalPlan::Union(union)
+            }
+            LogicalPlan::Intersect(mut intersect) => {
+                intersect.left = unorder(intersect.left);
+                intersect.right = unorder(intersect.right);
+                LogicalPlan::Intersect(intersect)
+            }
+            LogicalPlan::Except(mut except) => {
+                except.left = unorder(except.left);
+                except.right = unorder(except.right);
+                LogicalPlan::Except(except)
+            }
+            other => other,
+        };
+        // Subqueries in expressions yield a value, a truth value or a set, never an order
+        let plan = plan.map_subqueries(|subquery| Ok(unordered(subquery)))?;
+        Ok(Transformed::new(plan.data, transformed || plan.transformed))
+    })
+}
+
+fn without_empty_sorts(mut plan: LogicalPlan) -> Transformed<LogicalPlan> {
+    let mut transformed = false;
+    while let LogicalPlan::Sort(sort) = &plan
+        && sort.expressions.is_empty()
+    {
+        let LogicalPlan::Sort(sort) = plan else {
+            unreachable!("matched above");
+        };
+        plan = *sort.input;
+        transformed = true;
+    }
+    Transformed::new(plan, transformed)
+}
+
+/// `plan` without the sort at its top, looking through projections and filters,
+/// which keep their input's order. A sort under a limit is kept, it picks the rows.
+fn unordered(plan: LogicalPlan) -> Transformed<LogicalPlan> {
+    match plan {
+        LogicalPlan::Sort(sort) => Transformed::yes(unordered(*sort.input).data),
+        LogicalPlan::Projection(mut projection) => {
+            let input = unordered(*projection.input);
+            projection.input = Box::new(input.data);
+            Transformed::new(LogicalPlan::Projection(projection), input.transformed)
+        }
+        LogicalPlan::Filter(mut filter) => {
+            let input = unordered(*filter.input);
+            filter.input = Box::new(input.data);
+            Transformed::new(LogicalPlan::Filter(filter), input.transformed)
+        }
+        other => Transformed::no(other),
+    }
+}
+
+/// Removes projections that return their input as is, and merges a projection into
+/// the projection directly below it
+pub struct EliminateProjections;
+
+impl OptimizerRule for EliminateProjections {
+    fn name(&self) -> &'static str {
+        "eliminate_projections"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+        eliminate_projections(plan)
+    }
+}
+
+fn eliminate_projections(plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+    plan.transform_down_with_subqueries(|mut plan| {
+        let mut transformed = false;
+        while let LogicalPlan::Projection(projection) = &mut plan {
+            if is_identity(projection) {
+                let LogicalPlan::Projection(projection) = plan else {
+                    unreachable!("matched above");
+                };
+                plan = *projection.input;
+            } else if !merge_projections(projection) {
+                break;
+            }
+            transformed = true;
+        }
+        Ok(Transformed::new(plan, transformed))
+    })
+}
+
+/// Whether `projection` returns exactly the columns of its input, in order
+fn is_identity(projection: &ProjectionNode) -> bool {
+    let input = projection.input.schema();
+    if projection.schema != *input {
+        return false;
+    }
+    if projection.expressions == [Expression::wildcard()] {
+        return true;
+    }
+    projection.expressions.len() == input.columns.len()
+        && projection
+            .expressions
+            .iter()
+            .enumerate()
+            .all(|(index, expr)| match expr {
+                Expression::Column(column) => position(input, column) == Some(index),
+                _ => false,
+            })
+}
+
+/// Fold the projection below `projection` into it, by substituting that projection's
+/// expressions for the columns that read them. Does nothing and returns false when
+/// this would compute anything more than once, or move aggregates or window functions.
+fn merge_projections(projection: &mut ProjectionNode) -> bool {
+    let LogicalPlan::Projection(inner) = projection.input.as_ref() else {
+        return false;
+    };
+    let has_wildcard = |exprs: &[Expression]| {
+        exprs
+            .iter()
+            .any(|expr| matches!(expr, Expression::Wildcard { .. }))
+    };
+    if has_wildcard(&projection.expressions) || has_wildcard(&inner.expressions) {
+        return false;
+    }
+
+    let mut reads = vec![0; inner.expressions.len()];
+    for expr in &projection.expressions {
+        for column in expr.column_refs() {
+            match position(&inner.schema, column) {
+                Some(index) => reads[index] += 1,
+                None => return false,
+            }
+        }
+    }
+    let mergeable = inner.expressions.iter().zip(&reads).all(|(expr, reads)| {
+        let expr = unaliased(expr);
+        let simple = matches!(expr, Expression::Column(_) | Expression::Literal(_));
+        expr.is_deterministic()
+            && !contains_aggregate(expr)
+            && expr.window_exprs().is_empty()
+            && (simple || *reads <= 1)
+    });
+    if !mergeable {
+        return false;
+    }
+
+    let LogicalPlan::Projection(inner) = projection.input.as_ref().clone() else {
+        unreachable!("checked above");
+    };
+    for expr in &mut projection.expressions {
+        replace_columns(expr, &|column| match position(&inner.schema, column) {
+            Some(index) => unaliased(&inner.expressions[index]).clone(),
+            None => Expression::Column(column.clone()),
+        });
+    }
+    projection.input = inner.input;
+    true
+}
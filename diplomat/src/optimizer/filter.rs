@@ -0,0 +1,724 @@
+//! Rules that move filters down the plan, so rows are dropped as close to the scans
+//! as possible.
+
+use shared_types::Value;
+
+use super::{
+    bind, decorrelate::contains_aggregate, position, replace_columns, resolves,
+    rule::OptimizerRule, scope, unaliased,
+};
+use crate::{
+    common::LogicalPlanError,
+    expression::{BinaryOperator, Expression},
+    logical_plan::{
+        AggregateNode, FilterNode, JoinNode, LogicalPlan, ProjectionNode, SubqueryNode, WindowNode,
+    },
+    types::{ColumnDef, ColumnRef, JoinType, LogicalSchema, PlanStatistics},
+    visitor::Transformed,
+};
+
+/// Combines a filter directly above another into one, and drops conditions that are
+/// always true
+pub struct MergeFilters;
+
+impl OptimizerRule for MergeFilters {
+    fn name(&self) -> &'static str {
+        "merge_filters"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+        merge_filters(plan)
+    }
+}
+
+fn merge_filters(plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+    plan.transform_down_with_subqueries(|plan| Ok(merge(plan)))
+}
+
+fn merge(plan: LogicalPlan) -> Transformed<LogicalPlan> {
+    let LogicalPlan::Filter(FilterNode {
+        predicate,
+        input,
+        statistics,
+    }) = plan
+    else {
+        return Transformed::no(plan);
+    };
+    match *input {
+        LogicalPlan::Filter(inner) => {
+            // The inner filter was written first, so its conditions stay first
+            let mut conjuncts = inner.predicate.split_conjunction();
+            for conjunct in predicate.split_conjunction() {
+                if !conjuncts.contains(&conjunct) {
+                    conjuncts.push(conjunct);
+                }
+            }
+            Transformed::yes(merge(with_conjuncts(conjuncts, *inner.input, statistics)).data)
+        }
+        input if predicate.clone().split_conjunction().iter().any(is_true) => {
+            let conjuncts = predicate
+                .split_conjunction()
+                .into_iter()
+                .filter(|conjunct| !is_true(conjunct))
+                .collect();
+            Transformed::yes(merge(with_conjuncts(conjuncts, input, statistics)).data)
+        }
+        input => Transformed::no(LogicalPlan::Filter(FilterNode {
+            predicate,
+            input: Box::new(input),
+            statistics,
+        })),
+    }
+}
+
+/// Moves filter conditions below projections, sorts, aggregates (conditions on the
+/// grouping keys), windows (conditions on the partition keys), set operations and
+/// joins, into the table scans they restrict.
+/// Conditions on the join keys are restated for the other side of the join, so
+/// `a.x = b.x AND a.x = 5` also filters `b` on `b.x = 5`.
+pub struct PushDownFilter;
+
+impl OptimizerRule for PushDownFilter {
+    fn name(&self) -> &'static str {
+        "push_down_filter"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+        push_down(plan)
+    }
+}
+
+fn push_down(plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+    plan.transform_down_with_subqueries(|plan| {
+        Ok(match plan {
+            LogicalPlan::Filter(filter) => push_filter(filter),
+            LogicalPlan::Join(join) => push_into_join(join, Vec::new(), &mut Vec::new()),
+            other => Transformed::no(other),
+        })
+    })
+}
+
+fn push_filter(filter: FilterNode) -> Transformed<LogicalPlan> {
+    let FilterNode {
+        predicate,
+        input,
+        statistics,
+    } = filter;
+    let conjuncts = predicate.clone().split_conjunction();
+    let count = conjuncts.len();
+    let mut kept = Vec::new();
+    // Whether the input changed even if no condition moved into it
+    let mut input_changed = false;
+
+    let input = match *input {
+        LogicalPlan::TableScan(scan) => {
+            let mut plan = LogicalPlan::TableScan(scan);
+            let scope = scope(&plan);
+            if let LogicalPlan::TableScan(scan) = &mut plan {
+                for mut conjunct in conjuncts {
+                    if conjunct.is_deterministic() && covers(&scope, &conjunct) {
+                        bind(&mut conjunct, &scope);
+                        if !scan.filters.contains(&conjunct) {
+                            scan.filters.push(conjunct);
+                        }
+                    } else {
+                        kept.push(conjunct);
+                    }
+                }
+            }
+            plan
+        }
+        LogicalPlan::Join(join) => {
+            let join = push_into_join(join, conjuncts, &mut kept);
+            input_changed = join.transformed;
+            join.data
+        }
+        LogicalPlan::Projection(mut projection) => {
+            let mut below = Vec::new();
+            for conjunct in conjuncts {
+                match through_projection(&conjunct, &projection) {
+                    Some(rewritten) => below.push(rewritten),
+                    None => kept.push(conjunct),
+                }
+            }
+            projection.input = Box::new(with_filter(below, *projection.input));
+            LogicalPlan::Projection(projection)
+        }
+        LogicalPlan::Subquery(subquery) => into_subquery(subquery, conjuncts, &mut kept),
+        LogicalPlan::Aggregate(aggregate) => into_aggregate(aggregate, conjuncts, &mut kept),
+        LogicalPlan::Window(window) => into_window(window, conjuncts, &mut kept),
+        LogicalPlan::Sort(mut sort) => {
+            let (below, above) = conjuncts
+                .into_iter()
+                .partition(Expression::is_deterministic);
+            kept = above;
+            sort.input = Box::new(with_filter(below, *sort.input));
+            LogicalPlan::Sort(sort)
+        }
+        LogicalPlan::Distinct(mut distinct) => {
+            let (below, above) = conjuncts
+                .into_iter()
+                .partition(Expression::is_deterministic);
+            kept = above;
+            distinct.input = Box::new(with_filter(below, *distinct.input));
+            LogicalPlan::Distinct(distinct)
+        }
+        LogicalPlan::With(mut with) => {
+            with.input = Box::new(with_conjuncts(conjuncts, *with.input, statistics.clone()));
+            LogicalPlan::With(with)
+        }
+        LogicalPlan::Union(mut union) => {
+            (union.left, union.right) =
+                into_set_operation(&union.schema, union.left, union.right, conjuncts, &mut kept);
+            LogicalPlan::Union(union)
+        }
+        LogicalPlan::Intersect(mut intersect) => {
+            (intersect.left, intersect.right) = into_set_operation(
+                &intersect.schema,
+                intersect.left,
+                intersect.right,
+                conjuncts,
+                &mut kept,
+            );
+            LogicalPlan::Intersect(intersect)
+        }
+        LogicalPlan::Except(mut except) => {
+            (except.left, except.right) = into_set_operation(
+                &except.schema,
+                except.left,
+                except.right,
+                conjuncts,
+                &mut kept,
+            );
+            LogicalPlan::Except(except)
+        }
+        other => {
+            kept = conjuncts;
+            other
+        }
+    };
+
+    if kept.len() == count {
+        // Nothing moved; keep the condition as it was written
+        let filter = LogicalPlan::Filter(FilterNode {
+            predicate,
+            input: Box::new(input),
+            statistics,
+        });
+        return Transformed::new(filter, input_changed);
+    }
+    Transformed::yes(with_conjuncts(kept, input, statistics))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Side {
+    Left,
+    Right,
+    Both,
+}
+
+/// Which inputs of a join `predicate` reads from; `None` when it can't be moved
+/// because it is volatile, reads no column or reads columns neither input has
+fn side(predicate: &Expression, left: &LogicalSchema, right: &LogicalSchema) -> Option<Side> {
+    if !predicate.is_deterministic() {
+        return None;
+    }
+    let (mut reads_left, mut reads_right) = (false, false);
+    for column in predicate.column_refs() {
+        match (resolves(left, column), resolves(right, column)) {
+            (true, false) => reads_left = true,
+            (false, true) => reads_right = true,
+            _ => return None,
+        }
+    }
+    match (reads_left, reads_right) {
+        (true, false) => Some(Side::Left),
+        (false, true) => Some(Side::Right),
+        (true, true) => Some(Side::Both),
+        (false, false) => None,
+    }
+}
+
+/// Push `conjuncts` of a filter above `join`, and the conditions of its ON clause
+/// that only restrict one input, into its inputs. The join is transformed unless every
+/// conjunct ends up in `kept` and the ON clause stays as it is.
+fn push_into_join(
+    mut join: JoinNode,
+    conjuncts: Vec<Expression>,
+    kept: &mut Vec<Expression>,
+) -> Transformed<LogicalPlan> {
+    let left_scope = scope(&join.left);
+    let right_scope = scope(&join.right);
+    let (mut to_left, mut to_right, mut to_join) = (Vec::new(), Vec::new(), Vec::new());
+    let mut transformed = false;
+
+    // Above the join, a condition may only filter an input whose unmatched rows the
+    // join drops; the rows of a preserved side must all reach the join
+    let (filter_left, filter_right) = match join.join_type {
+        JoinType::Inner | JoinType::Cross => (true, true),
+        JoinType::Left | JoinType::LeftSemi | JoinType::LeftAnti | JoinType::NullAwareLeftAnti => {
+            (true, false)
+        }
+        JoinType::Right => (false, true),
+        JoinType::Full => (false, false),
+    };
+    let visible_right = if join.join_type.returns_left_only() {
+        LogicalSchema::empty()
+    } else {
+        right_scope.clone()
+    };
+    for conjunct in conjuncts {
+        match side(&conjunct, &left_scope, &visible_right) {
+            Some(Side::Left) if filter_left => to_left.push(conjunct),
+            Some(Side::Right) if filter_right => to_right.push(conjunct),
+            Some(Side::Both) if matches!(join.join_type, JoinType::Inner | JoinType::Cross) => {
+                to_join.push(conjunct)
+            }
+            _ => kept.push(conjunct),
+        }
+    }
+
+    // In the ON clause it is the other way around: a condition on one input only
+    // decides which of its rows can match, so it can filter that input unless the
+    // input's unmatched rows are returned too
+    let (on_left, on_right) = match join.join_type {
+        JoinType::Inner | JoinType::LeftSemi => (true, true),
+        JoinType::Left | JoinType::LeftAnti | JoinType::NullAwareLeftAnti => (false, true),
+        JoinType::Right => (true, false),
+        JoinType::Full | JoinType::Cross => (false, false),
+    };
+    if let Some(constraint) = &join.join_constraint {
+        let conjuncts = constraint.clone().split_conjunction();
+        let count = conjuncts.len();
+        let mut remaining = Vec::new();
+        for conjunct in conjuncts {
+            match side(&conjunct, &left_scope, &right_scope) {
+                Some(Side::Left) if on_left => to_left.push(conjunct),
+                Some(Side::Right) if on_right => to_right.push(conjunct),
+                _ => remaining.push(conjunct),
+            }
+        }
+        if remaining.len() < count {
+            join.join_constraint = Expression::conjunction(remaining);
+            transformed = true;
+        }
+    }
+
+    if !to_join.is_empty() {
+        transformed = true;
+        let mut combined = left_scope.columns.clone();
+        combined.extend(right_scope.columns.iter().cloned());
+        let combined = LogicalSchema::new(combined);
+        for conjunct in &mut to_join {
+            bind(conjunct, &combined);
+        }
+        let mut conjuncts = join
+            .join_constraint
+            .take()
+            .map(Expression::split_conjunction)
+            .unwrap_or_default();
+        conjuncts.extend(to_join);
+        join.join_constraint = Expression::conjunction(conjuncts);
+        if join.join_type == JoinType::Cross {
+            join.join_type = JoinType::Inner;
+        }
+    }
+
+    // Restate conditions on a join key for the other input. Rows of that input that
+    // fail them can't match any row left on this side.
+    let pairs = equi_pairs(join.join_constraint.as_ref(), &left_scope, &right_scope);
+    if !pairs.is_empty() {
+        let derive_right = matches!(
+            join.join_type,
+            JoinType::Inner | JoinType::Left | JoinType::LeftSemi | JoinType::LeftAnti
+        );
+        let derive_left = matches!(
+            join.join_type,
+            JoinType::Inner | JoinType::Right | JoinType::LeftSemi
+        );
+        let from_left: Vec<Expression> = if derive_right {
+            to_left
+                .iter()
+                .filter_map(|conjunct| transfer(conjunct, &pairs, true))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let from_right: Vec<Expression> = if derive_left {
+            to_right
+                .iter()
+                .filter_map(|conjunct| transfer(conjunct, &pairs, false))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        for derived in from_left {
+            if !is_restricted_by(&to_right, &join.right, &derived) {
+                to_right.push(derived);
+            }
+        }
+        for derived in from_right {
+            if !is_restricted_by(&to_left, &join.left, &derived) {
+                to_left.push(derived);
+            }
+        }
+    }
+
+    transformed |= !to_left.is_empty() || !to_right.is_empty();
+    join.left = Box::new(with_filter(to_left, *join.left));
+    join.right = Box::new(with_filter(to_right, *join.right));
+    Transformed::new(LogicalPlan::Join(join), transformed)
+}
+
+/// The `left = right` column pairs of a join condition, left input first
+fn equi_pairs(
+    constraint: Option<&Expression>,
+    left: &LogicalSchema,
+    right: &LogicalSchema,
+) -> Vec<(ColumnRef, ColumnRef)> {
+    let conjuncts = constraint
+        .cloned()
+        .map(Expression::split_conjunction)
+        .unwrap_or_default();
+    conjuncts
+        .iter()
+        .filter_map(|conjunct| match conjunct {
+            Expression::BinaryOp {
+                left: a,
+                op: BinaryOperator::Eq,
+                right: b,
+            } => match (a.as_ref(), b.as_ref()) {
+                (Expression::Column(a), Expression::Column(b)) => {
+                    let a_side = side(&Expression::Column(a.clone()), left, right);
+                    let b_side = side(&Expression::Column(b.clone()), left, right);
+                    match (a_side, b_side) {
+                        (Some(Side::Left), Some(Side::Right)) => Some((a.clone(), b.clone())),
+                        (Some(Side::Right), Some(Side::Left)) => Some((b.clone(), a.clone())),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// `predicate`, a comparison of one join key with constants, restated over the key
+/// it is equal to on the other input
+fn transfer(
+    predicate: &Expression,
+    pairs: &[(ColumnRef, ColumnRef)],
+    from_left: bool,
+) -> Option<Expression> {
+    let columns = predicate.column_refs();
+    let column = *columns.first()?;
+    if columns.iter().any(|other| !same_column(other, column)) {
+        return None;
+    }
+    let constant = |expr: &Expression| matches!(expr, Expression::Literal(_));
+    let key = |expr: &Expression| matches!(expr, Expression::Column(_));
+    let restricts_key = match predicate {
+        Expression::BinaryOp { left, op, right } => {
+            matches!(
+                op,
+                BinaryOperator::Eq
+                    | BinaryOperator::NotEq
+                    | BinaryOperator::Lt
+                    | BinaryOperator::LtEq
+                    | BinaryOperator::Gt
+                    | BinaryOperator::GtEq
+            ) && ((key(left) && constant(right)) || (constant(left) && key(right)))
+        }
+        Expression::In { expr, list, .. } => key(expr) && list.iter().all(constant),
+        Expression::Between {
+            expr, low, high, ..
+        } => key(expr) && constant(low) && constant(high),
+        Expression::Like { expr, pattern, .. } => key(expr) && constant(pattern),
+        _ => false,
+    };
+    if !restricts_key {
+        return None;
+    }
+    let other = pairs.iter().find_map(|(left, right)| {
+        if from_left {
+            same_column(left, column).then_some(right)
+        } else {
+            same_column(right, column).then_some(left)
+        }
+    })?;
+
+    let mut derived = predicate.clone();
+    for column in derived.column_refs_mut() {
+        *column = ColumnRef {
+            index: None,
+            ..other.clone()
+        };
+    }
+    Some(derived)
+}
+
+fn same_column(a: &ColumnRef, b: &ColumnRef) -> bool {
+    a.name == b.name && (a.table == b.table || a.table.is_none() || b.table.is_none())
+}
+
+/// Whether `predicate` is among `pending` or already filters `plan`
+fn is_restricted_by(pending: &[Expression], plan: &LogicalPlan, predicate: &Expression) -> bool {
+    // Compare as text, so a condition bound to other positions still matches
+    let text = predicate.to_string();
+    let same = |other: &Expression| other.to_string() == text;
+    if pending.iter().any(same) {
+        return true;
+    }
+    let mut plan = plan;
+    loop {
+        match plan {
+            LogicalPlan::Filter(filter) => {
+                if filter
+                    .predicate
+                    .clone()
+                    .split_conjunction()
+                    .iter()
+                    .any(same)
+                {
+                    return true;
+                }
+                plan = &filter.input;
+            }
+            LogicalPlan::TableScan(scan) => return scan.filters.iter().any(same),
+            _ => return false,
+        }
+    }
+}
+
+/// `predicate` over the output of `projection`, restated over its input
+fn through_projection(predicate: &Expression, projection: &ProjectionNode) -> Option<Expression> {
+    if !predicate.is_deterministic() {
+        return None;
+    }
+    if projection.expressions == [Expression::wildcard()] {
+        // The output is the input
+        return Some(predicate.clone());
+    }
+    if projection
+        .expressions
+        .iter()
+        .any(|expr| matches!(expr, Expression::Wildcard { .. }))
+    {
+        return None;
+    }
+    for column in predicate.column_refs() {
+        let source = unaliased(&projection.expressions[position(&projection.schema, column)?]);
+        if !source.is_deterministic()
+            || contains_aggregate(source)
+            || !source.window_exprs().is_empty()
+        {
+            return None;
+        }
+    }
+
+    let mut rewritten = predicate.clone();
+    replace_columns(
+        &mut rewritten,
+        &|column| match position(&projection.schema, column) {
+            Some(index) => unaliased(&projection.expressions[index]).clone(),
+            None => Expression::Column(column.clone()),
+        },
+    );
+    Some(rewritten)
+}
+
+/// Push conditions on the columns of a derived table into its query
+fn into_subquery(
+    mut subquery: SubqueryNode,
+    conjuncts: Vec<Expression>,
+    kept: &mut Vec<Expression>,
+) -> LogicalPlan {
+    let inner = subquery.subquery.schema().clone();
+    let outer = match &subquery.alias {
+        Some(alias) => LogicalSchema::new(
+            inner
+                .columns
+                .iter()
+                .map(|column| ColumnDef {
+                    table: Some(alias.clone()),
+                    ..column.clone()
+                })
+                .collect(),
+        ),
+        None => inner.clone(),
+    };
+    let mut below = Vec::new();
+    for conjunct in conjuncts {
+        match restate(&conjunct, &outer, &inner) {
+            Some(rewritten) => below.push(rewritten),
+            None => kept.push(conjunct),
+        }
+    }
+    subquery.subquery = Box::new(with_filter(below, *subquery.subquery));
+    LogicalPlan::Subquery(subquery)
+}
+
+/// Push HAVING conditions that only read grouping keys below the aggregation, where
+/// they drop rows before they are grouped
+fn into_aggregate(
+    mut aggregate: AggregateNode,
+    conjuncts: Vec<Expression>,
+    kept: &mut Vec<Expression>,
+) -> LogicalPlan {
+    let mut below = Vec::new();
+    for conjunct in conjuncts {
+        let columns = conjunct.column_refs();
+        let on_group_keys = !aggregate.group_expr.is_empty()
+            && !columns.is_empty()
+            && conjunct.is_deterministic()
+            && !contains_aggregate(&conjunct)
+            && columns.iter().all(|column| {
+                aggregate
+                    .group_expr
+                    .iter()
+                    .any(|key| matches!(key, Expression::Column(key) if same_column(key, column)))
+            });
+        if on_group_keys {
+            below.push(conjunct);
+        } else {
+            kept.push(conjunct);
+        }
+    }
+    aggregate.input = Box::new(with_filter(below, *aggregate.input));
+    LogicalPlan::Aggregate(aggregate)
+}
+
+/// Push conditions on keys that every window expression partitions by below the
+/// window. They keep or drop whole partitions, so the partitions left see the same rows.
+fn into_window(
+    mut window: WindowNode,
+    conjuncts: Vec<Expression>,
+    kept: &mut Vec<Expression>,
+) -> LogicalPlan {
+    let input = scope(&window.input);
+    let windows: Vec<&Expression> = window
+        .window_expr
+        .iter()
+        .flat_map(Expression::window_exprs)
+        .collect();
+    let is_partition_key = |column: &ColumnRef| {
+        resolves(&input, column)
+            && windows.iter().all(|window| {
+                matches!(window, Expression::Window { partition_by, .. }
+                if partition_by.iter().any(
+                    |key| matches!(key, Expression::Column(key) if same_column(key, column))
+                ))
+            })
+    };
+    let mut below = Vec::new();
+    for conjunct in conjuncts {
+        let columns = conjunct.column_refs();
+        if !columns.is_empty()
+            && conjunct.is_deterministic()
+            && columns.into_iter().all(is_partition_key)
+        {
+            below.push(conjunct);
+        } else {
+            kept.push(conjunct);
+        }
+    }
+    window.input = Box::new(with_filter(below, *window.input));
+    LogicalPlan::Window(window)
+}
+
+/// Push conditions on the output of a set operation into both of its inputs, whose
+/// columns match the output's by position
+fn into_set_operation(
+    schema: &LogicalSchema,
+    left: Box<LogicalPlan>,
+    right: Box<LogicalPlan>,
+    conjuncts: Vec<Expression>,
+    kept: &mut Vec<Expression>,
+) -> (Box<LogicalPlan>, Box<LogicalPlan>) {
+    let (mut to_left, mut to_right) = (Vec::new(), Vec::new());
+    for conjunct in conjuncts {
+        match (
+            restate(&conjunct, schema, left.schema()),
+            restate(&conjunct, schema, right.schema()),
+        ) {
+            (Some(left), Some(right)) => {
+                to_left.push(left);
+                to_right.push(right);
+            }
+            _ => kept.push(conjunct),
+        }
+    }
+    (
+        Box::new(with_filter(to_left, *left)),
+        Box::new(with_filter(to_right, *right)),
+    )
+}
+
+/// `predicate` over the columns of `from`, restated over the columns of `to` at the
+/// same positions
+fn restate(predicate: &Expression, from: &LogicalSchema, to: &LogicalSchema) -> Option<Expression> {
+    if !predicate.is_deterministic() {
+        return None;
+    }
+    let target = |column: &ColumnRef| {
+        let def = to.columns.get(position(from, column)?)?;
+        let target = ColumnRef {
+            table: def.table.clone(),
+            name: def.name.clone(),
+            index: None,
+        };
+        // The restated column must not be ambiguous in `to`
+        position(to, &target).map(|_| target)
+    };
+    if predicate
+        .column_refs()
+        .into_iter()
+        .any(|column| target(column).is_none())
+    {
+        return None;
+    }
+    let mut rewritten = predicate.clone();
+    replace_columns(&mut rewritten, &|column| {
+        Expression::Column(target(column).unwrap_or_else(|| column.clone()))
+    });
+    Some(rewritten)
+}
+
+fn covers(scope: &LogicalSchema, predicate: &Expression) -> bool {
+    predicate
+        .column_refs()
+        .into_iter()
+        .all(|column| resolves(scope, column))
+}
+
+fn is_true(expr: &Expression) -> bool {
+    matches!(expr, Expression::Literal(Value::Boolean(true)))
+}
+
+/// `input` filtered on `conjuncts`, bound to the columns of `input`
+fn with_filter(mut conjuncts: Vec<Expression>, input: LogicalPlan) -> LogicalPlan {
+    if conjuncts.is_empty() {
+        return input;
+    }
+    let scope = scope(&input);
+    for conjunct in &mut conjuncts {
+        bind(conjunct, &scope);
+    }
+    with_conjuncts(conjuncts, input, PlanStatistics::unknown())
+}
+
+fn with_conjuncts(
+    conjuncts: Vec<Expression>,
+    input: LogicalPlan,
+    statistics: PlanStatistics,
+) -> LogicalPlan {
+    match Expression::conjunction(conjuncts) {
+        Some(predicate) => LogicalPlan::Filter(FilterNode {
+            predicate,
+            input: Box::new(input),
+            statistics,
+        }),
+        None => input,
+    }
+}
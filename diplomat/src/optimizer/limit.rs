@@ -0,0 +1,116 @@
+//! Limit pushdown: bound the rows produced below a LIMIT, so inputs stop early.
+
+use super::rule::OptimizerRule;
+use crate::{
+    common::LogicalPlanError,
+    logical_plan::{LimitNode, LogicalPlan},
+    types::{JoinType, PlanStatistics},
+    visitor::Transformed,
+};
+
+/// Moves limits below projections, merges nested limits, and bounds the inputs of
+/// `UNION ALL` and of the preserved side of outer joins by `OFFSET + LIMIT` rows
+pub struct PushDownLimit;
+
+impl OptimizerRule for PushDownLimit {
+    fn name(&self) -> &'static str {
+        "push_down_limit"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+        push_down(plan)
+    }
+}
+
+fn push_down(plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+    plan.transform_down_with_subqueries(|plan| {
+        Ok(match plan {
+            LogicalPlan::Limit(limit) => push_limit(limit),
+            other => Transformed::no(other),
+        })
+    })
+}
+
+fn push_limit(mut limit: LimitNode) -> Transformed<LogicalPlan> {
+    let Some(fetch) = limit.fetch else {
+        // An OFFSET alone doesn't bound anything
+        return Transformed::no(LogicalPlan::Limit(limit));
+    };
+    let skip = limit.skip.unwrap_or(0);
+    let bound = skip.saturating_add(fetch);
+
+    match *limit.input {
+        LogicalPlan::Limit(inner) => {
+            // The outer limit skips and fetches from the rows the inner one returns
+            let skip = inner.skip.unwrap_or(0).saturating_add(skip);
+            let fetch = match inner.fetch {
+                Some(inner_fetch) => inner_fetch
+                    .saturating_sub(limit.skip.unwrap_or(0))
+                    .min(fetch),
+                None => fetch,
+            };
+            let merged = push_limit(LimitNode {
+                skip: (skip > 0).then_some(skip),
+                fetch: Some(fetch),
+                input: inner.input,
+                statistics: limit.statistics,
+            });
+            Transformed::yes(merged.data)
+        }
+        LogicalPlan::Projection(mut projection) => {
+            // A projection maps every row to exactly one row
+            limit.input = projection.input;
+            projection.input = Box::new(push_limit(limit).data);
+            Transformed::yes(LogicalPlan::Projection(projection))
+        }
+        LogicalPlan::Union(mut union) if union.all => {
+            let left = bounded(*union.left, bound);
+            let right = bounded(*union.right, bound);
+            let transformed = left.transformed || right.transformed;
+            (union.left, union.right) = (Box::new(left.data), Box::new(right.data));
+            limit.input = Box::new(LogicalPlan::Union(union));
+            Transformed::new(LogicalPlan::Limit(limit), transformed)
+        }
+        LogicalPlan::Join(mut join) if join.join_type == JoinType::Left => {
+            // Every row of the preserved side yields at least one row
+            let left = bounded(*join.left, bound);
+            join.left = Box::new(left.data);
+            limit.input = Box::new(LogicalPlan::Join(join));
+            Transformed::new(LogicalPlan::Limit(limit), left.transformed)
+        }
+        LogicalPlan::Join(mut join) if join.join_type == JoinType::Right => {
+            let right = bounded(*join.right, bound);
+            join.right = Box::new(right.data);
+            limit.input = Box::new(LogicalPlan::Join(join));
+            Transformed::new(LogicalPlan::Limit(limit), right.transformed)
+        }
+        input => {
+            limit.input = Box::new(input);
+            Transformed::no(LogicalPlan::Limit(limit))
+        }
+    }
+}
+
+/// `plan` returning at most `bound` rows, transformed unless it already did
+fn bounded(plan: LogicalPlan, bound: usize) -> Transformed<LogicalPlan> {
+    if is_bounded(&plan, bound) {
+        return Transformed::no(plan);
+    }
+    let limit = push_limit(LimitNode {
+        skip: None,
+        fetch: Some(bound),
+        input: Box::new(plan),
+        statistics: PlanStatistics::unknown(),
+    });
+    Transformed::yes(limit.data)
+}
+
+fn is_bounded(plan: &LogicalPlan, bound: usize) -> bool {
+    match plan {
+        LogicalPlan::Limit(limit) => {
+            limit.skip.is_none() && limit.fetch.is_some_and(|fetch| fetch <= bound)
+        }
+        LogicalPlan::Projection(projection) => is_bounded(&projection.input, bound),
+        _ => false,
+    }
+}
@@ -0,0 +1,163 @@
+//! Column pruning: scans read only the columns the rest of the plan uses.
+
+use super::{decorrelate::contains_subquery, rule::OptimizerRule};
+use crate::{
+    common::LogicalPlanError,
+    expression::Expression,
+    logical_plan::{LogicalPlan, TableScanNode},
+    types::ColumnRef,
+    visitor::Transformed,
+};
+
+/// Sets `projected_columns` of every table scan to the columns read above it
+pub struct PruneColumns;
+
+impl OptimizerRule for PruneColumns {
+    fn name(&self) -> &'static str {
+        "prune_columns"
+    }
+
+    fn rewrite(&self, mut plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+        let transformed = prune(&mut plan, None);
+        Ok(Transformed::new(plan, transformed))
+    }
+}
+
+/// Columns a plan must produce; `None` when all of them are needed
+type Required = Option<Vec<ColumnRef>>;
+
+/// Prune the scans under `plan`, returning whether any of them changed
+fn prune(plan: &mut LogicalPlan, required: Required) -> bool {
+    let mut changed = false;
+    // Queries in expressions are pruned on their own
+    for expr in plan.expressions_mut() {
+        for subquery in expr.subquery_plans_mut() {
+            changed |= prune(subquery, None);
+        }
+    }
+    // A subquery may read any column of the row it is evaluated for
+    let correlated = plan
+        .expressions_mut()
+        .into_iter()
+        .any(|expr| contains_subquery(expr));
+    let reads = |required: Required, exprs: Vec<&Expression>| -> Required {
+        if correlated {
+            return None;
+        }
+        let mut required = required?;
+        for expr in exprs {
+            if matches!(expr, Expression::Wildcard { .. }) {
+                return None;
+            }
+            required.extend(expr.column_refs().into_iter().cloned());
+        }
+        Some(required)
+    };
+
+    match plan {
+        LogicalPlan::TableScan(scan) => {
+            if !correlated && let Some(required) = required {
+                changed |= project(scan, required);
+            }
+        }
+        LogicalPlan::Projection(projection) => {
+            // The projection's expressions are all its input has to provide
+            let required = reads(Some(Vec::new()), projection.expressions.iter().collect());
+            changed |= prune(&mut projection.input, required);
+        }
+        LogicalPlan::Filter(filter) => {
+            let required = reads(required, vec![&filter.predicate]);
+            changed |= prune(&mut filter.input, required);
+        }
+        LogicalPlan::Join(join) => {
+            let required = reads(required, join.join_constraint.iter().collect());
+            changed |= prune(&mut join.left, required.clone());
+            changed |= prune(&mut join.right, required);
+        }
+        LogicalPlan::Aggregate(aggregate) => {
+            // Only the grouping keys and aggregate arguments are read, e.g. none for
+            // COUNT(*)
+            let required = reads(
+                Some(Vec::new()),
+                aggregate
+                    .group_expr
+                    .iter()
+                    .chain(&aggregate.aggr_expr)
+                    .collect(),
+            );
+            changed |= prune(&mut aggregate.input, required);
+        }
+        LogicalPlan::Window(window) => {
+            let required = reads(required, window.window_expr.iter().collect());
+            changed |= prune(&mut window.input, required);
+        }
+        LogicalPlan::Sort(sort) => {
+            let required = reads(
+                required,
+                sort.expressions
+                    .iter()
+                    .map(|sort_expr| sort_expr.expr.as_ref())
+                    .collect(),
+            );
+            changed |= prune(&mut sort.input, required);
+        }
+        LogicalPlan::Limit(limit) => changed |= prune(&mut limit.input, required),
+        LogicalPlan::With(with) => {
+            for cte in &mut with.ctes {
+                changed |= prune(&mut cte.plan, None);
+            }
+            changed |= prune(&mut with.input, required);
+        }
+        // DISTINCT compares whole rows, and set operations match their inputs'
+        // columns by position
+        _ => {
+            for child in plan.children_mut() {
+                changed |= prune(child, None);
+            }
+        }
+    }
+    changed
+}
+
+/// Read only the columns of `scan` in `required`, besides those its filters read.
+/// Returns whether that changed the columns the scan reads.
+fn project(scan: &mut TableScanNode, mut required: Vec<ColumnRef>) -> bool {
+    required.extend(
+        scan.filters
+            .iter()
+            .flat_map(|filter| filter.column_refs())
+            .cloned(),
+    );
+    let qualifier = scan.table.effective_name();
+    let columns: Vec<String> = scan
+        .schema
+        .columns
+        .iter()
+        .filter(|column| {
+            required.iter().any(|needed| {
+                needed.name == column.name
+                    && needed.table.as_deref().is_none_or(|table| {
+                        table == qualifier || column.table.as_deref() == Some(table)
+                    })
+            })
+        })
+        .map(|column| column.name.clone())
+        .collect();
+
+    let projected_columns = if columns.len() == scan.schema.columns.len() {
+        None
+    } else if columns.is_empty() {
+        // Rows still have to be produced, e.g. for COUNT(*); read the first column
+        scan.schema
+            .columns
+            .first()
+            .map(|column| vec![column.name.clone()])
+    } else {
+        Some(columns)
+    };
+    if scan.projected_columns == projected_columns {
+        return false;
+    }
+    scan.projected_columns = projected_columns;
+    true
+}
@@ -0,0 +1,77 @@
+//! Rewrite rules, applied one after the other, pass after pass, until a whole pass
+//! leaves the plan unchanged.
+
+use std::time::{Duration, Instant};
+
+use super::{
+    eliminate::{EliminateProjections, EliminateSorts},
+    filter::{MergeFilters, PushDownFilter},
+    limit::PushDownLimit,
+    prune::PruneColumns,
+};
+use crate::{common::LogicalPlanError, logical_plan::LogicalPlan, visitor::Transformed};
+
+/// Passes over the rule set after which the optimizer stops even if rules still
+/// change the plan
+pub const DEFAULT_MAX_PASSES: usize = 16;
+
+pub trait OptimizerRule: Send + Sync {
+    /// Name of the rule in the optimizer trace
+    fn name(&self) -> &'static str;
+
+    /// Rewrite `plan`, or return it as is, flagged as not transformed, when the rule
+    /// doesn't apply anywhere in it
+    fn rewrite(&self, plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError>;
+}
+
+/// One application of a rule by the optimizer
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleApplication {
+    /// Pass over the rule set, starting at 1
+    pub pass: usize,
+    pub rule: &'static str,
+    /// Whether the rule changed the plan
+    pub changed: bool,
+    pub elapsed: Duration,
+}
+
+/// The rules the optimizer applies unless told otherwise, in order
+pub fn default_rules() -> Vec<Box<dyn OptimizerRule>> {
+    vec![
+        Box::new(MergeFilters),
+        Box::new(PushDownFilter),
+        Box::new(PushDownLimit),
+        Box::new(EliminateSorts),
+        Box::new(EliminateProjections),
+        Box::new(PruneColumns),
+    ]
+}
+
+/// Apply `rules` in order until a pass changes nothing or `max_passes` passes are
+/// done, recording every application in `trace`
+pub(super) fn apply_to_fixpoint(
+    rules: &[Box<dyn OptimizerRule>],
+    mut plan: LogicalPlan,
+    max_passes: usize,
+    trace: &mut Vec<RuleApplication>,
+) -> Result<LogicalPlan, LogicalPlanError> {
+    for pass in 1..=max_passes {
+        let mut pass_changed = false;
+        for rule in rules {
+            let start = Instant::now();
+            let rewritten = rule.rewrite(plan)?;
+            trace.push(RuleApplication {
+                pass,
+                rule: rule.name(),
+                changed: rewritten.transformed,
+                elapsed: start.elapsed(),
+            });
+            pass_changed |= rewritten.transformed;
+            plan = rewritten.data;
+        }
+        if !pass_changed {
+            break;
+        }
+    }
+    Ok(plan)
+}
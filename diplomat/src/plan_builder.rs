@@ -1,22 +1,38 @@
+use crate::binder::Binder;
 use crate::logical_plan::*;
+use crate::operator::analyze::AnalyzePlan;
 use crate::operator::create::CreatePlan;
 use crate::operator::delete::DeletePlan;
 use crate::operator::drop::DropPlan;
+use crate::operator::explain::ExplainPlan;
 use crate::operator::insert::InsertPlan;
 use crate::operator::update::UpdatePlan;
 use crate::types::LogicalSchema;
 use crate::{common::LogicalPlanError, operator::query::QueryPlan};
+use matan::manager::CatalogManager;
 use sqlparser::ast::{self, Statement, TableObject};
 use std::collections::HashMap;
 
 pub struct PlanBuilder {
     table_schemas: HashMap<String, LogicalSchema>,
+    binder: Option<Binder>,
 }
 
 impl PlanBuilder {
     pub fn new() -> Self {
         Self {
             table_schemas: HashMap::new(),
+            binder: None,
+        }
+    }
+
+    /// Plan against the tables in `catalog`, resolving every table and column name
+    /// in queries and rejecting unknown or ambiguous ones
+    pub fn from_catalog(catalog: &CatalogManager) -> Self {
+        let binder = Binder::new(catalog);
+        Self {
+            table_schemas: binder.table_schemas().clone(),
+            binder: Some(binder),
         }
     }
 
@@ -26,10 +42,18 @@ impl PlanBuilder {
         self
     }
 
+    /// `ANALYZE` without a table, which covers every table
+    pub fn analyze_all(&self) -> Result<LogicalPlan, LogicalPlanError> {
+        AnalyzePlan::new(self.table_schemas.clone()).generate(None, &[])
+    }
+
     pub fn generate(&mut self, statement: &Statement) -> Result<LogicalPlan, LogicalPlanError> {
         match statement {
             Statement::Query(query) => {
                 let mut builder = QueryPlan::new(self.table_schemas.clone());
+                if let Some(binder) = &self.binder {
+                    builder = builder.with_binder(binder.clone());
+                }
                 builder.generate(&query)
             }
             Statement::Insert(insert) => {
@@ -107,6 +131,21 @@ impl PlanBuilder {
                 let builder = DropPlan::new(self.table_schemas.clone());
                 builder.drop_table(object_type, names, *if_exists, *cascade)
             }
+            Statement::Analyze {
+                table_name,
+                columns,
+                ..
+            } => AnalyzePlan::new(self.table_schemas.clone()).generate(Some(table_name), columns),
+            Statement::Explain {
+                analyze,
+                statement,
+                format,
+                options,
+                ..
+            } => {
+                let explain = ExplainPlan::new(*analyze, format.as_ref(), options.as_deref())?;
+                Ok(explain.generate(self.generate(statement)?))
+            }
             _ => Err(LogicalPlanError::UnsupportedOperation(format!(
                 "Unsupported statement: {:?}",
                 statement
@@ -16,6 +16,16 @@ impl SQLParser {
     }
 
     pub fn parse(&mut self, sql: &str) -> Result<LogicalPlan, LogicalPlanError> {
+        // sqlparser wants a table after ANALYZE
+        if sql
+            .trim()
+            .trim_end_matches(';')
+            .trim_end()
+            .eq_ignore_ascii_case("analyze")
+        {
+            return self.builder.analyze_all();
+        }
+
         let statements = Parser::parse_sql(self.dialect.as_ref(), sql)?;
 
         if statements.is_empty() {
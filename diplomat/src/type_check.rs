@@ -0,0 +1,787 @@
+//! Static type checking of expressions
+//!
+//! Every expression is typed against the schema of the rows it is evaluated over.
+//! Where a conversion is safe an implicit one is inserted: literals are converted at
+//! plan time, so `bigint_column = 1` compares two BIGINTs and `date_column =
+//! '2024-01-31'` two DATEs, and other expressions are wrapped in a cast. Comparisons,
+//! arithmetic and assignments between types that don't convert fail here, before
+//! execution starts.
+//!
+//! Types that can't be told are `None`: NULL, columns of tables without a known
+//! schema and the like. Text, which columns of unknown type are planned as, converts
+//! to anything at run time and is not rejected either.
+
+use shared_types::{
+    DataType, Decimal, ParamType, Value,
+    decimal::{DIVISION_EXTRA_SCALE, MAX_DECIMAL_PRECISION},
+    function::common_type,
+};
+
+use crate::{
+    common::LogicalPlanError,
+    expression::{BinaryOperator, Expression, UnaryOperator},
+    functions::known_type,
+    types::{AggregateFunction, ColumnDef, ColumnRef, LogicalSchema},
+};
+
+/// Type `expr` over rows of `schema`, returning it with implicit conversions added
+pub fn coerce_expression(
+    expr: Expression,
+    schema: &LogicalSchema,
+) -> Result<(Expression, Option<DataType>), LogicalPlanError> {
+    match expr {
+        Expression::Literal(value) => {
+            let data_type = value.data_type();
+            Ok((Expression::Literal(value), data_type))
+        }
+        Expression::Column(column) => {
+            let data_type = column_type(&column, schema);
+            Ok((Expression::Column(column), data_type))
+        }
+        Expression::BinaryOp { left, op, right } => {
+            let (left, left_type) = coerce_expression(*left, schema)?;
+            let (right, right_type) = coerce_expression(*right, schema)?;
+            binary_op(left, left_type, op, right, right_type)
+        }
+        Expression::UnaryOp { op, expr } => {
+            let (expr, data_type) = coerce_expression(*expr, schema)?;
+            let data_type = match op {
+                UnaryOperator::Not => {
+                    expect_boolean(data_type.as_ref(), "NOT")?;
+                    Some(DataType::Boolean)
+                }
+                UnaryOperator::Plus | UnaryOperator::Minus => {
+                    expect_param(data_type.as_ref(), ParamType::Numeric, "unary minus")?;
+                    data_type
+                }
+                UnaryOperator::BitwiseNot => {
+                    expect_param(data_type.as_ref(), ParamType::Integer, "~")?;
+                    data_type
+                }
+            };
+            Ok((Expression::unary_op(op, expr), data_type))
+        }
+        Expression::Function { name, args } => {
+            let args = args
+                .into_iter()
+                .map(|arg| coerce_expression(arg, schema).map(|(arg, _)| arg))
+                .collect::<Result<Vec<_>, _>>()?;
+            let expr = Expression::Function { name, args };
+            let data_type = known_type(&expr, schema)?;
+            Ok((expr, data_type))
+        }
+        Expression::Aggregate {
+            func,
+            expr,
+            distinct,
+        } => {
+            let (expr, arg_type) = match expr {
+                Some(expr) => {
+                    let (expr, data_type) = coerce_expression(*expr, schema)?;
+                    (Some(expr), data_type)
+                }
+                None => (None, None),
+            };
+            let data_type = aggregate_type(&func, arg_type)?;
+            Ok((Expression::aggregate(func, expr, distinct), data_type))
+        }
+        Expression::Case {
+            expr,
+            when_clauses,
+            else_clause,
+        } => case(expr, when_clauses, else_clause, schema),
+        Expression::Cast { expr, data_type } => {
+            let (expr, source) = coerce_expression(*expr, schema)?;
+            let expr = match expr {
+                // String literals are parsed now, so a bad one is reported before execution
+                Expression::Literal(value @ (Value::String(_) | Value::Text(_))) => {
+                    coerce_literal(value, &data_type, "CAST")?
+                }
+                expr if source.as_ref() == Some(&data_type) => expr,
+                expr => Expression::cast(expr, data_type.clone()),
+            };
+            Ok((expr, Some(data_type)))
+        }
+        Expression::Alias { expr, name } => {
+            let (expr, data_type) = coerce_expression(*expr, schema)?;
+            Ok((Expression::alias(expr, name), data_type))
+        }
+        Expression::IsNull(expr) => {
+            let (expr, _) = coerce_expression(*expr, schema)?;
+            Ok((Expression::is_null(expr), Some(DataType::Boolean)))
+        }
+        Expression::IsNotNull(expr) => {
+            let (expr, _) = coerce_expression(*expr, schema)?;
+            Ok((Expression::is_not_null(expr), Some(DataType::Boolean)))
+        }
+        Expression::In {
+            expr,
+            list,
+            negated,
+        } => {
+            let (expr, data_type) = coerce_expression(*expr, schema)?;
+            let mut items = Vec::with_capacity(list.len());
+            for item in list {
+                let (item, item_type) = coerce_expression(item, schema)?;
+                let (_, item) = comparison(
+                    expr.clone(),
+                    data_type.as_ref(),
+                    item,
+                    item_type.as_ref(),
+                    "IN",
+                )?;
+                items.push(item);
+            }
+            Ok((
+                Expression::in_list(expr, items, negated),
+                Some(DataType::Boolean),
+            ))
+        }
+        Expression::Between {
+            expr,
+            low,
+            high,
+            negated,
+        } => {
+            let (expr, data_type) = coerce_expression(*expr, schema)?;
+            let (low, low_type) = coerce_expression(*low, schema)?;
+            let (high, high_type) = coerce_expression(*high, schema)?;
+            let (_, low) = comparison(
+                expr.clone(),
+                data_type.as_ref(),
+                low,
+                low_type.as_ref(),
+                "BETWEEN",
+            )?;
+            let (_, high) = comparison(
+                expr.clone(),
+                data_type.as_ref(),
+                high,
+                high_type.as_ref(),
+                "BETWEEN",
+            )?;
+            Ok((
+                Expression::between(expr, low, high, negated),
+                Some(DataType::Boolean),
+            ))
+        }
+        Expression::Like {
+            expr,
+            pattern,
+            negated,
+            case_insensitive,
+        } => {
+            let (expr, data_type) = coerce_expression(*expr, schema)?;
+            let (pattern, pattern_type) = coerce_expression(*pattern, schema)?;
+            expect_param(data_type.as_ref(), ParamType::Text, "LIKE")?;
+            expect_param(pattern_type.as_ref(), ParamType::Text, "LIKE")?;
+            Ok((
+                Expression::Like {
+                    expr: Box::new(expr),
+                    pattern: Box::new(pattern),
+                    negated,
+                    case_insensitive,
+                },
+                Some(DataType::Boolean),
+            ))
+        }
+        Expression::InSubquery {
+            expr,
+            subquery,
+            negated,
+        } => {
+            let (expr, data_type) = coerce_expression(*expr, schema)?;
+            let column_type = subquery
+                .schema()
+                .columns
+                .first()
+                .map(|column| column.data_type.clone());
+            check_comparable(data_type.as_ref(), column_type.as_ref(), "IN")?;
+            Ok((
+                Expression::InSubquery {
+                    expr: Box::new(expr),
+                    subquery,
+                    negated,
+                },
+                Some(DataType::Boolean),
+            ))
+        }
+        Expression::Exists { .. } => Ok((expr, Some(DataType::Boolean))),
+        Expression::Subquery { .. } => {
+            let data_type = known_type(&expr, schema)?;
+            Ok((expr, data_type))
+        }
+        Expression::Window { .. } | Expression::Wildcard { .. } => Ok((expr, None)),
+    }
+}
+
+/// Type a WHERE, HAVING or join condition, which must be a boolean
+pub fn check_predicate(
+    expr: Expression,
+    schema: &LogicalSchema,
+    clause: &str,
+) -> Result<Expression, LogicalPlanError> {
+    let (expr, data_type) = coerce_expression(expr, schema)?;
+    expect_boolean(data_type.as_ref(), clause)?;
+    Ok(expr)
+}
+
+/// Convert `expr`, of type `data_type`, for storing in `column`. Beyond the
+/// conversions comparisons make, an assignment may narrow a number, which is checked
+/// against the column's range when the row is written, and store anything as text.
+pub fn coerce_assignment(
+    expr: Expression,
+    data_type: Option<&DataType>,
+    column: &ColumnDef,
+) -> Result<Expression, LogicalPlanError> {
+    let target = &column.data_type;
+    let context = format!("column {}", column.name);
+    match (expr, data_type) {
+        (Expression::Literal(Value::Null), _) if !column.nullable => {
+            Err(LogicalPlanError::ValidationError(format!(
+                "NULL value in column \"{}\" violates not-null constraint",
+                column.name
+            )))
+        }
+        (expr, None) => Ok(expr),
+        (expr, Some(source)) if source == target => Ok(expr),
+        (Expression::Literal(value), Some(_)) => coerce_literal(value, target, &context),
+        (expr, Some(source)) => {
+            check_assignable(source, column)?;
+            Ok(Expression::cast(expr, target.clone()))
+        }
+    }
+}
+
+/// Check that values of type `source` can be stored in `column`, as by
+/// [`coerce_assignment`]
+pub fn check_assignable(source: &DataType, column: &ColumnDef) -> Result<(), LogicalPlanError> {
+    let target = &column.data_type;
+    if source == target
+        || is_text(source)
+        || is_text(target)
+        || common_type(source, target).is_some()
+    {
+        return Ok(());
+    }
+    Err(mismatch(
+        format!("{:?} for column {}", target, column.name),
+        source,
+    ))
+}
+
+/// Convert `expr`, of type `data_type`, to `target`: literals of any type that
+/// represents a `target` value, and other expressions of a type `target` widens
+pub fn coerce_to(
+    expr: Expression,
+    data_type: Option<&DataType>,
+    target: &DataType,
+    context: &str,
+) -> Result<Expression, LogicalPlanError> {
+    match (expr, data_type) {
+        (expr, None) => Ok(expr),
+        (expr, Some(source)) if source == target => Ok(expr),
+        (Expression::Literal(value), Some(_)) => coerce_literal(value, target, context),
+        (expr, Some(source)) if common_type(source, target).as_ref() == Some(target) => {
+            Ok(Expression::cast(expr, target.clone()))
+        }
+        (_, Some(source)) => Err(mismatch(format!("{:?} for {}", target, context), source)),
+    }
+}
+
+/// Type of the aggregate over arguments of type `arg_type`, as the executor computes it
+fn aggregate_type(
+    func: &AggregateFunction,
+    arg_type: Option<DataType>,
+) -> Result<Option<DataType>, LogicalPlanError> {
+    if matches!(func, AggregateFunction::Sum | AggregateFunction::Avg) {
+        let name = format!("{:?}", func).to_uppercase();
+        expect_param(arg_type.as_ref(), ParamType::Numeric, &name)?;
+    }
+    Ok(match func {
+        AggregateFunction::Count | AggregateFunction::CountDistinct => Some(DataType::Integer),
+        AggregateFunction::Sum => arg_type.filter(|t| !is_text(t)).map(|t| match t {
+            DataType::Decimal { scale, .. } => DataType::Decimal {
+                precision: MAX_DECIMAL_PRECISION,
+                scale,
+            },
+            DataType::Float => DataType::Float,
+            _ => DataType::BigInt,
+        }),
+        AggregateFunction::Avg => arg_type.filter(|t| !is_text(t)).map(|t| match t {
+            DataType::Decimal { scale, .. } => DataType::Decimal {
+                precision: MAX_DECIMAL_PRECISION,
+                scale: (scale + DIVISION_EXTRA_SCALE).min(MAX_DECIMAL_PRECISION),
+            },
+            _ => DataType::Float,
+        }),
+        AggregateFunction::Min | AggregateFunction::Max => arg_type,
+    })
+}
+
+fn binary_op(
+    left: Expression,
+    left_type: Option<DataType>,
+    op: BinaryOperator,
+    right: Expression,
+    right_type: Option<DataType>,
+) -> Result<(Expression, Option<DataType>), LogicalPlanError> {
+    let (left_ref, right_ref) = (left_type.as_ref(), right_type.as_ref());
+    let (left, right, data_type) = match op {
+        BinaryOperator::Eq
+        | BinaryOperator::NotEq
+        | BinaryOperator::Lt
+        | BinaryOperator::LtEq
+        | BinaryOperator::Gt
+        | BinaryOperator::GtEq
+        | BinaryOperator::IsDistinctFrom
+        | BinaryOperator::IsNotDistinctFrom => {
+            let (left, right) = comparison(left, left_ref, right, right_ref, &op.to_string())?;
+            (left, right, Some(DataType::Boolean))
+        }
+        BinaryOperator::And | BinaryOperator::Or => {
+            expect_boolean(left_ref, &op.to_string())?;
+            expect_boolean(right_ref, &op.to_string())?;
+            (left, right, Some(DataType::Boolean))
+        }
+        BinaryOperator::StringConcat => (left, right, Some(DataType::String)),
+        BinaryOperator::Plus
+        | BinaryOperator::Minus
+        | BinaryOperator::Multiply
+        | BinaryOperator::Divide
+        | BinaryOperator::Modulo => arithmetic(
+            left,
+            left_type,
+            op.clone(),
+            right,
+            right_type,
+            ParamType::Numeric,
+        )?,
+        BinaryOperator::BitwiseAnd
+        | BinaryOperator::BitwiseOr
+        | BinaryOperator::BitwiseXor
+        | BinaryOperator::BitwiseShiftLeft
+        | BinaryOperator::BitwiseShiftRight => arithmetic(
+            left,
+            left_type,
+            op.clone(),
+            right,
+            right_type,
+            ParamType::Integer,
+        )?,
+    };
+    Ok((Expression::binary_op(left, op, right), data_type))
+}
+
+/// Operands of an arithmetic operator, whose result is of their common type. Date and
+/// time arithmetic is typed when it runs.
+fn arithmetic(
+    left: Expression,
+    left_type: Option<DataType>,
+    op: BinaryOperator,
+    right: Expression,
+    right_type: Option<DataType>,
+    operands: ParamType,
+) -> Result<(Expression, Expression, Option<DataType>), LogicalPlanError> {
+    let (Some(left_known), Some(right_known)) = (&left_type, &right_type) else {
+        return Ok((left, right, None));
+    };
+    if is_temporal(left_known) || is_temporal(right_known) {
+        return Ok((left, right, None));
+    }
+    // A string literal standing for a number takes the other operand's type
+    let (left, left_known) = match left {
+        Expression::Literal(value) if is_text(left_known) && operands.accepts(right_known) => (
+            coerce_literal(value, right_known, &op.to_string())?,
+            right_known,
+        ),
+        left => (left, left_known),
+    };
+    let (right, right_known) = match right {
+        Expression::Literal(value) if is_text(right_known) && operands.accepts(left_known) => (
+            coerce_literal(value, left_known, &op.to_string())?,
+            left_known,
+        ),
+        right => (right, right_known),
+    };
+    if is_text(left_known) || is_text(right_known) {
+        return Ok((left, right, None));
+    }
+    if !operands.accepts(left_known) || !operands.accepts(right_known) {
+        return Err(LogicalPlanError::TypeMismatch {
+            expected: format!("{} operands for {}", operands, op),
+            found: format!("{:?} and {:?}", left_known, right_known),
+        });
+    }
+    let data_type = common_type(left_known, right_known);
+    Ok((left, right, data_type))
+}
+
+/// Operands of a comparison, converting a literal on either side to the other
+/// side's type where the two differ
+fn comparison(
+    left: Expression,
+    left_type: Option<&DataType>,
+    right: Expression,
+    right_type: Option<&DataType>,
+    op: &str,
+) -> Result<(Expression, Expression), LogicalPlanError> {
+    let (Some(left_known), Some(right_known)) = (left_type, right_type) else {
+        return Ok((left, right));
+    };
+    if left_known == right_known {
+        return Ok((left, right));
+    }
+    let common = common_type(left_known, right_known);
+    match (left, right) {
+        (left, Expression::Literal(value))
+            if common.as_ref() == Some(left_known) || common.is_none() && is_text(right_known) =>
+        {
+            Ok((left, coerce_literal(value, left_known, op)?))
+        }
+        (Expression::Literal(value), right)
+            if common.as_ref() == Some(right_known) || common.is_none() && is_text(left_known) =>
+        {
+            Ok((coerce_literal(value, right_known, op)?, right))
+        }
+        (left, right) => {
+            check_comparable(left_type, right_type, op)?;
+            Ok((left, right))
+        }
+    }
+}
+
+fn check_comparable(
+    left: Option<&DataType>,
+    right: Option<&DataType>,
+    op: &str,
+) -> Result<(), LogicalPlanError> {
+    match (left, right) {
+        (Some(left), Some(right))
+            if common_type(left, right).is_none() && !is_text(left) && !is_text(right) =>
+        {
+            Err(LogicalPlanError::TypeMismatch {
+                expected: format!("a value comparable with {:?} for {}", left, op),
+                found: format!("{:?}", right),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn case(
+    operand: Option<Box<Expression>>,
+    when_clauses: Vec<(Expression, Expression)>,
+    else_clause: Option<Box<Expression>>,
+    schema: &LogicalSchema,
+) -> Result<(Expression, Option<DataType>), LogicalPlanError> {
+    let operand = match operand {
+        Some(operand) => Some(coerce_expression(*operand, schema)?),
+        None => None,
+    };
+    let mut result_type: Option<DataType> = None;
+    let mut unify = |data_type: Option<DataType>| -> Result<(), LogicalPlanError> {
+        result_type = match (result_type.take(), data_type) {
+            (Some(result), Some(data_type)) if is_text(&result) || is_text(&data_type) => {
+                Some(if is_text(&result) { data_type } else { result })
+            }
+            (Some(result), Some(data_type)) => {
+                Some(common_type(&result, &data_type).ok_or_else(|| {
+                    LogicalPlanError::TypeMismatch {
+                        expected: format!("CASE results of type {:?}", result),
+                        found: format!("{:?}", data_type),
+                    }
+                })?)
+            }
+            (result, data_type) => result.or(data_type),
+        };
+        Ok(())
+    };
+
+    let mut clauses = Vec::with_capacity(when_clauses.len());
+    for (when, then) in when_clauses {
+        let (when, when_type) = coerce_expression(when, schema)?;
+        let when = match &operand {
+            Some((operand, operand_type)) => {
+                comparison(
+                    operand.clone(),
+                    operand_type.as_ref(),
+                    when,
+                    when_type.as_ref(),
+                    "CASE",
+                )?
+                .1
+            }
+            None => {
+                expect_boolean(when_type.as_ref(), "CASE WHEN")?;
+                when
+            }
+        };
+        let (then, then_type) = coerce_expression(then, schema)?;
+        unify(then_type)?;
+        clauses.push((when, then));
+    }
+    let else_clause = match else_clause {
+        Some(else_expr) => {
+            let (else_expr, else_type) = coerce_expression(*else_expr, schema)?;
+            unify(else_type)?;
+            Some(Box::new(else_expr))
+        }
+        None => None,
+    };
+
+    Ok((
+        Expression::Case {
+            expr: operand.map(|(operand, _)| Box::new(operand)),
+            when_clauses: clauses,
+            else_clause,
+        },
+        result_type,
+    ))
+}
+
+/// Type of the column of `schema` that `column` refers to. Unqualified schema columns
+/// match any qualifier, and the `*` of a table without a known schema matches nothing.
+fn column_type(column: &ColumnRef, schema: &LogicalSchema) -> Option<DataType> {
+    schema
+        .columns
+        .iter()
+        .find(|def| {
+            def.name == column.name
+                && match (&column.table, &def.table) {
+                    (Some(table), Some(def_table)) => table == def_table,
+                    _ => true,
+                }
+        })
+        .map(|def| def.data_type.clone())
+}
+
+/// `value` as a `target` literal, converted now where that is straightforward and
+/// cast when the query runs otherwise
+fn coerce_literal(
+    value: Value,
+    target: &DataType,
+    context: &str,
+) -> Result<Expression, LogicalPlanError> {
+    if value
+        .data_type()
+        .is_none_or(|data_type| &data_type == target)
+    {
+        return Ok(Expression::Literal(value));
+    }
+    let converted = match &value {
+        Value::String(s) | Value::Text(s) => parse_literal(s, target),
+        Value::Date(days) if matches!(target, DataType::Timestamp | DataType::DateTime) => {
+            Some(instant(target, i64::from(*days) * MILLIS_PER_DAY))
+        }
+        _ => match integer_value(&value) {
+            Some(int) if ParamType::Integer.accepts(target) => integer_literal(int, target),
+            Some(int) if *target == DataType::Float => Some(Value::Float(int as f64)),
+            _ => None,
+        },
+    };
+    match converted {
+        Some(converted) => Ok(Expression::Literal(converted)),
+        None if castable(&value, target) => {
+            Ok(Expression::cast(Expression::Literal(value), target.clone()))
+        }
+        None => Err(LogicalPlanError::TypeMismatch {
+            expected: format!("{:?} for {}", target, context),
+            found: describe_literal(&value),
+        }),
+    }
+}
+
+/// Literals converted when the query runs: numbers, and strings that spell one, to
+/// other numeric types, and anything to text
+fn castable(value: &Value, target: &DataType) -> bool {
+    match value {
+        Value::String(s) | Value::Text(s) if matches!(target, DataType::Decimal { .. }) => {
+            s.trim().parse::<Decimal>().is_ok()
+        }
+        Value::String(_) | Value::Text(_) => false,
+        _ => {
+            is_text(target)
+                || value.data_type().is_some_and(|source| {
+                    ParamType::Numeric.accepts(&source) && ParamType::Numeric.accepts(target)
+                })
+        }
+    }
+}
+
+/// A string literal as a value of `target`, or `None` if it isn't one
+fn parse_literal(s: &str, target: &DataType) -> Option<Value> {
+    let trimmed = s.trim();
+    match target {
+        DataType::String => Some(Value::String(s.to_string())),
+        DataType::Text => Some(Value::Text(s.to_string())),
+        DataType::Char => {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(Value::Char(c)),
+                _ => None,
+            }
+        }
+        DataType::Json => Some(Value::Json(s.to_string())),
+        DataType::Binary => Some(Value::Binary(s.as_bytes().to_vec())),
+        DataType::TinyInt | DataType::SmallInt | DataType::Integer | DataType::BigInt => {
+            integer_literal(trimmed.parse().ok()?, target)
+        }
+        DataType::Float => trimmed.parse().ok().map(Value::Float),
+        DataType::Decimal { .. } => None,
+        DataType::Boolean => match trimmed.to_lowercase().as_str() {
+            "true" | "t" | "1" => Some(Value::Boolean(true)),
+            "false" | "f" | "0" => Some(Value::Boolean(false)),
+            _ => None,
+        },
+        DataType::Date => parse_date(trimmed).map(Value::Date),
+        DataType::Time => parse_time(trimmed).map(Value::Time),
+        DataType::Timestamp | DataType::DateTime => {
+            let (date, time) = match trimmed.split_once([' ', 'T']) {
+                Some((date, time)) => (date, parse_time(time.trim())?),
+                None => (trimmed, 0),
+            };
+            let millis = i64::from(parse_date(date)?) * MILLIS_PER_DAY + i64::from(time);
+            Some(instant(target, millis))
+        }
+        DataType::Uuid => parse_uuid(trimmed).map(Value::Uuid),
+    }
+}
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+fn instant(target: &DataType, millis: i64) -> Value {
+    match target {
+        DataType::DateTime => Value::DateTime(millis),
+        _ => Value::Timestamp(millis),
+    }
+}
+
+/// `YYYY-MM-DD` as days since 1970-01-01
+fn parse_date(s: &str) -> Option<i32> {
+    let mut parts = s.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if day == 0 || day > days_in_month {
+        return None;
+    }
+
+    // Days from the civil calendar, counting years from March so leap days come last
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * i64::from(month) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    i32::try_from(era * 146_097 + day_of_era - 719_468).ok()
+}
+
+/// `HH:MM[:SS[.fff]]` as milliseconds since midnight
+fn parse_time(s: &str) -> Option<u32> {
+    let mut parts = s.splitn(3, ':');
+    let hours: u32 = parts.next()?.parse().ok()?;
+    let minutes: u32 = parts.next()?.parse().ok()?;
+    let (seconds, millis) = match parts.next() {
+        Some(seconds) => match seconds.split_once('.') {
+            Some((seconds, fraction)) => {
+                let digits: String = fraction.chars().chain("000".chars()).take(3).collect();
+                (seconds.parse().ok()?, digits.parse().ok()?)
+            }
+            None => (seconds.parse::<u32>().ok()?, 0),
+        },
+        None => (0, 0),
+    };
+    if hours > 23 || minutes > 59 || seconds > 59 {
+        return None;
+    }
+    Some(((hours * 60 + minutes) * 60 + seconds) * 1000 + millis)
+}
+
+fn parse_uuid(s: &str) -> Option<[u8; 16]> {
+    let hex: String = s.chars().filter(|c| *c != '-').collect();
+    if s.len() != 36 || hex.len() != 32 {
+        return None;
+    }
+    let mut bytes = [0u8; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+fn integer_value(value: &Value) -> Option<i128> {
+    match value {
+        Value::TinyInt(v) => Some(i128::from(*v)),
+        Value::SmallInt(v) => Some(i128::from(*v)),
+        Value::Integer(v) => Some(i128::from(*v)),
+        Value::BigInt(v) => Some(*v),
+        _ => None,
+    }
+}
+
+/// `int` as a value of the integer type `target`, if it is in range
+fn integer_literal(int: i128, target: &DataType) -> Option<Value> {
+    match target {
+        DataType::TinyInt => i8::try_from(int).ok().map(Value::TinyInt),
+        DataType::SmallInt => i16::try_from(int).ok().map(Value::SmallInt),
+        DataType::Integer => i64::try_from(int).ok().map(Value::Integer),
+        _ => Some(Value::BigInt(int)),
+    }
+}
+
+fn describe_literal(value: &Value) -> String {
+    match value {
+        Value::String(s) | Value::Text(s) => format!("'{}'", s),
+        other => format!("{:?}", other),
+    }
+}
+
+fn expect_boolean(data_type: Option<&DataType>, context: &str) -> Result<(), LogicalPlanError> {
+    match data_type {
+        Some(data_type) if *data_type != DataType::Boolean && !is_text(data_type) => {
+            Err(mismatch(format!("Boolean for {}", context), data_type))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn expect_param(
+    data_type: Option<&DataType>,
+    param: ParamType,
+    context: &str,
+) -> Result<(), LogicalPlanError> {
+    match data_type {
+        Some(data_type) if !param.accepts(data_type) && !is_text(data_type) => Err(mismatch(
+            format!("{} operand for {}", param, context),
+            data_type,
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn mismatch(expected: String, found: &DataType) -> LogicalPlanError {
+    LogicalPlanError::TypeMismatch {
+        expected,
+        found: format!("{:?}", found),
+    }
+}
+
+fn is_text(data_type: &DataType) -> bool {
+    ParamType::Text.accepts(data_type)
+}
+
+fn is_temporal(data_type: &DataType) -> bool {
+    ParamType::Temporal.accepts(data_type)
+}
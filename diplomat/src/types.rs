@@ -1,7 +1,8 @@
 //! Type definitions for the logical plan system
 
 use serde::{Deserialize, Serialize};
-use shared_types::{DataType, Value};
+use matan::statistics::TableStatistics;
+use shared_types::{DataType, OrderBy, SortDirection, Value};
 use std::collections::HashMap;
 
 /// Represents a column reference in a logical plan
@@ -11,6 +12,10 @@ pub struct ColumnRef {
     pub table: Option<String>,
     /// Column name
     pub name: String,
+    /// Position of the column in the row produced by the FROM clause, set by the
+    /// binder; `None` for unbound and outer (correlated) references
+    #[serde(default)]
+    pub index: Option<usize>,
 }
 
 impl ColumnRef {
@@ -18,6 +23,7 @@ impl ColumnRef {
         Self {
             table: None,
             name: name.into(),
+            index: None,
         }
     }
 
@@ -25,9 +31,15 @@ impl ColumnRef {
         Self {
             table: Some(table.into()),
             name: name.into(),
+            index: None,
         }
     }
 
+    pub fn at_index(mut self, index: usize) -> Self {
+        self.index = Some(index);
+        self
+    }
+
     pub fn qualified_name(&self) -> String {
         match &self.table {
             Some(table) => format!("{}.{}", table, self.name),
@@ -153,6 +165,53 @@ pub enum JoinType {
     Right,
     Full,
     Cross,
+    /// Left rows with at least one match, each returned once (`EXISTS`, `IN`)
+    LeftSemi,
+    /// Left rows without a match (`NOT EXISTS`)
+    LeftAnti,
+    /// Left rows that are definitely unmatched under SQL `NOT IN`, where a NULL key
+    /// on either side makes the comparison UNKNOWN and drops the row
+    NullAwareLeftAnti,
+}
+
+impl JoinType {
+    /// Semi and anti joins return the left rows alone, without any right columns
+    pub fn returns_left_only(&self) -> bool {
+        matches!(
+            self,
+            JoinType::LeftSemi | JoinType::LeftAnti | JoinType::NullAwareLeftAnti
+        )
+    }
+}
+
+/// Physical join algorithm, chosen by the cost-based optimizer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JoinAlgorithm {
+    Hash,
+    SortMerge,
+    NestedLoop,
+}
+
+/// Input of a join that is held in memory: the hash table of a hash join, or the
+/// inner loop of a nested-loop join
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildSide {
+    Left,
+    Right,
+}
+
+/// How a join is executed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JoinStrategy {
+    pub algorithm: JoinAlgorithm,
+    pub build_side: BuildSide,
+}
+
+/// Output format of EXPLAIN
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExplainFormat {
+    Text,
+    Json,
 }
 
 /// Represents sort order
@@ -171,11 +230,13 @@ pub struct SortExpr {
 }
 
 impl SortExpr {
+    /// NULLs sort as larger than any value, so they come last ascending and first descending
     pub fn new(expr: crate::expression::Expression, order: SortOrder) -> Self {
+        let nulls_first = order == SortOrder::Descending;
         Self {
             expr: Box::new(expr),
             order,
-            nulls_first: false,
+            nulls_first,
         }
     }
 
@@ -183,6 +244,28 @@ impl SortExpr {
         self.nulls_first = true;
         self
     }
+
+    pub fn nulls_last(mut self) -> Self {
+        self.nulls_first = false;
+        self
+    }
+
+    /// Storage engine sort key, available when sorting by a plain column
+    pub fn to_order_by(&self) -> Option<OrderBy> {
+        let crate::expression::Expression::Column(column) = self.expr.as_ref() else {
+            return None;
+        };
+        let direction = match self.order {
+            SortOrder::Ascending => SortDirection::Ascending,
+            SortOrder::Descending => SortDirection::Descending,
+        };
+        let order_by = OrderBy::new(column.name.clone(), direction);
+        Some(if self.nulls_first {
+            order_by.nulls_first()
+        } else {
+            order_by.nulls_last()
+        })
+    }
 }
 
 /// Represents aggregate functions
@@ -196,6 +279,70 @@ pub enum AggregateFunction {
     CountDistinct,
 }
 
+/// Functions that can be evaluated over a window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowFunction {
+    RowNumber,
+    Rank,
+    DenseRank,
+    /// `LAG(expr [, offset [, default]])`
+    Lag,
+    /// `LEAD(expr [, offset [, default]])`
+    Lead,
+    /// An aggregate evaluated over the frame, e.g. `SUM(x) OVER (...)`
+    Aggregate(AggregateFunction),
+}
+
+/// Whether frame offsets count rows or ORDER BY key distance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowFrameUnits {
+    Rows,
+    Range,
+}
+
+/// One end of a window frame
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowFrameBound {
+    UnboundedPreceding,
+    Preceding(u64),
+    CurrentRow,
+    Following(u64),
+    UnboundedFollowing,
+}
+
+/// `ROWS | RANGE BETWEEN start AND end`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowFrame {
+    pub units: WindowFrameUnits,
+    pub start: WindowFrameBound,
+    pub end: WindowFrameBound,
+}
+
+impl WindowFrame {
+    pub fn new(units: WindowFrameUnits, start: WindowFrameBound, end: WindowFrameBound) -> Self {
+        Self { units, start, end }
+    }
+
+    /// The SQL default when the window has an ORDER BY: `RANGE BETWEEN UNBOUNDED
+    /// PRECEDING AND CURRENT ROW`, so aggregates become running totals
+    pub fn running() -> Self {
+        Self::new(
+            WindowFrameUnits::Range,
+            WindowFrameBound::UnboundedPreceding,
+            WindowFrameBound::CurrentRow,
+        )
+    }
+
+    /// The SQL default without an ORDER BY: every row of the partition
+    pub fn whole_partition() -> Self {
+        Self::new(
+            WindowFrameUnits::Rows,
+            WindowFrameBound::UnboundedPreceding,
+            WindowFrameBound::UnboundedFollowing,
+        )
+    }
+}
+
 /// Statistics about a logical plan node
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PlanStatistics {
@@ -203,6 +350,10 @@ pub struct PlanStatistics {
     pub row_count: Option<usize>,
     /// Estimated size in bytes
     pub size_bytes: Option<usize>,
+    /// Estimated cost of producing the rows, inputs included, in the units of the
+    /// cost model
+    #[serde(default)]
+    pub cost: Option<f64>,
     /// Column statistics
     pub column_stats: HashMap<String, ColumnStatistics>,
 }
@@ -212,6 +363,7 @@ impl PlanStatistics {
         Self {
             row_count: None,
             size_bytes: None,
+            cost: None,
             column_stats: HashMap::new(),
         }
     }
@@ -220,11 +372,46 @@ impl PlanStatistics {
         Self {
             row_count: Some(row_count),
             size_bytes: None,
+            cost: None,
             column_stats: HashMap::new(),
         }
     }
 }
 
+/// Statistics gathered by ANALYZE, with column statistics keyed by column name
+impl From<&TableStatistics> for PlanStatistics {
+    fn from(stats: &TableStatistics) -> Self {
+        let row_count = stats.row_count as usize;
+        let column_stats = stats
+            .columns
+            .iter()
+            .map(|(name, column)| {
+                let most_common_values = column
+                    .most_common_values
+                    .iter()
+                    .cloned()
+                    .zip(column.most_common_frequencies.iter().copied())
+                    .collect::<Vec<_>>();
+                let stats = ColumnStatistics {
+                    distinct_count: Some(column.distinct_count as usize),
+                    null_count: Some((column.null_fraction * row_count as f64).round() as usize),
+                    min_value: column.min_value.clone(),
+                    max_value: column.max_value.clone(),
+                    most_common_values: (!most_common_values.is_empty()).then_some(most_common_values),
+                    histogram: (!column.histogram_bounds.is_empty()).then(|| column.histogram_bounds.clone()),
+                };
+                (name.clone(), stats)
+            })
+            .collect();
+        Self {
+            row_count: Some(row_count),
+            size_bytes: None,
+            cost: None,
+            column_stats,
+        }
+    }
+}
+
 /// Statistics about a column
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ColumnStatistics {
@@ -236,6 +423,14 @@ pub struct ColumnStatistics {
     pub min_value: Option<Value>,
     /// Maximum value
     pub max_value: Option<Value>,
+    /// Most common values with the fraction of all rows holding each
+    #[serde(default)]
+    pub most_common_values: Option<Vec<(Value, f64)>>,
+    /// Bounds of an equi-depth histogram over the non-null values not among
+    /// `most_common_values`: `n + 1` ascending values splitting them into `n` buckets
+    /// of about the same number of rows
+    #[serde(default)]
+    pub histogram: Option<Vec<Value>>,
 }
 
 impl ColumnStatistics {
@@ -245,6 +440,17 @@ impl ColumnStatistics {
             null_count: None,
             min_value: None,
             max_value: None,
+            most_common_values: None,
+            histogram: None,
+        }
+    }
+
+    /// Fraction of the `row_count` rows where the column is NULL
+    pub fn null_fraction(&self, row_count: usize) -> Option<f64> {
+        match (self.null_count, row_count) {
+            (Some(_), 0) => Some(0.0),
+            (Some(nulls), rows) => Some((nulls as f64 / rows as f64).min(1.0)),
+            (None, _) => None,
         }
     }
 }
\ No newline at end of file
@@ -1,15 +1,21 @@
-use shared_types::{DataType, Value};
+use shared_types::{DataType, Value, decimal::MAX_DECIMAL_PRECISION, function::common_type};
 use sqlparser::ast::{
-    BinaryOperator as SqlBinaryOperator, DataType as SqlDataType, Expr, Function, FunctionArg,
-    FunctionArgExpr, FunctionArguments, ObjectName, SelectItem, UnaryOperator as SqlUnaryOperator,
-    Value as SqlValue, Values,
+    BinaryOperator as SqlBinaryOperator, CeilFloorKind, DataType as SqlDataType, DateTimeField,
+    ExactNumberInfo, Expr, Function, FunctionArg, FunctionArgExpr, FunctionArguments, Ident,
+    ObjectName, OrderByExpr, Query, SelectItem, SetExpr, TrimWhereField,
+    UnaryOperator as SqlUnaryOperator, Value as SqlValue, Values, WindowFrame as SqlWindowFrame,
+    WindowFrameBound as SqlWindowFrameBound, WindowFrameUnits as SqlWindowFrameUnits, WindowType,
 };
 
 use crate::{
     common::LogicalPlanError,
     expression::{BinaryOperator, Expression, UnaryOperator},
     logical_plan::{LogicalPlan, ValuesNode},
-    types::{AggregateFunction, ColumnDef, LogicalSchema},
+    type_check::{coerce_expression, coerce_to},
+    types::{
+        AggregateFunction, ColumnDef, LogicalSchema, SortExpr, SortOrder, WindowFrame,
+        WindowFrameBound, WindowFrameUnits, WindowFunction,
+    },
 };
 
 /// Convert object name to string
@@ -38,7 +44,8 @@ pub fn expr_to_column_name(expr: &Expr) -> String {
 /// Check if expression is an aggregate
 pub fn is_aggregate_expr(expr: &Expr) -> bool {
     match expr {
-        Expr::Function(func) => {
+        // `SUM(x) OVER (...)` is a window function, not a grouping aggregate
+        Expr::Function(func) if func.over.is_none() => {
             let name = object_name_to_string(&func.name).to_lowercase();
             matches!(name.as_str(), "count" | "sum" | "avg" | "min" | "max")
         }
@@ -101,7 +108,7 @@ pub fn sql_data_type_to_data_type(data_type: &SqlDataType) -> Result<DataType, L
         SqlDataType::Date => Ok(DataType::Date),
         SqlDataType::Time(_, _) => Ok(DataType::Time),
         SqlDataType::Timestamp(_, _) => Ok(DataType::Timestamp),
-        SqlDataType::Decimal(_) | SqlDataType::Numeric(_) => Ok(DataType::Decimal),
+        SqlDataType::Decimal(info) | SqlDataType::Numeric(info) => decimal_data_type(info),
         SqlDataType::Bytea => Ok(DataType::Binary),
         _ => Err(LogicalPlanError::UnsupportedOperation(format!(
             "Unsupported data type: {:?}",
@@ -110,6 +117,31 @@ pub fn sql_data_type_to_data_type(data_type: &SqlDataType) -> Result<DataType, L
     }
 }
 
+/// Convert `DECIMAL[(p[, s])]` to a decimal type, validating precision and scale
+fn decimal_data_type(info: &ExactNumberInfo) -> Result<DataType, LogicalPlanError> {
+    let (precision, scale) = match info {
+        ExactNumberInfo::None => (MAX_DECIMAL_PRECISION as u64, 0),
+        ExactNumberInfo::Precision(p) => (*p, 0),
+        ExactNumberInfo::PrecisionAndScale(p, s) => (*p, *s),
+    };
+    if precision == 0 || precision > MAX_DECIMAL_PRECISION as u64 {
+        return Err(LogicalPlanError::ValidationError(format!(
+            "DECIMAL precision must be between 1 and {}, got {}",
+            MAX_DECIMAL_PRECISION, precision
+        )));
+    }
+    if scale > precision {
+        return Err(LogicalPlanError::ValidationError(format!(
+            "DECIMAL scale {} exceeds precision {}",
+            scale, precision
+        )));
+    }
+    Ok(DataType::Decimal {
+        precision: precision as u8,
+        scale: scale as u8,
+    })
+}
+
 /// Convert SQL unary operator to logical unary operator
 pub fn sql_unary_op_to_unary_op(op: &SqlUnaryOperator) -> Result<UnaryOperator, LogicalPlanError> {
     match op {
@@ -152,16 +184,64 @@ pub fn sql_binary_op_to_binary_op(
     }
 }
 
+/// Hooks for the parts of an expression that depend on the enclosing query: the
+/// queries nested in it (`EXISTS`, `IN (SELECT ...)` and scalar subqueries), planned
+/// with the same tables in scope, and its column names
+pub trait ExprPlanner {
+    fn plan_subquery(&mut self, query: &Query) -> Result<LogicalPlan, LogicalPlanError>;
+    /// Plan the bare `SELECT` of an `IN (SELECT ...)`, which has no ORDER BY or LIMIT
+    fn plan_set_expr(&mut self, set_expr: &SetExpr) -> Result<LogicalPlan, LogicalPlanError>;
+    /// Resolve `qualifier.name` or `name`; by default the reference is left unbound
+    fn bind_column(
+        &mut self,
+        qualifier: Option<&Ident>,
+        name: &Ident,
+    ) -> Result<Expression, LogicalPlanError> {
+        Ok(unbound_column(qualifier, name))
+    }
+}
+
+/// A column reference as written, not yet resolved to a position
+pub fn unbound_column(qualifier: Option<&Ident>, name: &Ident) -> Expression {
+    match qualifier {
+        Some(table) => Expression::qualified_column(&table.value, &name.value),
+        None => Expression::column(&name.value),
+    }
+}
+
+/// Used where no query planner is at hand, so subqueries are rejected
+struct NoSubqueries;
+
+impl NoSubqueries {
+    fn unsupported() -> LogicalPlanError {
+        LogicalPlanError::UnsupportedOperation("Subqueries are not supported here".to_string())
+    }
+}
+
+impl ExprPlanner for NoSubqueries {
+    fn plan_subquery(&mut self, _query: &Query) -> Result<LogicalPlan, LogicalPlanError> {
+        Err(Self::unsupported())
+    }
+    fn plan_set_expr(&mut self, _set_expr: &SetExpr) -> Result<LogicalPlan, LogicalPlanError> {
+        Err(Self::unsupported())
+    }
+}
+
 /// Convert SQL expression to logical expression
 pub fn expr_to_logical_expr(expr: &Expr) -> Result<Expression, LogicalPlanError> {
+    expr_to_logical_expr_with(expr, &mut NoSubqueries)
+}
+
+/// Convert SQL expression to logical expression, planning any subqueries in it with `planner`
+pub fn expr_to_logical_expr_with(
+    expr: &Expr,
+    planner: &mut dyn ExprPlanner,
+) -> Result<Expression, LogicalPlanError> {
     match expr {
-        Expr::Identifier(ident) => Ok(Expression::column(&ident.value)),
+        Expr::Identifier(ident) => planner.bind_column(None, ident),
         Expr::CompoundIdentifier(idents) => {
             if idents.len() == 2 {
-                Ok(Expression::qualified_column(
-                    &idents[0].value,
-                    &idents[1].value,
-                ))
+                planner.bind_column(Some(&idents[0]), &idents[1])
             } else {
                 Err(LogicalPlanError::UnsupportedOperation(
                     "Complex identifiers not supported".to_string(),
@@ -173,41 +253,51 @@ pub fn expr_to_logical_expr(expr: &Expr) -> Result<Expression, LogicalPlanError>
             Ok(Expression::literal(logical_value))
         }
         Expr::BinaryOp { left, op, right } => {
-            let left_expr = expr_to_logical_expr(left)?;
-            let right_expr = expr_to_logical_expr(right)?;
+            let left_expr = expr_to_logical_expr_with(left, planner)?;
+            let right_expr = expr_to_logical_expr_with(right, planner)?;
             let logical_op = sql_binary_op_to_binary_op(op)?;
             Ok(Expression::binary_op(left_expr, logical_op, right_expr))
         }
         Expr::UnaryOp { op, expr } => {
-            let logical_expr = expr_to_logical_expr(expr)?;
+            let logical_expr = expr_to_logical_expr_with(expr, planner)?;
             let logical_op = sql_unary_op_to_unary_op(op)?;
             Ok(Expression::unary_op(logical_op, logical_expr))
         }
-        Expr::Function(function) => function_to_logical_expr(function),
+        Expr::Function(function) => function_to_logical_expr_with(function, planner),
         Expr::Cast {
             expr, data_type, ..
         } => {
-            let logical_expr = expr_to_logical_expr(expr)?;
+            let logical_expr = expr_to_logical_expr_with(expr, planner)?;
             let logical_data_type = sql_data_type_to_data_type(data_type)?;
             Ok(Expression::cast(logical_expr, logical_data_type))
         }
         Expr::IsNull(expr) => {
-            let logical_expr = expr_to_logical_expr(expr)?;
+            let logical_expr = expr_to_logical_expr_with(expr, planner)?;
             Ok(Expression::is_null(logical_expr))
         }
         Expr::IsNotNull(expr) => {
-            let logical_expr = expr_to_logical_expr(expr)?;
+            let logical_expr = expr_to_logical_expr_with(expr, planner)?;
             Ok(Expression::is_not_null(logical_expr))
         }
+        Expr::IsDistinctFrom(left, right) => Ok(Expression::binary_op(
+            expr_to_logical_expr_with(left, planner)?,
+            BinaryOperator::IsDistinctFrom,
+            expr_to_logical_expr_with(right, planner)?,
+        )),
+        Expr::IsNotDistinctFrom(left, right) => Ok(Expression::binary_op(
+            expr_to_logical_expr_with(left, planner)?,
+            BinaryOperator::IsNotDistinctFrom,
+            expr_to_logical_expr_with(right, planner)?,
+        )),
         Expr::InList {
             expr,
             list,
             negated,
         } => {
-            let logical_expr = expr_to_logical_expr(expr)?;
+            let logical_expr = expr_to_logical_expr_with(expr, planner)?;
             let mut logical_list = Vec::new();
             for item in list {
-                logical_list.push(expr_to_logical_expr(item)?);
+                logical_list.push(expr_to_logical_expr_with(item, planner)?);
             }
             Ok(Expression::in_list(logical_expr, logical_list, *negated))
         }
@@ -217,9 +307,9 @@ pub fn expr_to_logical_expr(expr: &Expr) -> Result<Expression, LogicalPlanError>
             low,
             high,
         } => {
-            let logical_expr = expr_to_logical_expr(expr)?;
-            let logical_low = expr_to_logical_expr(low)?;
-            let logical_high = expr_to_logical_expr(high)?;
+            let logical_expr = expr_to_logical_expr_with(expr, planner)?;
+            let logical_low = expr_to_logical_expr_with(low, planner)?;
+            let logical_high = expr_to_logical_expr_with(high, planner)?;
             Ok(Expression::between(
                 logical_expr,
                 logical_low,
@@ -233,8 +323,8 @@ pub fn expr_to_logical_expr(expr: &Expr) -> Result<Expression, LogicalPlanError>
             pattern,
             ..
         } => {
-            let logical_expr = expr_to_logical_expr(expr)?;
-            let logical_pattern = expr_to_logical_expr(pattern)?;
+            let logical_expr = expr_to_logical_expr_with(expr, planner)?;
+            let logical_pattern = expr_to_logical_expr_with(pattern, planner)?;
             Ok(Expression::like(
                 logical_expr,
                 logical_pattern,
@@ -248,8 +338,8 @@ pub fn expr_to_logical_expr(expr: &Expr) -> Result<Expression, LogicalPlanError>
             pattern,
             ..
         } => {
-            let logical_expr = expr_to_logical_expr(expr)?;
-            let logical_pattern = expr_to_logical_expr(pattern)?;
+            let logical_expr = expr_to_logical_expr_with(expr, planner)?;
+            let logical_pattern = expr_to_logical_expr_with(pattern, planner)?;
             Ok(Expression::like(
                 logical_expr,
                 logical_pattern,
@@ -265,20 +355,20 @@ pub fn expr_to_logical_expr(expr: &Expr) -> Result<Expression, LogicalPlanError>
             else_result,
         } => {
             let operand_expr = if let Some(operand) = operand {
-                Some(Box::new(expr_to_logical_expr(operand)?))
+                Some(Box::new(expr_to_logical_expr_with(operand, planner)?))
             } else {
                 None
             };
 
             let mut when_clauses = Vec::new();
-            for (condition, result) in conditions.iter().zip(else_result.iter()) {
-                let when_expr = expr_to_logical_expr(&condition.condition)?;
-                let then_expr = expr_to_logical_expr(result)?;
+            for condition in conditions {
+                let when_expr = expr_to_logical_expr_with(&condition.condition, planner)?;
+                let then_expr = expr_to_logical_expr_with(&condition.result, planner)?;
                 when_clauses.push((when_expr, then_expr));
             }
 
             let else_clause = if let Some(else_result) = else_result {
-                Some(Box::new(expr_to_logical_expr(else_result)?))
+                Some(Box::new(expr_to_logical_expr_with(else_result, planner)?))
             } else {
                 None
             };
@@ -289,6 +379,76 @@ pub fn expr_to_logical_expr(expr: &Expr) -> Result<Expression, LogicalPlanError>
                 else_clause,
             })
         }
+        Expr::Subquery(query) => {
+            let subquery = single_column(planner.plan_subquery(query)?, "Scalar")?;
+            Ok(Expression::scalar_subquery(subquery))
+        }
+        Expr::Exists { subquery, negated } => Ok(Expression::exists(
+            planner.plan_subquery(subquery)?,
+            *negated,
+        )),
+        Expr::InSubquery {
+            expr,
+            subquery,
+            negated,
+        } => {
+            let logical_expr = expr_to_logical_expr_with(expr, planner)?;
+            let subquery = single_column(planner.plan_set_expr(subquery)?, "IN")?;
+            Ok(Expression::in_subquery(logical_expr, subquery, *negated))
+        }
+        Expr::Nested(expr) => expr_to_logical_expr_with(expr, planner),
+        // The special syntax forms of built-in functions become ordinary calls
+        Expr::Extract { field, expr, .. } => Ok(Expression::function(
+            "extract",
+            vec![
+                Expression::literal(Value::String(field.to_string().to_lowercase())),
+                expr_to_logical_expr_with(expr, planner)?,
+            ],
+        )),
+        Expr::Ceil { expr, field } => ceil_floor_to_logical_expr("ceil", expr, field, planner),
+        Expr::Floor { expr, field } => ceil_floor_to_logical_expr("floor", expr, field, planner),
+        Expr::Substring {
+            expr,
+            substring_from,
+            substring_for,
+            ..
+        } => {
+            let start = match substring_from {
+                Some(from) => expr_to_logical_expr_with(from, planner)?,
+                None => Expression::literal(Value::Integer(1)),
+            };
+            let mut args = vec![expr_to_logical_expr_with(expr, planner)?, start];
+            if let Some(length) = substring_for {
+                args.push(expr_to_logical_expr_with(length, planner)?);
+            }
+            Ok(Expression::function("substr", args))
+        }
+        Expr::Trim {
+            expr,
+            trim_where,
+            trim_what,
+            trim_characters,
+        } => {
+            let name = match trim_where {
+                Some(TrimWhereField::Leading) => "ltrim",
+                Some(TrimWhereField::Trailing) => "rtrim",
+                Some(TrimWhereField::Both) | None => "trim",
+            };
+            let mut args = vec![expr_to_logical_expr_with(expr, planner)?];
+            match (trim_what, trim_characters.as_deref()) {
+                (Some(what), _) => args.push(expr_to_logical_expr_with(what, planner)?),
+                (None, Some([characters])) => {
+                    args.push(expr_to_logical_expr_with(characters, planner)?)
+                }
+                (None, Some(_)) => {
+                    return Err(LogicalPlanError::UnsupportedOperation(
+                        "TRIM takes a single set of characters".to_string(),
+                    ));
+                }
+                (None, None) => {}
+            }
+            Ok(Expression::function(name, args))
+        }
         Expr::Wildcard(wildcard) => Ok(Expression::wildcard()),
         Expr::QualifiedWildcard(object_name, attached) => {
             let table_name = object_name_to_string(object_name);
@@ -301,8 +461,77 @@ pub fn expr_to_logical_expr(expr: &Expr) -> Result<Expression, LogicalPlanError>
     }
 }
 
+/// Scalar and `IN` subqueries must produce exactly one column
+fn single_column(subquery: LogicalPlan, kind: &str) -> Result<LogicalPlan, LogicalPlanError> {
+    let columns = subquery.schema().column_count();
+    if columns != 1 {
+        return Err(LogicalPlanError::ValidationError(format!(
+            "{} subquery must return exactly one column, got {}",
+            kind, columns
+        )));
+    }
+    Ok(subquery)
+}
+
+/// `CEIL(x)` / `FLOOR(x)`; the scale and `TO <field>` forms have no counterpart in the engine
+fn ceil_floor_to_logical_expr(
+    name: &str,
+    expr: &Expr,
+    field: &CeilFloorKind,
+    planner: &mut dyn ExprPlanner,
+) -> Result<Expression, LogicalPlanError> {
+    match field {
+        CeilFloorKind::DateTimeField(DateTimeField::NoDateTime) => Ok(Expression::function(
+            name,
+            vec![expr_to_logical_expr_with(expr, planner)?],
+        )),
+        _ => Err(LogicalPlanError::UnsupportedOperation(format!(
+            "Unsupported {} form: {:?}",
+            name.to_uppercase(),
+            field
+        ))),
+    }
+}
+
+/// Convert an ORDER BY item to a sort expression
+pub fn order_by_expr_to_sort_expr(order_expr: &OrderByExpr) -> Result<SortExpr, LogicalPlanError> {
+    order_by_expr_to_sort_expr_with(order_expr, &mut NoSubqueries)
+}
+
+/// Convert an ORDER BY item to a sort expression, planning any subqueries in it with `planner`
+pub fn order_by_expr_to_sort_expr_with(
+    order_expr: &OrderByExpr,
+    planner: &mut dyn ExprPlanner,
+) -> Result<SortExpr, LogicalPlanError> {
+    let expr = expr_to_logical_expr_with(&order_expr.expr, planner)?;
+
+    // one of the expressions' fields has a field of the same name: `options.`
+    let order = if order_expr.options.asc.unwrap_or(true) {
+        SortOrder::Ascending
+    } else {
+        SortOrder::Descending
+    };
+
+    Ok(match order_expr.options.nulls_first {
+        Some(true) => SortExpr::new(expr, order).nulls_first(),
+        Some(false) => SortExpr::new(expr, order).nulls_last(),
+        None => SortExpr::new(expr, order),
+    })
+}
+
 /// Convert SQL function to logical expression
 pub fn function_to_logical_expr(function: &Function) -> Result<Expression, LogicalPlanError> {
+    function_to_logical_expr_with(function, &mut NoSubqueries)
+}
+
+fn function_to_logical_expr_with(
+    function: &Function,
+    planner: &mut dyn ExprPlanner,
+) -> Result<Expression, LogicalPlanError> {
+    if let Some(over) = &function.over {
+        return window_function_to_logical_expr(function, over, planner);
+    }
+
     let function_name = object_name_to_string(&function.name);
 
     // Check if it's an aggregate function
@@ -319,10 +548,9 @@ pub fn function_to_logical_expr(function: &Function) -> Result<Expression, Logic
         // let distinct = function.distinct.is_some();
         let expr = match &function.args {
             FunctionArguments::None => None,
-            FunctionArguments::Subquery(_) => {
-                return Err(LogicalPlanError::UnsupportedOperation(
-                    "Subquery function arguments not supported".to_string(),
-                ));
+            FunctionArguments::Subquery(query) => {
+                let subquery = single_column(planner.plan_subquery(query)?, "Scalar")?;
+                Some(Expression::scalar_subquery(subquery))
             }
             FunctionArguments::List(args) => {
                 if args.args.is_empty() {
@@ -344,7 +572,7 @@ pub fn function_to_logical_expr(function: &Function) -> Result<Expression, Logic
                             ));
                         }
                     };
-                    Some(expr_to_logical_expr(arg)?)
+                    Some(expr_to_logical_expr_with(arg, planner)?)
                 }
             }
         };
@@ -352,64 +580,289 @@ pub fn function_to_logical_expr(function: &Function) -> Result<Expression, Logic
         Ok(Expression::aggregate(agg_func, expr, false))
     } else {
         // Regular function
-        let mut args = Vec::new();
-        match &function.args {
-            FunctionArguments::None => {}
-            FunctionArguments::Subquery(_) => {
-                return Err(LogicalPlanError::UnsupportedOperation(
-                    "Subquery function arguments not supported".to_string(),
-                ));
-            }
-            FunctionArguments::List(arg_list) => {
-                for arg in &arg_list.args {
-                    match arg {
-                        FunctionArg::Named { .. } => {
-                            return Err(LogicalPlanError::UnsupportedOperation(
-                                "Named function arguments not supported".to_string(),
-                            ));
-                        }
-                        FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => {
-                            args.push(expr_to_logical_expr(expr)?);
-                        }
-                        FunctionArg::Unnamed(FunctionArgExpr::Wildcard) => {
-                            args.push(Expression::wildcard());
-                        }
-                        _ => {
-                            return Err(LogicalPlanError::UnsupportedOperation(
-                                "Unsupported function argument".to_string(),
-                            ));
-                        }
+        let args = function_args_to_logical_exprs(function, planner)?;
+        Ok(Expression::function(function_name, args))
+    }
+}
+
+/// Convert the positional arguments of a function call
+fn function_args_to_logical_exprs(
+    function: &Function,
+    planner: &mut dyn ExprPlanner,
+) -> Result<Vec<Expression>, LogicalPlanError> {
+    let mut args = Vec::new();
+    match &function.args {
+        FunctionArguments::None => {}
+        // `f(SELECT ...)`: the subquery's value is the single argument
+        FunctionArguments::Subquery(query) => {
+            let subquery = single_column(planner.plan_subquery(query)?, "Scalar")?;
+            args.push(Expression::scalar_subquery(subquery));
+        }
+        FunctionArguments::List(arg_list) => {
+            for arg in &arg_list.args {
+                match arg {
+                    FunctionArg::Named { .. } => {
+                        return Err(LogicalPlanError::UnsupportedOperation(
+                            "Named function arguments not supported".to_string(),
+                        ));
+                    }
+                    FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => {
+                        args.push(expr_to_logical_expr_with(expr, planner)?);
+                    }
+                    FunctionArg::Unnamed(FunctionArgExpr::Wildcard) => {
+                        args.push(Expression::wildcard());
+                    }
+                    _ => {
+                        return Err(LogicalPlanError::UnsupportedOperation(
+                            "Unsupported function argument".to_string(),
+                        ));
                     }
                 }
             }
         }
+    }
+    Ok(args)
+}
+
+/// Convert a function call with an `OVER (...)` clause to a window expression
+fn window_function_to_logical_expr(
+    function: &Function,
+    over: &WindowType,
+    planner: &mut dyn ExprPlanner,
+) -> Result<Expression, LogicalPlanError> {
+    let spec = match over {
+        WindowType::WindowSpec(spec) if spec.window_name.is_none() => spec,
+        _ => {
+            return Err(LogicalPlanError::UnsupportedOperation(
+                "Named windows not supported".to_string(),
+            ));
+        }
+    };
 
-        Ok(Expression::function(function_name, args))
+    let function_name = object_name_to_string(&function.name);
+    let (func, args) = match function_name.to_lowercase().as_str() {
+        "row_number" => (
+            WindowFunction::RowNumber,
+            function_args_to_logical_exprs(function, planner)?,
+        ),
+        "rank" => (
+            WindowFunction::Rank,
+            function_args_to_logical_exprs(function, planner)?,
+        ),
+        "dense_rank" => (
+            WindowFunction::DenseRank,
+            function_args_to_logical_exprs(function, planner)?,
+        ),
+        "lag" => (
+            WindowFunction::Lag,
+            function_args_to_logical_exprs(function, planner)?,
+        ),
+        "lead" => (
+            WindowFunction::Lead,
+            function_args_to_logical_exprs(function, planner)?,
+        ),
+        "count" | "sum" | "avg" | "min" | "max" => {
+            let mut aggregate = function.clone();
+            aggregate.over = None;
+            match function_to_logical_expr_with(&aggregate, planner)? {
+                Expression::Aggregate { func, expr, .. } => (
+                    WindowFunction::Aggregate(func),
+                    expr.map(|expr| vec![*expr]).unwrap_or_default(),
+                ),
+                other => {
+                    return Err(LogicalPlanError::InternalError(format!(
+                        "Expected an aggregate, got {}",
+                        other
+                    )));
+                }
+            }
+        }
+        _ => {
+            return Err(LogicalPlanError::UnsupportedOperation(format!(
+                "Unsupported window function: {}",
+                function_name
+            )));
+        }
+    };
+
+    let arity = match func {
+        WindowFunction::RowNumber | WindowFunction::Rank | WindowFunction::DenseRank => 0..=0,
+        WindowFunction::Lag | WindowFunction::Lead => 1..=3,
+        WindowFunction::Aggregate(_) => 0..=1,
+    };
+    if !arity.contains(&args.len()) {
+        return Err(LogicalPlanError::InvalidExpression(format!(
+            "{} takes {} to {} arguments, got {}",
+            function_name,
+            arity.start(),
+            arity.end(),
+            args.len()
+        )));
     }
+
+    let partition_by = spec
+        .partition_by
+        .iter()
+        .map(|expr| expr_to_logical_expr_with(expr, planner))
+        .collect::<Result<Vec<_>, _>>()?;
+    let order_by = spec
+        .order_by
+        .iter()
+        .map(|expr| order_by_expr_to_sort_expr_with(expr, planner))
+        .collect::<Result<Vec<_>, _>>()?;
+    let frame = spec
+        .window_frame
+        .as_ref()
+        .map(|frame| window_frame_to_frame(frame, order_by.len()))
+        .transpose()?;
+
+    Ok(Expression::window(
+        func,
+        args,
+        partition_by,
+        order_by,
+        frame,
+    ))
+}
+
+/// Convert a `ROWS | RANGE` frame clause, checking its bounds are in order
+fn window_frame_to_frame(
+    frame: &SqlWindowFrame,
+    order_by_len: usize,
+) -> Result<WindowFrame, LogicalPlanError> {
+    let units = match frame.units {
+        SqlWindowFrameUnits::Rows => WindowFrameUnits::Rows,
+        SqlWindowFrameUnits::Range => WindowFrameUnits::Range,
+        SqlWindowFrameUnits::Groups => {
+            return Err(LogicalPlanError::UnsupportedOperation(
+                "GROUPS window frames not supported".to_string(),
+            ));
+        }
+    };
+    let start = window_frame_bound(&frame.start_bound)?;
+    // `ROWS 2 PRECEDING` is short for `ROWS BETWEEN 2 PRECEDING AND CURRENT ROW`
+    let end = match &frame.end_bound {
+        Some(bound) => window_frame_bound(bound)?,
+        None => WindowFrameBound::CurrentRow,
+    };
+
+    if start == WindowFrameBound::UnboundedFollowing || end == WindowFrameBound::UnboundedPreceding
+    {
+        return Err(LogicalPlanError::ValidationError(format!(
+            "Invalid window frame: BETWEEN {} AND {}",
+            start, end
+        )));
+    }
+    let has_offset = |bound: &WindowFrameBound| {
+        matches!(
+            bound,
+            WindowFrameBound::Preceding(_) | WindowFrameBound::Following(_)
+        )
+    };
+    if units == WindowFrameUnits::Range
+        && (has_offset(&start) || has_offset(&end))
+        && order_by_len != 1
+    {
+        return Err(LogicalPlanError::ValidationError(
+            "RANGE with an offset requires exactly one ORDER BY expression".to_string(),
+        ));
+    }
+
+    Ok(WindowFrame::new(units, start, end))
+}
+
+fn window_frame_bound(bound: &SqlWindowFrameBound) -> Result<WindowFrameBound, LogicalPlanError> {
+    let offset = |expr: &Expr| match expr {
+        Expr::Value(value) => match &value.value {
+            SqlValue::Number(n, _) => n.parse::<u64>().map_err(|_| {
+                LogicalPlanError::SqlParseError(format!("Invalid window frame offset: {}", n))
+            }),
+            _ => Err(LogicalPlanError::UnsupportedOperation(
+                "Window frame offset must be a number".to_string(),
+            )),
+        },
+        _ => Err(LogicalPlanError::UnsupportedOperation(
+            "Window frame offset must be a number".to_string(),
+        )),
+    };
+    Ok(match bound {
+        SqlWindowFrameBound::CurrentRow => WindowFrameBound::CurrentRow,
+        SqlWindowFrameBound::Preceding(Some(expr)) => WindowFrameBound::Preceding(offset(expr)?),
+        SqlWindowFrameBound::Following(Some(expr)) => WindowFrameBound::Following(offset(expr)?),
+        SqlWindowFrameBound::Preceding(None) => WindowFrameBound::UnboundedPreceding,
+        SqlWindowFrameBound::Following(None) => WindowFrameBound::UnboundedFollowing,
+    })
 }
 
 /// Convert VALUES clause to a logical plan
 pub fn values_to_plan(values: &Values) -> Result<LogicalPlan, LogicalPlanError> {
-    let mut value_rows = Vec::new();
-
+    let mut typed_rows = Vec::new();
     for row in &values.rows {
-        let mut value_row = Vec::new();
+        let mut typed_row = Vec::new();
         for expr in row {
-            value_row.push(expr_to_logical_expr(expr)?);
+            typed_row.push(coerce_expression(
+                expr_to_logical_expr(expr)?,
+                &LogicalSchema::empty(),
+            )?);
         }
-        value_rows.push(value_row);
+        typed_rows.push(typed_row);
+    }
+
+    let width = typed_rows.first().map_or(0, Vec::len);
+    if typed_rows.iter().any(|row| row.len() != width) {
+        return Err(LogicalPlanError::ValidationError(
+            "VALUES lists must all be the same length".to_string(),
+        ));
     }
 
-    // Infer schema from first row
-    let schema = if let Some(first_row) = value_rows.first() {
-        let mut columns = Vec::new();
-        for (i, _) in first_row.iter().enumerate() {
-            columns.push(ColumnDef::new(format!("column_{}", i), DataType::String));
+    // Each column takes the common type of its values. String literals take the type
+    // of the others, so `VALUES (DATE '2024-01-01'), ('2024-01-31')` is a DATE column.
+    let mut columns = Vec::with_capacity(width);
+    for i in 0..width {
+        let mut column_type: Option<DataType> = None;
+        let mut has_string = false;
+        for row in &typed_rows {
+            match &row[i] {
+                (Expression::Literal(Value::String(_)), _) => has_string = true,
+                (_, Some(data_type)) => {
+                    column_type = Some(match column_type {
+                        Some(current) => common_type(&current, data_type).ok_or_else(|| {
+                            LogicalPlanError::TypeMismatch {
+                                expected: format!("{:?} in VALUES column {}", current, i + 1),
+                                found: format!("{:?}", data_type),
+                            }
+                        })?,
+                        None => data_type.clone(),
+                    });
+                }
+                (_, None) => {}
+            }
         }
-        LogicalSchema::new(columns)
-    } else {
-        LogicalSchema::empty()
-    };
+        if column_type.is_none() && has_string {
+            column_type = Some(DataType::String);
+        }
+        columns.push(ColumnDef::new(
+            format!("column_{}", i),
+            column_type.unwrap_or(DataType::String),
+        ));
+    }
+
+    let mut value_rows = Vec::with_capacity(typed_rows.len());
+    for row in typed_rows {
+        let mut value_row = Vec::with_capacity(width);
+        for ((expr, data_type), column) in row.into_iter().zip(&columns) {
+            let context = format!("VALUES column {}", column.name);
+            value_row.push(coerce_to(
+                expr,
+                data_type.as_ref(),
+                &column.data_type,
+                &context,
+            )?);
+        }
+        value_rows.push(value_row);
+    }
+
+    let schema = LogicalSchema::new(columns);
 
     Ok(LogicalPlan::Values(ValuesNode {
         values: value_rows,
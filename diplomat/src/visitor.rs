@@ -0,0 +1,421 @@
+//! Generic traversal and rewriting of logical plans and expressions.
+//!
+//! `TreeNode` gives both trees the same API: `apply` and `visit` walk a tree,
+//! `transform_down`, `transform_up` and `rewrite` rebuild it. The children of a plan
+//! are its input plans, those of an expression its operands. Subqueries sit in
+//! between: they are reached from a plan through `LogicalPlan::apply_subqueries` and
+//! `LogicalPlan::map_subqueries`, or the `_with_subqueries` traversals.
+
+use shared_types::Value;
+
+use crate::{
+    common::LogicalPlanError,
+    expression::Expression,
+    logical_plan::{LogicalPlan, ValuesNode},
+    types::{LogicalSchema, PlanStatistics},
+};
+
+/// How a traversal goes on after visiting a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recursion {
+    /// Visit the children of the node, then go on
+    Continue,
+    /// Skip the children of the node, but go on with the rest of the tree
+    Jump,
+    /// End the traversal
+    Stop,
+}
+
+/// A node returned by a transformation, and whether the transformation changed it or
+/// anything below it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transformed<T> {
+    pub data: T,
+    pub transformed: bool,
+}
+
+impl<T> Transformed<T> {
+    pub fn new(data: T, transformed: bool) -> Self {
+        Self { data, transformed }
+    }
+
+    pub fn yes(data: T) -> Self {
+        Self::new(data, true)
+    }
+
+    pub fn no(data: T) -> Self {
+        Self::new(data, false)
+    }
+
+    /// Transform the data further with `f`, keeping track of whether either step
+    /// changed it
+    pub fn update<U>(
+        self,
+        f: impl FnOnce(T) -> Result<Transformed<U>, LogicalPlanError>,
+    ) -> Result<Transformed<U>, LogicalPlanError> {
+        let next = f(self.data)?;
+        Ok(Transformed::new(
+            next.data,
+            self.transformed || next.transformed,
+        ))
+    }
+
+    /// Replace the data with `f` of it, without changing the flag
+    pub fn map_data<U>(
+        self,
+        f: impl FnOnce(T) -> Result<U, LogicalPlanError>,
+    ) -> Result<Transformed<U>, LogicalPlanError> {
+        Ok(Transformed::new(f(self.data)?, self.transformed))
+    }
+}
+
+/// Callbacks of `TreeNode::visit`
+pub trait TreeNodeVisitor<N> {
+    /// Called on a node before its children
+    fn pre_visit(&mut self, node: &N) -> Result<Recursion, LogicalPlanError>;
+
+    /// Called on a node after its children, unless the traversal was stopped
+    fn post_visit(&mut self, _node: &N) -> Result<Recursion, LogicalPlanError> {
+        Ok(Recursion::Continue)
+    }
+}
+
+/// Callbacks of `TreeNode::rewrite`
+pub trait TreeNodeRewriter<N> {
+    /// Called on a node before its children are rewritten. `Jump` leaves the node's
+    /// children as they are, `Stop` leaves the rest of the tree as it is.
+    fn pre_visit(&mut self, _node: &N) -> Result<Recursion, LogicalPlanError> {
+        Ok(Recursion::Continue)
+    }
+
+    /// Rewrite a node whose children were already rewritten
+    fn mutate(&mut self, node: N) -> Result<Transformed<N>, LogicalPlanError>;
+}
+
+pub trait TreeNode: Sized {
+    /// Call `f` on every child of this node, in order, until it returns `Stop`
+    fn apply_children<'n>(
+        &'n self,
+        f: &mut dyn FnMut(&'n Self) -> Result<Recursion, LogicalPlanError>,
+    ) -> Result<Recursion, LogicalPlanError>;
+
+    /// Replace every child of this node with what `f` returns for it
+    fn map_children(
+        self,
+        f: &mut dyn FnMut(Self) -> Result<Transformed<Self>, LogicalPlanError>,
+    ) -> Result<Transformed<Self>, LogicalPlanError>;
+
+    /// Call `f` on every node of the tree, parents before their children
+    fn apply<'n, F>(&'n self, mut f: F) -> Result<Recursion, LogicalPlanError>
+    where
+        F: FnMut(&'n Self) -> Result<Recursion, LogicalPlanError>,
+    {
+        apply_impl(self, &mut f)
+    }
+
+    /// Walk the tree with `visitor`, calling `pre_visit` on every node before its
+    /// children and `post_visit` after them
+    fn visit<V: TreeNodeVisitor<Self>>(
+        &self,
+        visitor: &mut V,
+    ) -> Result<Recursion, LogicalPlanError> {
+        match visitor.pre_visit(self)? {
+            Recursion::Continue => {}
+            Recursion::Jump => return visitor.post_visit(self),
+            Recursion::Stop => return Ok(Recursion::Stop),
+        }
+        match self.apply_children(&mut |child| child.visit(visitor))? {
+            Recursion::Stop => Ok(Recursion::Stop),
+            _ => visitor.post_visit(self),
+        }
+    }
+
+    /// Whether `predicate` holds for any node of the tree
+    fn exists(&self, mut predicate: impl FnMut(&Self) -> bool) -> bool {
+        let mut found = false;
+        let walked = self.apply(|node| {
+            if predicate(node) {
+                found = true;
+                Ok(Recursion::Stop)
+            } else {
+                Ok(Recursion::Continue)
+            }
+        });
+        walked.is_ok() && found
+    }
+
+    /// Rebuild the tree with `f`, applied to every node before its children. The
+    /// children visited are those of the node `f` returns.
+    fn transform_down<F>(self, mut f: F) -> Result<Transformed<Self>, LogicalPlanError>
+    where
+        F: FnMut(Self) -> Result<Transformed<Self>, LogicalPlanError>,
+    {
+        transform_down_impl(self, &mut f)
+    }
+
+    /// Rebuild the tree with `f`, applied to every node after its children
+    fn transform_up<F>(self, mut f: F) -> Result<Transformed<Self>, LogicalPlanError>
+    where
+        F: FnMut(Self) -> Result<Transformed<Self>, LogicalPlanError>,
+    {
+        transform_up_impl(self, &mut f)
+    }
+
+    /// Rebuild the tree with `rewriter`, calling `pre_visit` on every node on the
+    /// way down and `mutate` on the way up
+    fn rewrite<R: TreeNodeRewriter<Self>>(
+        self,
+        rewriter: &mut R,
+    ) -> Result<Transformed<Self>, LogicalPlanError> {
+        rewrite_impl(self, rewriter).map(|(node, _)| node)
+    }
+}
+
+fn apply_impl<'n, N: TreeNode>(
+    node: &'n N,
+    f: &mut dyn FnMut(&'n N) -> Result<Recursion, LogicalPlanError>,
+) -> Result<Recursion, LogicalPlanError> {
+    match f(node)? {
+        Recursion::Continue => node.apply_children(&mut |child| apply_impl(child, f)),
+        Recursion::Jump => Ok(Recursion::Continue),
+        Recursion::Stop => Ok(Recursion::Stop),
+    }
+}
+
+fn transform_down_impl<N: TreeNode>(
+    node: N,
+    f: &mut dyn FnMut(N) -> Result<Transformed<N>, LogicalPlanError>,
+) -> Result<Transformed<N>, LogicalPlanError> {
+    f(node)?.update(|node| node.map_children(&mut |child| transform_down_impl(child, f)))
+}
+
+fn transform_up_impl<N: TreeNode>(
+    node: N,
+    f: &mut dyn FnMut(N) -> Result<Transformed<N>, LogicalPlanError>,
+) -> Result<Transformed<N>, LogicalPlanError> {
+    node.map_children(&mut |child| transform_up_impl(child, f))?
+        .update(f)
+}
+
+/// The rewritten node, and whether the rewriter stopped the traversal
+fn rewrite_impl<N: TreeNode, R: TreeNodeRewriter<N>>(
+    node: N,
+    rewriter: &mut R,
+) -> Result<(Transformed<N>, bool), LogicalPlanError> {
+    match rewriter.pre_visit(&node)? {
+        Recursion::Stop => return Ok((Transformed::no(node), true)),
+        Recursion::Jump => return Ok((rewriter.mutate(node)?, false)),
+        Recursion::Continue => {}
+    }
+    let mut stopped = false;
+    let node = node.map_children(&mut |child| {
+        if stopped {
+            return Ok(Transformed::no(child));
+        }
+        let (child, stop) = rewrite_impl(child, rewriter)?;
+        stopped = stop;
+        Ok(child)
+    })?;
+    if stopped {
+        return Ok((node, true));
+    }
+    Ok((node.update(|node| rewriter.mutate(node))?, false))
+}
+
+impl TreeNode for LogicalPlan {
+    fn apply_children<'n>(
+        &'n self,
+        f: &mut dyn FnMut(&'n Self) -> Result<Recursion, LogicalPlanError>,
+    ) -> Result<Recursion, LogicalPlanError> {
+        for child in self.children() {
+            if f(child)? == Recursion::Stop {
+                return Ok(Recursion::Stop);
+            }
+        }
+        Ok(Recursion::Continue)
+    }
+
+    fn map_children(
+        mut self,
+        f: &mut dyn FnMut(Self) -> Result<Transformed<Self>, LogicalPlanError>,
+    ) -> Result<Transformed<Self>, LogicalPlanError> {
+        let mut transformed = false;
+        for child in self.children_mut() {
+            let new_child = f(std::mem::replace(child, empty_plan()))?;
+            transformed |= new_child.transformed;
+            *child = new_child.data;
+        }
+        Ok(Transformed::new(self, transformed))
+    }
+}
+
+impl TreeNode for Expression {
+    fn apply_children<'n>(
+        &'n self,
+        f: &mut dyn FnMut(&'n Self) -> Result<Recursion, LogicalPlanError>,
+    ) -> Result<Recursion, LogicalPlanError> {
+        for child in self.children() {
+            if f(child)? == Recursion::Stop {
+                return Ok(Recursion::Stop);
+            }
+        }
+        Ok(Recursion::Continue)
+    }
+
+    fn map_children(
+        mut self,
+        f: &mut dyn FnMut(Self) -> Result<Transformed<Self>, LogicalPlanError>,
+    ) -> Result<Transformed<Self>, LogicalPlanError> {
+        let mut transformed = false;
+        for child in self.children_mut() {
+            let new_child = f(std::mem::replace(child, Expression::Literal(Value::Null)))?;
+            transformed |= new_child.transformed;
+            *child = new_child.data;
+        }
+        Ok(Transformed::new(self, transformed))
+    }
+}
+
+impl LogicalPlan {
+    /// Call `f` on every expression this node holds itself, until it returns `Stop`
+    pub fn apply_expressions(
+        &self,
+        mut f: impl FnMut(&Expression) -> Result<Recursion, LogicalPlanError>,
+    ) -> Result<Recursion, LogicalPlanError> {
+        for expr in self.expressions() {
+            if f(expr)? == Recursion::Stop {
+                return Ok(Recursion::Stop);
+            }
+        }
+        Ok(Recursion::Continue)
+    }
+
+    /// Replace every expression this node holds itself with what `f` returns for it
+    pub fn map_expressions(
+        mut self,
+        mut f: impl FnMut(Expression) -> Result<Transformed<Expression>, LogicalPlanError>,
+    ) -> Result<Transformed<Self>, LogicalPlanError> {
+        let mut transformed = false;
+        for expr in self.expressions_mut() {
+            let new_expr = f(std::mem::replace(expr, Expression::Literal(Value::Null)))?;
+            transformed |= new_expr.transformed;
+            *expr = new_expr.data;
+        }
+        Ok(Transformed::new(self, transformed))
+    }
+
+    /// Call `f` on the plan of every subquery in the expressions of this node, until
+    /// it returns `Stop`. Subqueries nested in those plans are left to them.
+    pub fn apply_subqueries(
+        &self,
+        mut f: impl FnMut(&LogicalPlan) -> Result<Recursion, LogicalPlanError>,
+    ) -> Result<Recursion, LogicalPlanError> {
+        self.apply_expressions(|expr| {
+            expr.apply(|expr| match expr {
+                Expression::Subquery { subquery }
+                | Expression::Exists { subquery, .. }
+                | Expression::InSubquery { subquery, .. } => match f(subquery)? {
+                    Recursion::Stop => Ok(Recursion::Stop),
+                    _ => Ok(Recursion::Continue),
+                },
+                _ => Ok(Recursion::Continue),
+            })
+        })
+    }
+
+    /// Replace the plan of every subquery in the expressions of this node with what
+    /// `f` returns for it
+    pub fn map_subqueries(
+        mut self,
+        mut f: impl FnMut(LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError>,
+    ) -> Result<Transformed<Self>, LogicalPlanError> {
+        let mut transformed = false;
+        for expr in self.expressions_mut() {
+            for subquery in expr.subquery_plans_mut() {
+                let new_plan = f(std::mem::replace(subquery, empty_plan()))?;
+                transformed |= new_plan.transformed;
+                *subquery = new_plan.data;
+            }
+        }
+        Ok(Transformed::new(self, transformed))
+    }
+
+    /// `apply` that also walks the plans of subqueries, after the node holding them
+    pub fn apply_with_subqueries(
+        &self,
+        mut f: impl FnMut(&LogicalPlan) -> Result<Recursion, LogicalPlanError>,
+    ) -> Result<Recursion, LogicalPlanError> {
+        apply_with_subqueries_impl(self, &mut f)
+    }
+
+    /// `transform_down` that also rewrites the plans of subqueries, after the node
+    /// holding them
+    pub fn transform_down_with_subqueries(
+        self,
+        mut f: impl FnMut(LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError>,
+    ) -> Result<Transformed<Self>, LogicalPlanError> {
+        transform_down_with_subqueries_impl(self, &mut f)
+    }
+
+    /// `transform_up` that also rewrites the plans of subqueries, before the node
+    /// holding them
+    pub fn transform_up_with_subqueries(
+        self,
+        mut f: impl FnMut(LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError>,
+    ) -> Result<Transformed<Self>, LogicalPlanError> {
+        transform_up_with_subqueries_impl(self, &mut f)
+    }
+}
+
+type PlanVisit<'a> = dyn FnMut(&LogicalPlan) -> Result<Recursion, LogicalPlanError> + 'a;
+type PlanTransform<'a> =
+    dyn FnMut(LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> + 'a;
+
+fn apply_with_subqueries_impl(
+    plan: &LogicalPlan,
+    f: &mut PlanVisit,
+) -> Result<Recursion, LogicalPlanError> {
+    match f(plan)? {
+        Recursion::Continue => {}
+        Recursion::Jump => return Ok(Recursion::Continue),
+        Recursion::Stop => return Ok(Recursion::Stop),
+    }
+    if plan.apply_subqueries(|subquery| apply_with_subqueries_impl(subquery, f))? == Recursion::Stop
+    {
+        return Ok(Recursion::Stop);
+    }
+    plan.apply_children(&mut |child| apply_with_subqueries_impl(child, f))
+}
+
+fn transform_down_with_subqueries_impl(
+    plan: LogicalPlan,
+    f: &mut PlanTransform,
+) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+    f(plan)?
+        .update(|plan| {
+            plan.map_subqueries(|subquery| transform_down_with_subqueries_impl(subquery, f))
+        })?
+        .update(|plan| {
+            plan.map_children(&mut |child| transform_down_with_subqueries_impl(child, f))
+        })
+}
+
+fn transform_up_with_subqueries_impl(
+    plan: LogicalPlan,
+    f: &mut PlanTransform,
+) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+    plan.map_children(&mut |child| transform_up_with_subqueries_impl(child, f))?
+        .update(|plan| {
+            plan.map_subqueries(|subquery| transform_up_with_subqueries_impl(subquery, f))
+        })?
+        .update(f)
+}
+
+/// Stands in for a child plan while it is being transformed
+fn empty_plan() -> LogicalPlan {
+    LogicalPlan::Values(ValuesNode {
+        values: Vec::new(),
+        schema: LogicalSchema::empty(),
+        statistics: PlanStatistics::unknown(),
+    })
+}
@@ -0,0 +1,190 @@
+use diplomat::{
+    common::LogicalPlanError, expression::Expression, logical_plan::LogicalPlan,
+    plan_builder::PlanBuilder, types::ColumnRef,
+};
+use matan::manager::CatalogManager;
+use shared_types::{Column, DataType, Schema};
+use sqlparser::{dialect::GenericDialect, parser::Parser};
+use tempfile::TempDir;
+
+fn test_catalog() -> (TempDir, CatalogManager) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut catalog =
+        CatalogManager::new(dir.path().join("catalog.db"), "shop".to_string()).unwrap();
+    catalog
+        .create_table(
+            "customers".to_string(),
+            Schema::new(vec![
+                Column::primary_key("id".to_string(), DataType::Integer),
+                Column::nullable("name".to_string(), DataType::String),
+                Column::nullable("region".to_string(), DataType::Integer),
+            ]),
+            "customers.db".to_string(),
+        )
+        .unwrap();
+    catalog
+        .create_table(
+            "orders".to_string(),
+            Schema::new(vec![
+                Column::primary_key("id".to_string(), DataType::Integer),
+                Column::nullable("customer_id".to_string(), DataType::Integer),
+                Column::nullable("amount".to_string(), DataType::Float),
+            ]),
+            "orders.db".to_string(),
+        )
+        .unwrap();
+    (dir, catalog)
+}
+
+fn bind(sql: &str) -> Result<LogicalPlan, LogicalPlanError> {
+    let (_dir, catalog) = test_catalog();
+    let statement = Parser::parse_sql(&GenericDialect {}, sql)
+        .unwrap()
+        .remove(0);
+    PlanBuilder::from_catalog(&catalog).generate(&statement)
+}
+
+/// Every column reference in the plan and its subqueries
+fn column_refs(plan: &LogicalPlan) -> Vec<ColumnRef> {
+    let mut refs = Vec::new();
+    plan_column_refs(plan, &mut refs);
+    refs
+}
+
+fn plan_column_refs(plan: &LogicalPlan, refs: &mut Vec<ColumnRef>) {
+    let exprs: Vec<&Expression> = match plan {
+        LogicalPlan::Projection(projection) => projection.expressions.iter().collect(),
+        LogicalPlan::Filter(filter) => vec![&filter.predicate],
+        LogicalPlan::Join(join) => join.join_constraint.iter().collect(),
+        LogicalPlan::TableScan(scan) => scan.filters.iter().collect(),
+        _ => Vec::new(),
+    };
+    for expr in exprs {
+        expr_column_refs(expr, refs);
+    }
+    for child in plan.children() {
+        plan_column_refs(child, refs);
+    }
+}
+
+fn expr_column_refs(expr: &Expression, refs: &mut Vec<ColumnRef>) {
+    match expr {
+        Expression::Column(column) => refs.push(column.clone()),
+        Expression::BinaryOp { left, right, .. } => {
+            expr_column_refs(left, refs);
+            expr_column_refs(right, refs);
+        }
+        Expression::UnaryOp { expr, .. } | Expression::Alias { expr, .. } => {
+            expr_column_refs(expr, refs)
+        }
+        Expression::Exists { subquery, .. } | Expression::Subquery { subquery } => {
+            plan_column_refs(subquery, refs)
+        }
+        Expression::InSubquery { expr, subquery, .. } => {
+            expr_column_refs(expr, refs);
+            plan_column_refs(subquery, refs);
+        }
+        _ => {}
+    }
+}
+
+fn index_of(refs: &[ColumnRef], qualified_name: &str) -> Option<usize> {
+    refs.iter()
+        .find(|column| column.qualified_name() == qualified_name)
+        .unwrap_or_else(|| panic!("no reference to {}", qualified_name))
+        .index
+}
+
+#[test]
+fn test_unknown_names_are_reported_with_positions() {
+    match bind("SELECT id FROM missing") {
+        Err(LogicalPlanError::TableNotFound(message)) => {
+            assert_eq!(message, "missing at line 1, column 16")
+        }
+        other => panic!("expected an unknown table, got {:?}", other),
+    }
+    match bind("SELECT nope FROM customers") {
+        Err(LogicalPlanError::ColumnNotFound(message)) => {
+            assert_eq!(message, "nope at line 1, column 8")
+        }
+        other => panic!("expected an unknown column, got {:?}", other),
+    }
+    // An alias that isn't in the FROM clause
+    match bind("SELECT x.id FROM customers c") {
+        Err(LogicalPlanError::ColumnNotFound(message)) => {
+            assert_eq!(message, "x.id at line 1, column 10")
+        }
+        other => panic!("expected an unknown column, got {:?}", other),
+    }
+    match bind("SELECT name FROM customers WHERE\n  amount > 1") {
+        Err(LogicalPlanError::ColumnNotFound(message)) => {
+            assert_eq!(message, "amount at line 2, column 3")
+        }
+        other => panic!("expected an unknown column, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_ambiguous_column_across_tables() {
+    match bind("SELECT id FROM customers c, orders o WHERE c.id = o.customer_id") {
+        Err(LogicalPlanError::AmbiguousColumn(message)) => {
+            assert_eq!(
+                message,
+                "id at line 1, column 8 could refer to c.id or o.id"
+            )
+        }
+        other => panic!("expected an ambiguous column, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_columns_bind_to_positions_in_the_from_row() {
+    let plan = bind("SELECT o.amount, name FROM customers c, orders o WHERE c.id = o.customer_id")
+        .unwrap();
+    let refs = column_refs(&plan);
+    assert_eq!(index_of(&refs, "o.amount"), Some(5));
+    // Unqualified names are qualified by the table they resolved to
+    assert_eq!(index_of(&refs, "c.name"), Some(1));
+    assert_eq!(index_of(&refs, "c.id"), Some(0));
+    assert_eq!(index_of(&refs, "o.customer_id"), Some(4));
+}
+
+#[test]
+fn test_correlated_references_stay_unbound() {
+    let plan = bind(
+        "SELECT id FROM customers c WHERE EXISTS \
+         (SELECT 1 FROM orders o WHERE o.customer_id = c.id AND region > 1)",
+    )
+    .unwrap();
+    let refs = column_refs(&plan);
+    assert_eq!(index_of(&refs, "o.customer_id"), Some(1));
+    // Outer columns, qualified or not, are correlated and have no position in the subquery
+    let outer: Vec<&ColumnRef> = refs
+        .iter()
+        .filter(|column| column.index.is_none())
+        .collect();
+    let outer_names: Vec<String> = outer.iter().map(|column| column.qualified_name()).collect();
+    assert_eq!(outer_names, vec!["c.id", "c.region"]);
+
+    // The innermost FROM clause wins over the outer query
+    let plan = bind(
+        "SELECT id FROM customers c WHERE EXISTS \
+         (SELECT 1 FROM orders o WHERE customer_id = id)",
+    )
+    .unwrap();
+    assert!(
+        column_refs(&plan)
+            .iter()
+            .all(|column| column.index.is_some())
+    );
+
+    match bind(
+        "SELECT id FROM customers c WHERE EXISTS \
+         (SELECT 1 FROM orders o WHERE o.customer_id = c.nope)",
+    ) {
+        Err(LogicalPlanError::ColumnNotFound(message)) => {
+            assert_eq!(message, "c.nope at line 1, column 89")
+        }
+        other => panic!("expected an unknown column, got {:?}", other),
+    }
+}
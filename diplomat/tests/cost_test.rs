@@ -0,0 +1,412 @@
+use diplomat::{
+    logical_plan::{JoinNode, LogicalPlan, TableScanNode},
+    optimizer::{CostModel, Optimizer},
+    plan_builder::PlanBuilder,
+    types::{
+        BuildSide, ColumnDef, ColumnStatistics, JoinAlgorithm, JoinStrategy, JoinType,
+        LogicalSchema, PlanStatistics,
+    },
+};
+use shared_types::{DataType, Value};
+use sqlparser::{dialect::GenericDialect, parser::Parser};
+
+fn schema(columns: &[&str]) -> LogicalSchema {
+    LogicalSchema::new(
+        columns
+            .iter()
+            .map(|name| ColumnDef::new(*name, DataType::Integer))
+            .collect(),
+    )
+}
+
+fn table(rows: usize, columns: Vec<(&str, ColumnStatistics)>) -> PlanStatistics {
+    PlanStatistics {
+        row_count: Some(rows),
+        size_bytes: None,
+        cost: None,
+        column_stats: columns
+            .into_iter()
+            .map(|(name, stats)| (name.to_string(), stats))
+            .collect(),
+    }
+}
+
+fn distinct(count: usize) -> ColumnStatistics {
+    ColumnStatistics {
+        distinct_count: Some(count),
+        null_count: Some(0),
+        ..ColumnStatistics::unknown()
+    }
+}
+
+fn plan(optimizer: Optimizer, tables: &[(&str, &[&str])], sql: &str) -> LogicalPlan {
+    let statement = Parser::parse_sql(&GenericDialect {}, sql)
+        .unwrap()
+        .remove(0);
+    let mut builder = tables
+        .iter()
+        .fold(PlanBuilder::new(), |builder, (name, columns)| {
+            builder.with_table_schema(name.to_string(), schema(columns))
+        });
+    let plan = builder.generate(&statement).unwrap();
+    optimizer.optimize(plan).unwrap()
+}
+
+/// `plan` and the nodes below it, parents first
+fn nodes(plan: &LogicalPlan) -> Vec<&LogicalPlan> {
+    let mut nodes = vec![plan];
+    for child in plan.children() {
+        nodes.extend(self::nodes(child));
+    }
+    nodes
+}
+
+fn scans(plan: &LogicalPlan) -> Vec<&TableScanNode> {
+    nodes(plan)
+        .into_iter()
+        .filter_map(|node| match node {
+            LogicalPlan::TableScan(scan) => Some(scan),
+            _ => None,
+        })
+        .collect()
+}
+
+fn joins(plan: &LogicalPlan) -> Vec<&JoinNode> {
+    nodes(plan)
+        .into_iter()
+        .filter_map(|node| match node {
+            LogicalPlan::Join(join) => Some(join),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Names of the tables under `plan`, in scan order
+fn tables(plan: &LogicalPlan) -> Vec<String> {
+    scans(plan)
+        .into_iter()
+        .map(|scan| scan.table.effective_name().to_string())
+        .collect()
+}
+
+/// Estimated rows of the single scan of `sql` over `t(a, b, c)`
+fn scan_rows(sql: &str) -> usize {
+    let mut common_a = distinct(10);
+    common_a.most_common_values = Some(vec![(Value::Integer(1), 0.5)]);
+    // Nine tenths of the values of b are below 10, though it ranges up to 100
+    let skewed_b = ColumnStatistics {
+        min_value: Some(Value::Integer(0)),
+        max_value: Some(Value::Integer(100)),
+        histogram: Some(
+            [0, 3, 6, 8, 9, 10, 20, 40, 60, 80, 100]
+                .map(Value::Integer)
+                .to_vec(),
+        ),
+        ..distinct(100)
+    };
+    let nullable_c = ColumnStatistics {
+        null_count: Some(200),
+        ..distinct(10)
+    };
+    let optimizer = Optimizer::new().with_table_statistics(
+        "t",
+        table(
+            1000,
+            vec![("a", common_a), ("b", skewed_b), ("c", nullable_c)],
+        ),
+    );
+    let plan = plan(optimizer, &[("t", &["a", "b", "c"])], sql);
+    let scans = scans(&plan);
+    assert_eq!(scans.len(), 1);
+    assert!(!scans[0].filters.is_empty(), "{} was not pushed", sql);
+    scans[0].statistics.row_count.unwrap()
+}
+
+#[test]
+fn test_selectivity_from_most_common_values() {
+    assert_eq!(scan_rows("SELECT * FROM t WHERE a = 1"), 500);
+    // The other half of the rows is spread over the other nine values
+    assert_eq!(scan_rows("SELECT * FROM t WHERE a = 2"), 56);
+    assert_eq!(scan_rows("SELECT * FROM t WHERE a <> 1"), 500);
+    assert_eq!(scan_rows("SELECT * FROM t WHERE a IN (1, 2)"), 556);
+}
+
+#[test]
+fn test_selectivity_from_histogram() {
+    assert_eq!(scan_rows("SELECT * FROM t WHERE b < 10"), 500);
+    assert_eq!(scan_rows("SELECT * FROM t WHERE b >= 10"), 500);
+    assert_eq!(scan_rows("SELECT * FROM t WHERE b < 30"), 650);
+    assert_eq!(scan_rows("SELECT * FROM t WHERE 30 > b"), 650);
+    assert_eq!(scan_rows("SELECT * FROM t WHERE b BETWEEN 10 AND 30"), 160);
+    // Out of the range of the column
+    assert_eq!(scan_rows("SELECT * FROM t WHERE b > 100"), 0);
+    assert_eq!(scan_rows("SELECT * FROM t WHERE b = 500"), 0);
+}
+
+#[test]
+fn test_selectivity_from_null_fraction() {
+    assert_eq!(scan_rows("SELECT * FROM t WHERE c IS NULL"), 200);
+    assert_eq!(scan_rows("SELECT * FROM t WHERE c IS NOT NULL"), 800);
+    // NULL never equals anything, so only the non-null rows can match
+    assert_eq!(scan_rows("SELECT * FROM t WHERE c = 7"), 80);
+    assert_eq!(scan_rows("SELECT * FROM t WHERE c <> 7"), 720);
+    assert_eq!(scan_rows("SELECT * FROM t WHERE c IS NULL OR a = 1"), 600);
+}
+
+fn customers_and_orders(customers: usize, orders: usize) -> Optimizer {
+    Optimizer::new()
+        .with_table_statistics(
+            "customers",
+            table(
+                customers,
+                vec![("id", distinct(customers)), ("region", distinct(5))],
+            ),
+        )
+        .with_table_statistics(
+            "orders",
+            table(
+                orders,
+                vec![
+                    ("id", distinct(orders)),
+                    ("customer_id", distinct(customers.min(orders))),
+                ],
+            ),
+        )
+}
+
+const SHOP: &[(&str, &[&str])] = &[
+    ("customers", &["id", "region"]),
+    ("orders", &["id", "customer_id"]),
+];
+
+#[test]
+fn test_join_cardinality() {
+    let inner = plan(
+        customers_and_orders(100, 10_000),
+        SHOP,
+        "SELECT * FROM customers c JOIN orders o ON c.id = o.customer_id",
+    );
+    assert_eq!(joins(&inner)[0].statistics.row_count, Some(10_000));
+
+    let filtered = plan(
+        customers_and_orders(100, 10_000),
+        SHOP,
+        "SELECT * FROM customers c JOIN orders o ON c.id = o.customer_id WHERE c.region = 1",
+    );
+    assert_eq!(joins(&filtered)[0].statistics.row_count, Some(2_000));
+
+    // Half of the customers have no orders but are still returned
+    let left = plan(
+        customers_and_orders(100, 50),
+        SHOP,
+        "SELECT * FROM customers c LEFT JOIN orders o ON c.id = o.customer_id",
+    );
+    assert_eq!(joins(&left)[0].statistics.row_count, Some(100));
+
+    let cross = plan(
+        customers_and_orders(100, 50),
+        SHOP,
+        "SELECT * FROM customers CROSS JOIN orders",
+    );
+    assert_eq!(joins(&cross)[0].statistics.row_count, Some(5_000));
+
+    // Without statistics the row count is not claimed to be known
+    let unknown = plan(
+        Optimizer::new(),
+        SHOP,
+        "SELECT * FROM customers c JOIN orders o ON c.id = o.customer_id",
+    );
+    assert_eq!(joins(&unknown)[0].statistics.row_count, None);
+    assert!(joins(&unknown)[0].statistics.cost.is_some());
+}
+
+/// Two large tables linked only through a small one, written so that the large ones
+/// are joined first without a predicate
+fn star(cost_model: CostModel) -> LogicalPlan {
+    let optimizer = Optimizer::new()
+        .with_cost_model(cost_model)
+        .with_table_statistics("a", table(1000, vec![("id", distinct(1000))]))
+        .with_table_statistics("b", table(1000, vec![("id", distinct(1000))]))
+        .with_table_statistics(
+            "c",
+            table(10, vec![("a_id", distinct(10)), ("b_id", distinct(10))]),
+        );
+    plan(
+        optimizer,
+        &[("a", &["id"]), ("b", &["id"]), ("c", &["a_id", "b_id"])],
+        "SELECT * FROM a, b, c WHERE a.id = c.a_id AND b.id = c.b_id",
+    )
+}
+
+fn assert_joined_through_c(plan: &LogicalPlan) {
+    let joins = joins(plan);
+    assert_eq!(joins.len(), 2);
+    for join in &joins {
+        assert_eq!(join.join_type, JoinType::Inner);
+        assert!(join.join_constraint.is_some());
+    }
+    // The lower join pairs one of the large tables with c
+    let mut lower = tables(&LogicalPlan::Join(joins[1].clone()));
+    lower.sort();
+    assert!(lower == ["a", "c"] || lower == ["b", "c"], "{:?}", lower);
+    // The columns keep the order of the FROM clause
+    let names: Vec<String> = plan
+        .schema()
+        .columns
+        .iter()
+        .map(|column| column.name.clone())
+        .collect();
+    assert_eq!(names, ["id", "id", "a_id", "b_id"]);
+    assert_eq!(joins[0].statistics.row_count, Some(10));
+}
+
+#[test]
+fn test_reorder_with_dynamic_programming() {
+    let plan = star(CostModel::default());
+    assert_joined_through_c(&plan);
+    assert_eq!(tables(&plan).len(), 3);
+}
+
+#[test]
+fn test_reorder_greedily() {
+    let plan = star(CostModel {
+        max_dp_relations: 2,
+        ..CostModel::default()
+    });
+    assert_joined_through_c(&plan);
+}
+
+#[test]
+fn test_reorder_keeps_a_cheaper_written_order() {
+    let plan = plan(
+        customers_and_orders(100, 10_000),
+        SHOP,
+        "SELECT * FROM customers c JOIN orders o ON c.id = o.customer_id",
+    );
+    assert_eq!(tables(&plan), ["c", "o"]);
+}
+
+fn strategy(optimizer: Optimizer, sql: &str) -> JoinStrategy {
+    let plan = plan(optimizer, SHOP, sql);
+    joins(&plan)[0].strategy.clone().unwrap()
+}
+
+#[test]
+fn test_hash_join_builds_the_smaller_side() {
+    let sql = "SELECT * FROM customers c JOIN orders o ON c.id = o.customer_id";
+    assert_eq!(
+        strategy(customers_and_orders(10, 10_000), sql),
+        JoinStrategy {
+            algorithm: JoinAlgorithm::Hash,
+            build_side: BuildSide::Left,
+        }
+    );
+    assert_eq!(
+        strategy(customers_and_orders(10_000, 10), sql),
+        JoinStrategy {
+            algorithm: JoinAlgorithm::Hash,
+            build_side: BuildSide::Right,
+        }
+    );
+}
+
+#[test]
+fn test_outer_join_builds_the_inner_side() {
+    // Customers are fewer, but every one of them has to be returned
+    assert_eq!(
+        strategy(
+            customers_and_orders(10, 10_000),
+            "SELECT * FROM customers c LEFT JOIN orders o ON c.id = o.customer_id",
+        ),
+        JoinStrategy {
+            algorithm: JoinAlgorithm::Hash,
+            build_side: BuildSide::Right,
+        }
+    );
+    assert_eq!(
+        strategy(
+            customers_and_orders(10_000, 10),
+            "SELECT * FROM customers c RIGHT OUTER JOIN orders o ON c.id = o.customer_id",
+        ),
+        JoinStrategy {
+            algorithm: JoinAlgorithm::Hash,
+            build_side: BuildSide::Left,
+        }
+    );
+}
+
+#[test]
+fn test_join_algorithm_choice() {
+    // Only a nested loop can evaluate a join without equalities
+    assert_eq!(
+        strategy(
+            customers_and_orders(10, 10_000),
+            "SELECT * FROM customers c JOIN orders o ON c.id < o.customer_id",
+        ),
+        JoinStrategy {
+            algorithm: JoinAlgorithm::NestedLoop,
+            build_side: BuildSide::Left,
+        }
+    );
+    // A single row is cheaper to loop over than to hash
+    let one = strategy(
+        customers_and_orders(1, 10_000),
+        "SELECT * FROM customers c JOIN orders o ON c.id = o.customer_id",
+    );
+    assert_eq!(one.algorithm, JoinAlgorithm::NestedLoop);
+
+    // Sorting wins once hashing is expensive enough
+    let sort_merge = strategy(
+        customers_and_orders(100, 100).with_cost_model(CostModel {
+            hash_build_cost: 1000.0,
+            hash_probe_cost: 1000.0,
+            ..CostModel::default()
+        }),
+        "SELECT * FROM customers c JOIN orders o ON c.id = o.customer_id",
+    );
+    assert_eq!(sort_merge.algorithm, JoinAlgorithm::SortMerge);
+}
+
+#[test]
+fn test_scan_parallelism() {
+    let parallelism = |rows: Option<usize>| {
+        let optimizer = match rows {
+            Some(rows) => Optimizer::new().with_table_statistics("t", table(rows, vec![])),
+            None => Optimizer::new(),
+        };
+        let plan = plan(optimizer, &[("t", &["a", "b", "c"])], "SELECT a FROM t");
+        scans(&plan)[0].parallelism
+    };
+    assert_eq!(parallelism(None), Some(1));
+    assert_eq!(parallelism(Some(10)), Some(1));
+    assert_eq!(parallelism(Some(250_000)), Some(3));
+    assert_eq!(parallelism(Some(100_000_000)), Some(8));
+
+    let optimizer = Optimizer::new()
+        .with_cost_model(CostModel {
+            max_parallelism: 64,
+            rows_per_worker: 1000,
+            ..CostModel::default()
+        })
+        .with_table_statistics("t", table(20_500, vec![]));
+    let plan = plan(optimizer, &[("t", &["a", "b", "c"])], "SELECT a FROM t");
+    assert_eq!(scans(&plan)[0].parallelism, Some(21));
+}
+
+#[test]
+fn test_costs_include_inputs() {
+    let plan = plan(
+        customers_and_orders(100, 10_000),
+        SHOP,
+        "SELECT * FROM customers c JOIN orders o ON c.id = o.customer_id",
+    );
+    let join = joins(&plan)[0];
+    let scan_cost: f64 = scans(&plan)
+        .iter()
+        .map(|scan| scan.statistics.cost.unwrap())
+        .sum();
+    assert_eq!(scan_cost, 10_100.0);
+    // Building 100 customers and probing 10000 orders
+    assert_eq!(join.statistics.cost, Some(scan_cost + 200.0 + 10_000.0));
+    assert!(plan.statistics().cost.unwrap() >= join.statistics.cost.unwrap());
+}
@@ -0,0 +1,57 @@
+use diplomat::{
+    logical_plan::LogicalPlan,
+    plan_builder::PlanBuilder,
+    types::{ColumnDef, LogicalSchema},
+};
+use shared_types::DataType;
+use sqlparser::{dialect::GenericDialect, parser::Parser};
+
+fn schema(columns: &[&str]) -> LogicalSchema {
+    LogicalSchema::new(
+        columns
+            .iter()
+            .map(|name| ColumnDef::new(*name, DataType::Integer))
+            .collect(),
+    )
+}
+
+fn plan(sql: &str) -> LogicalPlan {
+    let statement = Parser::parse_sql(&GenericDialect {}, sql)
+        .unwrap()
+        .remove(0);
+    PlanBuilder::new()
+        .with_table_schema("customers".to_string(), schema(&["id", "region"]))
+        .with_table_schema("orders".to_string(), schema(&["id", "customer_id"]))
+        .generate(&statement)
+        .unwrap()
+}
+
+#[test]
+fn test_recursive_keyword_does_not_change_non_recursive_ctes() {
+    let query = "a AS (SELECT id FROM customers), \
+                 b AS (SELECT id FROM a UNION SELECT id FROM orders) \
+                 SELECT * FROM b";
+    let plain = plan(&format!("WITH {}", query));
+    let recursive = plan(&format!("WITH RECURSIVE {}", query));
+
+    // `a` is read once, so it is inlined either way
+    assert!(!matches!(recursive, LogicalPlan::With(_)));
+    assert_eq!(format!("{:?}", recursive), format!("{:?}", plain));
+}
+
+#[test]
+fn test_recursive_cte_is_a_fixpoint() {
+    let plan = plan(
+        "WITH RECURSIVE chain AS (SELECT id FROM customers \
+         UNION ALL SELECT o.id FROM orders o JOIN chain c ON o.customer_id = c.id) \
+         SELECT * FROM chain",
+    );
+    let LogicalPlan::With(with) = plan else {
+        panic!(
+            "expected the recursive CTE to be materialized, got {:?}",
+            plan
+        );
+    };
+    assert_eq!(with.ctes.len(), 1);
+    assert!(matches!(with.ctes[0].plan, LogicalPlan::RecursiveQuery(_)));
+}
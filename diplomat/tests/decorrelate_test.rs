@@ -0,0 +1,178 @@
+use diplomat::{
+    expression::Expression,
+    logical_plan::{JoinNode, LogicalPlan},
+    optimizer::Optimizer,
+    plan_builder::PlanBuilder,
+    types::{ColumnDef, JoinType, LogicalSchema},
+};
+use shared_types::DataType;
+use sqlparser::{dialect::GenericDialect, parser::Parser};
+
+fn schema(columns: &[&str]) -> LogicalSchema {
+    LogicalSchema::new(
+        columns
+            .iter()
+            .map(|name| ColumnDef::new(*name, DataType::Integer))
+            .collect(),
+    )
+}
+
+/// Plan and optimize `sql`
+fn decorrelate(sql: &str) -> LogicalPlan {
+    let statement = Parser::parse_sql(&GenericDialect {}, sql)
+        .unwrap()
+        .remove(0);
+    let plan = PlanBuilder::new()
+        .with_table_schema("customers".to_string(), schema(&["id", "region"]))
+        .with_table_schema(
+            "orders".to_string(),
+            schema(&["id", "customer_id", "amount"]),
+        )
+        .generate(&statement)
+        .unwrap();
+    Optimizer::new().optimize(plan).unwrap()
+}
+
+/// `plan` and the nodes below it, parents first
+fn nodes(plan: &LogicalPlan) -> Vec<&LogicalPlan> {
+    let mut nodes = vec![plan];
+    for child in plan.children() {
+        nodes.extend(self::nodes(child));
+    }
+    nodes
+}
+
+fn joins(plan: &LogicalPlan) -> Vec<&JoinNode> {
+    nodes(plan)
+        .into_iter()
+        .filter_map(|node| match node {
+            LogicalPlan::Join(join) => Some(join),
+            _ => None,
+        })
+        .collect()
+}
+
+fn condition(join: &JoinNode) -> String {
+    join.join_constraint.as_ref().unwrap().to_string()
+}
+
+/// Filter predicates of `plan`, whether still in a filter or pushed into a scan
+fn predicates(plan: &LogicalPlan) -> Vec<String> {
+    nodes(plan)
+        .into_iter()
+        .flat_map(|node| match node {
+            LogicalPlan::Filter(filter) => vec![filter.predicate.to_string()],
+            LogicalPlan::TableScan(scan) => scan.filters.iter().map(|f| f.to_string()).collect(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+fn contains_subquery(expr: &Expression) -> bool {
+    match expr {
+        Expression::BinaryOp { left, right, .. } => {
+            contains_subquery(left) || contains_subquery(right)
+        }
+        Expression::UnaryOp { expr, .. } => contains_subquery(expr),
+        other => other.is_subquery(),
+    }
+}
+
+fn has_subquery(plan: &LogicalPlan) -> bool {
+    nodes(plan).into_iter().any(|node| match node {
+        LogicalPlan::Filter(filter) => contains_subquery(&filter.predicate),
+        LogicalPlan::TableScan(scan) => scan.filters.iter().any(contains_subquery),
+        LogicalPlan::Join(join) => join.join_constraint.as_ref().is_some_and(contains_subquery),
+        _ => false,
+    })
+}
+
+#[test]
+fn test_in_subquery_becomes_semi_join() {
+    let plan = decorrelate("SELECT id FROM customers WHERE id IN (SELECT customer_id FROM orders)");
+    let joins = joins(&plan);
+    assert_eq!(joins.len(), 1);
+    assert_eq!(joins[0].join_type, JoinType::LeftSemi);
+    assert_eq!(condition(joins[0]), "(id = customer_id)");
+    assert!(!has_subquery(&plan));
+    // Semi joins return the outer rows only
+    assert_eq!(joins[0].schema.column_count(), 2);
+}
+
+#[test]
+fn test_not_in_becomes_null_aware_anti_join() {
+    let plan =
+        decorrelate("SELECT id FROM customers WHERE id NOT IN (SELECT customer_id FROM orders)");
+    let joins = joins(&plan);
+    assert_eq!(joins.len(), 1);
+    // A NULL customer_id must reject every row, which a plain anti join would not do
+    assert_eq!(joins[0].join_type, JoinType::NullAwareLeftAnti);
+    assert_eq!(condition(joins[0]), "(id = customer_id)");
+    assert!(!has_subquery(&plan));
+}
+
+#[test]
+fn test_correlated_exists_joins_on_correlation() {
+    let plan = decorrelate(
+        "SELECT id FROM customers c WHERE EXISTS \
+         (SELECT 1 FROM orders o WHERE o.customer_id = c.id AND o.amount > 10)",
+    );
+    let semi_joins = joins(&plan);
+    assert_eq!(semi_joins.len(), 1);
+    assert_eq!(semi_joins[0].join_type, JoinType::LeftSemi);
+    // The correlated predicate becomes the join condition; the rest stays inside
+    assert_eq!(condition(semi_joins[0]), "(o.customer_id = c.id)");
+    assert_eq!(
+        predicates(&semi_joins[0].right),
+        ["(o.amount > Integer(10))"]
+    );
+    assert!(!has_subquery(&plan));
+
+    let plan = decorrelate(
+        "SELECT id FROM customers c WHERE NOT EXISTS \
+         (SELECT 1 FROM orders o WHERE o.customer_id = c.id)",
+    );
+    assert_eq!(joins(&plan)[0].join_type, JoinType::LeftAnti);
+
+    let plan = decorrelate(
+        "SELECT id FROM customers c WHERE EXISTS \
+         (SELECT 1 FROM orders o WHERE o.customer_id > c.id)",
+    );
+    assert_eq!(condition(joins(&plan)[0]), "(o.customer_id > c.id)");
+}
+
+#[test]
+fn test_correlated_scalar_aggregate_becomes_left_join() {
+    let plan = decorrelate(
+        "SELECT id FROM customers c WHERE region > \
+         (SELECT sum(amount) FROM orders o WHERE o.customer_id = c.id)",
+    );
+    let joins = joins(&plan);
+    assert_eq!(joins.len(), 1);
+    // Customers without orders must still reach the comparison, with a NULL sum
+    assert_eq!(joins[0].join_type, JoinType::Left);
+    assert_eq!(condition(joins[0]), "(c.id = __scalar_subquery_0.key_0)");
+    assert!(nodes(&joins[0].right).into_iter().any(|node| matches!(
+        node,
+        LogicalPlan::Aggregate(aggregate) if aggregate.group_expr.len() == 1
+    )));
+    assert!(!has_subquery(&plan));
+    assert_eq!(plan.schema().column_count(), 1);
+}
+
+#[test]
+fn test_other_subqueries_stay_in_the_filter() {
+    // Uncorrelated scalar subqueries are evaluated once per outer row
+    let plan =
+        decorrelate("SELECT id FROM customers WHERE region = (SELECT max(amount) FROM orders)");
+    assert!(joins(&plan).is_empty());
+    assert!(has_subquery(&plan));
+
+    // Under OR the subquery cannot filter the outer rows on its own
+    let plan = decorrelate(
+        "SELECT id FROM customers c WHERE c.region = 1 OR EXISTS \
+         (SELECT 1 FROM orders o WHERE o.customer_id = c.id)",
+    );
+    assert!(joins(&plan).is_empty());
+    assert!(has_subquery(&plan));
+}
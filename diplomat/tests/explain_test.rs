@@ -0,0 +1,212 @@
+use diplomat::{
+    common::LogicalPlanError,
+    explain::explain,
+    logical_plan::{ExplainNode, LogicalPlan},
+    optimizer::Optimizer,
+    plan_builder::PlanBuilder,
+    types::{ColumnDef, ExplainFormat, LogicalSchema},
+};
+use shared_types::DataType;
+use sqlparser::{dialect::GenericDialect, parser::Parser};
+
+fn builder() -> PlanBuilder {
+    PlanBuilder::new()
+        .with_table_schema(
+            "customers".to_string(),
+            LogicalSchema::new(vec![
+                ColumnDef::new("id", DataType::Integer),
+                ColumnDef::new("name", DataType::Text),
+            ]),
+        )
+        .with_table_schema(
+            "orders".to_string(),
+            LogicalSchema::new(vec![
+                ColumnDef::new("id", DataType::Integer),
+                ColumnDef::new("customer_id", DataType::Integer),
+                ColumnDef::new("amount", DataType::Integer),
+            ]),
+        )
+}
+
+fn generate(sql: &str) -> Result<LogicalPlan, LogicalPlanError> {
+    let statement = Parser::parse_sql(&GenericDialect {}, sql)
+        .unwrap()
+        .remove(0);
+    builder().generate(&statement)
+}
+
+fn explained(sql: &str) -> ExplainNode {
+    match Optimizer::new().optimize(generate(sql).unwrap()).unwrap() {
+        LogicalPlan::Explain(explain) => explain,
+        other => panic!("expected an EXPLAIN plan, got {}", other.description()),
+    }
+}
+
+/// The lines of the physical plan section of text output
+fn physical_lines(text: &str) -> Vec<&str> {
+    text.lines()
+        .skip_while(|line| *line != "Physical Plan:")
+        .skip(1)
+        .collect()
+}
+
+#[test]
+fn test_text_output() {
+    let explain_node = explained("EXPLAIN SELECT id FROM orders WHERE amount > 10 ORDER BY id");
+    assert_eq!(
+        explain(&explain_node),
+        "Logical Plan:\n\
+         \x20 Sort: id ASC NULLS LAST\n\
+         \x20   Projection: id\n\
+         \x20     TableScan: orders filters=[(amount > Integer(10))] columns=[id, amount]\n\
+         Physical Plan:\n\
+         \x20 Sort: id ASC NULLS LAST  (rows=? cost=10965.78)\n\
+         \x20   Projection: id  (rows=? cost=1000.00)\n\
+         \x20     SeqScan: orders filters=[(amount > Integer(10))] columns=[id, amount]  \
+         (rows=? cost=1000.00)\n"
+    );
+}
+
+#[test]
+fn test_json_output() {
+    let explain_node = explained("EXPLAIN FORMAT JSON SELECT id FROM orders WHERE amount > 10");
+    assert_eq!(explain_node.format, ExplainFormat::Json);
+
+    assert_eq!(
+        explain(&explain_node),
+        concat!(
+            r#"{"logical_plan": {"node": "Projection: id", "children": ["#,
+            r#"{"node": "TableScan: orders filters=[(amount > Integer(10))] columns=[id, amount]", "children": []}]}, "#,
+            r#""physical_plan": {"operator": "Projection: id", "estimated_rows": null, "estimated_cost": 1000.00, "#,
+            r#""children": ["#,
+            r#"{"operator": "SeqScan: orders filters=[(amount > Integer(10))] columns=[id, amount]", "#,
+            r#""estimated_rows": null, "estimated_cost": 1000.00, "children": []}]}}"#
+        )
+    );
+}
+
+#[test]
+fn test_json_output_escapes_strings() {
+    let explain_node =
+        explained("EXPLAIN FORMAT JSON SELECT name FROM customers WHERE name LIKE 'a\"%'");
+    assert!(
+        explain(&explain_node).contains(r#"(name LIKE String(\"a\\\"%\"))"#),
+        "{}",
+        explain(&explain_node)
+    );
+}
+
+#[test]
+fn test_every_node_gets_an_operator() {
+    let explain_node = explained(
+        "EXPLAIN SELECT c.name, COUNT(*) FROM customers c \
+         JOIN orders o ON c.id = o.customer_id GROUP BY c.name",
+    );
+    let text = explain(&explain_node);
+    let operators: Vec<&str> = physical_lines(&text)
+        .iter()
+        .map(|line| line.trim_start().split(':').next().unwrap())
+        .collect();
+    assert_eq!(
+        operators,
+        [
+            "Projection",
+            "HashAggregate",
+            "HashJoin",
+            "SeqScan",
+            "SeqScan"
+        ]
+    );
+    // Only estimates are shown
+    for line in physical_lines(&text) {
+        assert!(line.contains("  (rows="), "{}", line);
+        assert!(!line.contains("actual"), "{}", line);
+    }
+}
+
+#[test]
+fn test_analyze_is_rejected() {
+    // The planner doesn't execute plans, so it has no actual rows or timings to show
+    for sql in [
+        "EXPLAIN ANALYZE SELECT id FROM orders",
+        "EXPLAIN (ANALYZE, FORMAT JSON) SELECT id FROM orders",
+        "EXPLAIN (ANALYZE true) SELECT id FROM orders",
+    ] {
+        assert!(
+            matches!(
+                generate(sql),
+                Err(LogicalPlanError::UnsupportedOperation(message))
+                    if message.starts_with("EXPLAIN ANALYZE")
+            ),
+            "{}",
+            sql
+        );
+    }
+}
+
+#[test]
+fn test_option_parsing() {
+    let options = |sql: &str| match generate(sql)? {
+        LogicalPlan::Explain(explain) => Ok(explain.format),
+        other => panic!("expected an EXPLAIN plan, got {}", other.description()),
+    };
+
+    for (sql, expected) in [
+        ("EXPLAIN SELECT id FROM orders", ExplainFormat::Text),
+        (
+            "EXPLAIN FORMAT JSON SELECT id FROM orders",
+            ExplainFormat::Json,
+        ),
+        (
+            "EXPLAIN (FORMAT JSON) SELECT id FROM orders",
+            ExplainFormat::Json,
+        ),
+        (
+            "EXPLAIN (ANALYZE off, FORMAT 'text') SELECT id FROM orders",
+            ExplainFormat::Text,
+        ),
+    ] {
+        assert_eq!(options(sql).unwrap(), expected, "{}", sql);
+    }
+
+    assert!(matches!(
+        options("EXPLAIN (ANALYZE maybe) SELECT id FROM orders"),
+        Err(LogicalPlanError::ValidationError(_))
+    ));
+    assert!(matches!(
+        options("EXPLAIN (FORMAT) SELECT id FROM orders"),
+        Err(LogicalPlanError::ValidationError(_))
+    ));
+    assert!(matches!(
+        options("EXPLAIN (FORMAT xml) SELECT id FROM orders"),
+        Err(LogicalPlanError::UnsupportedOperation(message)) if message == "EXPLAIN format XML"
+    ));
+    assert!(matches!(
+        options("EXPLAIN (VERBOSE) SELECT id FROM orders"),
+        Err(LogicalPlanError::UnsupportedOperation(message)) if message == "EXPLAIN option VERBOSE"
+    ));
+}
+
+#[test]
+fn test_expressions_are_rendered() {
+    let explain_node = explained(
+        "EXPLAIN SELECT CAST(amount AS BIGINT), amount BETWEEN 1 AND 5, id NOT IN (1, 2), \
+         customer_id IS NULL, customer_id IS NOT NULL, \
+         CASE WHEN amount > 1 THEN 'x' ELSE 'y' END FROM orders",
+    );
+    assert!(explain(&explain_node).contains(
+        "Projection: CAST(amount AS BigInt), (amount BETWEEN Integer(1) AND Integer(5)), \
+         (id NOT IN (Integer(1), Integer(2))), (customer_id IS NULL), \
+         (customer_id IS NOT NULL), \
+         CASE WHEN (amount > Integer(1)) THEN String(\"x\") ELSE String(\"y\") END\n"
+    ));
+
+    let explain_node = explained(
+        "EXPLAIN SELECT name FROM customers \
+         WHERE name NOT ILIKE 'a%' AND name LIKE 'b%' AND CASE id WHEN 1 THEN TRUE END",
+    );
+    assert!(explain(&explain_node).contains(
+        "filters=[(name NOT ILIKE String(\"a%\")), (name LIKE String(\"b%\")), \
+         CASE id WHEN Integer(1) THEN Boolean(true) END]"
+    ));
+}
@@ -0,0 +1,353 @@
+use diplomat::{
+    common::LogicalPlanError,
+    logical_plan::LogicalPlan,
+    optimizer::{Optimizer, OptimizerRule, PushDownFilter, RuleApplication, rule::default_rules},
+    plan_builder::PlanBuilder,
+    types::{ColumnDef, LogicalSchema},
+    visitor::Transformed,
+};
+use shared_types::DataType;
+use sqlparser::{dialect::GenericDialect, parser::Parser};
+
+fn schema(columns: &[&str]) -> LogicalSchema {
+    LogicalSchema::new(
+        columns
+            .iter()
+            .map(|name| ColumnDef::new(*name, DataType::Integer))
+            .collect(),
+    )
+}
+
+fn plan(sql: &str) -> LogicalPlan {
+    let statement = Parser::parse_sql(&GenericDialect {}, sql)
+        .unwrap()
+        .remove(0);
+    PlanBuilder::new()
+        .with_table_schema("customers".to_string(), schema(&["id", "region"]))
+        .with_table_schema(
+            "orders".to_string(),
+            schema(&["id", "customer_id", "amount"]),
+        )
+        .generate(&statement)
+        .unwrap()
+}
+
+fn optimize(sql: &str) -> String {
+    let plan = Optimizer::new().optimize(plan(sql)).unwrap();
+    let mut lines = Vec::new();
+    render(&plan, 0, &mut lines);
+    lines.join("\n")
+}
+
+/// One line per node, with the filters of scans and the conditions of joins
+fn render(plan: &LogicalPlan, depth: usize, lines: &mut Vec<String>) {
+    let detail = match plan {
+        LogicalPlan::TableScan(scan) => scan
+            .filters
+            .iter()
+            .map(|filter| format!(" {}", filter))
+            .collect(),
+        LogicalPlan::Join(join) => join
+            .join_constraint
+            .iter()
+            .map(|constraint| format!(" ON {}", constraint))
+            .collect(),
+        _ => String::new(),
+    };
+    lines.push(format!(
+        "{}{}{}",
+        "  ".repeat(depth),
+        plan.description(),
+        detail
+    ));
+    for child in plan.children() {
+        render(child, depth + 1, lines);
+    }
+}
+
+#[test]
+fn test_where_on_nullable_side_of_left_join_stays_above() {
+    // Pushed into orders, `o.amount > 10` would turn dropped orders into customers
+    // with NULL orders instead of dropping those customers
+    assert_eq!(
+        optimize(
+            "SELECT * FROM customers c LEFT JOIN orders o ON c.id = o.customer_id \
+             WHERE o.amount > 10 AND c.region = 1"
+        ),
+        "Filter: (o.amount > Integer(10))\n\
+         \x20 Join: Left ON (c.id = o.customer_id)\n\
+         \x20   TableScan: customers (c.region = Integer(1))\n\
+         \x20   TableScan: orders"
+    );
+}
+
+#[test]
+fn test_on_clause_on_preserved_side_stays_in_join() {
+    // Customers outside region 1 are still returned, just without orders
+    assert_eq!(
+        optimize(
+            "SELECT * FROM customers c LEFT JOIN orders o \
+             ON c.id = o.customer_id AND c.region = 1 AND o.amount > 10"
+        ),
+        "Join: Left ON ((c.id = o.customer_id) AND (c.region = Integer(1)))\n\
+         \x20 TableScan: customers\n\
+         \x20 TableScan: orders (o.amount > Integer(10))"
+    );
+}
+
+#[test]
+fn test_filter_on_join_key_is_derived_for_other_side() {
+    assert_eq!(
+        optimize("SELECT * FROM customers c JOIN orders o ON c.id = o.customer_id WHERE c.id = 5"),
+        "Join: Inner ON (c.id = o.customer_id)\n\
+         \x20 TableScan: customers (c.id = Integer(5))\n\
+         \x20 TableScan: orders (o.customer_id = Integer(5))"
+    );
+    // Not from the side whose unmatched rows are returned
+    assert_eq!(
+        optimize(
+            "SELECT * FROM customers c LEFT JOIN orders o ON c.id = o.customer_id \
+             WHERE o.customer_id = 5"
+        ),
+        "Filter: (o.customer_id = Integer(5))\n\
+         \x20 Join: Left ON (c.id = o.customer_id)\n\
+         \x20   TableScan: customers\n\
+         \x20   TableScan: orders"
+    );
+}
+
+#[test]
+fn test_limit_pushed_to_left_of_left_join_only() {
+    assert_eq!(
+        optimize("SELECT * FROM customers c LEFT JOIN orders o ON c.id = o.customer_id LIMIT 10"),
+        "Limit: skip=None, fetch=Some(10)\n\
+         \x20 Join: Left ON (c.id = o.customer_id)\n\
+         \x20   Limit: skip=None, fetch=Some(10)\n\
+         \x20     TableScan: customers\n\
+         \x20   TableScan: orders"
+    );
+    // Any input row may fail to match, so neither input can be cut short
+    for join in ["JOIN", "FULL JOIN"] {
+        assert_eq!(
+            optimize(&format!(
+                "SELECT * FROM customers c {} orders o ON c.id = o.customer_id LIMIT 10",
+                join
+            )),
+            format!(
+                "Limit: skip=None, fetch=Some(10)\n\
+                 \x20 Join: {} ON (c.id = o.customer_id)\n\
+                 \x20   TableScan: customers\n\
+                 \x20   TableScan: orders",
+                if join == "JOIN" { "Inner" } else { "Full" }
+            )
+        );
+    }
+}
+
+#[test]
+fn test_filter_not_pushed_below_limit() {
+    assert_eq!(
+        optimize("SELECT * FROM (SELECT * FROM orders LIMIT 10) s WHERE s.amount > 5"),
+        "Subquery\n\
+         \x20 Filter: (amount > Integer(5))\n\
+         \x20   Limit: skip=None, fetch=Some(10)\n\
+         \x20     TableScan: orders"
+    );
+}
+
+#[test]
+fn test_filter_pushed_below_window_on_partition_key_only() {
+    assert_eq!(
+        optimize(
+            "SELECT * FROM (SELECT customer_id, amount, \
+             ROW_NUMBER() OVER (PARTITION BY customer_id ORDER BY amount) AS rn FROM orders) s \
+             WHERE s.customer_id = 5 AND s.amount > 3 AND s.rn = 1"
+        ),
+        "Subquery\n\
+         \x20 Filter: (rn = Integer(1))\n\
+         \x20   Projection: 3 expressions\n\
+         \x20     Filter: (amount > Integer(3))\n\
+         \x20       Window: 1 expressions\n\
+         \x20         TableScan: orders (customer_id = Integer(5))"
+    );
+    // Every window has to partition by the column
+    assert_eq!(
+        optimize(
+            "SELECT * FROM (SELECT customer_id, \
+             ROW_NUMBER() OVER (PARTITION BY customer_id) AS a, \
+             ROW_NUMBER() OVER (ORDER BY amount) AS b FROM orders) s \
+             WHERE s.customer_id = 5"
+        ),
+        "Subquery\n\
+         \x20 Projection: 3 expressions\n\
+         \x20   Filter: (customer_id = Integer(5))\n\
+         \x20     Window: 2 expressions\n\
+         \x20       TableScan: orders"
+    );
+}
+
+#[test]
+fn test_sorts_and_projections_eliminated() {
+    // The order of a derived table is lost unless a limit picks its rows
+    assert_eq!(
+        optimize("SELECT * FROM (SELECT * FROM orders ORDER BY amount) s"),
+        "Subquery\n\
+         \x20 TableScan: orders"
+    );
+    assert_eq!(
+        optimize("SELECT * FROM (SELECT * FROM orders ORDER BY amount LIMIT 3) s"),
+        "Subquery\n\
+         \x20 Limit: skip=None, fetch=Some(3)\n\
+         \x20   Sort: 1 expressions\n\
+         \x20     TableScan: orders"
+    );
+    assert_eq!(
+        optimize("SELECT * FROM (SELECT * FROM orders WHERE amount > 1) s WHERE s.amount < 9"),
+        "Subquery\n\
+         \x20 TableScan: orders (amount > Integer(1)) (amount < Integer(9))"
+    );
+}
+
+#[test]
+fn test_columns_pruned_into_scans() {
+    let plan = Optimizer::new()
+        .optimize(plan(
+            "SELECT c.region FROM customers c JOIN orders o ON c.id = o.customer_id",
+        ))
+        .unwrap();
+    let mut projected = Vec::new();
+    let mut pending = vec![&plan];
+    while let Some(node) = pending.pop() {
+        if let LogicalPlan::TableScan(scan) = node {
+            projected.push((scan.table.name.clone(), scan.projected_columns.clone()));
+        }
+        pending.extend(node.children());
+    }
+    projected.sort();
+    // Every column of customers is read, so its scan isn't narrowed
+    assert_eq!(
+        projected,
+        [
+            ("customers".to_string(), None),
+            ("orders".to_string(), Some(vec!["customer_id".to_string()])),
+        ]
+    );
+}
+
+/// Grows the limit of the plan by one on every pass, so it never reaches a fixpoint
+struct GrowLimit;
+
+impl OptimizerRule for GrowLimit {
+    fn name(&self) -> &'static str {
+        "grow_limit"
+    }
+
+    fn rewrite(&self, plan: LogicalPlan) -> Result<Transformed<LogicalPlan>, LogicalPlanError> {
+        Ok(match plan {
+            LogicalPlan::Limit(mut limit) => {
+                limit.fetch = limit.fetch.map(|fetch| fetch + 1);
+                Transformed::yes(LogicalPlan::Limit(limit))
+            }
+            other => Transformed::no(other),
+        })
+    }
+}
+
+#[test]
+fn test_rules_stop_after_max_passes() {
+    let (grown, trace) = Optimizer::new()
+        .with_rules(vec![Box::new(GrowLimit), Box::new(PushDownFilter)])
+        .with_max_passes(3)
+        .optimize_with_trace(plan("SELECT * FROM orders LIMIT 10"))
+        .unwrap();
+    let LogicalPlan::Limit(limit) = grown else {
+        panic!("expected a limit, got {}", grown);
+    };
+    assert_eq!(limit.fetch, Some(13));
+    let passes: Vec<(usize, &str, bool)> = trace
+        .iter()
+        .map(|application| (application.pass, application.rule, application.changed))
+        .collect();
+    assert_eq!(
+        passes,
+        [
+            (1, "grow_limit", true),
+            (1, "push_down_filter", false),
+            (2, "grow_limit", true),
+            (2, "push_down_filter", false),
+            (3, "grow_limit", true),
+            (3, "push_down_filter", false),
+        ]
+    );
+
+    let (_, trace) = Optimizer::new()
+        .with_max_passes(0)
+        .optimize_with_trace(plan("SELECT * FROM orders LIMIT 10"))
+        .unwrap();
+    assert!(trace.is_empty());
+}
+
+#[test]
+fn test_trace() {
+    let (_, trace) = Optimizer::new()
+        .optimize_with_trace(plan(
+            "SELECT * FROM customers c JOIN orders o ON c.id = o.customer_id WHERE c.id = 5",
+        ))
+        .unwrap();
+    let rules = [
+        "merge_filters",
+        "push_down_filter",
+        "push_down_limit",
+        "eliminate_sorts",
+        "eliminate_projections",
+        "prune_columns",
+    ];
+    // Every rule runs in order on each pass, and the second pass confirms the fixpoint
+    let expected: Vec<RuleApplication> = [1, 2]
+        .into_iter()
+        .flat_map(|pass| {
+            rules.iter().map(move |rule| RuleApplication {
+                pass,
+                rule,
+                changed: pass == 1 && matches!(*rule, "push_down_filter" | "eliminate_projections"),
+                elapsed: Default::default(),
+            })
+        })
+        .collect();
+    let without_time: Vec<RuleApplication> = trace
+        .into_iter()
+        .map(|application| RuleApplication {
+            elapsed: Default::default(),
+            ..application
+        })
+        .collect();
+    assert_eq!(without_time, expected);
+}
+
+#[test]
+fn test_rules_report_whether_they_changed_the_plan() {
+    let queries = [
+        "SELECT * FROM customers c JOIN orders o ON c.id = o.customer_id WHERE c.id = 5",
+        "SELECT * FROM customers c LEFT JOIN orders o ON c.id = o.customer_id AND o.amount > 3",
+        "SELECT id FROM (SELECT * FROM orders WHERE amount > 1 ORDER BY amount) t WHERE id = 2",
+        "SELECT id FROM orders UNION ALL SELECT id FROM customers LIMIT 4",
+        "SELECT customer_id, SUM(amount) FROM orders GROUP BY customer_id HAVING customer_id > 1",
+        "SELECT * FROM orders WHERE amount IN (SELECT id FROM (SELECT * FROM customers ORDER BY id) c)",
+    ];
+    for sql in queries {
+        // Each rule on the planned query, and again on what the whole optimizer made of it
+        let optimized = Optimizer::new().optimize(plan(sql)).unwrap();
+        for input in [plan(sql), optimized] {
+            for rule in default_rules() {
+                let rewritten = rule.rewrite(input.clone()).unwrap();
+                assert_eq!(
+                    rewritten.transformed,
+                    rewritten.data != input,
+                    "{} on {}",
+                    rule.name(),
+                    sql
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use diplomat::{
+    plan_builder::PlanBuilder,
+    types::{ColumnDef, LogicalSchema},
+};
+use shared_types::DataType;
+use sqlparser::{dialect::GenericDialect, parser::Parser};
+
+/// Nullability of each output column of `sql`, where `a.x` is NOT NULL and `b.y` nullable
+fn nullability(sql: &str) -> Vec<bool> {
+    let statement = Parser::parse_sql(&GenericDialect {}, sql)
+        .unwrap()
+        .remove(0);
+    let plan = PlanBuilder::new()
+        .with_table_schema(
+            "a".to_string(),
+            LogicalSchema::new(vec![ColumnDef::new("x", DataType::Integer).not_null()]),
+        )
+        .with_table_schema(
+            "b".to_string(),
+            LogicalSchema::new(vec![ColumnDef::new("y", DataType::Integer)]),
+        )
+        .generate(&statement)
+        .unwrap();
+    plan.schema()
+        .columns
+        .iter()
+        .map(|column| column.nullable)
+        .collect()
+}
+
+#[test]
+fn test_set_operation_nullability() {
+    assert_eq!(
+        nullability("SELECT * FROM a UNION SELECT * FROM b"),
+        vec![true]
+    );
+    // Only NULLs present on both sides survive INTERSECT
+    assert_eq!(
+        nullability("SELECT * FROM a INTERSECT SELECT * FROM b"),
+        vec![false]
+    );
+    assert_eq!(
+        nullability("SELECT * FROM b INTERSECT SELECT * FROM b"),
+        vec![true]
+    );
+    // EXCEPT only returns rows of its left input
+    assert_eq!(
+        nullability("SELECT * FROM a EXCEPT SELECT * FROM b"),
+        vec![false]
+    );
+    assert_eq!(
+        nullability("SELECT * FROM b EXCEPT SELECT * FROM a"),
+        vec![true]
+    );
+}
@@ -0,0 +1,223 @@
+use diplomat::{
+    common::LogicalPlanError,
+    expression::Expression,
+    logical_plan::{InsertSource, LogicalPlan},
+    plan_builder::PlanBuilder,
+};
+use matan::manager::CatalogManager;
+use shared_types::{Column, DataType, Schema, Value};
+use sqlparser::{dialect::GenericDialect, parser::Parser};
+
+/// Days from 1970-01-01 to 2024-01-31
+const JAN_31_2024: i32 = 19_753;
+
+fn plan(sql: &str) -> Result<LogicalPlan, LogicalPlanError> {
+    let dir = tempfile::tempdir().unwrap();
+    let mut catalog =
+        CatalogManager::new(dir.path().join("catalog.db"), "shop".to_string()).unwrap();
+    catalog
+        .create_table(
+            "events".to_string(),
+            Schema::new(vec![
+                Column::primary_key("id".to_string(), DataType::BigInt),
+                Column::nullable("amount".to_string(), DataType::Float),
+                Column::nullable("day".to_string(), DataType::Date),
+                Column::nullable("name".to_string(), DataType::String),
+                Column::nullable("flag".to_string(), DataType::Boolean),
+            ]),
+            "events.db".to_string(),
+        )
+        .unwrap();
+    let statement = Parser::parse_sql(&GenericDialect {}, sql)
+        .unwrap()
+        .remove(0);
+    PlanBuilder::from_catalog(&catalog).generate(&statement)
+}
+
+fn planned(sql: &str) -> LogicalPlan {
+    plan(sql).unwrap_or_else(|error| panic!("{}: {}", sql, error))
+}
+
+/// Every literal in the plan, parents before children and each expression left to right
+fn literals(plan: &LogicalPlan) -> Vec<Value> {
+    let mut values = Vec::new();
+    plan_literals(plan, &mut values);
+    values
+}
+
+fn plan_literals(plan: &LogicalPlan, values: &mut Vec<Value>) {
+    let exprs: Vec<&Expression> = match plan {
+        LogicalPlan::Projection(projection) => projection.expressions.iter().collect(),
+        LogicalPlan::Filter(filter) => vec![&filter.predicate],
+        LogicalPlan::TableScan(scan) => scan.filters.iter().collect(),
+        LogicalPlan::Insert(insert) => match &insert.source {
+            InsertSource::Values(rows) => rows.iter().flatten().collect(),
+            // A query source is a child of the insert
+            InsertSource::Query(_) => Vec::new(),
+        },
+        LogicalPlan::Values(node) => node.values.iter().flatten().collect(),
+        LogicalPlan::Update(update) => update
+            .assignments
+            .iter()
+            .map(|assignment| &assignment.value)
+            .chain(&update.filter)
+            .collect(),
+        _ => Vec::new(),
+    };
+    for expr in exprs {
+        expr_literals(expr, values);
+    }
+    for child in plan.children() {
+        plan_literals(child, values);
+    }
+}
+
+fn expr_literals(expr: &Expression, values: &mut Vec<Value>) {
+    let children: Vec<&Expression> = match expr {
+        Expression::Literal(value) => {
+            values.push(value.clone());
+            Vec::new()
+        }
+        Expression::BinaryOp { left, right, .. } => vec![left, right],
+        Expression::UnaryOp { expr, .. }
+        | Expression::Alias { expr, .. }
+        | Expression::Cast { expr, .. }
+        | Expression::IsNull(expr)
+        | Expression::IsNotNull(expr) => vec![expr],
+        Expression::Case {
+            expr,
+            when_clauses,
+            else_clause,
+        } => expr
+            .iter()
+            .map(AsRef::as_ref)
+            .chain(when_clauses.iter().flat_map(|(when, then)| [when, then]))
+            .chain(else_clause.iter().map(AsRef::as_ref))
+            .collect(),
+        Expression::In { expr, list, .. } => std::iter::once(expr.as_ref()).chain(list).collect(),
+        Expression::Between {
+            expr, low, high, ..
+        } => vec![expr, low, high],
+        Expression::Function { args, .. } => args.iter().collect(),
+        _ => Vec::new(),
+    };
+    for child in children {
+        expr_literals(child, values);
+    }
+}
+
+fn assert_type_mismatch(sql: &str) {
+    match plan(sql) {
+        Err(LogicalPlanError::TypeMismatch { .. }) => {}
+        other => panic!("{} planned as {:?}", sql, other),
+    }
+}
+
+#[test]
+fn test_integer_literal_compared_with_bigint_column() {
+    let plan = planned("SELECT * FROM events WHERE id = 1");
+    assert!(matches!(literals(&plan)[..], [Value::BigInt(1)]));
+    // Either side of the comparison
+    let plan = planned("SELECT * FROM events WHERE 1 < id");
+    assert!(matches!(literals(&plan)[..], [Value::BigInt(1)]));
+    // An integer compared with a float column becomes a float
+    let plan = planned("SELECT * FROM events WHERE amount > 2");
+    assert!(matches!(literals(&plan)[..], [Value::Float(2.0)]));
+}
+
+#[test]
+fn test_string_literal_compared_with_date_column() {
+    let plan = planned("SELECT * FROM events WHERE day = '2024-01-31'");
+    assert!(matches!(literals(&plan)[..], [Value::Date(JAN_31_2024)]));
+    let plan = planned("SELECT * FROM events WHERE day >= ' 2024-01-31 '");
+    assert!(matches!(literals(&plan)[..], [Value::Date(JAN_31_2024)]));
+}
+
+#[test]
+fn test_mismatched_comparisons_rejected() {
+    assert_type_mismatch("SELECT * FROM events WHERE amount = 'abc'");
+    assert_type_mismatch("SELECT * FROM events WHERE day = 5");
+    assert_type_mismatch("SELECT * FROM events WHERE day = '2024-13-01'");
+    assert_type_mismatch("SELECT * FROM events WHERE flag = day");
+    assert_type_mismatch("SELECT * FROM events WHERE amount");
+}
+
+#[test]
+fn test_mismatched_assignments_rejected() {
+    assert_type_mismatch("INSERT INTO events (id, day) VALUES (1, '2024-02-30')");
+    assert_type_mismatch("UPDATE events SET amount = 'abc'");
+    assert_type_mismatch("UPDATE events SET day = 5 WHERE id = 1");
+
+    // 2024 is a leap year
+    let plan = planned("INSERT INTO events (id, day) VALUES (1, '2024-02-29')");
+    assert!(matches!(
+        literals(&plan)[..],
+        [Value::BigInt(1), Value::Date(19_782)]
+    ));
+    let plan = planned("UPDATE events SET amount = 3 WHERE id = 1");
+    assert!(matches!(
+        literals(&plan)[..],
+        [Value::Float(3.0), Value::BigInt(1)] | [Value::BigInt(1), Value::Float(3.0)]
+    ));
+}
+
+#[test]
+fn test_null_assigned_to_not_null_column_rejected() {
+    for sql in [
+        "INSERT INTO events (id, name) VALUES (NULL, 'a')",
+        "UPDATE events SET id = NULL",
+    ] {
+        match plan(sql) {
+            Err(LogicalPlanError::ValidationError(message)) => {
+                assert!(message.contains("not-null"), "{}", message)
+            }
+            other => panic!("{} planned as {:?}", sql, other),
+        }
+    }
+    planned("INSERT INTO events (id, name) VALUES (1, NULL)");
+    planned("UPDATE events SET day = NULL");
+}
+
+#[test]
+fn test_case_results_unified() {
+    let plan = planned("SELECT CASE WHEN flag THEN id ELSE 1.5 END AS v FROM events");
+    assert_eq!(plan.schema().columns[0].data_type, DataType::Float);
+    let plan = planned("SELECT CASE WHEN flag THEN NULL ELSE day END AS v FROM events");
+    assert_eq!(plan.schema().columns[0].data_type, DataType::Date);
+
+    // The operand of a simple CASE types its WHEN values
+    let plan = planned("SELECT CASE day WHEN '2024-01-31' THEN 1 END AS v FROM events");
+    assert!(matches!(
+        literals(&plan)[..],
+        [Value::Date(JAN_31_2024), Value::Integer(1)]
+    ));
+
+    assert_type_mismatch("SELECT CASE WHEN flag THEN day ELSE 1 END FROM events");
+    assert_type_mismatch("SELECT CASE WHEN amount THEN 1 END FROM events");
+    assert_type_mismatch("SELECT CASE day WHEN 5 THEN 1 END FROM events");
+}
+
+#[test]
+fn test_in_list_and_between_unified() {
+    let plan = planned("SELECT * FROM events WHERE id IN (1, 2)");
+    assert!(matches!(
+        literals(&plan)[..],
+        [Value::BigInt(1), Value::BigInt(2)]
+    ));
+    let plan = planned("SELECT * FROM events WHERE day BETWEEN '2024-01-01' AND '2024-01-31'");
+    assert!(matches!(
+        literals(&plan)[..],
+        [Value::Date(19_723), Value::Date(JAN_31_2024)]
+    ));
+
+    assert_type_mismatch("SELECT * FROM events WHERE day IN ('2024-01-31', 5)");
+    assert_type_mismatch("SELECT * FROM events WHERE amount BETWEEN 'a' AND 2");
+    assert_type_mismatch("SELECT * FROM events WHERE day NOT BETWEEN 1 AND 2");
+}
+
+#[test]
+fn test_join_condition_checked() {
+    planned("SELECT a.id FROM events a JOIN events b ON a.id = b.id");
+    planned("SELECT a.id FROM events a LEFT JOIN events b ON a.day = '2024-01-31'");
+    assert_type_mismatch("SELECT a.id FROM events a JOIN events b ON a.day = 5");
+}
@@ -0,0 +1,476 @@
+use diplomat::{
+    common::LogicalPlanError,
+    expression::{BinaryOperator, Expression},
+    logical_plan::LogicalPlan,
+    plan_builder::PlanBuilder,
+    types::{ColumnDef, LogicalSchema},
+    visitor::{Recursion, Transformed, TreeNode, TreeNodeRewriter, TreeNodeVisitor},
+};
+use shared_types::{DataType, Value};
+use sqlparser::{dialect::GenericDialect, parser::Parser};
+
+/// `(a + 1) * (b - 2)`
+fn expression() -> Expression {
+    let int = |value| Expression::literal(Value::Integer(value));
+    Expression::binary_op(
+        Expression::binary_op(Expression::column("a"), BinaryOperator::Plus, int(1)),
+        BinaryOperator::Multiply,
+        Expression::binary_op(Expression::column("b"), BinaryOperator::Minus, int(2)),
+    )
+}
+
+/// What `apply` visits when `f` decides on `target` with `recursion`, and what it
+/// returns
+fn applied(target: &str, recursion: Recursion) -> (Vec<String>, Recursion) {
+    let expr = expression();
+    let mut visited = Vec::new();
+    let result = expr
+        .apply(|node| {
+            let text = node.to_string();
+            visited.push(text.clone());
+            Ok(if text == target {
+                recursion
+            } else {
+                Recursion::Continue
+            })
+        })
+        .unwrap();
+    (visited, result)
+}
+
+#[test]
+fn test_apply() {
+    assert_eq!(
+        applied("", Recursion::Continue),
+        (
+            vec![
+                "((a + Integer(1)) * (b - Integer(2)))".to_string(),
+                "(a + Integer(1))".to_string(),
+                "a".to_string(),
+                "Integer(1)".to_string(),
+                "(b - Integer(2))".to_string(),
+                "b".to_string(),
+                "Integer(2)".to_string(),
+            ],
+            Recursion::Continue
+        )
+    );
+    // The operands of `a + 1` are skipped, the rest is still visited
+    let (visited, result) = applied("(a + Integer(1))", Recursion::Jump);
+    assert_eq!(
+        visited,
+        [
+            "((a + Integer(1)) * (b - Integer(2)))",
+            "(a + Integer(1))",
+            "(b - Integer(2))",
+            "b",
+            "Integer(2)",
+        ]
+    );
+    assert_eq!(result, Recursion::Continue);
+    let (visited, result) = applied("a", Recursion::Stop);
+    assert_eq!(
+        visited,
+        [
+            "((a + Integer(1)) * (b - Integer(2)))",
+            "(a + Integer(1))",
+            "a"
+        ]
+    );
+    assert_eq!(result, Recursion::Stop);
+
+    let literal = Expression::literal(Value::Integer(1));
+    assert!(expression().exists(|node| *node == literal));
+    assert!(!expression().exists(|node| node.to_string() == "c"));
+}
+
+/// Records the calls `visit` makes, deciding on `target` with `recursion`
+struct Recorder {
+    calls: Vec<String>,
+    target: &'static str,
+    pre: Recursion,
+    post: Recursion,
+}
+
+impl Recorder {
+    fn new(target: &'static str, pre: Recursion, post: Recursion) -> Self {
+        Self {
+            calls: Vec::new(),
+            target,
+            pre,
+            post,
+        }
+    }
+
+    fn decide(&mut self, call: &str, node: &Expression, recursion: Recursion) -> Recursion {
+        let text = node.to_string();
+        self.calls.push(format!("{} {}", call, text));
+        if text == self.target {
+            recursion
+        } else {
+            Recursion::Continue
+        }
+    }
+}
+
+impl TreeNodeVisitor<Expression> for Recorder {
+    fn pre_visit(&mut self, node: &Expression) -> Result<Recursion, LogicalPlanError> {
+        Ok(self.decide("pre", node, self.pre))
+    }
+
+    fn post_visit(&mut self, node: &Expression) -> Result<Recursion, LogicalPlanError> {
+        Ok(self.decide("post", node, self.post))
+    }
+}
+
+fn visited(mut recorder: Recorder) -> (Vec<String>, Recursion) {
+    let result = expression().visit(&mut recorder).unwrap();
+    (recorder.calls, result)
+}
+
+#[test]
+fn test_visit() {
+    let (calls, result) = visited(Recorder::new(
+        "(b - Integer(2))",
+        Recursion::Jump,
+        Recursion::Continue,
+    ));
+    assert_eq!(
+        calls,
+        [
+            "pre ((a + Integer(1)) * (b - Integer(2)))",
+            "pre (a + Integer(1))",
+            "pre a",
+            "post a",
+            "pre Integer(1)",
+            "post Integer(1)",
+            "post (a + Integer(1))",
+            // Jumping over the children still ends the node
+            "pre (b - Integer(2))",
+            "post (b - Integer(2))",
+            "post ((a + Integer(1)) * (b - Integer(2)))",
+        ]
+    );
+    assert_eq!(result, Recursion::Continue);
+
+    // Stopping skips every post_visit still pending
+    let (calls, result) = visited(Recorder::new("a", Recursion::Stop, Recursion::Continue));
+    assert_eq!(
+        calls,
+        [
+            "pre ((a + Integer(1)) * (b - Integer(2)))",
+            "pre (a + Integer(1))",
+            "pre a",
+        ]
+    );
+    assert_eq!(result, Recursion::Stop);
+
+    // post_visit can stop the traversal too
+    let (calls, result) = visited(Recorder::new(
+        "(a + Integer(1))",
+        Recursion::Continue,
+        Recursion::Stop,
+    ));
+    assert_eq!(calls.last().unwrap(), "post (a + Integer(1))");
+    assert!(!calls.iter().any(|call| call.contains("pre b")));
+    assert_eq!(result, Recursion::Stop);
+}
+
+/// Adds 10 to every integer literal, deciding on `target` with `recursion`
+struct AddTen {
+    target: &'static str,
+    recursion: Recursion,
+    mutated: Vec<String>,
+}
+
+impl AddTen {
+    fn new(target: &'static str, recursion: Recursion) -> Self {
+        Self {
+            target,
+            recursion,
+            mutated: Vec::new(),
+        }
+    }
+}
+
+impl TreeNodeRewriter<Expression> for AddTen {
+    fn pre_visit(&mut self, node: &Expression) -> Result<Recursion, LogicalPlanError> {
+        Ok(if node.to_string() == self.target {
+            self.recursion
+        } else {
+            Recursion::Continue
+        })
+    }
+
+    fn mutate(&mut self, node: Expression) -> Result<Transformed<Expression>, LogicalPlanError> {
+        self.mutated.push(node.to_string());
+        Ok(match node {
+            Expression::Literal(Value::Integer(value)) => {
+                Transformed::yes(Expression::literal(Value::Integer(value + 10)))
+            }
+            other => Transformed::no(other),
+        })
+    }
+}
+
+#[test]
+fn test_rewrite() {
+    let mut rewriter = AddTen::new("", Recursion::Continue);
+    let rewritten = expression().rewrite(&mut rewriter).unwrap();
+    assert!(rewritten.transformed);
+    assert_eq!(
+        rewritten.data.to_string(),
+        "((a + Integer(11)) * (b - Integer(12)))"
+    );
+    // Children are rewritten before their parents
+    assert_eq!(
+        rewriter.mutated,
+        [
+            "a",
+            "Integer(1)",
+            "(a + Integer(11))",
+            "b",
+            "Integer(2)",
+            "(b - Integer(12))",
+            "((a + Integer(11)) * (b - Integer(12)))",
+        ]
+    );
+
+    // A jumped node is mutated, its children are not
+    let mut rewriter = AddTen::new("(b - Integer(2))", Recursion::Jump);
+    let rewritten = expression().rewrite(&mut rewriter).unwrap();
+    assert_eq!(
+        rewritten.data.to_string(),
+        "((a + Integer(11)) * (b - Integer(2)))"
+    );
+    assert!(rewriter.mutated.contains(&"(b - Integer(2))".to_string()));
+    assert!(!rewriter.mutated.contains(&"b".to_string()));
+
+    // Nothing is mutated once the traversal stops, not even the stopped node's parents
+    let mut rewriter = AddTen::new("b", Recursion::Stop);
+    let rewritten = expression().rewrite(&mut rewriter).unwrap();
+    assert!(rewritten.transformed);
+    assert_eq!(
+        rewritten.data.to_string(),
+        "((a + Integer(11)) * (b - Integer(2)))"
+    );
+    assert_eq!(rewriter.mutated, ["a", "Integer(1)", "(a + Integer(11))"]);
+
+    // Stopping before anything changed reports no change
+    let mut rewriter = AddTen::new("a", Recursion::Stop);
+    let rewritten = expression().rewrite(&mut rewriter).unwrap();
+    assert!(!rewritten.transformed);
+    assert_eq!(rewritten.data, expression());
+}
+
+#[test]
+fn test_transformed_propagation() {
+    let change_two = |node: Expression| {
+        Ok(match node {
+            Expression::Literal(Value::Integer(2)) => {
+                Transformed::yes(Expression::literal(Value::Integer(3)))
+            }
+            other => Transformed::no(other),
+        })
+    };
+    // A change deep in the tree marks the whole result as changed
+    let up = expression().transform_up(change_two).unwrap();
+    assert!(up.transformed);
+    assert_eq!(up.data.to_string(), "((a + Integer(1)) * (b - Integer(3)))");
+    let down = expression().transform_down(change_two).unwrap();
+    assert!(down.transformed);
+    assert_eq!(down.data, up.data);
+
+    let unchanged = expression()
+        .transform_up(|node| Ok(Transformed::no(node)))
+        .unwrap();
+    assert!(!unchanged.transformed);
+    assert_eq!(unchanged.data, expression());
+
+    // transform_down visits the children of the node f returns
+    let replaced = expression()
+        .transform_down(|node| {
+            Ok(match node {
+                Expression::BinaryOp {
+                    op: BinaryOperator::Minus,
+                    ..
+                } => Transformed::yes(Expression::literal(Value::Integer(2))),
+                other => Transformed::no(other),
+            })
+        })
+        .unwrap()
+        .data
+        .transform_down(change_two)
+        .unwrap();
+    assert_eq!(replaced.data.to_string(), "((a + Integer(1)) * Integer(3))");
+
+    let flagged = Transformed::no(1)
+        .update(|n| Ok(Transformed::yes(n + 1)))
+        .unwrap()
+        .update(|n| Ok(Transformed::no(n * 10)))
+        .unwrap();
+    assert_eq!(flagged, Transformed::yes(20));
+    assert_eq!(
+        Transformed::no(1).map_data(|n| Ok(n + 1)).unwrap(),
+        Transformed::no(2)
+    );
+}
+
+fn plan(sql: &str) -> LogicalPlan {
+    let schema = |columns: &[&str]| {
+        LogicalSchema::new(
+            columns
+                .iter()
+                .map(|name| ColumnDef::new(*name, DataType::Integer))
+                .collect(),
+        )
+    };
+    let statement = Parser::parse_sql(&GenericDialect {}, sql)
+        .unwrap()
+        .remove(0);
+    PlanBuilder::new()
+        .with_table_schema("customers".to_string(), schema(&["id", "region"]))
+        .with_table_schema(
+            "orders".to_string(),
+            schema(&["id", "customer_id", "amount"]),
+        )
+        .generate(&statement)
+        .unwrap()
+}
+
+const WITH_SUBQUERY: &str = "SELECT c.id FROM customers c \
+     WHERE EXISTS (SELECT o.id FROM orders o WHERE o.customer_id = c.id)";
+
+/// The kind of node, or the table for scans
+fn label(plan: &LogicalPlan) -> String {
+    match plan {
+        LogicalPlan::TableScan(scan) => scan.table.name.clone(),
+        other => other.description().split(':').next().unwrap().to_string(),
+    }
+}
+
+fn applied_with_subqueries(plan: &LogicalPlan, target: &str, recursion: Recursion) -> Vec<String> {
+    let mut visited = Vec::new();
+    plan.apply_with_subqueries(|node| {
+        let label = label(node);
+        visited.push(label.clone());
+        Ok(if label == target {
+            recursion
+        } else {
+            Recursion::Continue
+        })
+    })
+    .unwrap();
+    visited
+}
+
+#[test]
+fn test_apply_with_subqueries() {
+    let plan = plan(WITH_SUBQUERY);
+    let mut outer = Vec::new();
+    plan.apply(|node| {
+        outer.push(label(node));
+        Ok(Recursion::Continue)
+    })
+    .unwrap();
+    assert_eq!(outer, ["Projection", "Filter", "customers"]);
+
+    // The subquery comes after the node holding it and before that node's input
+    assert_eq!(
+        applied_with_subqueries(&plan, "", Recursion::Continue),
+        [
+            "Projection",
+            "Filter",
+            "Projection",
+            "Filter",
+            "orders",
+            "customers"
+        ]
+    );
+    // Jumping over a node skips its subqueries too
+    assert_eq!(
+        applied_with_subqueries(&plan, "Filter", Recursion::Jump),
+        ["Projection", "Filter"]
+    );
+    // Stopping inside a subquery ends the whole traversal
+    assert_eq!(
+        applied_with_subqueries(&plan, "orders", Recursion::Stop),
+        ["Projection", "Filter", "Projection", "Filter", "orders"]
+    );
+    let mut subqueries = 0;
+    plan.apply(|node| {
+        node.apply_subqueries(|_| {
+            subqueries += 1;
+            Ok(Recursion::Continue)
+        })
+    })
+    .unwrap();
+    assert_eq!(subqueries, 1);
+}
+
+#[test]
+fn test_transform_with_subqueries() {
+    let plan = plan(WITH_SUBQUERY);
+    let mut order = Vec::new();
+    let unchanged = plan
+        .clone()
+        .transform_up_with_subqueries(|node| {
+            order.push(label(&node));
+            Ok(Transformed::no(node))
+        })
+        .unwrap();
+    assert!(!unchanged.transformed);
+    assert_eq!(unchanged.data, plan);
+    // Inputs first, then subqueries, then the node holding them
+    assert_eq!(
+        order,
+        [
+            "customers",
+            "orders",
+            "Filter",
+            "Projection",
+            "Filter",
+            "Projection"
+        ]
+    );
+
+    // Only a scan inside the subquery changes, and the change reaches the top
+    let parallel = |node: LogicalPlan| {
+        Ok(match node {
+            LogicalPlan::TableScan(mut scan) if scan.table.name == "orders" => {
+                scan.parallelism = Some(4);
+                Transformed::yes(LogicalPlan::TableScan(scan))
+            }
+            other => Transformed::no(other),
+        })
+    };
+    for transformed in [
+        plan.clone().transform_up_with_subqueries(parallel).unwrap(),
+        plan.clone()
+            .transform_down_with_subqueries(parallel)
+            .unwrap(),
+    ] {
+        assert!(transformed.transformed);
+        let mut parallelism = Vec::new();
+        transformed
+            .data
+            .apply_with_subqueries(|node| {
+                if let LogicalPlan::TableScan(scan) = node {
+                    parallelism.push((scan.table.name.clone(), scan.parallelism));
+                }
+                Ok(Recursion::Continue)
+            })
+            .unwrap();
+        assert_eq!(
+            parallelism,
+            [
+                ("orders".to_string(), Some(4)),
+                ("customers".to_string(), None)
+            ]
+        );
+    }
+    // Without the subqueries the scan is never reached
+    let outer_only = plan.clone().transform_up(parallel).unwrap();
+    assert!(!outer_only.transformed);
+    assert_eq!(outer_only.data, plan);
+}
@@ -34,13 +34,13 @@ impl DatabaseCatalog {
     }
 
     pub fn to_bytes(&self) -> Result<Vec<u8>, CatalogError> {
-        let encoded: Vec<u8> = bincode::encode_to_vec(self, standard()).unwrap();
-        Ok(encoded)
+        bincode::encode_to_vec(self, standard()).map_err(CatalogError::SerializationError)
     }
 
     pub fn from_bytes(data: &[u8]) -> Result<Self, CatalogError> {
         let (decoded, _len): (DatabaseCatalog, usize) =
-            bincode::decode_from_slice(data, standard()).unwrap();
+            bincode::decode_from_slice(data, standard())
+                .map_err(CatalogError::DeserializationError)?;
         Ok(decoded)
     }
 
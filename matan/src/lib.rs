@@ -1,4 +1,5 @@
 pub mod common;
 pub mod database;
 pub mod manager;
+pub mod statistics;
 pub mod table;
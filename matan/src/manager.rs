@@ -1,12 +1,21 @@
 use std::path::Path;
 
-use shared_types::Schema;
+use shared_types::{Row, Schema};
 
-use crate::{common::CatalogError, database::DatabaseCatalog, table::TableCatalog};
+use crate::{
+    common::CatalogError,
+    database::DatabaseCatalog,
+    statistics::{
+        DEFAULT_SAMPLE_SIZE, DEFAULT_STATISTICS_TARGET, StatisticsCatalog, TableStatistics,
+    },
+    table::TableCatalog,
+};
 
 pub struct CatalogManager {
     pub catalog_file: String,
     pub database_catalog: DatabaseCatalog,
+    pub statistics_file: String,
+    pub statistics_catalog: StatisticsCatalog,
 }
 
 impl CatalogManager {
@@ -24,9 +33,19 @@ impl CatalogManager {
             catalog
         };
 
+        // Written by the first ANALYZE
+        let statistics_file = format!("{}.stats", catalog_file);
+        let statistics_catalog = if Path::new(&statistics_file).exists() {
+            StatisticsCatalog::load_from_file(&statistics_file)?
+        } else {
+            StatisticsCatalog::default()
+        };
+
         Ok(CatalogManager {
             catalog_file,
             database_catalog,
+            statistics_file,
+            statistics_catalog,
         })
     }
 
@@ -52,6 +71,9 @@ impl CatalogManager {
     pub fn drop_table(&mut self, table_name: &str) -> Result<(), CatalogError> {
         if self.database_catalog.tables.remove(table_name).is_some() {
             self.save_catalog()?;
+            if self.statistics_catalog.tables.remove(table_name).is_some() {
+                self.save_statistics()?;
+            }
             Ok(())
         } else {
             Err(CatalogError::TableNotFound(table_name.to_string()))
@@ -114,7 +136,54 @@ impl CatalogManager {
         }
     }
 
+    /// ANALYZE: compute the statistics of `table_name` from a sample of `rows`, the
+    /// result of scanning it, and store them in the catalog. With `columns`, only those
+    /// columns are analyzed and the others keep their earlier statistics.
+    pub fn analyze_table(
+        &mut self,
+        table_name: &str,
+        rows: impl IntoIterator<Item = Row>,
+        columns: Option<&[String]>,
+    ) -> Result<&TableStatistics, CatalogError> {
+        let schema = self
+            .get_schema(table_name)
+            .ok_or_else(|| CatalogError::TableNotFound(table_name.to_string()))?;
+        if let Some(missing) = columns
+            .unwrap_or_default()
+            .iter()
+            .find(|column| schema.get_column(column).is_none())
+        {
+            return Err(CatalogError::ColumnNotFound(missing.clone()));
+        }
+
+        let analyzed = TableStatistics::analyze(
+            schema,
+            rows,
+            columns,
+            DEFAULT_SAMPLE_SIZE,
+            DEFAULT_STATISTICS_TARGET,
+        );
+        match self.statistics_catalog.tables.get_mut(table_name) {
+            Some(existing) if columns.is_some() => existing.update(analyzed),
+            _ => {
+                self.statistics_catalog
+                    .tables
+                    .insert(table_name.to_string(), analyzed);
+            }
+        }
+        self.save_statistics()?;
+        Ok(&self.statistics_catalog.tables[table_name])
+    }
+
+    pub fn get_table_statistics(&self, table_name: &str) -> Option<&TableStatistics> {
+        self.statistics_catalog.tables.get(table_name)
+    }
+
     fn save_catalog(&self) -> Result<(), CatalogError> {
         self.database_catalog.save_to_file(&self.catalog_file)
     }
+
+    fn save_statistics(&self) -> Result<(), CatalogError> {
+        self.statistics_catalog.save_to_file(&self.statistics_file)
+    }
 }
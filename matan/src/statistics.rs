@@ -0,0 +1,287 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+};
+
+use bincode::{Decode, Encode, config::standard};
+use serde::{Deserialize, Serialize};
+use shared_types::{Row, Schema, Value};
+
+use crate::common::CatalogError;
+
+/// Rows kept by the sampled scan of `ANALYZE`
+pub const DEFAULT_SAMPLE_SIZE: usize = 30_000;
+/// Most-common values and histogram buckets kept per column
+pub const DEFAULT_STATISTICS_TARGET: usize = 100;
+
+// Table statistics gathered by ANALYZE, in the spirit of pg_class.reltuples and pg_stats
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct TableStatistics {
+    pub row_count: u64,
+    pub sampled_rows: u64,
+    pub columns: HashMap<String, ColumnStatistics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+pub struct ColumnStatistics {
+    /// Fraction of rows where the column is NULL
+    pub null_fraction: f64,
+    /// Estimated number of distinct non-null values in the whole table
+    pub distinct_count: u64,
+    #[bincode(with_serde)]
+    pub min_value: Option<Value>,
+    #[bincode(with_serde)]
+    pub max_value: Option<Value>,
+    /// Most common values, most frequent first
+    #[bincode(with_serde)]
+    pub most_common_values: Vec<Value>,
+    /// Fraction of all rows holding each of `most_common_values`
+    pub most_common_frequencies: Vec<f64>,
+    /// Bounds of an equi-depth histogram over the non-null values that are not among
+    /// `most_common_values`; empty when there are too few of them
+    #[bincode(with_serde)]
+    pub histogram_bounds: Vec<Value>,
+}
+
+/// Statistics of every analyzed table, stored in a file of their own next to the
+/// database catalog so that catalogs written before ANALYZE existed still load
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Encode, Decode)]
+pub struct StatisticsCatalog {
+    pub tables: HashMap<String, TableStatistics>,
+}
+
+impl StatisticsCatalog {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CatalogError> {
+        bincode::encode_to_vec(self, standard()).map_err(CatalogError::SerializationError)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, CatalogError> {
+        let (decoded, _len): (StatisticsCatalog, usize) =
+            bincode::decode_from_slice(data, standard())
+                .map_err(CatalogError::DeserializationError)?;
+        Ok(decoded)
+    }
+
+    pub fn save_to_file(&self, path: &str) -> Result<(), CatalogError> {
+        let data = self.to_bytes()?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+        file.write_all(&data)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, CatalogError> {
+        let mut file = File::open(path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        Self::from_bytes(&data)
+    }
+}
+
+impl TableStatistics {
+    /// Statistics of the rows of a table with `schema`, computed from a uniform sample
+    /// of at most `sample_size` rows. With `columns`, only those columns are analyzed.
+    pub fn analyze(
+        schema: &Schema,
+        rows: impl IntoIterator<Item = Row>,
+        columns: Option<&[String]>,
+        sample_size: usize,
+        statistics_target: usize,
+    ) -> Self {
+        let mut sampler = RowSampler::new(sample_size);
+        for row in rows {
+            sampler.add(row);
+        }
+        let row_count = sampler.rows_seen();
+        let sample = sampler.into_sample();
+
+        let columns = schema
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| columns.is_none_or(|names| names.contains(&column.name)))
+            .map(|(index, column)| {
+                let values = sample
+                    .iter()
+                    .map(|row| row.data.get(index).cloned().unwrap_or(Value::Null))
+                    .collect();
+                let stats = ColumnStatistics::from_sample(values, row_count, statistics_target);
+                (column.name.clone(), stats)
+            })
+            .collect();
+
+        TableStatistics {
+            row_count,
+            sampled_rows: sample.len() as u64,
+            columns,
+        }
+    }
+
+    /// Take the table-wide counts and the analyzed columns of a newer ANALYZE,
+    /// keeping the statistics of columns it left out
+    pub fn update(&mut self, newer: TableStatistics) {
+        self.row_count = newer.row_count;
+        self.sampled_rows = newer.sampled_rows;
+        self.columns.extend(newer.columns);
+    }
+}
+
+impl ColumnStatistics {
+    /// Statistics of a column from the values of `sample`, drawn from a table of
+    /// `row_count` rows
+    pub fn from_sample(sample: Vec<Value>, row_count: u64, statistics_target: usize) -> Self {
+        let sampled = sample.len();
+        let mut values: Vec<Value> = sample
+            .into_iter()
+            .filter(|value| !value.is_null())
+            .collect();
+        let null_fraction = if sampled == 0 {
+            0.0
+        } else {
+            (sampled - values.len()) as f64 / sampled as f64
+        };
+        values.sort();
+
+        // Runs of equal values in the sorted sample, as (first index, length)
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for (index, value) in values.iter().enumerate() {
+            match runs.last_mut() {
+                Some((start, len)) if values[*start].cmp(value) == Ordering::Equal => *len += 1,
+                _ => runs.push((index, 1)),
+            }
+        }
+
+        let distinct_count = estimate_distinct(
+            values.len(),
+            runs.len(),
+            runs.iter().filter(|(_, len)| *len == 1).count(),
+            row_count as f64 * (1.0 - null_fraction),
+        );
+
+        // Values noticeably more frequent than average are worth tracking exactly
+        let average = values.len() as f64 / runs.len().max(1) as f64;
+        let mut common: Vec<(usize, usize)> = runs
+            .iter()
+            .copied()
+            .filter(|(_, len)| *len > 1 && *len as f64 > average * 1.25)
+            .collect();
+        common.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        common.truncate(statistics_target);
+        let most_common_values = common
+            .iter()
+            .map(|(start, _)| values[*start].clone())
+            .collect();
+        let most_common_frequencies = common
+            .iter()
+            .map(|(_, len)| *len as f64 / sampled as f64)
+            .collect();
+
+        let mut rest = Vec::with_capacity(values.len());
+        for (start, len) in &runs {
+            if !common.iter().any(|(common_start, _)| common_start == start) {
+                rest.extend_from_slice(&values[*start..start + len]);
+            }
+        }
+        let histogram_bounds = equi_depth_bounds(&rest, statistics_target);
+
+        ColumnStatistics {
+            null_fraction,
+            distinct_count,
+            min_value: values.first().cloned(),
+            max_value: values.last().cloned(),
+            most_common_values,
+            most_common_frequencies,
+            histogram_bounds,
+        }
+    }
+}
+
+/// Haas and Stokes' Duj1 estimator of the distinct values in `total` rows, from a
+/// sample of `sampled` rows holding `distinct` values of which `singletons` occur once
+fn estimate_distinct(sampled: usize, distinct: usize, singletons: usize, total: f64) -> u64 {
+    if sampled == 0 {
+        return 0;
+    }
+    let (n, d, f1) = (sampled as f64, distinct as f64, singletons as f64);
+    let estimate = if n >= total {
+        // The sample is the whole table
+        d
+    } else if singletons == sampled {
+        // Every sampled value is unique, so the column likely is too
+        total
+    } else {
+        n * d / (n - f1 + f1 * n / total)
+    };
+    estimate.clamp(d, total.max(d)).round() as u64
+}
+
+/// `buckets + 1` bounds splitting sorted `values` into buckets of equal size, or
+/// fewer bounds when there are fewer values
+fn equi_depth_bounds(values: &[Value], buckets: usize) -> Vec<Value> {
+    if values.len() < 2 || buckets == 0 {
+        return Vec::new();
+    }
+    let buckets = buckets.min(values.len() - 1);
+    let mut bounds: Vec<Value> = (0..=buckets)
+        .map(|i| values[i * (values.len() - 1) / buckets].clone())
+        .collect();
+    bounds.dedup();
+    if bounds.len() < 2 { Vec::new() } else { bounds }
+}
+
+/// Reservoir sample of a stream of rows: every row has the same chance of ending up
+/// in the sample, whatever the length of the stream
+pub struct RowSampler {
+    capacity: usize,
+    seen: u64,
+    sample: Vec<Row>,
+    rng: u64,
+}
+
+impl RowSampler {
+    pub fn new(capacity: usize) -> Self {
+        RowSampler {
+            capacity,
+            seen: 0,
+            sample: Vec::with_capacity(capacity.min(DEFAULT_SAMPLE_SIZE)),
+            // Fixed seed so that ANALYZE of unchanged data is reproducible
+            rng: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    pub fn add(&mut self, row: Row) {
+        self.seen += 1;
+        if self.sample.len() < self.capacity {
+            self.sample.push(row);
+            return;
+        }
+        let slot = self.next_random() % self.seen;
+        if slot < self.capacity as u64 {
+            self.sample[slot as usize] = row;
+        }
+    }
+
+    pub fn rows_seen(&self) -> u64 {
+        self.seen
+    }
+
+    pub fn into_sample(self) -> Vec<Row> {
+        self.sample
+    }
+
+    // splitmix64
+    fn next_random(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
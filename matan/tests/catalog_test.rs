@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use bincode::{Decode, Encode, config::standard};
+use matan::{common::CatalogError, manager::CatalogManager};
+use shared_types::{Column, DataType, Row, Schema, Value};
+use tempfile::TempDir;
+
+/// Table entry as catalogs were written before ANALYZE existed
+#[derive(Encode, Decode)]
+struct TableCatalogV0 {
+    table_name: String,
+    #[bincode(with_serde)]
+    schema: Schema,
+    data_file_path: String,
+    first_page_id: u64,
+}
+
+#[derive(Encode, Decode)]
+struct DatabaseCatalogV0 {
+    database_name: String,
+    tables: HashMap<String, TableCatalogV0>,
+}
+
+fn points_schema() -> Schema {
+    Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::nullable("x".to_string(), DataType::Integer),
+        Column::nullable("y".to_string(), DataType::Integer),
+    ])
+}
+
+fn points(count: i64, y: impl Fn(i64) -> i64) -> Vec<Row> {
+    (0..count)
+        .map(|i| {
+            Row::new(
+                i as u64,
+                vec![
+                    Value::Integer(i),
+                    Value::Integer(i % 4),
+                    Value::Integer(y(i)),
+                ],
+            )
+        })
+        .collect()
+}
+
+fn catalog_with_points() -> (TempDir, CatalogManager) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut catalog =
+        CatalogManager::new(dir.path().join("catalog.db"), "geo".to_string()).unwrap();
+    catalog
+        .create_table(
+            "points".to_string(),
+            points_schema(),
+            "points.db".to_string(),
+        )
+        .unwrap();
+    (dir, catalog)
+}
+
+#[test]
+fn test_catalog_written_before_statistics_loads() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("catalog.db");
+    let old = DatabaseCatalogV0 {
+        database_name: "geo".to_string(),
+        tables: HashMap::from([(
+            "points".to_string(),
+            TableCatalogV0 {
+                table_name: "points".to_string(),
+                schema: points_schema(),
+                data_file_path: "points.db".to_string(),
+                first_page_id: 7,
+            },
+        )]),
+    };
+    std::fs::write(&path, bincode::encode_to_vec(&old, standard()).unwrap()).unwrap();
+
+    let mut catalog = CatalogManager::new(&path, "geo".to_string()).unwrap();
+    assert_eq!(
+        catalog.get_table_catalog("points").unwrap().first_page_id,
+        7
+    );
+    assert!(catalog.get_table_statistics("points").is_none());
+
+    catalog
+        .analyze_table("points", points(100, |i| i), None)
+        .unwrap();
+    // Statistics live in their own file, so the catalog keeps its old layout
+    let (reread, _): (DatabaseCatalogV0, usize) =
+        bincode::decode_from_slice(&std::fs::read(&path).unwrap(), standard()).unwrap();
+    assert_eq!(reread.tables["points"].first_page_id, 7);
+
+    let reopened = CatalogManager::new(&path, "geo".to_string()).unwrap();
+    assert_eq!(
+        reopened.get_table_statistics("points").unwrap().row_count,
+        100
+    );
+}
+
+#[test]
+fn test_corrupt_catalog_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("catalog.db");
+    std::fs::write(&path, [0xff, 0xff, 0xff]).unwrap();
+    assert!(matches!(
+        CatalogManager::new(&path, "geo".to_string()),
+        Err(CatalogError::DeserializationError(_))
+    ));
+
+    let (dir, mut catalog) = catalog_with_points();
+    catalog
+        .analyze_table("points", points(10, |i| i), None)
+        .unwrap();
+    std::fs::write(dir.path().join("catalog.db.stats"), [0xff]).unwrap();
+    assert!(matches!(
+        CatalogManager::new(dir.path().join("catalog.db"), "geo".to_string()),
+        Err(CatalogError::DeserializationError(_))
+    ));
+}
+
+#[test]
+fn test_analyze_column_list() {
+    let (_dir, mut catalog) = catalog_with_points();
+    catalog
+        .analyze_table("points", points(100, |i| i), None)
+        .unwrap();
+    assert_eq!(
+        catalog.get_table_statistics("points").unwrap().columns["y"].distinct_count,
+        100
+    );
+
+    // Only y is recomputed; x keeps the statistics of the first ANALYZE
+    let only_y = ["y".to_string()];
+    let stats = catalog
+        .analyze_table("points", points(200, |_| 1), Some(&only_y))
+        .unwrap();
+    assert_eq!(stats.row_count, 200);
+    assert_eq!(stats.columns["y"].distinct_count, 1);
+    assert_eq!(stats.columns["x"].distinct_count, 4);
+    assert_eq!(stats.columns["id"].distinct_count, 100);
+
+    let unknown = ["z".to_string()];
+    assert!(matches!(
+        catalog.analyze_table("points", points(1, |i| i), Some(&unknown)),
+        Err(CatalogError::ColumnNotFound(column)) if column == "z"
+    ));
+    assert!(matches!(
+        catalog.analyze_table("lines", points(1, |i| i), None),
+        Err(CatalogError::TableNotFound(_))
+    ));
+}
+
+#[test]
+fn test_drop_table_forgets_statistics() {
+    let (dir, mut catalog) = catalog_with_points();
+    catalog
+        .analyze_table("points", points(10, |i| i), None)
+        .unwrap();
+    catalog.drop_table("points").unwrap();
+    assert!(catalog.get_table_statistics("points").is_none());
+
+    let reopened = CatalogManager::new(dir.path().join("catalog.db"), "geo".to_string()).unwrap();
+    assert!(reopened.get_table_statistics("points").is_none());
+}
@@ -0,0 +1,157 @@
+use matan::statistics::{ColumnStatistics, RowSampler, TableStatistics};
+use shared_types::{Column, DataType, Row, Schema, Value};
+
+fn integers(values: impl IntoIterator<Item = i64>) -> Vec<Value> {
+    values.into_iter().map(Value::Integer).collect()
+}
+
+#[test]
+fn test_all_equal_column() {
+    let stats = ColumnStatistics::from_sample(integers(vec![7; 1000]), 1000, 100);
+    assert_eq!(stats.distinct_count, 1);
+    assert_eq!(stats.null_fraction, 0.0);
+    assert_eq!(stats.min_value, Some(Value::Integer(7)));
+    assert_eq!(stats.max_value, Some(Value::Integer(7)));
+    // A single value is no more common than the average, and spans no range
+    assert!(stats.most_common_values.is_empty());
+    assert!(stats.histogram_bounds.is_empty());
+}
+
+#[test]
+fn test_all_unique_column() {
+    // A sample of 1000 distinct values from a table of 10000 rows
+    let stats = ColumnStatistics::from_sample(integers(0..1000), 10_000, 100);
+    assert_eq!(stats.distinct_count, 10_000);
+    assert!(stats.most_common_values.is_empty());
+    assert_eq!(stats.histogram_bounds.len(), 101);
+    assert_eq!(stats.histogram_bounds.first(), Some(&Value::Integer(0)));
+    assert_eq!(stats.histogram_bounds.last(), Some(&Value::Integer(999)));
+    assert_eq!(stats.histogram_bounds[50], Value::Integer(499));
+
+    // When the sample is the whole table, the sample's count is exact
+    let stats = ColumnStatistics::from_sample(integers(0..1000), 1000, 100);
+    assert_eq!(stats.distinct_count, 1000);
+}
+
+#[test]
+fn test_skewed_column() {
+    // Half the rows hold 0, the other half are 1..=500 once each
+    let values = integers(std::iter::repeat_n(0, 500).chain(1..=500));
+    let stats = ColumnStatistics::from_sample(values, 1000, 100);
+    assert_eq!(stats.distinct_count, 501);
+    assert_eq!(stats.most_common_values, vec![Value::Integer(0)]);
+    assert_eq!(stats.most_common_frequencies, vec![0.5]);
+    // The histogram only covers the values outside the MCV list
+    assert_eq!(stats.histogram_bounds.first(), Some(&Value::Integer(1)));
+    assert_eq!(stats.histogram_bounds.last(), Some(&Value::Integer(500)));
+    assert_eq!(stats.histogram_bounds.len(), 101);
+}
+
+#[test]
+fn test_most_common_values_are_ordered_by_frequency() {
+    let values = integers(
+        std::iter::repeat_n(3, 30)
+            .chain(std::iter::repeat_n(1, 50))
+            .chain(std::iter::repeat_n(2, 30))
+            .chain(100..200),
+    );
+    let stats = ColumnStatistics::from_sample(values, 210, 2);
+    // Ties go to the smaller value, and the target caps the list
+    assert_eq!(
+        stats.most_common_values,
+        vec![Value::Integer(1), Value::Integer(2)]
+    );
+    assert_eq!(
+        stats.most_common_frequencies,
+        vec![50.0 / 210.0, 30.0 / 210.0]
+    );
+    assert_eq!(stats.histogram_bounds.len(), 3);
+}
+
+#[test]
+fn test_distinct_estimate_from_partial_sample() {
+    // 50 values seen twice and 100 seen once, sampled from 2000 rows: Duj1 gives
+    // 200 * 150 / (200 - 100 + 100 * 200 / 2000) = 272.7
+    let values = integers((0..50).chain(0..50).chain(1000..1100));
+    let stats = ColumnStatistics::from_sample(values, 2000, 100);
+    assert_eq!(stats.distinct_count, 273);
+}
+
+#[test]
+fn test_all_null_column() {
+    let stats = ColumnStatistics::from_sample(vec![Value::Null; 100], 100, 100);
+    assert_eq!(stats.null_fraction, 1.0);
+    assert_eq!(stats.distinct_count, 0);
+    assert_eq!(stats.min_value, None);
+    assert_eq!(stats.max_value, None);
+    assert!(stats.most_common_values.is_empty());
+    assert!(stats.histogram_bounds.is_empty());
+
+    let stats = ColumnStatistics::from_sample(
+        vec![
+            Value::Null,
+            Value::Integer(1),
+            Value::Null,
+            Value::Integer(2),
+        ],
+        4,
+        100,
+    );
+    assert_eq!(stats.null_fraction, 0.5);
+    assert_eq!(stats.distinct_count, 2);
+}
+
+fn numbered_rows(count: u64) -> impl Iterator<Item = Row> {
+    (0..count).map(|i| {
+        Row::new(
+            i,
+            vec![Value::Integer(i as i64), Value::Integer(i as i64 % 10)],
+        )
+    })
+}
+
+#[test]
+fn test_sampler_keeps_a_uniform_subset() {
+    let sample = |capacity| {
+        let mut sampler = RowSampler::new(capacity);
+        numbered_rows(10_000).for_each(|row| sampler.add(row));
+        assert_eq!(sampler.rows_seen(), 10_000);
+        sampler.into_sample()
+    };
+
+    let rows = sample(100);
+    assert_eq!(rows.len(), 100);
+    let mut ids: Vec<u64> = rows.iter().map(|row| row.id).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 100);
+    // Rows from the whole stream replace the first ones, not just its head
+    assert!(ids.iter().filter(|id| **id >= 5000).count() > 25);
+    // The seed is fixed, so the same input gives the same sample
+    let again: Vec<u64> = sample(100).iter().map(|row| row.id).collect();
+    assert_eq!(again, rows.iter().map(|row| row.id).collect::<Vec<_>>());
+
+    // A stream shorter than the capacity is kept whole
+    assert_eq!(sample(20_000).len(), 10_000);
+}
+
+#[test]
+fn test_analyze_sample_smaller_than_table() {
+    let schema = Schema::new(vec![
+        Column::primary_key("id".to_string(), DataType::Integer),
+        Column::nullable("bucket".to_string(), DataType::Integer),
+    ]);
+    let stats = TableStatistics::analyze(&schema, numbered_rows(10_000), None, 100, 10);
+    assert_eq!(stats.row_count, 10_000);
+    assert_eq!(stats.sampled_rows, 100);
+    // Every bucket value is seen several times, so the sample already has them all
+    assert_eq!(stats.columns["bucket"].distinct_count, 10);
+    // Every id is seen once, so the id column is estimated unique
+    assert_eq!(stats.columns["id"].distinct_count, 10_000);
+
+    let only_bucket = ["bucket".to_string()];
+    let stats =
+        TableStatistics::analyze(&schema, numbered_rows(10_000), Some(&only_bucket), 100, 10);
+    assert_eq!(stats.columns.len(), 1);
+    assert!(stats.columns.contains_key("bucket"));
+}
@@ -0,0 +1,367 @@
+//! Fixed-point decimal numbers for exact arithmetic
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use crate::error::StorageError;
+
+/// Largest precision (total significant digits) a decimal can hold
+pub const MAX_DECIMAL_PRECISION: u8 = 38;
+
+/// Extra fractional digits kept when dividing, on top of the operands' scale
+pub const DIVISION_EXTRA_SCALE: u8 = 6;
+
+/// A decimal number stored as `mantissa * 10^-scale`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// `mantissa * 10^-scale`. A scale beyond `MAX_DECIMAL_PRECISION` is brought down
+    /// to it, rounding the mantissa half away from zero so the value is kept.
+    pub fn new(mantissa: i128, scale: u8) -> Self {
+        if scale <= MAX_DECIMAL_PRECISION {
+            return Self { mantissa, scale };
+        }
+        let mantissa = match pow10(scale - MAX_DECIMAL_PRECISION) {
+            Some(factor) => div_round(mantissa, factor),
+            // Wider than any i128, so nothing is left at the maximum scale
+            None => 0,
+        };
+        Self {
+            mantissa,
+            scale: MAX_DECIMAL_PRECISION,
+        }
+    }
+
+    pub fn from_integer(value: i128) -> Self {
+        Self::new(value, 0)
+    }
+
+    /// Convert a float, rounding half away from zero to `scale` digits
+    pub fn from_f64(value: f64, scale: u8) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        let scaled = (value * 10f64.powi(scale as i32)).round();
+        if scaled.abs() >= 1e38 {
+            return None;
+        }
+        Some(Self::new(scaled as i128, scale))
+    }
+
+    /// The exact value of a float, when it fits in a decimal of at most
+    /// `MAX_DECIMAL_PRECISION` fractional digits
+    pub fn from_f64_exact(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        if value == 0.0 {
+            return Some(Self::default());
+        }
+        // `value` is `mantissa * 2^exponent`
+        let bits = value.to_bits();
+        let biased_exponent = ((bits >> 52) & 0x7ff) as i32;
+        let mut mantissa = (bits & 0x000f_ffff_ffff_ffff) as i128;
+        let mut exponent = if biased_exponent == 0 {
+            -1074
+        } else {
+            mantissa |= 1 << 52;
+            biased_exponent - 1075
+        };
+        while mantissa % 2 == 0 {
+            mantissa /= 2;
+            exponent += 1;
+        }
+        let (mantissa, scale) = if exponent >= 0 {
+            (
+                mantissa.checked_mul(2i128.checked_pow(exponent as u32)?)?,
+                0,
+            )
+        } else {
+            // 2^-n is 5^n * 10^-n
+            let scale = u8::try_from(-exponent)
+                .ok()
+                .filter(|scale| *scale <= MAX_DECIMAL_PRECISION)?;
+            (
+                mantissa.checked_mul(5i128.checked_pow(scale as u32)?)?,
+                scale,
+            )
+        };
+        let mantissa = if value < 0.0 { -mantissa } else { mantissa };
+        Some(Self::new(mantissa, scale))
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// Number of significant digits in the mantissa
+    pub fn precision(&self) -> u8 {
+        let mut digits = 1;
+        let mut rest = self.mantissa.unsigned_abs() / 10;
+        while rest > 0 {
+            digits += 1;
+            rest /= 10;
+        }
+        digits.max(self.scale)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.mantissa as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    /// The integer value when there is no fractional part
+    pub fn to_integer(&self) -> Option<i128> {
+        let factor = pow10(self.scale)?;
+        if self.mantissa % factor == 0 {
+            Some(self.mantissa / factor)
+        } else {
+            None
+        }
+    }
+
+    /// Change the scale, rounding half away from zero when digits are dropped
+    pub fn rescale(&self, scale: u8) -> Option<Self> {
+        match scale.cmp(&self.scale) {
+            Ordering::Equal => Some(*self),
+            Ordering::Greater => {
+                let factor = pow10(scale - self.scale)?;
+                Some(Self::new(self.mantissa.checked_mul(factor)?, scale))
+            }
+            Ordering::Less => {
+                let factor = pow10(self.scale - scale)?;
+                Some(Self::new(div_round(self.mantissa, factor), scale))
+            }
+        }
+    }
+
+    /// Round to `scale` fractional digits
+    pub fn round(&self, scale: u8) -> Self {
+        if scale >= self.scale {
+            *self
+        } else {
+            self.rescale(scale).unwrap_or(*self)
+        }
+    }
+
+    /// Check the value fits `DECIMAL(precision, scale)`, rounding to that scale
+    pub fn fit(&self, precision: u8, scale: u8) -> Result<Self, StorageError> {
+        let rounded = self
+            .rescale(scale)
+            .ok_or_else(|| self.overflow(precision, scale))?;
+        let integer_digits = precision.saturating_sub(scale) as u32;
+        let limit = pow10(scale)
+            .and_then(|f| 10i128.checked_pow(integer_digits)?.checked_mul(f))
+            .unwrap_or(i128::MAX);
+        if rounded.mantissa.unsigned_abs() >= limit as u128 {
+            return Err(self.overflow(precision, scale));
+        }
+        Ok(rounded)
+    }
+
+    fn overflow(&self, precision: u8, scale: u8) -> StorageError {
+        StorageError::InvalidData(format!(
+            "Decimal {} does not fit DECIMAL({}, {})",
+            self, precision, scale
+        ))
+    }
+
+    fn aligned(&self, other: &Self) -> Option<(i128, i128, u8)> {
+        let scale = self.scale.max(other.scale);
+        Some((
+            self.rescale(scale)?.mantissa,
+            other.rescale(scale)?.mantissa,
+            scale,
+        ))
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let (a, b, scale) = self.aligned(other)?;
+        Some(Self::new(a.checked_add(b)?, scale))
+    }
+
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        let (a, b, scale) = self.aligned(other)?;
+        Some(Self::new(a.checked_sub(b)?, scale))
+    }
+
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let mantissa = self.mantissa.checked_mul(other.mantissa)?;
+        let scale = self.scale + other.scale;
+        if scale > MAX_DECIMAL_PRECISION {
+            let factor = pow10(scale - MAX_DECIMAL_PRECISION)?;
+            Some(Self::new(
+                div_round(mantissa, factor),
+                MAX_DECIMAL_PRECISION,
+            ))
+        } else {
+            Some(Self::new(mantissa, scale))
+        }
+    }
+
+    /// Divide, keeping `DIVISION_EXTRA_SCALE` more digits than the wider operand.
+    /// Returns `None` on division by zero or overflow.
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        let scale = (self.scale.max(other.scale) + DIVISION_EXTRA_SCALE).min(MAX_DECIMAL_PRECISION);
+        self.checked_div_with_scale(other, scale)
+    }
+
+    pub fn checked_div_with_scale(&self, other: &Self, scale: u8) -> Option<Self> {
+        if other.mantissa == 0 {
+            return None;
+        }
+        // self / other = (a * 10^-sa) / (b * 10^-sb); shift a so the quotient lands on `scale`
+        let shift = scale as i32 + other.scale as i32 - self.scale as i32;
+        let (numerator, denominator) = if shift >= 0 {
+            (
+                self.mantissa.checked_mul(pow10(shift as u8)?)?,
+                other.mantissa,
+            )
+        } else {
+            (
+                self.mantissa,
+                other.mantissa.checked_mul(pow10((-shift) as u8)?)?,
+            )
+        };
+        Some(Self::new(div_round(numerator, denominator), scale))
+    }
+
+    pub fn checked_rem(&self, other: &Self) -> Option<Self> {
+        let (a, b, scale) = self.aligned(other)?;
+        Some(Self::new(a.checked_rem(b)?, scale))
+    }
+
+    /// Returns `None` when the mantissa is `i128::MIN`, which has no positive counterpart
+    pub fn checked_neg(&self) -> Option<Self> {
+        Some(Self::new(self.mantissa.checked_neg()?, self.scale))
+    }
+
+    pub fn checked_abs(&self) -> Option<Self> {
+        Some(Self::new(self.mantissa.checked_abs()?, self.scale))
+    }
+
+    /// Same value with trailing fractional zeros removed
+    pub fn normalize(&self) -> Self {
+        let mut result = *self;
+        while result.scale > 0 && result.mantissa % 10 == 0 {
+            result.mantissa /= 10;
+            result.scale -= 1;
+        }
+        result
+    }
+}
+
+fn pow10(exp: u8) -> Option<i128> {
+    10i128.checked_pow(exp as u32)
+}
+
+/// Integer division rounding half away from zero
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    if remainder.unsigned_abs() * 2 >= denominator.unsigned_abs() {
+        if (numerator < 0) == (denominator < 0) {
+            quotient + 1
+        } else {
+            quotient - 1
+        }
+    } else {
+        quotient
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.aligned(other) {
+            Some((a, b, _)) => a.cmp(&b),
+            // Rescaling overflowed: the mantissas are far apart, floats are accurate enough
+            None => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let normalized = self.normalize();
+        normalized.mantissa.hash(state);
+        normalized.scale.hash(state);
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let padded = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer, fraction) = padded.split_at(padded.len() - scale);
+        write!(f, "{}{}.{}", sign, integer, fraction)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = StorageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || StorageError::InvalidInput(format!("Invalid decimal: '{}'", s));
+        let trimmed = s.trim();
+        let (negative, unsigned) = match trimmed.as_bytes().first() {
+            Some(b'-') => (true, &trimmed[1..]),
+            Some(b'+') => (false, &trimmed[1..]),
+            _ => (false, trimmed),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if integer.is_empty() && fraction.is_empty()
+            || !integer
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|b| b.is_ascii_digit())
+            || fraction.len() > MAX_DECIMAL_PRECISION as usize
+        {
+            return Err(invalid());
+        }
+
+        let mut mantissa: i128 = 0;
+        for digit in integer.bytes().chain(fraction.bytes()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add((digit - b'0') as i128))
+                .ok_or_else(invalid)?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+        Ok(Self::new(mantissa, fraction.len() as u8))
+    }
+}
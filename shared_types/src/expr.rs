@@ -0,0 +1,208 @@
+//! Scalar expressions evaluated by the execution layer
+
+use crate::{schema::DataType, value::Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Column(String),
+    Literal(Value),
+    Binary {
+        left: Box<Expr>,
+        op: BinaryOp,
+        right: Box<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr>,
+    },
+    Function {
+        name: String,
+        args: Vec<Expr>,
+    },
+    Case {
+        operand: Option<Box<Expr>>,
+        when_then: Vec<(Expr, Expr)>,
+        else_expr: Option<Box<Expr>>,
+    },
+    Cast {
+        expr: Box<Expr>,
+        data_type: DataType,
+    },
+    IsNull(Box<Expr>),
+    IsNotNull(Box<Expr>),
+    InList {
+        expr: Box<Expr>,
+        list: Vec<Expr>,
+        negated: bool,
+    },
+    Between {
+        expr: Box<Expr>,
+        low: Box<Expr>,
+        high: Box<Expr>,
+        negated: bool,
+    },
+    Like {
+        expr: Box<Expr>,
+        pattern: Box<Expr>,
+        negated: bool,
+        case_insensitive: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    And,
+    Or,
+    StringConcat,
+    IsDistinctFrom,
+    IsNotDistinctFrom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Plus,
+    Minus,
+    Not,
+}
+
+impl Expr {
+    pub fn column(name: impl Into<String>) -> Self {
+        Expr::Column(name.into())
+    }
+    pub fn literal(value: Value) -> Self {
+        Expr::Literal(value)
+    }
+    pub fn binary(left: Expr, op: BinaryOp, right: Expr) -> Self {
+        Expr::Binary {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        }
+    }
+    pub fn unary(op: UnaryOp, expr: Expr) -> Self {
+        Expr::Unary {
+            op,
+            expr: Box::new(expr),
+        }
+    }
+    pub fn function(name: impl Into<String>, args: Vec<Expr>) -> Self {
+        Expr::Function {
+            name: name.into(),
+            args,
+        }
+    }
+    pub fn cast(expr: Expr, data_type: DataType) -> Self {
+        Expr::Cast {
+            expr: Box::new(expr),
+            data_type,
+        }
+    }
+    pub fn and(left: Expr, right: Expr) -> Self {
+        Self::binary(left, BinaryOp::And, right)
+    }
+    pub fn or(left: Expr, right: Expr) -> Self {
+        Self::binary(left, BinaryOp::Or, right)
+    }
+    pub fn equals(left: Expr, right: Expr) -> Self {
+        Self::binary(left, BinaryOp::Eq, right)
+    }
+    pub fn multiply(left: Expr, right: Expr) -> Self {
+        Self::binary(left, BinaryOp::Multiply, right)
+    }
+    pub fn divide(left: Expr, right: Expr) -> Self {
+        Self::binary(left, BinaryOp::Divide, right)
+    }
+    pub fn is_null(expr: Expr) -> Self {
+        Expr::IsNull(Box::new(expr))
+    }
+    pub fn in_list(expr: Expr, list: Vec<Expr>, negated: bool) -> Self {
+        Expr::InList {
+            expr: Box::new(expr),
+            list,
+            negated,
+        }
+    }
+    pub fn between(expr: Expr, low: Expr, high: Expr, negated: bool) -> Self {
+        Expr::Between {
+            expr: Box::new(expr),
+            low: Box::new(low),
+            high: Box::new(high),
+            negated,
+        }
+    }
+
+    /// Names of every column referenced by this expression
+    pub fn column_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_column_names(&mut names);
+        names
+    }
+
+    fn collect_column_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Expr::Column(name) => {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+            Expr::Literal(_) => {}
+            Expr::Binary { left, right, .. } => {
+                left.collect_column_names(names);
+                right.collect_column_names(names);
+            }
+            Expr::Unary { expr, .. }
+            | Expr::Cast { expr, .. }
+            | Expr::IsNull(expr)
+            | Expr::IsNotNull(expr) => expr.collect_column_names(names),
+            Expr::Function { args, .. } => {
+                for arg in args {
+                    arg.collect_column_names(names);
+                }
+            }
+            Expr::Case {
+                operand,
+                when_then,
+                else_expr,
+            } => {
+                if let Some(operand) = operand {
+                    operand.collect_column_names(names);
+                }
+                for (when, then) in when_then {
+                    when.collect_column_names(names);
+                    then.collect_column_names(names);
+                }
+                if let Some(else_expr) = else_expr {
+                    else_expr.collect_column_names(names);
+                }
+            }
+            Expr::InList { expr, list, .. } => {
+                expr.collect_column_names(names);
+                for item in list {
+                    item.collect_column_names(names);
+                }
+            }
+            Expr::Between {
+                expr, low, high, ..
+            } => {
+                expr.collect_column_names(names);
+                low.collect_column_names(names);
+                high.collect_column_names(names);
+            }
+            Expr::Like { expr, pattern, .. } => {
+                expr.collect_column_names(names);
+                pattern.collect_column_names(names);
+            }
+        }
+    }
+}
@@ -0,0 +1,452 @@
+//! Built-in scalar functions and their type signatures, shared by the planner,
+//! which type-checks calls, and the execution layer, which evaluates them
+
+use std::fmt;
+
+use crate::decimal::MAX_DECIMAL_PRECISION;
+use crate::schema::DataType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScalarFunction {
+    // String
+    Lower,
+    Upper,
+    Substr,
+    Trim,
+    Ltrim,
+    Rtrim,
+    Length,
+    Concat,
+    Replace,
+    // Math
+    Abs,
+    Round,
+    Floor,
+    Ceil,
+    Power,
+    Mod,
+    // Conditional
+    Coalesce,
+    NullIf,
+    Greatest,
+    Least,
+    // Date/time
+    DateTrunc,
+    Extract,
+    Now,
+    DateAdd,
+}
+
+/// Family of types a parameter accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamType {
+    Any,
+    Text,
+    Numeric,
+    Integer,
+    /// DATE, TIME, TIMESTAMP or DATETIME
+    Temporal,
+}
+
+impl ParamType {
+    pub fn accepts(&self, data_type: &DataType) -> bool {
+        match self {
+            ParamType::Any => true,
+            ParamType::Text => is_text(data_type),
+            ParamType::Numeric => numeric_rank(data_type).is_some(),
+            ParamType::Integer => numeric_rank(data_type).is_some_and(|rank| rank <= 3),
+            ParamType::Temporal => is_temporal(data_type),
+        }
+    }
+}
+
+impl fmt::Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ParamType::Any => "any type",
+            ParamType::Text => "text",
+            ParamType::Numeric => "numeric",
+            ParamType::Integer => "integer",
+            ParamType::Temporal => "date/time",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How a function's result type follows from its argument types
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReturnType {
+    Fixed(DataType),
+    /// The type of the argument at this position
+    Argument(usize),
+    /// The common type all arguments convert to
+    Common,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSignature {
+    pub params: Vec<ParamType>,
+    /// Leading parameters that must be given; the rest are optional
+    pub required: usize,
+    /// Whether the last parameter may repeat
+    pub variadic: bool,
+    pub returns: ReturnType,
+}
+
+impl FunctionSignature {
+    fn new(params: Vec<ParamType>, required: usize, returns: ReturnType) -> Self {
+        Self {
+            params,
+            required,
+            variadic: false,
+            returns,
+        }
+    }
+
+    fn variadic(param: ParamType, returns: ReturnType) -> Self {
+        Self {
+            params: vec![param],
+            required: 1,
+            variadic: true,
+            returns,
+        }
+    }
+
+    /// Parameter type of the argument at `position`
+    pub fn param(&self, position: usize) -> Option<ParamType> {
+        match self.params.get(position) {
+            Some(param) => Some(*param),
+            None if self.variadic => self.params.last().copied(),
+            None => None,
+        }
+    }
+
+    pub fn accepts_argument_count(&self, count: usize) -> bool {
+        count >= self.required && (self.variadic || count <= self.params.len())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignatureError {
+    UnknownFunction(String),
+    WrongArgumentCount {
+        function: ScalarFunction,
+        expected: String,
+        found: usize,
+    },
+    TypeMismatch {
+        function: ScalarFunction,
+        position: usize,
+        expected: String,
+        found: DataType,
+    },
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::UnknownFunction(name) => write!(f, "Unknown function '{}'", name),
+            SignatureError::WrongArgumentCount {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} takes {} arguments, got {}",
+                function.name(),
+                expected,
+                found
+            ),
+            SignatureError::TypeMismatch {
+                function,
+                position,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Argument {} of {} must be {}, got {:?}",
+                position + 1,
+                function.name(),
+                expected,
+                found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+impl ScalarFunction {
+    pub const ALL: [ScalarFunction; 23] = [
+        ScalarFunction::Lower,
+        ScalarFunction::Upper,
+        ScalarFunction::Substr,
+        ScalarFunction::Trim,
+        ScalarFunction::Ltrim,
+        ScalarFunction::Rtrim,
+        ScalarFunction::Length,
+        ScalarFunction::Concat,
+        ScalarFunction::Replace,
+        ScalarFunction::Abs,
+        ScalarFunction::Round,
+        ScalarFunction::Floor,
+        ScalarFunction::Ceil,
+        ScalarFunction::Power,
+        ScalarFunction::Mod,
+        ScalarFunction::Coalesce,
+        ScalarFunction::NullIf,
+        ScalarFunction::Greatest,
+        ScalarFunction::Least,
+        ScalarFunction::DateTrunc,
+        ScalarFunction::Extract,
+        ScalarFunction::Now,
+        ScalarFunction::DateAdd,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "char_length" | "character_length" => Some(ScalarFunction::Length),
+            "substring" => Some(ScalarFunction::Substr),
+            "ceiling" => Some(ScalarFunction::Ceil),
+            "pow" => Some(ScalarFunction::Power),
+            lower => Self::ALL
+                .into_iter()
+                .find(|function| function.name() == lower),
+        }
+    }
+
+    /// Canonical lower-case name
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScalarFunction::Lower => "lower",
+            ScalarFunction::Upper => "upper",
+            ScalarFunction::Substr => "substr",
+            ScalarFunction::Trim => "trim",
+            ScalarFunction::Ltrim => "ltrim",
+            ScalarFunction::Rtrim => "rtrim",
+            ScalarFunction::Length => "length",
+            ScalarFunction::Concat => "concat",
+            ScalarFunction::Replace => "replace",
+            ScalarFunction::Abs => "abs",
+            ScalarFunction::Round => "round",
+            ScalarFunction::Floor => "floor",
+            ScalarFunction::Ceil => "ceil",
+            ScalarFunction::Power => "power",
+            ScalarFunction::Mod => "mod",
+            ScalarFunction::Coalesce => "coalesce",
+            ScalarFunction::NullIf => "nullif",
+            ScalarFunction::Greatest => "greatest",
+            ScalarFunction::Least => "least",
+            ScalarFunction::DateTrunc => "date_trunc",
+            ScalarFunction::Extract => "extract",
+            ScalarFunction::Now => "now",
+            ScalarFunction::DateAdd => "date_add",
+        }
+    }
+
+    pub fn signature(&self) -> FunctionSignature {
+        use ParamType::*;
+        let string = || ReturnType::Fixed(DataType::String);
+        match self {
+            ScalarFunction::Lower | ScalarFunction::Upper => {
+                FunctionSignature::new(vec![Text], 1, string())
+            }
+            // substr(text, start [, length])
+            ScalarFunction::Substr => {
+                FunctionSignature::new(vec![Text, Integer, Integer], 2, string())
+            }
+            // trim(text [, characters])
+            ScalarFunction::Trim | ScalarFunction::Ltrim | ScalarFunction::Rtrim => {
+                FunctionSignature::new(vec![Text, Text], 1, string())
+            }
+            ScalarFunction::Length => {
+                FunctionSignature::new(vec![Text], 1, ReturnType::Fixed(DataType::Integer))
+            }
+            ScalarFunction::Concat => FunctionSignature::variadic(Any, string()),
+            // replace(text, from, to)
+            ScalarFunction::Replace => FunctionSignature::new(vec![Text, Text, Text], 3, string()),
+            ScalarFunction::Abs | ScalarFunction::Floor | ScalarFunction::Ceil => {
+                FunctionSignature::new(vec![Numeric], 1, ReturnType::Argument(0))
+            }
+            // round(value [, digits])
+            ScalarFunction::Round => {
+                FunctionSignature::new(vec![Numeric, Integer], 1, ReturnType::Argument(0))
+            }
+            ScalarFunction::Power => FunctionSignature::new(
+                vec![Numeric, Numeric],
+                2,
+                ReturnType::Fixed(DataType::Float),
+            ),
+            ScalarFunction::Mod => {
+                FunctionSignature::new(vec![Numeric, Numeric], 2, ReturnType::Common)
+            }
+            ScalarFunction::Coalesce | ScalarFunction::Greatest | ScalarFunction::Least => {
+                FunctionSignature::variadic(Any, ReturnType::Common)
+            }
+            ScalarFunction::NullIf => {
+                FunctionSignature::new(vec![Any, Any], 2, ReturnType::Argument(0))
+            }
+            // date_trunc(unit, value)
+            ScalarFunction::DateTrunc => {
+                FunctionSignature::new(vec![Text, Temporal], 2, ReturnType::Argument(1))
+            }
+            // extract(field, value)
+            ScalarFunction::Extract => FunctionSignature::new(
+                vec![Text, Temporal],
+                2,
+                ReturnType::Fixed(DataType::Integer),
+            ),
+            ScalarFunction::Now => {
+                FunctionSignature::new(vec![], 0, ReturnType::Fixed(DataType::Timestamp))
+            }
+            // date_add(value, amount [, unit]), the unit defaulting to days
+            ScalarFunction::DateAdd => {
+                FunctionSignature::new(vec![Temporal, Integer, Text], 2, ReturnType::Argument(0))
+            }
+        }
+    }
+
+    /// Whether repeated calls with the same arguments give the same result
+    pub fn is_deterministic(&self) -> bool {
+        *self != ScalarFunction::Now
+    }
+
+    pub fn check_argument_count(&self, count: usize) -> Result<(), SignatureError> {
+        let signature = self.signature();
+        if signature.accepts_argument_count(count) {
+            return Ok(());
+        }
+        let expected = if signature.variadic {
+            format!("at least {}", signature.required)
+        } else if signature.required == signature.params.len() {
+            signature.required.to_string()
+        } else {
+            format!("{} to {}", signature.required, signature.params.len())
+        };
+        Err(SignatureError::WrongArgumentCount {
+            function: *self,
+            expected,
+            found: count,
+        })
+    }
+
+    /// Check a call's arguments and derive its result type. Arguments of unknown type,
+    /// such as NULL literals, match any parameter; the result is `None` when it
+    /// depends only on unknown types.
+    pub fn return_type(
+        &self,
+        arguments: &[Option<DataType>],
+    ) -> Result<Option<DataType>, SignatureError> {
+        self.check_argument_count(arguments.len())?;
+        let signature = self.signature();
+        for (position, argument) in arguments.iter().enumerate() {
+            if let (Some(data_type), Some(param)) = (argument, signature.param(position))
+                && !param.accepts(data_type)
+            {
+                return Err(SignatureError::TypeMismatch {
+                    function: *self,
+                    position,
+                    expected: param.to_string(),
+                    found: data_type.clone(),
+                });
+            }
+        }
+
+        match signature.returns {
+            ReturnType::Fixed(data_type) => Ok(Some(data_type)),
+            ReturnType::Argument(position) => Ok(arguments.get(position).cloned().flatten()),
+            ReturnType::Common => {
+                let mut common: Option<DataType> = None;
+                for (position, argument) in arguments.iter().enumerate() {
+                    let Some(data_type) = argument else {
+                        continue;
+                    };
+                    common = match common {
+                        None => Some(data_type.clone()),
+                        Some(current) => {
+                            Some(common_type(&current, data_type).ok_or_else(|| {
+                                SignatureError::TypeMismatch {
+                                    function: *self,
+                                    position,
+                                    expected: format!("a type compatible with {:?}", current),
+                                    found: data_type.clone(),
+                                }
+                            })?)
+                        }
+                    };
+                }
+                Ok(common)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ScalarFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The type both `a` and `b` convert to without losing their meaning: the wider
+/// numeric type, text for mixed text types, and TIMESTAMP for a DATE with a timestamp
+pub fn common_type(a: &DataType, b: &DataType) -> Option<DataType> {
+    if a == b {
+        return Some(a.clone());
+    }
+    match (numeric_rank(a), numeric_rank(b)) {
+        (Some(rank_a), Some(rank_b)) => {
+            return Some(match (a, b) {
+                (DataType::Float, _) | (_, DataType::Float) => DataType::Float,
+                (
+                    DataType::Decimal { scale: scale_a, .. },
+                    DataType::Decimal { scale: scale_b, .. },
+                ) => DataType::Decimal {
+                    precision: MAX_DECIMAL_PRECISION,
+                    scale: (*scale_a).max(*scale_b),
+                },
+                (DataType::Decimal { .. }, _) => a.clone(),
+                (_, DataType::Decimal { .. }) => b.clone(),
+                _ if rank_a >= rank_b => a.clone(),
+                _ => b.clone(),
+            });
+        }
+        (None, None) => {}
+        _ => return None,
+    }
+    if is_text(a) && is_text(b) {
+        return Some(DataType::String);
+    }
+    let instant =
+        |t: &DataType| matches!(t, DataType::Date | DataType::Timestamp | DataType::DateTime);
+    if instant(a) && instant(b) {
+        return Some(DataType::Timestamp);
+    }
+    None
+}
+
+/// Position in the numeric tower, from TINYINT up to FLOAT
+fn numeric_rank(data_type: &DataType) -> Option<u8> {
+    match data_type {
+        DataType::TinyInt => Some(0),
+        DataType::SmallInt => Some(1),
+        DataType::Integer => Some(2),
+        DataType::BigInt => Some(3),
+        DataType::Decimal { .. } => Some(4),
+        DataType::Float => Some(5),
+        _ => None,
+    }
+}
+
+fn is_text(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::String | DataType::Text | DataType::Char
+    )
+}
+
+fn is_temporal(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Date | DataType::Time | DataType::Timestamp | DataType::DateTime
+    )
+}
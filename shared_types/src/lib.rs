@@ -1,14 +1,22 @@
 pub mod constant;
+pub mod decimal;
 pub mod error;
+pub mod expr;
+pub mod function;
 pub mod pretty_print;
 pub mod row;
+pub mod runtime_filter;
 pub mod scan;
 pub mod schema;
 pub mod value;
 
+pub use decimal::Decimal;
 pub use error::StorageError;
+pub use expr::{BinaryOp, Expr, UnaryOp};
+pub use function::{FunctionSignature, ParamType, ReturnType, ScalarFunction, SignatureError};
 pub use pretty_print::pretty_print_rows;
 pub use row::Row;
+pub use runtime_filter::{BloomFilter, RuntimeFilter};
 pub use scan::{OrderBy, Predicate, ScanOptions, ScanResult, SortDirection};
 pub use schema::{Column, DataType, Schema};
 pub use value::Value;
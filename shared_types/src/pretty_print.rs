@@ -85,7 +85,7 @@ fn format_value(value: &Value) -> String {
         Value::Null => "NULL".to_string(),
         Value::SmallInt(i) => i.to_string(),
         Value::BigInt(i) => i.to_string(),
-        Value::Decimal(d) => d.clone(),
+        Value::Decimal(d) => d.to_string(),
         Value::Binary(b) => format!("Binary({} bytes)", b.len()),
         Value::Date(d) => format!("Date({})", d),
         Value::Time(t) => format!("Time({})", t),
@@ -1,5 +1,6 @@
 //! Row type for database storage
 
+use crate::error::StorageError;
 use crate::value::Value;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -45,4 +46,31 @@ impl Row {
         4 + // data length (u32)
         self.data.iter().map(|v| v.serialized_size()).sum::<usize>()
     }
-}
\ No newline at end of file
+
+    /// Serialize the row as id, value count, then each value
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.serialized_size());
+        bytes.extend_from_slice(&self.id.to_le_bytes());
+        bytes.extend_from_slice(&(self.data.len() as u32).to_le_bytes());
+        for value in &self.data {
+            bytes.extend_from_slice(&value.to_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize a row written by `to_bytes`
+    pub fn from_bytes(bytes: &[u8], offset: &mut usize) -> Result<Self, StorageError> {
+        let header = bytes
+            .get(*offset..*offset + 12)
+            .ok_or_else(|| StorageError::CorruptedData("Truncated row header".into()))?;
+        let id = u64::from_le_bytes(header[..8].try_into().unwrap());
+        let count = u32::from_le_bytes(header[8..].try_into().unwrap()) as usize;
+        *offset += 12;
+
+        let mut data = Vec::with_capacity(count);
+        for _ in 0..count {
+            data.push(Value::from_bytes(bytes, offset)?);
+        }
+        Ok(Self { id, data })
+    }
+}
@@ -0,0 +1,130 @@
+//! Filters built from a join's build side and applied while scanning the probe side
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::value::Value;
+
+/// Bits reserved per inserted key; with `BLOOM_HASHES` probes this gives about 1% false positives
+const BLOOM_BITS_PER_KEY: usize = 10;
+const BLOOM_HASHES: u32 = 7;
+
+/// Above this many build keys the bloom filter is skipped and only the min/max range is used
+pub const MAX_BLOOM_KEYS: usize = 1 << 24;
+
+/// Bloom filter over values, hashed consistently with `Value` equality so that
+/// e.g. an INTEGER probe finds a BIGINT build key of the same value
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    pub fn with_capacity(expected_keys: usize) -> Self {
+        let words = (expected_keys.max(1) * BLOOM_BITS_PER_KEY).div_ceil(64);
+        Self {
+            bits: vec![0; words],
+            num_hashes: BLOOM_HASHES,
+        }
+    }
+
+    pub fn insert(&mut self, value: &Value) {
+        let (h1, h2) = Self::hashes(value);
+        let num_bits = self.num_bits();
+        for i in 0..self.num_hashes as u64 {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % num_bits;
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    /// False means the value was definitely never inserted
+    pub fn might_contain(&self, value: &Value) -> bool {
+        let (h1, h2) = Self::hashes(value);
+        let num_bits = self.num_bits();
+        (0..self.num_hashes as u64).all(|i| {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % num_bits;
+            self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0
+        })
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    fn num_bits(&self) -> u64 {
+        self.bits.len() as u64 * 64
+    }
+
+    /// Two independent hashes combined as `h1 + i * h2` (Kirsch-Mitzenmacher)
+    fn hashes(value: &Value) -> (u64, u64) {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let h1 = hasher.finish();
+        // Odd step so the probes cycle through every bit position
+        let h2 = h1.rotate_left(32).wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        (h1, h2)
+    }
+}
+
+/// Necessary condition on one probe-side column: a row whose value falls outside
+/// the build side's range, or is missing from its bloom filter, cannot join
+#[derive(Debug, Clone)]
+pub struct RuntimeFilter {
+    pub column: String,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    pub bloom: Option<Arc<BloomFilter>>,
+}
+
+impl RuntimeFilter {
+    /// Build a filter for `column` from the build side's non-NULL key values
+    pub fn from_values<'a>(
+        column: impl Into<String>,
+        values: impl ExactSizeIterator<Item = &'a Value>,
+    ) -> Self {
+        let use_bloom = values.len() <= MAX_BLOOM_KEYS;
+        let mut bloom = use_bloom.then(|| BloomFilter::with_capacity(values.len()));
+        let mut min: Option<&Value> = None;
+        let mut max: Option<&Value> = None;
+
+        for value in values {
+            if value.is_null() {
+                continue;
+            }
+            if min.is_none_or(|m| value < m) {
+                min = Some(value);
+            }
+            if max.is_none_or(|m| value > m) {
+                max = Some(value);
+            }
+            if let Some(bloom) = bloom.as_mut() {
+                bloom.insert(value);
+            }
+        }
+
+        Self {
+            column: column.into(),
+            min: min.cloned(),
+            max: max.cloned(),
+            bloom: bloom.map(Arc::new),
+        }
+    }
+
+    /// False means no build row can match this value. NULL never matches.
+    pub fn might_match(&self, value: &Value) -> bool {
+        if value.is_null() {
+            return false;
+        }
+        let (Some(min), Some(max)) = (&self.min, &self.max) else {
+            return false; // Empty build side
+        };
+        if value < min || value > max {
+            return false;
+        }
+        self.bloom
+            .as_ref()
+            .is_none_or(|bloom| bloom.might_contain(value))
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{row::Row, schema::Schema, value::Value};
+use crate::{expr::Expr, row::Row, runtime_filter::RuntimeFilter, schema::Schema, value::Value};
 
 #[derive(Debug, Clone)]
 pub enum Predicate {
@@ -14,6 +14,8 @@ pub enum Predicate {
     ColumnIsNotNull { column: String },
     ColumnLike { column: String, pattern: String },
     ColumnBetween { column: String, start: Value, end: Value },
+    ColumnIsDistinctFrom { column: String, value: Value },
+    ColumnIsNotDistinctFrom { column: String, value: Value },
     And(Box<Predicate>, Box<Predicate>),
     Or(Box<Predicate>, Box<Predicate>),
     Not(Box<Predicate>),
@@ -50,17 +52,28 @@ impl Predicate {
     pub fn column_between(column: String, start: Value, end: Value) -> Self {
         Predicate::ColumnBetween { column, start, end }
     }
+    pub fn column_is_distinct_from(column: String, value: Value) -> Self {
+        Predicate::ColumnIsDistinctFrom { column, value }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OrderBy {
     pub column: String,
     pub direction: SortDirection,
+    /// Whether NULLs come before every value, regardless of direction
+    pub nulls_first: bool,
 }
 
 impl OrderBy {
+    /// NULL sorts as the smallest value: first when ascending, last when descending
     pub fn new(column: String, direction: SortDirection) -> Self {
-        Self { column, direction }
+        let nulls_first = matches!(direction, SortDirection::Ascending);
+        Self {
+            column,
+            direction,
+            nulls_first,
+        }
     }
     pub fn asc(column: String) -> Self {
         Self::new(column, SortDirection::Ascending)
@@ -68,9 +81,17 @@ impl OrderBy {
     pub fn desc(column: String) -> Self {
         Self::new(column, SortDirection::Descending)
     }
+    pub fn nulls_first(mut self) -> Self {
+        self.nulls_first = true;
+        self
+    }
+    pub fn nulls_last(mut self) -> Self {
+        self.nulls_first = false;
+        self
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SortDirection {
     Ascending,
     Descending,
@@ -79,24 +100,28 @@ pub enum SortDirection {
 #[derive(Debug, Clone)]
 pub struct ScanOptions {
     pub predicate: Option<Predicate>,
+    pub filter: Option<Expr>,
     pub projection: Option<Vec<String>>,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
     pub parallel: bool,
     pub order_by: Option<Vec<OrderBy>>,
     pub schema: Option<Schema>,
+    pub runtime_filters: Vec<RuntimeFilter>,
 }
 
 impl Default for ScanOptions {
     fn default() -> Self {
         Self {
             predicate: None,
+            filter: None,
             projection: None,
             limit: None,
             offset: None,
             parallel: false,
             order_by: None,
             schema: None,
+            runtime_filters: Vec::new(),
         }
     }
 }
@@ -109,6 +134,10 @@ impl ScanOptions {
         self.predicate = Some(predicate);
         self
     }
+    pub fn with_filter(mut self, filter: Expr) -> Self {
+        self.filter = Some(filter);
+        self
+    }
     pub fn with_projection(mut self, columns: Vec<String>) -> Self {
         self.projection = Some(columns);
         self
@@ -133,6 +162,10 @@ impl ScanOptions {
         self.schema = Some(schema);
         self
     }
+    pub fn with_runtime_filter(mut self, filter: RuntimeFilter) -> Self {
+        self.runtime_filters.push(filter);
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -142,6 +175,10 @@ pub struct ScanResult {
     pub pages_read: usize,
     pub filtered_count: usize,
     pub result_schema: Option<Schema>,
+    /// Pages this scan and its read-ahead found in the buffer pool
+    pub buffer_hits: usize,
+    /// Workers the scan was split across
+    pub workers: usize,
 }
 
 impl ScanResult {
@@ -152,6 +189,8 @@ impl ScanResult {
             pages_read,
             filtered_count,
             result_schema,
+            buffer_hits: 0,
+            workers: 1,
         }
     }
     pub fn row_count(&self) -> usize {
@@ -166,6 +205,8 @@ impl ScanResult {
             total_scanned: self.total_scanned,
             pages_read: self.pages_read,
             filtered_count: self.filtered_count,
+            buffer_hits: self.buffer_hits,
+            workers: self.workers,
         }
     }
 }
@@ -176,6 +217,8 @@ pub struct ScanStats {
     pub total_scanned: usize,
     pub pages_read: usize,
     pub filtered_count: usize,
+    pub buffer_hits: usize,
+    pub workers: usize,
 }
 
 impl ScanStats {
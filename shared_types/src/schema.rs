@@ -11,7 +11,7 @@ pub enum DataType {
     Boolean,
     SmallInt,
     BigInt,
-    Decimal,
+    Decimal { precision: u8, scale: u8 },
     Binary,
     Date,
     Time,
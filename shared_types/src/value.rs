@@ -2,11 +2,14 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::decimal::{Decimal, MAX_DECIMAL_PRECISION};
 use crate::error::StorageError;
+use crate::schema::DataType;
+use std::cmp::Ordering;
 use std::convert::TryInto;
 use std::hash::{Hash, Hasher};
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Value {
     Integer(i64),
     String(String),
@@ -15,7 +18,7 @@ pub enum Value {
     Null,
     SmallInt(i16),
     BigInt(i128),
-    Decimal(String), // Store as string to maintain precision
+    Decimal(Decimal),
     Binary(Vec<u8>),
     Date(i32),      // Days since epoch (1970-01-01)
     Time(u32),      // Milliseconds since midnight
@@ -28,81 +31,213 @@ pub enum Value {
     TinyInt(i8),    // 8-bit integer
 }
 
+/// Ordering classes used when comparing values of unrelated types.
+/// Values within the same class compare by content: every numeric width is
+/// promoted, String/Text compare as text and Timestamp/DateTime as instants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum TypeFamily {
+    Null,
+    Boolean,
+    Numeric,
+    Text,
+    Char,
+    Binary,
+    Date,
+    Time,
+    Timestamp,
+    Json,
+    Uuid,
+}
+
+impl Value {
+    fn family(&self) -> TypeFamily {
+        match self {
+            Value::Null => TypeFamily::Null,
+            Value::Boolean(_) => TypeFamily::Boolean,
+            Value::TinyInt(_)
+            | Value::SmallInt(_)
+            | Value::Integer(_)
+            | Value::BigInt(_)
+            | Value::Float(_) => TypeFamily::Numeric,
+            Value::Decimal(_) => TypeFamily::Numeric,
+            Value::String(_) | Value::Text(_) => TypeFamily::Text,
+            Value::Char(_) => TypeFamily::Char,
+            Value::Binary(_) => TypeFamily::Binary,
+            Value::Date(_) => TypeFamily::Date,
+            Value::Time(_) => TypeFamily::Time,
+            Value::Timestamp(_) | Value::DateTime(_) => TypeFamily::Timestamp,
+            Value::Json(_) => TypeFamily::Json,
+            Value::Uuid(_) => TypeFamily::Uuid,
+        }
+    }
+
+    /// Whether the value belongs to the numeric tower
+    pub fn is_numeric(&self) -> bool {
+        self.family() == TypeFamily::Numeric
+    }
+
+    /// Position in the numeric promotion tower:
+    /// TinyInt < SmallInt < Integer < BigInt < Decimal < Float
+    pub fn numeric_rank(&self) -> Option<u8> {
+        match self {
+            Value::TinyInt(_) => Some(0),
+            Value::SmallInt(_) => Some(1),
+            Value::Integer(_) => Some(2),
+            Value::BigInt(_) => Some(3),
+            Value::Decimal(_) => Some(4),
+            Value::Float(_) => Some(5),
+            _ => None,
+        }
+    }
+
+    /// Exact integer view of an integer-typed value
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            Value::TinyInt(v) => Some(*v as i128),
+            Value::SmallInt(v) => Some(*v as i128),
+            Value::Integer(v) => Some(*v as i128),
+            Value::BigInt(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Approximate floating point view of any numeric value
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Float(v) => Some(*v),
+            Value::Decimal(d) => Some(d.to_f64()),
+            other => other.as_i128().map(|v| v as f64),
+        }
+    }
+
+    /// Exact decimal view of an integer or decimal value
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Value::Decimal(d) => Some(*d),
+            other => other.as_i128().map(Decimal::from_integer),
+        }
+    }
+
+    /// Exact integer view of a numeric value that has no fractional part
+    fn integral_value(&self) -> Option<i128> {
+        if let Some(v) = self.as_i128() {
+            return Some(v);
+        }
+        if let Value::Decimal(d) = self {
+            return d.to_integer();
+        }
+        let f = self.as_f64()?;
+        if f.fract() == 0.0 && f >= i128::MIN as f64 && f < i128::MAX as f64 {
+            Some(f as i128)
+        } else {
+            None
+        }
+    }
+
+    fn compare_numeric(&self, other: &Value) -> Ordering {
+        if let (Some(a), Some(b)) = (self.integral_value(), other.integral_value()) {
+            return a.cmp(&b);
+        }
+        // Integers and decimals compare exactly; floats are involved otherwise
+        if let (Some(a), Some(b)) = (self.as_decimal(), other.as_decimal()) {
+            return a.cmp(&b);
+        }
+        let a = self.as_f64().unwrap_or(f64::NAN);
+        let b = other.as_f64().unwrap_or(f64::NAN);
+        a.total_cmp(&b)
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (left_family, right_family) = (self.family(), other.family());
+        if left_family != right_family {
+            return left_family.cmp(&right_family);
+        }
+
+        match (self, other) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (
+                Value::String(a) | Value::Text(a),
+                Value::String(b) | Value::Text(b),
+            ) => a.cmp(b),
+            (Value::Char(a), Value::Char(b)) => a.cmp(b),
+            (Value::Binary(a), Value::Binary(b)) => a.cmp(b),
+            (Value::Date(a), Value::Date(b)) => a.cmp(b),
+            (Value::Time(a), Value::Time(b)) => a.cmp(b),
+            (
+                Value::Timestamp(a) | Value::DateTime(a),
+                Value::Timestamp(b) | Value::DateTime(b),
+            ) => a.cmp(b),
+            (Value::Json(a), Value::Json(b)) => a.cmp(b),
+            (Value::Uuid(a), Value::Uuid(b)) => a.cmp(b),
+            _ => self.compare_numeric(other),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
 impl Eq for Value {}
 
 impl Hash for Value {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        // Must agree with `Ord`: equal values across widths hash identically
+        self.family().hash(state);
         match self {
-            Value::Integer(v) => {
-                0u8.hash(state);
-                v.hash(state);
-            }
-            Value::String(v) => {
+            Value::Null => {}
+            Value::Boolean(v) => v.hash(state),
+            Value::String(v) | Value::Text(v) | Value::Json(v) => v.hash(state),
+            Value::Char(v) => v.hash(state),
+            Value::Binary(v) => v.hash(state),
+            Value::Date(v) => v.hash(state),
+            Value::Time(v) => v.hash(state),
+            Value::Timestamp(v) | Value::DateTime(v) => v.hash(state),
+            Value::Uuid(v) => v.hash(state),
+            Value::TinyInt(_)
+            | Value::SmallInt(_)
+            | Value::Integer(_)
+            | Value::BigInt(_)
+            | Value::Decimal(_)
+            | Value::Float(_) => self.hash_numeric(state),
+        }
+    }
+}
+
+impl Value {
+    fn hash_numeric<H: Hasher>(&self, state: &mut H) {
+        if let Some(v) = self.integral_value() {
+            0u8.hash(state);
+            v.hash(state);
+            return;
+        }
+        // Decimals compare exactly, so they hash exactly: `Decimal::hash` ignores the
+        // scale. Only floats with no exact decimal value fall back to their bits.
+        let exact = match self {
+            Value::Float(f) => Decimal::from_f64_exact(*f),
+            other => other.as_decimal(),
+        };
+        match exact {
+            Some(d) => {
                 1u8.hash(state);
-                v.hash(state);
+                d.hash(state);
             }
-            Value::Float(v) => {
+            None => {
+                let f = self.as_f64().unwrap_or(f64::NAN);
+                let bits = if f.is_nan() { f64::NAN.to_bits() } else { f.to_bits() };
                 2u8.hash(state);
-                v.to_bits().hash(state);
-            }
-            Value::Boolean(v) => {
-                3u8.hash(state);
-                v.hash(state);
-            }
-            Value::Null => {
-                4u8.hash(state);
-            }
-            Value::SmallInt(v) => {
-                5u8.hash(state);
-                v.hash(state);
-            }
-            Value::BigInt(v) => {
-                6u8.hash(state);
-                v.hash(state);
-            }
-            Value::Decimal(v) => {
-                7u8.hash(state);
-                v.hash(state);
-            }
-            Value::Binary(v) => {
-                8u8.hash(state);
-                v.hash(state);
-            }
-            Value::Date(v) => {
-                9u8.hash(state);
-                v.hash(state);
-            }
-            Value::Time(v) => {
-                10u8.hash(state);
-                v.hash(state);
-            }
-            Value::Timestamp(v) => {
-                11u8.hash(state);
-                v.hash(state);
-            }
-            Value::DateTime(v) => {
-                12u8.hash(state);
-                v.hash(state);
-            }
-            Value::Json(v) => {
-                13u8.hash(state);
-                v.hash(state);
-            }
-            Value::Uuid(v) => {
-                14u8.hash(state);
-                v.hash(state);
-            }
-            Value::Text(v) => {
-                15u8.hash(state);
-                v.hash(state);
-            }
-            Value::Char(v) => {
-                16u8.hash(state);
-                v.hash(state);
-            }
-            Value::TinyInt(v) => {
-                17u8.hash(state);
-                v.hash(state);
+                bits.hash(state);
             }
         }
     }
@@ -127,6 +262,7 @@ const UUID_TYPE: u8 = 14;
 const TEXT_TYPE: u8 = 15;
 const CHAR_TYPE: u8 = 16;
 const TINYINT_TYPE: u8 = 17;
+const COMPACT_DECIMAL_TYPE: u8 = 18;
 
 impl Value {
     /// Convert the Value to bytes for storage
@@ -162,8 +298,8 @@ impl Value {
                 bytes.extend_from_slice(&val.to_le_bytes());
             }
             Value::Decimal(val) => {
-                bytes.push(DECIMAL_TYPE);
-                Self::serialize_string_like(&mut bytes, val.as_bytes());
+                bytes.push(COMPACT_DECIMAL_TYPE);
+                Self::serialize_decimal(&mut bytes, val);
             }
             Value::Binary(val) => {
                 bytes.push(BINARY_TYPE);
@@ -269,7 +405,12 @@ impl Value {
                 *offset += 16;
                 Ok(Value::BigInt(val))
             }
-            DECIMAL_TYPE => Self::deserialize_string(bytes, offset).map(Value::Decimal),
+            // Older pages stored decimals as text
+            DECIMAL_TYPE => Self::deserialize_string(bytes, offset)?
+                .parse()
+                .map(Value::Decimal)
+                .map_err(|_| StorageError::CorruptedData("Invalid decimal text".into())),
+            COMPACT_DECIMAL_TYPE => Self::deserialize_decimal(bytes, offset).map(Value::Decimal),
             BINARY_TYPE => {
                 Self::ensure_bytes_available(bytes, *offset, 4, "binary length")?;
                 let len =
@@ -370,6 +511,46 @@ impl Value {
         bytes.extend_from_slice(data);
     }
 
+    /// Decimals are stored as scale, mantissa length and the shortest
+    /// little-endian two's complement encoding of the mantissa
+    fn serialize_decimal(bytes: &mut Vec<u8>, decimal: &Decimal) {
+        let len = Self::decimal_mantissa_len(decimal);
+        bytes.push(decimal.scale());
+        bytes.push(len as u8);
+        bytes.extend_from_slice(&decimal.mantissa().to_le_bytes()[..len]);
+    }
+
+    fn decimal_mantissa_len(decimal: &Decimal) -> usize {
+        let mantissa = decimal.mantissa();
+        let significant_bits = if mantissa < 0 {
+            128 - mantissa.leading_ones() as usize
+        } else {
+            128 - mantissa.leading_zeros() as usize
+        };
+        // One extra bit for the sign
+        (significant_bits + 1).div_ceil(8).max(1)
+    }
+
+    fn deserialize_decimal(bytes: &[u8], offset: &mut usize) -> Result<Decimal, StorageError> {
+        Self::ensure_bytes_available(bytes, *offset, 2, "decimal header")?;
+        let scale = bytes[*offset];
+        let len = bytes[*offset + 1] as usize;
+        *offset += 2;
+        if len == 0 || len > 16 {
+            return Err(StorageError::CorruptedData(format!(
+                "Invalid decimal mantissa length: {}",
+                len
+            )));
+        }
+
+        Self::ensure_bytes_available(bytes, *offset, len, "decimal mantissa")?;
+        let negative = bytes[*offset + len - 1] & 0x80 != 0;
+        let mut buf = if negative { [0xFF; 16] } else { [0u8; 16] };
+        buf[..len].copy_from_slice(&bytes[*offset..*offset + len]);
+        *offset += len;
+        Ok(Decimal::new(i128::from_le_bytes(buf), scale))
+    }
+
     /// Helper function to serialize binary data
     fn serialize_binary(bytes: &mut Vec<u8>, data: &[u8]) {
         bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
@@ -421,7 +602,7 @@ impl Value {
             Value::Boolean(_) => 2,           // 1 byte type + 1 byte data
             Value::SmallInt(_) => 3,          // 1 byte type + 2 bytes data
             Value::BigInt(_) => 17,           // 1 byte type + 16 bytes data
-            Value::Decimal(s) => 5 + s.len(), // 1 byte type + 4 bytes length + data
+            Value::Decimal(d) => 3 + Self::decimal_mantissa_len(d), // type + scale + length + mantissa
             Value::Binary(b) => 5 + b.len(),  // 1 byte type + 4 bytes length + data
             Value::Date(_) => 5,              // 1 byte type + 4 bytes data
             Value::Time(_) => 5,              // 1 byte type + 4 bytes data
@@ -444,6 +625,34 @@ impl Value {
         matches!(self, Value::Null)
     }
 
+    /// Column type a value of this kind is stored as; `None` for NULL, which has no
+    /// type of its own
+    pub fn data_type(&self) -> Option<DataType> {
+        Some(match self {
+            Value::Null => return None,
+            Value::Integer(_) => DataType::Integer,
+            Value::String(_) => DataType::String,
+            Value::Float(_) => DataType::Float,
+            Value::Boolean(_) => DataType::Boolean,
+            Value::SmallInt(_) => DataType::SmallInt,
+            Value::BigInt(_) => DataType::BigInt,
+            Value::Decimal(d) => DataType::Decimal {
+                precision: MAX_DECIMAL_PRECISION,
+                scale: d.scale(),
+            },
+            Value::Binary(_) => DataType::Binary,
+            Value::Date(_) => DataType::Date,
+            Value::Time(_) => DataType::Time,
+            Value::Timestamp(_) => DataType::Timestamp,
+            Value::DateTime(_) => DataType::DateTime,
+            Value::Json(_) => DataType::Json,
+            Value::Uuid(_) => DataType::Uuid,
+            Value::Text(_) => DataType::Text,
+            Value::Char(_) => DataType::Char,
+            Value::TinyInt(_) => DataType::TinyInt,
+        })
+    }
+
     /// Get the type name as a string
     pub fn type_name(&self) -> &'static str {
         match self {